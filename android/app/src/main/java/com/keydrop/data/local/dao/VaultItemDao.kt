@@ -59,6 +59,9 @@ interface VaultItemDao {
     @Query("SELECT * FROM vault_items WHERE pending_sync = 1")
     suspend fun getPendingSyncItems(): List<VaultItemEntity>
 
+    @Query("SELECT id FROM vault_items WHERE pending_sync = 0")
+    suspend fun getSyncedItemIds(): List<String>
+
     @Query("UPDATE vault_items SET pending_sync = 0, sync_version = :version WHERE id = :id")
     suspend fun markSynced(id: String, version: Long)
 
@@ -16,6 +16,7 @@ import kotlinx.coroutines.flow.MutableStateFlow
 import kotlinx.coroutines.flow.StateFlow
 import kotlinx.coroutines.flow.asStateFlow
 import kotlinx.coroutines.flow.map
+import retrofit2.HttpException
 import java.util.concurrent.TimeUnit
 import javax.inject.Inject
 import javax.inject.Singleton
@@ -148,7 +149,14 @@ class SyncManager @Inject constructor(
             val sinceVersion = currentState.lastSyncVersion
 
             // 2. Pull changes from server
-            val pullResponse = syncApi.pull(sinceVersion)
+            val pullResponse = try {
+                syncApi.pull(sinceVersion)
+            } catch (e: HttpException) {
+                if (e.code() != HTTP_GONE || sinceVersion == 0L) throw e
+                // Deletions we missed were pruned; start over and drop synced
+                // items the server no longer has
+                resyncFromScratch()
+            }
 
             // 3. Apply server changes locally
             for (item in pullResponse.items) {
@@ -198,6 +206,24 @@ class SyncManager @Inject constructor(
             _isSyncing.value = false
         }
     }
+
+    /**
+     * Pull the whole vault from version 0, deleting local items that were
+     * synced before but are gone from the server. Returns the last page.
+     */
+    private suspend fun resyncFromScratch(): SyncPullResponse {
+        val serverIds = mutableSetOf<String>()
+        var response = syncApi.pull(0)
+        while (true) {
+            response.items.filterNot { it.isDeleted }.mapTo(serverIds) { it.id }
+            if (!response.hasMore || response.items.isEmpty()) break
+            response = syncApi.pull(response.items.last().version)
+        }
+        for (id in vaultItemDao.getSyncedItemIds()) {
+            if (id !in serverIds) vaultItemDao.deleteById(id)
+        }
+        return response
+    }
 }
 
 // API models
@@ -226,6 +252,8 @@ data class SyncPushResponse(
     val conflicts: List<SyncItem>
 )
 
+private const val HTTP_GONE = 410
+
 interface SyncApi {
     suspend fun pull(sinceVersion: Long): SyncPullResponse
     suspend fun push(request: SyncPushRequest): SyncPushResponse
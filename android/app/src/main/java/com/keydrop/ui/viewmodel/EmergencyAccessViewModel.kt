@@ -54,7 +54,7 @@ interface EmergencyAccessApi {
     suspend fun getContacts(): List<EmergencyContact>
     suspend fun addContact(email: String, name: String?, waitingPeriodHours: Int): EmergencyContact
     suspend fun removeContact(contactId: String)
-    suspend fun acceptInvitation(contactId: String, token: String)
+    suspend fun acceptInvitation(contactId: String, token: String, publicKey: String)
     suspend fun getPendingRequests(): List<EmergencyAccessRequest>
     suspend fun denyRequest(requestId: String)
     suspend fun requestAccess(contactId: String, reason: String?)
@@ -0,0 +1,13 @@
+-- Refresh token families for reuse detection
+--
+-- Each login starts a family; every rotation adds a token to it and revokes
+-- the one it replaced. Presenting a revoked token means it was copied, so the
+-- whole family is revoked.
+
+ALTER TABLE refresh_tokens ADD COLUMN family_id UUID;
+UPDATE refresh_tokens SET family_id = id;
+ALTER TABLE refresh_tokens ALTER COLUMN family_id SET NOT NULL;
+
+ALTER TABLE refresh_tokens ADD COLUMN revoked_at TIMESTAMPTZ;
+
+CREATE INDEX idx_refresh_tokens_family_id ON refresh_tokens(family_id);
@@ -0,0 +1,29 @@
+-- Account deletion
+--
+-- An account can wait out a grace period before it is purged; signing in
+-- again before then cancels the deletion.
+
+ALTER TABLE users ADD COLUMN deletion_scheduled_at TIMESTAMPTZ;
+
+CREATE INDEX idx_users_deletion_scheduled_at ON users(deletion_scheduled_at)
+    WHERE deletion_scheduled_at IS NOT NULL;
+
+-- References without ON DELETE would otherwise block deleting the user or
+-- their devices
+ALTER TABLE emergency_contacts
+    DROP CONSTRAINT emergency_contacts_contact_user_id_fkey,
+    ADD CONSTRAINT emergency_contacts_contact_user_id_fkey
+        FOREIGN KEY (contact_user_id) REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE emergency_access_logs
+    DROP CONSTRAINT emergency_access_logs_emergency_contact_id_fkey,
+    ADD CONSTRAINT emergency_access_logs_emergency_contact_id_fkey
+        FOREIGN KEY (emergency_contact_id) REFERENCES emergency_contacts(id) ON DELETE SET NULL;
+
+ALTER TABLE remote_commands
+    DROP CONSTRAINT remote_commands_issued_by_device_id_fkey,
+    ADD CONSTRAINT remote_commands_issued_by_device_id_fkey
+        FOREIGN KEY (issued_by_device_id) REFERENCES devices(id) ON DELETE SET NULL,
+    DROP CONSTRAINT remote_commands_issued_by_emergency_contact_id_fkey,
+    ADD CONSTRAINT remote_commands_issued_by_emergency_contact_id_fkey
+        FOREIGN KEY (issued_by_emergency_contact_id) REFERENCES emergency_contacts(id) ON DELETE SET NULL;
@@ -0,0 +1,19 @@
+-- Login throttling and account lockout
+--
+-- failed_auth_attempts counts consecutive wrong auth_keys and is reset by a
+-- correct one. While auth_locked_until is in the future the auth_key isn't
+-- checked at all.
+
+ALTER TABLE users ADD COLUMN failed_auth_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN auth_locked_until TIMESTAMPTZ;
+
+-- Security log shown to the user: sign-ins, failed attempts and lockouts
+CREATE TABLE security_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    action VARCHAR(100) NOT NULL,
+    details JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_security_events_user_id ON security_events(user_id, created_at);
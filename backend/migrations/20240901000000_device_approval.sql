@@ -0,0 +1,13 @@
+-- New-device approval
+--
+-- With require_device_approval set, a device created by login starts out
+-- pending (approved_at NULL) and can't sync until one of the user's approved
+-- devices answers the device_approval auth request sent to it.
+
+ALTER TABLE users ADD COLUMN require_device_approval BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE devices ADD COLUMN approved_at TIMESTAMPTZ;
+UPDATE devices SET approved_at = created_at;
+
+-- 'biometric' for cross-device auth, 'device_approval' for new devices
+ALTER TABLE auth_requests ADD COLUMN purpose VARCHAR(50) NOT NULL DEFAULT 'biometric';
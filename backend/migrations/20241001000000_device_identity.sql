@@ -0,0 +1,10 @@
+-- Sticky device identity
+--
+-- Clients send a stable identifier they generate once; logging in again from
+-- the same device updates its row instead of adding another one. Older rows
+-- and clients without one keep the NULL, which never conflicts.
+
+ALTER TABLE devices ADD COLUMN client_device_id VARCHAR(255);
+
+ALTER TABLE devices
+    ADD CONSTRAINT devices_user_client_device_id_key UNIQUE (user_id, client_device_id);
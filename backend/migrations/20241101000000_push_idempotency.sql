@@ -0,0 +1,16 @@
+-- Idempotency keys for sync push
+--
+-- A push sent with an Idempotency-Key header stores its response under the
+-- key; retrying it (e.g. after a timeout) returns that response instead of
+-- applying the items again.
+
+CREATE TABLE sync_push_idempotency_keys (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    idempotency_key VARCHAR(255) NOT NULL,
+    response JSONB NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, idempotency_key)
+);
+
+CREATE INDEX idx_sync_push_idempotency_keys_expires_at ON sync_push_idempotency_keys(expires_at);
@@ -0,0 +1,15 @@
+-- Blob garbage collection
+--
+-- Every push writes a new blob; the one the item pointed at before is
+-- recorded here when the row is replaced. A background job deletes these
+-- once they're past the retention window and nothing references them.
+
+CREATE TABLE superseded_blobs (
+    blob_id TEXT PRIMARY KEY,
+    superseded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_superseded_blobs_superseded_at ON superseded_blobs(superseded_at);
+
+-- For the "still referenced?" check before deleting
+CREATE INDEX idx_vault_items_sync_encrypted_blob_id ON vault_items_sync(encrypted_blob_id);
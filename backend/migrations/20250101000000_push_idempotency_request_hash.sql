@@ -0,0 +1,7 @@
+-- Bind push idempotency keys to the request they were first used with
+--
+-- A retry must carry the same body; reusing a key for a different push is
+-- rejected instead of replaying an unrelated response. Keys stored before
+-- this have no hash and expire within a day.
+
+ALTER TABLE sync_push_idempotency_keys ADD COLUMN request_hash BYTEA;
@@ -0,0 +1,9 @@
+-- Proof of possession for sticky device identity
+--
+-- `client_device_id` is an identifier, not a secret, so presenting it alone
+-- no longer carries over a device's approval. The device is issued a secret
+-- the first time it's seen and has to present it again to keep its approval;
+-- a login without it leaves the device pending when approval is required.
+-- Devices from before this get a secret at their next login.
+
+ALTER TABLE devices ADD COLUMN device_secret_hash VARCHAR(500);
@@ -0,0 +1,8 @@
+-- Tombstone prune watermark
+--
+-- Deletion markers are pruned after a retention period, so a client that
+-- last synced before them would never learn of those deletions. The highest
+-- pruned version is kept per user; a pull from below it is refused with 410
+-- and the client resyncs from version 0.
+
+ALTER TABLE sync_versions ADD COLUMN pruned_version BIGINT NOT NULL DEFAULT 0;
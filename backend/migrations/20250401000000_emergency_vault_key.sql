@@ -0,0 +1,11 @@
+-- Emergency access vault key
+--
+-- A contact publishes an X25519 public key when accepting an invitation, and
+-- the owner's client stores the vault key sealed for it on the contact.
+-- Granting a request copies the sealed key into the request for the contact
+-- to pick up. A new master password means a new vault key, so changing it
+-- clears the sealed keys until the owner's client seals them again.
+
+ALTER TABLE emergency_contacts
+    ADD COLUMN contact_public_key TEXT,
+    ADD COLUMN vault_key_encrypted TEXT;
@@ -0,0 +1,44 @@
+use axum::{extract::State, routing::post, Json, Router};
+use axum_extra::TypedHeader;
+use headers::{authorization::Bearer, Authorization};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::sync;
+use crate::{AppError, AppState, Result};
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/blob-gc", post(run_blob_gc))
+}
+
+/// Check the admin bearer token
+///
+/// Hashes are compared so the check doesn't leak how much of the token
+/// matched. Without a configured token the admin API doesn't exist.
+fn require_admin(state: &AppState, auth_header: &TypedHeader<Authorization<Bearer>>) -> Result<()> {
+    let Some(admin_token) = &state.admin_token else {
+        return Err(AppError::NotFound("Admin API is disabled".to_string()));
+    };
+    if Sha256::digest(auth_header.token().as_bytes()) != Sha256::digest(admin_token.as_bytes()) {
+        return Err(AppError::Unauthorized("Invalid admin token".to_string()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlobGcResponse {
+    pub deleted: u64,
+}
+
+/// Run blob garbage collection now instead of waiting for the job
+async fn run_blob_gc(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<BlobGcResponse>> {
+    require_admin(&state, &auth_header)?;
+
+    let deleted = sync::collect_superseded_blobs(&state).await?;
+    tracing::info!("Admin blob GC deleted {} blobs", deleted);
+
+    Ok(Json(BlobGcResponse { deleted }))
+}
@@ -2,16 +2,33 @@ use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
-use axum::{extract::State, routing::post, Json, Router};
-use chrono::{Duration, Utc};
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use axum_extra::TypedHeader;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use headers::{authorization::Bearer, Authorization};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use uuid::Uuid;
 
+use super::{devices, emergency, two_factor, webauthn};
 use crate::{
-    auth::jwt::{
-        generate_token_pair, hash_refresh_token, validate_refresh_token, REFRESH_TOKEN_EXPIRY_DAYS,
+    auth::{
+        jwt::{
+            generate_token_pair, hash_refresh_token, hash_token, validate_access_token,
+            validate_refresh_token, REFRESH_TOKEN_EXPIRY_DAYS,
+        },
+        lockout, AuthUser,
     },
-    db::{self, DeviceType},
+    db::{self, Device, DeviceIdentity, DeviceType, User},
+    mail::Mailer,
+    sync::{SyncItem, SyncNotification, SyncNotificationType},
     AppError, AppState, Result,
 };
 
@@ -20,6 +37,96 @@ pub fn router() -> Router<AppState> {
         .route("/register", post(register))
         .route("/login", post(login))
         .route("/refresh", post(refresh))
+        .route("/change-auth-key", post(change_auth_key))
+        .route("/account", delete(delete_account))
+        .route("/verify-email", get(verify_email))
+        .route("/resend-verification", post(resend_verification))
+        .route("/security-log", get(get_security_log))
+}
+
+/// How long an email verification link stays valid
+pub const EMAIL_VERIFICATION_EXPIRY_HOURS: i64 = 48;
+
+/// Unverified accounts older than this are purged
+pub const UNVERIFIED_ACCOUNT_EXPIRY_DAYS: i64 = 7;
+
+/// Longest grace period an account deletion may be scheduled for
+pub const MAX_DELETION_GRACE_DAYS: u32 = 30;
+
+/// Extract and validate auth from Authorization header
+async fn extract_auth(
+    state: &AppState,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+) -> Result<AuthUser> {
+    let token = auth_header.token();
+    let claims = validate_access_token(token, &state.jwt_secret)?;
+
+    let user_id = claims
+        .sub
+        .parse::<Uuid>()
+        .map_err(|_| AppError::InvalidToken)?;
+
+    let device_id = claims
+        .device_id
+        .parse::<Uuid>()
+        .map_err(|_| AppError::InvalidToken)?;
+
+    Ok(AuthUser { user_id, device_id })
+}
+
+/// Check an auth_key against the user's stored Argon2 hash
+///
+/// Failures are counted per account and throttled as described in
+/// [`lockout`]; while locked, the key isn't checked at all.
+pub(crate) async fn verify_auth_key(state: &AppState, user: &User, auth_key: &str) -> Result<()> {
+    let now = Utc::now();
+    if let Some(locked_until) = user.auth_locked_until.filter(|until| *until > now) {
+        return Err(AppError::AccountLocked {
+            retry_after: (locked_until - now).num_seconds().max(1),
+        });
+    }
+
+    let parsed_hash = PasswordHash::new(&user.auth_key_hash)
+        .map_err(|_| AppError::Internal("Invalid stored hash".to_string()))?;
+
+    if Argon2::default()
+        .verify_password(auth_key.as_bytes(), &parsed_hash)
+        .is_ok()
+    {
+        if user.failed_auth_attempts > 0 {
+            db::reset_failed_auth_attempts(&state.db, user.id).await?;
+        }
+        return Ok(());
+    }
+
+    let failed_attempts = db::record_failed_auth_attempt(&state.db, user.id).await?;
+    let locked_until = lockout::lockout_duration(failed_attempts).map(|duration| now + duration);
+    if let Some(locked_until) = locked_until {
+        db::lock_user_auth(&state.db, user.id, locked_until).await?;
+    }
+
+    let action = if failed_attempts >= lockout::MAX_FAILED_ATTEMPTS {
+        tracing::warn!(
+            "User {} locked out after {} failed attempts",
+            user.id,
+            failed_attempts
+        );
+        "account_locked"
+    } else {
+        "auth_failed"
+    };
+    db::create_security_event(
+        &state.db,
+        user.id,
+        action,
+        Some(serde_json::json!({
+            "failed_attempts": failed_attempts,
+            "locked_until": locked_until.map(|t| t.timestamp()),
+        })),
+    )
+    .await?;
+
+    Err(AppError::InvalidCredentials)
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,6 +136,9 @@ pub struct RegisterRequest {
     pub salt: String,     // Base64-encoded salt for the client to store
     pub device_name: String,
     pub device_type: String,
+    /// Stable client-generated identifier; see `LoginRequest::client_device_id`
+    #[serde(default)]
+    pub client_device_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -38,12 +148,76 @@ pub struct RegisterResponse {
     pub access_token: String,
     pub refresh_token: String,
     pub expires_in: i64,
+    /// Sync is refused until this is true
+    pub email_verified: bool,
+    /// See `LoginResponse::device_secret`
+    pub device_secret: Option<String>,
+}
+
+/// Longest accepted `client_device_id`
+const MAX_CLIENT_DEVICE_ID_LEN: usize = 255;
+
+/// Validate a client device identifier; blank counts as none
+pub(crate) fn client_device_id(raw: Option<&str>) -> Result<Option<&str>> {
+    match raw.map(str::trim).filter(|id| !id.is_empty()) {
+        Some(id) if id.len() > MAX_CLIENT_DEVICE_ID_LEN => Err(AppError::BadRequest(format!(
+            "client_device_id can be at most {} bytes",
+            MAX_CLIENT_DEVICE_ID_LEN
+        ))),
+        id => Ok(id),
+    }
+}
+
+/// Create or reuse the device a session is for
+///
+/// A device that sends a `client_device_id` is offered a secret; it's stored
+/// if the device has none yet and returned so the client can present it as
+/// `device_secret` next time. Reusing a device without its secret keeps it
+/// approved only if the user doesn't require approval.
+async fn register_device(
+    state: &AppState,
+    user: &User,
+    device_name: &str,
+    device_type: DeviceType,
+    approved: bool,
+    client_device_id: Option<&str>,
+    presented_secret: Option<&str>,
+) -> Result<(Device, Option<String>)> {
+    let secret = client_device_id.map(|_| {
+        let mut secret_bytes = [0u8; 32];
+        rand::thread_rng().fill(&mut secret_bytes);
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(secret_bytes)
+    });
+    let issued_hash = secret.as_deref().map(hash_token);
+    let presented_hash = presented_secret.map(hash_token);
+    let identity = client_device_id.zip(issued_hash.as_deref()).map(
+        |(client_device_id, issued_secret_hash)| DeviceIdentity {
+            client_device_id,
+            issued_secret_hash,
+            presented_secret_hash: presented_hash.as_deref(),
+            approved_without_secret: !user.require_device_approval,
+        },
+    );
+
+    let (device, secret_issued) = db::create_device(
+        &state.db,
+        user.id,
+        device_name,
+        device_type,
+        None,
+        approved,
+        identity,
+    )
+    .await?;
+    Ok((device, secret.filter(|_| secret_issued)))
 }
 
 async fn register(
     State(state): State<AppState>,
     Json(req): Json<RegisterRequest>,
 ) -> Result<Json<RegisterResponse>> {
+    let client_device_id = client_device_id(req.client_device_id.as_deref())?;
+
     // Check if user already exists
     if db::get_user_by_email(&state.db, &req.email)
         .await?
@@ -64,26 +238,63 @@ async fn register(
     let user = db::create_user(&state.db, &req.email, &auth_key_hash, &req.salt).await?;
 
     // Create device
-    let device_type = DeviceType::from(req.device_type);
-    let device = db::create_device(&state.db, user.id, &req.device_name, device_type, None).await?;
+    let (device, device_secret) = register_device(
+        &state,
+        &user,
+        &req.device_name,
+        DeviceType::from(req.device_type),
+        true,
+        client_device_id,
+        None,
+    )
+    .await?;
 
     // Generate tokens
     let tokens = generate_token_pair(user.id, device.id, &state.jwt_secret)?;
 
-    // Store refresh token hash
+    // Store refresh token hash, starting a new token family
     let token_hash = hash_refresh_token(&tokens.refresh_token);
     let expires_at = Utc::now() + Duration::days(REFRESH_TOKEN_EXPIRY_DAYS);
-    db::create_refresh_token(&state.db, user.id, device.id, &token_hash, expires_at).await?;
+    db::create_refresh_token(
+        &state.db,
+        user.id,
+        device.id,
+        Uuid::new_v4(),
+        &token_hash,
+        expires_at,
+    )
+    .await?;
 
     // Initialize sync version for user
     db::increment_sync_version(&state.db, user.id).await?;
 
+    // Without a mailer there's no way to confirm the address, so skip it
+    let email_verified = match &state.mailer {
+        Some(mailer) => {
+            // The user can ask for another link if this one doesn't arrive
+            if let Err(e) = send_verification_email(&state, mailer, user.id, &user.email).await {
+                tracing::warn!(
+                    "Failed to send verification email to user {}: {}",
+                    user.id,
+                    e
+                );
+            }
+            false
+        }
+        None => {
+            db::mark_email_verified(&state.db, user.id).await?;
+            true
+        }
+    };
+
     Ok(Json(RegisterResponse {
         user_id: user.id,
         device_id: device.id,
         access_token: tokens.access_token,
         refresh_token: tokens.refresh_token,
         expires_in: tokens.expires_in,
+        email_verified,
+        device_secret,
     }))
 }
 
@@ -93,6 +304,17 @@ pub struct LoginRequest {
     pub auth_key: String,
     pub device_name: String,
     pub device_type: String,
+    /// Stable identifier the client generates once and keeps; logging in
+    /// again with it reuses the device instead of registering a new one
+    #[serde(default)]
+    pub client_device_id: Option<String>,
+    /// Secret issued to this `client_device_id` by an earlier login; without
+    /// it the device has to be approved again if approval is required
+    #[serde(default)]
+    pub device_secret: Option<String>,
+    /// TOTP or recovery code, required once two-factor auth is enabled
+    #[serde(default)]
+    pub totp_code: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -103,45 +325,141 @@ pub struct LoginResponse {
     pub access_token: String,
     pub refresh_token: String,
     pub expires_in: i64,
+    pub email_verified: bool,
+    /// False if the device has to be approved from another one before syncing
+    pub device_approved: bool,
+    /// Secret for a device first seen with this `client_device_id`, to send
+    /// back as `device_secret` on later logins
+    pub device_secret: Option<String>,
+}
+
+/// Result of a password login: either the session, or a passkey challenge
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginOutcome {
+    Complete(LoginResponse),
+    /// Finish with `POST /auth/webauthn/login`
+    WebauthnRequired(webauthn::LoginChallenge),
 }
 
 async fn login(
     State(state): State<AppState>,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>> {
+) -> Result<Json<LoginOutcome>> {
     // Find user
     let user = db::get_user_by_email(&state.db, &req.email)
         .await?
         .ok_or(AppError::InvalidCredentials)?;
 
-    // Verify auth_key
-    let parsed_hash = PasswordHash::new(&user.auth_key_hash)
-        .map_err(|_| AppError::Internal("Invalid stored hash".to_string()))?;
+    verify_auth_key(&state, &user, &req.auth_key).await?;
+    let client_device_id = client_device_id(req.client_device_id.as_deref())?;
 
-    Argon2::default()
-        .verify_password(req.auth_key.as_bytes(), &parsed_hash)
-        .map_err(|_| AppError::InvalidCredentials)?;
+    // With passkeys registered, a TOTP code is only an alternative if TOTP is
+    // enabled too; otherwise the client has to answer a passkey challenge
+    let passkeys = db::get_webauthn_credentials_by_user(&state.db, user.id).await?;
+    let totp_fallback = user.totp_enabled_at.is_some() && req.totp_code.is_some();
+    if !passkeys.is_empty() && !totp_fallback {
+        let challenge = webauthn::start_login_challenge(&state, &user, &passkeys).await?;
+        return Ok(Json(LoginOutcome::WebauthnRequired(challenge)));
+    }
+    two_factor::verify_second_factor(&state, &user, req.totp_code.as_deref()).await?;
+
+    let response = complete_login(
+        &state,
+        user,
+        &req.device_name,
+        req.device_type,
+        client_device_id,
+        req.device_secret.as_deref(),
+    )
+    .await?;
+    Ok(Json(LoginOutcome::Complete(response)))
+}
 
-    // Create or find device
-    let device_type = DeviceType::from(req.device_type);
-    let device = db::create_device(&state.db, user.id, &req.device_name, device_type, None).await?;
+/// Register the device and issue tokens once every factor checked out
+pub(crate) async fn complete_login(
+    state: &AppState,
+    user: User,
+    device_name: &str,
+    device_type: String,
+    client_device_id: Option<&str>,
+    device_secret: Option<&str>,
+) -> Result<LoginResponse> {
+    // Signing in during the grace period keeps the account
+    if db::cancel_user_deletion(&state.db, user.id).await? {
+        tracing::info!("Scheduled deletion of user {} cancelled by login", user.id);
+    }
+
+    // With approval required, a new device waits for one of the approved
+    // devices to let it in; with none left there is nobody to ask
+    let approvers: Vec<_> = if user.require_device_approval {
+        db::get_devices_by_user(&state.db, user.id)
+            .await?
+            .into_iter()
+            .filter(|d| d.approved_at.is_some())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // Create the device, or reuse the one with the same client identifier.
+    // Reusing it without its secret leaves it pending, and the device itself
+    // may be among the approvers it now has to ask.
+    let (device, device_secret) = register_device(
+        state,
+        &user,
+        device_name,
+        DeviceType::from(device_type),
+        approvers.is_empty(),
+        client_device_id,
+        device_secret,
+    )
+    .await?;
+    if device.approved_at.is_none() {
+        let approvers: Vec<_> = approvers
+            .into_iter()
+            .filter(|d| d.id != device.id)
+            .collect();
+        devices::request_device_approval(state, user.id, device.id, &approvers).await?;
+    }
+    db::create_security_event(
+        &state.db,
+        user.id,
+        "login",
+        Some(serde_json::json!({
+            "device_id": device.id,
+            "device_name": device.device_name,
+        })),
+    )
+    .await?;
 
     // Generate tokens
     let tokens = generate_token_pair(user.id, device.id, &state.jwt_secret)?;
 
-    // Store refresh token hash
+    // Store refresh token hash, starting a new token family
     let token_hash = hash_refresh_token(&tokens.refresh_token);
     let expires_at = Utc::now() + Duration::days(REFRESH_TOKEN_EXPIRY_DAYS);
-    db::create_refresh_token(&state.db, user.id, device.id, &token_hash, expires_at).await?;
-
-    Ok(Json(LoginResponse {
+    db::create_refresh_token(
+        &state.db,
+        user.id,
+        device.id,
+        Uuid::new_v4(),
+        &token_hash,
+        expires_at,
+    )
+    .await?;
+
+    Ok(LoginResponse {
         user_id: user.id,
         device_id: device.id,
         salt: user.salt,
         access_token: tokens.access_token,
         refresh_token: tokens.refresh_token,
         expires_in: tokens.expires_in,
-    }))
+        email_verified: user.email_verified_at.is_some(),
+        device_approved: device.approved_at.is_some(),
+        device_secret,
+    })
 }
 
 #[derive(Debug, Deserialize)]
@@ -156,6 +474,12 @@ pub struct RefreshResponse {
     pub expires_in: i64,
 }
 
+/// Rotate a refresh token
+///
+/// The presented token is revoked and replaced by one in the same family.
+/// A token that was already rotated can only come from a copy, so presenting
+/// it revokes the whole family, signing out both the thief and the user, and
+/// alerts the user's other devices.
 async fn refresh(
     State(state): State<AppState>,
     Json(req): Json<RefreshRequest>,
@@ -179,16 +503,42 @@ async fn refresh(
         .await?
         .ok_or(AppError::InvalidToken)?;
 
-    // Delete old refresh token
-    db::delete_refresh_token(&state.db, stored_token.id).await?;
+    // Revoke the old refresh token; if it already was, it's being replayed
+    if stored_token.revoked_at.is_some()
+        || !db::revoke_refresh_token(&state.db, stored_token.id).await?
+    {
+        let revoked = db::revoke_refresh_token_family(&state.db, stored_token.family_id).await?;
+        tracing::warn!(
+            "Refresh token reuse for user {} (device {}), revoked {} tokens in family {}",
+            stored_token.user_id,
+            stored_token.device_id,
+            revoked,
+            stored_token.family_id
+        );
+        let _ = state.sync_tx.send(SyncNotification {
+            user_id: stored_token.user_id,
+            notification_type: SyncNotificationType::SecurityAlert,
+            version: 0,
+            source_device_id: None,
+        });
+        return Err(AppError::InvalidToken);
+    }
 
     // Generate new token pair
     let tokens = generate_token_pair(user_id, device_id, &state.jwt_secret)?;
 
-    // Store new refresh token hash
+    // Store new refresh token hash in the same family
     let new_token_hash = hash_refresh_token(&tokens.refresh_token);
     let expires_at = Utc::now() + Duration::days(REFRESH_TOKEN_EXPIRY_DAYS);
-    db::create_refresh_token(&state.db, user_id, device_id, &new_token_hash, expires_at).await?;
+    db::create_refresh_token(
+        &state.db,
+        user_id,
+        device_id,
+        stored_token.family_id,
+        &new_token_hash,
+        expires_at,
+    )
+    .await?;
 
     // Update device last seen
     db::update_device_last_seen(&state.db, device_id).await?;
@@ -199,3 +549,359 @@ async fn refresh(
         expires_in: tokens.expires_in,
     }))
 }
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeAuthKeyRequest {
+    pub old_auth_key: String,
+    pub new_auth_key: String,
+    /// Base64-encoded salt the new master key was derived with
+    pub new_salt: String,
+    /// Server version the items were re-encrypted from
+    pub base_version: i64,
+    /// Every item that isn't deleted, re-encrypted with the new vault key
+    pub items: Vec<SyncItem>,
+    /// Public key derived from the new sharing key, for vaults this user is
+    /// an emergency contact of
+    #[serde(default)]
+    pub sharing_public_key: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangeAuthKeyResponse {
+    pub new_version: i64,
+}
+
+/// Change the master password
+///
+/// The new auth key and salt, and the vault re-encrypted under the new key,
+/// replace the old ones in a single transaction, so other devices never see
+/// a mix of old and new. Other devices' sessions are revoked; they have to
+/// sign in again with the new password. Vault keys sealed for emergency
+/// contacts are dropped until the owner's client seals the new one.
+async fn change_auth_key(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
+    Json(req): Json<ChangeAuthKeyRequest>,
+) -> Result<Json<ChangeAuthKeyResponse>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    if req.new_auth_key.is_empty() || req.new_salt.is_empty() {
+        return Err(AppError::BadRequest(
+            "New auth key and salt are required".to_string(),
+        ));
+    }
+    let blob_storage = state
+        .blob_storage
+        .as_ref()
+        .ok_or_else(|| AppError::Internal("Blob storage not configured".into()))?;
+
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    verify_auth_key(&state, &user, &req.old_auth_key).await?;
+    two_factor::verify_second_factor(&state, &user, two_factor::header_code(&headers)).await?;
+    if let Some(public_key) = &req.sharing_public_key {
+        emergency::validate_public_key(public_key)?;
+    }
+
+    let salt = SaltString::generate(&mut OsRng);
+    let auth_key_hash = Argon2::default()
+        .hash_password(req.new_auth_key.as_bytes(), &salt)
+        .map_err(|e| AppError::Internal(format!("Failed to hash auth key: {}", e)))?
+        .to_string();
+
+    let mut decoded = Vec::with_capacity(req.items.len());
+    for item in &req.items {
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(&item.encrypted_data)
+            .map_err(|e| AppError::BadRequest(format!("Invalid base64 data: {}", e)))?;
+        decoded.push((item, data));
+    }
+
+    // Blobs can't take part in the transaction; those this change created
+    // are removed again if it fails
+    let mut stored_blobs = Vec::new();
+    let result = async {
+        let mut tx = state.db.begin().await?;
+
+        if db::lock_sync_version(&mut tx, auth_user.user_id).await? != req.base_version {
+            return Err(AppError::Conflict(
+                "Vault changed since it was re-encrypted; sync and try again".to_string(),
+            ));
+        }
+        let submitted: HashSet<Uuid> = req.items.iter().map(|item| item.id).collect();
+        let missing = db::get_live_vault_item_ids(&mut *tx, auth_user.user_id)
+            .await?
+            .into_iter()
+            .filter(|id| !submitted.contains(id))
+            .count();
+        if missing > 0 {
+            return Err(AppError::BadRequest(format!(
+                "{} items were not re-encrypted",
+                missing
+            )));
+        }
+
+        db::update_user_auth_key(&mut *tx, auth_user.user_id, &auth_key_hash, &req.new_salt)
+            .await?;
+        // Still bump once for an empty vault so other devices hear about it
+        let new_version =
+            db::advance_sync_version(&mut *tx, auth_user.user_id, decoded.len().max(1) as i64)
+                .await?;
+        let first_version = new_version - decoded.len() as i64 + 1;
+        for (version, (item, data)) in (first_version..).zip(&decoded) {
+            let (blob_id, created) = blob_storage
+                .store_deduplicated(auth_user.user_id, data)
+                .await?;
+            if created {
+                stored_blobs.push(blob_id.clone());
+            }
+            db::upsert_vault_item(
+                &mut *tx,
+                item.id,
+                auth_user.user_id,
+                version,
+                &blob_id,
+                item.is_deleted,
+            )
+            .await?
+            .ok_or_else(|| {
+                AppError::Conflict(format!("Item {} belongs to another account", item.id))
+            })?;
+        }
+        db::revoke_other_refresh_tokens(&mut *tx, auth_user.user_id, auth_user.device_id).await?;
+        // Vault keys sealed for emergency contacts are under the old password
+        db::clear_emergency_vault_keys(&mut *tx, auth_user.user_id).await?;
+        if let Some(public_key) = &req.sharing_public_key {
+            db::update_emergency_contact_public_key(&mut *tx, auth_user.user_id, public_key)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(new_version)
+    }
+    .await;
+
+    let new_version = match result {
+        Ok(version) => version,
+        Err(e) => {
+            for blob_id in &stored_blobs {
+                if blob_storage.delete(blob_id).await.is_err() {
+                    let _ = db::record_superseded_blob(&state.db, blob_id).await;
+                }
+            }
+            return Err(e);
+        }
+    };
+
+    let _ = state.sync_tx.send(SyncNotification {
+        user_id: auth_user.user_id,
+        notification_type: SyncNotificationType::AuthKeyChanged,
+        version: new_version,
+        source_device_id: Some(auth_user.device_id),
+    });
+
+    Ok(Json(ChangeAuthKeyResponse { new_version }))
+}
+
+/// Issue a fresh verification token, invalidating older ones, and email it
+async fn send_verification_email(
+    state: &AppState,
+    mailer: &Mailer,
+    user_id: Uuid,
+    email: &str,
+) -> Result<()> {
+    let mut token_bytes = [0u8; 32];
+    rand::thread_rng().fill(&mut token_bytes);
+    let token = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(token_bytes);
+
+    let expires_at = Utc::now() + Duration::hours(EMAIL_VERIFICATION_EXPIRY_HOURS);
+    db::create_email_verification_token(&state.db, user_id, &hash_token(&token), expires_at)
+        .await?;
+
+    mailer.send_verification(email, &token).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailQuery {
+    pub token: String,
+}
+
+/// Confirm an email address from the link in the verification email
+async fn verify_email(
+    State(state): State<AppState>,
+    Query(query): Query<VerifyEmailQuery>,
+) -> Result<&'static str> {
+    let user_id = db::consume_email_verification_token(&state.db, &hash_token(&query.token))
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Invalid or expired verification link".to_string()))?;
+
+    db::mark_email_verified(&state.db, user_id).await?;
+
+    let _ = state.sync_tx.send(SyncNotification {
+        user_id,
+        notification_type: SyncNotificationType::EmailVerified,
+        version: 0,
+        source_device_id: None,
+    });
+
+    Ok("Email address verified. You can return to Keydrop.")
+}
+
+/// Send another verification email
+async fn resend_verification(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+) -> Result<()> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    if user.email_verified_at.is_some() {
+        return Err(AppError::BadRequest(
+            "Email address already verified".to_string(),
+        ));
+    }
+    let mailer = state
+        .mailer
+        .as_ref()
+        .ok_or_else(|| AppError::Internal("Mailer not configured".into()))?;
+
+    send_verification_email(&state, mailer, user.id, &user.email).await
+}
+
+#[derive(Debug, Serialize)]
+pub struct SecurityLogEntry {
+    pub id: Uuid,
+    pub action: String,
+    pub details: Option<serde_json::Value>,
+    pub created_at: i64,
+}
+
+/// Recent sign-ins, failed auth_key attempts and lockouts
+async fn get_security_log(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<Vec<SecurityLogEntry>>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+
+    let events = db::get_security_events_for_user(&state.db, auth_user.user_id, 100).await?;
+
+    let response: Vec<SecurityLogEntry> = events
+        .into_iter()
+        .map(|e| SecurityLogEntry {
+            id: e.id,
+            action: e.action,
+            details: e.details,
+            created_at: e.created_at.timestamp(),
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteAccountRequest {
+    pub auth_key: String,
+    /// Days to wait before purging; 0 purges immediately
+    #[serde(default)]
+    pub grace_period_days: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteAccountResponse {
+    /// When the account will be purged, or `None` if it already was
+    pub scheduled_for: Option<DateTime<Utc>>,
+}
+
+/// Delete the account and everything stored for it
+///
+/// Requires the current auth_key. With a grace period the account is only
+/// marked and signed out everywhere; logging in before it ends cancels the
+/// deletion, otherwise `purge_due_accounts` removes it.
+async fn delete_account(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
+    Json(req): Json<DeleteAccountRequest>,
+) -> Result<Json<DeleteAccountResponse>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    if req.grace_period_days > MAX_DELETION_GRACE_DAYS {
+        return Err(AppError::BadRequest(format!(
+            "Grace period can be at most {} days",
+            MAX_DELETION_GRACE_DAYS
+        )));
+    }
+
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    verify_auth_key(&state, &user, &req.auth_key).await?;
+    two_factor::verify_second_factor(&state, &user, two_factor::header_code(&headers)).await?;
+
+    let scheduled_for = if req.grace_period_days == 0 {
+        purge_account(&state, user.id).await?;
+        None
+    } else {
+        let scheduled_for = Utc::now() + Duration::days(i64::from(req.grace_period_days));
+        db::schedule_user_deletion(&state.db, user.id, scheduled_for).await?;
+        db::revoke_user_refresh_tokens(&state.db, user.id).await?;
+        tracing::info!(
+            "User {} scheduled for deletion at {}",
+            user.id,
+            scheduled_for
+        );
+        Some(scheduled_for)
+    };
+
+    let _ = state.sync_tx.send(SyncNotification {
+        user_id: user.id,
+        notification_type: SyncNotificationType::AccountDeleted,
+        version: 0,
+        source_device_id: Some(auth_user.device_id),
+    });
+
+    Ok(Json(DeleteAccountResponse { scheduled_for }))
+}
+
+/// Remove a user's rows and blobs for good
+///
+/// The database goes first so a blob storage failure can't leave a live
+/// account with missing data; blobs left behind are only logged.
+pub async fn purge_account(state: &AppState, user_id: Uuid) -> Result<()> {
+    let mut tx = state.db.begin().await?;
+    db::revoke_emergency_contacts_for_contact_user(&mut *tx, user_id).await?;
+    db::delete_user(&mut *tx, user_id).await?;
+    tx.commit().await?;
+
+    if let Some(blob_storage) = &state.blob_storage {
+        match blob_storage.delete_prefix(&format!("{}/", user_id)).await {
+            Ok(removed) => tracing::info!("Purged user {} and {} blobs", user_id, removed),
+            Err(e) => tracing::error!("Purged user {} but failed to delete blobs: {}", user_id, e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Purge every account whose deletion grace period has ended
+pub async fn purge_due_accounts(state: &AppState) -> Result<u64> {
+    let due = db::get_users_due_for_deletion(&state.db).await?;
+    for &user_id in &due {
+        purge_account(state, user_id).await?;
+    }
+    Ok(due.len() as u64)
+}
+
+/// Purge accounts that never verified their email address
+pub async fn purge_unverified_accounts(state: &AppState) -> Result<u64> {
+    let cutoff = Utc::now() - Duration::days(UNVERIFIED_ACCOUNT_EXPIRY_DAYS);
+    let stale = db::get_unverified_users(&state.db, cutoff).await?;
+    for &user_id in &stale {
+        purge_account(state, user_id).await?;
+    }
+    Ok(stale.len() as u64)
+}
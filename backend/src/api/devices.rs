@@ -1,5 +1,6 @@
 use axum::{
     extract::{Path, State},
+    http::HeaderMap,
     routing::{delete, get, post},
     Json, Router,
 };
@@ -11,9 +12,12 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::two_factor;
 use crate::{
     auth::{jwt::validate_access_token, AuthUser},
-    db::{self, AuthRequestStatus, RemoteCommandStatus, RemoteCommandType},
+    db::{
+        self, AuthRequestPurpose, AuthRequestStatus, Device, RemoteCommandStatus, RemoteCommandType,
+    },
     sync::{SyncNotification, SyncNotificationType},
     AppError, AppState, Result,
 };
@@ -21,18 +25,26 @@ use crate::{
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/", get(list_devices))
-        .route("/{device_id}", get(get_device))
-        .route("/{device_id}", delete(delete_device))
-        .route("/{device_id}/push-token", post(update_push_token))
-        .route("/{device_id}/auth-request", post(create_auth_request))
-        .route("/{device_id}/auth-response", post(respond_auth_request))
+        .route("/:device_id", get(get_device))
+        .route("/:device_id", delete(delete_device))
+        .route("/:device_id/push-token", post(update_push_token))
+        .route("/:device_id/auth-request", post(create_auth_request))
+        .route("/:device_id/auth-response", post(respond_auth_request))
         .route("/auth-requests/pending", get(get_pending_auth_requests))
-        .route("/{device_id}/lock", post(lock_device))
-        .route("/{device_id}/wipe", post(wipe_device))
+        .route("/:device_id/lock", post(lock_device))
+        .route("/:device_id/wipe", post(wipe_device))
+        .route("/:device_id/approve", post(approve_device))
         .route("/commands", get(get_pending_commands))
-        .route("/commands/{command_id}/ack", post(acknowledge_command))
+        .route("/commands/:command_id/ack", post(acknowledge_command))
+        .route(
+            "/approval-policy",
+            get(get_approval_policy).put(set_approval_policy),
+        )
 }
 
+/// How long a new device's approval requests stay answerable
+pub const DEVICE_APPROVAL_EXPIRY_HOURS: i64 = 24;
+
 /// Extract and validate auth from Authorization header
 async fn extract_auth(
     state: &AppState,
@@ -54,6 +66,24 @@ async fn extract_auth(
     Ok(AuthUser { user_id, device_id })
 }
 
+/// Refuse devices that are gone or still waiting for approval
+pub(crate) async fn require_approved_device(state: &AppState, device_id: Uuid) -> Result<()> {
+    let device = db::get_device_by_id(&state.db, device_id)
+        .await?
+        .ok_or(AppError::DeviceNotFound)?;
+    if device.approved_at.is_none() {
+        return Err(AppError::DeviceNotApproved);
+    }
+    Ok(())
+}
+
+/// Random 32-byte challenge, base64 encoded
+fn generate_challenge() -> String {
+    let mut challenge_bytes = [0u8; 32];
+    rand::thread_rng().fill(&mut challenge_bytes);
+    base64::engine::general_purpose::STANDARD.encode(challenge_bytes)
+}
+
 #[derive(Debug, Serialize)]
 pub struct DeviceResponse {
     pub id: Uuid,
@@ -62,6 +92,8 @@ pub struct DeviceResponse {
     pub last_seen_at: i64,
     pub created_at: i64,
     pub is_current: bool,
+    /// False while the device waits for approval
+    pub approved: bool,
 }
 
 async fn list_devices(
@@ -80,6 +112,7 @@ async fn list_devices(
             last_seen_at: d.last_seen_at.timestamp(),
             created_at: d.created_at.timestamp(),
             is_current: d.id == auth_user.device_id,
+            approved: d.approved_at.is_some(),
         })
         .collect();
 
@@ -108,12 +141,14 @@ async fn get_device(
         last_seen_at: device.last_seen_at.timestamp(),
         created_at: device.created_at.timestamp(),
         is_current: device.id == auth_user.device_id,
+        approved: device.approved_at.is_some(),
     }))
 }
 
 async fn delete_device(
     State(state): State<AppState>,
     auth_header: TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
     Path(device_id): Path<Uuid>,
 ) -> Result<Json<serde_json::Value>> {
     let auth_user = extract_auth(&state, auth_header).await?;
@@ -132,6 +167,8 @@ async fn delete_device(
             "Cannot delete current device".to_string(),
         ));
     }
+    require_approved_device(&state, auth_user.device_id).await?;
+    two_factor::require_second_factor(&state, auth_user.user_id, &headers).await?;
 
     db::delete_device(&state.db, device_id).await?;
 
@@ -202,10 +239,7 @@ async fn create_auth_request(
         ));
     }
 
-    // Generate challenge (random 32 bytes, base64 encoded)
-    let mut challenge_bytes = [0u8; 32];
-    rand::thread_rng().fill(&mut challenge_bytes);
-    let challenge = base64::engine::general_purpose::STANDARD.encode(challenge_bytes);
+    let challenge = generate_challenge();
 
     // Expires in 5 minutes
     let expires_at = Utc::now() + Duration::minutes(5);
@@ -216,6 +250,7 @@ async fn create_auth_request(
         target_device_id,
         &challenge,
         expires_at,
+        AuthRequestPurpose::Biometric,
     )
     .await?;
 
@@ -313,6 +348,8 @@ pub struct PendingAuthRequest {
     pub challenge: String,
     pub expires_at: i64,
     pub created_at: i64,
+    /// `biometric` or `device_approval`
+    pub purpose: String,
 }
 
 async fn get_pending_auth_requests(
@@ -330,12 +367,142 @@ async fn get_pending_auth_requests(
             challenge: r.challenge,
             expires_at: r.expires_at.timestamp(),
             created_at: r.created_at.timestamp(),
+            purpose: r.purpose,
         })
         .collect();
 
     Ok(Json(response))
 }
 
+// ============ New-Device Approval ============
+
+/// Ask each of `approvers` to let a newly signed-in device sync
+pub(crate) async fn request_device_approval(
+    state: &AppState,
+    user_id: Uuid,
+    device_id: Uuid,
+    approvers: &[Device],
+) -> Result<()> {
+    let expires_at = Utc::now() + Duration::hours(DEVICE_APPROVAL_EXPIRY_HOURS);
+    for approver in approvers {
+        db::create_auth_request(
+            &state.db,
+            device_id,
+            approver.id,
+            &generate_challenge(),
+            expires_at,
+            AuthRequestPurpose::DeviceApproval,
+        )
+        .await?;
+    }
+
+    let _ = state.sync_tx.send(SyncNotification {
+        user_id,
+        notification_type: SyncNotificationType::DeviceApprovalRequested,
+        version: 0,
+        source_device_id: Some(device_id),
+    });
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApprovalPolicy {
+    pub require_approval: bool,
+}
+
+async fn get_approval_policy(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<ApprovalPolicy>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    Ok(Json(ApprovalPolicy {
+        require_approval: user.require_device_approval,
+    }))
+}
+
+/// Turn new-device approval on or off; turning it off needs the second factor
+async fn set_approval_policy(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
+    Json(req): Json<ApprovalPolicy>,
+) -> Result<Json<ApprovalPolicy>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    require_approved_device(&state, auth_user.device_id).await?;
+    if !req.require_approval {
+        two_factor::require_second_factor(&state, auth_user.user_id, &headers).await?;
+    }
+
+    db::set_device_approval_required(&state.db, auth_user.user_id, req.require_approval).await?;
+
+    Ok(Json(req))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApproveDeviceRequest {
+    pub approved: bool,
+}
+
+/// Answer a pending device's approval request from an approved device
+///
+/// Approving lets the device sync; rejecting removes it, which also revokes
+/// its refresh tokens.
+async fn approve_device(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    Path(device_id): Path<Uuid>,
+    Json(req): Json<ApproveDeviceRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    require_approved_device(&state, auth_user.device_id).await?;
+
+    let device = db::get_device_by_id(&state.db, device_id)
+        .await?
+        .ok_or(AppError::DeviceNotFound)?;
+    if device.user_id != auth_user.user_id {
+        return Err(AppError::DeviceNotFound);
+    }
+    if device.approved_at.is_some() {
+        return Err(AppError::BadRequest(
+            "Device is already approved".to_string(),
+        ));
+    }
+    if !db::has_pending_device_approval(&state.db, device_id).await? {
+        return Err(AppError::BadRequest(
+            "Approval request has expired; sign in again on the new device".to_string(),
+        ));
+    }
+
+    let status = if req.approved {
+        AuthRequestStatus::Approved
+    } else {
+        AuthRequestStatus::Rejected
+    };
+    db::resolve_device_approval_requests(&state.db, device_id, auth_user.device_id, status).await?;
+
+    let notification_type = if req.approved {
+        db::approve_device(&state.db, device_id).await?;
+        SyncNotificationType::DeviceApproved
+    } else {
+        db::delete_device(&state.db, device_id).await?;
+        SyncNotificationType::DeviceRemoved
+    };
+
+    let _ = state.sync_tx.send(SyncNotification {
+        user_id: auth_user.user_id,
+        notification_type,
+        version: 0,
+        source_device_id: Some(auth_user.device_id),
+    });
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
 // ============ Remote Lock/Wipe ============
 
 async fn lock_device(
@@ -360,6 +527,7 @@ async fn lock_device(
             "Cannot remotely lock current device".to_string(),
         ));
     }
+    require_approved_device(&state, auth_user.device_id).await?;
 
     // Create lock command
     let command = db::create_remote_command(
@@ -389,6 +557,7 @@ async fn lock_device(
 async fn wipe_device(
     State(state): State<AppState>,
     auth_header: TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
     Path(target_device_id): Path<Uuid>,
 ) -> Result<Json<serde_json::Value>> {
     let auth_user = extract_auth(&state, auth_header).await?;
@@ -408,6 +577,8 @@ async fn wipe_device(
             "Cannot remotely wipe current device".to_string(),
         ));
     }
+    require_approved_device(&state, auth_user.device_id).await?;
+    two_factor::require_second_factor(&state, auth_user.user_id, &headers).await?;
 
     // Create wipe command
     let command = db::create_remote_command(
@@ -1,6 +1,6 @@
 use axum::{
-    extract::{Path, State},
-    routing::{delete, get, post},
+    extract::{Path, Query, State},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use axum_extra::TypedHeader;
@@ -10,10 +10,11 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::sync;
 use crate::{
     auth::jwt::validate_access_token,
     db::{self, EmergencyAccessRequestStatus, EmergencyContactStatus},
-    sync::{SyncNotification, SyncNotificationType},
+    sync::{SyncNotification, SyncNotificationType, SyncPullResponse},
     AppError, AppState, Result,
 };
 
@@ -21,12 +22,14 @@ pub fn router() -> Router<AppState> {
     Router::new()
         .route("/contacts", post(add_contact))
         .route("/contacts", get(list_contacts))
-        .route("/contacts/{id}", delete(remove_contact))
-        .route("/contacts/{id}/accept", post(accept_invitation))
+        .route("/contacts/:id", delete(remove_contact))
+        .route("/contacts/:id/accept", post(accept_invitation))
+        .route("/contacts/:id/key", put(share_vault_key))
         .route("/request", post(request_access))
         .route("/requests", get(list_requests))
-        .route("/requests/{id}/deny", post(deny_request))
+        .route("/requests/:id/deny", post(deny_request))
         .route("/vault", get(get_vault_access))
+        .route("/vault/:id/items", get(get_granted_vault_items))
         .route("/granted", get(list_granted_access))
         .route("/logs", get(get_logs))
 }
@@ -63,6 +66,10 @@ pub struct EmergencyContactResponse {
     pub can_view_vault: bool,
     pub accepted_at: Option<i64>,
     pub created_at: i64,
+    /// Key the owner's client seals the vault key for, once accepted
+    pub contact_public_key: Option<String>,
+    /// Whether a sealed vault key is stored for the contact
+    pub vault_key_shared: bool,
 }
 
 async fn add_contact(
@@ -115,6 +122,8 @@ async fn add_contact(
         can_view_vault: contact.can_view_vault,
         accepted_at: contact.accepted_at.map(|t| t.timestamp()),
         created_at: contact.created_at.timestamp(),
+        contact_public_key: contact.contact_public_key,
+        vault_key_shared: contact.vault_key_encrypted.is_some(),
     }))
 }
 
@@ -136,6 +145,8 @@ async fn list_contacts(
             can_view_vault: c.can_view_vault,
             accepted_at: c.accepted_at.map(|t| t.timestamp()),
             created_at: c.created_at.timestamp(),
+            contact_public_key: c.contact_public_key,
+            vault_key_shared: c.vault_key_encrypted.is_some(),
         })
         .collect();
 
@@ -163,13 +174,16 @@ async fn remove_contact(
 
     db::delete_emergency_contact(&state.db, contact_id).await?;
 
-    // Log the action
+    // Log the action; the contact row is gone, so record it in the details
     db::create_emergency_access_log(
         &state.db,
         user_id,
-        Some(contact_id),
-        "contact_removed",
         None,
+        "contact_removed",
+        Some(serde_json::json!({
+            "contact_id": contact_id.to_string(),
+            "email": contact.contact_email,
+        })),
         None,
     )
     .await?;
@@ -182,6 +196,18 @@ async fn remove_contact(
 #[derive(Debug, Deserialize)]
 pub struct AcceptInvitationRequest {
     pub token: String,
+    /// X25519 public key (base64) derived from the contact's sharing key
+    pub public_key: String,
+}
+
+/// Check that `key` is a base64 X25519 public key
+pub fn validate_public_key(key: &str) -> Result<()> {
+    let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, key)
+        .map_err(|_| AppError::BadRequest("Invalid public key".to_string()))?;
+    if bytes.len() != 32 {
+        return Err(AppError::BadRequest("Invalid public key".to_string()));
+    }
+    Ok(())
 }
 
 async fn accept_invitation(
@@ -191,6 +217,7 @@ async fn accept_invitation(
     Json(req): Json<AcceptInvitationRequest>,
 ) -> Result<Json<serde_json::Value>> {
     let accepting_user_id = extract_user_id(&state, &auth_header).await?;
+    validate_public_key(&req.public_key)?;
 
     // Find contact by ID and verify token
     let contact = db::get_emergency_contact_by_id(&state.db, contact_id)
@@ -209,7 +236,13 @@ async fn accept_invitation(
     }
 
     // Accept the invitation
-    db::accept_emergency_contact_invitation(&state.db, contact_id, accepting_user_id).await?;
+    db::accept_emergency_contact_invitation(
+        &state.db,
+        contact_id,
+        accepting_user_id,
+        &req.public_key,
+    )
+    .await?;
 
     // Log the action
     db::create_emergency_access_log(
@@ -233,6 +266,61 @@ async fn accept_invitation(
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
+// ============ Vault Key (Owner Side) ============
+
+#[derive(Debug, Deserialize)]
+pub struct ShareVaultKeyRequest {
+    /// Owner's vault key sealed for the contact's public key (base64)
+    pub vault_key_encrypted: String,
+}
+
+/// Store the vault key the owner's client sealed for an accepted contact
+///
+/// The server can't open it; it's handed to the contact when a request is
+/// granted, including requests granted before it was stored.
+async fn share_vault_key(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    Path(contact_id): Path<Uuid>,
+    Json(req): Json<ShareVaultKeyRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let user_id = extract_user_id(&state, &auth_header).await?;
+
+    let contact = db::get_emergency_contact_by_id(&state.db, contact_id)
+        .await?
+        .filter(|contact| contact.user_id == user_id)
+        .ok_or(AppError::NotFound(
+            "Emergency contact not found".to_string(),
+        ))?;
+    if contact.status != EmergencyContactStatus::Accepted || contact.contact_public_key.is_none() {
+        return Err(AppError::Conflict(
+            "The contact hasn't accepted the invitation".to_string(),
+        ));
+    }
+    if base64::Engine::decode(
+        &base64::engine::general_purpose::STANDARD,
+        &req.vault_key_encrypted,
+    )
+    .map_or(true, |key| key.is_empty())
+    {
+        return Err(AppError::BadRequest("Invalid sealed key".to_string()));
+    }
+
+    db::set_emergency_contact_vault_key(&state.db, contact_id, &req.vault_key_encrypted).await?;
+
+    db::create_emergency_access_log(
+        &state.db,
+        user_id,
+        Some(contact_id),
+        "vault_key_shared",
+        None,
+        None,
+    )
+    .await?;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
 // ============ Access Request (Contact Side) ============
 
 #[derive(Debug, Deserialize)]
@@ -482,27 +570,8 @@ async fn get_vault_access(
             if request.status == EmergencyAccessRequestStatus::Pending
                 && request.waiting_period_ends_at <= Utc::now()
             {
-                // Auto-approve (in real implementation, would encrypt vault key for contact)
-                db::approve_emergency_access_request(&state.db, request.id, "").await?;
-
-                // Log the auto-approval
-                db::create_emergency_access_log(
-                    &state.db,
-                    contact.user_id,
-                    Some(contact.id),
-                    "access_auto_approved",
-                    Some(serde_json::json!({ "request_id": request.id.to_string() })),
-                    None,
-                )
-                .await?;
-
-                // Notify the vault owner
-                let _ = state.sync_tx.send(SyncNotification {
-                    user_id: contact.user_id,
-                    notification_type: SyncNotificationType::EmergencyAccessApproved,
-                    version: 0,
-                    source_device_id: None,
-                });
+                auto_approve_access_request(&state, contact.user_id, contact.id, request.id)
+                    .await?;
             }
         }
     }
@@ -515,6 +584,109 @@ async fn get_vault_access(
     })))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct GrantedVaultQuery {
+    /// `next_cursor` from the previous page
+    pub cursor: Option<i64>,
+}
+
+/// A page of the owner's encrypted items for a contact holding a granted request
+///
+/// Items stay encrypted under the owner's vault key, which the contact opens
+/// from the grant. Deletions are included so the contact can skip them.
+async fn get_granted_vault_items(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    Path(request_id): Path<Uuid>,
+    Query(query): Query<GrantedVaultQuery>,
+) -> Result<Json<SyncPullResponse>> {
+    let user_id = extract_user_id(&state, &auth_header).await?;
+
+    let not_granted = || AppError::NotFound("Access request not found".to_string());
+    let request = db::get_emergency_access_request_by_id(&state.db, request_id)
+        .await?
+        .filter(|request| request.status == EmergencyAccessRequestStatus::Approved)
+        .ok_or_else(not_granted)?;
+    let contact = db::get_emergency_contact_by_id(&state.db, request.emergency_contact_id)
+        .await?
+        .filter(|contact| {
+            contact.contact_user_id == Some(user_id)
+                && contact.status == EmergencyContactStatus::Accepted
+                && contact.can_view_vault
+        })
+        .ok_or_else(not_granted)?;
+
+    let page = sync::read_page(
+        &state,
+        contact.user_id,
+        query.cursor.unwrap_or(0),
+        sync::MAX_PULL_LIMIT,
+    )
+    .await?;
+
+    // One entry per viewing, not per page
+    if query.cursor.is_none() {
+        db::create_emergency_access_log(
+            &state.db,
+            contact.user_id,
+            Some(contact.id),
+            "vault_viewed",
+            Some(serde_json::json!({ "request_id": request_id.to_string() })),
+            None,
+        )
+        .await?;
+    }
+
+    Ok(Json(page))
+}
+
+/// Grant a request whose waiting period ran out without the owner denying it
+async fn auto_approve_access_request(
+    state: &AppState,
+    owner_id: Uuid,
+    contact_id: Uuid,
+    request_id: Uuid,
+) -> Result<bool> {
+    if !db::approve_due_access_request(&state.db, request_id).await? {
+        return Ok(false);
+    }
+
+    // Log the auto-approval
+    db::create_emergency_access_log(
+        &state.db,
+        owner_id,
+        Some(contact_id),
+        "access_auto_approved",
+        Some(serde_json::json!({ "request_id": request_id.to_string() })),
+        None,
+    )
+    .await?;
+
+    // Notify the vault owner
+    let _ = state.sync_tx.send(SyncNotification {
+        user_id: owner_id,
+        notification_type: SyncNotificationType::EmergencyAccessApproved,
+        version: 0,
+        source_device_id: None,
+    });
+
+    Ok(true)
+}
+
+/// Auto-approve every request past its waiting period
+///
+/// Runs as a background job, so the owner is told when the waiting period
+/// ends rather than whenever the contact next checks for access.
+pub async fn approve_due_access_requests(state: &AppState) -> Result<u64> {
+    let mut approved = 0;
+    for (request_id, contact_id, owner_id) in db::get_due_access_requests(&state.db).await? {
+        if auto_approve_access_request(state, owner_id, contact_id, request_id).await? {
+            approved += 1;
+        }
+    }
+    Ok(approved)
+}
+
 // ============ Logs ============
 
 #[derive(Debug, Serialize)]
@@ -2,18 +2,24 @@ use axum::{routing::get, Router};
 
 use crate::AppState;
 
+pub mod admin;
 pub mod auth;
 pub mod devices;
 pub mod emergency;
 pub mod sync;
+pub mod two_factor;
+pub mod webauthn;
 
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/health", get(health_check))
         .nest("/auth", auth::router())
+        .nest("/auth/2fa", two_factor::router())
+        .nest("/auth/webauthn", webauthn::router())
         .nest("/sync", sync::router())
         .nest("/devices", devices::router())
         .nest("/emergency", emergency::router())
+        .nest("/admin", admin::router())
 }
 
 async fn health_check() -> &'static str {
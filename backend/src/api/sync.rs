@@ -5,18 +5,22 @@ use axum::{
         ws::{Message, WebSocket},
         Query, State, WebSocketUpgrade,
     },
+    http::HeaderMap,
     response::Response,
     routing::{get, post},
     Json, Router,
 };
 use axum_extra::TypedHeader;
 use base64::Engine;
+use chrono::{Duration, Utc};
 use futures_util::{SinkExt, StreamExt};
 use headers::{authorization::Bearer, Authorization};
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
+use super::devices;
 use crate::{
     auth::{jwt::validate_access_token, AuthUser},
     blob::BlobStorage,
@@ -28,6 +32,15 @@ use crate::{
     AppError, AppState, Result,
 };
 
+/// How long an unauthenticated notify socket may wait for its token frame
+pub const WS_AUTH_TIMEOUT_SECS: u64 = 10;
+
+/// How often the server pings notify sockets
+pub const WS_PING_INTERVAL_SECS: u64 = 30;
+
+/// Notify sockets silent for this long (no pongs either) are dropped
+pub const WS_IDLE_TIMEOUT_SECS: u64 = 90;
+
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/pull", get(pull))
@@ -56,41 +69,85 @@ async fn extract_auth(
     Ok(AuthUser { user_id, device_id })
 }
 
+/// Sync stays off until the account's email address is confirmed
+async fn require_verified_email(state: &AppState, user_id: Uuid) -> Result<()> {
+    if !db::is_email_verified(&state.db, user_id).await? {
+        return Err(AppError::EmailNotVerified);
+    }
+    Ok(())
+}
+
+/// Largest page a pull may ask for
+pub const MAX_PULL_LIMIT: i64 = 1000;
+
 #[derive(Debug, Deserialize)]
 pub struct PullQuery {
     pub since_version: Option<i64>,
     pub limit: Option<i64>,
+    /// `next_cursor` from the previous page; takes precedence over `since_version`
+    pub cursor: Option<i64>,
 }
 
+/// Items changed after the client's version, a page at a time
+///
+/// Pages are cut in SQL by version; `next_cursor` is set while more follow.
+/// A version below the user's tombstone prune watermark gets 410.
 async fn pull(
     State(state): State<AppState>,
     auth_header: TypedHeader<Authorization<Bearer>>,
     Query(query): Query<PullQuery>,
 ) -> Result<Json<SyncPullResponse>> {
     let auth_user = extract_auth(&state, auth_header).await?;
+    require_verified_email(&state, auth_user.user_id).await?;
+    devices::require_approved_device(&state, auth_user.device_id).await?;
+    let after_version = query.cursor.or(query.since_version).unwrap_or(0);
+    let limit = query.limit.unwrap_or(100).clamp(1, MAX_PULL_LIMIT);
+
+    let page = read_page(&state, auth_user.user_id, after_version, limit).await?;
+
+    // Checked after the read, so a prune that raced it is still caught:
+    // deletions this client hasn't seen may be gone and it has to start over
+    if after_version > 0
+        && after_version < db::get_pruned_version(&state.db, auth_user.user_id).await?
+    {
+        return Err(AppError::ResyncRequired);
+    }
+
+    // Update device last seen
+    db::update_device_last_seen(&state.db, auth_user.device_id).await?;
+
+    Ok(Json(page))
+}
+
+/// Up to `limit` of the user's items changed after `after_version`, with
+/// their encrypted data
+pub async fn read_page(
+    state: &AppState,
+    user_id: Uuid,
+    after_version: i64,
+    limit: i64,
+) -> Result<SyncPullResponse> {
     let blob_storage = state
         .blob_storage
         .as_ref()
         .ok_or_else(|| AppError::Internal("Blob storage not configured".into()))?;
-    let since_version = query.since_version.unwrap_or(0);
-    let limit = query.limit.unwrap_or(100).min(1000) as usize;
 
     // Get current server version
-    let current_version = db::get_sync_version(&state.db, auth_user.user_id).await?;
-
-    // Get items changed since requested version
-    let items =
-        db::get_vault_items_since_version(&state.db, auth_user.user_id, since_version).await?;
+    let current_version = db::get_sync_version(&state.db, user_id).await?;
+
+    // One row past the page tells whether another page follows
+    let mut items = db::get_vault_items_page(&state.db, user_id, after_version, limit + 1).await?;
+    let has_more = items.len() as i64 > limit;
+    items.truncate(limit as usize);
+    let next_cursor = if has_more {
+        items.last().map(|item| item.version)
+    } else {
+        None
+    };
 
     // Fetch encrypted data for each item
-    let mut sync_items = Vec::new();
-    let mut item_count = 0;
-
+    let mut sync_items = Vec::with_capacity(items.len());
     for item in items {
-        if item_count >= limit {
-            break;
-        }
-
         // Retrieve encrypted blob
         let encrypted_data = match blob_storage.retrieve(&item.encrypted_blob_id).await {
             Ok(data) => base64::engine::general_purpose::STANDARD.encode(&data),
@@ -107,126 +164,232 @@ async fn pull(
             is_deleted: item.is_deleted,
             modified_at: item.modified_at.timestamp(),
         });
-
-        item_count += 1;
     }
 
-    let has_more = item_count >= limit;
-
-    // Update device last seen
-    db::update_device_last_seen(&state.db, auth_user.device_id).await?;
-
-    Ok(Json(SyncPullResponse {
+    Ok(SyncPullResponse {
         current_version,
         items: sync_items,
         has_more,
-    }))
+        next_cursor,
+    })
 }
 
+/// Header a client sets to make a push safe to retry
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// How long a push response is replayed for its idempotency key
+pub const IDEMPOTENCY_KEY_EXPIRY_HOURS: i64 = 24;
+
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// The `Idempotency-Key` header, if present; blank counts as none
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<&str>> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let key = value
+        .to_str()
+        .map_err(|_| AppError::BadRequest("Invalid Idempotency-Key header".to_string()))?
+        .trim();
+    match key {
+        "" => Ok(None),
+        key if key.len() > MAX_IDEMPOTENCY_KEY_LEN => Err(AppError::BadRequest(format!(
+            "Idempotency-Key can be at most {} bytes",
+            MAX_IDEMPOTENCY_KEY_LEN
+        ))),
+        key => Ok(Some(key)),
+    }
+}
+
+/// Hash identifying a push body, so a reused idempotency key can be caught
+fn push_request_hash(req: &SyncPushRequest) -> Result<Vec<u8>> {
+    let body = serde_json::to_vec(req)
+        .map_err(|e| AppError::Internal(format!("Failed to hash push request: {}", e)))?;
+    Ok(Sha256::digest(body).to_vec())
+}
+
+/// Apply a batch of item changes atomically
+///
+/// The whole push runs in one transaction holding the user's sync version
+/// row, so either every accepted item lands or none do. Accepted items get
+/// consecutive versions from a single bump of the sync version.
+///
+/// With an `Idempotency-Key` header the response is stored under the key,
+/// and a retry with the same key gets it back without applying anything.
+/// Reusing a key with a different body is rejected with 422.
 async fn push(
     State(state): State<AppState>,
     auth_header: TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
     Json(req): Json<SyncPushRequest>,
 ) -> Result<Json<SyncPushResponse>> {
     let auth_user = extract_auth(&state, auth_header).await?;
+    require_verified_email(&state, auth_user.user_id).await?;
+    devices::require_approved_device(&state, auth_user.device_id).await?;
     let blob_storage = state
         .blob_storage
         .as_ref()
         .ok_or_else(|| AppError::Internal("Blob storage not configured".into()))?;
-    let current_version = db::get_sync_version(&state.db, auth_user.user_id).await?;
-
-    // Check for version mismatch (client is behind)
-    if req.base_version < current_version {
-        // Get items that changed since client's base version
-        let server_items =
-            db::get_vault_items_since_version(&state.db, auth_user.user_id, req.base_version)
-                .await?;
-
-        // Build map of server items for conflict detection
-        let server_items_map: HashMap<Uuid, _> =
-            server_items.into_iter().map(|i| (i.id, i)).collect();
-
-        let mut conflicts = Vec::new();
-        let mut items_to_update = Vec::new();
+    let idempotency_key = idempotency_key(&headers)?;
+    let request_hash = push_request_hash(&req)?;
 
-        for client_item in &req.items {
-            if let Some(server_item) = server_items_map.get(&client_item.id) {
-                // Conflict detected - use last-write-wins strategy
-                let server_sync_item = SyncItem {
-                    id: server_item.id,
-                    encrypted_data: String::new(), // Not needed for comparison
-                    version: server_item.version,
-                    is_deleted: server_item.is_deleted,
-                    modified_at: server_item.modified_at.timestamp(),
-                };
-
-                let resolution = resolve_conflict(
-                    &server_sync_item,
-                    client_item,
-                    ConflictStrategy::LastWriteWins,
-                );
+    // Reject bad payloads before anything is written
+    let mut decoded = Vec::with_capacity(req.items.len());
+    for item in &req.items {
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(&item.encrypted_data)
+            .map_err(|e| AppError::BadRequest(format!("Invalid base64 data: {}", e)))?;
+        decoded.push((item, data));
+    }
 
-                match resolution {
-                    ConflictResolution::UseClient => {
-                        items_to_update.push(client_item.clone());
-                    }
-                    ConflictResolution::UseServer => {
-                        // Fetch the server's encrypted data for the conflict response
-                        if let Ok(data) =
-                            blob_storage.retrieve(&server_item.encrypted_blob_id).await
-                        {
-                            conflicts.push(SyncItem {
-                                id: server_item.id,
-                                encrypted_data: base64::engine::general_purpose::STANDARD
-                                    .encode(&data),
-                                version: server_item.version,
-                                is_deleted: server_item.is_deleted,
-                                modified_at: server_item.modified_at.timestamp(),
-                            });
-                        }
-                    }
+    // Blobs can't take part in the transaction; those this push created are
+    // removed again if it fails
+    let mut stored_blobs = Vec::new();
+    let result = async {
+        let mut tx = state.db.begin().await?;
+        let current_version = db::lock_sync_version(&mut tx, auth_user.user_id).await?;
+
+        // The version lock serializes pushes, so a retry racing the original
+        // sees its stored response here
+        if let Some(key) = idempotency_key {
+            if let Some((stored, stored_hash)) =
+                db::get_push_response(&mut *tx, auth_user.user_id, key).await?
+            {
+                if stored_hash.is_some_and(|hash| hash != request_hash) {
+                    return Err(AppError::Unprocessable(
+                        "Idempotency-Key was already used for a different request".to_string(),
+                    ));
                 }
-            } else {
-                // No conflict - new item or item not modified on server
-                items_to_update.push(client_item.clone());
+                let response = serde_json::from_value(stored).map_err(|e| {
+                    AppError::Internal(format!("Invalid stored push response: {}", e))
+                })?;
+                return Ok((response, 0));
             }
         }
 
-        // Process items that should be updated
-        let mut new_version = current_version;
-        for item in items_to_update {
-            new_version = process_sync_item(&state, auth_user.user_id, &item).await?;
+        // Client is behind: items changed on the server since its base
+        // version are resolved last-write-wins
+        let server_items: HashMap<Uuid, _> = if req.base_version < current_version {
+            db::get_vault_items_since_version(&mut *tx, auth_user.user_id, req.base_version)
+                .await?
+                .into_iter()
+                .map(|i| (i.id, i))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut accepted = Vec::with_capacity(decoded.len());
+        let mut server_wins = Vec::new();
+        for (client_item, data) in &decoded {
+            let Some(server_item) = server_items.get(&client_item.id) else {
+                accepted.push((*client_item, data));
+                continue;
+            };
+            let server_sync_item = SyncItem {
+                id: server_item.id,
+                encrypted_data: String::new(), // Not needed for comparison
+                version: server_item.version,
+                is_deleted: server_item.is_deleted,
+                modified_at: server_item.modified_at.timestamp(),
+            };
+            match resolve_conflict(
+                &server_sync_item,
+                client_item,
+                ConflictStrategy::LastWriteWins,
+            ) {
+                ConflictResolution::UseClient => accepted.push((*client_item, data)),
+                ConflictResolution::UseServer => server_wins.push(server_item),
+            }
         }
 
-        // Notify other devices
-        if new_version > current_version {
-            let _ = state.sync_tx.send(SyncNotification {
-                user_id: auth_user.user_id,
-                notification_type: SyncNotificationType::ChangesAvailable,
-                version: new_version,
-                source_device_id: Some(auth_user.device_id),
-            });
+        let accepted_count = accepted.len();
+        let new_version = if accepted.is_empty() {
+            current_version
+        } else {
+            db::advance_sync_version(&mut *tx, auth_user.user_id, accepted_count as i64).await?
+        };
+        let first_version = new_version - accepted_count as i64 + 1;
+        for (version, (item, data)) in (first_version..).zip(accepted) {
+            // Unchanged content re-pushed (retries, other devices) reuses its blob
+            let (blob_id, created) = blob_storage
+                .store_deduplicated(auth_user.user_id, data)
+                .await?;
+            if created {
+                stored_blobs.push(blob_id.clone());
+            }
+
+            db::upsert_vault_item(
+                &mut *tx,
+                item.id,
+                auth_user.user_id,
+                version,
+                &blob_id,
+                item.is_deleted,
+            )
+            .await?
+            .ok_or_else(|| {
+                AppError::Conflict(format!("Item {} belongs to another account", item.id))
+            })?;
         }
 
-        return Ok(Json(SyncPushResponse {
+        // Send back the server's data for items the client lost
+        let mut conflicts = Vec::with_capacity(server_wins.len());
+        for server_item in server_wins {
+            if let Ok(data) = blob_storage.retrieve(&server_item.encrypted_blob_id).await {
+                conflicts.push(SyncItem {
+                    id: server_item.id,
+                    encrypted_data: base64::engine::general_purpose::STANDARD.encode(&data),
+                    version: server_item.version,
+                    is_deleted: server_item.is_deleted,
+                    modified_at: server_item.modified_at.timestamp(),
+                });
+            }
+        }
+        let response = SyncPushResponse {
             new_version,
             had_conflicts: !conflicts.is_empty(),
             conflicts,
-        }));
-    }
+        };
 
-    // No version conflict - process all items
-    let mut new_version = current_version;
-    for item in &req.items {
-        new_version = process_sync_item(&state, auth_user.user_id, item).await?;
+        if let Some(key) = idempotency_key {
+            let stored = serde_json::to_value(&response)
+                .map_err(|e| AppError::Internal(format!("Failed to store push response: {}", e)))?;
+            let expires_at = Utc::now() + Duration::hours(IDEMPOTENCY_KEY_EXPIRY_HOURS);
+            db::store_push_response(
+                &mut tx,
+                auth_user.user_id,
+                key,
+                &request_hash,
+                &stored,
+                expires_at,
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok::<_, AppError>((response, accepted_count))
     }
+    .await;
+
+    let (response, accepted_count) = match result {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            for blob_id in &stored_blobs {
+                if blob_storage.delete(blob_id).await.is_err() {
+                    let _ = db::record_superseded_blob(&state.db, blob_id).await;
+                }
+            }
+            return Err(e);
+        }
+    };
 
     // Notify other devices
-    if new_version > current_version {
+    if accepted_count > 0 {
         let _ = state.sync_tx.send(SyncNotification {
             user_id: auth_user.user_id,
             notification_type: SyncNotificationType::ChangesAvailable,
-            version: new_version,
+            version: response.new_version,
             source_device_id: Some(auth_user.device_id),
         });
     }
@@ -234,95 +397,177 @@ async fn push(
     // Update device last seen
     db::update_device_last_seen(&state.db, auth_user.device_id).await?;
 
-    Ok(Json(SyncPushResponse {
-        new_version,
-        had_conflicts: false,
-        conflicts: Vec::new(),
-    }))
+    Ok(Json(response))
 }
 
-async fn process_sync_item(state: &AppState, user_id: Uuid, item: &SyncItem) -> Result<i64> {
-    let blob_storage = state
-        .blob_storage
-        .as_ref()
-        .ok_or_else(|| AppError::Internal("Blob storage not configured".into()))?;
-
-    // Decode and store encrypted blob
-    let encrypted_data = base64::engine::general_purpose::STANDARD
-        .decode(&item.encrypted_data)
-        .map_err(|e| AppError::BadRequest(format!("Invalid base64 data: {}", e)))?;
-
-    let blob_id = BlobStorage::generate_blob_id(user_id);
-    blob_storage.store(&blob_id, &encrypted_data).await?;
-
-    // Increment version
-    let new_version = db::increment_sync_version(&state.db, user_id).await?;
-
-    // Upsert vault item record
-    db::upsert_vault_item(
-        &state.db,
-        item.id,
-        user_id,
-        new_version,
-        &blob_id,
-        item.is_deleted,
-    )
-    .await?;
+/// Deletion markers are kept this long so offline devices still learn of
+/// the deletion; a device away for longer gets 410 and pulls from scratch
+pub const TOMBSTONE_RETENTION_DAYS: i64 = 90;
+
+/// Drop deleted items older than the retention period
+///
+/// Their blobs go to blob GC rather than being deleted here, since deletions
+/// with the same content share one blob.
+pub async fn prune_tombstones(state: &AppState) -> Result<u64> {
+    let cutoff = Utc::now() - Duration::days(TOMBSTONE_RETENTION_DAYS);
+    let blob_ids = db::delete_tombstones_before(&state.db, cutoff).await?;
+    for blob_id in &blob_ids {
+        db::record_superseded_blob(&state.db, blob_id).await?;
+    }
 
-    Ok(new_version)
+    Ok(blob_ids.len() as u64)
 }
 
-async fn notify_ws(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
-    ws.on_upgrade(|socket| handle_notify_ws(socket, state))
-}
+/// Superseded blobs are kept this long, so a pull that read the old row can
+/// still fetch its blob
+pub const BLOB_RETENTION_HOURS: i64 = 24;
 
-async fn handle_notify_ws(socket: WebSocket, state: AppState) {
-    let (mut sender, mut receiver) = socket.split();
+/// Blobs deleted per round trip to the database
+const BLOB_GC_BATCH_SIZE: i64 = 500;
 
-    // Wait for authentication message
-    let auth_user = match receiver.next().await {
-        Some(Ok(Message::Text(text))) => {
-            // Expect: {"token": "..."}
-            #[derive(Deserialize)]
-            struct AuthMessage {
-                token: String,
-            }
+/// Delete superseded blobs past the retention window that nothing references
+///
+/// Each blob is checked and deleted under its owner's sync version lock, so
+/// a push can't start referencing it again in between.
+pub async fn collect_superseded_blobs(state: &AppState) -> Result<u64> {
+    let Some(blob_storage) = &state.blob_storage else {
+        return Ok(0);
+    };
+    let cutoff = Utc::now() - Duration::hours(BLOB_RETENTION_HOURS);
 
-            match serde_json::from_str::<AuthMessage>(&text) {
-                Ok(auth_msg) => match validate_access_token(&auth_msg.token, &state.jwt_secret) {
-                    Ok(claims) => {
-                        let user_id = match claims.sub.parse::<Uuid>() {
-                            Ok(id) => id,
-                            Err(_) => {
-                                let _ = sender.send(Message::Close(None)).await;
-                                return;
-                            }
-                        };
-                        let device_id = match claims.device_id.parse::<Uuid>() {
-                            Ok(id) => id,
-                            Err(_) => {
-                                let _ = sender.send(Message::Close(None)).await;
-                                return;
-                            }
-                        };
-                        AuthUser { user_id, device_id }
-                    }
-                    Err(_) => {
-                        let _ = sender.send(Message::Close(None)).await;
-                        return;
-                    }
-                },
-                Err(_) => {
-                    let _ = sender.send(Message::Close(None)).await;
-                    return;
+    let mut collected = 0;
+    loop {
+        let batch = db::get_collectable_blobs(&state.db, cutoff, BLOB_GC_BATCH_SIZE).await?;
+        let mut failed = false;
+        for blob_id in &batch {
+            match collect_blob(state, blob_storage, blob_id).await {
+                Ok(true) => collected += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!("Failed to collect blob {}: {}", blob_id, e);
+                    failed = true;
                 }
             }
         }
-        _ => {
-            return;
+
+        // Stop at the last page, or when deletes fail so they aren't retried
+        // in a tight loop; the next run picks them up
+        if (batch.len() as i64) < BLOB_GC_BATCH_SIZE || failed {
+            return Ok(collected);
         }
+    }
+}
+
+/// Delete one superseded blob unless an item references it again
+async fn collect_blob(state: &AppState, blob_storage: &BlobStorage, blob_id: &str) -> Result<bool> {
+    let owner = blob_id
+        .split_once('/')
+        .and_then(|(user_id, _)| user_id.parse::<Uuid>().ok());
+
+    let mut tx = state.db.begin().await?;
+    if let Some(user_id) = owner {
+        db::lock_sync_version(&mut tx, user_id).await?;
+    }
+    let referenced = db::is_blob_referenced(&mut *tx, blob_id).await?;
+    if !referenced {
+        blob_storage.delete(blob_id).await?;
+    }
+    db::delete_superseded_blobs(&mut *tx, &[blob_id.to_string()]).await?;
+    tx.commit().await?;
+
+    Ok(!referenced)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotifyQuery {
+    /// Access token, for clients that can't set headers on the upgrade request
+    pub token: Option<String>,
+}
+
+/// Validate an access token for the notify socket
+///
+/// The device must still exist, so a removed device can't keep listening
+/// with a token that hasn't expired yet.
+async fn authenticate_socket(state: &AppState, token: &str) -> Result<AuthUser> {
+    let claims = validate_access_token(token, &state.jwt_secret)?;
+    let user_id = claims
+        .sub
+        .parse::<Uuid>()
+        .map_err(|_| AppError::InvalidToken)?;
+    let device_id = claims
+        .device_id
+        .parse::<Uuid>()
+        .map_err(|_| AppError::InvalidToken)?;
+
+    let auth_user = AuthUser { user_id, device_id };
+    if !device_exists(state, &auth_user).await? {
+        return Err(AppError::DeviceNotFound);
+    }
+    Ok(auth_user)
+}
+
+async fn device_exists(state: &AppState, auth_user: &AuthUser) -> Result<bool> {
+    Ok(db::get_device_by_id(&state.db, auth_user.device_id)
+        .await?
+        .is_some_and(|device| device.user_id == auth_user.user_id))
+}
+
+/// Real-time sync notifications
+///
+/// Authenticates at upgrade time from the `Authorization` header or the
+/// `token` query parameter. Without either, the first text frame must be
+/// `{"token": "..."}` within `WS_AUTH_TIMEOUT_SECS`.
+async fn notify_ws(
+    State(state): State<AppState>,
+    auth_header: Option<TypedHeader<Authorization<Bearer>>>,
+    Query(query): Query<NotifyQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    let token = auth_header
+        .map(|header| header.token().to_string())
+        .or(query.token);
+    let auth_user = match token {
+        Some(token) => Some(authenticate_socket(&state, &token).await?),
+        None => None,
     };
 
+    Ok(ws.on_upgrade(move |socket| handle_notify_ws(socket, state, auth_user)))
+}
+
+/// Wait for the `{"token": "..."}` frame from clients that didn't authenticate
+/// at upgrade time
+async fn authenticate_first_frame(state: &AppState, socket: &mut WebSocket) -> Option<AuthUser> {
+    #[derive(Deserialize)]
+    struct AuthMessage {
+        token: String,
+    }
+
+    let first = tokio::time::timeout(
+        std::time::Duration::from_secs(WS_AUTH_TIMEOUT_SECS),
+        socket.recv(),
+    )
+    .await;
+    let Ok(Some(Ok(Message::Text(text)))) = first else {
+        return None;
+    };
+    let auth_msg = serde_json::from_str::<AuthMessage>(&text).ok()?;
+    authenticate_socket(state, &auth_msg.token).await.ok()
+}
+
+async fn handle_notify_ws(mut socket: WebSocket, state: AppState, auth_user: Option<AuthUser>) {
+    let auth_user = match auth_user {
+        Some(auth_user) => auth_user,
+        None => match authenticate_first_frame(&state, &mut socket).await {
+            Some(auth_user) => auth_user,
+            None => {
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+        },
+    };
+
+    let (mut sender, mut receiver) = socket.split();
+
     // Subscribe to sync notifications
     let mut rx = state.sync_tx.subscribe();
 
@@ -333,35 +578,62 @@ async fn handle_notify_ws(socket: WebSocket, state: AppState) {
         ))
         .await;
 
+    let idle_timeout = std::time::Duration::from_secs(WS_IDLE_TIMEOUT_SECS);
+    let mut ping_interval =
+        tokio::time::interval(std::time::Duration::from_secs(WS_PING_INTERVAL_SECS));
+    ping_interval.tick().await;
+    let mut last_activity = tokio::time::Instant::now();
+
     // Listen for notifications and forward to client
     loop {
         tokio::select! {
             // Handle incoming messages (ping/pong, close)
             msg = receiver.next() => {
+                last_activity = tokio::time::Instant::now();
                 match msg {
                     Some(Ok(Message::Ping(data))) => {
-                        if sender.send(Message::Pong(data)).await.is_err() {
+                        let pong = sender.send(Message::Pong(data)).await;
+                        if pong.is_err() {
                             break;
                         }
                     }
-                    Some(Ok(Message::Close(_))) | None => {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
                         break;
                     }
                     _ => {}
                 }
             }
+            // Keep the connection alive, dropping clients that stopped
+            // answering and devices that were removed meanwhile
+            _ = ping_interval.tick() => {
+                if last_activity.elapsed() >= idle_timeout {
+                    break;
+                }
+                if !matches!(device_exists(&state, &auth_user).await, Ok(true)) {
+                    break;
+                }
+                if sender.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
             // Forward sync notifications
             notification = rx.recv() => {
                 match notification {
                     Ok(notif) => {
                         // Only forward notifications for this user
-                        if notif.user_id == auth_user.user_id {
-                            // Don't notify the device that made the change
-                            if notif.source_device_id != Some(auth_user.device_id) {
-                                let msg = serde_json::to_string(&notif).unwrap_or_default();
-                                if sender.send(Message::Text(msg)).await.is_err() {
-                                    break;
-                                }
+                        if notif.user_id != auth_user.user_id {
+                            continue;
+                        }
+                        if matches!(notif.notification_type, SyncNotificationType::DeviceRemoved)
+                            && !matches!(device_exists(&state, &auth_user).await, Ok(true))
+                        {
+                            break;
+                        }
+                        // Don't notify the device that made the change
+                        if notif.source_device_id != Some(auth_user.device_id) {
+                            let msg = serde_json::to_string(&notif).unwrap_or_default();
+                            if sender.send(Message::Text(msg)).await.is_err() {
+                                break;
                             }
                         }
                     }
@@ -376,4 +648,6 @@ async fn handle_notify_ws(socket: WebSocket, state: AppState) {
             }
         }
     }
+
+    let _ = sender.send(Message::Close(None)).await;
 }
@@ -0,0 +1,279 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    routing::{get, post},
+    Json, Router,
+};
+use axum_extra::TypedHeader;
+use chrono::Utc;
+use headers::{authorization::Bearer, Authorization};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::auth::verify_auth_key;
+use crate::{
+    auth::{
+        jwt::{hash_token, validate_access_token},
+        totp, AuthUser,
+    },
+    db::{self, User},
+    AppError, AppState, Result,
+};
+
+/// Header carrying the second factor on sensitive authenticated requests
+pub const TWO_FACTOR_HEADER: &str = "x-two-factor-code";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(status))
+        .route("/enroll", post(enroll))
+        .route("/confirm", post(confirm))
+        .route("/disable", post(disable))
+        .route("/recovery-codes", post(regenerate_recovery_codes))
+}
+
+/// Extract and validate auth from Authorization header
+async fn extract_auth(
+    state: &AppState,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+) -> Result<AuthUser> {
+    let token = auth_header.token();
+    let claims = validate_access_token(token, &state.jwt_secret)?;
+
+    let user_id = claims
+        .sub
+        .parse::<Uuid>()
+        .map_err(|_| AppError::InvalidToken)?;
+
+    let device_id = claims
+        .device_id
+        .parse::<Uuid>()
+        .map_err(|_| AppError::InvalidToken)?;
+
+    Ok(AuthUser { user_id, device_id })
+}
+
+/// The code from the `X-Two-Factor-Code` header, if present
+pub fn header_code(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(TWO_FACTOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Check the second factor of a user with two-factor auth enabled
+///
+/// Accepts a TOTP code or an unused recovery code, which is then spent.
+/// Users without two-factor auth pass without a code.
+pub async fn verify_second_factor(state: &AppState, user: &User, code: Option<&str>) -> Result<()> {
+    let Some(encrypted) = user
+        .totp_secret_encrypted
+        .as_deref()
+        .filter(|_| user.totp_enabled_at.is_some())
+    else {
+        return Ok(());
+    };
+    let code = code
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .ok_or(AppError::TwoFactorRequired)?;
+
+    let secret = totp::decrypt_secret(encrypted, &state.jwt_secret)?;
+    if let Some(step) =
+        totp::verify_code(&secret, code, Utc::now().timestamp(), user.totp_last_step)
+    {
+        // A concurrent request may have used the same code first
+        if db::record_totp_step(&state.db, user.id, step).await? {
+            return Ok(());
+        }
+        return Err(AppError::InvalidTwoFactorCode);
+    }
+
+    let code_hash = hash_token(&totp::normalize_recovery_code(code));
+    if db::use_recovery_code(&state.db, user.id, &code_hash).await? {
+        tracing::info!("User {} used a two-factor recovery code", user.id);
+        return Ok(());
+    }
+
+    Err(AppError::InvalidTwoFactorCode)
+}
+
+/// Load the user and check the second factor sent in the request headers
+pub async fn require_second_factor(
+    state: &AppState,
+    user_id: Uuid,
+    headers: &HeaderMap,
+) -> Result<()> {
+    let user = db::get_user_by_id(&state.db, user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+    verify_second_factor(state, &user, header_code(headers)).await
+}
+
+/// Generate fresh recovery codes, replacing any unused ones
+async fn issue_recovery_codes(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<Vec<String>> {
+    let codes = totp::generate_recovery_codes();
+    let hashes: Vec<String> = codes
+        .iter()
+        .map(|code| hash_token(&totp::normalize_recovery_code(code)))
+        .collect();
+    db::replace_recovery_codes(conn, user_id, &hashes).await?;
+    Ok(codes)
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+async fn status(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<TwoFactorStatus>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    Ok(Json(TwoFactorStatus {
+        enabled: user.totp_enabled_at.is_some(),
+        recovery_codes_remaining: db::count_unused_recovery_codes(&state.db, user.id).await?,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnrollRequest {
+    pub auth_key: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnrollResponse {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// `otpauth://` URI for a QR code
+    pub otpauth_uri: String,
+}
+
+/// Start enrollment with a new secret; it takes effect once confirmed
+async fn enroll(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    Json(req): Json<EnrollRequest>,
+) -> Result<Json<EnrollResponse>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    verify_auth_key(&state, &user, &req.auth_key).await?;
+    if user.totp_enabled_at.is_some() {
+        return Err(AppError::Conflict(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let secret = totp::generate_secret();
+    let encrypted = totp::encrypt_secret(&secret, &state.jwt_secret)?;
+    db::set_pending_totp_secret(&state.db, user.id, &encrypted).await?;
+
+    Ok(Json(EnrollResponse {
+        secret: crypto_core::totp::encode_secret(&secret),
+        otpauth_uri: totp::provisioning_uri(&secret, &user.email),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    /// Shown once; only hashes are stored
+    pub recovery_codes: Vec<String>,
+}
+
+/// Enable two-factor auth with a first code from the authenticator
+async fn confirm(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    Json(req): Json<ConfirmRequest>,
+) -> Result<Json<RecoveryCodesResponse>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    let encrypted = match (&user.totp_secret_encrypted, user.totp_enabled_at) {
+        (Some(encrypted), None) => encrypted,
+        _ => {
+            return Err(AppError::BadRequest(
+                "No pending two-factor enrollment".to_string(),
+            ))
+        }
+    };
+    let secret = totp::decrypt_secret(encrypted, &state.jwt_secret)?;
+    let step = totp::verify_code(&secret, &req.code, Utc::now().timestamp(), None)
+        .ok_or(AppError::InvalidTwoFactorCode)?;
+
+    let mut tx = state.db.begin().await?;
+    db::enable_totp(&mut *tx, user.id, step).await?;
+    let recovery_codes = issue_recovery_codes(&mut tx, user.id).await?;
+    tx.commit().await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisableRequest {
+    pub auth_key: String,
+}
+
+/// Turn two-factor auth off; needs the auth_key and a current code
+async fn disable(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
+    Json(req): Json<DisableRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    verify_auth_key(&state, &user, &req.auth_key).await?;
+    if user.totp_enabled_at.is_none() {
+        return Err(AppError::BadRequest(
+            "Two-factor authentication is not enabled".to_string(),
+        ));
+    }
+    verify_second_factor(&state, &user, header_code(&headers)).await?;
+
+    db::disable_totp(&state.db, user.id).await?;
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+/// Replace the recovery codes; needs a current code
+async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
+) -> Result<Json<RecoveryCodesResponse>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+
+    if user.totp_enabled_at.is_none() {
+        return Err(AppError::BadRequest(
+            "Two-factor authentication is not enabled".to_string(),
+        ));
+    }
+    verify_second_factor(&state, &user, header_code(&headers)).await?;
+
+    let mut conn = state.db.acquire().await?;
+    let recovery_codes = issue_recovery_codes(&mut conn, user.id).await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
@@ -0,0 +1,345 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use axum_extra::TypedHeader;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use headers::{authorization::Bearer, Authorization};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, CredentialID, Passkey, PasskeyAuthentication, PasskeyRegistration,
+    PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse, Url, Webauthn,
+    WebauthnBuilder,
+};
+
+use super::auth::{client_device_id, complete_login, verify_auth_key, LoginResponse};
+use super::two_factor;
+use crate::{
+    auth::{jwt::validate_access_token, AuthUser},
+    db::{self, User, WebauthnCredential},
+    AppError, AppState, Result,
+};
+
+/// How long a started ceremony can be finished
+const CHALLENGE_EXPIRY_MINUTES: i64 = 5;
+
+const REGISTRATION: &str = "registration";
+const AUTHENTICATION: &str = "authentication";
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_credentials))
+        .route("/register/start", post(start_registration))
+        .route("/register/finish", post(finish_registration))
+        .route("/login", post(finish_login))
+        .route("/:credential_id", delete(delete_credential))
+}
+
+/// Build the relying party for `rp_id` (a domain) served from `origin`
+pub fn build_webauthn(rp_id: &str, origin: &str) -> Result<Webauthn> {
+    let origin = Url::parse(origin)
+        .map_err(|e| AppError::Internal(format!("Invalid WebAuthn origin: {}", e)))?;
+    WebauthnBuilder::new(rp_id, &origin)
+        .and_then(|builder| builder.rp_name("Keydrop").build())
+        .map_err(|e| AppError::Internal(format!("Invalid WebAuthn configuration: {}", e)))
+}
+
+/// Extract and validate auth from Authorization header
+async fn extract_auth(
+    state: &AppState,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+) -> Result<AuthUser> {
+    let token = auth_header.token();
+    let claims = validate_access_token(token, &state.jwt_secret)?;
+
+    let user_id = claims
+        .sub
+        .parse::<Uuid>()
+        .map_err(|_| AppError::InvalidToken)?;
+
+    let device_id = claims
+        .device_id
+        .parse::<Uuid>()
+        .map_err(|_| AppError::InvalidToken)?;
+
+    Ok(AuthUser { user_id, device_id })
+}
+
+fn webauthn(state: &AppState) -> Result<&Webauthn> {
+    state
+        .webauthn
+        .as_deref()
+        .ok_or_else(|| AppError::Internal("WebAuthn not configured".into()))
+}
+
+fn encode_credential_id(id: &CredentialID) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(id.as_ref())
+}
+
+fn parse_passkey(credential: &WebauthnCredential) -> Result<Passkey> {
+    serde_json::from_value(credential.passkey.clone())
+        .map_err(|e| AppError::Internal(format!("Invalid stored passkey: {}", e)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value> {
+    serde_json::to_value(value).map_err(|e| AppError::Internal(e.to_string()))
+}
+
+async fn take_challenge<T: serde::de::DeserializeOwned>(
+    state: &AppState,
+    challenge_id: Uuid,
+    kind: &str,
+) -> Result<(Uuid, T)> {
+    let (user_id, stored) = db::consume_webauthn_challenge(&state.db, challenge_id, kind)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Unknown or expired challenge".to_string()))?;
+    let ceremony = serde_json::from_value(stored)
+        .map_err(|e| AppError::Internal(format!("Invalid stored challenge: {}", e)))?;
+    Ok((user_id, ceremony))
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub device_id: Uuid,
+    pub last_used_at: Option<i64>,
+    pub created_at: i64,
+}
+
+impl From<WebauthnCredential> for CredentialResponse {
+    fn from(credential: WebauthnCredential) -> Self {
+        Self {
+            id: credential.id,
+            name: credential.name,
+            device_id: credential.device_id,
+            last_used_at: credential.last_used_at.map(|t| t.timestamp()),
+            created_at: credential.created_at.timestamp(),
+        }
+    }
+}
+
+async fn list_credentials(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<Vec<CredentialResponse>>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let credentials = db::get_webauthn_credentials_by_user(&state.db, auth_user.user_id).await?;
+
+    Ok(Json(credentials.into_iter().map(Into::into).collect()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartRegistrationRequest {
+    pub auth_key: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StartRegistrationResponse {
+    pub challenge_id: Uuid,
+    /// Pass to `navigator.credentials.create()`
+    pub options: CreationChallengeResponse,
+}
+
+async fn start_registration(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    Json(req): Json<StartRegistrationRequest>,
+) -> Result<Json<StartRegistrationResponse>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+    verify_auth_key(&state, &user, &req.auth_key).await?;
+
+    // Don't register the same authenticator twice
+    let existing = db::get_webauthn_credentials_by_user(&state.db, user.id)
+        .await?
+        .iter()
+        .map(|credential| parse_passkey(credential).map(|passkey| passkey.cred_id().clone()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let (options, registration) = webauthn(&state)?
+        .start_passkey_registration(user.id, &user.email, &user.email, Some(existing))
+        .map_err(|e| AppError::Internal(format!("Failed to start registration: {}", e)))?;
+
+    let expires_at = Utc::now() + Duration::minutes(CHALLENGE_EXPIRY_MINUTES);
+    let challenge_id = db::create_webauthn_challenge(
+        &state.db,
+        user.id,
+        REGISTRATION,
+        &to_json(&registration)?,
+        expires_at,
+    )
+    .await?;
+
+    Ok(Json(StartRegistrationResponse {
+        challenge_id,
+        options,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FinishRegistrationRequest {
+    pub challenge_id: Uuid,
+    pub name: String,
+    pub credential: RegisterPublicKeyCredential,
+}
+
+async fn finish_registration(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    Json(req): Json<FinishRegistrationRequest>,
+) -> Result<Json<CredentialResponse>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let (user_id, registration) =
+        take_challenge::<PasskeyRegistration>(&state, req.challenge_id, REGISTRATION).await?;
+    if user_id != auth_user.user_id {
+        return Err(AppError::BadRequest(
+            "Unknown or expired challenge".to_string(),
+        ));
+    }
+
+    let passkey = webauthn(&state)?
+        .finish_passkey_registration(&req.credential, &registration)
+        .map_err(|e| AppError::BadRequest(format!("Passkey registration failed: {}", e)))?;
+
+    let credential = db::create_webauthn_credential(
+        &state.db,
+        auth_user.user_id,
+        auth_user.device_id,
+        &req.name,
+        &encode_credential_id(passkey.cred_id()),
+        &to_json(&passkey)?,
+    )
+    .await?;
+
+    Ok(Json(credential.into()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteCredentialRequest {
+    pub auth_key: String,
+}
+
+/// Remove a credential; needs the auth_key, plus a TOTP code if enabled
+async fn delete_credential(
+    State(state): State<AppState>,
+    auth_header: TypedHeader<Authorization<Bearer>>,
+    headers: HeaderMap,
+    Path(credential_id): Path<Uuid>,
+    Json(req): Json<DeleteCredentialRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let auth_user = extract_auth(&state, auth_header).await?;
+    let user = db::get_user_by_id(&state.db, auth_user.user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+    verify_auth_key(&state, &user, &req.auth_key).await?;
+    two_factor::verify_second_factor(&state, &user, two_factor::header_code(&headers)).await?;
+
+    if !db::delete_webauthn_credential(&state.db, credential_id, user.id).await? {
+        return Err(AppError::NotFound("Credential not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+/// Login step returned instead of tokens when the user has passkeys
+#[derive(Debug, Serialize)]
+pub struct LoginChallenge {
+    pub webauthn_required: bool,
+    pub challenge_id: Uuid,
+    /// Pass to `navigator.credentials.get()`
+    pub options: RequestChallengeResponse,
+}
+
+/// Start a passkey assertion for a user whose auth_key already checked out
+pub async fn start_login_challenge(
+    state: &AppState,
+    user: &User,
+    credentials: &[WebauthnCredential],
+) -> Result<LoginChallenge> {
+    let passkeys = credentials
+        .iter()
+        .map(parse_passkey)
+        .collect::<Result<Vec<_>>>()?;
+
+    let (options, authentication) = webauthn(state)?
+        .start_passkey_authentication(&passkeys)
+        .map_err(|e| AppError::Internal(format!("Failed to start authentication: {}", e)))?;
+
+    let expires_at = Utc::now() + Duration::minutes(CHALLENGE_EXPIRY_MINUTES);
+    let challenge_id = db::create_webauthn_challenge(
+        &state.db,
+        user.id,
+        AUTHENTICATION,
+        &to_json(&authentication)?,
+        expires_at,
+    )
+    .await?;
+
+    Ok(LoginChallenge {
+        webauthn_required: true,
+        challenge_id,
+        options,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FinishLoginRequest {
+    pub challenge_id: Uuid,
+    pub credential: PublicKeyCredential,
+    pub device_name: String,
+    pub device_type: String,
+    #[serde(default)]
+    pub client_device_id: Option<String>,
+    #[serde(default)]
+    pub device_secret: Option<String>,
+}
+
+/// Answer the login challenge with a passkey assertion
+async fn finish_login(
+    State(state): State<AppState>,
+    Json(req): Json<FinishLoginRequest>,
+) -> Result<Json<LoginResponse>> {
+    let client_device_id = client_device_id(req.client_device_id.as_deref())?;
+    let (user_id, authentication) =
+        take_challenge::<PasskeyAuthentication>(&state, req.challenge_id, AUTHENTICATION).await?;
+
+    let result = webauthn(&state)?
+        .finish_passkey_authentication(&req.credential, &authentication)
+        .map_err(|_| AppError::InvalidTwoFactorCode)?;
+
+    // Keep the signature counter current so cloned authenticators show up
+    let used_id = encode_credential_id(result.cred_id());
+    let credential = db::get_webauthn_credentials_by_user(&state.db, user_id)
+        .await?
+        .into_iter()
+        .find(|credential| credential.credential_id == used_id)
+        .ok_or(AppError::InvalidTwoFactorCode)?;
+    let mut passkey = parse_passkey(&credential)?;
+    let updated = match passkey.update_credential(&result) {
+        Some(true) => Some(to_json(&passkey)?),
+        _ => None,
+    };
+    db::update_webauthn_credential_usage(&state.db, credential.id, updated.as_ref()).await?;
+
+    let user = db::get_user_by_id(&state.db, user_id)
+        .await?
+        .ok_or(AppError::UserNotFound)?;
+    let response = complete_login(
+        &state,
+        user,
+        &req.device_name,
+        req.device_type,
+        client_device_id,
+        req.device_secret.as_deref(),
+    )
+    .await?;
+
+    Ok(Json(response))
+}
@@ -23,6 +23,9 @@ pub struct Claims {
     pub iat: i64,
     /// Token type
     pub token_type: TokenType,
+    /// Unique token ID, so tokens issued in the same second still differ
+    #[serde(default)]
+    pub jti: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -49,6 +52,7 @@ pub fn generate_access_token(user_id: Uuid, device_id: Uuid, secret: &str) -> Re
         exp: exp.timestamp(),
         iat: now.timestamp(),
         token_type: TokenType::Access,
+        jti: Uuid::new_v4().to_string(),
     };
 
     let token = encode(
@@ -72,6 +76,7 @@ pub fn generate_refresh_token(user_id: Uuid, device_id: Uuid, secret: &str) -> R
         exp: exp.timestamp(),
         iat: now.timestamp(),
         token_type: TokenType::Refresh,
+        jti: Uuid::new_v4().to_string(),
     };
 
     let token = encode(
@@ -135,6 +140,11 @@ pub fn validate_refresh_token(token: &str, secret: &str) -> Result<Claims> {
 
 /// Hash a refresh token for storage
 pub fn hash_refresh_token(token: &str) -> String {
+    hash_token(token)
+}
+
+/// Hash an opaque bearer token (refresh, email verification) for storage
+pub fn hash_token(token: &str) -> String {
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
     hasher.update(token.as_bytes());
@@ -198,5 +208,9 @@ mod tests {
         let claims = validate_refresh_token(&tokens.refresh_token, secret).unwrap();
         assert_eq!(claims.sub, user_id.to_string());
         assert_eq!(claims.device_id, device_id.to_string());
+
+        // Tokens issued in the same second are still distinct
+        let again = generate_token_pair(user_id, device_id, secret).unwrap();
+        assert_ne!(again.refresh_token, tokens.refresh_token);
     }
 }
@@ -0,0 +1,59 @@
+//! Throttling of auth_key guesses
+//!
+//! Argon2 makes each guess expensive, but not enough against a distributed
+//! attack on one account. Wrong auth_keys are counted per account: a few are
+//! free, then each one blocks further attempts for twice as long as the last,
+//! and at `MAX_FAILED_ATTEMPTS` the account is locked for `LOCKOUT_MINUTES`.
+
+use chrono::Duration;
+
+/// Failures allowed before any delay applies
+pub const FREE_ATTEMPTS: i32 = 3;
+
+/// Failures after which the account is locked outright
+pub const MAX_FAILED_ATTEMPTS: i32 = 10;
+
+/// How long a full lockout lasts
+pub const LOCKOUT_MINUTES: i64 = 15;
+
+/// How long to refuse auth_key checks after `failed_attempts` consecutive failures
+pub fn lockout_duration(failed_attempts: i32) -> Option<Duration> {
+    if failed_attempts >= MAX_FAILED_ATTEMPTS {
+        Some(Duration::minutes(LOCKOUT_MINUTES))
+    } else if failed_attempts > FREE_ATTEMPTS {
+        Some(Duration::seconds(1 << (failed_attempts - FREE_ATTEMPTS)))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockout_duration() {
+        assert_eq!(lockout_duration(0), None);
+        assert_eq!(lockout_duration(FREE_ATTEMPTS), None);
+        assert_eq!(
+            lockout_duration(FREE_ATTEMPTS + 1),
+            Some(Duration::seconds(2))
+        );
+        assert_eq!(
+            lockout_duration(FREE_ATTEMPTS + 2),
+            Some(Duration::seconds(4))
+        );
+        assert_eq!(
+            lockout_duration(MAX_FAILED_ATTEMPTS - 1),
+            Some(Duration::seconds(64))
+        );
+        assert_eq!(
+            lockout_duration(MAX_FAILED_ATTEMPTS),
+            Some(Duration::minutes(LOCKOUT_MINUTES))
+        );
+        assert_eq!(
+            lockout_duration(MAX_FAILED_ATTEMPTS + 5),
+            Some(Duration::minutes(LOCKOUT_MINUTES))
+        );
+    }
+}
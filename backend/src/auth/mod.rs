@@ -1,5 +1,7 @@
 pub mod jwt;
+pub mod lockout;
 pub mod middleware;
+pub mod totp;
 
 pub use jwt::*;
 pub use middleware::*;
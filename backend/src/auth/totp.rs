@@ -0,0 +1,149 @@
+//! TOTP two-factor authentication helpers
+//!
+//! Secrets are stored encrypted with a key derived from the JWT secret, so
+//! rotating `JWT_SECRET` also invalidates every enrolled authenticator.
+
+use crypto_core::totp::{encode_secret, hotp, TotpAlgorithm};
+use crypto_core::{cipher, EncryptedBlob};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{AppError, Result};
+
+/// Time step in seconds
+pub const TOTP_PERIOD: i64 = 30;
+
+/// Number of one-time recovery codes issued on enrollment
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const TOTP_DIGITS: u32 = 6;
+const SECRET_LEN: usize = 20;
+const ISSUER: &str = "Keydrop";
+
+fn encryption_key(jwt_secret: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"keydrop-totp-secret:");
+    hasher.update(jwt_secret.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Generate a random TOTP secret
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_LEN];
+    rand::thread_rng().fill(&mut secret[..]);
+    secret
+}
+
+/// Encrypt a TOTP secret for storage
+pub fn encrypt_secret(secret: &[u8], jwt_secret: &str) -> Result<String> {
+    let blob = cipher::encrypt(secret, &encryption_key(jwt_secret))
+        .map_err(|e| AppError::Internal(format!("Failed to encrypt TOTP secret: {}", e)))?;
+    Ok(blob.to_base64())
+}
+
+/// Decrypt a stored TOTP secret
+pub fn decrypt_secret(encrypted: &str, jwt_secret: &str) -> Result<Vec<u8>> {
+    let blob = EncryptedBlob::from_base64(encrypted)
+        .map_err(|e| AppError::Internal(format!("Invalid stored TOTP secret: {}", e)))?;
+    cipher::decrypt(&blob, &encryption_key(jwt_secret))
+        .map_err(|e| AppError::Internal(format!("Failed to decrypt TOTP secret: {}", e)))
+}
+
+/// `otpauth://` URI for authenticator apps, usually shown as a QR code
+pub fn provisioning_uri(secret: &[u8], email: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{email}?secret={secret}&issuer={issuer}&digits={digits}&period={period}",
+        issuer = ISSUER,
+        email = email,
+        secret = encode_secret(secret),
+        digits = TOTP_DIGITS,
+        period = TOTP_PERIOD,
+    )
+}
+
+/// Check `code` against the time steps around `timestamp`
+///
+/// One step of clock drift is allowed either way. Steps at or before
+/// `last_step` are rejected so a code can't be replayed. Returns the matched
+/// step, which the caller records as the new `last_step`.
+pub fn verify_code(
+    secret: &[u8],
+    code: &str,
+    timestamp: i64,
+    last_step: Option<i64>,
+) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != TOTP_DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = timestamp / TOTP_PERIOD;
+    (current - 1..=current + 1)
+        .filter(|&step| step >= 0 && last_step.is_none_or(|last| step > last))
+        .find(|&step| {
+            hotp(secret, step as u64, TOTP_DIGITS, TotpAlgorithm::Sha1)
+                .is_ok_and(|expected| expected == code)
+        })
+}
+
+/// Generate one-time recovery codes, formatted `xxxxx-xxxxx`
+pub fn generate_recovery_codes() -> Vec<String> {
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    let mut rng = rand::thread_rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..10)
+                .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+/// Canonical form of a recovery code for hashing; case and separators are ignored
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_encryption_roundtrip() {
+        let secret = generate_secret();
+        let encrypted = encrypt_secret(&secret, "jwt-secret").unwrap();
+        assert_eq!(decrypt_secret(&encrypted, "jwt-secret").unwrap(), secret);
+        assert!(decrypt_secret(&encrypted, "other-secret").is_err());
+    }
+
+    #[test]
+    fn test_verify_code() {
+        // RFC 6238 SHA-1 test key at T = 59s; the 8-digit vector is 94287082
+        let secret = b"12345678901234567890";
+        let code = hotp(secret, 1, TOTP_DIGITS, TotpAlgorithm::Sha1).unwrap();
+        assert_eq!(code, "287082");
+
+        assert_eq!(verify_code(secret, &code, 59, None), Some(1));
+        // Drift of one step is tolerated, two is not
+        assert_eq!(verify_code(secret, &code, 89, None), Some(1));
+        assert_eq!(verify_code(secret, &code, 119, None), None);
+        // Replays of an already used step are rejected
+        assert_eq!(verify_code(secret, &code, 59, Some(1)), None);
+        assert_eq!(verify_code(secret, "28708", 59, None), None);
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(codes[0].len(), 11);
+        assert_eq!(
+            normalize_recovery_code(&codes[0].to_uppercase()),
+            codes[0].replace('-', "")
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use futures_util::TryStreamExt;
+use object_store::{path::Path, ObjectStore, PutPayload};
+
+use super::{BlobBackend, BlobFuture};
+use crate::{AppError, Result};
+
+/// Google Cloud Storage or Azure Blob Storage, through `object_store`
+pub struct ObjectStoreBackend {
+    store: Arc<dyn ObjectStore>,
+}
+
+impl ObjectStoreBackend {
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self { store }
+    }
+
+    /// A GCS bucket
+    ///
+    /// Without a service account key file, credentials come from the
+    /// environment (`GOOGLE_APPLICATION_CREDENTIALS` or the metadata server).
+    #[cfg(feature = "gcs")]
+    pub fn gcs(bucket: &str, service_account_path: Option<&str>) -> Result<Self> {
+        let mut builder =
+            object_store::gcp::GoogleCloudStorageBuilder::from_env().with_bucket_name(bucket);
+        if let Some(path) = service_account_path {
+            builder = builder.with_service_account_path(path);
+        }
+        let store = builder
+            .build()
+            .map_err(|e| AppError::BlobStorage(format!("Invalid GCS configuration: {}", e)))?;
+        Ok(Self::new(Arc::new(store)))
+    }
+
+    /// An Azure Blob Storage container
+    ///
+    /// Without an access key, credentials come from the environment (service
+    /// principal variables or managed identity).
+    #[cfg(feature = "azure")]
+    pub fn azure(account: &str, container: &str, access_key: Option<&str>) -> Result<Self> {
+        let mut builder = object_store::azure::MicrosoftAzureBuilder::from_env()
+            .with_account(account)
+            .with_container_name(container);
+        if let Some(key) = access_key {
+            builder = builder.with_access_key(key);
+        }
+        let store = builder
+            .build()
+            .map_err(|e| AppError::BlobStorage(format!("Invalid Azure configuration: {}", e)))?;
+        Ok(Self::new(Arc::new(store)))
+    }
+}
+
+fn storage_error(action: &str, e: object_store::Error) -> AppError {
+    AppError::BlobStorage(format!("Failed to {}: {}", action, e))
+}
+
+impl BlobBackend for ObjectStoreBackend {
+    fn store<'a>(&'a self, blob_id: &'a str, data: &'a [u8]) -> BlobFuture<'a, ()> {
+        Box::pin(async move {
+            self.store
+                .put(&Path::from(blob_id), PutPayload::from(data.to_vec()))
+                .await
+                .map_err(|e| storage_error("store blob", e))?;
+            Ok(())
+        })
+    }
+
+    fn retrieve<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            let data = self
+                .store
+                .get(&Path::from(blob_id))
+                .await
+                .map_err(|e| storage_error("retrieve blob", e))?
+                .bytes()
+                .await
+                .map_err(|e| storage_error("read blob body", e))?;
+            Ok(data.to_vec())
+        })
+    }
+
+    fn delete<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, ()> {
+        Box::pin(async move {
+            // Like S3, deleting a missing blob isn't an error
+            match self.store.delete(&Path::from(blob_id)).await {
+                Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+                Err(e) => Err(storage_error("delete blob", e)),
+            }
+        })
+    }
+
+    /// `prefix` is matched by whole path segments, e.g. `"{user_id}/"`
+    fn delete_prefix<'a>(&'a self, prefix: &'a str) -> BlobFuture<'a, u64> {
+        Box::pin(async move {
+            let locations: Vec<Path> = self
+                .store
+                .list(Some(&Path::from(prefix)))
+                .map_ok(|meta| meta.location)
+                .try_collect()
+                .await
+                .map_err(|e| storage_error("list blobs", e))?;
+
+            for location in &locations {
+                self.store
+                    .delete(location)
+                    .await
+                    .map_err(|e| storage_error("delete blobs", e))?;
+            }
+            Ok(locations.len() as u64)
+        })
+    }
+
+    fn exists<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, bool> {
+        Box::pin(async move {
+            match self.store.head(&Path::from(blob_id)).await {
+                Ok(_) => Ok(true),
+                Err(object_store::Error::NotFound { .. }) => Ok(false),
+                Err(e) => Err(storage_error("check blob existence", e)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    #[tokio::test]
+    async fn test_object_store_backend() {
+        let backend = ObjectStoreBackend::new(Arc::new(InMemory::new()));
+
+        backend.store("user-a/one", b"first").await.unwrap();
+        backend.store("user-a/two", b"second").await.unwrap();
+        backend.store("user-b/one", b"other").await.unwrap();
+        assert_eq!(backend.retrieve("user-a/one").await.unwrap(), b"first");
+        assert!(backend.exists("user-a/two").await.unwrap());
+        assert!(!backend.exists("user-a/three").await.unwrap());
+
+        backend.delete("user-a/one").await.unwrap();
+        backend.delete("user-a/one").await.unwrap();
+        assert!(backend.retrieve("user-a/one").await.is_err());
+
+        assert_eq!(backend.delete_prefix("user-a/").await.unwrap(), 1);
+        assert!(!backend.exists("user-a/two").await.unwrap());
+        assert!(backend.exists("user-b/one").await.unwrap());
+    }
+}
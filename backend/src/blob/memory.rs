@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::{BlobBackend, BlobFuture};
+use crate::AppError;
+
+/// Blobs kept in process memory (for testing)
+#[derive(Default)]
+pub struct InMemoryBackend {
+    blobs: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl BlobBackend for InMemoryBackend {
+    fn store<'a>(&'a self, blob_id: &'a str, data: &'a [u8]) -> BlobFuture<'a, ()> {
+        self.blobs
+            .lock()
+            .unwrap()
+            .insert(blob_id.to_string(), data.to_vec());
+        Box::pin(async { Ok(()) })
+    }
+
+    fn retrieve<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, Vec<u8>> {
+        let data = self
+            .blobs
+            .lock()
+            .unwrap()
+            .get(blob_id)
+            .cloned()
+            .ok_or_else(|| AppError::BlobStorage(format!("Blob not found: {}", blob_id)));
+        Box::pin(async { data })
+    }
+
+    fn delete<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, ()> {
+        self.blobs.lock().unwrap().remove(blob_id);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn delete_prefix<'a>(&'a self, prefix: &'a str) -> BlobFuture<'a, u64> {
+        let mut blobs = self.blobs.lock().unwrap();
+        let before = blobs.len();
+        blobs.retain(|blob_id, _| !blob_id.starts_with(prefix));
+        let removed = (before - blobs.len()) as u64;
+        Box::pin(async move { Ok(removed) })
+    }
+
+    fn exists<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, bool> {
+        let exists = self.blobs.lock().unwrap().contains_key(blob_id);
+        Box::pin(async move { Ok(exists) })
+    }
+}
@@ -1,155 +1,207 @@
-use aws_config::BehaviorVersion;
-use aws_sdk_s3::Client;
-use std::collections::HashMap;
-use std::sync::Mutex;
+//! Blob storage for encrypted vault data
+//!
+//! `BlobStorage` sits in front of a `BlobBackend`, chosen at startup by
+//! `BlobConfig::from_env`. S3 is always built in; Google Cloud Storage and
+//! Azure Blob Storage are behind the `gcs` and `azure` cargo features.
+
+use base64::Engine;
+use futures_util::future::BoxFuture;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::{AppError, Result};
 
-enum Backend {
-    S3 { client: Client, bucket: String },
-    InMemory(Mutex<HashMap<String, Vec<u8>>>),
+#[cfg(any(feature = "gcs", feature = "azure"))]
+mod cloud;
+mod memory;
+mod s3;
+
+#[cfg(any(feature = "gcs", feature = "azure"))]
+pub use cloud::ObjectStoreBackend;
+pub use memory::InMemoryBackend;
+pub use s3::S3Backend;
+
+/// Future returned by a `BlobBackend` operation
+pub type BlobFuture<'a, T> = BoxFuture<'a, Result<T>>;
+
+/// A place to keep blobs
+pub trait BlobBackend: Send + Sync {
+    /// Store a blob, replacing any existing one with the same ID
+    fn store<'a>(&'a self, blob_id: &'a str, data: &'a [u8]) -> BlobFuture<'a, ()>;
+
+    fn retrieve<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, Vec<u8>>;
+
+    /// Delete a blob; deleting one that doesn't exist succeeds
+    fn delete<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, ()>;
+
+    /// Delete every blob whose ID starts with `prefix`, returning how many were removed
+    fn delete_prefix<'a>(&'a self, prefix: &'a str) -> BlobFuture<'a, u64>;
+
+    fn exists<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, bool>;
+}
+
+/// Which backend to use and how to reach it
+#[derive(Debug, Clone)]
+pub enum BlobConfig {
+    S3 {
+        bucket: String,
+        /// Local S3-compatible server (MinIO, LocalStack)
+        endpoint: Option<String>,
+    },
+    #[cfg(feature = "gcs")]
+    Gcs {
+        bucket: String,
+        service_account_path: Option<String>,
+    },
+    #[cfg(feature = "azure")]
+    Azure {
+        account: String,
+        container: String,
+        access_key: Option<String>,
+    },
+}
+
+impl BlobConfig {
+    /// Read the backend from `BLOB_BACKEND` (`s3`, `gcs` or `azure`; default `s3`)
+    pub fn from_env() -> Result<Self> {
+        let backend = std::env::var("BLOB_BACKEND").unwrap_or_else(|_| "s3".to_string());
+
+        match backend.as_str() {
+            "s3" => Ok(Self::S3 {
+                bucket: std::env::var("S3_BUCKET")
+                    .unwrap_or_else(|_| "keydrop-vault-blobs".to_string()),
+                endpoint: std::env::var("S3_ENDPOINT").ok(),
+            }),
+            #[cfg(feature = "gcs")]
+            "gcs" => Ok(Self::Gcs {
+                bucket: required_var("GCS_BUCKET")?,
+                service_account_path: std::env::var("GCS_SERVICE_ACCOUNT_PATH").ok(),
+            }),
+            #[cfg(feature = "azure")]
+            "azure" => Ok(Self::Azure {
+                account: required_var("AZURE_STORAGE_ACCOUNT")?,
+                container: required_var("AZURE_STORAGE_CONTAINER")?,
+                access_key: std::env::var("AZURE_STORAGE_ACCESS_KEY").ok(),
+            }),
+            // Only reachable for backends left out of this build
+            #[allow(unreachable_patterns)]
+            "gcs" | "azure" => Err(AppError::BlobStorage(format!(
+                "BLOB_BACKEND={} requires building with the `{}` feature",
+                backend, backend
+            ))),
+            _ => Err(AppError::BlobStorage(format!(
+                "Unknown BLOB_BACKEND: {}",
+                backend
+            ))),
+        }
+    }
+}
+
+#[cfg(any(feature = "gcs", feature = "azure"))]
+fn required_var(name: &str) -> Result<String> {
+    std::env::var(name).map_err(|_| AppError::BlobStorage(format!("{} is not set", name)))
 }
 
 /// Blob storage service for encrypted vault data
 pub struct BlobStorage {
-    backend: Backend,
+    backend: Box<dyn BlobBackend>,
 }
 
 impl BlobStorage {
-    /// Create a new blob storage instance backed by S3
+    /// Create a blob storage instance configured from the environment
     pub async fn new() -> Result<Self> {
-        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
-
-        let bucket =
-            std::env::var("S3_BUCKET").unwrap_or_else(|_| "keydrop-vault-blobs".to_string());
-
-        // Check for local S3 endpoint (for development with MinIO/LocalStack)
-        let client = if let Ok(endpoint) = std::env::var("S3_ENDPOINT") {
-            let s3_config = aws_sdk_s3::config::Builder::from(&config)
-                .endpoint_url(endpoint)
-                .force_path_style(true)
-                .build();
-            Client::from_conf(s3_config)
-        } else {
-            Client::new(&config)
+        Self::from_config(BlobConfig::from_env()?).await
+    }
+
+    pub async fn from_config(config: BlobConfig) -> Result<Self> {
+        let backend: Box<dyn BlobBackend> = match config {
+            BlobConfig::S3 { bucket, endpoint } => Box::new(S3Backend::new(bucket, endpoint).await),
+            #[cfg(feature = "gcs")]
+            BlobConfig::Gcs {
+                bucket,
+                service_account_path,
+            } => Box::new(ObjectStoreBackend::gcs(
+                &bucket,
+                service_account_path.as_deref(),
+            )?),
+            #[cfg(feature = "azure")]
+            BlobConfig::Azure {
+                account,
+                container,
+                access_key,
+            } => Box::new(ObjectStoreBackend::azure(
+                &account,
+                &container,
+                access_key.as_deref(),
+            )?),
         };
+        Ok(Self::with_backend(backend))
+    }
 
-        Ok(Self {
-            backend: Backend::S3 { client, bucket },
-        })
+    pub fn with_backend(backend: Box<dyn BlobBackend>) -> Self {
+        Self { backend }
     }
 
     /// Create an in-memory blob storage instance (for testing)
     pub fn in_memory() -> Self {
-        Self {
-            backend: Backend::InMemory(Mutex::new(HashMap::new())),
-        }
+        Self::with_backend(Box::new(InMemoryBackend::default()))
     }
 
-    /// Generate a unique blob ID
-    pub fn generate_blob_id(user_id: Uuid) -> String {
-        format!("{}/{}", user_id, Uuid::new_v4())
+    /// Content-addressed blob ID for `data` owned by `user_id`
+    ///
+    /// The hash is keyed by the user, so identical ciphertext from different
+    /// users never maps to the same blob and can't be correlated.
+    pub fn content_blob_id(user_id: Uuid, data: &[u8]) -> String {
+        let digest = Sha256::new()
+            .chain_update(user_id.as_bytes())
+            .chain_update(data)
+            .finalize();
+        format!(
+            "{}/{}",
+            user_id,
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(digest)
+        )
+    }
+
+    /// Store `data` under its content address unless it's already there
+    ///
+    /// Returns the blob ID and whether this call created it. Callers hold
+    /// the user's sync version lock, which blob GC takes too, so an existing
+    /// blob can't be collected before the caller's row references it.
+    pub async fn store_deduplicated(&self, user_id: Uuid, data: &[u8]) -> Result<(String, bool)> {
+        let blob_id = Self::content_blob_id(user_id, data);
+        if self.exists(&blob_id).await? {
+            return Ok((blob_id, false));
+        }
+        self.store(&blob_id, data).await?;
+        Ok((blob_id, true))
     }
 
     /// Store an encrypted blob
     pub async fn store(&self, blob_id: &str, data: &[u8]) -> Result<()> {
-        match &self.backend {
-            Backend::S3 { client, bucket } => {
-                client
-                    .put_object()
-                    .bucket(bucket)
-                    .key(blob_id)
-                    .body(data.to_vec().into())
-                    .content_type("application/octet-stream")
-                    .send()
-                    .await
-                    .map_err(|e| AppError::BlobStorage(format!("Failed to store blob: {}", e)))?;
-            }
-            Backend::InMemory(map) => {
-                map.lock()
-                    .unwrap()
-                    .insert(blob_id.to_string(), data.to_vec());
-            }
-        }
-        Ok(())
+        self.backend.store(blob_id, data).await
     }
 
     /// Retrieve an encrypted blob
     pub async fn retrieve(&self, blob_id: &str) -> Result<Vec<u8>> {
-        match &self.backend {
-            Backend::S3 { client, bucket } => {
-                let response = client
-                    .get_object()
-                    .bucket(bucket)
-                    .key(blob_id)
-                    .send()
-                    .await
-                    .map_err(|e| {
-                        AppError::BlobStorage(format!("Failed to retrieve blob: {}", e))
-                    })?;
-
-                let data = response
-                    .body
-                    .collect()
-                    .await
-                    .map_err(|e| AppError::BlobStorage(format!("Failed to read blob body: {}", e)))?
-                    .into_bytes()
-                    .to_vec();
-
-                Ok(data)
-            }
-            Backend::InMemory(map) => map
-                .lock()
-                .unwrap()
-                .get(blob_id)
-                .cloned()
-                .ok_or_else(|| AppError::BlobStorage(format!("Blob not found: {}", blob_id))),
-        }
+        self.backend.retrieve(blob_id).await
     }
 
     /// Delete an encrypted blob
     pub async fn delete(&self, blob_id: &str) -> Result<()> {
-        match &self.backend {
-            Backend::S3 { client, bucket } => {
-                client
-                    .delete_object()
-                    .bucket(bucket)
-                    .key(blob_id)
-                    .send()
-                    .await
-                    .map_err(|e| AppError::BlobStorage(format!("Failed to delete blob: {}", e)))?;
-            }
-            Backend::InMemory(map) => {
-                map.lock().unwrap().remove(blob_id);
-            }
-        }
-        Ok(())
+        self.backend.delete(blob_id).await
+    }
+
+    /// Delete every blob whose ID starts with `prefix`, returning how many were removed
+    ///
+    /// Blob IDs are prefixed with the owning user's ID, so this removes all of
+    /// a user's blobs, including ones no sync row references any more.
+    pub async fn delete_prefix(&self, prefix: &str) -> Result<u64> {
+        self.backend.delete_prefix(prefix).await
     }
 
     /// Check if a blob exists
     pub async fn exists(&self, blob_id: &str) -> Result<bool> {
-        match &self.backend {
-            Backend::S3 { client, bucket } => match client
-                .head_object()
-                .bucket(bucket)
-                .key(blob_id)
-                .send()
-                .await
-            {
-                Ok(_) => Ok(true),
-                Err(e) => {
-                    if e.to_string().contains("404") || e.to_string().contains("NoSuchKey") {
-                        Ok(false)
-                    } else {
-                        Err(AppError::BlobStorage(format!(
-                            "Failed to check blob existence: {}",
-                            e
-                        )))
-                    }
-                }
-            },
-            Backend::InMemory(map) => Ok(map.lock().unwrap().contains_key(blob_id)),
-        }
+        self.backend.exists(blob_id).await
     }
 }
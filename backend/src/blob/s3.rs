@@ -0,0 +1,164 @@
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+use aws_sdk_s3::Client;
+
+use super::{BlobBackend, BlobFuture};
+use crate::AppError;
+
+/// Amazon S3 or an S3-compatible store (MinIO, LocalStack)
+pub struct S3Backend {
+    client: Client,
+    bucket: String,
+}
+
+impl S3Backend {
+    /// Connect with credentials from the standard AWS environment
+    ///
+    /// `endpoint` points at a local S3-compatible server for development.
+    pub async fn new(bucket: String, endpoint: Option<String>) -> Self {
+        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+
+        let client = if let Some(endpoint) = endpoint {
+            let s3_config = aws_sdk_s3::config::Builder::from(&config)
+                .endpoint_url(endpoint)
+                .force_path_style(true)
+                .build();
+            Client::from_conf(s3_config)
+        } else {
+            Client::new(&config)
+        };
+
+        Self { client, bucket }
+    }
+}
+
+impl BlobBackend for S3Backend {
+    fn store<'a>(&'a self, blob_id: &'a str, data: &'a [u8]) -> BlobFuture<'a, ()> {
+        Box::pin(async move {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(blob_id)
+                .body(data.to_vec().into())
+                .content_type("application/octet-stream")
+                .send()
+                .await
+                .map_err(|e| AppError::BlobStorage(format!("Failed to store blob: {}", e)))?;
+            Ok(())
+        })
+    }
+
+    fn retrieve<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(blob_id)
+                .send()
+                .await
+                .map_err(|e| AppError::BlobStorage(format!("Failed to retrieve blob: {}", e)))?;
+
+            let data = response
+                .body
+                .collect()
+                .await
+                .map_err(|e| AppError::BlobStorage(format!("Failed to read blob body: {}", e)))?
+                .into_bytes()
+                .to_vec();
+
+            Ok(data)
+        })
+    }
+
+    fn delete<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, ()> {
+        Box::pin(async move {
+            self.client
+                .delete_object()
+                .bucket(&self.bucket)
+                .key(blob_id)
+                .send()
+                .await
+                .map_err(|e| AppError::BlobStorage(format!("Failed to delete blob: {}", e)))?;
+            Ok(())
+        })
+    }
+
+    fn delete_prefix<'a>(&'a self, prefix: &'a str) -> BlobFuture<'a, u64> {
+        Box::pin(async move {
+            let mut removed = 0;
+            let mut continuation_token = None;
+            loop {
+                let page = self
+                    .client
+                    .list_objects_v2()
+                    .bucket(&self.bucket)
+                    .prefix(prefix)
+                    .set_continuation_token(continuation_token)
+                    .send()
+                    .await
+                    .map_err(|e| AppError::BlobStorage(format!("Failed to list blobs: {}", e)))?;
+
+                let objects = page
+                    .contents()
+                    .iter()
+                    .filter_map(|object| object.key())
+                    .map(|key| ObjectIdentifier::builder().key(key).build())
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|e| AppError::BlobStorage(e.to_string()))?;
+
+                if !objects.is_empty() {
+                    let count = objects.len() as u64;
+                    let delete = Delete::builder()
+                        .set_objects(Some(objects))
+                        .quiet(true)
+                        .build()
+                        .map_err(|e| AppError::BlobStorage(e.to_string()))?;
+                    self.client
+                        .delete_objects()
+                        .bucket(&self.bucket)
+                        .delete(delete)
+                        .send()
+                        .await
+                        .map_err(|e| {
+                            AppError::BlobStorage(format!("Failed to delete blobs: {}", e))
+                        })?;
+                    removed += count;
+                }
+
+                match page.next_continuation_token() {
+                    Some(token) if page.is_truncated() == Some(true) => {
+                        continuation_token = Some(token.to_string());
+                    }
+                    _ => break,
+                }
+            }
+            Ok(removed)
+        })
+    }
+
+    fn exists<'a>(&'a self, blob_id: &'a str) -> BlobFuture<'a, bool> {
+        Box::pin(async move {
+            match self
+                .client
+                .head_object()
+                .bucket(&self.bucket)
+                .key(blob_id)
+                .send()
+                .await
+            {
+                Ok(_) => Ok(true),
+                Err(e) => {
+                    if e.to_string().contains("404") || e.to_string().contains("NoSuchKey") {
+                        Ok(false)
+                    } else {
+                        Err(AppError::BlobStorage(format!(
+                            "Failed to check blob existence: {}",
+                            e
+                        )))
+                    }
+                }
+            }
+        })
+    }
+}
@@ -11,6 +11,21 @@ pub struct User {
     pub salt: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set while the account waits out its deletion grace period
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+    /// When the email address was confirmed; sync is refused until then
+    pub email_verified_at: Option<DateTime<Utc>>,
+    /// Encrypted TOTP secret; pending until `totp_enabled_at` is set
+    pub totp_secret_encrypted: Option<String>,
+    pub totp_enabled_at: Option<DateTime<Utc>>,
+    /// Last accepted TOTP time step, to reject replayed codes
+    pub totp_last_step: Option<i64>,
+    /// Consecutive wrong auth_keys since the last correct one
+    pub failed_auth_attempts: i32,
+    /// auth_key checks are refused until then
+    pub auth_locked_until: Option<DateTime<Utc>>,
+    /// New devices must be approved from an existing one before syncing
+    pub require_device_approval: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +69,9 @@ pub struct DeviceRow {
     pub push_token: Option<String>,
     pub last_seen_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub client_device_id: Option<String>,
+    pub device_secret_hash: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +84,10 @@ pub struct Device {
     pub push_token: Option<String>,
     pub last_seen_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    /// `None` while the device waits for approval
+    pub approved_at: Option<DateTime<Utc>>,
+    /// Stable identifier generated by the client, used to recognize it on login
+    pub client_device_id: Option<String>,
 }
 
 impl From<DeviceRow> for Device {
@@ -79,10 +101,25 @@ impl From<DeviceRow> for Device {
             push_token: row.push_token,
             last_seen_at: row.last_seen_at,
             created_at: row.created_at,
+            approved_at: row.approved_at,
+            client_device_id: row.client_device_id,
         }
     }
 }
 
+/// How a login identifies a device it has seen before
+#[derive(Debug, Clone, Copy)]
+pub struct DeviceIdentity<'a> {
+    pub client_device_id: &'a str,
+    /// Hash of the secret offered to the device by this login; stored only
+    /// if the device doesn't have one yet
+    pub issued_secret_hash: &'a str,
+    /// Hash of the secret the client presented as proof it is that device
+    pub presented_secret_hash: Option<&'a str>,
+    /// Whether the device is approved even without that proof
+    pub approved_without_secret: bool,
+}
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct VaultItemSync {
     pub id: Uuid,
@@ -99,6 +136,8 @@ pub struct SyncVersion {
     pub user_id: Uuid,
     pub current_version: i64,
     pub updated_at: DateTime<Utc>,
+    /// Highest version whose deletion marker was pruned
+    pub pruned_version: i64,
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -109,6 +148,26 @@ pub struct RefreshToken {
     pub token_hash: String,
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    /// Tokens descended from the same login
+    pub family_id: Uuid,
+    /// Set once the token has been rotated or its family revoked
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// A registered passkey or security key
+#[derive(Debug, Clone, FromRow)]
+pub struct WebauthnCredential {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Device the credential was registered from
+    pub device_id: Uuid,
+    pub name: String,
+    /// Base64url credential ID
+    pub credential_id: String,
+    /// Serialized `webauthn_rs::prelude::Passkey`
+    pub passkey: serde_json::Value,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
@@ -121,6 +180,33 @@ pub struct AuthRequest {
     pub status: String,
     pub expires_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    pub purpose: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AuthRequestPurpose {
+    /// Cross-device biometric auth
+    Biometric,
+    /// Letting a newly signed-in device sync
+    DeviceApproval,
+}
+
+impl From<String> for AuthRequestPurpose {
+    fn from(s: String) -> Self {
+        match s.to_lowercase().as_str() {
+            "device_approval" => AuthRequestPurpose::DeviceApproval,
+            _ => AuthRequestPurpose::Biometric,
+        }
+    }
+}
+
+impl From<AuthRequestPurpose> for String {
+    fn from(p: AuthRequestPurpose) -> Self {
+        match p {
+            AuthRequestPurpose::Biometric => "biometric".to_string(),
+            AuthRequestPurpose::DeviceApproval => "device_approval".to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -198,6 +284,8 @@ pub struct EmergencyContactRow {
     pub invitation_expires_at: Option<DateTime<Utc>>,
     pub accepted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub contact_public_key: Option<String>,
+    pub vault_key_encrypted: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -214,6 +302,10 @@ pub struct EmergencyContact {
     pub invitation_expires_at: Option<DateTime<Utc>>,
     pub accepted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// X25519 public key (base64) the contact published on accepting
+    pub contact_public_key: Option<String>,
+    /// Owner's vault key sealed for `contact_public_key`
+    pub vault_key_encrypted: Option<String>,
 }
 
 impl From<EmergencyContactRow> for EmergencyContact {
@@ -231,6 +323,8 @@ impl From<EmergencyContactRow> for EmergencyContact {
             invitation_expires_at: row.invitation_expires_at,
             accepted_at: row.accepted_at,
             created_at: row.created_at,
+            contact_public_key: row.contact_public_key,
+            vault_key_encrypted: row.vault_key_encrypted,
         }
     }
 }
@@ -308,6 +402,16 @@ impl From<EmergencyAccessRequestRow> for EmergencyAccessRequest {
     }
 }
 
+/// Entry in a user's security log
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SecurityEvent {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub action: String,
+    pub details: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct EmergencyAccessLog {
     pub id: Uuid,
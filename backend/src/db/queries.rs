@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgExecutor, PgPool};
 use uuid::Uuid;
 
 use super::models::*;
@@ -56,20 +56,593 @@ pub async fn get_user_by_id(pool: &PgPool, user_id: Uuid) -> Result<Option<User>
     Ok(user)
 }
 
+pub async fn update_user_auth_key(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    auth_key_hash: &str,
+    salt: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users SET auth_key_hash = $2, salt = $3 WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(auth_key_hash)
+    .bind(salt)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn mark_email_verified(pool: &PgPool, user_id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users SET email_verified_at = COALESCE(email_verified_at, NOW()) WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn is_email_verified(pool: &PgPool, user_id: Uuid) -> Result<bool> {
+    let verified = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT email_verified_at IS NOT NULL FROM users WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(verified.unwrap_or(false))
+}
+
+/// Users who registered before `created_before` and never verified their email
+pub async fn get_unverified_users(
+    pool: &PgPool,
+    created_before: DateTime<Utc>,
+) -> Result<Vec<Uuid>> {
+    let ids = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM users WHERE email_verified_at IS NULL AND created_at < $1
+        "#,
+    )
+    .bind(created_before)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Mark the account for deletion once `scheduled_at` passes
+pub async fn schedule_user_deletion(
+    pool: &PgPool,
+    user_id: Uuid,
+    scheduled_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users SET deletion_scheduled_at = $2 WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(scheduled_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Clear a pending deletion, returning whether one was scheduled
+pub async fn cancel_user_deletion(pool: &PgPool, user_id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE users SET deletion_scheduled_at = NULL
+        WHERE id = $1 AND deletion_scheduled_at IS NOT NULL
+        "#,
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_users_due_for_deletion(pool: &PgPool) -> Result<Vec<Uuid>> {
+    let ids = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM users WHERE deletion_scheduled_at <= NOW()
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Delete the user; devices, tokens, sync rows and the user's own emergency
+/// contacts go with it through `ON DELETE CASCADE`
+pub async fn delete_user(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        DELETE FROM users WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// ============ Email Verification Queries ============
+
+/// Store a verification token, replacing any the user had before
+pub async fn create_email_verification_token(
+    pool: &PgPool,
+    user_id: Uuid,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM email_verification_tokens WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO email_verification_tokens (id, user_id, token_hash, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(token_hash)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Consume an unexpired verification token, returning the user it belonged to
+pub async fn consume_email_verification_token(
+    pool: &PgPool,
+    token_hash: &str,
+) -> Result<Option<Uuid>> {
+    let user_id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        DELETE FROM email_verification_tokens
+        WHERE token_hash = $1 AND expires_at > NOW()
+        RETURNING user_id
+        "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user_id)
+}
+
+// ============ Two-Factor Queries ============
+
+/// Store a new, not yet confirmed TOTP secret
+pub async fn set_pending_totp_secret(
+    pool: &PgPool,
+    user_id: Uuid,
+    secret_encrypted: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_secret_encrypted = $2, totp_enabled_at = NULL, totp_last_step = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(secret_encrypted)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn enable_totp(executor: impl PgExecutor<'_>, user_id: Uuid, step: i64) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users SET totp_enabled_at = NOW(), totp_last_step = $2 WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Turn TOTP off and drop the recovery codes
+pub async fn disable_totp(pool: &PgPool, user_id: Uuid) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_secret_encrypted = NULL, totp_enabled_at = NULL, totp_last_step = NULL
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM totp_recovery_codes WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Record `step` as used, returning false if it (or a later one) already was
+pub async fn record_totp_step(pool: &PgPool, user_id: Uuid, step: i64) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE users SET totp_last_step = $2
+        WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Replace the user's recovery codes
+pub async fn replace_recovery_codes(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    code_hashes: &[String],
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        DELETE FROM totp_recovery_codes WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    for code_hash in code_hashes {
+        sqlx::query(
+            r#"
+            INSERT INTO totp_recovery_codes (id, user_id, code_hash, created_at)
+            VALUES ($1, $2, $3, NOW())
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Mark an unused recovery code as used, returning whether one matched
+pub async fn use_recovery_code(pool: &PgPool, user_id: Uuid, code_hash: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE totp_recovery_codes SET used_at = NOW()
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn count_unused_recovery_codes(pool: &PgPool, user_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT COUNT(*) FROM totp_recovery_codes WHERE user_id = $1 AND used_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+// ============ Login Throttling Queries ============
+
+/// Count a wrong auth_key, returning the consecutive failures so far
+pub async fn record_failed_auth_attempt(pool: &PgPool, user_id: Uuid) -> Result<i32> {
+    let (failed_attempts,): (i32,) = sqlx::query_as(
+        r#"
+        UPDATE users SET failed_auth_attempts = failed_auth_attempts + 1
+        WHERE id = $1
+        RETURNING failed_auth_attempts
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(failed_attempts)
+}
+
+/// Refuse auth_key checks until `locked_until`
+pub async fn lock_user_auth(
+    pool: &PgPool,
+    user_id: Uuid,
+    locked_until: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users SET auth_locked_until = $2 WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(locked_until)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Clear the failure count after a correct auth_key
+pub async fn reset_failed_auth_attempts(pool: &PgPool, user_id: Uuid) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users SET failed_auth_attempts = 0, auth_locked_until = NULL WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// ============ Security Event Queries ============
+
+pub async fn create_security_event(
+    pool: &PgPool,
+    user_id: Uuid,
+    action: &str,
+    details: Option<serde_json::Value>,
+) -> Result<SecurityEvent> {
+    let event = sqlx::query_as::<_, SecurityEvent>(
+        r#"
+        INSERT INTO security_events (user_id, action, details)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(action)
+    .bind(details)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(event)
+}
+
+pub async fn get_security_events_for_user(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+) -> Result<Vec<SecurityEvent>> {
+    let events = sqlx::query_as::<_, SecurityEvent>(
+        r#"
+        SELECT * FROM security_events WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(events)
+}
+
+// ============ WebAuthn Queries ============
+
+pub async fn create_webauthn_credential(
+    pool: &PgPool,
+    user_id: Uuid,
+    device_id: Uuid,
+    name: &str,
+    credential_id: &str,
+    passkey: &serde_json::Value,
+) -> Result<WebauthnCredential> {
+    let credential = sqlx::query_as::<_, WebauthnCredential>(
+        r#"
+        INSERT INTO webauthn_credentials (id, user_id, device_id, name, credential_id, passkey, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, NOW())
+        RETURNING *
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(device_id)
+    .bind(name)
+    .bind(credential_id)
+    .bind(passkey)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(credential)
+}
+
+pub async fn get_webauthn_credentials_by_user(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<WebauthnCredential>> {
+    let credentials = sqlx::query_as::<_, WebauthnCredential>(
+        r#"
+        SELECT * FROM webauthn_credentials WHERE user_id = $1 ORDER BY created_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(credentials)
+}
+
+/// Record a successful assertion, storing the passkey if its counter changed
+pub async fn update_webauthn_credential_usage(
+    pool: &PgPool,
+    credential_id: Uuid,
+    passkey: Option<&serde_json::Value>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE webauthn_credentials
+        SET last_used_at = NOW(), passkey = COALESCE($2, passkey)
+        WHERE id = $1
+        "#,
+    )
+    .bind(credential_id)
+    .bind(passkey)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_webauthn_credential(
+    pool: &PgPool,
+    credential_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM webauthn_credentials WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(credential_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Store ceremony state until the finish request, dropping expired ones
+pub async fn create_webauthn_challenge(
+    pool: &PgPool,
+    user_id: Uuid,
+    kind: &str,
+    state: &serde_json::Value,
+    expires_at: DateTime<Utc>,
+) -> Result<Uuid> {
+    sqlx::query(
+        r#"
+        DELETE FROM webauthn_challenges WHERE expires_at <= NOW()
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    let id = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        INSERT INTO webauthn_challenges (id, user_id, kind, state, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, NOW())
+        RETURNING id
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(kind)
+    .bind(state)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(id)
+}
+
+/// Take an unexpired challenge of `kind`, returning its user and state
+pub async fn consume_webauthn_challenge(
+    pool: &PgPool,
+    challenge_id: Uuid,
+    kind: &str,
+) -> Result<Option<(Uuid, serde_json::Value)>> {
+    let challenge = sqlx::query_as::<_, (Uuid, serde_json::Value)>(
+        r#"
+        DELETE FROM webauthn_challenges
+        WHERE id = $1 AND kind = $2 AND expires_at > NOW()
+        RETURNING user_id, state
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(kind)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(challenge)
+}
+
 // ============ Device Queries ============
 
+/// Create a device, or update the user's device with the same `client_device_id`
+///
+/// An existing device keeps its id, push token, creation time and secret. It
+/// keeps its approval only if the client presented that secret, and is
+/// otherwise approved only if `identity` allows it without. `approved` is
+/// for a new device, or an existing one that proved itself but was pending.
+/// Returns the device and whether the issued secret is now its secret.
 pub async fn create_device(
     pool: &PgPool,
     user_id: Uuid,
     device_name: &str,
     device_type: DeviceType,
     public_key: Option<&str>,
-) -> Result<Device> {
+    approved: bool,
+    identity: Option<DeviceIdentity<'_>>,
+) -> Result<(Device, bool)> {
     let device_type_str: String = device_type.into();
     let row = sqlx::query_as::<_, DeviceRow>(
         r#"
-        INSERT INTO devices (id, user_id, device_name, device_type, public_key, last_seen_at, created_at)
-        VALUES ($1, $2, $3, $4, $5, NOW(), NOW())
+        INSERT INTO devices (id, user_id, device_name, device_type, public_key, last_seen_at, created_at, approved_at, client_device_id, device_secret_hash)
+        VALUES ($1, $2, $3, $4, $5, NOW(), NOW(), CASE WHEN $6 THEN NOW() END, $7, $8)
+        ON CONFLICT (user_id, client_device_id) DO UPDATE SET
+            device_name = EXCLUDED.device_name,
+            device_type = EXCLUDED.device_type,
+            public_key = COALESCE(EXCLUDED.public_key, devices.public_key),
+            last_seen_at = NOW(),
+            approved_at = CASE
+                WHEN devices.device_secret_hash = $9 THEN COALESCE(devices.approved_at, EXCLUDED.approved_at)
+                WHEN $10 THEN COALESCE(devices.approved_at, NOW())
+            END,
+            device_secret_hash = COALESCE(devices.device_secret_hash, EXCLUDED.device_secret_hash)
         RETURNING *
         "#,
     )
@@ -78,10 +651,17 @@ pub async fn create_device(
     .bind(device_name)
     .bind(device_type_str)
     .bind(public_key)
+    .bind(approved)
+    .bind(identity.map(|i| i.client_device_id))
+    .bind(identity.map(|i| i.issued_secret_hash))
+    .bind(identity.and_then(|i| i.presented_secret_hash))
+    .bind(identity.is_some_and(|i| i.approved_without_secret))
     .fetch_one(pool)
     .await?;
 
-    Ok(Device::from(row))
+    let secret_issued =
+        identity.is_some_and(|i| row.device_secret_hash.as_deref() == Some(i.issued_secret_hash));
+    Ok((Device::from(row), secret_issued))
 }
 
 pub async fn get_device_by_id(pool: &PgPool, device_id: Uuid) -> Result<Option<Device>> {
@@ -141,6 +721,38 @@ pub async fn update_device_push_token(
     Ok(())
 }
 
+/// Let a pending device sync; returns false if it wasn't pending
+pub async fn approve_device(pool: &PgPool, device_id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE devices SET approved_at = NOW() WHERE id = $1 AND approved_at IS NULL
+        "#,
+    )
+    .bind(device_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn set_device_approval_required(
+    pool: &PgPool,
+    user_id: Uuid,
+    required: bool,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE users SET require_device_approval = $2 WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(required)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn delete_device(pool: &PgPool, device_id: Uuid) -> Result<()> {
     sqlx::query(
         r#"
@@ -169,7 +781,36 @@ pub async fn get_sync_version(pool: &PgPool, user_id: Uuid) -> Result<i64> {
     Ok(result.map(|sv| sv.current_version).unwrap_or(0))
 }
 
-pub async fn increment_sync_version(pool: &PgPool, user_id: Uuid) -> Result<i64> {
+/// Highest version whose deletion marker was pruned, 0 if none were
+pub async fn get_pruned_version(pool: &PgPool, user_id: Uuid) -> Result<i64> {
+    let result = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT pruned_version FROM sync_versions WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result.unwrap_or(0))
+}
+
+/// Current sync version, locked until the transaction ends so no push can
+/// slip in between
+pub async fn lock_sync_version(conn: &mut PgConnection, user_id: Uuid) -> Result<i64> {
+    let result = sqlx::query_scalar::<_, i64>(
+        r#"
+        SELECT current_version FROM sync_versions WHERE user_id = $1 FOR UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(result.unwrap_or(0))
+}
+
+pub async fn increment_sync_version(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<i64> {
     let result = sqlx::query_scalar::<_, i64>(
         r#"
         INSERT INTO sync_versions (user_id, current_version, updated_at)
@@ -180,42 +821,149 @@ pub async fn increment_sync_version(pool: &PgPool, user_id: Uuid) -> Result<i64>
         "#,
     )
     .bind(user_id)
-    .fetch_one(pool)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(result)
+}
+
+/// Reserve `count` consecutive versions in one bump, returning the last
+///
+/// Callers give their items `last - count + 1 ..= last`, so every item keeps
+/// a distinct version and cursor pagination never splits a shared one.
+pub async fn advance_sync_version(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    count: i64,
+) -> Result<i64> {
+    let result = sqlx::query_scalar::<_, i64>(
+        r#"
+        INSERT INTO sync_versions (user_id, current_version, updated_at)
+        VALUES ($1, $2, NOW())
+        ON CONFLICT (user_id)
+        DO UPDATE SET current_version = sync_versions.current_version + $2, updated_at = NOW()
+        RETURNING current_version
+        "#,
+    )
+    .bind(user_id)
+    .bind(count)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(result)
+}
+
+/// IDs of the user's items that aren't deleted
+pub async fn get_live_vault_item_ids(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+) -> Result<Vec<Uuid>> {
+    let ids = sqlx::query_scalar::<_, Uuid>(
+        r#"
+        SELECT id FROM vault_items_sync WHERE user_id = $1 AND NOT is_deleted
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(ids)
+}
+
+pub async fn get_vault_items_since_version(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    since_version: i64,
+) -> Result<Vec<VaultItemSync>> {
+    let items = sqlx::query_as::<_, VaultItemSync>(
+        r#"
+        SELECT * FROM vault_items_sync
+        WHERE user_id = $1 AND version > $2
+        ORDER BY version ASC
+        "#,
+    )
+    .bind(user_id)
+    .bind(since_version)
+    .fetch_all(executor)
     .await?;
 
-    Ok(result)
+    Ok(items)
 }
 
-pub async fn get_vault_items_since_version(
+/// One page of items changed after `after_version`, oldest first
+///
+/// Versions are unique per user, so the last version returned is a complete
+/// cursor for the next page.
+pub async fn get_vault_items_page(
     pool: &PgPool,
     user_id: Uuid,
-    since_version: i64,
+    after_version: i64,
+    limit: i64,
 ) -> Result<Vec<VaultItemSync>> {
     let items = sqlx::query_as::<_, VaultItemSync>(
         r#"
         SELECT * FROM vault_items_sync
         WHERE user_id = $1 AND version > $2
         ORDER BY version ASC
+        LIMIT $3
         "#,
     )
     .bind(user_id)
-    .bind(since_version)
+    .bind(after_version)
+    .bind(limit)
     .fetch_all(pool)
     .await?;
 
     Ok(items)
 }
 
+/// Remove deletion markers last changed before `cutoff`, returning their blobs
+///
+/// Each user's prune watermark is raised to the newest marker removed, so a
+/// pull from below it can be told to start over.
+pub async fn delete_tombstones_before(pool: &PgPool, cutoff: DateTime<Utc>) -> Result<Vec<String>> {
+    let blob_ids = sqlx::query_scalar::<_, String>(
+        r#"
+        WITH pruned AS (
+            DELETE FROM vault_items_sync
+            WHERE is_deleted AND modified_at < $1
+            RETURNING user_id, version, encrypted_blob_id
+        ), watermark AS (
+            UPDATE sync_versions s
+            SET pruned_version = GREATEST(s.pruned_version, p.max_version)
+            FROM (SELECT user_id, MAX(version) AS max_version FROM pruned GROUP BY user_id) p
+            WHERE s.user_id = p.user_id
+        )
+        SELECT encrypted_blob_id FROM pruned
+        "#,
+    )
+    .bind(cutoff)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(blob_ids)
+}
+
+/// Insert or replace an item, recording the blob it pointed at before as superseded
+///
+/// Returns `None` without changing anything if the ID belongs to another
+/// user's item.
 pub async fn upsert_vault_item(
-    pool: &PgPool,
+    executor: impl PgExecutor<'_>,
     id: Uuid,
     user_id: Uuid,
     version: i64,
     encrypted_blob_id: &str,
     is_deleted: bool,
-) -> Result<VaultItemSync> {
+) -> Result<Option<VaultItemSync>> {
     let item = sqlx::query_as::<_, VaultItemSync>(
         r#"
+        WITH superseded AS (
+            INSERT INTO superseded_blobs (blob_id, superseded_at)
+            SELECT encrypted_blob_id, NOW() FROM vault_items_sync
+            WHERE id = $1 AND user_id = $2 AND encrypted_blob_id <> $4
+            ON CONFLICT (blob_id) DO UPDATE SET superseded_at = NOW()
+        )
         INSERT INTO vault_items_sync (id, user_id, version, encrypted_blob_id, modified_at, is_deleted, created_at)
         VALUES ($1, $2, $3, $4, NOW(), $5, NOW())
         ON CONFLICT (id)
@@ -224,6 +972,7 @@ pub async fn upsert_vault_item(
             encrypted_blob_id = $4,
             modified_at = NOW(),
             is_deleted = $5
+        WHERE vault_items_sync.user_id = $2
         RETURNING *
         "#,
     )
@@ -232,7 +981,7 @@ pub async fn upsert_vault_item(
     .bind(version)
     .bind(encrypted_blob_id)
     .bind(is_deleted)
-    .fetch_one(pool)
+    .fetch_optional(executor)
     .await?;
 
     Ok(item)
@@ -256,25 +1005,163 @@ pub async fn get_vault_item_by_id(
     Ok(item)
 }
 
+/// Response and request hash stored for an unexpired push idempotency key
+pub async fn get_push_response(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    idempotency_key: &str,
+) -> Result<Option<(serde_json::Value, Option<Vec<u8>>)>> {
+    let response = sqlx::query_as::<_, (serde_json::Value, Option<Vec<u8>>)>(
+        r#"
+        SELECT response, request_hash FROM sync_push_idempotency_keys
+        WHERE user_id = $1 AND idempotency_key = $2 AND expires_at > NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(idempotency_key)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(response)
+}
+
+/// Remember a push response under its idempotency key, dropping expired ones
+pub async fn store_push_response(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    idempotency_key: &str,
+    request_hash: &[u8],
+    response: &serde_json::Value,
+    expires_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        DELETE FROM sync_push_idempotency_keys WHERE expires_at <= NOW()
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO sync_push_idempotency_keys (user_id, idempotency_key, request_hash, response, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, NOW())
+        "#,
+    )
+    .bind(user_id)
+    .bind(idempotency_key)
+    .bind(request_hash)
+    .bind(response)
+    .bind(expires_at)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// ============ Blob GC Queries ============
+
+/// Record a blob nothing should reference any more, for the GC job to delete
+pub async fn record_superseded_blob(pool: &PgPool, blob_id: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO superseded_blobs (blob_id, superseded_at)
+        VALUES ($1, NOW())
+        ON CONFLICT (blob_id) DO NOTHING
+        "#,
+    )
+    .bind(blob_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Superseded blobs older than `cutoff` that no item references, oldest first
+///
+/// Ones referenced again are dropped from tracking instead.
+pub async fn get_collectable_blobs(
+    pool: &PgPool,
+    cutoff: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<String>> {
+    sqlx::query(
+        r#"
+        DELETE FROM superseded_blobs s
+        WHERE EXISTS (SELECT 1 FROM vault_items_sync v WHERE v.encrypted_blob_id = s.blob_id)
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    let blob_ids = sqlx::query_scalar::<_, String>(
+        r#"
+        SELECT blob_id FROM superseded_blobs
+        WHERE superseded_at < $1
+        ORDER BY superseded_at ASC
+        LIMIT $2
+        "#,
+    )
+    .bind(cutoff)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(blob_ids)
+}
+
+/// Whether any item points at the blob
+pub async fn is_blob_referenced(executor: impl PgExecutor<'_>, blob_id: &str) -> Result<bool> {
+    let referenced = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS(SELECT 1 FROM vault_items_sync WHERE encrypted_blob_id = $1)
+        "#,
+    )
+    .bind(blob_id)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(referenced)
+}
+
+/// Stop tracking blobs that have been deleted from storage or are in use
+pub async fn delete_superseded_blobs(
+    executor: impl PgExecutor<'_>,
+    blob_ids: &[String],
+) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM superseded_blobs WHERE blob_id = ANY($1)
+        "#,
+    )
+    .bind(blob_ids)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
 // ============ Refresh Token Queries ============
 
 pub async fn create_refresh_token(
     pool: &PgPool,
     user_id: Uuid,
     device_id: Uuid,
+    family_id: Uuid,
     token_hash: &str,
     expires_at: DateTime<Utc>,
 ) -> Result<RefreshToken> {
     let token = sqlx::query_as::<_, RefreshToken>(
         r#"
-        INSERT INTO refresh_tokens (id, user_id, device_id, token_hash, expires_at, created_at)
-        VALUES ($1, $2, $3, $4, $5, NOW())
+        INSERT INTO refresh_tokens (id, user_id, device_id, family_id, token_hash, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, NOW())
         RETURNING *
         "#,
     )
     .bind(Uuid::new_v4())
     .bind(user_id)
     .bind(device_id)
+    .bind(family_id)
     .bind(token_hash)
     .bind(expires_at)
     .fetch_one(pool)
@@ -283,6 +1170,7 @@ pub async fn create_refresh_token(
     Ok(token)
 }
 
+/// Look up an unexpired refresh token, including revoked ones
 pub async fn get_refresh_token_by_hash(
     pool: &PgPool,
     token_hash: &str,
@@ -299,6 +1187,68 @@ pub async fn get_refresh_token_by_hash(
     Ok(token)
 }
 
+/// Mark a token as used; returns false if it was already revoked
+pub async fn revoke_refresh_token(pool: &PgPool, token_id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE refresh_tokens SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(token_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revoke every live token descended from the same login
+pub async fn revoke_refresh_token_family(pool: &PgPool, family_id: Uuid) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE refresh_tokens SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(family_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Revoke the user's tokens on every device but `keep_device_id`
+pub async fn revoke_other_refresh_tokens(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    keep_device_id: Uuid,
+) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE refresh_tokens SET revoked_at = NOW()
+        WHERE user_id = $1 AND device_id <> $2 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(keep_device_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn revoke_user_refresh_tokens(pool: &PgPool, user_id: Uuid) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE refresh_tokens SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
 pub async fn delete_refresh_token(pool: &PgPool, token_id: Uuid) -> Result<()> {
     sqlx::query(
         r#"
@@ -332,11 +1282,13 @@ pub async fn create_auth_request(
     target_device_id: Uuid,
     challenge: &str,
     expires_at: DateTime<Utc>,
+    purpose: AuthRequestPurpose,
 ) -> Result<AuthRequest> {
+    let purpose_str: String = purpose.into();
     let request = sqlx::query_as::<_, AuthRequest>(
         r#"
-        INSERT INTO auth_requests (id, requester_device_id, target_device_id, challenge, status, expires_at, created_at)
-        VALUES ($1, $2, $3, $4, 'pending', $5, NOW())
+        INSERT INTO auth_requests (id, requester_device_id, target_device_id, challenge, status, expires_at, created_at, purpose)
+        VALUES ($1, $2, $3, $4, 'pending', $5, NOW(), $6)
         RETURNING *
         "#,
     )
@@ -345,6 +1297,7 @@ pub async fn create_auth_request(
     .bind(target_device_id)
     .bind(challenge)
     .bind(expires_at)
+    .bind(purpose_str)
     .fetch_one(pool)
     .await?;
 
@@ -406,6 +1359,61 @@ pub async fn update_auth_request_response(
     Ok(())
 }
 
+/// Whether a device still has an unexpired approval request outstanding
+pub async fn has_pending_device_approval(pool: &PgPool, device_id: Uuid) -> Result<bool> {
+    let pending: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM auth_requests
+            WHERE requester_device_id = $1 AND purpose = 'device_approval'
+              AND status = 'pending' AND expires_at > NOW()
+        )
+        "#,
+    )
+    .bind(device_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(pending)
+}
+
+/// Close every approval request sent for a device, recording who answered
+pub async fn resolve_device_approval_requests(
+    pool: &PgPool,
+    device_id: Uuid,
+    responder_device_id: Uuid,
+    status: AuthRequestStatus,
+) -> Result<u64> {
+    let status_str: String = status.into();
+    let result = sqlx::query(
+        r#"
+        UPDATE auth_requests SET status = $3, response = $2
+        WHERE requester_device_id = $1 AND purpose = 'device_approval' AND status = 'pending'
+        "#,
+    )
+    .bind(device_id)
+    .bind(responder_device_id.to_string())
+    .bind(status_str)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Mark pending auth requests past their expiry as expired
+pub async fn expire_auth_requests(pool: &PgPool) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE auth_requests SET status = 'expired'
+        WHERE status = 'pending' AND expires_at <= NOW()
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
 // ============ Emergency Contact Queries ============
 
 pub async fn create_emergency_contact(
@@ -504,22 +1512,100 @@ pub async fn accept_emergency_contact_invitation(
     pool: &PgPool,
     contact_id: Uuid,
     contact_user_id: Uuid,
+    contact_public_key: &str,
 ) -> Result<()> {
     sqlx::query(
         r#"
         UPDATE emergency_contacts
-        SET status = 'accepted', contact_user_id = $2, accepted_at = NOW(), invitation_token = NULL
+        SET status = 'accepted', contact_user_id = $2, accepted_at = NOW(), invitation_token = NULL,
+            contact_public_key = $3
         WHERE id = $1
         "#,
     )
     .bind(contact_id)
     .bind(contact_user_id)
+    .bind(contact_public_key)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Store the owner's vault key sealed for the contact, handing it to
+/// requests already granted too
+pub async fn set_emergency_contact_vault_key(
+    pool: &PgPool,
+    contact_id: Uuid,
+    vault_key_encrypted: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        WITH contact AS (
+            UPDATE emergency_contacts SET vault_key_encrypted = $2 WHERE id = $1
+        )
+        UPDATE emergency_access_requests SET vault_key_encrypted = $2
+        WHERE emergency_contact_id = $1 AND status = 'approved'
+        "#,
+    )
+    .bind(contact_id)
+    .bind(vault_key_encrypted)
     .execute(pool)
     .await?;
 
     Ok(())
 }
 
+/// Drop the vault keys sealed for this owner's contacts, which a new master
+/// password made stale
+pub async fn clear_emergency_vault_keys(
+    executor: impl PgExecutor<'_>,
+    owner_id: Uuid,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        WITH contacts AS (
+            UPDATE emergency_contacts SET vault_key_encrypted = NULL
+            WHERE user_id = $1
+            RETURNING id
+        )
+        UPDATE emergency_access_requests SET vault_key_encrypted = ''
+        WHERE emergency_contact_id IN (SELECT id FROM contacts) AND status = 'approved'
+        "#,
+    )
+    .bind(owner_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Replace the public key this user published as a contact; keys sealed for
+/// the old one can't be opened any more and are dropped
+pub async fn update_emergency_contact_public_key(
+    executor: impl PgExecutor<'_>,
+    contact_user_id: Uuid,
+    contact_public_key: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        WITH contacts AS (
+            UPDATE emergency_contacts
+            SET contact_public_key = $2, vault_key_encrypted = NULL
+            WHERE contact_user_id = $1
+            RETURNING id
+        )
+        UPDATE emergency_access_requests SET vault_key_encrypted = ''
+        WHERE emergency_contact_id IN (SELECT id FROM contacts) AND status = 'approved'
+        "#,
+    )
+    .bind(contact_user_id)
+    .bind(contact_public_key)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
 pub async fn revoke_emergency_contact(pool: &PgPool, contact_id: Uuid) -> Result<()> {
     sqlx::query(
         r#"
@@ -533,6 +1619,24 @@ pub async fn revoke_emergency_contact(pool: &PgPool, contact_id: Uuid) -> Result
     Ok(())
 }
 
+/// Revoke every contact relation where the user is someone else's contact
+pub async fn revoke_emergency_contacts_for_contact_user(
+    executor: impl PgExecutor<'_>,
+    contact_user_id: Uuid,
+) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE emergency_contacts SET status = 'revoked', contact_user_id = NULL
+        WHERE contact_user_id = $1
+        "#,
+    )
+    .bind(contact_user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
 pub async fn delete_emergency_contact(pool: &PgPool, contact_id: Uuid) -> Result<()> {
     sqlx::query(
         r#"
@@ -668,6 +1772,45 @@ pub async fn expire_pending_access_requests(pool: &PgPool) -> Result<u64> {
     Ok(result.rows_affected())
 }
 
+/// Pending requests past their waiting period, as (request, contact, owner) ids
+pub async fn get_due_access_requests(pool: &PgPool) -> Result<Vec<(Uuid, Uuid, Uuid)>> {
+    let due = sqlx::query_as::<_, (Uuid, Uuid, Uuid)>(
+        r#"
+        SELECT r.id, r.emergency_contact_id, c.user_id
+        FROM emergency_access_requests r
+        JOIN emergency_contacts c ON c.id = r.emergency_contact_id
+        WHERE r.status = 'pending' AND r.waiting_period_ends_at <= NOW()
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(due)
+}
+
+/// Approve a request whose waiting period is over, if it's still pending
+///
+/// The vault key the owner sealed for the contact goes with the grant; it's
+/// left empty if the owner's client hasn't sealed one yet. Returns false when
+/// it was already answered, so concurrent callers don't both log and notify.
+pub async fn approve_due_access_request(pool: &PgPool, request_id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE emergency_access_requests r
+        SET status = 'approved', approved_at = NOW(),
+            vault_key_encrypted = COALESCE(c.vault_key_encrypted, '')
+        FROM emergency_contacts c
+        WHERE r.id = $1 AND c.id = r.emergency_contact_id
+            AND r.status = 'pending' AND r.waiting_period_ends_at <= NOW()
+        "#,
+    )
+    .bind(request_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
 // ============ Emergency Access Log Queries ============
 
 pub async fn create_emergency_access_log(
@@ -804,3 +1947,21 @@ pub async fn get_commands_for_user(
 
     Ok(rows.into_iter().map(RemoteCommand::from).collect())
 }
+
+// ============ Background Job Queries ============
+
+/// Take a job's advisory lock for the rest of the transaction
+///
+/// Returns false if another server instance holds it.
+pub async fn try_job_lock(conn: &mut PgConnection, lock_id: i64) -> Result<bool> {
+    let locked = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT pg_try_advisory_xact_lock($1)
+        "#,
+    )
+    .bind(lock_id)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(locked)
+}
@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -39,6 +39,12 @@ pub enum AppError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    #[error("Unprocessable request: {0}")]
+    Unprocessable(String),
+
+    #[error("Resync required")]
+    ResyncRequired,
+
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 
@@ -47,6 +53,24 @@ pub enum AppError {
 
     #[error("Blob storage error: {0}")]
     BlobStorage(String),
+
+    #[error("Email address not verified")]
+    EmailNotVerified,
+
+    #[error("Mail error: {0}")]
+    Mail(String),
+
+    #[error("Two-factor code required")]
+    TwoFactorRequired,
+
+    #[error("Invalid two-factor code")]
+    InvalidTwoFactorCode,
+
+    #[error("Device not approved")]
+    DeviceNotApproved,
+
+    #[error("Too many failed attempts, retry in {retry_after}s")]
+    AccountLocked { retry_after: i64 },
 }
 
 impl IntoResponse for AppError {
@@ -66,6 +90,11 @@ impl IntoResponse for AppError {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
             AppError::Conflict(msg) => (StatusCode::CONFLICT, msg.clone()),
+            AppError::Unprocessable(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg.clone()),
+            AppError::ResyncRequired => (
+                StatusCode::GONE,
+                "Deletions since this version were pruned; pull from version 0".to_string(),
+            ),
             AppError::Database(e) => {
                 tracing::error!("Database error: {:?}", e);
                 (
@@ -87,12 +116,44 @@ impl IntoResponse for AppError {
                     "Storage error".to_string(),
                 )
             }
+            AppError::EmailNotVerified => (
+                StatusCode::FORBIDDEN,
+                "Email address not verified".to_string(),
+            ),
+            AppError::TwoFactorRequired => (
+                StatusCode::UNAUTHORIZED,
+                "Two-factor code required".to_string(),
+            ),
+            AppError::InvalidTwoFactorCode => (
+                StatusCode::UNAUTHORIZED,
+                "Invalid two-factor code".to_string(),
+            ),
+            AppError::DeviceNotApproved => {
+                (StatusCode::FORBIDDEN, "Device not approved".to_string())
+            }
+            AppError::AccountLocked { .. } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Too many failed attempts, try again later".to_string(),
+            ),
+            AppError::Mail(msg) => {
+                tracing::error!("Mail error: {}", msg);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to send email".to_string(),
+                )
+            }
         };
 
         let body = Json(json!({
             "error": error_message,
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let AppError::AccountLocked { retry_after } = self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
     }
 }
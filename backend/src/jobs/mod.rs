@@ -0,0 +1,240 @@
+//! Scheduled background jobs
+//!
+//! Each job runs in its own task on a fixed interval, with random jitter so
+//! several server instances don't all fire at once. A run holds a Postgres
+//! advisory lock for the job, so only one instance does the work at a time;
+//! the others skip that round. Outcomes are counted in `JobMetrics`.
+
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use futures_util::future::BoxFuture;
+use rand::Rng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{api, db, AppError, AppState, Result};
+
+/// Future returned by a job, resolving to the number of records it handled
+pub type JobFuture<'a> = BoxFuture<'a, Result<u64>>;
+
+/// Largest share of the interval added as jitter before each run
+const JITTER_FRACTION: f64 = 0.1;
+
+/// A named task run on an interval
+pub struct Job {
+    pub name: &'static str,
+    pub interval: Duration,
+    pub run: fn(&AppState) -> JobFuture<'_>,
+}
+
+/// The server's cleanup jobs
+pub fn default_jobs() -> Vec<Job> {
+    const MINUTE: Duration = Duration::from_secs(60);
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    vec![
+        Job {
+            name: "purge_deleted_accounts",
+            interval: HOUR,
+            run: |state| Box::pin(api::auth::purge_due_accounts(state)),
+        },
+        Job {
+            name: "purge_unverified_accounts",
+            interval: HOUR,
+            run: |state| Box::pin(api::auth::purge_unverified_accounts(state)),
+        },
+        Job {
+            name: "delete_expired_refresh_tokens",
+            interval: HOUR,
+            run: |state| Box::pin(db::delete_expired_refresh_tokens(&state.db)),
+        },
+        Job {
+            name: "expire_auth_requests",
+            interval: 5 * MINUTE,
+            run: |state| Box::pin(db::expire_auth_requests(&state.db)),
+        },
+        Job {
+            name: "approve_due_emergency_requests",
+            interval: 5 * MINUTE,
+            run: |state| Box::pin(api::emergency::approve_due_access_requests(state)),
+        },
+        Job {
+            name: "collect_superseded_blobs",
+            interval: HOUR,
+            run: |state| Box::pin(api::sync::collect_superseded_blobs(state)),
+        },
+        Job {
+            name: "prune_tombstones",
+            interval: 24 * HOUR,
+            run: |state| Box::pin(api::sync::prune_tombstones(state)),
+        },
+    ]
+}
+
+/// Counters for one job
+#[derive(Debug, Default)]
+pub struct JobMetrics {
+    runs: AtomicU64,
+    failures: AtomicU64,
+    /// Rounds skipped because another instance held the lock
+    skipped: AtomicU64,
+    processed: AtomicU64,
+    last_run_at: AtomicI64,
+    last_duration_ms: AtomicU64,
+}
+
+/// Point-in-time copy of a job's metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct JobMetricsSnapshot {
+    pub name: &'static str,
+    pub runs: u64,
+    pub failures: u64,
+    pub skipped: u64,
+    pub processed: u64,
+    /// Unix timestamp of the last completed run, 0 if none yet
+    pub last_run_at: i64,
+    pub last_duration_ms: u64,
+}
+
+/// Runs jobs on their schedules and tracks how they went
+pub struct JobRunner {
+    state: AppState,
+    jobs: Vec<(Job, JobMetrics)>,
+}
+
+impl JobRunner {
+    pub fn new(state: AppState, jobs: Vec<Job>) -> Self {
+        Self {
+            state,
+            jobs: jobs
+                .into_iter()
+                .map(|job| (job, JobMetrics::default()))
+                .collect(),
+        }
+    }
+
+    /// Start one task per job
+    pub fn spawn(self: &Arc<Self>) {
+        for index in 0..self.jobs.len() {
+            let runner = Arc::clone(self);
+            tokio::spawn(async move {
+                // First run shortly after startup, then on the interval
+                let interval = runner.jobs[index].0.interval;
+                tokio::time::sleep(jitter(interval)).await;
+                loop {
+                    // Failures are logged and counted; the next round retries
+                    let _ = runner.run_index(index).await;
+                    tokio::time::sleep(interval + jitter(interval)).await;
+                }
+            });
+        }
+    }
+
+    /// Run a job now, outside its schedule
+    ///
+    /// Returns how many records it handled, or `None` if another instance
+    /// held its lock.
+    pub async fn run(&self, name: &str) -> Result<Option<u64>> {
+        let index = self
+            .jobs
+            .iter()
+            .position(|(job, _)| job.name == name)
+            .ok_or_else(|| AppError::NotFound(format!("Job {}", name)))?;
+        self.run_index(index).await
+    }
+
+    /// Metrics of every job
+    pub fn metrics(&self) -> Vec<JobMetricsSnapshot> {
+        self.jobs
+            .iter()
+            .map(|(job, metrics)| JobMetricsSnapshot {
+                name: job.name,
+                runs: metrics.runs.load(Ordering::Relaxed),
+                failures: metrics.failures.load(Ordering::Relaxed),
+                skipped: metrics.skipped.load(Ordering::Relaxed),
+                processed: metrics.processed.load(Ordering::Relaxed),
+                last_run_at: metrics.last_run_at.load(Ordering::Relaxed),
+                last_duration_ms: metrics.last_duration_ms.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    async fn run_index(&self, index: usize) -> Result<Option<u64>> {
+        let (job, metrics) = &self.jobs[index];
+        let started = Instant::now();
+        let result = run_locked(&self.state, job).await;
+
+        match &result {
+            Ok(None) => {
+                metrics.skipped.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!("Job {} is running elsewhere; skipped", job.name);
+                return result;
+            }
+            Ok(Some(processed)) => {
+                metrics.processed.fetch_add(*processed, Ordering::Relaxed);
+                if *processed > 0 {
+                    tracing::info!("Job {} handled {} records", job.name, processed);
+                }
+            }
+            Err(e) => {
+                metrics.failures.fetch_add(1, Ordering::Relaxed);
+                tracing::error!("Job {} failed: {}", job.name, e);
+            }
+        }
+        metrics.runs.fetch_add(1, Ordering::Relaxed);
+        metrics
+            .last_run_at
+            .store(Utc::now().timestamp(), Ordering::Relaxed);
+        metrics
+            .last_duration_ms
+            .store(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+
+        result
+    }
+}
+
+/// Run a job under its advisory lock, which the transaction releases
+async fn run_locked(state: &AppState, job: &Job) -> Result<Option<u64>> {
+    let mut lock = state.db.begin().await?;
+    if !db::try_job_lock(&mut lock, lock_id(job.name)).await? {
+        return Ok(None);
+    }
+    let processed = (job.run)(state).await?;
+    lock.commit().await?;
+    Ok(Some(processed))
+}
+
+/// Advisory lock key for a job, stable across builds and instances
+fn lock_id(name: &str) -> i64 {
+    let digest = Sha256::digest(name.as_bytes());
+    i64::from_be_bytes(digest[..8].try_into().expect("digest is 32 bytes"))
+}
+
+fn jitter(interval: Duration) -> Duration {
+    let max = interval.mul_f64(JITTER_FRACTION);
+    Duration::from_millis(rand::thread_rng().gen_range(0..=max.as_millis() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_ids_are_distinct() {
+        let ids: std::collections::HashSet<i64> =
+            default_jobs().iter().map(|job| lock_id(job.name)).collect();
+        assert_eq!(ids.len(), default_jobs().len());
+        assert_eq!(lock_id("prune_tombstones"), lock_id("prune_tombstones"));
+    }
+
+    #[test]
+    fn test_jitter_bounds() {
+        let interval = Duration::from_secs(600);
+        for _ in 0..100 {
+            assert!(jitter(interval) <= Duration::from_secs(60));
+        }
+    }
+}
@@ -7,6 +7,8 @@ pub mod auth;
 pub mod blob;
 pub mod db;
 pub mod error;
+pub mod jobs;
+pub mod mail;
 pub mod sync;
 
 pub use error::{AppError, Result};
@@ -20,6 +22,12 @@ pub struct AppState {
     pub db: sqlx::PgPool,
     pub jwt_secret: String,
     pub blob_storage: Option<Arc<blob::BlobStorage>>,
+    /// Outgoing email; without it new accounts skip email verification
+    pub mailer: Option<Arc<mail::Mailer>>,
+    /// WebAuthn relying party for passkey second factors
+    pub webauthn: Option<Arc<webauthn_rs::Webauthn>>,
+    /// Bearer token for the admin API; without it the admin API is disabled
+    pub admin_token: Option<String>,
     /// Broadcast channel for real-time sync notifications
     pub sync_tx: broadcast::Sender<sync::SyncNotification>,
 }
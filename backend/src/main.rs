@@ -8,7 +8,7 @@ use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use keydrop_backend::{api, blob, AppState};
+use keydrop_backend::{api, blob, jobs, mail, AppState};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -41,6 +41,18 @@ async fn main() -> anyhow::Result<()> {
     // Initialize blob storage
     let blob_storage = Arc::new(blob::BlobStorage::new().await?);
 
+    // Initialize email delivery
+    let mailer = mail::Mailer::from_env()?.map(Arc::new);
+    if mailer.is_none() {
+        tracing::warn!("SMTP_URL not set; email verification is disabled");
+    }
+
+    // WebAuthn relying party; the origin is where the web client is served
+    let rp_id = std::env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_string());
+    let rp_origin =
+        std::env::var("WEBAUTHN_ORIGIN").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let webauthn = Arc::new(api::webauthn::build_webauthn(&rp_id, &rp_origin)?);
+
     // Create broadcast channel for sync notifications (capacity 100)
     let (sync_tx, _) = broadcast::channel(100);
 
@@ -48,13 +60,24 @@ async fn main() -> anyhow::Result<()> {
     let jwt_secret =
         std::env::var("JWT_SECRET").unwrap_or_else(|_| "development-secret-change-me".to_string());
 
+    // Admin API token (maintenance endpoints); unset disables them
+    let admin_token = std::env::var("ADMIN_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
+
     let state = AppState {
         db,
         jwt_secret,
         blob_storage: Some(blob_storage),
+        mailer,
+        webauthn: Some(webauthn),
+        admin_token,
         sync_tx,
     };
 
+    // Cleanup jobs: account purges, expiry of tokens and requests, tombstones
+    Arc::new(jobs::JobRunner::new(state.clone(), jobs::default_jobs())).spawn();
+
     // Build router
     let app = Router::new()
         .nest("/api/v1", api::router())
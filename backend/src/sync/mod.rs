@@ -42,6 +42,18 @@ pub enum SyncNotificationType {
     RemoteLockCommand,
     /// Remote wipe command issued
     RemoteWipeCommand,
+    /// Suspicious account activity, e.g. a stolen refresh token was replayed
+    SecurityAlert,
+    /// Master password changed; other devices must sign in again
+    AuthKeyChanged,
+    /// Account deleted or scheduled for deletion; devices must sign out
+    AccountDeleted,
+    /// Email address confirmed; sync is now allowed
+    EmailVerified,
+    /// A new device signed in and waits for approval
+    DeviceApprovalRequested,
+    /// A pending device was approved and may sync
+    DeviceApproved,
 }
 
 /// Item change to be synced
@@ -88,4 +100,6 @@ pub struct SyncPullResponse {
     pub items: Vec<SyncItem>,
     /// Whether there are more items to pull
     pub has_more: bool,
+    /// Pass back as `cursor` to fetch the next page; set when `has_more`
+    pub next_cursor: Option<i64>,
 }
@@ -7,7 +7,12 @@ use axum::{
 use serde_json::{json, Value};
 use tower::ServiceExt;
 
-use common::{create_test_router, random_email};
+use common::{create_test_router, create_test_state, random_email};
+use keydrop_backend::{
+    api,
+    mail::{Mailer, SentMail},
+};
+use std::sync::Arc;
 
 /// Helper to make JSON request
 fn json_request(method: Method, uri: &str, body: Value) -> Request<Body> {
@@ -240,6 +245,664 @@ async fn test_refresh_token() {
     assert!(json.get("refresh_token").is_some());
 }
 
+#[tokio::test]
+async fn test_refresh_token_reuse_revokes_family() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": email,
+            "auth_key": "dGVzdF9hdXRoX2tleQ==",
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Test Device",
+            "device_type": "desktop"
+        }),
+    );
+
+    let register_response = router.clone().oneshot(register_req).await.unwrap();
+    let body = axum::body::to_bytes(register_response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let first_token = json["refresh_token"].as_str().unwrap().to_string();
+
+    let refresh = |token: String| {
+        json_request(
+            Method::POST,
+            "/api/v1/auth/refresh",
+            json!({ "refresh_token": token }),
+        )
+    };
+
+    // Rotate once
+    let response = router
+        .clone()
+        .oneshot(refresh(first_token.clone()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let second_token = json["refresh_token"].as_str().unwrap().to_string();
+    assert_ne!(first_token, second_token);
+
+    // Replaying the rotated token is rejected...
+    let response = router.clone().oneshot(refresh(first_token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // ...and revokes the token it was rotated into
+    let response = router.oneshot(refresh(second_token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+/// Helper to make an authenticated JSON request
+fn auth_json_request(method: Method, uri: &str, body: Value, token: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::from(serde_json::to_string(&body).unwrap()))
+        .unwrap()
+}
+
+async fn response_json(response: axum::response::Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_change_auth_key() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+    let old_key = "dGVzdF9hdXRoX2tleQ==";
+    let new_key = "bmV3X2F1dGhfa2V5";
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": email,
+            "auth_key": old_key,
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Device 1",
+            "device_type": "desktop"
+        }),
+    );
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    let access_token = json["access_token"].as_str().unwrap().to_string();
+
+    let login = |auth_key: &str| {
+        json_request(
+            Method::POST,
+            "/api/v1/auth/login",
+            json!({
+                "email": email,
+                "auth_key": auth_key,
+                "device_name": "Device 2",
+                "device_type": "android"
+            }),
+        )
+    };
+    let json = response_json(router.clone().oneshot(login(old_key)).await.unwrap()).await;
+    let other_refresh_token = json["refresh_token"].as_str().unwrap().to_string();
+
+    // One item on the server, encrypted with the old key
+    let item_id = uuid::Uuid::new_v4().to_string();
+    let push_req = auth_json_request(
+        Method::POST,
+        "/api/v1/sync/push",
+        json!({
+            "base_version": 1,
+            "items": [{
+                "id": item_id,
+                "encrypted_data": "b2xkX2tleV9kYXRh",
+                "version": 0,
+                "is_deleted": false,
+                "modified_at": 1704067200
+            }]
+        }),
+        &access_token,
+    );
+    let json = response_json(router.clone().oneshot(push_req).await.unwrap()).await;
+    let base_version = json["new_version"].as_i64().unwrap();
+
+    let change = |old: &str, items: Value| {
+        auth_json_request(
+            Method::POST,
+            "/api/v1/auth/change-auth-key",
+            json!({
+                "old_auth_key": old,
+                "new_auth_key": new_key,
+                "new_salt": "bmV3X3NhbHQ=",
+                "base_version": base_version,
+                "items": items
+            }),
+            &access_token,
+        )
+    };
+    let items = json!([{
+        "id": item_id,
+        "encrypted_data": "bmV3X2tleV9kYXRh",
+        "version": base_version,
+        "is_deleted": false,
+        "modified_at": 1704067300
+    }]);
+
+    // Wrong old key, or an item left out, changes nothing
+    let response = router
+        .clone()
+        .oneshot(change("d3Jvbmc=", items.clone()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = router
+        .clone()
+        .oneshot(change(old_key, json!([])))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = router
+        .clone()
+        .oneshot(change(old_key, items))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response_json(response).await;
+    assert_eq!(json["new_version"].as_i64().unwrap(), base_version + 1);
+
+    // Only the new key signs in, and gets the new salt
+    let response = router.clone().oneshot(login(old_key)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = router.clone().oneshot(login(new_key)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response_json(response).await["salt"], "bmV3X3NhbHQ=");
+
+    // The other device's session is gone
+    let refresh_req = json_request(
+        Method::POST,
+        "/api/v1/auth/refresh",
+        json!({ "refresh_token": other_refresh_token }),
+    );
+    let response = router.clone().oneshot(refresh_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // The re-encrypted item replaced the old one
+    let pull_req = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/api/v1/sync/pull?since_version={}", base_version))
+        .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    let json = response_json(router.oneshot(pull_req).await.unwrap()).await;
+    let items = json["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["encrypted_data"], "bmV3X2tleV9kYXRh");
+}
+
+#[tokio::test]
+async fn test_delete_account() {
+    let (router, pool) = create_test_router().await;
+    let email = random_email();
+    let auth_key = "dGVzdF9hdXRoX2tleQ==";
+
+    let login = || {
+        json_request(
+            Method::POST,
+            "/api/v1/auth/login",
+            json!({
+                "email": email,
+                "auth_key": auth_key,
+                "device_name": "Test Device",
+                "device_type": "desktop"
+            }),
+        )
+    };
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": email,
+            "auth_key": auth_key,
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Test Device",
+            "device_type": "desktop"
+        }),
+    );
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    let user_id: uuid::Uuid = json["user_id"].as_str().unwrap().parse().unwrap();
+    let access_token = json["access_token"].as_str().unwrap().to_string();
+    let refresh_token = json["refresh_token"].as_str().unwrap().to_string();
+
+    let delete = |auth_key: &str, grace_period_days: u32| {
+        auth_json_request(
+            Method::DELETE,
+            "/api/v1/auth/account",
+            json!({ "auth_key": auth_key, "grace_period_days": grace_period_days }),
+            &access_token,
+        )
+    };
+
+    let response = router.clone().oneshot(delete("d3Jvbmc=", 0)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // With a grace period the account is only signed out everywhere
+    let response = router.clone().oneshot(delete(auth_key, 7)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response_json(response).await["scheduled_for"].is_string());
+
+    let refresh_req = json_request(
+        Method::POST,
+        "/api/v1/auth/refresh",
+        json!({ "refresh_token": refresh_token }),
+    );
+    let response = router.clone().oneshot(refresh_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Signing in again cancels the deletion
+    let response = router.clone().oneshot(login()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let scheduled: Option<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT deletion_scheduled_at FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(scheduled.is_none());
+
+    // Without one it is purged right away
+    let response = router.clone().oneshot(delete(auth_key, 0)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response_json(response).await["scheduled_for"].is_null());
+
+    let devices: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM devices WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(devices, 0);
+    let response = router.oneshot(login()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_purge_due_accounts() {
+    let (router, pool) = create_test_router().await;
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": random_email(),
+            "auth_key": "dGVzdF9hdXRoX2tleQ==",
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Test Device",
+            "device_type": "desktop"
+        }),
+    );
+    let json = response_json(router.oneshot(register_req).await.unwrap()).await;
+    let user_id: uuid::Uuid = json["user_id"].as_str().unwrap().parse().unwrap();
+
+    sqlx::query("UPDATE users SET deletion_scheduled_at = NOW() - INTERVAL '1 day' WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let state = create_test_state(pool.clone()).await;
+    let purged = api::auth::purge_due_accounts(&state).await.unwrap();
+    assert!(purged >= 1);
+
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users, 0);
+}
+
+/// Router whose state delivers email to an in-memory mailer
+async fn create_mail_test_router() -> (axum::Router, sqlx::PgPool, Arc<Mailer>) {
+    let (_, pool) = create_test_router().await;
+    let mailer = Arc::new(Mailer::in_memory());
+    let mut state = create_test_state(pool.clone()).await;
+    state.mailer = Some(mailer.clone());
+    let router = axum::Router::new()
+        .nest("/api/v1", api::router())
+        .with_state(state);
+    (router, pool, mailer)
+}
+
+fn verification_token(mail: &SentMail) -> String {
+    let start = mail.body.find("token=").unwrap() + "token=".len();
+    mail.body[start..]
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_email_verification() {
+    let (router, _pool, mailer) = create_mail_test_router().await;
+    let email = random_email();
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": email,
+            "auth_key": "dGVzdF9hdXRoX2tleQ==",
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Test Device",
+            "device_type": "desktop"
+        }),
+    );
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    assert_eq!(json["email_verified"], false);
+    let access_token = json["access_token"].as_str().unwrap().to_string();
+
+    let pull = || {
+        Request::builder()
+            .method(Method::GET)
+            .uri("/api/v1/sync/pull?since_version=0")
+            .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let verify = |token: &str| {
+        Request::builder()
+            .method(Method::GET)
+            .uri(format!("/api/v1/auth/verify-email?token={}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let resend = || {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/v1/auth/resend-verification")
+            .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let sent_to = || -> Vec<SentMail> {
+        mailer
+            .sent()
+            .into_iter()
+            .filter(|mail| mail.to == email)
+            .collect()
+    };
+
+    let response = router.clone().oneshot(pull()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // A resent link replaces the first one
+    let response = router.clone().oneshot(resend()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let sent = sent_to();
+    assert_eq!(sent.len(), 2);
+    let response = router
+        .clone()
+        .oneshot(verify(&verification_token(&sent[0])))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = router
+        .clone()
+        .oneshot(verify(&verification_token(&sent[1])))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = router.clone().oneshot(pull()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = router.oneshot(resend()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_purge_unverified_accounts() {
+    let (router, pool, _mailer) = create_mail_test_router().await;
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": random_email(),
+            "auth_key": "dGVzdF9hdXRoX2tleQ==",
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Test Device",
+            "device_type": "desktop"
+        }),
+    );
+    let json = response_json(router.oneshot(register_req).await.unwrap()).await;
+    let user_id: uuid::Uuid = json["user_id"].as_str().unwrap().parse().unwrap();
+
+    sqlx::query("UPDATE users SET created_at = NOW() - INTERVAL '8 days' WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let state = create_test_state(pool.clone()).await;
+    let purged = api::auth::purge_unverified_accounts(&state).await.unwrap();
+    assert!(purged >= 1);
+
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users, 0);
+}
+
+#[tokio::test]
+async fn test_login_reuses_device_with_client_id() {
+    let (router, pool) = create_test_router().await;
+    let email = random_email();
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": email,
+            "auth_key": "dGVzdF9hdXRoX2tleQ==",
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Desktop",
+            "device_type": "desktop",
+            "client_device_id": "desktop-3f9a"
+        }),
+    );
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    let user_id: uuid::Uuid = json["user_id"].as_str().unwrap().parse().unwrap();
+    let device_id = json["device_id"].clone();
+
+    sqlx::query("UPDATE devices SET push_token = 'push-123' WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let login = |device_name: &str, client_device_id: Option<&str>| {
+        json_request(
+            Method::POST,
+            "/api/v1/auth/login",
+            json!({
+                "email": email,
+                "auth_key": "dGVzdF9hdXRoX2tleQ==",
+                "device_name": device_name,
+                "device_type": "desktop",
+                "client_device_id": client_device_id
+            }),
+        )
+    };
+
+    // Same identifier: same device, renamed, push token kept
+    for _ in 0..2 {
+        let json = response_json(
+            router
+                .clone()
+                .oneshot(login("Work Desktop", Some("desktop-3f9a")))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(json["device_id"], device_id);
+    }
+    let (name, push_token): (String, Option<String>) =
+        sqlx::query_as("SELECT device_name, push_token FROM devices WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(name, "Work Desktop");
+    assert_eq!(push_token.as_deref(), Some("push-123"));
+
+    // Without one, or with another, a new device is registered
+    let json = response_json(router.clone().oneshot(login("Phone", None)).await.unwrap()).await;
+    assert_ne!(json["device_id"], device_id);
+    let json = response_json(
+        router
+            .clone()
+            .oneshot(login("Laptop", Some("laptop-71c2")))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_ne!(json["device_id"], device_id);
+
+    let devices: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM devices WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(devices, 3);
+
+    let response = router
+        .oneshot(login("Desktop", Some(&"x".repeat(256))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_login_throttling_and_lockout() {
+    let (router, pool) = create_test_router().await;
+    let email = random_email();
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": email,
+            "auth_key": "dGVzdF9hdXRoX2tleQ==",
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Test Device",
+            "device_type": "desktop"
+        }),
+    );
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    let token = json["access_token"].as_str().unwrap().to_string();
+    let user_id: uuid::Uuid = json["user_id"].as_str().unwrap().parse().unwrap();
+
+    let login = |auth_key: &str| {
+        json_request(
+            Method::POST,
+            "/api/v1/auth/login",
+            json!({
+                "email": email,
+                "auth_key": auth_key,
+                "device_name": "Test Device",
+                "device_type": "desktop"
+            }),
+        )
+    };
+
+    // The first few failures are free; the next one starts a backoff
+    for _ in 0..4 {
+        let response = router.clone().oneshot(login("d3Jvbmc=")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    // Even the right key is refused during the backoff
+    let response = router
+        .clone()
+        .oneshot(login("dGVzdF9hdXRoX2tleQ=="))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+    // Once it has passed, a correct key resets the count
+    sqlx::query("UPDATE users SET auth_locked_until = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = router
+        .clone()
+        .oneshot(login("dGVzdF9hdXRoX2tleQ=="))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let failed: i32 = sqlx::query_scalar("SELECT failed_auth_attempts FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(failed, 0);
+
+    // Repeated failures end in a full lockout
+    sqlx::query("UPDATE users SET failed_auth_attempts = 9 WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = router.clone().oneshot(login("d3Jvbmc=")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = router
+        .clone()
+        .oneshot(login("dGVzdF9hdXRoX2tleQ=="))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: i64 = response.headers()[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 60);
+
+    // All of it shows up in the user's security log
+    let log_req = Request::builder()
+        .method(Method::GET)
+        .uri("/api/v1/auth/security-log")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(log_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response_json(response).await;
+    let actions: Vec<&str> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions[0], "account_locked");
+    assert_eq!(actions.iter().filter(|a| **a == "auth_failed").count(), 4);
+    assert_eq!(actions.iter().filter(|a| **a == "login").count(), 1);
+}
+
 #[tokio::test]
 async fn test_refresh_token_invalid() {
     let (router, _pool) = create_test_router().await;
@@ -0,0 +1,236 @@
+//! Requests shaped like the ones the desktop `SyncClient` sends, so a route
+//! it depends on can't stop matching unnoticed
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use common::{create_test_router, random_email};
+
+/// Send a request to `/api/v1{path}`, as `SyncClient::url` builds it
+async fn client_request(
+    router: &Router,
+    method: Method,
+    path: &str,
+    token: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let builder = Request::builder()
+        .method(method)
+        .uri(format!("/api/v1{}", path))
+        .header(header::AUTHORIZATION, format!("Bearer {}", token));
+    let request = match body {
+        Some(body) => builder
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    }
+    .unwrap();
+
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Sign in on a device, registering the account first if `register` is set
+///
+/// Returns the access token and device ID.
+async fn sign_in(
+    router: &Router,
+    email: &str,
+    device_name: &str,
+    register: bool,
+) -> (String, String) {
+    let (path, body) = if register {
+        (
+            "/api/v1/auth/register",
+            json!({
+                "email": email,
+                "auth_key": "dGVzdF9hdXRoX2tleQ==",
+                "salt": "dGVzdF9zYWx0",
+                "device_name": device_name,
+                "device_type": "desktop"
+            }),
+        )
+    } else {
+        (
+            "/api/v1/auth/login",
+            json!({
+                "email": email,
+                "auth_key": "dGVzdF9hdXRoX2tleQ==",
+                "device_name": device_name,
+                "device_type": "desktop"
+            }),
+        )
+    };
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(path)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    (
+        json["access_token"].as_str().unwrap().to_string(),
+        json["device_id"].as_str().unwrap().to_string(),
+    )
+}
+
+#[tokio::test]
+async fn test_device_command_routes() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+    let (token1, _) = sign_in(&router, &email, "Desktop", true).await;
+    let (token2, device_id2) = sign_in(&router, &email, "Laptop", false).await;
+
+    let (status, _) = client_request(
+        &router,
+        Method::POST,
+        &format!("/devices/{}/lock", device_id2),
+        &token1,
+        Some(json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // SyncClient::pending_commands
+    let (status, commands) =
+        client_request(&router, Method::GET, "/devices/commands", &token2, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let command_id = commands[0]["id"].as_str().unwrap();
+
+    // SyncClient::ack_command
+    let (status, _) = client_request(
+        &router,
+        Method::POST,
+        &format!("/devices/commands/{}/ack", command_id),
+        &token2,
+        Some(json!({ "success": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_emergency_routes() {
+    let (router, pool) = create_test_router().await;
+    let (owner_token, _) = sign_in(&router, &random_email(), "Desktop", true).await;
+    let contact_email = random_email();
+    let (contact_token, _) = sign_in(&router, &contact_email, "Desktop", true).await;
+
+    // SyncClient::add_emergency_contact
+    let (status, contact) = client_request(
+        &router,
+        Method::POST,
+        "/emergency/contacts",
+        &owner_token,
+        Some(json!({ "email": contact_email, "waiting_period_hours": 24 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let contact_id = contact["id"].as_str().unwrap();
+
+    // The invitation token is normally delivered by email
+    let invitation_token: String =
+        sqlx::query_scalar("SELECT invitation_token FROM emergency_contacts WHERE id = $1::uuid")
+            .bind(contact_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    // SyncClient::accept_emergency_invitation
+    let (status, _) = client_request(
+        &router,
+        Method::POST,
+        &format!("/emergency/contacts/{}/accept", contact_id),
+        &contact_token,
+        Some(json!({
+            "token": invitation_token,
+            "public_key": "CQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQk="
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // SyncClient::share_emergency_vault_key
+    let (status, _) = client_request(
+        &router,
+        Method::PUT,
+        &format!("/emergency/contacts/{}/key", contact_id),
+        &owner_token,
+        Some(json!({ "vault_key_encrypted": "c2VhbGVkIGtleQ==" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // SyncClient::request_emergency_access
+    let (status, _) = client_request(
+        &router,
+        Method::POST,
+        "/emergency/request",
+        &contact_token,
+        Some(json!({ "emergency_contact_id": contact_id, "reason": "Locked out" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // SyncClient::emergency_requests
+    let (status, requests) = client_request(
+        &router,
+        Method::GET,
+        "/emergency/requests",
+        &owner_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let request_id = requests[0]["request_id"].as_str().unwrap();
+
+    // SyncClient::deny_emergency_request
+    let (status, _) = client_request(
+        &router,
+        Method::POST,
+        &format!("/emergency/requests/{}/deny", request_id),
+        &owner_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // SyncClient::remove_emergency_contact
+    let (status, _) = client_request(
+        &router,
+        Method::DELETE,
+        &format!("/emergency/contacts/{}", contact_id),
+        &owner_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, contacts) = client_request(
+        &router,
+        Method::GET,
+        "/emergency/contacts",
+        &owner_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(contacts.as_array().unwrap().is_empty());
+}
@@ -12,6 +12,10 @@ pub static TEST_DATABASE_URL: Lazy<String> = Lazy::new(|| {
         .expect("TEST_DATABASE_URL or DATABASE_URL must be set")
 });
 
+/// Admin API token configured in test state
+#[allow(dead_code)]
+pub const TEST_ADMIN_TOKEN: &str = "test_admin_token";
+
 /// Create a test database pool
 pub async fn create_test_pool() -> PgPool {
     PgPoolOptions::new()
@@ -30,6 +34,7 @@ pub async fn run_migrations(pool: &PgPool) {
 }
 
 /// Clean up test data (call before/after tests)
+#[allow(dead_code)]
 pub async fn cleanup_test_data(pool: &PgPool) {
     // Delete in order respecting foreign keys
     let tables = [
@@ -64,10 +69,16 @@ pub async fn create_test_state(pool: PgPool) -> AppState {
         blob_storage: Some(std::sync::Arc::new(
             keydrop_backend::blob::BlobStorage::in_memory(),
         )),
+        mailer: None,
+        admin_token: Some(TEST_ADMIN_TOKEN.to_string()),
+        webauthn: Some(std::sync::Arc::new(
+            api::webauthn::build_webauthn("localhost", "http://localhost:3000").unwrap(),
+        )),
     }
 }
 
 /// Create a test router
+#[allow(dead_code)]
 pub async fn create_test_router() -> (Router, PgPool) {
     let pool = create_test_pool().await;
     run_migrations(&pool).await;
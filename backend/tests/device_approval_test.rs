@@ -0,0 +1,306 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use common::{create_test_router, random_email};
+
+const AUTH_KEY: &str = "dGVzdF9hdXRoX2tleQ==";
+
+fn json_request(method: Method, uri: &str, body: Value, token: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    builder
+        .body(Body::from(serde_json::to_string(&body).unwrap()))
+        .unwrap()
+}
+
+fn get_request(uri: &str, token: &str) -> Request<Body> {
+    Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn response_json(response: axum::response::Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn login_request(email: &str, device_name: &str) -> Request<Body> {
+    json_request(
+        Method::POST,
+        "/api/v1/auth/login",
+        json!({
+            "email": email,
+            "auth_key": AUTH_KEY,
+            "device_name": device_name,
+            "device_type": "android"
+        }),
+        None,
+    )
+}
+
+#[tokio::test]
+async fn test_new_device_approval() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": email,
+            "auth_key": AUTH_KEY,
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Laptop",
+            "device_type": "desktop"
+        }),
+        None,
+    );
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    let laptop_token = json["access_token"].as_str().unwrap().to_string();
+
+    // Without the policy a new device can sync right away
+    let json = response_json(
+        router
+            .clone()
+            .oneshot(login_request(&email, "Tablet"))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(json["device_approved"], true);
+
+    let policy_req = json_request(
+        Method::PUT,
+        "/api/v1/devices/approval-policy",
+        json!({ "require_approval": true }),
+        Some(&laptop_token),
+    );
+    let response = router.clone().oneshot(policy_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Now it gets tokens but no sync access until approved
+    let json = response_json(
+        router
+            .clone()
+            .oneshot(login_request(&email, "Phone"))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(json["device_approved"], false);
+    let phone_token = json["access_token"].as_str().unwrap().to_string();
+    let phone_id = json["device_id"].as_str().unwrap().to_string();
+
+    let response = router
+        .clone()
+        .oneshot(get_request("/api/v1/sync/pull", &phone_token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // A pending device can't approve itself
+    let approve = |token: &str, device_id: &str, approved: bool| {
+        json_request(
+            Method::POST,
+            &format!("/api/v1/devices/{}/approve", device_id),
+            json!({ "approved": approved }),
+            Some(token),
+        )
+    };
+    let response = router
+        .clone()
+        .oneshot(approve(&phone_token, &phone_id, true))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // The laptop sees the pending device and its approval request
+    let json = response_json(
+        router
+            .clone()
+            .oneshot(get_request("/api/v1/devices", &laptop_token))
+            .await
+            .unwrap(),
+    )
+    .await;
+    let phone = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["id"] == phone_id.as_str())
+        .unwrap();
+    assert_eq!(phone["approved"], false);
+
+    let json = response_json(
+        router
+            .clone()
+            .oneshot(get_request(
+                "/api/v1/devices/auth-requests/pending",
+                &laptop_token,
+            ))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(json[0]["purpose"], "device_approval");
+    assert_eq!(json[0]["requester_device_id"], phone_id.as_str());
+
+    let response = router
+        .clone()
+        .oneshot(approve(&laptop_token, &phone_id, true))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = router
+        .clone()
+        .oneshot(get_request("/api/v1/sync/pull", &phone_token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A rejected device is removed
+    let json = response_json(
+        router
+            .clone()
+            .oneshot(login_request(&email, "Unknown"))
+            .await
+            .unwrap(),
+    )
+    .await;
+    let unknown_token = json["access_token"].as_str().unwrap().to_string();
+    let unknown_id = json["device_id"].as_str().unwrap().to_string();
+
+    let response = router
+        .clone()
+        .oneshot(approve(&phone_token, &unknown_id, false))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = router
+        .oneshot(get_request("/api/v1/sync/pull", &unknown_token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_reused_client_device_id_needs_device_secret() {
+    let (router, pool) = create_test_router().await;
+    let email = random_email();
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": email,
+            "auth_key": AUTH_KEY,
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Laptop",
+            "device_type": "desktop",
+            "client_device_id": "laptop-3f9a"
+        }),
+        None,
+    );
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    let laptop_token = json["access_token"].as_str().unwrap().to_string();
+    let laptop_id = json["device_id"].clone();
+    let device_secret = json["device_secret"].as_str().unwrap().to_string();
+
+    let policy_req = json_request(
+        Method::PUT,
+        "/api/v1/devices/approval-policy",
+        json!({ "require_approval": true }),
+        Some(&laptop_token),
+    );
+    let response = router.clone().oneshot(policy_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let login = |device_secret: Option<&str>| {
+        json_request(
+            Method::POST,
+            "/api/v1/auth/login",
+            json!({
+                "email": email,
+                "auth_key": AUTH_KEY,
+                "device_name": "Laptop",
+                "device_type": "desktop",
+                "client_device_id": "laptop-3f9a",
+                "device_secret": device_secret
+            }),
+            None,
+        )
+    };
+
+    // With the secret the device keeps its approval, and no new secret is issued
+    let json = response_json(
+        router
+            .clone()
+            .oneshot(login(Some(&device_secret)))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(json["device_id"], laptop_id);
+    assert_eq!(json["device_approved"], true);
+    assert!(json["device_secret"].is_null());
+
+    // The identifier alone is no proof: the device is pending again
+    let json = response_json(router.clone().oneshot(login(None)).await.unwrap()).await;
+    assert_eq!(json["device_id"], laptop_id);
+    assert_eq!(json["device_approved"], false);
+    assert!(json["device_secret"].is_null());
+    let intruder_token = json["access_token"].as_str().unwrap().to_string();
+
+    let approved_at: Option<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT approved_at FROM devices WHERE id = $1")
+            .bind(laptop_id.as_str().unwrap().parse::<uuid::Uuid>().unwrap())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(approved_at.is_none());
+    let response = router
+        .clone()
+        .oneshot(get_request("/api/v1/sync/pull", &intruder_token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let json = response_json(
+        router
+            .clone()
+            .oneshot(login(Some("not-the-secret")))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(json["device_approved"], false);
+
+    // The device holding the secret gets back in, having nobody else to ask
+    let json = response_json(
+        router
+            .clone()
+            .oneshot(login(Some(&device_secret)))
+            .await
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(json["device_approved"], true);
+}
@@ -43,6 +43,8 @@ fn auth_json_request(method: Method, uri: &str, body: Value, token: &str) -> Req
 #[tokio::test]
 async fn test_full_user_journey() {
     let (router, _pool) = create_test_router().await;
+    let item1 = uuid::Uuid::new_v4();
+    let item2 = uuid::Uuid::new_v4();
 
     // 1. Register new user
     let email = random_email();
@@ -76,14 +78,14 @@ async fn test_full_user_journey() {
             "base_version": 1,
             "items": [
                 {
-                    "id": "00000000-0000-0000-0000-000000000001",
+                    "id": item1,
                     "encrypted_data": "ZW5jcnlwdGVkX2xvZ2lu",
                     "version": 0,
                     "is_deleted": false,
                     "modified_at": 1704067200
                 },
                 {
-                    "id": "00000000-0000-0000-0000-000000000002",
+                    "id": item2,
                     "encrypted_data": "ZW5jcnlwdGVkX2xvZ2luXzI=",
                     "version": 0,
                     "is_deleted": false,
@@ -97,6 +99,12 @@ async fn test_full_user_journey() {
     let push_response = router.clone().oneshot(push_req).await.unwrap();
     assert_eq!(push_response.status(), StatusCode::OK);
 
+    let body = axum::body::to_bytes(push_response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let synced_version = json["new_version"].as_i64().unwrap();
+
     // 3. Login on second device
     let login_req = json_request(
         Method::POST,
@@ -153,12 +161,12 @@ async fn test_full_user_journey() {
         Method::POST,
         "/api/v1/sync/push",
         json!({
-            "base_version": 2,
+            "base_version": synced_version,
             "items": [
                 {
-                    "id": "00000000-0000-0000-0000-000000000001",
+                    "id": item1,
                     "encrypted_data": "dXBkYXRlZF9sb2dpbg==",
-                    "version": 2,
+                    "version": synced_version,
                     "is_deleted": false,
                     "modified_at": 1704067300
                 }
@@ -173,7 +181,7 @@ async fn test_full_user_journey() {
     // 7. Sync on first device
     let pull_req2 = auth_request(
         Method::GET,
-        "/api/v1/sync/pull?since_version=2",
+        &format!("/api/v1/sync/pull?since_version={}", synced_version),
         &access_token,
     );
 
@@ -186,7 +194,7 @@ async fn test_full_user_journey() {
     let json: Value = serde_json::from_slice(&body).unwrap();
     let items = json["items"].as_array().unwrap();
     assert_eq!(items.len(), 1);
-    assert_eq!(items[0]["id"], "00000000-0000-0000-0000-000000000001");
+    assert_eq!(items[0]["id"], item1.to_string());
 }
 
 #[tokio::test]
@@ -297,3 +297,220 @@ async fn test_emergency_request_flow() {
     assert_eq!(contacts.len(), 1);
     assert_eq!(contacts[0]["id"], contact_id);
 }
+
+async fn response_json(response: axum::response::Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_grant_carries_sealed_vault_key() {
+    let (router, pool) = create_test_router().await;
+    let owner_token = register_user(&router, &random_email()).await;
+    let contact_email = random_email();
+    let contact_token = register_user(&router, &contact_email).await;
+    let public_key = "CQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQk=";
+    let sealed_key = "c2VhbGVkIGtleQ==";
+
+    let add_req = auth_json_request(
+        Method::POST,
+        "/api/v1/emergency/contacts",
+        json!({ "email": contact_email, "waiting_period_hours": 1 }),
+        &owner_token,
+    );
+    let json = response_json(router.clone().oneshot(add_req).await.unwrap()).await;
+    let contact_id = json["id"].as_str().unwrap().to_string();
+    let share_key = || {
+        auth_json_request(
+            Method::PUT,
+            &format!("/api/v1/emergency/contacts/{}/key", contact_id),
+            json!({ "vault_key_encrypted": sealed_key }),
+            &owner_token,
+        )
+    };
+
+    // Nothing to seal the key for until the contact accepts
+    let response = router.clone().oneshot(share_key()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let invitation_token: String =
+        sqlx::query_scalar("SELECT invitation_token FROM emergency_contacts WHERE id = $1::uuid")
+            .bind(&contact_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let accept = |public_key: &str| {
+        auth_json_request(
+            Method::POST,
+            &format!("/api/v1/emergency/contacts/{}/accept", contact_id),
+            json!({ "token": invitation_token, "public_key": public_key }),
+            &contact_token,
+        )
+    };
+    let response = router.clone().oneshot(accept("c2hvcnQ=")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = router.clone().oneshot(accept(public_key)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The owner sees the key to seal for
+    let list_req = auth_request(Method::GET, "/api/v1/emergency/contacts", &owner_token);
+    let json = response_json(router.clone().oneshot(list_req).await.unwrap()).await;
+    assert_eq!(json[0]["contact_public_key"], public_key);
+    assert_eq!(json[0]["vault_key_shared"], false);
+
+    // A grant made before the owner sealed the key carries an empty one
+    let request_access = || {
+        auth_json_request(
+            Method::POST,
+            "/api/v1/emergency/request",
+            json!({ "emergency_contact_id": contact_id }),
+            &contact_token,
+        )
+    };
+    let granted = || async {
+        sqlx::query(
+            "UPDATE emergency_access_requests SET waiting_period_ends_at = NOW()
+             WHERE emergency_contact_id = $1::uuid AND status = 'pending'",
+        )
+        .bind(&contact_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let vault_req = auth_request(Method::GET, "/api/v1/emergency/vault", &contact_token);
+        response_json(router.clone().oneshot(vault_req).await.unwrap()).await["granted_access"]
+            .as_array()
+            .unwrap()
+            .clone()
+    };
+    router.clone().oneshot(request_access()).await.unwrap();
+    let grants = granted().await;
+    assert_eq!(grants.len(), 1);
+    assert_eq!(grants[0]["vault_key_encrypted"], "");
+
+    // Sealing it afterwards hands it to that grant and later ones
+    let response = router.clone().oneshot(share_key()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(granted().await[0]["vault_key_encrypted"], sealed_key);
+
+    router.clone().oneshot(request_access()).await.unwrap();
+    let grants = granted().await;
+    assert_eq!(grants.len(), 2);
+    assert!(grants
+        .iter()
+        .all(|grant| grant["vault_key_encrypted"] == sealed_key));
+
+    let list_req = auth_request(Method::GET, "/api/v1/emergency/contacts", &owner_token);
+    let json = response_json(router.clone().oneshot(list_req).await.unwrap()).await;
+    assert_eq!(json[0]["vault_key_shared"], true);
+}
+
+#[tokio::test]
+async fn test_granted_vault_items() {
+    let (router, pool) = create_test_router().await;
+    let owner_token = register_user(&router, &random_email()).await;
+    let contact_email = random_email();
+    let contact_token = register_user(&router, &contact_email).await;
+    let outsider_token = register_user(&router, &random_email()).await;
+
+    let push_req = auth_json_request(
+        Method::POST,
+        "/api/v1/sync/push",
+        json!({
+            "base_version": 0,
+            "items": [{
+                "id": uuid::Uuid::new_v4(),
+                "encrypted_data": "b3duZXIgaXRlbQ==",
+                "version": 0,
+                "is_deleted": false,
+                "modified_at": 1704067200
+            }]
+        }),
+        &owner_token,
+    );
+    let response = router.clone().oneshot(push_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let add_req = auth_json_request(
+        Method::POST,
+        "/api/v1/emergency/contacts",
+        json!({ "email": contact_email, "waiting_period_hours": 1 }),
+        &owner_token,
+    );
+    let json = response_json(router.clone().oneshot(add_req).await.unwrap()).await;
+    let contact_id = json["id"].as_str().unwrap().to_string();
+    let invitation_token: String =
+        sqlx::query_scalar("SELECT invitation_token FROM emergency_contacts WHERE id = $1::uuid")
+            .bind(&contact_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let accept_req = auth_json_request(
+        Method::POST,
+        &format!("/api/v1/emergency/contacts/{}/accept", contact_id),
+        json!({
+            "token": invitation_token,
+            "public_key": "CQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQkJCQk="
+        }),
+        &contact_token,
+    );
+    router.clone().oneshot(accept_req).await.unwrap();
+    let request_req = auth_json_request(
+        Method::POST,
+        "/api/v1/emergency/request",
+        json!({ "emergency_contact_id": contact_id }),
+        &contact_token,
+    );
+    let json = response_json(router.clone().oneshot(request_req).await.unwrap()).await;
+    let request_id = json["request_id"].as_str().unwrap().to_string();
+    let items_uri = format!("/api/v1/emergency/vault/{}/items", request_id);
+
+    // Nothing until the request is granted
+    let response = router
+        .clone()
+        .oneshot(auth_request(Method::GET, &items_uri, &contact_token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    sqlx::query(
+        "UPDATE emergency_access_requests SET waiting_period_ends_at = NOW() WHERE id = $1::uuid",
+    )
+    .bind(&request_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let vault_req = auth_request(Method::GET, "/api/v1/emergency/vault", &contact_token);
+    router.clone().oneshot(vault_req).await.unwrap();
+
+    let response = router
+        .clone()
+        .oneshot(auth_request(Method::GET, &items_uri, &contact_token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response_json(response).await;
+    assert_eq!(json["items"].as_array().unwrap().len(), 1);
+    assert_eq!(json["items"][0]["encrypted_data"], "b3duZXIgaXRlbQ==");
+    assert_eq!(json["has_more"], false);
+
+    // Only the contact the request was granted to
+    for token in [&outsider_token, &owner_token] {
+        let response = router
+            .clone()
+            .oneshot(auth_request(Method::GET, &items_uri, token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    // The owner sees the viewing in their log
+    let logs_req = auth_request(Method::GET, "/api/v1/emergency/logs", &owner_token);
+    let json = response_json(router.clone().oneshot(logs_req).await.unwrap()).await;
+    assert!(json
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|entry| entry["action"] == "vault_viewed"));
+}
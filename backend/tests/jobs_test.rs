@@ -0,0 +1,452 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use common::{create_test_pool, create_test_state, random_email, run_migrations, TEST_ADMIN_TOKEN};
+use keydrop_backend::{
+    api,
+    jobs::{default_jobs, Job, JobRunner},
+    AppState,
+};
+
+async fn setup() -> (Router, AppState) {
+    let pool = create_test_pool().await;
+    run_migrations(&pool).await;
+    let state = create_test_state(pool).await;
+    let router = Router::new()
+        .nest("/api/v1", api::router())
+        .with_state(state.clone());
+    (router, state)
+}
+
+/// Register a user, returning the user id, device id and access token
+async fn register(router: &Router) -> (Uuid, Uuid, String) {
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/auth/register")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({
+                "email": random_email(),
+                "auth_key": "dGVzdF9hdXRoX2tleQ==",
+                "salt": "dGVzdF9zYWx0",
+                "device_name": "Test Device",
+                "device_type": "desktop"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = router.clone().oneshot(req).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    (
+        json["user_id"].as_str().unwrap().parse().unwrap(),
+        json["device_id"].as_str().unwrap().parse().unwrap(),
+        json["access_token"].as_str().unwrap().to_string(),
+    )
+}
+
+fn push_request(token: &str, base_version: i64, item: Value) -> Request<Body> {
+    Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/sync/push")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::from(
+            json!({ "base_version": base_version, "items": [item] }).to_string(),
+        ))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_cleanup_jobs() {
+    let (router, state) = setup().await;
+    let runner = JobRunner::new(state.clone(), default_jobs());
+    let (user_id, device_id, token) = register(&router).await;
+
+    // Expired refresh tokens are deleted
+    sqlx::query(
+        "UPDATE refresh_tokens SET expires_at = NOW() - INTERVAL '1 day' WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .execute(&state.db)
+    .await
+    .unwrap();
+    assert!(
+        runner
+            .run("delete_expired_refresh_tokens")
+            .await
+            .unwrap()
+            .unwrap()
+            >= 1
+    );
+    let remaining: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(remaining, 0);
+
+    // Pending auth requests past their expiry are marked expired
+    let request_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO auth_requests (id, requester_device_id, target_device_id, challenge, expires_at)
+         VALUES ($1, $2, $2, 'challenge', NOW() - INTERVAL '1 minute')",
+    )
+    .bind(request_id)
+    .bind(device_id)
+    .execute(&state.db)
+    .await
+    .unwrap();
+    assert!(runner.run("expire_auth_requests").await.unwrap().unwrap() >= 1);
+    let status: String = sqlx::query_scalar("SELECT status FROM auth_requests WHERE id = $1")
+        .bind(request_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(status, "expired");
+
+    // Emergency requests past their waiting period are granted
+    let contact_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO emergency_contacts (user_id, contact_email, status)
+         VALUES ($1, $2, 'accepted') RETURNING id",
+    )
+    .bind(user_id)
+    .bind(random_email())
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    let access_request_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO emergency_access_requests (emergency_contact_id, waiting_period_ends_at)
+         VALUES ($1, NOW() - INTERVAL '1 minute') RETURNING id",
+    )
+    .bind(contact_id)
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    let mut notifications = state.sync_tx.subscribe();
+    assert!(
+        runner
+            .run("approve_due_emergency_requests")
+            .await
+            .unwrap()
+            .unwrap()
+            >= 1
+    );
+    let status: String =
+        sqlx::query_scalar("SELECT status FROM emergency_access_requests WHERE id = $1")
+            .bind(access_request_id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(status, "approved");
+    loop {
+        let notification = notifications.recv().await.unwrap();
+        if notification.user_id == user_id {
+            break;
+        }
+    }
+
+    // Old tombstones go, with their blobs
+    let item_id = Uuid::new_v4();
+    let req = push_request(
+        &token,
+        1,
+        json!({
+            "id": item_id,
+            "encrypted_data": "ZGVsZXRlZA==",
+            "version": 0,
+            "is_deleted": true,
+            "modified_at": 1704067200
+        }),
+    );
+    router.clone().oneshot(req).await.unwrap();
+    let blob_id: String = sqlx::query_scalar(
+        "UPDATE vault_items_sync SET modified_at = NOW() - INTERVAL '100 days'
+         WHERE id = $1 RETURNING encrypted_blob_id",
+    )
+    .bind(item_id)
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    assert!(runner.run("prune_tombstones").await.unwrap().unwrap() >= 1);
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM vault_items_sync WHERE id = $1")
+        .bind(item_id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+
+    // Its blob is left to blob GC
+    sqlx::query(
+        "UPDATE superseded_blobs SET superseded_at = NOW() - INTERVAL '2 days' WHERE blob_id = $1",
+    )
+    .bind(&blob_id)
+    .execute(&state.db)
+    .await
+    .unwrap();
+    runner.run("collect_superseded_blobs").await.unwrap();
+    let blob_storage = state.blob_storage.as_ref().unwrap();
+    assert!(!blob_storage.exists(&blob_id).await.unwrap());
+
+    let metrics = runner.metrics();
+    let prune = metrics
+        .iter()
+        .find(|m| m.name == "prune_tombstones")
+        .unwrap();
+    assert_eq!(prune.runs, 1);
+    assert_eq!(prune.failures, 0);
+    assert!(prune.processed >= 1);
+    assert!(prune.last_run_at > 0);
+}
+
+#[tokio::test]
+async fn test_job_lock_skips_run() {
+    let (_router, state) = setup().await;
+    let job = Job {
+        name: "test_lock_job",
+        interval: Duration::from_secs(60),
+        run: |_| Box::pin(async { Ok(0) }),
+    };
+    let runner = JobRunner::new(state.clone(), vec![job]);
+
+    // Another instance holding the lock makes this one skip the round
+    let mut other = state.db.begin().await.unwrap();
+    let held: bool = sqlx::query_scalar(
+        "SELECT pg_try_advisory_xact_lock(('x' || substr(encode(sha256('test_lock_job'), 'hex'), 1, 16))::bit(64)::bigint)",
+    )
+    .fetch_one(&mut *other)
+    .await
+    .unwrap();
+    assert!(held);
+
+    assert_eq!(runner.run("test_lock_job").await.unwrap(), None);
+    other.rollback().await.unwrap();
+    assert_eq!(runner.run("test_lock_job").await.unwrap(), Some(0));
+
+    let metrics = runner.metrics();
+    assert_eq!(metrics[0].skipped, 1);
+    assert_eq!(metrics[0].runs, 1);
+
+    assert!(runner.run("no_such_job").await.is_err());
+}
+
+#[tokio::test]
+async fn test_blob_gc() {
+    let (router, state) = setup().await;
+    let (_, _, token) = register(&router).await;
+    let blob_storage = state.blob_storage.clone().unwrap();
+
+    let item_id = Uuid::new_v4();
+    let item = |data: &str| {
+        json!({
+            "id": item_id,
+            "encrypted_data": data,
+            "version": 0,
+            "is_deleted": false,
+            "modified_at": 1704067200
+        })
+    };
+    let blob_of_item = || {
+        sqlx::query_scalar::<_, String>(
+            "SELECT encrypted_blob_id FROM vault_items_sync WHERE id = $1",
+        )
+        .bind(item_id)
+        .fetch_one(&state.db)
+    };
+
+    router
+        .clone()
+        .oneshot(push_request(&token, 1, item("Zmlyc3Q=")))
+        .await
+        .unwrap();
+    let old_blob = blob_of_item().await.unwrap();
+    router
+        .clone()
+        .oneshot(push_request(&token, 2, item("c2Vjb25k")))
+        .await
+        .unwrap();
+    let current_blob = blob_of_item().await.unwrap();
+    assert_ne!(old_blob, current_blob);
+
+    let gc = |token: &str| {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/v1/admin/blob-gc")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = router.clone().oneshot(gc(&token)).await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    // Within the retention window the replaced blob is kept
+    router.clone().oneshot(gc(TEST_ADMIN_TOKEN)).await.unwrap();
+    assert!(blob_storage.exists(&old_blob).await.unwrap());
+
+    sqlx::query(
+        "UPDATE superseded_blobs SET superseded_at = NOW() - INTERVAL '2 days' WHERE blob_id = $1",
+    )
+    .bind(&old_blob)
+    .execute(&state.db)
+    .await
+    .unwrap();
+    let response = router.clone().oneshot(gc(TEST_ADMIN_TOKEN)).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert!(json["deleted"].as_u64().unwrap() >= 1);
+
+    assert!(!blob_storage.exists(&old_blob).await.unwrap());
+    assert!(blob_storage.exists(&current_blob).await.unwrap());
+    let tracked: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM superseded_blobs WHERE blob_id = $1")
+            .bind(&old_blob)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(tracked, 0);
+}
+
+#[tokio::test]
+async fn test_blob_gc_keeps_shared_blobs() {
+    let (router, state) = setup().await;
+    let runner = JobRunner::new(state.clone(), default_jobs());
+    let (_, _, token) = register(&router).await;
+    let blob_storage = state.blob_storage.clone().unwrap();
+
+    // Two items with the same ciphertext share a blob
+    let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+    for (base_version, id) in [(1, first), (2, second)] {
+        let req = push_request(
+            &token,
+            base_version,
+            json!({
+                "id": id,
+                "encrypted_data": "c2hhcmVk",
+                "version": 0,
+                "is_deleted": false,
+                "modified_at": 1704067200
+            }),
+        );
+        router.clone().oneshot(req).await.unwrap();
+    }
+    let shared: String =
+        sqlx::query_scalar("SELECT encrypted_blob_id FROM vault_items_sync WHERE id = $1")
+            .bind(first)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+
+    // Replacing one item supersedes the blob the other still uses
+    let req = push_request(
+        &token,
+        3,
+        json!({
+            "id": first,
+            "encrypted_data": "Y2hhbmdlZA==",
+            "version": 0,
+            "is_deleted": false,
+            "modified_at": 1704067300
+        }),
+    );
+    router.clone().oneshot(req).await.unwrap();
+    sqlx::query(
+        "UPDATE superseded_blobs SET superseded_at = NOW() - INTERVAL '2 days' WHERE blob_id = $1",
+    )
+    .bind(&shared)
+    .execute(&state.db)
+    .await
+    .unwrap();
+
+    runner.run("collect_superseded_blobs").await.unwrap();
+    assert!(blob_storage.exists(&shared).await.unwrap());
+    let tracked: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM superseded_blobs WHERE blob_id = $1")
+            .bind(&shared)
+            .fetch_one(&state.db)
+            .await
+            .unwrap();
+    assert_eq!(tracked, 0);
+}
+
+#[tokio::test]
+async fn test_pull_from_before_pruned_tombstones_needs_resync() {
+    let (router, state) = setup().await;
+    let runner = JobRunner::new(state.clone(), default_jobs());
+    let (_, _, token) = register(&router).await;
+
+    let pull = |since_version: i64| {
+        Request::builder()
+            .method(Method::GET)
+            .uri(format!("/api/v1/sync/pull?since_version={}", since_version))
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // A live item, then a deletion that gets pruned
+    let item = |is_deleted: bool| {
+        json!({
+            "id": Uuid::new_v4(),
+            "encrypted_data": "aXRlbQ==",
+            "version": 0,
+            "is_deleted": is_deleted,
+            "modified_at": 1704067200
+        })
+    };
+    router
+        .clone()
+        .oneshot(push_request(&token, 0, item(false)))
+        .await
+        .unwrap();
+    let deleted = item(true);
+    router
+        .clone()
+        .oneshot(push_request(&token, 1, deleted.clone()))
+        .await
+        .unwrap();
+    let deleted_version: i64 = sqlx::query_scalar(
+        "UPDATE vault_items_sync SET modified_at = NOW() - INTERVAL '100 days'
+         WHERE id = $1 RETURNING version",
+    )
+    .bind(deleted["id"].as_str().unwrap().parse::<Uuid>().unwrap())
+    .fetch_one(&state.db)
+    .await
+    .unwrap();
+    runner.run("prune_tombstones").await.unwrap();
+
+    // A device last synced before it would never see the deletion
+    let response = router
+        .clone()
+        .oneshot(pull(deleted_version - 1))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 410);
+
+    // Pulling from scratch, or from past the watermark, is fine
+    let response = router.clone().oneshot(pull(0)).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["items"].as_array().unwrap().len(), 1);
+
+    let response = router.clone().oneshot(pull(deleted_version)).await.unwrap();
+    assert_eq!(response.status(), 200);
+}
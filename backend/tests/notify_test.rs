@@ -0,0 +1,166 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request},
+    Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{client::IntoClientRequest, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+use tower::ServiceExt;
+
+use common::{create_test_pool, create_test_state, random_email, run_migrations};
+use keydrop_backend::{
+    api,
+    sync::{SyncNotification, SyncNotificationType},
+    AppState,
+};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Serve the API on a local port, returning its address and state
+async fn spawn_server() -> (String, Router, AppState) {
+    let pool = create_test_pool().await;
+    run_migrations(&pool).await;
+    let state = create_test_state(pool).await;
+    let router = Router::new()
+        .nest("/api/v1", api::router())
+        .with_state(state.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = router.clone();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (format!("ws://{}/api/v1/sync/notify", addr), router, state)
+}
+
+/// Register a user, returning the user id, device id and access token
+async fn register(router: &Router) -> (String, String, String) {
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/auth/register")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({
+                "email": random_email(),
+                "auth_key": "dGVzdF9hdXRoX2tleQ==",
+                "salt": "dGVzdF9zYWx0",
+                "device_name": "Test Device",
+                "device_type": "desktop"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let response = router.clone().oneshot(req).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    (
+        json["user_id"].as_str().unwrap().to_string(),
+        json["device_id"].as_str().unwrap().to_string(),
+        json["access_token"].as_str().unwrap().to_string(),
+    )
+}
+
+/// Next text frame, failing the test if none arrives in time
+async fn next_text(socket: &mut Socket) -> Option<String> {
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("timed out waiting for a frame");
+        match msg {
+            Some(Ok(Message::Text(text))) => return Some(text),
+            Some(Ok(Message::Ping(_))) | Some(Ok(Message::Pong(_))) => continue,
+            _ => return None,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_notify_authenticates_at_upgrade() {
+    let (url, router, _state) = spawn_server().await;
+    let (_, _, token) = register(&router).await;
+
+    // Authorization header
+    let mut request = url.as_str().into_client_request().unwrap();
+    request.headers_mut().insert(
+        header::AUTHORIZATION,
+        format!("Bearer {}", token).parse().unwrap(),
+    );
+    let (mut socket, _) = connect_async(request).await.unwrap();
+    assert!(next_text(&mut socket).await.unwrap().contains("connected"));
+
+    // Query parameter
+    let (mut socket, _) = connect_async(format!("{}?token={}", url, token))
+        .await
+        .unwrap();
+    assert!(next_text(&mut socket).await.unwrap().contains("connected"));
+
+    // A bad token is refused before the upgrade
+    let err = connect_async(format!("{}?token=invalid", url))
+        .await
+        .unwrap_err();
+    match err {
+        tokio_tungstenite::tungstenite::Error::Http(response) => {
+            assert_eq!(response.status(), 401)
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    // Clients sending the token as the first frame still work
+    let (mut socket, _) = connect_async(url.as_str()).await.unwrap();
+    socket
+        .send(Message::Text(json!({ "token": token }).to_string()))
+        .await
+        .unwrap();
+    assert!(next_text(&mut socket).await.unwrap().contains("connected"));
+}
+
+#[tokio::test]
+async fn test_notify_drops_removed_device() {
+    let (url, router, state) = spawn_server().await;
+    let (user_id, device_id, token) = register(&router).await;
+
+    let (mut socket, _) = connect_async(format!("{}?token={}", url, token))
+        .await
+        .unwrap();
+    assert!(next_text(&mut socket).await.unwrap().contains("connected"));
+
+    sqlx::query("DELETE FROM devices WHERE id = $1::uuid")
+        .bind(&device_id)
+        .execute(&state.db)
+        .await
+        .unwrap();
+    state
+        .sync_tx
+        .send(SyncNotification {
+            user_id: user_id.parse().unwrap(),
+            notification_type: SyncNotificationType::DeviceRemoved,
+            version: 0,
+            source_device_id: Some(device_id.parse().unwrap()),
+        })
+        .unwrap();
+
+    assert_eq!(next_text(&mut socket).await, None);
+
+    // Its token no longer opens a socket either
+    let err = connect_async(format!("{}?token={}", url, token))
+        .await
+        .unwrap_err();
+    match err {
+        tokio_tungstenite::tungstenite::Error::Http(response) => {
+            assert_eq!(response.status(), 404)
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
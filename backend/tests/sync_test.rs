@@ -95,6 +95,7 @@ async fn test_pull_empty_vault() {
 #[tokio::test]
 async fn test_push_and_pull_items() {
     let (router, _pool) = create_test_router().await;
+    let item_id = uuid::Uuid::new_v4();
     let email = random_email();
     let (access_token, _device_id) = register_user(&router, &email).await;
 
@@ -106,7 +107,7 @@ async fn test_push_and_pull_items() {
             "base_version": 1,
             "items": [
                 {
-                    "id": "10000000-0000-0000-0000-000000000001",
+                    "id": item_id,
                     "encrypted_data": "ZW5jcnlwdGVkX2RhdGFfMQ==",
                     "version": 0,
                     "is_deleted": false,
@@ -144,12 +145,15 @@ async fn test_push_and_pull_items() {
 
     let items = json["items"].as_array().unwrap();
     assert_eq!(items.len(), 1);
-    assert_eq!(items[0]["id"], "10000000-0000-0000-0000-000000000001");
+    assert_eq!(items[0]["id"], item_id.to_string());
 }
 
 #[tokio::test]
 async fn test_push_multiple_items() {
     let (router, _pool) = create_test_router().await;
+    let item1 = uuid::Uuid::new_v4();
+    let item2 = uuid::Uuid::new_v4();
+    let item3 = uuid::Uuid::new_v4();
     let email = random_email();
     let (access_token, _device_id) = register_user(&router, &email).await;
 
@@ -161,21 +165,21 @@ async fn test_push_multiple_items() {
             "base_version": 1,
             "items": [
                 {
-                    "id": "10000000-0000-0000-0000-000000000001",
+                    "id": item1,
                     "encrypted_data": "ZW5jcnlwdGVkX2RhdGFfMQ==",
                     "version": 0,
                     "is_deleted": false,
                     "modified_at": 1704067200
                 },
                 {
-                    "id": "10000000-0000-0000-0000-000000000002",
+                    "id": item2,
                     "encrypted_data": "ZW5jcnlwdGVkX2RhdGFfMg==",
                     "version": 0,
                     "is_deleted": false,
                     "modified_at": 1704067201
                 },
                 {
-                    "id": "10000000-0000-0000-0000-000000000003",
+                    "id": item3,
                     "encrypted_data": "ZW5jcnlwdGVkX2RhdGFfMw==",
                     "version": 0,
                     "is_deleted": false,
@@ -211,6 +215,7 @@ async fn test_push_multiple_items() {
 #[tokio::test]
 async fn test_push_deleted_item() {
     let (router, _pool) = create_test_router().await;
+    let item_id = uuid::Uuid::new_v4();
     let email = random_email();
     let (access_token, _device_id) = register_user(&router, &email).await;
 
@@ -222,7 +227,7 @@ async fn test_push_deleted_item() {
             "base_version": 1,
             "items": [
                 {
-                    "id": "10000000-0000-0000-0000-000000000001",
+                    "id": item_id,
                     "encrypted_data": "ZW5jcnlwdGVkX2RhdGFfMQ==",
                     "version": 0,
                     "is_deleted": false,
@@ -250,7 +255,7 @@ async fn test_push_deleted_item() {
             "base_version": version,
             "items": [
                 {
-                    "id": "10000000-0000-0000-0000-000000000001",
+                    "id": item_id,
                     "encrypted_data": "",
                     "version": version,
                     "is_deleted": true,
@@ -287,6 +292,8 @@ async fn test_push_deleted_item() {
 #[tokio::test]
 async fn test_pull_since_version() {
     let (router, _pool) = create_test_router().await;
+    let item1 = uuid::Uuid::new_v4();
+    let item2 = uuid::Uuid::new_v4();
     let email = random_email();
     let (access_token, _device_id) = register_user(&router, &email).await;
 
@@ -298,7 +305,7 @@ async fn test_pull_since_version() {
             "base_version": 1,
             "items": [
                 {
-                    "id": "10000000-0000-0000-0000-000000000001",
+                    "id": item1,
                     "encrypted_data": "ZW5jcnlwdGVkX2RhdGFfMQ==",
                     "version": 0,
                     "is_deleted": false,
@@ -324,7 +331,7 @@ async fn test_pull_since_version() {
             "base_version": version_after_first,
             "items": [
                 {
-                    "id": "10000000-0000-0000-0000-000000000002",
+                    "id": item2,
                     "encrypted_data": "ZW5jcnlwdGVkX2RhdGFfMg==",
                     "version": 0,
                     "is_deleted": false,
@@ -355,7 +362,7 @@ async fn test_pull_since_version() {
 
     let items = json["items"].as_array().unwrap();
     assert_eq!(items.len(), 1);
-    assert_eq!(items[0]["id"], "10000000-0000-0000-0000-000000000002");
+    assert_eq!(items[0]["id"], item2.to_string());
 }
 
 #[tokio::test]
@@ -376,6 +383,7 @@ async fn test_sync_without_auth() {
 #[tokio::test]
 async fn test_two_devices_sync() {
     let (router, _pool) = create_test_router().await;
+    let item_id = uuid::Uuid::new_v4();
     let email = random_email();
 
     // Register first device
@@ -408,7 +416,7 @@ async fn test_two_devices_sync() {
             "base_version": 1,
             "items": [
                 {
-                    "id": "10000000-0000-0000-0000-000000000001",
+                    "id": item_id,
                     "encrypted_data": "ZnJvbV9kZXZpY2VfMQ==",
                     "version": 0,
                     "is_deleted": false,
@@ -439,5 +447,348 @@ async fn test_two_devices_sync() {
 
     let items = json["items"].as_array().unwrap();
     assert_eq!(items.len(), 1);
-    assert_eq!(items[0]["id"], "10000000-0000-0000-0000-000000000001");
+    assert_eq!(items[0]["id"], item_id.to_string());
+}
+
+#[tokio::test]
+async fn test_pull_pagination() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+    let (access_token, _device_id) = register_user(&router, &email).await;
+
+    let items: Vec<Value> = (0..5)
+        .map(|i| {
+            json!({
+                "id": uuid::Uuid::new_v4(),
+                "encrypted_data": "ZW5jcnlwdGVkX2RhdGE=",
+                "version": 0,
+                "is_deleted": false,
+                "modified_at": 1704067200 + i
+            })
+        })
+        .collect();
+    let push_req = auth_json_request(
+        Method::POST,
+        "/api/v1/sync/push",
+        json!({ "base_version": 1, "items": items }),
+        &access_token,
+    );
+    let push_response = router.clone().oneshot(push_req).await.unwrap();
+    assert_eq!(push_response.status(), StatusCode::OK);
+
+    // Walk the pages, echoing the cursor back
+    let mut pulled = Vec::new();
+    let mut uri = "/api/v1/sync/pull?since_version=0&limit=2".to_string();
+    let mut pages = 0;
+    loop {
+        let pull_req = auth_request(Method::GET, &uri, &access_token);
+        let pull_response = router.clone().oneshot(pull_req).await.unwrap();
+        assert_eq!(pull_response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(pull_response.into_body(), 1024 * 1024)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        pages += 1;
+
+        let page = json["items"].as_array().unwrap();
+        assert!(page.len() <= 2);
+        pulled.extend(page.iter().map(|item| item["id"].clone()));
+
+        if !json["has_more"].as_bool().unwrap() {
+            assert!(json["next_cursor"].is_null());
+            break;
+        }
+        let cursor = json["next_cursor"].as_i64().unwrap();
+        assert_eq!(cursor, page.last().unwrap()["version"].as_i64().unwrap());
+        uri = format!("/api/v1/sync/pull?cursor={}&limit=2", cursor);
+    }
+
+    // Exactly full pages don't report a phantom extra page
+    assert_eq!(pages, 3);
+    let expected: Vec<Value> = items.iter().map(|item| item["id"].clone()).collect();
+    assert_eq!(pulled, expected);
+
+    let pull_req = auth_request(
+        Method::GET,
+        "/api/v1/sync/pull?since_version=0&limit=5",
+        &access_token,
+    );
+    let body = axum::body::to_bytes(
+        router.oneshot(pull_req).await.unwrap().into_body(),
+        1024 * 1024,
+    )
+    .await
+    .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["items"].as_array().unwrap().len(), 5);
+    assert_eq!(json["has_more"], false);
+}
+
+#[tokio::test]
+async fn test_push_is_atomic() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+    let (access_token, _device_id) = register_user(&router, &email).await;
+
+    let item = |encrypted_data: &str| {
+        json!({
+            "id": uuid::Uuid::new_v4(),
+            "encrypted_data": encrypted_data,
+            "version": 0,
+            "is_deleted": false,
+            "modified_at": 1704067200
+        })
+    };
+    let push = |items: Vec<Value>| {
+        auth_json_request(
+            Method::POST,
+            "/api/v1/sync/push",
+            json!({ "base_version": 1, "items": items }),
+            &access_token,
+        )
+    };
+    let pull = || {
+        auth_request(
+            Method::GET,
+            "/api/v1/sync/pull?since_version=0",
+            &access_token,
+        )
+    };
+
+    // One bad item rejects the whole batch
+    let response = router
+        .clone()
+        .oneshot(push(vec![item("ZW5jcnlwdGVk"), item("not base64!")]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(
+        router.clone().oneshot(pull()).await.unwrap().into_body(),
+        1024 * 1024,
+    )
+    .await
+    .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["current_version"], 1);
+    assert!(json["items"].as_array().unwrap().is_empty());
+
+    // A good batch lands whole, one version per item
+    let items = vec![item("Zmlyc3Q="), item("c2Vjb25k"), item("dGhpcmQ=")];
+    let response = router.clone().oneshot(push(items.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["new_version"], 4);
+
+    let body = axum::body::to_bytes(
+        router.oneshot(pull()).await.unwrap().into_body(),
+        1024 * 1024,
+    )
+    .await
+    .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    let pulled = json["items"].as_array().unwrap();
+    let ids: Vec<&Value> = pulled.iter().map(|item| &item["id"]).collect();
+    let versions: Vec<i64> = pulled
+        .iter()
+        .map(|item| item["version"].as_i64().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        items.iter().map(|item| &item["id"]).collect::<Vec<_>>()
+    );
+    assert_eq!(versions, vec![2, 3, 4]);
+}
+
+#[tokio::test]
+async fn test_push_idempotency_key() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+    let (access_token, _device_id) = register_user(&router, &email).await;
+
+    let item_id = uuid::Uuid::new_v4();
+    let push = |key: &str, base_version: i64| {
+        let body = json!({
+            "base_version": base_version,
+            "items": [{
+                "id": item_id,
+                "encrypted_data": "ZW5jcnlwdGVk",
+                "version": 0,
+                "is_deleted": false,
+                "modified_at": 1704067200
+            }]
+        });
+        let mut req = auth_json_request(Method::POST, "/api/v1/sync/push", body, &access_token);
+        req.headers_mut()
+            .insert("idempotency-key", key.parse().unwrap());
+        req
+    };
+    let send = |req: Request<Body>| {
+        let router = router.clone();
+        async move {
+            let response = router.oneshot(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+                .await
+                .unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        }
+    };
+
+    let first = send(push("push-1", 1)).await;
+    assert_eq!(first["new_version"], 2);
+
+    // A retry gets the original response and burns no version
+    let retry = send(push("push-1", 1)).await;
+    assert_eq!(retry, first);
+    let pull = send(auth_request(
+        Method::GET,
+        "/api/v1/sync/pull?since_version=0",
+        &access_token,
+    ))
+    .await;
+    assert_eq!(pull["current_version"], 2);
+    assert_eq!(pull["items"].as_array().unwrap().len(), 1);
+
+    // A new key is a new push
+    let second = send(push("push-2", 2)).await;
+    assert_eq!(second["new_version"], 3);
+
+    // Reusing a key for a different request is rejected
+    let response = router.clone().oneshot(push("push-1", 3)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let mut req = push("push-3", 3);
+    req.headers_mut()
+        .insert("idempotency-key", "k".repeat(256).parse().unwrap());
+    let response = router.oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_push_dedupes_blobs() {
+    let (router, pool) = create_test_router().await;
+    let (token, _) = register_user(&router, &random_email()).await;
+    let (other_token, _) = register_user(&router, &random_email()).await;
+
+    let push = |token: &str, base_version: i64, id: uuid::Uuid| {
+        auth_json_request(
+            Method::POST,
+            "/api/v1/sync/push",
+            json!({
+                "base_version": base_version,
+                "items": [{
+                    "id": id,
+                    "encrypted_data": "c2FtZV9jaXBoZXJ0ZXh0",
+                    "version": 0,
+                    "is_deleted": false,
+                    "modified_at": 1704067200
+                }]
+            }),
+            token,
+        )
+    };
+    let blob_of = |id: uuid::Uuid| {
+        sqlx::query_scalar::<_, String>(
+            "SELECT encrypted_blob_id FROM vault_items_sync WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_one(&pool)
+    };
+
+    // The same ciphertext is stored once per user
+    let (first, retried, other) = (
+        uuid::Uuid::new_v4(),
+        uuid::Uuid::new_v4(),
+        uuid::Uuid::new_v4(),
+    );
+    router
+        .clone()
+        .oneshot(push(&token, 1, first))
+        .await
+        .unwrap();
+    router
+        .clone()
+        .oneshot(push(&token, 2, retried))
+        .await
+        .unwrap();
+    router
+        .clone()
+        .oneshot(push(&other_token, 1, other))
+        .await
+        .unwrap();
+    assert_eq!(
+        blob_of(first).await.unwrap(),
+        blob_of(retried).await.unwrap()
+    );
+
+    // but never shared with another user
+    assert_ne!(blob_of(first).await.unwrap(), blob_of(other).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_push_rejects_other_users_item() {
+    let (router, pool) = create_test_router().await;
+    let (token, _) = register_user(&router, &random_email()).await;
+    let (other_token, _) = register_user(&router, &random_email()).await;
+
+    let item_id = uuid::Uuid::new_v4();
+    let push = |token: &str, encrypted_data: &str| {
+        auth_json_request(
+            Method::POST,
+            "/api/v1/sync/push",
+            json!({
+                "base_version": 1,
+                "items": [{
+                    "id": item_id,
+                    "encrypted_data": encrypted_data,
+                    "version": 0,
+                    "is_deleted": false,
+                    "modified_at": 1704067200
+                }]
+            }),
+            token,
+        )
+    };
+
+    let response = router
+        .clone()
+        .oneshot(push(&token, "b3duZXJfZGF0YQ=="))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let (owner, blob_id): (uuid::Uuid, String) =
+        sqlx::query_as("SELECT user_id, encrypted_blob_id FROM vault_items_sync WHERE id = $1")
+            .bind(item_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    // Another user pushing the same item ID can't take it over
+    let response = router
+        .clone()
+        .oneshot(push(&other_token, "b3RoZXJfZGF0YQ=="))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let row: (uuid::Uuid, String) =
+        sqlx::query_as("SELECT user_id, encrypted_blob_id FROM vault_items_sync WHERE id = $1")
+            .bind(item_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(row, (owner, blob_id.clone()));
+
+    // and the owner's blob isn't queued for collection
+    let superseded: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM superseded_blobs WHERE blob_id = $1)")
+            .bind(&blob_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(!superseded);
 }
@@ -0,0 +1,269 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use crypto_core::totp::{decode_secret, hotp, TotpAlgorithm};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use common::{create_test_router, random_email};
+
+const AUTH_KEY: &str = "dGVzdF9hdXRoX2tleQ==";
+
+/// Helper to make an authenticated JSON request, optionally with a second factor
+fn auth_json_request(
+    method: Method,
+    uri: &str,
+    body: Value,
+    token: &str,
+    code: Option<&str>,
+) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token));
+    if let Some(code) = code {
+        builder = builder.header("x-two-factor-code", code);
+    }
+    builder
+        .body(Body::from(serde_json::to_string(&body).unwrap()))
+        .unwrap()
+}
+
+async fn response_json(response: axum::response::Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// TOTP code for the step `offset` steps from now
+fn totp_code(secret: &[u8], offset: i64) -> String {
+    let step = chrono::Utc::now().timestamp() / 30 + offset;
+    hotp(secret, step as u64, 6, TotpAlgorithm::Sha1).unwrap()
+}
+
+fn login_request(email: &str, totp_code: Option<&str>) -> Request<Body> {
+    Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/auth/login")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({
+                "email": email,
+                "auth_key": AUTH_KEY,
+                "device_name": "Test Device",
+                "device_type": "desktop",
+                "totp_code": totp_code
+            })
+            .to_string(),
+        ))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_totp_enrollment_and_login() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+
+    let register_req = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/auth/register")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({
+                "email": email,
+                "auth_key": AUTH_KEY,
+                "salt": "dGVzdF9zYWx0",
+                "device_name": "Test Device",
+                "device_type": "desktop"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    let token = json["access_token"].as_str().unwrap().to_string();
+
+    // Enroll and confirm with a first code
+    let enroll_req = auth_json_request(
+        Method::POST,
+        "/api/v1/auth/2fa/enroll",
+        json!({ "auth_key": AUTH_KEY }),
+        &token,
+        None,
+    );
+    let response = router.clone().oneshot(enroll_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response_json(response).await;
+    assert!(json["otpauth_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("otpauth://totp/Keydrop:"));
+    let secret = decode_secret(json["secret"].as_str().unwrap()).unwrap();
+
+    let confirm = |code: String| {
+        auth_json_request(
+            Method::POST,
+            "/api/v1/auth/2fa/confirm",
+            json!({ "code": code }),
+            &token,
+            None,
+        )
+    };
+    let response = router
+        .clone()
+        .oneshot(confirm("000000".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = router
+        .clone()
+        .oneshot(confirm(totp_code(&secret, 0)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let recovery_codes: Vec<String> = response_json(response).await["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(recovery_codes.len(), 10);
+
+    // Login now needs a code, and the one used to confirm can't be replayed
+    let response = router
+        .clone()
+        .oneshot(login_request(&email, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response_json(response).await["error"],
+        "Two-factor code required"
+    );
+    let response = router
+        .clone()
+        .oneshot(login_request(&email, Some(&totp_code(&secret, 0))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = router
+        .clone()
+        .oneshot(login_request(&email, Some(&totp_code(&secret, 1))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Recovery codes work once
+    let response = router
+        .clone()
+        .oneshot(login_request(
+            &email,
+            Some(&recovery_codes[0].to_uppercase()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let status_req = Request::builder()
+        .method(Method::GET)
+        .uri("/api/v1/auth/2fa")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let json = response_json(router.clone().oneshot(status_req).await.unwrap()).await;
+    assert_eq!(json["enabled"], true);
+    assert_eq!(json["recovery_codes_remaining"], 9);
+
+    // Disabling is a sensitive operation and needs the second factor too
+    let disable = |code: Option<&str>| {
+        auth_json_request(
+            Method::POST,
+            "/api/v1/auth/2fa/disable",
+            json!({ "auth_key": AUTH_KEY }),
+            &token,
+            code,
+        )
+    };
+    let response = router.clone().oneshot(disable(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = router
+        .clone()
+        .oneshot(disable(Some(&recovery_codes[0])))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = router
+        .clone()
+        .oneshot(disable(Some(&recovery_codes[1])))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = router.oneshot(login_request(&email, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_sensitive_operation_requires_second_factor() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+
+    let register_req = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/auth/register")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({
+                "email": email,
+                "auth_key": AUTH_KEY,
+                "salt": "dGVzdF9zYWx0",
+                "device_name": "Test Device",
+                "device_type": "desktop"
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    let token = json["access_token"].as_str().unwrap().to_string();
+
+    let enroll_req = auth_json_request(
+        Method::POST,
+        "/api/v1/auth/2fa/enroll",
+        json!({ "auth_key": AUTH_KEY }),
+        &token,
+        None,
+    );
+    let json = response_json(router.clone().oneshot(enroll_req).await.unwrap()).await;
+    let secret = decode_secret(json["secret"].as_str().unwrap()).unwrap();
+    let confirm_req = auth_json_request(
+        Method::POST,
+        "/api/v1/auth/2fa/confirm",
+        json!({ "code": totp_code(&secret, 0) }),
+        &token,
+        None,
+    );
+    let response = router.clone().oneshot(confirm_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let delete = |code: Option<String>| {
+        auth_json_request(
+            Method::DELETE,
+            "/api/v1/auth/account",
+            json!({ "auth_key": AUTH_KEY, "grace_period_days": 0 }),
+            &token,
+            code.as_deref(),
+        )
+    };
+    let response = router.clone().oneshot(delete(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = router
+        .clone()
+        .oneshot(delete(Some(totp_code(&secret, 1))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use webauthn_authenticator_rs::{softpasskey::SoftPasskey, WebauthnAuthenticator};
+use webauthn_rs::prelude::{CreationChallengeResponse, RequestChallengeResponse, Url};
+
+use common::{create_test_router, random_email};
+
+const AUTH_KEY: &str = "dGVzdF9hdXRoX2tleQ==";
+const ORIGIN: &str = "http://localhost:3000";
+
+fn json_request(method: Method, uri: &str, body: Value, token: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    builder
+        .body(Body::from(serde_json::to_string(&body).unwrap()))
+        .unwrap()
+}
+
+async fn response_json(response: axum::response::Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), 1024 * 1024)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn login_request(email: &str) -> Request<Body> {
+    json_request(
+        Method::POST,
+        "/api/v1/auth/login",
+        json!({
+            "email": email,
+            "auth_key": AUTH_KEY,
+            "device_name": "Laptop",
+            "device_type": "desktop"
+        }),
+        None,
+    )
+}
+
+#[tokio::test]
+async fn test_passkey_registration_and_login() {
+    let (router, _pool) = create_test_router().await;
+    let email = random_email();
+    let origin = Url::parse(ORIGIN).unwrap();
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+    let register_req = json_request(
+        Method::POST,
+        "/api/v1/auth/register",
+        json!({
+            "email": email,
+            "auth_key": AUTH_KEY,
+            "salt": "dGVzdF9zYWx0",
+            "device_name": "Test Device",
+            "device_type": "desktop"
+        }),
+        None,
+    );
+    let json = response_json(router.clone().oneshot(register_req).await.unwrap()).await;
+    let token = json["access_token"].as_str().unwrap().to_string();
+
+    // Register a passkey
+    let start_req = json_request(
+        Method::POST,
+        "/api/v1/auth/webauthn/register/start",
+        json!({ "auth_key": AUTH_KEY }),
+        Some(&token),
+    );
+    let response = router.clone().oneshot(start_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response_json(response).await;
+    let options: CreationChallengeResponse =
+        serde_json::from_value(json["options"].clone()).unwrap();
+    let credential = authenticator
+        .do_registration(origin.clone(), options)
+        .unwrap();
+
+    let finish_req = json_request(
+        Method::POST,
+        "/api/v1/auth/webauthn/register/finish",
+        json!({
+            "challenge_id": json["challenge_id"],
+            "name": "Security key",
+            "credential": credential
+        }),
+        Some(&token),
+    );
+    let response = router.clone().oneshot(finish_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let credential_id = response_json(response).await["id"].clone();
+
+    let list_credentials = || {
+        Request::builder()
+            .method(Method::GET)
+            .uri("/api/v1/auth/webauthn")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let json = response_json(router.clone().oneshot(list_credentials()).await.unwrap()).await;
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["name"], "Security key");
+
+    // Login now stops at a passkey challenge instead of issuing tokens
+    let response = router.clone().oneshot(login_request(&email)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = response_json(response).await;
+    assert_eq!(json["webauthn_required"], true);
+    assert!(json.get("access_token").is_none());
+    let options: RequestChallengeResponse =
+        serde_json::from_value(json["options"].clone()).unwrap();
+    let assertion = authenticator
+        .do_authentication(origin.clone(), options)
+        .unwrap();
+
+    let finish_login = |challenge_id: &Value| {
+        json_request(
+            Method::POST,
+            "/api/v1/auth/webauthn/login",
+            json!({
+                "challenge_id": challenge_id,
+                "credential": assertion,
+                "device_name": "Laptop",
+                "device_type": "desktop"
+            }),
+            None,
+        )
+    };
+    let response = router
+        .clone()
+        .oneshot(finish_login(&json["challenge_id"]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let login = response_json(response).await;
+    assert!(login["access_token"].is_string());
+    assert!(login["refresh_token"].is_string());
+
+    // Challenges are single use
+    let response = router
+        .clone()
+        .oneshot(finish_login(&json["challenge_id"]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let json = response_json(router.clone().oneshot(list_credentials()).await.unwrap()).await;
+    assert!(json[0]["last_used_at"].is_i64());
+
+    // Removing the last passkey restores plain login
+    let delete_req = json_request(
+        Method::DELETE,
+        &format!("/api/v1/auth/webauthn/{}", credential_id.as_str().unwrap()),
+        json!({ "auth_key": AUTH_KEY }),
+        Some(&token),
+    );
+    let response = router.clone().oneshot(delete_req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = router.oneshot(login_request(&email)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response_json(response).await["access_token"].is_string());
+}
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+use crate::strength::estimate_strength;
+use crate::vault::VaultItem;
+
+/// Seconds in a day
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Options controlling which items an audit flags
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditOptions {
+    /// Passwords with less estimated entropy than this are weak
+    pub min_entropy_bits: f64,
+    /// Passwords shorter than this are weak regardless of entropy
+    pub min_length: usize,
+    /// Passwords not changed for more days than this are old
+    pub max_age_days: u64,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            min_entropy_bits: 60.0,
+            min_length: 10,
+            max_age_days: 365,
+        }
+    }
+}
+
+/// Result of auditing a set of items; all lists hold item IDs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AuditReport {
+    /// Number of items with a password
+    pub total: usize,
+    /// Items with short or low-entropy passwords
+    pub weak: Vec<String>,
+    /// Groups of items sharing the same password
+    pub reused: Vec<Vec<String>>,
+    /// Items whose password has not changed within `max_age_days`
+    pub old: Vec<String>,
+    /// Items whose password is on the common-password list, and likely in breach corpora
+    pub breach_candidates: Vec<String>,
+    /// Percentage of audited items with no findings
+    pub score: u8,
+}
+
+impl AuditReport {
+    /// Check whether the audit found nothing
+    pub fn is_clean(&self) -> bool {
+        self.weak.is_empty()
+            && self.reused.is_empty()
+            && self.old.is_empty()
+            && self.breach_candidates.is_empty()
+    }
+}
+
+/// Audit item passwords for weakness, reuse, age, and likely breaches
+///
+/// `now` is the current Unix epoch seconds. Items without a password are skipped.
+pub fn audit_items<'a>(
+    items: impl IntoIterator<Item = &'a VaultItem>,
+    options: &AuditOptions,
+    now: u64,
+) -> AuditReport {
+    let items: Vec<&VaultItem> = items
+        .into_iter()
+        .filter(|item| !item.password.is_empty())
+        .collect();
+    let max_age = options.max_age_days.saturating_mul(DAY_SECS);
+
+    let mut report = AuditReport {
+        total: items.len(),
+        ..Default::default()
+    };
+    let mut by_password: HashMap<&str, Vec<String>> = HashMap::new();
+
+    for item in &items {
+        if item.password.chars().count() < options.min_length
+            || estimate_strength(&item.password).entropy_bits < options.min_entropy_bits
+        {
+            report.weak.push(item.id.clone());
+        }
+        if now.saturating_sub(item.modified_at) > max_age {
+            report.old.push(item.id.clone());
+        }
+        #[cfg(feature = "banned-list")]
+        if crate::password::BannedList::builtin().contains(&item.password) {
+            report.breach_candidates.push(item.id.clone());
+        }
+        by_password
+            .entry(item.password.as_str())
+            .or_default()
+            .push(item.id.clone());
+    }
+
+    report.reused = by_password
+        .into_values()
+        .filter(|ids| ids.len() > 1)
+        .collect();
+    report.reused.sort();
+
+    let flagged = items
+        .iter()
+        .filter(|item| {
+            let id = &item.id;
+            report.weak.contains(id)
+                || report.old.contains(id)
+                || report.breach_candidates.contains(id)
+                || report.reused.iter().any(|group| group.contains(id))
+        })
+        .count();
+    report.score = match report.total {
+        0 => 100,
+        total => (100 * (total - flagged) / total) as u8,
+    };
+
+    report
+}
+
+/// Hex characters of a password's SHA-1 hash sent in a breach range query
+pub const BREACH_PREFIX_LEN: usize = 5;
+
+/// A password's SHA-1 hash split for a k-anonymity range query
+///
+/// Only `prefix` leaves the device. The service answers with every known hash
+/// suffix under that prefix, and [`breach_count`] finds the match locally.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BreachQuery {
+    /// First [`BREACH_PREFIX_LEN`] uppercase hex characters of the hash
+    pub prefix: String,
+    /// The remaining 35 characters
+    pub suffix: String,
+}
+
+/// Split `password`'s hash for a Pwned Passwords style range query
+pub fn breach_query(password: &str) -> BreachQuery {
+    let hash: String = Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+    let (prefix, suffix) = hash.split_at(BREACH_PREFIX_LEN);
+    BreachQuery {
+        prefix: prefix.to_string(),
+        suffix: suffix.to_string(),
+    }
+}
+
+/// Times `suffix` appears in a range response, or 0 if it's not listed
+///
+/// The response has one `SUFFIX:COUNT` line per hash; padding entries have a
+/// count of 0.
+pub fn breach_count(response: &str, suffix: &str) -> u64 {
+    response
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn item(name: &str, password: &str, age_days: u64) -> VaultItem {
+        let mut item = VaultItem::new(name, "user", password);
+        item.modified_at = NOW - age_days * DAY_SECS;
+        item
+    }
+
+    #[test]
+    fn test_audit_items() {
+        let items = vec![
+            item("strong", "Xk9#mP2$vL7@qR4!", 10),
+            item("short", "aB3$", 10),
+            item("reused-a", "Tr0ub4dor&3-horse-staple", 10),
+            item("reused-b", "Tr0ub4dor&3-horse-staple", 10),
+            item("old", "Zq8!nW3@yH6#bJ1$", 400),
+            item("empty", "", 10),
+        ];
+
+        let report = audit_items(&items, &AuditOptions::default(), NOW);
+
+        assert_eq!(report.total, 5);
+        assert_eq!(report.weak, vec![items[1].id.clone()]);
+        assert_eq!(
+            report.reused,
+            vec![vec![items[2].id.clone(), items[3].id.clone()]]
+        );
+        assert_eq!(report.old, vec![items[4].id.clone()]);
+        assert_eq!(report.score, 20);
+        assert!(!report.is_clean());
+    }
+
+    #[cfg(feature = "banned-list")]
+    #[test]
+    fn test_audit_breach_candidates() {
+        let items = vec![item("common", "password123", 1)];
+        let report = audit_items(&items, &AuditOptions::default(), NOW);
+
+        assert_eq!(report.breach_candidates, vec![items[0].id.clone()]);
+    }
+
+    #[test]
+    fn test_breach_query() {
+        let query = breach_query("password");
+        assert_eq!(query.prefix, "5BAA6");
+        assert_eq!(query.suffix, "1E4C9B93F3F0682250B6CF8331B7EE68FD8");
+
+        let response = "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n\
+                        1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n\
+                        011053FD0102E94D6AE2F8B83D76FAF94F6:0\r\n";
+        assert_eq!(breach_count(response, &query.suffix), 9_659_365);
+        assert_eq!(
+            breach_count(response, "011053fd0102e94d6ae2f8b83d76faf94f6"),
+            0
+        );
+        assert_eq!(breach_count(response, &breach_query("unlisted").suffix), 0);
+    }
+
+    #[test]
+    fn test_audit_empty() {
+        let report = audit_items(&[], &AuditOptions::default(), NOW);
+        assert_eq!(report.score, 100);
+        assert!(report.is_clean());
+    }
+}
@@ -0,0 +1,276 @@
+use serde::Serialize;
+
+use crate::matching::{base_domain, extract_host, UrlMatchKind, UrlMatchStrategy};
+use crate::totp::{generate_totp, TotpOptions};
+use crate::vault::{rank_url_matches, UrlMatch, VaultItem};
+
+/// URL scheme linking an item to an Android app, e.g. `androidapp://com.example.app`
+pub const ANDROID_APP_SCHEME: &str = "androidapp://";
+
+/// Leading labels that mark a bare target as a reverse-domain package name
+const PACKAGE_PREFIXES: &[&str] = &[
+    "com", "org", "net", "io", "app", "dev", "co", "me", "de", "fr", "uk", "jp", "kr", "br", "in",
+    "au", "ca",
+];
+
+/// Form field names/autocomplete tokens that usually hold the username
+const USERNAME_HINTS: &[&str] = &[
+    "username",
+    "email",
+    "user",
+    "login",
+    "userid",
+    "user_id",
+    "account",
+    "identifier",
+];
+
+/// Form field names/autocomplete tokens that usually hold the password
+const PASSWORD_HINTS: &[&str] = &["current-password", "password", "passwd", "pass", "pwd"];
+
+/// Form field names/autocomplete tokens that usually hold a one-time code
+const TOTP_HINTS: &[&str] = &[
+    "one-time-code",
+    "otp",
+    "totp",
+    "2fa",
+    "mfa",
+    "code",
+    "token",
+    "verification",
+];
+
+/// Custom field names treated as a TOTP secret
+const TOTP_FIELD_NAMES: &[&str] = &["totp", "otp", "2fa", "one-time", "authenticator"];
+
+/// Kind of form field to fill
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutofillFieldKind {
+    Username,
+    Password,
+    Totp,
+}
+
+impl AutofillFieldKind {
+    /// Lowercase field name or autocomplete substrings that identify this kind
+    pub fn hints(&self) -> &'static [&'static str] {
+        match self {
+            AutofillFieldKind::Username => USERNAME_HINTS,
+            AutofillFieldKind::Password => PASSWORD_HINTS,
+            AutofillFieldKind::Totp => TOTP_HINTS,
+        }
+    }
+}
+
+/// A value to fill and how to recognize its form field
+#[derive(Clone, Debug, Serialize)]
+pub struct AutofillField {
+    pub kind: AutofillFieldKind,
+    pub value: String,
+    pub hints: &'static [&'static str],
+}
+
+/// A matching item with the fields to fill
+#[derive(Clone, Debug, Serialize)]
+pub struct AutofillCandidate {
+    pub item_id: String,
+    pub name: String,
+    pub match_kind: UrlMatchKind,
+    pub confidence: f64,
+    pub fields: Vec<AutofillField>,
+}
+
+/// Everything a content script needs to fill a page, best candidate first
+#[derive(Clone, Debug, Serialize)]
+pub struct AutofillPayload {
+    pub url: String,
+    pub host: String,
+    pub candidates: Vec<AutofillCandidate>,
+}
+
+/// Build the autofill payload for a page URL
+///
+/// `timestamp` (Unix epoch seconds) is used to generate TOTP codes for items
+/// with a TOTP secret or TOTP custom field.
+pub fn build_autofill_payload<'a>(
+    url: &str,
+    items: impl IntoIterator<Item = &'a VaultItem>,
+    strategy: UrlMatchStrategy,
+    timestamp: u64,
+) -> AutofillPayload {
+    let candidates = rank_url_matches(url, items, strategy)
+        .into_iter()
+        .map(|m| AutofillCandidate {
+            item_id: m.item.id.clone(),
+            name: m.item.name.clone(),
+            match_kind: m.kind,
+            confidence: m.kind.confidence(),
+            fields: autofill_fields(m.item, timestamp),
+        })
+        .collect();
+
+    AutofillPayload {
+        url: url.to_string(),
+        host: extract_host(url),
+        candidates,
+    }
+}
+
+/// Rank items for a web URL, an `androidapp://` URI, or a bare Android package name
+///
+/// For packages, items linked to the app match as `ExactHost`; items whose website
+/// shares the package's reversed domain (`com.github.android` -> `github.com`) match
+/// at most as `BaseDomain`, since the link between app and site is inferred.
+pub fn match_for_autofill<'a>(
+    target: &str,
+    items: impl IntoIterator<Item = &'a VaultItem>,
+    strategy: UrlMatchStrategy,
+) -> Vec<UrlMatch<'a>> {
+    let Some(package) = android_package(target) else {
+        return rank_url_matches(target, items, strategy);
+    };
+
+    let linked = |item: &VaultItem| {
+        item.url
+            .as_deref()
+            .and_then(|u| u.trim().strip_prefix(ANDROID_APP_SCHEME))
+            .is_some_and(|p| p.eq_ignore_ascii_case(package))
+    };
+    let (linked_items, others): (Vec<&VaultItem>, Vec<&VaultItem>) =
+        items.into_iter().partition(|item| linked(item));
+
+    let mut matches: Vec<UrlMatch<'a>> = rank_url_matches(target, linked_items, strategy);
+    let web_url = format!("https://{}", package_domain(package));
+    matches.extend(
+        rank_url_matches(&web_url, others, UrlMatchStrategy::Equivalent)
+            .into_iter()
+            .map(|m| UrlMatch {
+                kind: m.kind.min(UrlMatchKind::BaseDomain),
+                ..m
+            })
+            .filter(|m| strategy.accepts(m.kind)),
+    );
+    matches
+}
+
+/// Package name from an `androidapp://` URI or a bare name like `com.example.app`
+fn android_package(target: &str) -> Option<&str> {
+    let target = target.trim();
+    if let Some(package) = target.strip_prefix(ANDROID_APP_SCHEME) {
+        return Some(package);
+    }
+    if target.contains("://") || target.contains('/') || !target.contains('.') {
+        return None;
+    }
+    let first = target.split('.').next()?.to_lowercase();
+    PACKAGE_PREFIXES.contains(&first.as_str()).then_some(target)
+}
+
+/// Registrable web domain implied by a package name
+fn package_domain(package: &str) -> String {
+    let host: Vec<&str> = package.split('.').rev().collect();
+    base_domain(&host.join(".").to_lowercase())
+}
+
+/// Collect the non-empty fillable fields of an item
+fn autofill_fields(item: &VaultItem, timestamp: u64) -> Vec<AutofillField> {
+    let field = |kind: AutofillFieldKind, value: String| AutofillField {
+        kind,
+        value,
+        hints: kind.hints(),
+    };
+
+    let mut fields = Vec::new();
+    if !item.username.is_empty() {
+        fields.push(field(AutofillFieldKind::Username, item.username.clone()));
+    }
+    if !item.password.is_empty() {
+        fields.push(field(AutofillFieldKind::Password, item.password.clone()));
+    }
+
+    let totp_code = item
+        .totp_secret
+        .iter()
+        .chain(
+            item.custom_fields
+                .iter()
+                .filter(|f| {
+                    let name = f.name.to_lowercase();
+                    TOTP_FIELD_NAMES.iter().any(|n| name.contains(n))
+                })
+                .map(|f| &f.value),
+        )
+        .find_map(|secret| generate_totp(secret, &TotpOptions::default(), timestamp).ok());
+    if let Some(code) = totp_code {
+        fields.push(field(AutofillFieldKind::Totp, code.code));
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_autofill_payload() {
+        let github = VaultItem::new("GitHub", "dev", "pw")
+            .with_url("https://github.com")
+            .with_totp_secret("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        let gist = VaultItem::new("Gist", "dev", "").with_url("https://gist.github.com");
+        let other = VaultItem::new("Other", "dev", "pw").with_url("https://example.com");
+        let items = vec![gist, github, other];
+
+        let payload = build_autofill_payload(
+            "https://www.github.com/login",
+            &items,
+            UrlMatchStrategy::BaseDomain,
+            59,
+        );
+
+        assert_eq!(payload.host, "github.com");
+        assert_eq!(payload.candidates.len(), 2);
+
+        let best = &payload.candidates[0];
+        assert_eq!(best.name, "GitHub");
+        assert_eq!(best.match_kind, UrlMatchKind::ExactHost);
+        let kinds: Vec<AutofillFieldKind> = best.fields.iter().map(|f| f.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AutofillFieldKind::Username,
+                AutofillFieldKind::Password,
+                AutofillFieldKind::Totp
+            ]
+        );
+        assert_eq!(best.fields[2].value, "287082");
+
+        assert_eq!(payload.candidates[1].fields.len(), 1);
+    }
+
+    #[test]
+    fn test_match_for_autofill_packages() {
+        let app =
+            VaultItem::new("GitHub App", "dev", "pw").with_url("androidapp://com.github.android");
+        let web = VaultItem::new("GitHub", "dev", "pw").with_url("https://github.com");
+        let other = VaultItem::new("Other", "dev", "pw").with_url("https://example.com");
+        let items = vec![web, other, app];
+
+        for target in ["com.github.android", "androidapp://com.github.android"] {
+            let matches = match_for_autofill(target, &items, UrlMatchStrategy::BaseDomain);
+            let names: Vec<&str> = matches.iter().map(|m| m.item.name.as_str()).collect();
+            assert_eq!(names, ["GitHub App", "GitHub"]);
+            assert_eq!(matches[0].kind, UrlMatchKind::ExactHost);
+            assert_eq!(matches[1].kind, UrlMatchKind::BaseDomain);
+        }
+
+        let matches = match_for_autofill("com.github.android", &items, UrlMatchStrategy::ExactHost);
+        assert_eq!(matches.len(), 1);
+
+        // Web hosts are not mistaken for packages
+        let matches = match_for_autofill("github.com", &items, UrlMatchStrategy::BaseDomain);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].item.name, "GitHub");
+    }
+}
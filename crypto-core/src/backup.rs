@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::cipher::{decrypt, encrypt, EncryptedBlob, NONCE_SIZE};
+use crate::error::{CryptoError, Result};
+use crate::kdf::{derive_keys, derive_master_key, Salt, SALT_SIZE};
+use crate::vault::Vault;
+
+/// Magic bytes at the start of every backup file
+const BACKUP_MAGIC: &[u8; 4] = b"KDBK";
+
+/// Backup format version without attachments
+const BACKUP_VERSION: u8 = 1;
+
+/// Backup format version with attachments following the vault
+const BACKUP_VERSION_ATTACHMENTS: u8 = 2;
+
+/// Length of the fixed header: magic, version, salt
+const HEADER_SIZE: usize = BACKUP_MAGIC.len() + 1 + SALT_SIZE;
+
+/// A file attached to a vault item, carried alongside the vault in a backup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupAttachment {
+    pub id: String,
+    /// The item the file is attached to
+    pub item_id: String,
+    /// File name, without any directory
+    pub name: String,
+    /// Creation time (Unix epoch seconds)
+    pub created_at: u64,
+    /// File contents
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+/// Everything restored from a backup
+pub struct BackupContents {
+    pub vault: Vault,
+    pub attachments: Vec<BackupAttachment>,
+}
+
+/// Encrypt a vault into a self-contained backup protected by a passphrase
+///
+/// Layout: `KDBK` | version (1 byte) | salt | nonce || ciphertext. The key is
+/// derived from the passphrase and a fresh salt, so a backup can be restored
+/// without the account's master password.
+pub fn seal_backup(vault: &Vault, passphrase: &str) -> Result<Vec<u8>> {
+    seal_backup_with(vault, &[], passphrase)
+}
+
+/// Encrypt a vault and its attachments into a backup protected by a passphrase
+///
+/// Without attachments this writes the same version 1 file as [`seal_backup`].
+/// With them, version 2 length-prefixes the vault and follows it with one
+/// length-prefixed blob per attachment, each holding its metadata and
+/// contents. Lengths are big-endian `u64`.
+pub fn seal_backup_with(
+    vault: &Vault,
+    attachments: &[BackupAttachment],
+    passphrase: &str,
+) -> Result<Vec<u8>> {
+    let salt = Salt::generate()?;
+    let keys = derive_keys(&derive_master_key(passphrase, &salt)?)?;
+    let blob = vault.export(&keys.vault_key)?.to_bytes();
+
+    let attachments_size: usize = attachments.iter().map(|a| a.data.len() + 512).sum();
+    let mut data = Vec::with_capacity(HEADER_SIZE + 8 + blob.len() + attachments_size);
+    data.extend_from_slice(BACKUP_MAGIC);
+    if attachments.is_empty() {
+        data.push(BACKUP_VERSION);
+        data.extend_from_slice(salt.as_bytes());
+        data.extend_from_slice(&blob);
+        return Ok(data);
+    }
+
+    data.push(BACKUP_VERSION_ATTACHMENTS);
+    data.extend_from_slice(salt.as_bytes());
+    push_section(&mut data, &blob);
+    for attachment in attachments {
+        let meta = serde_json::to_vec(attachment)
+            .map_err(|e| CryptoError::Serialization(e.to_string()))?;
+        let mut plaintext =
+            Zeroizing::new(Vec::with_capacity(4 + meta.len() + attachment.data.len()));
+        plaintext.extend_from_slice(&(meta.len() as u32).to_be_bytes());
+        plaintext.extend_from_slice(&meta);
+        plaintext.extend_from_slice(&attachment.data);
+        push_section(&mut data, &encrypt(&plaintext, &keys.vault_key)?.to_bytes());
+    }
+    Ok(data)
+}
+
+/// Decrypt a backup produced by [`seal_backup`]
+///
+/// Attachments in a version 2 backup are skipped without being decrypted.
+pub fn open_backup(data: &[u8], passphrase: &str) -> Result<Vault> {
+    let (version, salt) = read_header(data)?;
+    let keys = derive_keys(&derive_master_key(passphrase, &salt)?)?;
+    let vault_blob = match version {
+        BACKUP_VERSION => &data[HEADER_SIZE..],
+        _ => Sections::new(&data[HEADER_SIZE..])
+            .next()
+            .unwrap_or(Err(truncated()))?,
+    };
+    Vault::import(&EncryptedBlob::from_bytes(vault_blob)?, &keys.vault_key)
+}
+
+/// Decrypt a backup produced by [`seal_backup`] or [`seal_backup_with`]
+pub fn open_backup_with(data: &[u8], passphrase: &str) -> Result<BackupContents> {
+    let (version, salt) = read_header(data)?;
+    let keys = derive_keys(&derive_master_key(passphrase, &salt)?)?;
+    if version == BACKUP_VERSION {
+        let vault = Vault::import(
+            &EncryptedBlob::from_bytes(&data[HEADER_SIZE..])?,
+            &keys.vault_key,
+        )?;
+        return Ok(BackupContents {
+            vault,
+            attachments: Vec::new(),
+        });
+    }
+
+    let mut sections = Sections::new(&data[HEADER_SIZE..]);
+    let vault_blob = sections.next().unwrap_or(Err(truncated()))?;
+    let vault = Vault::import(&EncryptedBlob::from_bytes(vault_blob)?, &keys.vault_key)?;
+    let mut attachments = Vec::new();
+    for section in sections {
+        let plaintext = Zeroizing::new(decrypt(
+            &EncryptedBlob::from_bytes(section?)?,
+            &keys.vault_key,
+        )?);
+        let meta_len = plaintext
+            .get(..4)
+            .map(|len| u32::from_be_bytes(len.try_into().unwrap()) as usize)
+            .filter(|len| 4 + len <= plaintext.len())
+            .ok_or_else(truncated)?;
+        let mut attachment: BackupAttachment = serde_json::from_slice(&plaintext[4..4 + meta_len])
+            .map_err(|e| CryptoError::Deserialization(e.to_string()))?;
+        attachment.data = plaintext[4 + meta_len..].to_vec();
+        attachments.push(attachment);
+    }
+    Ok(BackupContents { vault, attachments })
+}
+
+fn read_header(data: &[u8]) -> Result<(u8, Salt)> {
+    if data.len() < HEADER_SIZE || !data.starts_with(BACKUP_MAGIC) {
+        return Err(CryptoError::Deserialization(
+            "Not a Keydrop backup file".to_string(),
+        ));
+    }
+    let version = data[BACKUP_MAGIC.len()];
+    if version != BACKUP_VERSION && version != BACKUP_VERSION_ATTACHMENTS {
+        return Err(CryptoError::Deserialization(format!(
+            "Unsupported backup version {}",
+            version
+        )));
+    }
+
+    let salt_start = BACKUP_MAGIC.len() + 1;
+    let mut salt = [0u8; SALT_SIZE];
+    salt.copy_from_slice(&data[salt_start..HEADER_SIZE]);
+    Ok((version, Salt::from_bytes(salt)))
+}
+
+fn push_section(data: &mut Vec<u8>, section: &[u8]) {
+    data.extend_from_slice(&(section.len() as u64).to_be_bytes());
+    data.extend_from_slice(section);
+}
+
+fn truncated() -> CryptoError {
+    CryptoError::Deserialization("Backup file is truncated".to_string())
+}
+
+/// The length-prefixed sections of a version 2 backup
+struct Sections<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Sections<'a> {
+    fn new(rest: &'a [u8]) -> Self {
+        Self { rest }
+    }
+}
+
+impl<'a> Iterator for Sections<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let Some((len, rest)) = self.rest.split_first_chunk::<8>() else {
+            self.rest = &[];
+            return Some(Err(truncated()));
+        };
+        let len = u64::from_be_bytes(*len) as usize;
+        if len < NONCE_SIZE || len > rest.len() {
+            self.rest = &[];
+            return Some(Err(truncated()));
+        }
+        let (section, rest) = rest.split_at(len);
+        self.rest = rest;
+        Some(Ok(section))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault::VaultItem;
+
+    #[test]
+    fn test_backup_round_trip() {
+        let mut vault = Vault::new();
+        vault.add_item(VaultItem::new("GitHub", "user", "pass"));
+
+        let data = seal_backup(&vault, "correct horse").unwrap();
+        assert!(data.starts_with(BACKUP_MAGIC));
+
+        let restored = open_backup(&data, "correct horse").unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored.items[0].password, "pass");
+
+        assert!(matches!(
+            open_backup(&data, "wrong"),
+            Err(CryptoError::Decryption(_))
+        ));
+        assert!(matches!(
+            open_backup(b"not a backup", "correct horse"),
+            Err(CryptoError::Deserialization(_))
+        ));
+    }
+
+    #[test]
+    fn test_backup_with_attachments() {
+        let mut vault = Vault::new();
+        let id = vault.add_item(VaultItem::new("Passport", "", ""));
+        let attachment = BackupAttachment {
+            id: "a1".to_string(),
+            item_id: id,
+            name: "scan.pdf".to_string(),
+            created_at: 42,
+            data: vec![7u8; 1000],
+        };
+
+        let data = seal_backup_with(&vault, std::slice::from_ref(&attachment), "pass").unwrap();
+        assert_eq!(data[BACKUP_MAGIC.len()], BACKUP_VERSION_ATTACHMENTS);
+
+        let contents = open_backup_with(&data, "pass").unwrap();
+        assert_eq!(contents.vault.len(), 1);
+        assert_eq!(contents.attachments, vec![attachment]);
+        // Readers that only want the vault skip the attachments
+        assert_eq!(open_backup(&data, "pass").unwrap().len(), 1);
+
+        assert!(open_backup_with(&data[..data.len() - 10], "pass").is_err());
+
+        // Without attachments the file stays readable by version 1 readers
+        let data = seal_backup_with(&vault, &[], "pass").unwrap();
+        assert_eq!(data[BACKUP_MAGIC.len()], BACKUP_VERSION);
+        assert!(open_backup_with(&data, "pass")
+            .unwrap()
+            .attachments
+            .is_empty());
+    }
+}
@@ -1,9 +1,10 @@
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
 
 use crate::error::{CryptoError, Result};
 
@@ -13,6 +14,9 @@ pub const NONCE_SIZE: usize = 12;
 /// Size of the AES-256 key in bytes (256 bits)
 pub const KEY_SIZE: usize = 32;
 
+/// Associated data binding wrapped keys to their purpose
+const KEY_WRAP_AAD: &[u8] = b"keydrop-key-wrap-v1";
+
 /// Encrypted data blob containing ciphertext and nonce
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct EncryptedBlob {
@@ -35,6 +39,30 @@ impl EncryptedBlob {
             .map_err(|e| CryptoError::Deserialization(e.to_string()))?;
         serde_json::from_slice(&json).map_err(|e| CryptoError::Deserialization(e.to_string()))
     }
+
+    /// Encode as compact binary: nonce followed by ciphertext
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(NONCE_SIZE + self.ciphertext.len());
+        bytes.extend_from_slice(&self.nonce);
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+
+    /// Decode from compact binary produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < NONCE_SIZE {
+            return Err(CryptoError::InvalidNonceLength {
+                expected: NONCE_SIZE,
+                got: bytes.len(),
+            });
+        }
+        let mut nonce = [0u8; NONCE_SIZE];
+        nonce.copy_from_slice(&bytes[..NONCE_SIZE]);
+        Ok(Self {
+            nonce,
+            ciphertext: bytes[NONCE_SIZE..].to_vec(),
+        })
+    }
 }
 
 /// Encrypt data using AES-256-GCM
@@ -42,6 +70,10 @@ impl EncryptedBlob {
 /// Generates a random 96-bit nonce for each encryption.
 /// Returns an EncryptedBlob containing the nonce and ciphertext.
 pub fn encrypt(data: &[u8], key: &[u8; KEY_SIZE]) -> Result<EncryptedBlob> {
+    encrypt_with_aad(data, b"", key)
+}
+
+fn encrypt_with_aad(data: &[u8], aad: &[u8], key: &[u8; KEY_SIZE]) -> Result<EncryptedBlob> {
     let cipher =
         Aes256Gcm::new_from_slice(key).map_err(|e| CryptoError::Encryption(e.to_string()))?;
 
@@ -54,7 +86,7 @@ pub fn encrypt(data: &[u8], key: &[u8; KEY_SIZE]) -> Result<EncryptedBlob> {
     let nonce = Nonce::from_slice(&nonce_bytes);
 
     let ciphertext = cipher
-        .encrypt(nonce, data)
+        .encrypt(nonce, Payload { msg: data, aad })
         .map_err(|e| CryptoError::Encryption(e.to_string()))?;
 
     Ok(EncryptedBlob {
@@ -67,16 +99,48 @@ pub fn encrypt(data: &[u8], key: &[u8; KEY_SIZE]) -> Result<EncryptedBlob> {
 ///
 /// Verifies the authentication tag and returns the plaintext.
 pub fn decrypt(blob: &EncryptedBlob, key: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
+    decrypt_with_aad(blob, b"", key)
+}
+
+fn decrypt_with_aad(blob: &EncryptedBlob, aad: &[u8], key: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
     let cipher =
         Aes256Gcm::new_from_slice(key).map_err(|e| CryptoError::Decryption(e.to_string()))?;
 
     let nonce = Nonce::from_slice(&blob.nonce);
 
     cipher
-        .decrypt(nonce, blob.ciphertext.as_ref())
+        .decrypt(
+            nonce,
+            Payload {
+                msg: &blob.ciphertext,
+                aad,
+            },
+        )
         .map_err(|e| CryptoError::Decryption(e.to_string()))
 }
 
+/// Wrap a key under a key-encryption key (KEK), e.g. one held by a platform keystore
+///
+/// Returns `nonce || ciphertext`. Wrapped keys are bound to their purpose and can
+/// only be opened with [`unwrap_key`], not [`decrypt`].
+pub fn wrap_key(key: &[u8; KEY_SIZE], kek: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
+    Ok(encrypt_with_aad(key, KEY_WRAP_AAD, kek)?.to_bytes())
+}
+
+/// Recover a key produced by [`wrap_key`]
+pub fn unwrap_key(wrapped: &[u8], kek: &[u8; KEY_SIZE]) -> Result<Zeroizing<[u8; KEY_SIZE]>> {
+    let blob = EncryptedBlob::from_bytes(wrapped)?;
+    let key = Zeroizing::new(decrypt_with_aad(&blob, KEY_WRAP_AAD, kek)?);
+    let key: [u8; KEY_SIZE] =
+        key.as_slice()
+            .try_into()
+            .map_err(|_| CryptoError::InvalidKeyLength {
+                expected: KEY_SIZE,
+                got: key.len(),
+            })?;
+    Ok(Zeroizing::new(key))
+}
+
 /// Encrypt a string and return base64-encoded blob
 pub fn encrypt_string(plaintext: &str, key: &[u8; KEY_SIZE]) -> Result<String> {
     let blob = encrypt(plaintext.as_bytes(), key)?;
@@ -100,6 +164,21 @@ mod tests {
         key
     }
 
+    #[test]
+    fn test_wrap_unwrap_key() {
+        let key = test_key();
+        let kek = test_key();
+
+        let wrapped = wrap_key(&key, &kek).unwrap();
+        assert_eq!(*unwrap_key(&wrapped, &kek).unwrap(), key);
+
+        assert!(unwrap_key(&wrapped, &test_key()).is_err());
+
+        // A wrapped key is not an ordinary ciphertext
+        let blob = EncryptedBlob::from_bytes(&wrapped).unwrap();
+        assert!(decrypt(&blob, &kek).is_err());
+    }
+
     #[test]
     fn test_encrypt_decrypt() {
         let key = test_key();
@@ -159,4 +238,19 @@ mod tests {
         assert_eq!(blob.nonce, decoded.nonce);
         assert_eq!(blob.ciphertext, decoded.ciphertext);
     }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let key = test_key();
+        let plaintext = b"Test data";
+
+        let blob = encrypt(plaintext, &key).unwrap();
+        let bytes = blob.to_bytes();
+        assert_eq!(bytes.len(), NONCE_SIZE + blob.ciphertext.len());
+
+        let decoded = EncryptedBlob::from_bytes(&bytes).unwrap();
+        assert_eq!(decrypt(&decoded, &key).unwrap(), plaintext);
+
+        assert!(EncryptedBlob::from_bytes(&bytes[..NONCE_SIZE - 1]).is_err());
+    }
 }
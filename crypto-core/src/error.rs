@@ -31,6 +31,28 @@ pub enum CryptoError {
 
     #[error("Random generation failed: {0}")]
     RandomGeneration(String),
+
+    #[error("Invalid TOTP secret: {0}")]
+    InvalidTotpSecret(String),
+}
+
+impl CryptoError {
+    /// Stable machine-readable code for bindings to branch on, e.g. `DECRYPTION_FAILED`
+    pub fn code(&self) -> &'static str {
+        match self {
+            CryptoError::KeyDerivation(_) => "KEY_DERIVATION_FAILED",
+            CryptoError::Encryption(_) => "ENCRYPTION_FAILED",
+            CryptoError::Decryption(_) => "DECRYPTION_FAILED",
+            CryptoError::InvalidKeyLength { .. } => "INVALID_KEY_LENGTH",
+            CryptoError::InvalidNonceLength { .. } => "INVALID_NONCE_LENGTH",
+            CryptoError::Serialization(_) => "SERIALIZATION_FAILED",
+            CryptoError::Deserialization(_) => "DESERIALIZATION_FAILED",
+            CryptoError::ItemNotFound(_) => "ITEM_NOT_FOUND",
+            CryptoError::InvalidPasswordOptions(_) => "INVALID_PASSWORD_OPTIONS",
+            CryptoError::RandomGeneration(_) => "RANDOM_GENERATION_FAILED",
+            CryptoError::InvalidTotpSecret(_) => "INVALID_TOTP_SECRET",
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, CryptoError>;
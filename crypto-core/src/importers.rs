@@ -0,0 +1,672 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CryptoError, Result};
+use crate::matching::extract_host;
+use crate::totp::parse_otpauth_uri;
+use crate::vault::{ItemKind, VaultItem};
+
+/// Header names recognized for each field when no explicit mapping is given
+const NAME_HEADERS: &[&str] = &["name", "title"];
+const URL_HEADERS: &[&str] = &["url", "login_uri", "website", "web site", "uri"];
+const USERNAME_HEADERS: &[&str] = &["username", "login_username", "user", "email", "login"];
+const PASSWORD_HEADERS: &[&str] = &["password", "login_password", "pass"];
+const NOTES_HEADERS: &[&str] = &["notes", "note", "extra", "comments"];
+const CATEGORY_HEADERS: &[&str] = &["category", "folder", "group", "grouping"];
+
+/// CSV column names for each item field
+///
+/// Fields left as `None` are detected from common header names
+/// (e.g. `login_username`, `email`, or `user` for the username).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CsvMapping {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub notes: Option<String>,
+    pub category: Option<String>,
+}
+
+/// A row that could not be imported
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SkippedRow {
+    /// 1-based row number in the source, counting the header
+    pub row: usize,
+    pub reason: String,
+}
+
+/// Result of parsing an import file
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport {
+    pub items: Vec<VaultItem>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+/// Parse CSV text with a header row into vault items
+pub fn import_csv(text: &str, mapping: &CsvMapping) -> Result<ImportReport> {
+    let (header, rows) = csv_table(text)?;
+
+    let column = |explicit: &Option<String>, defaults: &[&str]| -> Result<Option<usize>> {
+        match explicit {
+            Some(name) => {
+                let name = name.trim().to_lowercase();
+                header
+                    .iter()
+                    .position(|h| *h == name)
+                    .map(Some)
+                    .ok_or_else(|| {
+                        CryptoError::Deserialization(format!("CSV column not found: {}", name))
+                    })
+            }
+            None => Ok(header.iter().position(|h| defaults.contains(&h.as_str()))),
+        }
+    };
+
+    let name_col = column(&mapping.name, NAME_HEADERS)?;
+    let url_col = column(&mapping.url, URL_HEADERS)?;
+    let username_col = column(&mapping.username, USERNAME_HEADERS)?;
+    let password_col = column(&mapping.password, PASSWORD_HEADERS)?;
+    let notes_col = column(&mapping.notes, NOTES_HEADERS)?;
+    let category_col = column(&mapping.category, CATEGORY_HEADERS)?;
+
+    if password_col.is_none() && username_col.is_none() {
+        return Err(CryptoError::Deserialization(
+            "CSV has no username or password column".to_string(),
+        ));
+    }
+
+    let mut report = ImportReport::default();
+    for (index, row) in rows.iter().enumerate() {
+        let row_number = index + 2;
+        let field = |col: Option<usize>| row_field(row, col);
+
+        let url = field(url_col);
+        let username = field(username_col);
+        let password = field(password_col);
+        let mut name = field(name_col);
+
+        if username.is_empty() && password.is_empty() {
+            if row.iter().all(|v| v.trim().is_empty()) {
+                continue;
+            }
+            report.skipped.push(SkippedRow {
+                row: row_number,
+                reason: "Row has no username or password".to_string(),
+            });
+            continue;
+        }
+
+        if name.is_empty() {
+            name = if url.is_empty() {
+                username.clone()
+            } else {
+                extract_host(&url)
+            };
+        }
+
+        let mut item = VaultItem::new(&name, &username, &password);
+        if !url.is_empty() {
+            item = item.with_url(&url);
+        }
+        let notes = field(notes_col);
+        if !notes.is_empty() {
+            item = item.with_notes(&notes);
+        }
+        let category = field(category_col);
+        if !category.is_empty() {
+            item = item.with_category(&category);
+        }
+        report.items.push(item);
+    }
+
+    Ok(report)
+}
+
+/// URL LastPass uses to mark secure notes
+const LASTPASS_SECURE_NOTE_URL: &str = "http://sn";
+
+/// Parse a LastPass CSV export into vault items
+///
+/// Secure notes (URL `http://sn`) become note items; `grouping` becomes the
+/// category and `fav` the favorite flag.
+pub fn import_lastpass_csv(text: &str) -> Result<ImportReport> {
+    let (header, rows) = csv_table(text)?;
+    let col = |name: &str| header.iter().position(|h| h == name);
+    let (name_col, password_col) = match (col("name"), col("password")) {
+        (Some(name), Some(password)) => (name, password),
+        _ => {
+            return Err(CryptoError::Deserialization(
+                "Not a LastPass CSV export".to_string(),
+            ))
+        }
+    };
+    let url_col = col("url");
+    let username_col = col("username");
+    let totp_col = col("totp");
+    let notes_col = col("extra");
+    let category_col = col("grouping");
+    let favorite_col = col("fav");
+
+    let mut report = ImportReport::default();
+    for (index, row) in rows.iter().enumerate() {
+        if row.iter().all(|v| v.trim().is_empty()) {
+            continue;
+        }
+        let field = |col: Option<usize>| row_field(row, col);
+
+        let url = field(url_col);
+        let username = field(username_col);
+        let password = field(Some(password_col));
+        let is_note = url == LASTPASS_SECURE_NOTE_URL;
+        let mut name = field(Some(name_col));
+        if name.is_empty() && !is_note {
+            name = if url.is_empty() {
+                username.clone()
+            } else {
+                extract_host(&url)
+            };
+        }
+        if name.is_empty() {
+            report.skipped.push(SkippedRow {
+                row: index + 2,
+                reason: "Row has no name".to_string(),
+            });
+            continue;
+        }
+
+        let mut item =
+            VaultItem::new(&name, &username, &password).with_favorite(field(favorite_col) == "1");
+        if is_note {
+            item = item.with_kind(ItemKind::SecureNote);
+        } else if !url.is_empty() {
+            item = item.with_url(&url);
+        }
+        let notes = field(notes_col);
+        if !notes.is_empty() {
+            item = item.with_notes(&notes);
+        }
+        let category = field(category_col);
+        if !category.is_empty() {
+            item = item.with_category(&category);
+        } else if is_note {
+            item = item.with_category("Secure Note");
+        }
+        let totp = field(totp_col);
+        if !totp.is_empty() {
+            item = item.with_totp_secret(&totp);
+        }
+        report.items.push(item);
+    }
+
+    Ok(report)
+}
+
+/// Parse a KeePassXC CSV export into vault items
+///
+/// The innermost group becomes the category. TOTP `otpauth://` URIs are reduced
+/// to their secret.
+pub fn import_keepass_csv(text: &str) -> Result<ImportReport> {
+    let (header, rows) = csv_table(text)?;
+    let col = |name: &str| header.iter().position(|h| h == name);
+    let (title_col, password_col) = match (col("title"), col("password")) {
+        (Some(title), Some(password)) => (title, password),
+        _ => {
+            return Err(CryptoError::Deserialization(
+                "Not a KeePass CSV export".to_string(),
+            ))
+        }
+    };
+    let group_col = col("group");
+    let username_col = col("username");
+    let url_col = col("url");
+    let notes_col = col("notes");
+    let totp_col = col("totp");
+
+    let mut report = ImportReport::default();
+    for (index, row) in rows.iter().enumerate() {
+        if row.iter().all(|v| v.trim().is_empty()) {
+            continue;
+        }
+        let field = |col: Option<usize>| row_field(row, col);
+
+        let title = field(Some(title_col));
+        let username = field(username_col);
+        let password = field(Some(password_col));
+        if title.is_empty() && username.is_empty() && password.is_empty() {
+            report.skipped.push(SkippedRow {
+                row: index + 2,
+                reason: "Row has no title, username, or password".to_string(),
+            });
+            continue;
+        }
+
+        let url = field(url_col);
+        let name = match (title.is_empty(), url.is_empty()) {
+            (false, _) => title,
+            (true, false) => extract_host(&url),
+            (true, true) => username.clone(),
+        };
+
+        let mut item = VaultItem::new(&name, &username, &password);
+        if !url.is_empty() {
+            item = item.with_url(&url);
+        }
+        let notes = field(notes_col);
+        if !notes.is_empty() {
+            item = item.with_notes(&notes);
+        }
+        let group = field(group_col);
+        let category = group.rsplit('/').next().unwrap_or_default();
+        // Entries directly under the database root have no category
+        if !category.is_empty() && group != "Root" {
+            item = item.with_category(category);
+        }
+        let totp = field(totp_col);
+        if !totp.is_empty() {
+            let secret = match parse_otpauth_uri(&totp) {
+                Ok(uri) => uri.secret,
+                Err(_) => totp,
+            };
+            item = item.with_totp_secret(&secret);
+        }
+        report.items.push(item);
+    }
+
+    Ok(report)
+}
+
+/// Bitwarden item type for logins
+const BITWARDEN_LOGIN: u8 = 1;
+/// Bitwarden item type for secure notes
+const BITWARDEN_SECURE_NOTE: u8 = 2;
+/// Bitwarden item type for payment cards
+const BITWARDEN_CARD: u8 = 3;
+/// Bitwarden item type for identities
+const BITWARDEN_IDENTITY: u8 = 4;
+/// Bitwarden custom field type for hidden values
+const BITWARDEN_FIELD_HIDDEN: u8 = 1;
+
+/// Card and identity properties stored as hidden custom fields
+const BITWARDEN_SENSITIVE_FIELDS: &[&str] =
+    &["number", "code", "ssn", "passportNumber", "licenseNumber"];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<BitwardenFolder>,
+    #[serde(default)]
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenFolder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+    #[serde(rename = "type")]
+    item_type: u8,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    favorite: bool,
+    #[serde(default)]
+    folder_id: Option<String>,
+    #[serde(default)]
+    fields: Vec<BitwardenField>,
+    #[serde(default)]
+    login: Option<BitwardenLogin>,
+    #[serde(default)]
+    card: Option<serde_json::Map<String, serde_json::Value>>,
+    #[serde(default)]
+    identity: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenField {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(rename = "type", default)]
+    field_type: u8,
+}
+
+#[derive(Deserialize, Default)]
+struct BitwardenLogin {
+    #[serde(default)]
+    uris: Option<Vec<BitwardenUri>>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    totp: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+    #[serde(default)]
+    uri: Option<String>,
+}
+
+/// Parse an unencrypted Bitwarden JSON export into vault items
+///
+/// Logins keep their first URI and TOTP secret; custom fields and card or
+/// identity details become custom fields. Encrypted exports are rejected.
+pub fn import_bitwarden_json(text: &str) -> Result<ImportReport> {
+    let export: BitwardenExport =
+        serde_json::from_str(text).map_err(|e| CryptoError::Deserialization(e.to_string()))?;
+    if export.encrypted {
+        return Err(CryptoError::Deserialization(
+            "Encrypted Bitwarden exports are not supported".to_string(),
+        ));
+    }
+
+    let mut report = ImportReport::default();
+    for (index, bw) in export.items.into_iter().enumerate() {
+        let row = index + 1;
+        let (kind, default_category) = match bw.item_type {
+            BITWARDEN_LOGIN => (ItemKind::Login, None),
+            BITWARDEN_SECURE_NOTE => (ItemKind::SecureNote, Some("Secure Note")),
+            BITWARDEN_CARD => (ItemKind::Card, Some("Credit Card")),
+            BITWARDEN_IDENTITY => (ItemKind::Identity, Some("Identity")),
+            other => {
+                report.skipped.push(SkippedRow {
+                    row,
+                    reason: format!("Unsupported Bitwarden item type {}", other),
+                });
+                continue;
+            }
+        };
+
+        let login = bw.login.unwrap_or_default();
+        let mut item = VaultItem::new(
+            bw.name.as_deref().unwrap_or_default(),
+            login.username.as_deref().unwrap_or_default(),
+            login.password.as_deref().unwrap_or_default(),
+        )
+        .with_favorite(bw.favorite)
+        .with_kind(kind);
+
+        let url = login
+            .uris
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|u| u.uri.filter(|u| !u.is_empty()));
+        if let Some(url) = url {
+            item = item.with_url(&url);
+        }
+        if let Some(notes) = bw.notes.filter(|n| !n.is_empty()) {
+            item = item.with_notes(&notes);
+        }
+
+        let folder = bw
+            .folder_id
+            .and_then(|id| export.folders.iter().find(|f| f.id == id));
+        if let Some(category) = folder.map(|f| f.name.as_str()).or(default_category) {
+            item = item.with_category(category);
+        }
+
+        if let Some(totp) = login.totp.filter(|t| !t.is_empty()) {
+            item = item.with_totp_secret(&totp);
+        }
+        for field in bw.fields {
+            let name = field.name.unwrap_or_default();
+            let value = field.value.unwrap_or_default();
+            item.add_custom_field(&name, &value, field.field_type == BITWARDEN_FIELD_HIDDEN);
+        }
+        for (key, value) in bw.card.into_iter().chain(bw.identity).flatten() {
+            if let Some(value) = value.as_str().filter(|v| !v.is_empty()) {
+                let hidden = BITWARDEN_SENSITIVE_FIELDS.contains(&key.as_str());
+                item.add_custom_field(&key, value, hidden);
+            }
+        }
+
+        if item.name.is_empty() {
+            report.skipped.push(SkippedRow {
+                row,
+                reason: "Item has no name".to_string(),
+            });
+            continue;
+        }
+        report.items.push(item);
+    }
+
+    Ok(report)
+}
+
+/// Split CSV text into a lowercased header row and the remaining data rows
+fn csv_table(text: &str) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let mut rows = parse_csv(text)?;
+    if rows.is_empty() {
+        return Err(CryptoError::Deserialization(
+            "CSV has no header row".to_string(),
+        ));
+    }
+    let header = rows
+        .remove(0)
+        .into_iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    Ok((header, rows))
+}
+
+/// Trimmed value of an optional column, empty if the column or cell is missing
+fn row_field(row: &[String], col: Option<usize>) -> String {
+    col.and_then(|c| row.get(c))
+        .map(|v| v.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Parse RFC 4180 CSV into rows of fields
+///
+/// Handles quoted fields, doubled quotes, embedded newlines, and CRLF line endings.
+pub(crate) fn parse_csv(text: &str) -> Result<Vec<Vec<String>>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(CryptoError::Deserialization(
+            "CSV has an unterminated quoted field".to_string(),
+        ));
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_quoting() {
+        let rows = parse_csv("a,\"b,c\",\"say \"\"hi\"\"\"\r\n\"multi\nline\",x,\n").unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], vec!["a", "b,c", "say \"hi\""]);
+        assert_eq!(rows[1], vec!["multi\nline", "x", ""]);
+
+        assert!(parse_csv("\"open").is_err());
+    }
+
+    #[test]
+    fn test_import_csv_detects_columns() {
+        let csv = "name,url,username,password,extra,grouping\n\
+                   GitHub,https://github.com,dev,pw1,note,Work\n\
+                   ,https://example.com/login,me,pw2,,\n\
+                   Broken,https://x.com,,,,\n\
+                   ,,,,,\n";
+        let report = import_csv(csv, &CsvMapping::default()).unwrap();
+
+        assert_eq!(report.items.len(), 2);
+        assert_eq!(report.items[0].name, "GitHub");
+        assert_eq!(report.items[0].notes.as_deref(), Some("note"));
+        assert_eq!(report.items[0].category.as_deref(), Some("Work"));
+        assert_eq!(report.items[1].name, "example.com");
+
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].row, 4);
+    }
+
+    #[test]
+    fn test_import_csv_explicit_mapping() {
+        let csv = "Site,Account,Secret\nBank,alice,hunter2\n";
+        let mapping = CsvMapping {
+            name: Some("Site".to_string()),
+            username: Some("account".to_string()),
+            password: Some("SECRET".to_string()),
+            ..Default::default()
+        };
+        let report = import_csv(csv, &mapping).unwrap();
+
+        assert_eq!(report.items.len(), 1);
+        assert_eq!(report.items[0].username, "alice");
+        assert_eq!(report.items[0].password, "hunter2");
+
+        let mapping = CsvMapping {
+            password: Some("missing".to_string()),
+            ..Default::default()
+        };
+        assert!(import_csv(csv, &mapping).is_err());
+    }
+
+    #[test]
+    fn test_import_lastpass_csv() {
+        let csv = "url,username,password,totp,extra,name,grouping,fav\n\
+                   https://github.com,dev,pw,JBSWY3DPEHPK3PXP,,GitHub,Work,1\n\
+                   http://sn,,,,wifi password,Home Wifi,,0\n\
+                   ,,,,,,,\n\
+                   http://sn,,,,orphan note,,,0\n";
+        let report = import_lastpass_csv(csv).unwrap();
+
+        assert_eq!(report.items.len(), 2);
+        let github = &report.items[0];
+        assert!(github.favorite);
+        assert_eq!(github.category.as_deref(), Some("Work"));
+        assert_eq!(github.totp_secret.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+
+        let note = &report.items[1];
+        assert_eq!(note.kind, ItemKind::SecureNote);
+        assert_eq!(note.url, None);
+        assert_eq!(note.notes.as_deref(), Some("wifi password"));
+        assert_eq!(note.category.as_deref(), Some("Secure Note"));
+
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].row, 5);
+
+        assert!(import_lastpass_csv("title,login\nx,y\n").is_err());
+    }
+
+    #[test]
+    fn test_import_keepass_csv() {
+        let csv = "\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\",\"TOTP\"\n\
+                   \"Root/Work\",\"GitHub\",\"dev\",\"pw\",\"https://github.com\",\"\",\
+                   \"otpauth://totp/GitHub:dev?secret=JBSWY3DPEHPK3PXP&issuer=GitHub\"\n\
+                   \"Root\",\"\",\"me\",\"pw2\",\"https://example.com\",\"note\",\"\"\n\
+                   \"Root\",\"\",\"\",\"\",\"https://empty.com\",\"\",\"\"\n";
+        let report = import_keepass_csv(csv).unwrap();
+
+        assert_eq!(report.items.len(), 2);
+        let github = &report.items[0];
+        assert_eq!(github.category.as_deref(), Some("Work"));
+        assert_eq!(github.totp_secret.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+
+        let example = &report.items[1];
+        assert_eq!(example.name, "example.com");
+        assert_eq!(example.category, None);
+        assert_eq!(example.notes.as_deref(), Some("note"));
+
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].row, 4);
+    }
+
+    #[test]
+    fn test_import_bitwarden_json() {
+        let json = r#"{
+            "encrypted": false,
+            "folders": [{ "id": "f1", "name": "Work" }],
+            "items": [
+                {
+                    "type": 1, "name": "GitHub", "folderId": "f1", "favorite": true,
+                    "fields": [{ "name": "PIN", "value": "1234", "type": 1 }],
+                    "login": {
+                        "uris": [{ "match": null, "uri": "https://github.com" }],
+                        "username": "dev", "password": "pw", "totp": "JBSWY3DPEHPK3PXP"
+                    }
+                },
+                { "type": 2, "name": "Wifi", "notes": "hunter2", "secureNote": { "type": 0 } },
+                { "type": 3, "name": "Visa", "card": { "brand": "Visa", "number": "4111", "code": null } },
+                { "type": 9, "name": "Future" }
+            ]
+        }"#;
+        let report = import_bitwarden_json(json).unwrap();
+
+        assert_eq!(report.items.len(), 3);
+        let github = &report.items[0];
+        assert_eq!(github.url.as_deref(), Some("https://github.com"));
+        assert_eq!(github.category.as_deref(), Some("Work"));
+        assert!(github.favorite);
+        assert_eq!(github.totp_secret.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+        assert_eq!(github.custom_fields.len(), 1);
+        assert!(github.custom_fields[0].hidden);
+
+        assert_eq!(report.items[1].kind, ItemKind::SecureNote);
+        assert_eq!(report.items[1].category.as_deref(), Some("Secure Note"));
+        assert_eq!(report.items[1].notes.as_deref(), Some("hunter2"));
+
+        let card = &report.items[2];
+        assert_eq!(card.custom_fields.len(), 2);
+        assert!(card
+            .custom_fields
+            .iter()
+            .any(|f| f.name == "number" && f.hidden));
+
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].row, 4);
+
+        assert!(import_bitwarden_json(r#"{ "encrypted": true, "items": [] }"#).is_err());
+    }
+}
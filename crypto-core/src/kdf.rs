@@ -1,7 +1,7 @@
 use argon2::{Algorithm, Argon2, Params, Version};
 use hkdf::Hkdf;
-use sha2::Sha256;
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 use crate::error::{CryptoError, Result};
 
@@ -162,6 +162,24 @@ pub fn derive_keys(master_key: &MasterKey) -> Result<KeySet> {
     })
 }
 
+/// Mix the contents of a key file into a password-derived master key
+///
+/// The Argon2 output is the HKDF input and the SHA-256 of the file is the
+/// salt, so unlocking needs both the password and the file. Any file works;
+/// its bytes are hashed, not parsed.
+pub fn combine_key_file(master_key: &MasterKey, key_file: &[u8]) -> Result<MasterKey> {
+    if key_file.is_empty() {
+        return Err(CryptoError::KeyDerivation("Key file is empty".to_string()));
+    }
+    let file_hash = Sha256::digest(key_file);
+    let hkdf = Hkdf::<Sha256>::new(Some(file_hash.as_slice()), master_key.as_bytes());
+
+    let mut key = Zeroizing::new([0u8; MASTER_KEY_SIZE]);
+    hkdf.expand(b"keydrop-key-file", key.as_mut())
+        .map_err(|e| CryptoError::KeyDerivation(e.to_string()))?;
+    Ok(MasterKey::from_bytes(*key))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -192,6 +210,27 @@ mod tests {
         assert_ne!(key_set.auth_key, key_set.sharing_key);
     }
 
+    #[test]
+    fn test_combine_key_file() {
+        let master_key = MasterKey::from_bytes([1u8; MASTER_KEY_SIZE]);
+        let combined = combine_key_file(&master_key, b"key file one").unwrap();
+
+        assert_ne!(combined.as_bytes(), master_key.as_bytes());
+        assert_eq!(
+            combined.as_bytes(),
+            combine_key_file(&master_key, b"key file one")
+                .unwrap()
+                .as_bytes()
+        );
+        assert_ne!(
+            combined.as_bytes(),
+            combine_key_file(&master_key, b"key file two")
+                .unwrap()
+                .as_bytes()
+        );
+        assert!(combine_key_file(&master_key, b"").is_err());
+    }
+
     #[test]
     fn test_salt_generation() {
         let salt1 = Salt::generate().unwrap();
@@ -8,7 +8,11 @@
 //! - **Key Derivation**: Argon2id for master key derivation, HKDF for key expansion
 //! - **Encryption**: AES-256-GCM authenticated encryption
 //! - **Vault Management**: Secure storage and retrieval of credentials
+//! - **Backups**: Passphrase-encrypted, self-contained vault backup files
+//! - **Sharing**: X25519 sealing of keys for another user, e.g. emergency contacts
 //! - **Password Generation**: Configurable random password generation
+//! - **Strength Estimation**: Pattern-aware password strength scoring
+//! - **TOTP**: RFC 6238 one-time codes for two-factor logins
 //!
 //! # Example
 //!
@@ -39,18 +43,33 @@
 //! let encrypted = vault.export(&keys.vault_key).unwrap();
 //! ```
 
+#[cfg(feature = "audit")]
+pub mod audit;
+pub mod autofill;
+pub mod backup;
 pub mod cipher;
 pub mod error;
+#[cfg(feature = "importers")]
+pub mod importers;
 pub mod kdf;
+pub mod matching;
 pub mod password;
+pub mod sharing;
+pub mod strength;
+pub mod totp;
 pub mod vault;
 
 // Re-export commonly used types
 pub use cipher::{decrypt, encrypt, EncryptedBlob};
 pub use error::{CryptoError, Result};
 pub use kdf::{derive_keys, derive_master_key, KeySet, MasterKey, Salt};
-pub use password::{generate_passphrase, generate_password, PasswordOptions};
-pub use vault::{Vault, VaultItem};
+#[cfg(feature = "eff-wordlist")]
+pub use password::generate_passphrase;
+pub use password::{generate_password, is_banned, BannedList, PasswordOptions};
+pub use vault::{
+    FallbackIcon, ItemFilter, ItemKind, ItemSort, MergePolicy, MergeReport, TrashedItem, Vault,
+    VaultDiff, VaultItem, VaultStats, VaultView, TRASH_RETENTION_SECS,
+};
 
 /// Library version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+/// Multi-label public suffixes treated as a single TLD when finding the base domain
+const MULTI_PART_SUFFIXES: &[&str] = &[
+    "co.uk", "org.uk", "ac.uk", "gov.uk", "com.au", "net.au", "org.au", "co.nz", "co.jp", "ne.jp",
+    "or.jp", "co.kr", "co.in", "com.br", "com.mx", "com.cn", "com.tw", "com.sg", "com.hk", "co.za",
+];
+
+/// Groups of domains that share one login
+const EQUIVALENT_DOMAINS: &[&[&str]] = &[
+    &["google.com", "youtube.com", "gmail.com"],
+    &["apple.com", "icloud.com"],
+    &[
+        "microsoft.com",
+        "live.com",
+        "outlook.com",
+        "office.com",
+        "microsoftonline.com",
+    ],
+    &[
+        "amazon.com",
+        "amazon.co.uk",
+        "amazon.de",
+        "amazon.fr",
+        "amazon.ca",
+        "amazon.co.jp",
+    ],
+    &["ebay.com", "ebay.co.uk", "ebay.de"],
+    &["facebook.com", "messenger.com"],
+    &["atlassian.com", "atlassian.net", "bitbucket.org"],
+];
+
+/// How an item URL matched a page URL, ordered from weakest to strongest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlMatchKind {
+    /// Different domains known to share a login (e.g. google.com and youtube.com)
+    EquivalentDomain,
+    /// Same registrable domain, different host (e.g. login.example.com and example.com)
+    BaseDomain,
+    /// Same host
+    ExactHost,
+}
+
+impl UrlMatchKind {
+    /// Confidence score between 0 and 1
+    pub fn confidence(&self) -> f64 {
+        match self {
+            UrlMatchKind::ExactHost => 1.0,
+            UrlMatchKind::BaseDomain => 0.7,
+            UrlMatchKind::EquivalentDomain => 0.4,
+        }
+    }
+}
+
+/// Which matches to accept when looking up items for a URL
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlMatchStrategy {
+    /// Only the exact host
+    ExactHost,
+    /// Exact host or same registrable domain
+    #[default]
+    BaseDomain,
+    /// Also known-equivalent domains
+    Equivalent,
+}
+
+impl UrlMatchStrategy {
+    /// Check whether a match kind is accepted by this strategy
+    pub fn accepts(&self, kind: UrlMatchKind) -> bool {
+        match self {
+            UrlMatchStrategy::ExactHost => kind == UrlMatchKind::ExactHost,
+            UrlMatchStrategy::BaseDomain => kind >= UrlMatchKind::BaseDomain,
+            UrlMatchStrategy::Equivalent => true,
+        }
+    }
+}
+
+/// Extract the lowercase host from a URL, without scheme, `www.`, credentials, or port
+pub fn extract_host(url: &str) -> String {
+    let url = url.trim();
+    let rest = match url.find("://") {
+        Some(idx) => &url[idx + 3..],
+        None => url,
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host_port = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host_port.split(':').next().unwrap_or(host_port);
+
+    let host = host.trim_end_matches('.').to_lowercase();
+    host.strip_prefix("www.")
+        .map(str::to_string)
+        .unwrap_or(host)
+}
+
+/// Get the registrable domain of a host (e.g. `login.example.co.uk` -> `example.co.uk`)
+pub fn base_domain(host: &str) -> String {
+    let labels: Vec<&str> = host.split('.').filter(|l| !l.is_empty()).collect();
+    if labels.len() <= 2 || host.parse::<std::net::IpAddr>().is_ok() {
+        return host.to_string();
+    }
+
+    let last_two = labels[labels.len() - 2..].join(".");
+    let keep = if MULTI_PART_SUFFIXES.contains(&last_two.as_str()) {
+        3
+    } else {
+        2
+    };
+    labels[labels.len() - keep.min(labels.len())..].join(".")
+}
+
+/// Check if two registrable domains belong to the same equivalence group
+fn equivalent_domains(a: &str, b: &str) -> bool {
+    EQUIVALENT_DOMAINS
+        .iter()
+        .any(|group| group.contains(&a) && group.contains(&b))
+}
+
+/// Classify how an item URL matches a page URL
+pub fn match_url(page_url: &str, item_url: &str) -> Option<UrlMatchKind> {
+    let page_host = extract_host(page_url);
+    let item_host = extract_host(item_url);
+    if page_host.is_empty() || item_host.is_empty() {
+        return None;
+    }
+
+    if page_host == item_host {
+        return Some(UrlMatchKind::ExactHost);
+    }
+
+    let page_base = base_domain(&page_host);
+    let item_base = base_domain(&item_host);
+    if page_base == item_base {
+        return Some(UrlMatchKind::BaseDomain);
+    }
+
+    if equivalent_domains(&page_base, &item_base) {
+        return Some(UrlMatchKind::EquivalentDomain);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_host() {
+        assert_eq!(extract_host("https://www.Example.com/login"), "example.com");
+        assert_eq!(
+            extract_host("https://user:pw@example.com:8443/x"),
+            "example.com"
+        );
+        assert_eq!(extract_host("example.com?next=/"), "example.com");
+        assert_eq!(
+            extract_host("http://sub.example.com#top"),
+            "sub.example.com"
+        );
+    }
+
+    #[test]
+    fn test_base_domain() {
+        assert_eq!(base_domain("login.example.com"), "example.com");
+        assert_eq!(base_domain("example.com"), "example.com");
+        assert_eq!(base_domain("a.b.example.co.uk"), "example.co.uk");
+        assert_eq!(base_domain("192.168.1.10"), "192.168.1.10");
+    }
+
+    #[test]
+    fn test_match_url() {
+        assert_eq!(
+            match_url("https://github.com/login", "https://github.com"),
+            Some(UrlMatchKind::ExactHost)
+        );
+        assert_eq!(
+            match_url("https://gist.github.com", "https://github.com"),
+            Some(UrlMatchKind::BaseDomain)
+        );
+        assert_eq!(
+            match_url("https://www.youtube.com", "https://accounts.google.com"),
+            Some(UrlMatchKind::EquivalentDomain)
+        );
+        assert_eq!(match_url("https://github.com", "https://gitlab.com"), None);
+        assert_eq!(
+            match_url("https://evilgithub.com", "https://github.com"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_strategy_accepts() {
+        assert!(UrlMatchStrategy::ExactHost.accepts(UrlMatchKind::ExactHost));
+        assert!(!UrlMatchStrategy::ExactHost.accepts(UrlMatchKind::BaseDomain));
+        assert!(UrlMatchStrategy::BaseDomain.accepts(UrlMatchKind::BaseDomain));
+        assert!(!UrlMatchStrategy::BaseDomain.accepts(UrlMatchKind::EquivalentDomain));
+        assert!(UrlMatchStrategy::Equivalent.accepts(UrlMatchKind::EquivalentDomain));
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use rand::seq::SliceRandom;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -179,89 +181,373 @@ pub fn generate_password(options: &PasswordOptions) -> Result<String> {
     String::from_utf8(password).map_err(|e| CryptoError::InvalidPasswordOptions(e.to_string()))
 }
 
+/// EFF large wordlist: 7776 words, five dice per word
+#[cfg(feature = "eff-wordlist")]
+const EFF_LARGE_WORDLIST: &str = include_str!("data/eff_large_wordlist.txt");
+
+/// Options for word-based passphrase generation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PassphraseOptions {
+    /// Number of words
+    pub word_count: usize,
+    /// String placed between words
+    pub separator: String,
+    /// Capitalize the first letter of each word
+    pub capitalize: bool,
+    /// Append a random digit to one of the words
+    pub include_number: bool,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        Self {
+            word_count: 5,
+            separator: "-".to_string(),
+            capitalize: false,
+            include_number: false,
+        }
+    }
+}
+
 /// Generate a passphrase using random words
+#[cfg(feature = "eff-wordlist")]
 pub fn generate_passphrase(word_count: usize, separator: &str) -> Result<String> {
-    if word_count == 0 {
+    generate_passphrase_with(&PassphraseOptions {
+        word_count,
+        separator: separator.to_string(),
+        ..Default::default()
+    })
+}
+
+/// Generate a passphrase with capitalization and number options
+#[cfg(feature = "eff-wordlist")]
+pub fn generate_passphrase_with(options: &PassphraseOptions) -> Result<String> {
+    if options.word_count == 0 {
         return Err(CryptoError::InvalidPasswordOptions(
             "Word count must be at least 1".to_string(),
         ));
     }
 
-    if word_count > 20 {
+    if options.word_count > 20 {
         return Err(CryptoError::InvalidPasswordOptions(
             "Word count must not exceed 20".to_string(),
         ));
     }
 
-    // EFF word list (abbreviated for size - in production use full list)
-    const WORDS: &[&str] = &[
-        "abandon", "ability", "able", "about", "above", "absent", "absorb", "abstract", "absurd",
-        "abuse", "access", "accident", "account", "accuse", "achieve", "acid", "acoustic",
-        "acquire", "across", "action", "actor", "actress", "actual", "adapt", "address", "adjust",
-        "admit", "adult", "advance", "advice", "aerobic", "affair", "afford", "afraid", "again",
-        "age", "agent", "agree", "ahead", "aim", "air", "airport", "aisle", "alarm", "album",
-        "alcohol", "alert", "alien", "allow", "almost", "alone", "alpha", "already", "also",
-        "alter", "always", "amateur", "amazing", "among", "amount", "amused", "analyst", "anchor",
-        "ancient", "anger", "angle", "angry", "animal", "ankle", "announce", "annual", "another",
-        "answer", "antenna", "antique", "anxiety", "apart", "apology", "appear", "apple",
-        "approve", "april", "arch", "arctic", "area", "arena", "argue", "arm", "armed", "armor",
-        "army", "around", "arrange", "arrest", "arrive", "arrow", "art", "artist", "artwork",
-        "aspect", "assault", "asset", "assist", "assume", "asthma", "athlete", "atom", "attack",
-        "attend", "attract", "auction", "audit", "august", "aunt", "author", "auto", "autumn",
-        "average", "avocado", "avoid", "awake", "aware", "away", "awesome", "awful", "awkward",
-        "axis", "baby", "bachelor", "bacon", "badge", "bag", "balance", "balcony", "ball",
-        "bamboo", "banana", "banner", "basket", "battle", "beach", "beauty", "become", "bedroom",
-        "before", "begin", "believe", "below", "bench", "benefit", "best", "better", "between",
-        "beyond", "bicycle", "bird", "birth", "bitter", "black", "blade", "blame", "blanket",
-        "blast", "bleak", "bless", "blind", "blood", "blossom", "blouse", "blue", "board", "boat",
-        "body", "boil", "bomb", "bone", "bonus", "book", "boost", "border", "boring", "borrow",
-        "boss", "bottom", "bounce", "box", "brain", "brand", "brave", "bread", "breeze", "brick",
-        "bridge", "brief", "bright", "bring", "broken", "bronze", "brother", "brown", "brush",
-        "bubble", "bucket", "budget", "buffalo", "build", "bulb", "bulk", "bullet", "bundle",
-        "burden", "burger", "burst", "butter", "cabin", "cable", "cactus", "cage", "camera",
-        "camp", "canal", "cancel", "candy", "cannon", "canyon", "capable", "capital", "captain",
-        "carbon", "career", "cargo", "carpet", "carry", "cart", "castle", "casual", "catalog",
-        "catch", "category", "cattle", "ceiling", "celery", "cement", "census", "century",
-        "cereal", "certain", "chair", "chalk", "champion", "change", "chaos", "chapter", "charge",
-        "charity", "cheap", "cheese", "cherry", "chicken", "chief", "child", "choice", "chunk",
-        "churn", "circle", "citizen", "city", "civil", "claim", "clap", "clarify", "claw", "clay",
-        "clean", "clerk", "clever", "click", "client", "cliff", "climb", "clinic", "clip", "clock",
-        "close", "cloth", "cloud", "clown", "club", "cluster", "coach", "coast", "coconut", "code",
-        "coffee", "coin", "collect", "color", "column", "combine", "comfort", "comic", "common",
-        "company", "concert", "conduct", "confirm", "congress", "connect", "consider", "control",
-        "convince", "cookie", "copper", "coral", "corner", "correct", "couch", "country", "couple",
-        "course", "cousin", "cover", "coyote", "crack", "cradle", "craft", "crane", "crash",
-        "crater", "crazy", "cream", "credit", "creek", "crew", "cricket", "crime", "crisp",
-        "critic", "crop", "cross", "crouch", "crowd", "crucial", "cruel", "cruise", "crumble",
-        "crush", "crystal", "cube", "culture", "cupboard", "curious", "current", "curtain",
-        "curve", "cushion", "custom", "cycle", "damage", "dance", "danger", "daring", "dash",
-        "daughter", "dawn", "decade", "decide", "decline", "decorate", "decrease", "deep",
-        "defense", "define", "delay", "deliver", "demand", "denial", "dentist", "deny", "depart",
-        "depend", "deposit", "depth", "deputy", "derive", "describe", "desert", "design", "desk",
-        "despair", "destroy", "detail", "detect", "develop", "device", "devote", "diagram",
-        "diamond", "diary", "diesel", "diet", "differ", "digital", "dignity", "dilemma", "dinner",
-        "dinosaur", "direct", "dirt", "disagree", "discover", "disease", "dish", "dismiss",
-        "display", "distance", "divert", "divide", "divorce", "dizzy", "doctor", "document",
-        "domain", "donate", "donkey", "door", "dose", "double", "dove", "draft", "dragon", "drama",
-        "drastic", "draw", "dream", "dress", "drift", "drill", "drink", "drip", "drive", "drop",
-        "drum", "dry", "duck", "dumb", "dune", "during", "dust", "dutch", "duty", "dwarf",
-        "dynamic", "eager", "eagle", "early", "earth", "easily", "east", "easy", "echo", "ecology",
-        "economy", "edge", "edit", "educate", "effort", "eight", "either", "elbow", "elder",
-        "electric", "elegant", "element", "elephant", "elevator", "elite", "else", "embark",
-        "embody", "embrace", "emerge", "emotion", "employ", "empower", "empty", "enable", "enact",
-        "endless", "endorse", "enemy", "energy", "enforce", "engage", "engine", "enhance", "enjoy",
-        "enlist", "enough", "enrich", "enroll",
-    ];
+    let mut rng = rand::thread_rng();
+    let mut words: Vec<String> = (0..options.word_count)
+        .map(|_| random_word(&mut rng, options.capitalize))
+        .collect();
 
+    if options.include_number {
+        let idx = rng.gen_range(0..words.len());
+        words[idx].push(char::from(DIGITS[rng.gen_range(0..DIGITS.len())]));
+    }
+
+    Ok(words.join(&options.separator))
+}
+
+/// Generate a numeric PIN of 4 to 16 digits
+pub fn generate_pin(length: usize) -> Result<String> {
+    if !(4..=16).contains(&length) {
+        return Err(CryptoError::InvalidPasswordOptions(
+            "PIN length must be between 4 and 16".to_string(),
+        ));
+    }
+
+    let mut rng = rand::thread_rng();
+    Ok((0..length)
+        .map(|_| char::from(DIGITS[rng.gen_range(0..DIGITS.len())]))
+        .collect())
+}
+
+/// Generate a username from two random words, e.g. `BraveCactus42`
+#[cfg(feature = "eff-wordlist")]
+pub fn generate_username(capitalize: bool, include_number: bool) -> String {
     let mut rng = rand::thread_rng();
-    let words: Vec<&str> = (0..word_count)
-        .map(|_| {
-            let idx = rng.gen_range(0..WORDS.len());
-            WORDS[idx]
+    let mut username = random_word(&mut rng, capitalize);
+    username.push_str(&random_word(&mut rng, capitalize));
+    // A few EFF words are hyphenated, e.g. "t-shirt"
+    username.retain(|c| c != '-');
+    if include_number {
+        username.push_str(&rng.gen_range(10..100).to_string());
+    }
+    username
+}
+
+/// Generate a password from a pattern
+///
+/// `l` is a lowercase letter, `u` an uppercase letter, `d` a digit, `s` a symbol,
+/// `a` any letter or digit, and `*` any of these or a symbol. `\` makes the next
+/// character literal; all other characters are copied as-is, so `uldd-dddd`
+/// yields e.g. `Kp42-8815`.
+pub fn generate_from_pattern(pattern: &str) -> Result<String> {
+    if pattern.is_empty() {
+        return Err(CryptoError::InvalidPasswordOptions(
+            "Pattern must not be empty".to_string(),
+        ));
+    }
+
+    if pattern.chars().count() > 1024 {
+        return Err(CryptoError::InvalidPasswordOptions(
+            "Pattern must not exceed 1024 characters".to_string(),
+        ));
+    }
+
+    let alphanumeric: Vec<u8> = [LOWERCASE, UPPERCASE, DIGITS].concat();
+    let any: Vec<u8> = [LOWERCASE, UPPERCASE, DIGITS, SYMBOLS].concat();
+
+    let mut rng = rand::thread_rng();
+    let mut output = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let set: &[u8] = match c {
+            'l' => LOWERCASE,
+            'u' => UPPERCASE,
+            'd' => DIGITS,
+            's' => SYMBOLS,
+            'a' => &alphanumeric,
+            '*' => &any,
+            '\\' => {
+                let literal = chars.next().ok_or_else(|| {
+                    CryptoError::InvalidPasswordOptions(
+                        "Pattern ends with an unfinished escape".to_string(),
+                    )
+                })?;
+                output.push(literal);
+                continue;
+            }
+            _ => {
+                output.push(c);
+                continue;
+            }
+        };
+        output.push(char::from(set[rng.gen_range(0..set.len())]));
+    }
+
+    Ok(output)
+}
+
+/// Pick a random word, optionally capitalized
+#[cfg(feature = "eff-wordlist")]
+fn random_word(rng: &mut impl Rng, capitalize: bool) -> String {
+    let words = &DicewareList::eff_large().words;
+    let word = &words[rng.gen_range(0..words.len())];
+    if !capitalize {
+        return word.to_string();
+    }
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Number of faces on a diceware die
+const DIE_FACES: u8 = 6;
+
+/// Word list indexed by dice rolls, such as the EFF large wordlist
+#[derive(Clone, Debug)]
+pub struct DicewareList {
+    dice_per_word: usize,
+    /// Words ordered by their dice index read as a base-6 number
+    words: Vec<String>,
+}
+
+impl DicewareList {
+    /// Parse a list in the EFF format (`11111<tab>abacus`, one entry per line)
+    ///
+    /// Every combination of rolls for the detected dice count must be present.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut entries: Vec<(usize, String)> = Vec::new();
+        let mut dice_per_word = 0;
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let (rolls, word) = match (parts.next(), parts.next()) {
+                (Some(rolls), Some(word)) => (rolls, word),
+                _ => {
+                    return Err(CryptoError::Deserialization(format!(
+                        "Invalid diceware line: {}",
+                        line
+                    )))
+                }
+            };
+
+            if dice_per_word == 0 {
+                dice_per_word = rolls.len();
+            } else if rolls.len() != dice_per_word {
+                return Err(CryptoError::Deserialization(format!(
+                    "Inconsistent dice count in line: {}",
+                    line
+                )));
+            }
+
+            let rolls: Vec<u8> = rolls.bytes().map(|b| b.wrapping_sub(b'0')).collect();
+            let index = dice_index(&rolls).map_err(|_| {
+                CryptoError::Deserialization(format!("Invalid dice rolls in line: {}", line))
+            })?;
+            entries.push((index, word.to_string()));
+        }
+
+        if dice_per_word == 0 {
+            return Err(CryptoError::Deserialization(
+                "Empty diceware list".to_string(),
+            ));
+        }
+
+        let expected = (DIE_FACES as usize)
+            .checked_pow(dice_per_word as u32)
+            .ok_or_else(|| CryptoError::Deserialization("Too many dice per word".to_string()))?;
+        if entries.len() != expected {
+            return Err(CryptoError::Deserialization(format!(
+                "Diceware list must have {} entries, got {}",
+                expected,
+                entries.len()
+            )));
+        }
+
+        entries.sort_by_key(|(index, _)| *index);
+        if entries
+            .iter()
+            .enumerate()
+            .any(|(i, (index, _))| i != *index)
+        {
+            return Err(CryptoError::Deserialization(
+                "Diceware list has duplicate dice indices".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            dice_per_word,
+            words: entries.into_iter().map(|(_, word)| word).collect(),
         })
-        .collect();
+    }
+
+    /// The built-in EFF large wordlist
+    #[cfg(feature = "eff-wordlist")]
+    pub fn eff_large() -> &'static DicewareList {
+        static EFF_LARGE: std::sync::OnceLock<DicewareList> = std::sync::OnceLock::new();
+        EFF_LARGE
+            .get_or_init(|| DicewareList::parse(EFF_LARGE_WORDLIST).expect("EFF list is valid"))
+    }
+
+    /// Number of dice rolled per word
+    pub fn dice_per_word(&self) -> usize {
+        self.dice_per_word
+    }
+
+    /// Look up the word for one word's worth of rolls
+    pub fn word_for_rolls(&self, rolls: &[u8]) -> Result<&str> {
+        if rolls.len() != self.dice_per_word {
+            return Err(CryptoError::InvalidPasswordOptions(format!(
+                "Expected {} dice rolls per word, got {}",
+                self.dice_per_word,
+                rolls.len()
+            )));
+        }
+        Ok(&self.words[dice_index(rolls)?])
+    }
+
+    /// Get number of words
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Check if list is empty
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Diceware passphrase together with the rolls that produced it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DicewarePassphrase {
+    /// Words joined with the separator
+    pub passphrase: String,
+    /// Selected words in order
+    pub words: Vec<String>,
+    /// Dice rolls (1-6) used for each word
+    pub rolls: Vec<Vec<u8>>,
+}
+
+/// Generate a diceware passphrase
+///
+/// When `rolls` is given it must hold `word_count * list.dice_per_word()` values
+/// from 1 to 6, e.g. from physical dice; otherwise rolls are drawn from the
+/// system CSPRNG. The returned roll record reproduces the same passphrase.
+pub fn generate_diceware(
+    list: &DicewareList,
+    word_count: usize,
+    separator: &str,
+    rolls: Option<&[u8]>,
+) -> Result<DicewarePassphrase> {
+    if word_count == 0 {
+        return Err(CryptoError::InvalidPasswordOptions(
+            "Word count must be at least 1".to_string(),
+        ));
+    }
+
+    if word_count > 20 {
+        return Err(CryptoError::InvalidPasswordOptions(
+            "Word count must not exceed 20".to_string(),
+        ));
+    }
 
-    Ok(words.join(separator))
+    let total = word_count * list.dice_per_word();
+    let rolls: Vec<u8> = match rolls {
+        Some(rolls) => {
+            if rolls.len() != total {
+                return Err(CryptoError::InvalidPasswordOptions(format!(
+                    "Expected {} dice rolls, got {}",
+                    total,
+                    rolls.len()
+                )));
+            }
+            rolls.to_vec()
+        }
+        None => {
+            let mut rng = rand::thread_rng();
+            (0..total).map(|_| rng.gen_range(1..=DIE_FACES)).collect()
+        }
+    };
+
+    let mut words = Vec::with_capacity(word_count);
+    let mut record = Vec::with_capacity(word_count);
+    for chunk in rolls.chunks(list.dice_per_word()) {
+        words.push(list.word_for_rolls(chunk)?.to_string());
+        record.push(chunk.to_vec());
+    }
+
+    Ok(DicewarePassphrase {
+        passphrase: words.join(separator),
+        words,
+        rolls: record,
+    })
+}
+
+/// Convert dice rolls (1-6) into a list index
+fn dice_index(rolls: &[u8]) -> Result<usize> {
+    rolls.iter().try_fold(0usize, |index, &roll| {
+        if !(1..=DIE_FACES).contains(&roll) {
+            return Err(CryptoError::InvalidPasswordOptions(format!(
+                "Dice roll must be between 1 and {}, got {}",
+                DIE_FACES, roll
+            )));
+        }
+        index
+            .checked_mul(DIE_FACES as usize)
+            .and_then(|index| index.checked_add((roll - 1) as usize))
+            .ok_or_else(|| {
+                CryptoError::InvalidPasswordOptions("Too many dice rolls per word".to_string())
+            })
+    })
 }
 
 /// Calculate password entropy in bits
@@ -294,6 +580,95 @@ pub fn calculate_entropy(options: &PasswordOptions) -> f64 {
     options.length as f64 * (pool_size as f64).log2()
 }
 
+/// Built-in list of commonly used passwords, one per line
+#[cfg(feature = "banned-list")]
+const COMMON_PASSWORDS: &str = include_str!("data/common_passwords.txt");
+
+/// A set of passwords that must not be used
+///
+/// Entries are compared case-insensitively with surrounding whitespace
+/// ignored, so "Password " and "password" are treated as the same entry.
+#[derive(Clone, Debug, Default)]
+pub struct BannedList {
+    entries: HashSet<String>,
+}
+
+impl BannedList {
+    /// Create an empty list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a list from text with one password per line
+    ///
+    /// Blank lines and lines starting with `#` are skipped.
+    pub fn from_text(text: &str) -> Self {
+        let mut list = Self::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            list.insert(line);
+        }
+        list
+    }
+
+    /// The built-in list of common passwords
+    #[cfg(feature = "banned-list")]
+    pub fn builtin() -> &'static BannedList {
+        static BUILTIN: std::sync::OnceLock<BannedList> = std::sync::OnceLock::new();
+        BUILTIN.get_or_init(|| BannedList::from_text(COMMON_PASSWORDS))
+    }
+
+    /// Add a password to the list
+    pub fn insert(&mut self, password: &str) {
+        let normalized = normalize_banned(password);
+        if !normalized.is_empty() {
+            self.entries.insert(normalized);
+        }
+    }
+
+    /// Add every entry of another list to this one
+    pub fn extend(&mut self, other: &BannedList) {
+        self.entries.extend(other.entries.iter().cloned());
+    }
+
+    /// Check whether the list contains the given password
+    pub fn contains(&self, password: &str) -> bool {
+        self.entries.contains(&normalize_banned(password))
+    }
+
+    /// Get number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if list is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for BannedList {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut list = Self::new();
+        for password in iter {
+            list.insert(password.as_ref());
+        }
+        list
+    }
+}
+
+fn normalize_banned(password: &str) -> String {
+    password.trim().to_lowercase()
+}
+
+/// Check if a password appears in the given banned list
+pub fn is_banned(password: &str, list: &BannedList) -> bool {
+    list.contains(password)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -379,6 +754,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "eff-wordlist")]
     fn test_generate_passphrase() {
         let passphrase = generate_passphrase(4, "-").unwrap();
         let words: Vec<&str> = passphrase.split('-').collect();
@@ -387,6 +763,58 @@ mod tests {
         assert!(words.iter().all(|w| !w.is_empty()));
     }
 
+    #[test]
+    #[cfg(feature = "eff-wordlist")]
+    fn test_generate_passphrase_with_options() {
+        let options = PassphraseOptions {
+            word_count: 4,
+            separator: " ".to_string(),
+            capitalize: true,
+            include_number: true,
+        };
+        let passphrase = generate_passphrase_with(&options).unwrap();
+        let words: Vec<&str> = passphrase.split(' ').collect();
+
+        assert_eq!(words.len(), 4);
+        assert!(words
+            .iter()
+            .all(|w| w.starts_with(|c: char| c.is_uppercase())));
+        assert_eq!(passphrase.chars().filter(char::is_ascii_digit).count(), 1);
+    }
+
+    #[test]
+    fn test_generate_pin() {
+        let pin = generate_pin(6).unwrap();
+        assert_eq!(pin.len(), 6);
+        assert!(pin.chars().all(|c| c.is_ascii_digit()));
+        assert!(generate_pin(3).is_err());
+        assert!(generate_pin(17).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "eff-wordlist")]
+    fn test_generate_username() {
+        let username = generate_username(true, true);
+        assert!(username.starts_with(|c: char| c.is_uppercase()));
+        assert!(username.ends_with(|c: char| c.is_ascii_digit()));
+        assert!(!generate_username(false, false).contains(|c: char| !c.is_lowercase()));
+    }
+
+    #[test]
+    fn test_generate_from_pattern() {
+        let password = generate_from_pattern("uldd-\\d\\l*").unwrap();
+        let chars: Vec<char> = password.chars().collect();
+
+        assert_eq!(chars.len(), 8);
+        assert!(chars[0].is_ascii_uppercase());
+        assert!(chars[1].is_ascii_lowercase());
+        assert!(chars[2].is_ascii_digit() && chars[3].is_ascii_digit());
+        assert_eq!(&password[4..7], "-dl");
+
+        assert!(generate_from_pattern("").is_err());
+        assert!(generate_from_pattern("dd\\").is_err());
+    }
+
     #[test]
     fn test_calculate_entropy() {
         let options = PasswordOptions::new(16);
@@ -396,4 +824,102 @@ mod tests {
         assert!(entropy > 100.0);
         assert!(entropy < 110.0);
     }
+
+    #[test]
+    fn test_banned_list_custom() {
+        let list: BannedList = ["hunter2", "CorrectHorse"].into_iter().collect();
+
+        assert_eq!(list.len(), 2);
+        assert!(is_banned("hunter2", &list));
+        assert!(is_banned("correcthorse", &list));
+        assert!(is_banned("  HUNTER2 ", &list));
+        assert!(!is_banned("hunter3", &list));
+    }
+
+    #[test]
+    fn test_banned_list_from_text() {
+        let list = BannedList::from_text("# comment\nletmein\n\n  qwerty  \n");
+
+        assert_eq!(list.len(), 2);
+        assert!(list.contains("letmein"));
+        assert!(list.contains("qwerty"));
+        assert!(!list.contains("# comment"));
+    }
+
+    #[cfg(feature = "banned-list")]
+    #[test]
+    fn test_banned_list_builtin() {
+        let list = BannedList::builtin();
+
+        assert!(!list.is_empty());
+        assert!(is_banned("password", list));
+        assert!(is_banned("Qwerty123", list));
+        assert!(!is_banned(
+            &generate_password(&PasswordOptions::new(24)).unwrap(),
+            list
+        ));
+    }
+
+    fn two_dice_list() -> DicewareList {
+        let mut text = String::new();
+        for a in 1..=6 {
+            for b in 1..=6 {
+                text.push_str(&format!("{}{}\tword{}{}\n", a, b, a, b));
+            }
+        }
+        DicewareList::parse(&text).unwrap()
+    }
+
+    #[test]
+    fn test_diceware_list_parse() {
+        let list = two_dice_list();
+
+        assert_eq!(list.len(), 36);
+        assert_eq!(list.dice_per_word(), 2);
+        assert_eq!(list.word_for_rolls(&[1, 1]).unwrap(), "word11");
+        assert_eq!(list.word_for_rolls(&[6, 6]).unwrap(), "word66");
+        assert!(list.word_for_rolls(&[7, 1]).is_err());
+        assert!(list.word_for_rolls(&[1]).is_err());
+
+        assert!(DicewareList::parse("11\tone\n12\ttwo\n").is_err());
+        assert!(DicewareList::parse("").is_err());
+        assert!(DicewareList::parse(&format!("{}\tlong\n", "6".repeat(40))).is_err());
+    }
+
+    #[test]
+    fn test_diceware_with_rolls() {
+        let list = two_dice_list();
+        let result = generate_diceware(&list, 3, " ", Some(&[1, 2, 3, 4, 6, 5])).unwrap();
+
+        assert_eq!(result.passphrase, "word12 word34 word65");
+        assert_eq!(result.rolls, vec![vec![1, 2], vec![3, 4], vec![6, 5]]);
+
+        assert!(generate_diceware(&list, 3, " ", Some(&[1, 2, 3])).is_err());
+        assert!(generate_diceware(&list, 1, " ", Some(&[0, 2])).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "eff-wordlist")]
+    fn test_diceware_eff_large() {
+        let list = DicewareList::eff_large();
+        assert_eq!(list.len(), 7776);
+        assert_eq!(list.dice_per_word(), 5);
+        assert_eq!(list.word_for_rolls(&[1, 1, 1, 1, 1]).unwrap(), "abacus");
+        assert_eq!(list.word_for_rolls(&[6, 6, 6, 6, 6]).unwrap(), "zoom");
+
+        let result = generate_diceware(list, 6, " ", None).unwrap();
+        assert_eq!(result.rolls.len(), 6);
+        assert!(result.rolls.iter().all(|rolls| rolls.len() == 5));
+    }
+
+    #[test]
+    fn test_diceware_random_is_reproducible() {
+        let list = two_dice_list();
+        let result = generate_diceware(&list, 5, "-", None).unwrap();
+
+        assert_eq!(result.words.len(), 5);
+        let flat: Vec<u8> = result.rolls.concat();
+        let replay = generate_diceware(&list, 5, "-", Some(&flat)).unwrap();
+        assert_eq!(replay.passphrase, result.passphrase);
+    }
 }
@@ -0,0 +1,120 @@
+//! Handing keys to another user
+//!
+//! A user's sharing key (see [`crate::kdf::KeySet`]) doubles as an X25519
+//! secret. Its public half is published, so anyone can wrap a key for that
+//! user without talking to them; only the holder of the sharing key can
+//! unwrap it. Emergency access uses this to give a contact the owner's vault
+//! key.
+
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::cipher::{self, KEY_SIZE};
+use crate::error::{CryptoError, Result};
+
+/// Size of an X25519 public key in bytes
+pub const PUBLIC_KEY_SIZE: usize = 32;
+
+/// HKDF info for the key derived from the Diffie-Hellman secret
+const SEAL_INFO: &[u8] = b"keydrop-sealed-key-v1";
+
+/// Public key others wrap keys for, derived from the recipient's sharing key
+pub fn public_key(sharing_key: &[u8; KEY_SIZE]) -> [u8; PUBLIC_KEY_SIZE] {
+    PublicKey::from(&StaticSecret::from(*sharing_key)).to_bytes()
+}
+
+/// Wrap `key` so only the holder of the sharing key behind `recipient` can open it
+///
+/// Returns `ephemeral public key || nonce || ciphertext`; open it with
+/// [`open_key`].
+pub fn seal_key(key: &[u8; KEY_SIZE], recipient: &[u8; PUBLIC_KEY_SIZE]) -> Result<Vec<u8>> {
+    let recipient = PublicKey::from(*recipient);
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&recipient);
+    if !shared.was_contributory() {
+        return Err(CryptoError::Encryption("Invalid recipient key".to_string()));
+    }
+
+    let kek = seal_kek(shared.as_bytes(), &ephemeral_public, &recipient)?;
+    let mut sealed = ephemeral_public.to_bytes().to_vec();
+    sealed.extend(cipher::wrap_key(key, &kek)?);
+    Ok(sealed)
+}
+
+/// Recover a key produced by [`seal_key`] for this sharing key
+pub fn open_key(sealed: &[u8], sharing_key: &[u8; KEY_SIZE]) -> Result<Zeroizing<[u8; KEY_SIZE]>> {
+    if sealed.len() < PUBLIC_KEY_SIZE {
+        return Err(CryptoError::Decryption("Sealed key too short".to_string()));
+    }
+    let (ephemeral_public, wrapped) = sealed.split_at(PUBLIC_KEY_SIZE);
+    let ephemeral_public: [u8; PUBLIC_KEY_SIZE] = ephemeral_public.try_into().unwrap();
+    let ephemeral_public = PublicKey::from(ephemeral_public);
+
+    let secret = StaticSecret::from(*sharing_key);
+    let shared = secret.diffie_hellman(&ephemeral_public);
+    let kek = seal_kek(
+        shared.as_bytes(),
+        &ephemeral_public,
+        &PublicKey::from(&secret),
+    )?;
+    cipher::unwrap_key(wrapped, &kek)
+}
+
+/// Key-encryption key for a sealed key, bound to both public keys
+fn seal_kek(
+    shared: &[u8; 32],
+    ephemeral_public: &PublicKey,
+    recipient: &PublicKey,
+) -> Result<Zeroizing<[u8; KEY_SIZE]>> {
+    let mut salt = [0u8; 2 * PUBLIC_KEY_SIZE];
+    salt[..PUBLIC_KEY_SIZE].copy_from_slice(ephemeral_public.as_bytes());
+    salt[PUBLIC_KEY_SIZE..].copy_from_slice(recipient.as_bytes());
+
+    let mut kek = Zeroizing::new([0u8; KEY_SIZE]);
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(SEAL_INFO, kek.as_mut())
+        .map_err(|e| CryptoError::KeyDerivation(e.to_string()))?;
+    Ok(kek)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    fn random_key() -> [u8; KEY_SIZE] {
+        let mut key = [0u8; KEY_SIZE];
+        OsRng.fill_bytes(&mut key);
+        key
+    }
+
+    #[test]
+    fn test_seal_open_key() {
+        let sharing_key = random_key();
+        let vault_key = random_key();
+
+        let sealed = seal_key(&vault_key, &public_key(&sharing_key)).unwrap();
+        assert_eq!(*open_key(&sealed, &sharing_key).unwrap(), vault_key);
+
+        // Sealing is randomized
+        let again = seal_key(&vault_key, &public_key(&sharing_key)).unwrap();
+        assert_ne!(sealed, again);
+
+        // Only the recipient can open it
+        assert!(open_key(&sealed, &random_key()).is_err());
+        assert!(open_key(&sealed[..PUBLIC_KEY_SIZE - 1], &sharing_key).is_err());
+
+        let mut tampered = sealed.clone();
+        tampered[0] ^= 1;
+        assert!(open_key(&tampered, &sharing_key).is_err());
+    }
+
+    #[test]
+    fn test_low_order_recipient_is_rejected() {
+        assert!(seal_key(&random_key(), &[0u8; PUBLIC_KEY_SIZE]).is_err());
+    }
+}
@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+
+/// Guesses per second assumed for crack time (offline attack on a fast hash)
+const GUESSES_PER_SECOND: f64 = 1e10;
+
+/// Keyboard rows checked for walks like "qwerty" or "asdf"
+const KEYBOARD_ROWS: &[&str] = &["qwertyuiop", "asdfghjkl", "zxcvbnm", "1234567890"];
+
+/// Entropy thresholds (bits) for scores 1 through 4
+const SCORE_THRESHOLDS: [f64; 4] = [25.0, 40.0, 60.0, 80.0];
+
+/// Strength estimate for a password
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StrengthEstimate {
+    /// 0 (very weak) to 4 (very strong)
+    pub score: u8,
+    /// Estimated entropy after pattern penalties
+    pub entropy_bits: f64,
+    /// Expected seconds to crack offline at 10 billion guesses per second
+    pub crack_time_seconds: f64,
+    /// Human-readable crack time, e.g. "3 hours" or "centuries"
+    pub crack_time_display: String,
+    /// Main problem with the password, if any
+    pub warning: Option<String>,
+    /// Ways to make the password stronger
+    pub suggestions: Vec<String>,
+}
+
+/// Predictable pattern found in a password
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pattern {
+    Repeat,
+    Sequence,
+    Keyboard,
+    Year,
+}
+
+/// Estimate password strength, zxcvbn-style
+///
+/// Entropy starts from the character classes used and is reduced for
+/// repeats, sequences, keyboard walks, years, and common passwords.
+pub fn estimate_strength(password: &str) -> StrengthEstimate {
+    let chars: Vec<char> = password.chars().collect();
+    let mut suggestions = Vec::new();
+    let mut warning = None;
+
+    let (entropy_bits, patterns) = if is_common(password) {
+        warning = Some("This is a commonly used password".to_string());
+        (10.0, Vec::new())
+    } else {
+        pattern_entropy(&chars)
+    };
+
+    for pattern in &patterns {
+        let (pattern_warning, suggestion) = match pattern {
+            Pattern::Repeat => (
+                "Repeated characters are easy to guess",
+                "Avoid repeated characters",
+            ),
+            Pattern::Sequence => (
+                "Sequences like abc or 123 are easy to guess",
+                "Avoid sequences",
+            ),
+            Pattern::Keyboard => (
+                "Keyboard patterns are easy to guess",
+                "Avoid keyboard patterns",
+            ),
+            Pattern::Year => ("Years are easy to guess", "Avoid years and dates"),
+        };
+        warning.get_or_insert_with(|| pattern_warning.to_string());
+        suggestions.push(suggestion.to_string());
+    }
+
+    if chars.len() < 12 {
+        suggestions.push("Use at least 12 characters".to_string());
+    }
+    if chars.len() < 16 {
+        if !chars.iter().any(|c| c.is_ascii_uppercase()) {
+            suggestions.push("Add uppercase letters".to_string());
+        }
+        if !chars.iter().any(|c| c.is_ascii_digit()) {
+            suggestions.push("Add digits".to_string());
+        }
+        if !chars.iter().any(|c| !c.is_ascii_alphanumeric()) {
+            suggestions.push("Add symbols".to_string());
+        }
+    }
+
+    let score = SCORE_THRESHOLDS
+        .iter()
+        .take_while(|threshold| entropy_bits >= **threshold)
+        .count() as u8;
+    let crack_time_seconds = if entropy_bits <= 0.0 {
+        0.0
+    } else {
+        2f64.powf(entropy_bits - 1.0) / GUESSES_PER_SECOND
+    };
+
+    StrengthEstimate {
+        score,
+        entropy_bits,
+        crack_time_seconds,
+        crack_time_display: display_time(crack_time_seconds),
+        warning,
+        suggestions,
+    }
+}
+
+#[cfg(feature = "banned-list")]
+fn is_common(password: &str) -> bool {
+    crate::password::BannedList::builtin().contains(password)
+}
+
+#[cfg(not(feature = "banned-list"))]
+fn is_common(_password: &str) -> bool {
+    false
+}
+
+/// Sum per-character entropy, charging each pattern run only for its start and length
+fn pattern_entropy(chars: &[char]) -> (f64, Vec<Pattern>) {
+    let char_bits = pool_size(chars).log2();
+    let mut bits = 0.0;
+    let mut patterns = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match longest_pattern(&chars[i..]) {
+            Some((pattern, len)) => {
+                bits += match pattern {
+                    Pattern::Year => 200f64.log2(),
+                    _ => char_bits + (len as f64).log2(),
+                };
+                if !patterns.contains(&pattern) {
+                    patterns.push(pattern);
+                }
+                i += len;
+            }
+            None => {
+                bits += char_bits;
+                i += 1;
+            }
+        }
+    }
+
+    (bits, patterns)
+}
+
+/// Number of possible characters given the classes present
+fn pool_size(chars: &[char]) -> f64 {
+    let mut pool = 0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if chars
+        .iter()
+        .any(|c| c.is_ascii() && !c.is_ascii_alphanumeric())
+    {
+        pool += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    pool.max(1) as f64
+}
+
+/// Find the longest pattern starting at the beginning of `chars`
+fn longest_pattern(chars: &[char]) -> Option<(Pattern, usize)> {
+    let candidates = [
+        (Pattern::Repeat, repeat_len(chars), 3),
+        (Pattern::Sequence, sequence_len(chars), 3),
+        (Pattern::Keyboard, keyboard_len(chars), 4),
+        (Pattern::Year, year_len(chars), 4),
+    ];
+    candidates
+        .into_iter()
+        .filter(|(_, len, min)| len >= min)
+        .max_by_key(|(_, len, _)| *len)
+        .map(|(pattern, len, _)| (pattern, len))
+}
+
+fn repeat_len(chars: &[char]) -> usize {
+    chars
+        .iter()
+        .take_while(|c| Some(*c) == chars.first())
+        .count()
+}
+
+fn sequence_len(chars: &[char]) -> usize {
+    if chars.len() < 2 || !chars[0].is_ascii_alphanumeric() {
+        return chars.len().min(1);
+    }
+    let step = chars[1] as i32 - chars[0] as i32;
+    if step.abs() != 1 {
+        return 1;
+    }
+    1 + chars
+        .windows(2)
+        .take_while(|w| w[1].is_ascii_alphanumeric() && w[1] as i32 - w[0] as i32 == step)
+        .count()
+}
+
+fn keyboard_len(chars: &[char]) -> usize {
+    let lower: String = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let mut best = 0;
+    for row in KEYBOARD_ROWS {
+        let reversed: String = row.chars().rev().collect();
+        for row in [row.to_string(), reversed] {
+            let len = (1..=lower.len().min(row.len()))
+                .rev()
+                .find(|&n| lower.is_char_boundary(n) && row.contains(&lower[..n]))
+                .unwrap_or(0);
+            best = best.max(len);
+        }
+    }
+    best
+}
+
+fn year_len(chars: &[char]) -> usize {
+    match chars {
+        ['1', '9', a, b, ..] | ['2', '0', a, b, ..] if a.is_ascii_digit() && b.is_ascii_digit() => {
+            4
+        }
+        _ => 0,
+    }
+}
+
+/// Format seconds as a rough human-readable duration
+fn display_time(seconds: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = MINUTE * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+    const MONTH: f64 = DAY * 31.0;
+    const YEAR: f64 = MONTH * 12.0;
+    const CENTURY: f64 = YEAR * 100.0;
+
+    let (amount, unit) = match seconds {
+        s if s < 1.0 => return "less than a second".to_string(),
+        s if s < MINUTE => (s, "second"),
+        s if s < HOUR => (s / MINUTE, "minute"),
+        s if s < DAY => (s / HOUR, "hour"),
+        s if s < MONTH => (s / DAY, "day"),
+        s if s < YEAR => (s / MONTH, "month"),
+        s if s < CENTURY => (s / YEAR, "year"),
+        _ => return "centuries".to_string(),
+    };
+    let amount = amount.round() as u64;
+    if amount == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", amount, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strength_weak_patterns() {
+        let repeated = estimate_strength("aaaaaaaaaaaa");
+        assert_eq!(repeated.score, 0);
+        assert!(repeated
+            .suggestions
+            .contains(&"Avoid repeated characters".to_string()));
+
+        let keyboard = estimate_strength("ZXCVBNMasdf");
+        assert!(keyboard.score <= 1);
+        assert_eq!(
+            keyboard.warning.as_deref(),
+            Some("Keyboard patterns are easy to guess")
+        );
+
+        let sequence = estimate_strength("abcdefgh1987");
+        assert!(sequence.score <= 1);
+    }
+
+    #[test]
+    fn test_strength_strong() {
+        let strong = estimate_strength("Xk9#mP2$vL7@qR4!");
+        assert_eq!(strong.score, 4);
+        assert!(strong.warning.is_none());
+        assert_eq!(strong.crack_time_display, "centuries");
+
+        let passphrase = estimate_strength("correct-horse-battery-staple");
+        assert!(passphrase.score >= 3);
+    }
+
+    #[cfg(feature = "banned-list")]
+    #[test]
+    fn test_strength_common_password() {
+        let common = estimate_strength("password123");
+        assert_eq!(common.score, 0);
+        assert_eq!(common.crack_time_display, "less than a second");
+        assert!(common.warning.is_some());
+    }
+
+    #[test]
+    fn test_display_time() {
+        assert_eq!(display_time(0.5), "less than a second");
+        assert_eq!(display_time(90.0), "2 minutes");
+        assert_eq!(display_time(3600.0), "1 hour");
+        assert_eq!(display_time(1e12), "centuries");
+    }
+
+    #[test]
+    fn test_strength_empty() {
+        let empty = estimate_strength("");
+        assert_eq!(empty.score, 0);
+        assert_eq!(empty.entropy_bits, 0.0);
+    }
+}
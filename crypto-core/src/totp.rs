@@ -0,0 +1,372 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+use crate::error::{CryptoError, Result};
+
+/// Base32 alphabet (RFC 4648)
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// HMAC algorithm used for TOTP
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TotpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// Options for TOTP generation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TotpOptions {
+    /// HMAC algorithm
+    pub algorithm: TotpAlgorithm,
+    /// Number of digits in the code (6-8)
+    pub digits: u32,
+    /// Time step in seconds
+    pub period: u64,
+}
+
+impl Default for TotpOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: TotpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+        }
+    }
+}
+
+/// A generated TOTP code
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TotpCode {
+    /// Zero-padded code
+    pub code: String,
+    /// Seconds until the code changes
+    pub seconds_remaining: u64,
+    /// Time step in seconds
+    pub period: u64,
+}
+
+/// Decode a base32 TOTP secret
+///
+/// Case, whitespace, hyphens, and `=` padding are ignored.
+pub fn decode_secret(secret: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(secret.len() * 5 / 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+
+    for c in secret.chars() {
+        if c.is_whitespace() || c == '-' || c == '=' {
+            continue;
+        }
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a as char == c.to_ascii_uppercase())
+            .ok_or_else(|| {
+                CryptoError::InvalidTotpSecret(format!("Invalid base32 character: {}", c))
+            })?;
+
+        buffer = (buffer << 5) | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    if bytes.is_empty() {
+        return Err(CryptoError::InvalidTotpSecret(
+            "Secret is empty".to_string(),
+        ));
+    }
+
+    Ok(bytes)
+}
+
+/// Encode a raw key as an unpadded base32 TOTP secret
+pub fn encode_secret(key: &[u8]) -> String {
+    let mut encoded = String::with_capacity(key.len().div_ceil(5) * 8);
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+
+    for &byte in key {
+        buffer = (buffer << 8) | byte as u64;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    encoded
+}
+
+/// Generate the TOTP code for a base32 secret at the given Unix timestamp
+pub fn generate_totp(secret: &str, options: &TotpOptions, timestamp: u64) -> Result<TotpCode> {
+    let key = decode_secret(secret)?;
+    generate_totp_with_key(&key, options, timestamp)
+}
+
+/// Generate the TOTP code for a raw key at the given Unix timestamp
+pub fn generate_totp_with_key(
+    key: &[u8],
+    options: &TotpOptions,
+    timestamp: u64,
+) -> Result<TotpCode> {
+    if options.period == 0 {
+        return Err(CryptoError::InvalidTotpSecret(
+            "Period must be at least 1 second".to_string(),
+        ));
+    }
+
+    let counter = timestamp / options.period;
+    Ok(TotpCode {
+        code: hotp(key, counter, options.digits, options.algorithm)?,
+        seconds_remaining: options.period - timestamp % options.period,
+        period: options.period,
+    })
+}
+
+/// Generate an HOTP code (RFC 4226) for the given counter
+pub fn hotp(key: &[u8], counter: u64, digits: u32, algorithm: TotpAlgorithm) -> Result<String> {
+    if !(6..=8).contains(&digits) {
+        return Err(CryptoError::InvalidTotpSecret(format!(
+            "Digits must be between 6 and 8, got {}",
+            digits
+        )));
+    }
+
+    let message = counter.to_be_bytes();
+    let digest = match algorithm {
+        TotpAlgorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(key, &message)?,
+        TotpAlgorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(key, &message)?,
+        TotpAlgorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(key, &message)?,
+    };
+
+    // Dynamic truncation
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    let code = binary % 10u32.pow(digits);
+    Ok(format!("{:0width$}", code, width = digits as usize))
+}
+
+fn hmac_digest<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = <M as hmac::digest::KeyInit>::new_from_slice(key)
+        .map_err(|e| CryptoError::InvalidTotpSecret(e.to_string()))?;
+    mac.update(message);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// Parsed `otpauth://totp/...` URI, as encoded in authenticator QR codes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OtpAuthUri {
+    /// Base32 secret
+    pub secret: String,
+    pub issuer: Option<String>,
+    /// Account name from the label
+    pub account: Option<String>,
+    pub options: TotpOptions,
+}
+
+/// Parse an `otpauth://totp/Issuer:account?secret=...` URI
+///
+/// The `issuer` parameter takes precedence over the label prefix.
+/// HOTP URIs are rejected.
+pub fn parse_otpauth_uri(uri: &str) -> Result<OtpAuthUri> {
+    let invalid = |msg: &str| CryptoError::InvalidTotpSecret(msg.to_string());
+
+    let rest = uri
+        .trim()
+        .strip_prefix("otpauth://")
+        .ok_or_else(|| invalid("URI must start with otpauth://"))?;
+    let (otp_type, rest) = rest
+        .split_once('/')
+        .ok_or_else(|| invalid("URI is missing a label"))?;
+    if !otp_type.eq_ignore_ascii_case("totp") {
+        return Err(invalid("Only TOTP URIs are supported"));
+    }
+    let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let label = percent_decode(label)?;
+    let (mut issuer, account) = match label.split_once(':') {
+        Some((issuer, account)) => (Some(issuer.trim().to_string()), account.trim()),
+        None => (None, label.trim()),
+    };
+    let account = (!account.is_empty()).then(|| account.to_string());
+
+    let mut secret = None;
+    let mut options = TotpOptions::default();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value)?;
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => secret = Some(value),
+            "issuer" if !value.is_empty() => issuer = Some(value),
+            "algorithm" => {
+                options.algorithm = match value.to_ascii_uppercase().as_str() {
+                    "SHA1" => TotpAlgorithm::Sha1,
+                    "SHA256" => TotpAlgorithm::Sha256,
+                    "SHA512" => TotpAlgorithm::Sha512,
+                    _ => return Err(invalid("Unsupported algorithm")),
+                }
+            }
+            "digits" => options.digits = value.parse().map_err(|_| invalid("Invalid digits"))?,
+            "period" => options.period = value.parse().map_err(|_| invalid("Invalid period"))?,
+            _ => {}
+        }
+    }
+
+    let secret = secret.ok_or_else(|| invalid("URI is missing the secret parameter"))?;
+    decode_secret(&secret)?;
+    if !(6..=8).contains(&options.digits) || options.period == 0 {
+        return Err(invalid("Digits must be 6-8 and period non-zero"));
+    }
+
+    Ok(OtpAuthUri {
+        secret,
+        issuer,
+        account,
+        options,
+    })
+}
+
+/// Decode `%XX` escapes and `+` in a URI component
+fn percent_decode(s: &str) -> Result<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'%' => {
+                let hex = [iter.next(), iter.next()];
+                let decoded = match hex {
+                    [Some(h), Some(l)] => std::str::from_utf8(&[h, l])
+                        .ok()
+                        .and_then(|h| u8::from_str_radix(h, 16).ok()),
+                    _ => None,
+                };
+                bytes.push(decoded.ok_or_else(|| {
+                    CryptoError::InvalidTotpSecret("Invalid percent-encoding".to_string())
+                })?);
+            }
+            b'+' => bytes.push(b' '),
+            _ => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes)
+        .map_err(|_| CryptoError::InvalidTotpSecret("URI is not valid UTF-8".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 Appendix B test vectors
+    const SHA1_KEY: &[u8] = b"12345678901234567890";
+    const SHA256_KEY: &[u8] = b"12345678901234567890123456789012";
+    const SHA512_KEY: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    fn options(algorithm: TotpAlgorithm) -> TotpOptions {
+        TotpOptions {
+            algorithm,
+            digits: 8,
+            period: 30,
+        }
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let cases = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+
+        for (time, sha1, sha256, sha512) in cases {
+            let code = generate_totp_with_key(SHA1_KEY, &options(TotpAlgorithm::Sha1), time);
+            assert_eq!(code.unwrap().code, sha1);
+            let code = generate_totp_with_key(SHA256_KEY, &options(TotpAlgorithm::Sha256), time);
+            assert_eq!(code.unwrap().code, sha256);
+            let code = generate_totp_with_key(SHA512_KEY, &options(TotpAlgorithm::Sha512), time);
+            assert_eq!(code.unwrap().code, sha512);
+        }
+    }
+
+    #[test]
+    fn test_generate_totp_base32() {
+        // base32 of "12345678901234567890"
+        let secret = "gezd gnbv gy3t qojq gezd gnbv gy3t qojq";
+        let code = generate_totp(secret, &TotpOptions::default(), 59).unwrap();
+
+        assert_eq!(code.code, "287082");
+        assert_eq!(code.seconds_remaining, 1);
+        assert_eq!(code.period, 30);
+    }
+
+    #[test]
+    fn test_decode_secret() {
+        assert_eq!(decode_secret("MZXW6===").unwrap(), b"foo");
+        assert_eq!(decode_secret("mzxw-6yq").unwrap(), b"foob");
+        assert!(decode_secret("not base32!").is_err());
+        assert!(decode_secret("").is_err());
+    }
+
+    #[test]
+    fn test_encode_secret() {
+        assert_eq!(encode_secret(b"foo"), "MZXW6");
+        assert_eq!(encode_secret(b"foob"), "MZXW6YQ");
+        let key = b"12345678901234567890";
+        assert_eq!(decode_secret(&encode_secret(key)).unwrap(), key);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let opts = TotpOptions {
+            digits: 4,
+            ..Default::default()
+        };
+        assert!(generate_totp_with_key(SHA1_KEY, &opts, 0).is_err());
+
+        let opts = TotpOptions {
+            period: 0,
+            ..Default::default()
+        };
+        assert!(generate_totp_with_key(SHA1_KEY, &opts, 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_otpauth_uri() {
+        let parsed = parse_otpauth_uri(
+            "otpauth://totp/ACME%20Co:john.doe%40email.com?secret=JBSWY3DPEHPK3PXP&algorithm=SHA256&digits=8&period=60",
+        )
+        .unwrap();
+        assert_eq!(parsed.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(parsed.issuer.as_deref(), Some("ACME Co"));
+        assert_eq!(parsed.account.as_deref(), Some("john.doe@email.com"));
+        assert_eq!(parsed.options.algorithm, TotpAlgorithm::Sha256);
+        assert_eq!(parsed.options.digits, 8);
+        assert_eq!(parsed.options.period, 60);
+
+        let parsed =
+            parse_otpauth_uri("otpauth://totp/alice?secret=JBSWY3DPEHPK3PXP&issuer=Example")
+                .unwrap();
+        assert_eq!(parsed.issuer.as_deref(), Some("Example"));
+        assert_eq!(parsed.options.digits, 6);
+
+        assert!(parse_otpauth_uri("otpauth://hotp/alice?secret=JBSWY3DPEHPK3PXP").is_err());
+        assert!(parse_otpauth_uri("otpauth://totp/alice").is_err());
+        assert!(parse_otpauth_uri("https://example.com").is_err());
+    }
+}
@@ -1,11 +1,13 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::cipher::{decrypt, encrypt, EncryptedBlob, KEY_SIZE};
 use crate::error::{CryptoError, Result};
+use crate::matching::{match_url, UrlMatchKind, UrlMatchStrategy};
 
 /// A single credential item in the vault
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct VaultItem {
     /// Unique identifier for the item
     pub id: String,
@@ -29,23 +31,73 @@ pub struct VaultItem {
     pub modified_at: u64,
     /// Custom fields
     pub custom_fields: Vec<CustomField>,
+    /// Icon (image URL or built-in icon identifier)
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Display color as `#rrggbb`
+    #[serde(default)]
+    pub color: Option<String>,
+    /// What the item stores
+    #[serde(default)]
+    pub kind: ItemKind,
+    /// Free-form labels for filtering
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// TOTP secret (base32)
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    /// Auto-type keystroke sequence, e.g. `{USERNAME}{TAB}{PASSWORD}{ENTER}`
+    #[serde(default)]
+    pub auto_type: Option<String>,
+}
+
+/// Kind of vault item
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    #[default]
+    Login,
+    SecureNote,
+    Card,
+    Identity,
 }
 
 /// Custom field for additional data
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CustomField {
     pub name: String,
     pub value: String,
     pub hidden: bool,
 }
 
+/// Colors used for generated item icons
+const ICON_PALETTE: &[&str] = &[
+    "#e53935", "#d81b60", "#8e24aa", "#5e35b1", "#3949ab", "#1e88e5", "#039be5", "#00897b",
+    "#43a047", "#7cb342", "#f4511e", "#6d4c41",
+];
+
+/// Fallback icon for items without an explicit icon
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FallbackIcon {
+    /// Single uppercase character to draw in the icon
+    pub initial: String,
+    /// Background color as `#rrggbb`
+    pub color: String,
+}
+
+/// Pick a palette color for a seed string (e.g. a domain or item name)
+///
+/// The same seed always maps to the same color on every platform.
+pub fn color_for(seed: &str) -> String {
+    let digest = Sha256::digest(seed.trim().to_lowercase().as_bytes());
+    let index = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as usize;
+    ICON_PALETTE[index % ICON_PALETTE.len()].to_string()
+}
+
 impl VaultItem {
     /// Create a new vault item
     pub fn new(name: &str, username: &str, password: &str) -> Self {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_secs();
 
         Self {
             id: Uuid::new_v4().to_string(),
@@ -59,6 +111,12 @@ impl VaultItem {
             created_at: now,
             modified_at: now,
             custom_fields: Vec::new(),
+            icon: None,
+            color: None,
+            kind: ItemKind::Login,
+            tags: Vec::new(),
+            totp_secret: None,
+            auto_type: None,
         }
     }
 
@@ -82,6 +140,93 @@ impl VaultItem {
         self
     }
 
+    pub fn with_icon(mut self, icon: &str) -> Self {
+        self.icon = Some(icon.to_string());
+        self
+    }
+
+    pub fn with_color(mut self, color: &str) -> Self {
+        self.color = Some(color.to_string());
+        self
+    }
+
+    pub fn with_kind(mut self, kind: ItemKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        if !self.tags.iter().any(|t| t == tag) {
+            self.tags.push(tag.to_string());
+        }
+        self
+    }
+
+    pub fn with_totp_secret(mut self, secret: &str) -> Self {
+        self.totp_secret = Some(secret.to_string());
+        self
+    }
+
+    pub fn with_auto_type(mut self, sequence: &str) -> Self {
+        self.auto_type = Some(sequence.to_string());
+        self
+    }
+
+    /// Color to render the item with, falling back to one derived from its domain or name
+    pub fn display_color(&self) -> String {
+        self.color
+            .clone()
+            .unwrap_or_else(|| color_for(&self.icon_seed()))
+    }
+
+    /// Generated icon for items without an explicit icon
+    pub fn fallback_icon(&self) -> FallbackIcon {
+        let seed = self.icon_seed();
+        let initial = seed
+            .chars()
+            .find(|c| c.is_alphanumeric())
+            .map(|c| c.to_uppercase().collect())
+            .unwrap_or_else(|| "?".to_string());
+
+        FallbackIcon {
+            initial,
+            color: self.display_color(),
+        }
+    }
+
+    /// Domain if the item has a URL, otherwise its name
+    fn icon_seed(&self) -> String {
+        self.url
+            .as_deref()
+            .map(extract_domain)
+            .filter(|d| !d.is_empty())
+            .unwrap_or_else(|| self.name.clone())
+    }
+
+    /// Encrypt this item on its own, for item-level sync
+    pub fn export(&self, key: &[u8; KEY_SIZE]) -> Result<EncryptedBlob> {
+        let json =
+            serde_json::to_vec(self).map_err(|e| CryptoError::Serialization(e.to_string()))?;
+        encrypt(&json, key)
+    }
+
+    /// Decrypt an item produced by [`VaultItem::export`]
+    pub fn import(blob: &EncryptedBlob, key: &[u8; KEY_SIZE]) -> Result<Self> {
+        let json = decrypt(blob, key)?;
+        serde_json::from_slice(&json).map_err(|e| CryptoError::Deserialization(e.to_string()))
+    }
+
+    /// Check a lowercased query against name, username, and URL
+    fn matches_query(&self, query_lower: &str) -> bool {
+        self.name.to_lowercase().contains(query_lower)
+            || self.username.to_lowercase().contains(query_lower)
+            || self
+                .url
+                .as_ref()
+                .map(|u| u.to_lowercase().contains(query_lower))
+                .unwrap_or(false)
+    }
+
     pub fn add_custom_field(&mut self, name: &str, value: &str, hidden: bool) {
         self.custom_fields.push(CustomField {
             name: name.to_string(),
@@ -91,10 +236,195 @@ impl VaultItem {
     }
 
     fn touch(&mut self) {
-        self.modified_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.modified_at = now_secs();
+    }
+}
+
+/// Current time in Unix epoch seconds
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// An item matched against a page URL
+#[derive(Clone, Debug)]
+pub struct UrlMatch<'a> {
+    pub item: &'a VaultItem,
+    pub kind: UrlMatchKind,
+}
+
+/// Criteria for listing items; unset fields match everything
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ItemFilter {
+    /// Case-insensitive match against name, username, or URL
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub favorite: Option<bool>,
+    /// Only items carrying this tag
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub kind: Option<ItemKind>,
+}
+
+impl ItemFilter {
+    /// Check whether an item satisfies every set criterion
+    pub fn matches(&self, item: &VaultItem) -> bool {
+        if let Some(query) = &self.query {
+            if !item.matches_query(&query.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(category) = &self.category {
+            if item.category.as_deref() != Some(category.as_str()) {
+                return false;
+            }
+        }
+        if let Some(tag) = &self.tag {
+            if !item.tags.contains(tag) {
+                return false;
+            }
+        }
+        self.kind.is_none_or(|kind| item.kind == kind)
+            && self
+                .favorite
+                .is_none_or(|favorite| item.favorite == favorite)
+    }
+}
+
+/// Sort order for listed items
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemSort {
+    /// Name A-Z, case-insensitive
+    #[default]
+    Name,
+    /// Name Z-A, case-insensitive
+    NameDesc,
+    /// Most recently created first
+    Newest,
+    /// Least recently created first
+    Oldest,
+    /// Most recently modified first
+    RecentlyModified,
+}
+
+/// One page of listed items
+#[derive(Clone, Debug)]
+pub struct ItemPage<'a> {
+    pub items: Vec<&'a VaultItem>,
+    /// Number of items matching the filter across all pages
+    pub total: usize,
+}
+
+/// Summary counts for dashboards and widgets
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct VaultStats {
+    pub total: usize,
+    pub logins: usize,
+    pub secure_notes: usize,
+    pub cards: usize,
+    pub identities: usize,
+    pub favorites: usize,
+    /// Tags with their item counts, most used first
+    pub tags: Vec<(String, usize)>,
+    /// Size of the serialized vault in bytes, as encrypted by [`Vault::export`]
+    pub storage_bytes: usize,
+}
+
+/// How [`Vault::merge`] resolves items that differ between the two vaults
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePolicy {
+    /// Keep whichever version was modified most recently
+    #[default]
+    NewestWins,
+    /// Always keep the local version
+    KeepLocal,
+    /// Always take the incoming version
+    KeepIncoming,
+    /// Keep the local version and add the incoming one as a new item
+    KeepBoth,
+}
+
+/// Outcome of [`Vault::merge`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MergeReport {
+    /// IDs of incoming items that were not in the vault
+    pub added: Vec<String>,
+    /// IDs of local items replaced by their incoming version
+    pub updated: Vec<String>,
+    /// IDs of items whose local and incoming versions differed
+    pub conflicts: Vec<String>,
+    /// New IDs given to incoming copies under [`MergePolicy::KeepBoth`]
+    pub duplicated: Vec<String>,
+    /// Number of incoming items identical to the local version
+    pub unchanged: usize,
+}
+
+/// Item-level changes between two snapshots of a vault
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VaultDiff {
+    /// IDs present only in the newer snapshot
+    pub added: Vec<String>,
+    /// IDs present in both snapshots whose contents differ
+    pub updated: Vec<String>,
+    /// IDs present only in the older snapshot
+    pub removed: Vec<String>,
+}
+
+impl VaultDiff {
+    /// Check if the snapshots hold identical items
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Match items against a page URL under the given strategy, strongest matches first
+///
+/// Ties are broken by favorite flag, then most recently modified.
+pub fn rank_url_matches<'a>(
+    url: &str,
+    items: impl IntoIterator<Item = &'a VaultItem>,
+    strategy: UrlMatchStrategy,
+) -> Vec<UrlMatch<'a>> {
+    let mut matches: Vec<UrlMatch<'a>> = items
+        .into_iter()
+        .filter_map(|item| {
+            let kind = match_url(url, item.url.as_deref()?)?;
+            strategy.accepts(kind).then_some(UrlMatch { item, kind })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| {
+        b.kind
+            .cmp(&a.kind)
+            .then(b.item.favorite.cmp(&a.item.favorite))
+            .then(b.item.modified_at.cmp(&a.item.modified_at))
+    });
+    matches
+}
+
+/// How long deleted items stay in the trash before they can be purged (30 days)
+pub const TRASH_RETENTION_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// An item moved to the trash, kept until restored or purged
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrashedItem {
+    pub item: VaultItem,
+    /// When the item was deleted (Unix epoch seconds)
+    pub deleted_at: u64,
+}
+
+impl TrashedItem {
+    /// When the item becomes eligible for automatic purging
+    pub fn expires_at(&self) -> u64 {
+        self.deleted_at.saturating_add(TRASH_RETENTION_SECS)
     }
 }
 
@@ -109,6 +439,9 @@ pub struct Vault {
     pub categories: Vec<String>,
     /// Last sync timestamp (Unix epoch seconds)
     pub last_sync: Option<u64>,
+    /// Deleted items awaiting restore or purge, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trash: Vec<TrashedItem>,
 }
 
 impl Default for Vault {
@@ -130,16 +463,33 @@ impl Vault {
                 "Secure Note".to_string(),
             ],
             last_sync: None,
+            trash: Vec::new(),
         }
     }
 
     /// Add an item to the vault
-    pub fn add_item(&mut self, item: VaultItem) -> String {
+    ///
+    /// Timestamps the item already carries, e.g. from an import, are kept;
+    /// a zero creation time is set to now and a zero modification time to
+    /// the creation time.
+    pub fn add_item(&mut self, mut item: VaultItem) -> String {
+        if item.created_at == 0 {
+            item.created_at = now_secs();
+        }
+        if item.modified_at == 0 {
+            item.modified_at = item.created_at;
+        }
         let id = item.id.clone();
         self.items.push(item);
         id
     }
 
+    /// Add several items at once, returning their IDs in order
+    pub fn add_items(&mut self, items: Vec<VaultItem>) -> Vec<String> {
+        self.items.reserve(items.len());
+        items.into_iter().map(|item| self.add_item(item)).collect()
+    }
+
     /// Get an item by ID
     pub fn get_item(&self, id: &str) -> Option<&VaultItem> {
         self.items.iter().find(|item| item.id == id)
@@ -151,6 +501,8 @@ impl Vault {
     }
 
     /// Update an item in the vault
+    ///
+    /// The original creation time is kept and the modification time set to now.
     pub fn update_item(&mut self, id: &str, mut updated: VaultItem) -> Result<()> {
         let index = self
             .items
@@ -159,11 +511,37 @@ impl Vault {
             .ok_or_else(|| CryptoError::ItemNotFound(id.to_string()))?;
 
         updated.id = id.to_string();
+        updated.created_at = self.items[index].created_at;
         updated.touch();
         self.items[index] = updated;
         Ok(())
     }
 
+    /// Mark an item as modified now without changing its contents
+    pub fn touch_item(&mut self, id: &str) -> Result<()> {
+        self.get_item_mut(id)
+            .ok_or_else(|| CryptoError::ItemNotFound(id.to_string()))?
+            .touch();
+        Ok(())
+    }
+
+    /// Insert an item, replacing any item with the same ID as-is
+    ///
+    /// Timestamps are kept, so synced items retain their remote modification time.
+    /// Returns true if an existing item was replaced.
+    pub fn upsert_item(&mut self, item: VaultItem) -> bool {
+        match self.get_item_mut(&item.id) {
+            Some(existing) => {
+                *existing = item;
+                true
+            }
+            None => {
+                self.items.push(item);
+                false
+            }
+        }
+    }
+
     /// Remove an item from the vault
     pub fn remove_item(&mut self, id: &str) -> Result<VaultItem> {
         let index = self
@@ -175,23 +553,104 @@ impl Vault {
         Ok(self.items.remove(index))
     }
 
+    /// Move an item to the trash so it can be restored later
+    pub fn trash_item(&mut self, id: &str) -> Result<&TrashedItem> {
+        let item = self.remove_item(id)?;
+        self.trash.push(TrashedItem {
+            item,
+            deleted_at: now_secs(),
+        });
+        Ok(self.trash.last().unwrap())
+    }
+
+    /// Items currently in the trash, oldest deletion first
+    pub fn deleted_items(&self) -> &[TrashedItem] {
+        &self.trash
+    }
+
+    /// Move an item out of the trash back into the vault
+    ///
+    /// The item's modification time is set to now so the restore syncs.
+    pub fn restore_item(&mut self, id: &str) -> Result<&VaultItem> {
+        let mut item = self.take_from_trash(id)?.item;
+        item.touch();
+        self.upsert_item(item);
+        Ok(self.get_item(id).unwrap())
+    }
+
+    /// Permanently delete an item from the trash
+    pub fn purge_item(&mut self, id: &str) -> Result<VaultItem> {
+        Ok(self.take_from_trash(id)?.item)
+    }
+
+    /// Permanently delete everything in the trash, returning how many items were purged
+    pub fn empty_trash(&mut self) -> usize {
+        let count = self.trash.len();
+        self.trash.clear();
+        count
+    }
+
+    /// Purge trashed items older than [`TRASH_RETENTION_SECS`] as of `now`
+    pub fn purge_expired(&mut self, now: u64) -> usize {
+        let before = self.trash.len();
+        self.trash.retain(|trashed| trashed.expires_at() > now);
+        before - self.trash.len()
+    }
+
+    fn take_from_trash(&mut self, id: &str) -> Result<TrashedItem> {
+        let index = self
+            .trash
+            .iter()
+            .position(|trashed| trashed.item.id == id)
+            .ok_or_else(|| CryptoError::ItemNotFound(id.to_string()))?;
+        Ok(self.trash.remove(index))
+    }
+
     /// Search items by name, URL, or username
     pub fn search(&self, query: &str) -> Vec<&VaultItem> {
         let query_lower = query.to_lowercase();
         self.items
             .iter()
-            .filter(|item| {
-                item.name.to_lowercase().contains(&query_lower)
-                    || item.username.to_lowercase().contains(&query_lower)
-                    || item
-                        .url
-                        .as_ref()
-                        .map(|u| u.to_lowercase().contains(&query_lower))
-                        .unwrap_or(false)
-            })
+            .filter(|item| item.matches_query(&query_lower))
             .collect()
     }
 
+    /// List a sorted page of items matching a filter
+    ///
+    /// `limit` of `None` returns every item from `offset` on.
+    pub fn list_items(
+        &self,
+        filter: &ItemFilter,
+        sort: ItemSort,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> ItemPage<'_> {
+        let mut items: Vec<&VaultItem> = self.items.iter().filter(|i| filter.matches(i)).collect();
+        let total = items.len();
+
+        match sort {
+            ItemSort::Name => items.sort_by_cached_key(|i| i.name.to_lowercase()),
+            ItemSort::NameDesc => {
+                items.sort_by_cached_key(|i| std::cmp::Reverse(i.name.to_lowercase()))
+            }
+            ItemSort::Newest => items.sort_by_key(|i| std::cmp::Reverse(i.created_at)),
+            ItemSort::Oldest => items.sort_by_key(|i| i.created_at),
+            ItemSort::RecentlyModified => items.sort_by_key(|i| std::cmp::Reverse(i.modified_at)),
+        }
+
+        let items = items
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        ItemPage { items, total }
+    }
+
+    /// Count items matching a filter
+    pub fn count_items(&self, filter: &ItemFilter) -> usize {
+        self.items.iter().filter(|i| filter.matches(i)).count()
+    }
+
     /// Find items matching a URL (for autofill)
     pub fn find_by_url(&self, url: &str) -> Vec<&VaultItem> {
         let domain = extract_domain(url);
@@ -209,6 +668,13 @@ impl Vault {
             .collect()
     }
 
+    /// Find items matching a URL under the given strategy, strongest matches first
+    ///
+    /// Ties are broken by favorite flag, then most recently modified.
+    pub fn find_by_url_ranked(&self, url: &str, strategy: UrlMatchStrategy) -> Vec<UrlMatch<'_>> {
+        rank_url_matches(url, &self.items, strategy)
+    }
+
     /// Get items by category
     pub fn get_by_category(&self, category: &str) -> Vec<&VaultItem> {
         self.items
@@ -266,6 +732,179 @@ impl Vault {
     pub fn is_empty(&self) -> bool {
         self.items.is_empty()
     }
+
+    /// Count items by kind, favorites, and tags
+    pub fn stats(&self) -> VaultStats {
+        let mut stats = VaultStats {
+            total: self.items.len(),
+            ..Default::default()
+        };
+        let mut tags: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+        for item in &self.items {
+            match item.kind {
+                ItemKind::Login => stats.logins += 1,
+                ItemKind::SecureNote => stats.secure_notes += 1,
+                ItemKind::Card => stats.cards += 1,
+                ItemKind::Identity => stats.identities += 1,
+            }
+            if item.favorite {
+                stats.favorites += 1;
+            }
+            for tag in &item.tags {
+                *tags.entry(tag).or_default() += 1;
+            }
+        }
+
+        stats.tags = tags
+            .into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect();
+        stats
+            .tags
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats.storage_bytes = serde_json::to_vec(self).map(|v| v.len()).unwrap_or(0);
+        stats
+    }
+
+    /// Merge another vault's items and categories into this one
+    ///
+    /// Merging is additive: items missing from `other` are kept. Incoming items keep
+    /// their timestamps; differing items are resolved by `policy`.
+    pub fn merge(&mut self, other: &Vault, policy: MergePolicy) -> MergeReport {
+        let mut report = MergeReport::default();
+        for category in &other.categories {
+            self.add_category(category);
+        }
+
+        for incoming in &other.items {
+            let Some(index) = self.items.iter().position(|i| i.id == incoming.id) else {
+                self.items.push(incoming.clone());
+                report.added.push(incoming.id.clone());
+                continue;
+            };
+            let local = &self.items[index];
+            if local == incoming {
+                report.unchanged += 1;
+                continue;
+            }
+
+            report.conflicts.push(incoming.id.clone());
+            let take_incoming = match policy {
+                MergePolicy::NewestWins => incoming.modified_at > local.modified_at,
+                MergePolicy::KeepLocal => false,
+                MergePolicy::KeepIncoming => true,
+                MergePolicy::KeepBoth => {
+                    let mut copy = incoming.clone();
+                    copy.id = Uuid::new_v4().to_string();
+                    report.duplicated.push(copy.id.clone());
+                    self.items.push(copy);
+                    false
+                }
+            };
+            if take_incoming {
+                self.items[index] = incoming.clone();
+                report.updated.push(incoming.id.clone());
+            }
+        }
+
+        report
+    }
+
+    /// Compute the item changes that turn this vault into `newer`
+    pub fn diff(&self, newer: &Vault) -> VaultDiff {
+        let mut diff = VaultDiff::default();
+        for item in &newer.items {
+            match self.get_item(&item.id) {
+                None => diff.added.push(item.id.clone()),
+                Some(old) if old != item => diff.updated.push(item.id.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = self
+            .items
+            .iter()
+            .filter(|item| newer.get_item(&item.id).is_none())
+            .map(|item| item.id.clone())
+            .collect();
+        diff
+    }
+
+    /// Convert into a read-only view
+    pub fn into_read_only(self) -> VaultView {
+        VaultView::new(self)
+    }
+}
+
+/// Read-only view of a vault
+///
+/// Exposes lookups and search but no mutation or export, for callers such as
+/// emergency-access recipients and audit tooling that must never write back.
+#[derive(Clone, Debug)]
+pub struct VaultView {
+    vault: Vault,
+}
+
+impl VaultView {
+    /// Wrap a vault in a read-only view
+    pub fn new(vault: Vault) -> Self {
+        Self { vault }
+    }
+
+    /// Open an encrypted vault blob as a read-only view
+    pub fn import(blob: &EncryptedBlob, key: &[u8; KEY_SIZE]) -> Result<Self> {
+        Vault::import(blob, key).map(Self::new)
+    }
+
+    /// All items in the vault
+    pub fn items(&self) -> &[VaultItem] {
+        &self.vault.items
+    }
+
+    /// Categories/folders
+    pub fn categories(&self) -> &[String] {
+        &self.vault.categories
+    }
+
+    /// Get an item by ID
+    pub fn get_item(&self, id: &str) -> Option<&VaultItem> {
+        self.vault.get_item(id)
+    }
+
+    /// Search items by name, URL, or username
+    pub fn search(&self, query: &str) -> Vec<&VaultItem> {
+        self.vault.search(query)
+    }
+
+    /// Find items matching a URL
+    pub fn find_by_url(&self, url: &str) -> Vec<&VaultItem> {
+        self.vault.find_by_url(url)
+    }
+
+    /// Get items by category
+    pub fn get_by_category(&self, category: &str) -> Vec<&VaultItem> {
+        self.vault.get_by_category(category)
+    }
+
+    /// Get favorite items
+    pub fn get_favorites(&self) -> Vec<&VaultItem> {
+        self.vault.get_favorites()
+    }
+
+    /// Get total number of items
+    pub fn len(&self) -> usize {
+        self.vault.len()
+    }
+
+    /// Check if vault is empty
+    pub fn is_empty(&self) -> bool {
+        self.vault.is_empty()
+    }
+}
+
+impl From<Vault> for VaultView {
+    fn from(vault: Vault) -> Self {
+        Self::new(vault)
+    }
 }
 
 /// Extract domain from URL
@@ -299,6 +938,129 @@ mod tests {
         key
     }
 
+    #[test]
+    fn test_trash() {
+        let mut vault = Vault::new();
+        let keep = vault.add_item(VaultItem::new("GitHub", "user", "pass"));
+        let gone = vault.add_item(VaultItem::new("Jira", "user", "pass"));
+
+        let deleted_at = vault.trash_item(&keep).unwrap().deleted_at;
+        vault.trash_item(&gone).unwrap();
+        assert!(vault.is_empty());
+        assert_eq!(vault.deleted_items().len(), 2);
+        assert!(vault.search("GitHub").is_empty());
+
+        // Trash survives a round trip
+        let json = vault.to_json().unwrap();
+        let mut vault = Vault::from_json(&json).unwrap();
+
+        assert_eq!(vault.restore_item(&keep).unwrap().name, "GitHub");
+        assert!(vault.get_item(&keep).is_some());
+        assert!(matches!(
+            vault.restore_item(&keep),
+            Err(CryptoError::ItemNotFound(_))
+        ));
+
+        assert_eq!(vault.purge_expired(deleted_at + 60), 0);
+        assert_eq!(vault.purge_expired(deleted_at + TRASH_RETENTION_SECS), 1);
+        assert!(vault.deleted_items().is_empty());
+
+        vault.trash_item(&keep).unwrap();
+        assert_eq!(vault.purge_item(&keep).unwrap().name, "GitHub");
+        vault.add_item(VaultItem::new("Wiki", "user", "pass"));
+        let id = vault.items[0].id.clone();
+        vault.trash_item(&id).unwrap();
+        assert_eq!(vault.empty_trash(), 1);
+        assert!(vault.is_empty());
+    }
+
+    #[test]
+    fn test_vault_stats() {
+        let mut vault = Vault::new();
+        vault.add_item(
+            VaultItem::new("GitHub", "user", "pass")
+                .with_favorite(true)
+                .with_tag("work")
+                .with_tag("dev"),
+        );
+        vault.add_item(VaultItem::new("Jira", "user", "pass").with_tag("work"));
+        vault.add_item(VaultItem::new("Visa", "", "").with_kind(ItemKind::Card));
+        vault.add_item(VaultItem::new("Wifi", "", "").with_kind(ItemKind::SecureNote));
+
+        let stats = vault.stats();
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.logins, 2);
+        assert_eq!(stats.cards, 1);
+        assert_eq!(stats.secure_notes, 1);
+        assert_eq!(stats.identities, 0);
+        assert_eq!(stats.favorites, 1);
+        assert_eq!(
+            stats.tags,
+            vec![("work".to_string(), 2), ("dev".to_string(), 1)]
+        );
+        assert_eq!(
+            stats.storage_bytes,
+            serde_json::to_vec(&vault).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_vault_merge() {
+        let mut local = Vault::new();
+        let same = local.add_item(VaultItem::new("Same", "user", "pass"));
+        let edited = local.add_item(VaultItem::new("Edited", "user", "pass"));
+
+        let mut incoming = local.clone();
+        incoming.add_category("Work");
+        let new = incoming.add_item(VaultItem::new("New", "user", "pass"));
+        let item = incoming.get_item_mut(&edited).unwrap();
+        item.password = "incoming".to_string();
+        item.modified_at += 10;
+
+        let mut merged = local.clone();
+        let report = merged.merge(&incoming, MergePolicy::NewestWins);
+        assert_eq!(report.added, vec![new.clone()]);
+        assert_eq!(report.updated, vec![edited.clone()]);
+        assert_eq!(report.conflicts, vec![edited.clone()]);
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(merged.get_item(&edited).unwrap().password, "incoming");
+        assert!(merged.get_item(&same).is_some());
+        assert!(merged.categories.contains(&"Work".to_string()));
+
+        let mut merged = local.clone();
+        let report = merged.merge(&incoming, MergePolicy::KeepLocal);
+        assert!(report.updated.is_empty());
+        assert_eq!(merged.get_item(&edited).unwrap().password, "pass");
+
+        let mut merged = local.clone();
+        let report = merged.merge(&incoming, MergePolicy::KeepBoth);
+        assert_eq!(report.duplicated.len(), 1);
+        assert_eq!(merged.len(), 4);
+        let copy = merged.get_item(&report.duplicated[0]).unwrap();
+        assert_eq!(copy.password, "incoming");
+    }
+
+    #[test]
+    fn test_vault_diff() {
+        let mut old = Vault::new();
+        let kept = old.add_item(VaultItem::new("Kept", "user", "pass"));
+        let changed = old.add_item(VaultItem::new("Changed", "user", "pass"));
+        let removed = old.add_item(VaultItem::new("Removed", "user", "pass"));
+
+        let mut new = old.clone();
+        assert!(old.diff(&new).is_empty());
+
+        new.get_item_mut(&changed).unwrap().password = "new-pass".to_string();
+        new.remove_item(&removed).unwrap();
+        let added = new.add_item(VaultItem::new("Added", "user", "pass"));
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, vec![added]);
+        assert_eq!(diff.updated, vec![changed]);
+        assert_eq!(diff.removed, vec![removed]);
+        assert!(!diff.updated.contains(&kept));
+    }
+
     #[test]
     fn test_vault_operations() {
         let mut vault = Vault::new();
@@ -436,4 +1198,222 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].name, "Test1");
     }
+
+    #[test]
+    fn test_color_for_is_deterministic() {
+        assert_eq!(color_for("github.com"), color_for("github.com"));
+        assert_eq!(color_for("GitHub.com "), color_for("github.com"));
+        assert!(ICON_PALETTE.contains(&color_for("example.com").as_str()));
+    }
+
+    #[test]
+    fn test_fallback_icon() {
+        let item =
+            VaultItem::new("Work Mail", "user", "pass").with_url("https://www.github.com/login");
+        let icon = item.fallback_icon();
+        assert_eq!(icon.initial, "G");
+        assert_eq!(icon.color, color_for("github.com"));
+
+        let item = VaultItem::new("bank", "user", "pass").with_color("#000000");
+        let icon = item.fallback_icon();
+        assert_eq!(icon.initial, "B");
+        assert_eq!(icon.color, "#000000");
+    }
+
+    #[test]
+    fn test_icon_fields_default_when_missing() {
+        let mut value = serde_json::to_value(VaultItem::new("Test", "user", "pass")).unwrap();
+        let obj = value.as_object_mut().unwrap();
+        obj.remove("icon");
+        obj.remove("color");
+        obj.remove("kind");
+        obj.remove("tags");
+        obj.remove("totp_secret");
+        obj.remove("auto_type");
+
+        let item: VaultItem = serde_json::from_value(value).unwrap();
+        assert!(item.icon.is_none());
+        assert!(item.color.is_none());
+        assert_eq!(item.kind, ItemKind::Login);
+        assert!(item.tags.is_empty());
+        assert!(item.totp_secret.is_none());
+        assert!(item.auto_type.is_none());
+    }
+
+    #[test]
+    fn test_vault_view() {
+        let key = test_key();
+        let mut vault = Vault::new();
+        let id = vault.add_item(
+            VaultItem::new("GitHub", "user", "pass")
+                .with_url("https://github.com")
+                .with_favorite(true),
+        );
+
+        let blob = vault.export(&key).unwrap();
+        let view = VaultView::import(&blob, &key).unwrap();
+
+        assert_eq!(view.len(), 1);
+        assert_eq!(view.get_item(&id).unwrap().name, "GitHub");
+        assert_eq!(view.search("git").len(), 1);
+        assert_eq!(view.find_by_url("https://github.com/login").len(), 1);
+        assert_eq!(view.get_favorites().len(), 1);
+        assert_eq!(view.categories(), vault.categories.as_slice());
+
+        let view = vault.into_read_only();
+        assert_eq!(view.items()[0].id, id);
+    }
+
+    #[test]
+    fn test_vault_find_by_url_ranked() {
+        let mut vault = Vault::new();
+
+        vault.add_item(VaultItem::new("Gist", "user", "pass").with_url("https://gist.github.com"));
+        vault.add_item(VaultItem::new("GitHub", "user", "pass").with_url("https://github.com"));
+        vault.add_item(VaultItem::new("Google", "user", "pass").with_url("https://google.com"));
+
+        let results =
+            vault.find_by_url_ranked("https://github.com/login", UrlMatchStrategy::BaseDomain);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].item.name, "GitHub");
+        assert_eq!(results[0].kind, UrlMatchKind::ExactHost);
+        assert_eq!(results[1].kind, UrlMatchKind::BaseDomain);
+
+        let results = vault.find_by_url_ranked("https://github.com", UrlMatchStrategy::ExactHost);
+        assert_eq!(results.len(), 1);
+
+        let results = vault.find_by_url_ranked("https://youtube.com", UrlMatchStrategy::Equivalent);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].kind, UrlMatchKind::EquivalentDomain);
+    }
+
+    #[test]
+    fn test_vault_list_items() {
+        let mut vault = Vault::new();
+        for name in ["delta", "Alpha", "charlie", "Bravo"] {
+            vault.add_item(VaultItem::new(name, "user", "pass").with_favorite(name != "delta"));
+        }
+
+        let page = vault.list_items(&ItemFilter::default(), ItemSort::Name, 1, Some(2));
+        assert_eq!(page.total, 4);
+        let names: Vec<&str> = page.items.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, vec!["Bravo", "charlie"]);
+
+        let filter = ItemFilter {
+            favorite: Some(true),
+            ..Default::default()
+        };
+        let page = vault.list_items(&filter, ItemSort::NameDesc, 0, None);
+        assert_eq!(page.total, 3);
+        assert_eq!(page.items[0].name, "charlie");
+
+        let filter = ItemFilter {
+            query: Some("ALP".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(vault.list_items(&filter, ItemSort::Name, 0, None).total, 1);
+        assert_eq!(vault.count_items(&filter), 1);
+
+        let page = vault.list_items(&ItemFilter::default(), ItemSort::Name, 10, Some(5));
+        assert_eq!(page.total, 4);
+        assert!(page.items.is_empty());
+    }
+
+    #[test]
+    fn test_item_filter_tag_and_kind() {
+        let mut vault = Vault::new();
+        vault.add_item(VaultItem::new("GitHub", "user", "pass").with_tag("work"));
+        vault.add_item(
+            VaultItem::new("Visa", "", "")
+                .with_kind(ItemKind::Card)
+                .with_tag("work"),
+        );
+        vault.add_item(VaultItem::new("Recipes", "", "").with_kind(ItemKind::SecureNote));
+
+        let filter = ItemFilter {
+            tag: Some("work".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(vault.count_items(&filter), 2);
+
+        let filter = ItemFilter {
+            tag: Some("work".to_string()),
+            kind: Some(ItemKind::Card),
+            ..Default::default()
+        };
+        let page = vault.list_items(&filter, ItemSort::Name, 0, None);
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].name, "Visa");
+
+        let filter = ItemFilter {
+            kind: Some(ItemKind::Identity),
+            ..Default::default()
+        };
+        assert_eq!(vault.count_items(&filter), 0);
+    }
+
+    #[test]
+    fn test_item_export_import_upsert() {
+        let key = [7u8; KEY_SIZE];
+        let mut vault = Vault::new();
+        let id = vault.add_item(VaultItem::new("GitHub", "user", "pass"));
+
+        let mut item = vault.get_item(&id).unwrap().clone();
+        item.password = "new-pass".to_string();
+        item.modified_at = 42;
+        let blob = item.export(&key).unwrap();
+
+        assert!(VaultItem::import(&blob, &[8u8; KEY_SIZE]).is_err());
+
+        let imported = VaultItem::import(&blob, &key).unwrap();
+        assert!(vault.upsert_item(imported));
+        assert_eq!(vault.len(), 1);
+        assert_eq!(vault.get_item(&id).unwrap().password, "new-pass");
+        assert_eq!(vault.get_item(&id).unwrap().modified_at, 42);
+
+        assert!(!vault.upsert_item(VaultItem::new("Other", "user", "pass")));
+        assert_eq!(vault.len(), 2);
+    }
+
+    #[test]
+    fn test_core_managed_timestamps() {
+        let mut vault = Vault::new();
+        let mut item = VaultItem::new("GitHub", "user", "pass");
+        item.created_at = 0;
+        item.modified_at = 0;
+        let id = vault.add_item(item);
+        let created_at = vault.get_item(&id).unwrap().created_at;
+        assert!(created_at > 0);
+        assert_eq!(vault.get_item(&id).unwrap().modified_at, created_at);
+
+        let mut updated = vault.get_item(&id).unwrap().clone();
+        updated.created_at = 5;
+        updated.modified_at = 5;
+        vault.update_item(&id, updated).unwrap();
+        let item = vault.get_item(&id).unwrap();
+        assert_eq!(item.created_at, created_at);
+        assert!(item.modified_at >= created_at);
+
+        vault.get_item_mut(&id).unwrap().modified_at = 0;
+        vault.touch_item(&id).unwrap();
+        assert!(vault.get_item(&id).unwrap().modified_at >= created_at);
+        assert!(vault.touch_item("missing").is_err());
+    }
+
+    #[test]
+    fn test_add_items_keeps_imported_timestamps() {
+        let mut vault = Vault::new();
+        let mut imported = VaultItem::new("GitHub", "user", "pass");
+        imported.created_at = 1_500_000_000;
+        imported.modified_at = 1_600_000_000;
+        let mut undated = VaultItem::new("Jira", "user", "pass");
+        undated.created_at = 0;
+        undated.modified_at = 0;
+
+        let ids = vault.add_items(vec![imported, undated]);
+        let item = vault.get_item(&ids[0]).unwrap();
+        assert_eq!(item.created_at, 1_500_000_000);
+        assert_eq!(item.modified_at, 1_600_000_000);
+        assert!(vault.get_item(&ids[1]).unwrap().created_at > 1_600_000_000);
+    }
 }
@@ -4,57 +4,135 @@
 //! for use in Android and iOS applications.
 
 use base64::{engine::general_purpose::STANDARD, Engine};
-use std::sync::Mutex;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use zeroize::{Zeroize, Zeroizing};
 
 // Re-export crypto_core types
 use crypto_core::{
-    cipher, kdf,
-    password::{self, PasswordOptions as CorePasswordOptions},
-    vault::{Vault as CoreVault, VaultItem as CoreVaultItem},
+    audit::{self, AuditOptions as CoreAuditOptions},
+    autofill, backup, cipher, importers, kdf,
+    matching::{UrlMatchKind as CoreUrlMatchKind, UrlMatchStrategy as CoreUrlMatchStrategy},
+    password::{
+        self, PassphraseOptions as CorePassphraseOptions, PasswordOptions as CorePasswordOptions,
+    },
+    strength,
+    totp::{self, TotpAlgorithm as CoreTotpAlgorithm, TotpOptions as CoreTotpOptions},
+    vault::{
+        CustomField as CoreCustomField, ItemFilter as CoreItemFilter, ItemKind as CoreItemKind,
+        ItemSort as CoreItemSort, MergePolicy as CoreMergePolicy, MergeReport as CoreMergeReport,
+        TrashedItem as CoreTrashedItem, Vault as CoreVault, VaultItem as CoreVaultItem,
+        VaultStats as CoreVaultStats,
+    },
     CryptoError as CoreCryptoError,
 };
 
 uniffi::include_scaffolding!("crypto_core");
 
 /// Error type for FFI
+///
+/// One variant per core `CryptoError` variant, plus binding-only failures.
 #[derive(Debug, thiserror::Error)]
 pub enum CryptoError {
-    #[error("Key derivation error: {0}")]
-    KeyDerivation(String),
-    #[error("Encryption error: {0}")]
-    Encryption(String),
-    #[error("Decryption error: {0}")]
-    Decryption(String),
+    #[error("Key derivation error: {reason}")]
+    KeyDerivation { reason: String },
+    #[error("Encryption error: {reason}")]
+    Encryption { reason: String },
+    #[error("Decryption error: {reason}")]
+    Decryption { reason: String },
     #[error("Invalid key length")]
     InvalidKeyLength,
-    #[error("Invalid input: {0}")]
-    InvalidInput(String),
-    #[error("Serialization error: {0}")]
-    Serialization(String),
+    #[error("Invalid nonce length")]
+    InvalidNonceLength,
+    #[error("Serialization error: {reason}")]
+    Serialization { reason: String },
+    #[error("Deserialization error: {reason}")]
+    Deserialization { reason: String },
+    #[error("Vault item not found: {id}")]
+    ItemNotFound { id: String },
+    #[error("Invalid password options: {reason}")]
+    InvalidPasswordOptions { reason: String },
+    #[error("Random generation failed: {reason}")]
+    RandomGeneration { reason: String },
+    #[error("Invalid TOTP secret: {reason}")]
+    InvalidTotpSecret { reason: String },
+    #[error("Invalid input: {reason}")]
+    InvalidInput { reason: String },
+    #[error("Key session has been destroyed")]
+    SessionDestroyed,
+    #[error("Vault is not open: {name}")]
+    VaultNotOpen { name: String },
+    #[error("I/O error: {reason}")]
+    Io { reason: String },
+}
+
+impl CryptoError {
+    fn invalid_input(reason: impl Into<String>) -> Self {
+        CryptoError::InvalidInput {
+            reason: reason.into(),
+        }
+    }
+}
+
+#[uniffi::export]
+impl CryptoError {
+    /// Stable machine-readable code, shared with the core and WASM bindings
+    pub fn code(&self) -> String {
+        match self {
+            CryptoError::KeyDerivation { .. } => "KEY_DERIVATION_FAILED",
+            CryptoError::Encryption { .. } => "ENCRYPTION_FAILED",
+            CryptoError::Decryption { .. } => "DECRYPTION_FAILED",
+            CryptoError::InvalidKeyLength => "INVALID_KEY_LENGTH",
+            CryptoError::InvalidNonceLength => "INVALID_NONCE_LENGTH",
+            CryptoError::Serialization { .. } => "SERIALIZATION_FAILED",
+            CryptoError::Deserialization { .. } => "DESERIALIZATION_FAILED",
+            CryptoError::ItemNotFound { .. } => "ITEM_NOT_FOUND",
+            CryptoError::InvalidPasswordOptions { .. } => "INVALID_PASSWORD_OPTIONS",
+            CryptoError::RandomGeneration { .. } => "RANDOM_GENERATION_FAILED",
+            CryptoError::InvalidTotpSecret { .. } => "INVALID_TOTP_SECRET",
+            CryptoError::InvalidInput { .. } => "INVALID_INPUT",
+            CryptoError::SessionDestroyed => "SESSION_DESTROYED",
+            CryptoError::VaultNotOpen { .. } => "VAULT_NOT_OPEN",
+            CryptoError::Io { .. } => "IO_ERROR",
+        }
+        .to_string()
+    }
 }
 
 impl From<CoreCryptoError> for CryptoError {
     fn from(e: CoreCryptoError) -> Self {
         match e {
-            CoreCryptoError::KeyDerivation(msg) => CryptoError::KeyDerivation(msg),
-            CoreCryptoError::Encryption(msg) => CryptoError::Encryption(msg),
-            CoreCryptoError::Decryption(msg) => CryptoError::Decryption(msg),
+            CoreCryptoError::KeyDerivation(reason) => CryptoError::KeyDerivation { reason },
+            CoreCryptoError::Encryption(reason) => CryptoError::Encryption { reason },
+            CoreCryptoError::Decryption(reason) => CryptoError::Decryption { reason },
             CoreCryptoError::InvalidKeyLength { .. } => CryptoError::InvalidKeyLength,
-            CoreCryptoError::InvalidNonceLength { .. } => {
-                CryptoError::InvalidInput("Invalid nonce length".to_string())
+            CoreCryptoError::InvalidNonceLength { .. } => CryptoError::InvalidNonceLength,
+            CoreCryptoError::Serialization(reason) => CryptoError::Serialization { reason },
+            CoreCryptoError::Deserialization(reason) => CryptoError::Deserialization { reason },
+            CoreCryptoError::ItemNotFound(id) => CryptoError::ItemNotFound { id },
+            CoreCryptoError::InvalidPasswordOptions(reason) => {
+                CryptoError::InvalidPasswordOptions { reason }
             }
-            CoreCryptoError::Serialization(msg) => CryptoError::Serialization(msg),
-            CoreCryptoError::Deserialization(msg) => CryptoError::Serialization(msg),
-            CoreCryptoError::ItemNotFound(msg) => CryptoError::InvalidInput(msg),
-            CoreCryptoError::InvalidPasswordOptions(msg) => CryptoError::InvalidInput(msg),
-            CoreCryptoError::RandomGeneration(msg) => CryptoError::KeyDerivation(msg),
+            CoreCryptoError::RandomGeneration(reason) => CryptoError::RandomGeneration { reason },
+            CoreCryptoError::InvalidTotpSecret(reason) => CryptoError::InvalidTotpSecret { reason },
+        }
+    }
+}
+
+impl From<std::io::Error> for CryptoError {
+    fn from(e: std::io::Error) -> Self {
+        CryptoError::Io {
+            reason: e.to_string(),
         }
     }
 }
 
 impl From<base64::DecodeError> for CryptoError {
     fn from(e: base64::DecodeError) -> Self {
-        CryptoError::InvalidInput(format!("Base64 decode error: {}", e))
+        CryptoError::invalid_input(format!("Base64 decode error: {}", e))
     }
 }
 
@@ -66,6 +144,14 @@ pub struct KeySet {
     pub sharing_key: String,
 }
 
+/// Derived key set as raw bytes
+#[derive(Debug, Clone)]
+pub struct KeySetBytes {
+    pub vault_key: Vec<u8>,
+    pub auth_key: Vec<u8>,
+    pub sharing_key: Vec<u8>,
+}
+
 /// Password generation options
 #[derive(Debug, Clone)]
 pub struct PasswordOptions {
@@ -106,6 +192,236 @@ impl From<PasswordOptions> for CorePasswordOptions {
     }
 }
 
+/// Password strength estimate
+#[derive(Debug, Clone)]
+pub struct StrengthEstimate {
+    pub score: u8,
+    pub entropy_bits: f64,
+    pub crack_time_seconds: f64,
+    pub crack_time_display: String,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+impl From<strength::StrengthEstimate> for StrengthEstimate {
+    fn from(estimate: strength::StrengthEstimate) -> Self {
+        StrengthEstimate {
+            score: estimate.score,
+            entropy_bits: estimate.entropy_bits,
+            crack_time_seconds: estimate.crack_time_seconds,
+            crack_time_display: estimate.crack_time_display,
+            warning: estimate.warning,
+            suggestions: estimate.suggestions,
+        }
+    }
+}
+
+/// Vault audit thresholds
+#[derive(Debug, Clone)]
+pub struct AuditOptions {
+    pub min_entropy_bits: f64,
+    pub min_length: u32,
+    pub max_age_days: u32,
+}
+
+impl From<AuditOptions> for CoreAuditOptions {
+    fn from(opts: AuditOptions) -> Self {
+        CoreAuditOptions {
+            min_entropy_bits: opts.min_entropy_bits,
+            min_length: opts.min_length as usize,
+            max_age_days: opts.max_age_days as u64,
+        }
+    }
+}
+
+/// Vault audit findings (item IDs)
+#[derive(Debug, Clone)]
+pub struct AuditReport {
+    pub total: u32,
+    pub weak: Vec<String>,
+    pub reused: Vec<Vec<String>>,
+    pub old: Vec<String>,
+    pub breach_candidates: Vec<String>,
+    pub score: u8,
+}
+
+impl From<audit::AuditReport> for AuditReport {
+    fn from(report: audit::AuditReport) -> Self {
+        AuditReport {
+            total: report.total as u32,
+            weak: report.weak,
+            reused: report.reused,
+            old: report.old,
+            breach_candidates: report.breach_candidates,
+            score: report.score,
+        }
+    }
+}
+
+/// HMAC algorithm for TOTP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TotpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// TOTP generation parameters
+#[derive(Debug, Clone)]
+pub struct TotpParams {
+    pub algorithm: TotpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+}
+
+impl From<CoreTotpOptions> for TotpParams {
+    fn from(opts: CoreTotpOptions) -> Self {
+        TotpParams {
+            algorithm: match opts.algorithm {
+                CoreTotpAlgorithm::Sha1 => TotpAlgorithm::Sha1,
+                CoreTotpAlgorithm::Sha256 => TotpAlgorithm::Sha256,
+                CoreTotpAlgorithm::Sha512 => TotpAlgorithm::Sha512,
+            },
+            digits: opts.digits,
+            period: opts.period,
+        }
+    }
+}
+
+impl From<TotpParams> for CoreTotpOptions {
+    fn from(params: TotpParams) -> Self {
+        CoreTotpOptions {
+            algorithm: match params.algorithm {
+                TotpAlgorithm::Sha1 => CoreTotpAlgorithm::Sha1,
+                TotpAlgorithm::Sha256 => CoreTotpAlgorithm::Sha256,
+                TotpAlgorithm::Sha512 => CoreTotpAlgorithm::Sha512,
+            },
+            digits: params.digits,
+            period: params.period,
+        }
+    }
+}
+
+/// A generated TOTP code
+#[derive(Debug, Clone)]
+pub struct TotpCode {
+    pub code: String,
+    pub seconds_remaining: u64,
+    pub period: u64,
+}
+
+/// Parsed otpauth:// URI
+#[derive(Debug, Clone)]
+pub struct OtpAuthData {
+    pub secret: String,
+    pub issuer: Option<String>,
+    pub account: Option<String>,
+    pub params: TotpParams,
+}
+
+/// Kind of vault item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Login,
+    SecureNote,
+    Card,
+    Identity,
+}
+
+impl From<CoreItemKind> for ItemKind {
+    fn from(kind: CoreItemKind) -> Self {
+        match kind {
+            CoreItemKind::Login => ItemKind::Login,
+            CoreItemKind::SecureNote => ItemKind::SecureNote,
+            CoreItemKind::Card => ItemKind::Card,
+            CoreItemKind::Identity => ItemKind::Identity,
+        }
+    }
+}
+
+impl From<ItemKind> for CoreItemKind {
+    fn from(kind: ItemKind) -> Self {
+        match kind {
+            ItemKind::Login => CoreItemKind::Login,
+            ItemKind::SecureNote => CoreItemKind::SecureNote,
+            ItemKind::Card => CoreItemKind::Card,
+            ItemKind::Identity => CoreItemKind::Identity,
+        }
+    }
+}
+
+/// Item filter for paged queries
+#[derive(Debug, Clone, Default)]
+pub struct ItemFilter {
+    pub query: Option<String>,
+    pub category: Option<String>,
+    pub favorite: Option<bool>,
+    pub tag: Option<String>,
+    pub kind: Option<ItemKind>,
+}
+
+impl From<ItemFilter> for CoreItemFilter {
+    fn from(filter: ItemFilter) -> Self {
+        CoreItemFilter {
+            query: filter.query,
+            category: filter.category,
+            favorite: filter.favorite,
+            tag: filter.tag,
+            kind: filter.kind.map(CoreItemKind::from),
+        }
+    }
+}
+
+/// Sort order for paged queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemSort {
+    Name,
+    NameDesc,
+    Newest,
+    Oldest,
+    RecentlyModified,
+}
+
+impl From<ItemSort> for CoreItemSort {
+    fn from(sort: ItemSort) -> Self {
+        match sort {
+            ItemSort::Name => CoreItemSort::Name,
+            ItemSort::NameDesc => CoreItemSort::NameDesc,
+            ItemSort::Newest => CoreItemSort::Newest,
+            ItemSort::Oldest => CoreItemSort::Oldest,
+            ItemSort::RecentlyModified => CoreItemSort::RecentlyModified,
+        }
+    }
+}
+
+/// Custom field data for FFI
+#[derive(Debug, Clone)]
+pub struct CustomFieldData {
+    pub name: String,
+    pub value: String,
+    pub hidden: bool,
+}
+
+impl From<&CoreCustomField> for CustomFieldData {
+    fn from(field: &CoreCustomField) -> Self {
+        CustomFieldData {
+            name: field.name.clone(),
+            value: field.value.clone(),
+            hidden: field.hidden,
+        }
+    }
+}
+
+impl From<CustomFieldData> for CoreCustomField {
+    fn from(data: CustomFieldData) -> Self {
+        CoreCustomField {
+            name: data.name,
+            value: data.value,
+            hidden: data.hidden,
+        }
+    }
+}
+
 /// Vault item data for FFI
 #[derive(Debug, Clone)]
 pub struct VaultItemData {
@@ -117,8 +433,15 @@ pub struct VaultItemData {
     pub notes: Option<String>,
     pub category: Option<String>,
     pub favorite: bool,
-    pub created_at: i64,
-    pub modified_at: i64,
+    pub created_at: u64,
+    pub modified_at: u64,
+    pub kind: ItemKind,
+    pub tags: Vec<String>,
+    pub custom_fields: Vec<CustomFieldData>,
+    pub totp_secret: Option<String>,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub auto_type: Option<String>,
 }
 
 impl From<&CoreVaultItem> for VaultItemData {
@@ -132,8 +455,19 @@ impl From<&CoreVaultItem> for VaultItemData {
             notes: item.notes.clone(),
             category: item.category.clone(),
             favorite: item.favorite,
-            created_at: item.created_at as i64,
-            modified_at: item.modified_at as i64,
+            created_at: item.created_at,
+            modified_at: item.modified_at,
+            kind: item.kind.into(),
+            tags: item.tags.clone(),
+            custom_fields: item
+                .custom_fields
+                .iter()
+                .map(CustomFieldData::from)
+                .collect(),
+            totp_secret: item.totp_secret.clone(),
+            icon: item.icon.clone(),
+            color: item.color.clone(),
+            auto_type: item.auto_type.clone(),
         }
     }
 }
@@ -141,7 +475,10 @@ impl From<&CoreVaultItem> for VaultItemData {
 impl From<VaultItemData> for CoreVaultItem {
     fn from(data: VaultItemData) -> Self {
         let mut item = CoreVaultItem::new(&data.name, &data.username, &data.password);
-        item.id = data.id;
+        // Keep the generated ID for new items
+        if !data.id.is_empty() {
+            item.id = data.id;
+        }
         if let Some(url) = data.url {
             item = item.with_url(&url);
         }
@@ -152,12 +489,260 @@ impl From<VaultItemData> for CoreVaultItem {
             item = item.with_category(&category);
         }
         item = item.with_favorite(data.favorite);
-        item.created_at = data.created_at as u64;
-        item.modified_at = data.modified_at as u64;
+        // Zero means unset; items carried between devices keep their original times
+        if data.created_at != 0 {
+            item.created_at = data.created_at;
+        }
+        if data.modified_at != 0 {
+            item.modified_at = data.modified_at;
+        }
+        item.kind = data.kind.into();
+        item.tags = data.tags;
+        item.custom_fields = data.custom_fields.into_iter().map(Into::into).collect();
+        item.totp_secret = data.totp_secret;
+        item.icon = data.icon;
+        item.color = data.color;
+        item.auto_type = data.auto_type;
         item
     }
 }
 
+/// How an item URL matched the autofill target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlMatchKind {
+    EquivalentDomain,
+    BaseDomain,
+    ExactHost,
+}
+
+impl From<CoreUrlMatchKind> for UrlMatchKind {
+    fn from(kind: CoreUrlMatchKind) -> Self {
+        match kind {
+            CoreUrlMatchKind::EquivalentDomain => UrlMatchKind::EquivalentDomain,
+            CoreUrlMatchKind::BaseDomain => UrlMatchKind::BaseDomain,
+            CoreUrlMatchKind::ExactHost => UrlMatchKind::ExactHost,
+        }
+    }
+}
+
+/// Which matches to accept for autofill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlMatchStrategy {
+    ExactHost,
+    BaseDomain,
+    Equivalent,
+}
+
+impl From<UrlMatchStrategy> for CoreUrlMatchStrategy {
+    fn from(strategy: UrlMatchStrategy) -> Self {
+        match strategy {
+            UrlMatchStrategy::ExactHost => CoreUrlMatchStrategy::ExactHost,
+            UrlMatchStrategy::BaseDomain => CoreUrlMatchStrategy::BaseDomain,
+            UrlMatchStrategy::Equivalent => CoreUrlMatchStrategy::Equivalent,
+        }
+    }
+}
+
+/// An item offered for autofill
+#[derive(Debug, Clone)]
+pub struct AutofillCandidate {
+    pub item_id: String,
+    pub name: String,
+    pub username: String,
+    pub match_kind: UrlMatchKind,
+    pub confidence: f64,
+}
+
+/// Conflict resolution for `Vault::merge_vault`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    NewestWins,
+    KeepLocal,
+    KeepIncoming,
+    KeepBoth,
+}
+
+impl From<MergePolicy> for CoreMergePolicy {
+    fn from(policy: MergePolicy) -> Self {
+        match policy {
+            MergePolicy::NewestWins => CoreMergePolicy::NewestWins,
+            MergePolicy::KeepLocal => CoreMergePolicy::KeepLocal,
+            MergePolicy::KeepIncoming => CoreMergePolicy::KeepIncoming,
+            MergePolicy::KeepBoth => CoreMergePolicy::KeepBoth,
+        }
+    }
+}
+
+/// Outcome of a vault merge (item IDs)
+#[derive(Debug, Clone)]
+pub struct MergeReport {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub duplicated: Vec<String>,
+    pub unchanged: u32,
+}
+
+impl From<CoreMergeReport> for MergeReport {
+    fn from(report: CoreMergeReport) -> Self {
+        MergeReport {
+            added: report.added,
+            updated: report.updated,
+            conflicts: report.conflicts,
+            duplicated: report.duplicated,
+            unchanged: report.unchanged as u32,
+        }
+    }
+}
+
+/// An item in the trash
+#[derive(Debug, Clone)]
+pub struct DeletedItem {
+    pub item: VaultItemData,
+    pub deleted_at: u64,
+    pub expires_at: u64,
+}
+
+impl From<&CoreTrashedItem> for DeletedItem {
+    fn from(trashed: &CoreTrashedItem) -> Self {
+        DeletedItem {
+            item: VaultItemData::from(&trashed.item),
+            deleted_at: trashed.deleted_at,
+            expires_at: trashed.expires_at(),
+        }
+    }
+}
+
+/// Number of items carrying a tag
+#[derive(Debug, Clone)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: u32,
+}
+
+/// Summary counts for dashboards and widgets
+#[derive(Debug, Clone)]
+pub struct VaultStats {
+    pub total: u32,
+    pub logins: u32,
+    pub secure_notes: u32,
+    pub cards: u32,
+    pub identities: u32,
+    pub favorites: u32,
+    pub tags: Vec<TagCount>,
+    pub storage_bytes: u64,
+}
+
+impl From<CoreVaultStats> for VaultStats {
+    fn from(stats: CoreVaultStats) -> Self {
+        VaultStats {
+            total: stats.total as u32,
+            logins: stats.logins as u32,
+            secure_notes: stats.secure_notes as u32,
+            cards: stats.cards as u32,
+            identities: stats.identities as u32,
+            favorites: stats.favorites as u32,
+            tags: stats
+                .tags
+                .into_iter()
+                .map(|(tag, count)| TagCount {
+                    tag,
+                    count: count as u32,
+                })
+                .collect(),
+            storage_bytes: stats.storage_bytes as u64,
+        }
+    }
+}
+
+/// Passphrase generation options
+#[derive(Debug, Clone)]
+pub struct PassphraseOptions {
+    pub word_count: u32,
+    pub separator: String,
+    pub capitalize: bool,
+    pub include_number: bool,
+}
+
+impl From<PassphraseOptions> for CorePassphraseOptions {
+    fn from(opts: PassphraseOptions) -> Self {
+        CorePassphraseOptions {
+            word_count: opts.word_count as usize,
+            separator: opts.separator,
+            capitalize: opts.capitalize,
+            include_number: opts.include_number,
+        }
+    }
+}
+
+/// Options for `Vault::export_backup`
+#[derive(Debug, Clone, Default)]
+pub struct BackupOptions {
+    pub filter: Option<ItemFilter>,
+}
+
+/// CSV column names; unset fields are detected from common headers
+#[derive(Debug, Clone, Default)]
+pub struct CsvMapping {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub notes: Option<String>,
+    pub category: Option<String>,
+}
+
+impl From<CsvMapping> for importers::CsvMapping {
+    fn from(mapping: CsvMapping) -> Self {
+        importers::CsvMapping {
+            name: mapping.name,
+            url: mapping.url,
+            username: mapping.username,
+            password: mapping.password,
+            notes: mapping.notes,
+            category: mapping.category,
+        }
+    }
+}
+
+/// A source row that could not be imported
+#[derive(Debug, Clone)]
+pub struct SkippedRow {
+    pub row: u32,
+    pub reason: String,
+}
+
+/// Items parsed from an import file
+#[derive(Debug, Clone)]
+pub struct ImportResult {
+    pub items: Vec<VaultItemData>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+impl From<importers::ImportReport> for ImportResult {
+    fn from(report: importers::ImportReport) -> Self {
+        ImportResult {
+            items: report.items.iter().map(VaultItemData::from).collect(),
+            skipped: report
+                .skipped
+                .into_iter()
+                .map(|s| SkippedRow {
+                    row: s.row as u32,
+                    reason: s.reason,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Item changes between two vault snapshots
+#[derive(Debug, Clone)]
+pub struct VaultDiff {
+    pub added: Vec<VaultItemData>,
+    pub updated: Vec<VaultItemData>,
+    pub removed: Vec<String>,
+}
+
 // ============ Free Functions ============
 
 /// Generate a random salt for key derivation
@@ -173,6 +758,17 @@ pub fn derive_master_key(password: String, salt_base64: String) -> Result<String
     Ok(master_key.to_base64())
 }
 
+/// Derive master key without blocking the caller
+///
+/// Argon2 takes seconds on low-end phones, so it runs on its own thread and
+/// the returned future resolves when it finishes.
+pub async fn derive_master_key_async(
+    password: String,
+    salt_base64: String,
+) -> Result<String, CryptoError> {
+    spawn_blocking(move || derive_master_key(password, salt_base64)).await
+}
+
 /// Derive encryption keys from master key
 pub fn derive_keys(master_key_base64: String) -> Result<KeySet, CryptoError> {
     let master_key_bytes = STANDARD.decode(&master_key_base64)?;
@@ -188,29 +784,115 @@ pub fn derive_keys(master_key_base64: String) -> Result<KeySet, CryptoError> {
 
 /// Encrypt plaintext with key
 pub fn encrypt(plaintext: String, key_base64: String) -> Result<String, CryptoError> {
-    let key_bytes = STANDARD.decode(&key_base64)?;
-    if key_bytes.len() != 32 {
-        return Err(CryptoError::InvalidKeyLength);
-    }
-
-    let key: [u8; 32] = key_bytes.try_into().unwrap();
+    let key = parse_key(&STANDARD.decode(&key_base64)?)?;
     let blob = cipher::encrypt(plaintext.as_bytes(), &key)?;
     Ok(blob.to_base64())
 }
 
 /// Decrypt ciphertext with key
 pub fn decrypt(encrypted_base64: String, key_base64: String) -> Result<String, CryptoError> {
-    let key_bytes = STANDARD.decode(&key_base64)?;
-    if key_bytes.len() != 32 {
-        return Err(CryptoError::InvalidKeyLength);
-    }
-
-    let key: [u8; 32] = key_bytes.try_into().unwrap();
+    let key = parse_key(&STANDARD.decode(&key_base64)?)?;
     let blob = cipher::EncryptedBlob::from_base64(&encrypted_base64)?;
     let plaintext = cipher::decrypt(&blob, &key)?;
 
-    String::from_utf8(plaintext)
-        .map_err(|e| CryptoError::Decryption(format!("Invalid UTF-8: {}", e)))
+    String::from_utf8(plaintext).map_err(|e| CryptoError::Decryption {
+        reason: format!("Invalid UTF-8: {}", e),
+    })
+}
+
+/// Generate a random salt as raw bytes
+pub fn generate_salt_bytes() -> Result<Vec<u8>, CryptoError> {
+    Ok(kdf::Salt::generate()?.as_bytes().to_vec())
+}
+
+/// Derive master key from password and raw salt bytes
+pub fn derive_master_key_bytes(password: String, salt: Vec<u8>) -> Result<Vec<u8>, CryptoError> {
+    let salt: [u8; kdf::SALT_SIZE] = salt
+        .try_into()
+        .map_err(|_| CryptoError::invalid_input("Invalid salt length"))?;
+    let master_key = kdf::derive_master_key(&password, &kdf::Salt::from_bytes(salt))?;
+    Ok(master_key.as_bytes().to_vec())
+}
+
+/// Derive encryption keys from raw master key bytes
+pub fn derive_keys_bytes(master_key: Vec<u8>) -> Result<KeySetBytes, CryptoError> {
+    let master_key = kdf::MasterKey::from_slice(&master_key)?;
+    let keys = kdf::derive_keys(&master_key)?;
+
+    Ok(KeySetBytes {
+        vault_key: keys.vault_key.to_vec(),
+        auth_key: keys.auth_key.to_vec(),
+        sharing_key: keys.sharing_key.to_vec(),
+    })
+}
+
+/// Encrypt raw bytes, returning nonce || ciphertext
+pub fn encrypt_bytes(plaintext: Vec<u8>, key: Vec<u8>) -> Result<Vec<u8>, CryptoError> {
+    let key = parse_key(&key)?;
+    Ok(cipher::encrypt(&plaintext, &key)?.to_bytes())
+}
+
+/// Decrypt nonce || ciphertext bytes
+pub fn decrypt_bytes(encrypted: Vec<u8>, key: Vec<u8>) -> Result<Vec<u8>, CryptoError> {
+    let key = parse_key(&key)?;
+    let blob = cipher::EncryptedBlob::from_bytes(&encrypted)?;
+    Ok(cipher::decrypt(&blob, &key)?)
+}
+
+/// Wrap a raw vault key under a key-encryption key
+pub fn wrap_vault_key(vault_key: Vec<u8>, kek: Vec<u8>) -> Result<Vec<u8>, CryptoError> {
+    let vault_key = Zeroizing::new(parse_key(&vault_key)?);
+    let kek = Zeroizing::new(parse_key(&kek)?);
+    Ok(cipher::wrap_key(&vault_key, &kek)?)
+}
+
+/// Unwrap a vault key produced by `wrap_vault_key`
+pub fn unwrap_vault_key(wrapped: Vec<u8>, kek: Vec<u8>) -> Result<Vec<u8>, CryptoError> {
+    let kek = Zeroizing::new(parse_key(&kek)?);
+    Ok(cipher::unwrap_key(&wrapped, &kek)?.to_vec())
+}
+
+/// Check a raw key is 32 bytes
+fn parse_key(bytes: &[u8]) -> Result<[u8; 32], CryptoError> {
+    bytes.try_into().map_err(|_| CryptoError::InvalidKeyLength)
+}
+
+/// Encrypt a single item for a sync push
+pub fn encrypt_item(item: VaultItemData, key_base64: String) -> Result<String, CryptoError> {
+    let key = parse_key(&STANDARD.decode(&key_base64)?)?;
+    let item: CoreVaultItem = item.into();
+    Ok(item.export(&key)?.to_base64())
+}
+
+/// Decrypt a single item received from a sync pull
+pub fn decrypt_item(
+    encrypted_base64: String,
+    key_base64: String,
+) -> Result<VaultItemData, CryptoError> {
+    let key = parse_key(&STANDARD.decode(&key_base64)?)?;
+    let blob = cipher::EncryptedBlob::from_base64(&encrypted_base64)?;
+    let item = CoreVaultItem::import(&blob, &key)?;
+    Ok(VaultItemData::from(&item))
+}
+
+/// Compute the items added, updated, and removed between two vault snapshots
+pub fn vault_diff(old: Arc<Vault>, new: Arc<Vault>) -> VaultDiff {
+    // Snapshot first so passing the same vault twice doesn't deadlock
+    let old = old.inner.lock().unwrap().clone();
+    let new = new.inner.lock().unwrap().clone();
+    let diff = old.diff(&new);
+    let collect = |ids: &[String]| {
+        ids.iter()
+            .filter_map(|id| new.get_item(id))
+            .map(VaultItemData::from)
+            .collect()
+    };
+
+    VaultDiff {
+        added: collect(&diff.added),
+        updated: collect(&diff.updated),
+        removed: diff.removed,
+    }
 }
 
 /// Generate a random password
@@ -227,12 +909,164 @@ pub fn generate_passphrase(word_count: u32, separator: String) -> Result<String,
     )?)
 }
 
+/// Generate a passphrase with capitalization and number options
+pub fn generate_passphrase_with_options(options: PassphraseOptions) -> Result<String, CryptoError> {
+    Ok(password::generate_passphrase_with(&options.into())?)
+}
+
+/// Generate a numeric PIN
+pub fn generate_pin(length: u32) -> Result<String, CryptoError> {
+    Ok(password::generate_pin(length as usize)?)
+}
+
+/// Generate a username from random words
+pub fn generate_username(capitalize: bool, include_number: bool) -> String {
+    password::generate_username(capitalize, include_number)
+}
+
+/// Generate a password from a pattern
+pub fn generate_from_pattern(pattern: String) -> Result<String, CryptoError> {
+    Ok(password::generate_from_pattern(&pattern)?)
+}
+
 /// Calculate password entropy
 pub fn calculate_entropy(options: PasswordOptions) -> f64 {
     let core_opts: CorePasswordOptions = options.into();
     password::calculate_entropy(&core_opts)
 }
 
+/// Estimate the strength of any password
+pub fn estimate_strength(password: String) -> StrengthEstimate {
+    strength::estimate_strength(&password).into()
+}
+
+/// Audit vault passwords for weakness, reuse, age, and likely breaches
+pub fn audit_vault(vault: Arc<Vault>, options: Option<AuditOptions>) -> AuditReport {
+    let options = options.map(CoreAuditOptions::from).unwrap_or_default();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let vault = vault.inner.lock().unwrap();
+    audit::audit_items(&vault.items, &options, now).into()
+}
+
+/// Rank items for a web URL or Android package name
+pub fn match_for_autofill(
+    url_or_package: String,
+    items: Vec<VaultItemData>,
+    strategy: UrlMatchStrategy,
+) -> Vec<AutofillCandidate> {
+    let items: Vec<CoreVaultItem> = items.into_iter().map(Into::into).collect();
+    autofill::match_for_autofill(&url_or_package, &items, strategy.into())
+        .into_iter()
+        .map(|m| AutofillCandidate {
+            item_id: m.item.id.clone(),
+            name: m.item.name.clone(),
+            username: m.item.username.clone(),
+            match_kind: m.kind.into(),
+            confidence: m.kind.confidence(),
+        })
+        .collect()
+}
+
+/// Import a generic CSV export
+pub fn import_csv(text: String, mapping: Option<CsvMapping>) -> Result<ImportResult, CryptoError> {
+    let mapping = mapping.unwrap_or_default().into();
+    Ok(importers::import_csv(&text, &mapping)?.into())
+}
+
+/// Import an unencrypted Bitwarden JSON export
+pub fn import_bitwarden_json(text: String) -> Result<ImportResult, CryptoError> {
+    Ok(importers::import_bitwarden_json(&text)?.into())
+}
+
+/// Import a LastPass CSV export
+pub fn import_lastpass_csv(text: String) -> Result<ImportResult, CryptoError> {
+    Ok(importers::import_lastpass_csv(&text)?.into())
+}
+
+/// Import a KeePassXC CSV export
+pub fn import_keepass_csv(text: String) -> Result<ImportResult, CryptoError> {
+    Ok(importers::import_keepass_csv(&text)?.into())
+}
+
+/// Generate the TOTP code for a base32 secret at a Unix timestamp (seconds)
+pub fn generate_totp(
+    secret: String,
+    timestamp: u64,
+    params: Option<TotpParams>,
+) -> Result<TotpCode, CryptoError> {
+    let options = params.map(CoreTotpOptions::from).unwrap_or_default();
+    let code = totp::generate_totp(&secret, &options, timestamp)?;
+    Ok(TotpCode {
+        code: code.code,
+        seconds_remaining: code.seconds_remaining,
+        period: code.period,
+    })
+}
+
+/// Parse an otpauth://totp/ URI from an authenticator QR code
+pub fn parse_otpauth_uri(uri: String) -> Result<OtpAuthData, CryptoError> {
+    let parsed = totp::parse_otpauth_uri(&uri)?;
+    Ok(OtpAuthData {
+        secret: parsed.secret,
+        issuer: parsed.issuer,
+        account: parsed.account,
+        params: parsed.options.into(),
+    })
+}
+
+// ============ Async Helpers ============
+
+/// Shared slot between a worker thread and the future awaiting it
+struct BlockingState<T> {
+    result: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// Future resolving to the result of a closure run on a new thread
+struct BlockingTask<T> {
+    state: Arc<Mutex<BlockingState<T>>>,
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Run blocking work on a dedicated thread, independent of the foreign executor
+fn spawn_blocking<T, F>(work: F) -> BlockingTask<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(BlockingState {
+        result: None,
+        waker: None,
+    }));
+    let worker_state = Arc::clone(&state);
+    std::thread::spawn(move || {
+        let result = work();
+        let mut state = worker_state.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    BlockingTask { state }
+}
+
 // ============ Vault Class ============
 
 /// Vault wrapper for FFI
@@ -240,6 +1074,12 @@ pub struct Vault {
     inner: Mutex<CoreVault>,
 }
 
+impl Default for Vault {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Vault {
     /// Create a new empty vault
     pub fn new() -> Self {
@@ -253,12 +1093,7 @@ impl Vault {
         encrypted_base64: String,
         vault_key_base64: String,
     ) -> Result<Self, CryptoError> {
-        let key_bytes = STANDARD.decode(&vault_key_base64)?;
-        if key_bytes.len() != 32 {
-            return Err(CryptoError::InvalidKeyLength);
-        }
-
-        let key: [u8; 32] = key_bytes.try_into().unwrap();
+        let key = parse_key(&STANDARD.decode(&vault_key_base64)?)?;
         let blob = cipher::EncryptedBlob::from_base64(&encrypted_base64)?;
         let vault = CoreVault::import(&blob, &key)?;
 
@@ -275,7 +1110,47 @@ impl Vault {
         })
     }
 
-    /// Add an item to the vault
+    /// Write a passphrase-encrypted backup file
+    pub fn export_backup(
+        &self,
+        path: String,
+        passphrase: String,
+        options: Option<BackupOptions>,
+    ) -> Result<(), CryptoError> {
+        let mut snapshot = self.inner.lock().unwrap().clone();
+        if let Some(filter) = options.and_then(|o| o.filter) {
+            let filter: CoreItemFilter = filter.into();
+            snapshot.items.retain(|item| filter.matches(item));
+        }
+        let data = backup::seal_backup(&snapshot, &passphrase)?;
+        std::fs::write(&path, data)?;
+        Ok(())
+    }
+
+    /// Read a backup file written by `export_backup`
+    pub fn import_backup(path: String, passphrase: String) -> Result<Self, CryptoError> {
+        let data = std::fs::read(&path)?;
+        let vault = backup::open_backup(&data, &passphrase)?;
+        Ok(Vault {
+            inner: Mutex::new(vault),
+        })
+    }
+
+    /// Decrypt another vault and merge its items into this one
+    pub fn merge_vault(
+        &self,
+        other_encrypted: String,
+        key_base64: String,
+        policy: MergePolicy,
+    ) -> Result<MergeReport, CryptoError> {
+        let key = Zeroizing::new(parse_key(&STANDARD.decode(&key_base64)?)?);
+        let blob = cipher::EncryptedBlob::from_base64(&other_encrypted)?;
+        let other = CoreVault::import(&blob, &key)?;
+        let mut vault = self.inner.lock().unwrap();
+        Ok(vault.merge(&other, policy.into()).into())
+    }
+
+    /// Add an item to the vault; the core fills in zero timestamps
     pub fn add_item(&self, item: VaultItemData) -> Result<String, CryptoError> {
         let mut vault = self.inner.lock().unwrap();
         let core_item: CoreVaultItem = item.into();
@@ -290,7 +1165,7 @@ impl Vault {
         vault.get_item(&id).map(VaultItemData::from)
     }
 
-    /// Update an item
+    /// Update an item; the core keeps its creation time and sets the modification time
     pub fn update_item(&self, id: String, item: VaultItemData) -> Result<(), CryptoError> {
         let mut vault = self.inner.lock().unwrap();
         let core_item: CoreVaultItem = item.into();
@@ -298,11 +1173,65 @@ impl Vault {
         Ok(())
     }
 
-    /// Remove an item
+    /// Mark an item as modified now
+    pub fn touch_item(&self, id: String) -> Result<(), CryptoError> {
+        let mut vault = self.inner.lock().unwrap();
+        Ok(vault.touch_item(&id)?)
+    }
+
+    /// Remove an item, returning it, or `None` if no item has that ID
     pub fn remove_item(&self, id: String) -> Result<Option<VaultItemData>, CryptoError> {
         let mut vault = self.inner.lock().unwrap();
-        let removed = vault.remove_item(&id)?;
-        Ok(Some(VaultItemData::from(&removed)))
+        match vault.remove_item(&id) {
+            Ok(removed) => Ok(Some(VaultItemData::from(&removed))),
+            Err(CoreCryptoError::ItemNotFound(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Move an item to the trash, where it can be restored for 30 days
+    pub fn trash_item(&self, id: String) -> Result<DeletedItem, CryptoError> {
+        let mut vault = self.inner.lock().unwrap();
+        Ok(DeletedItem::from(vault.trash_item(&id)?))
+    }
+
+    /// Items in the trash, oldest deletion first
+    pub fn get_deleted_items(&self) -> Vec<DeletedItem> {
+        let vault = self.inner.lock().unwrap();
+        vault
+            .deleted_items()
+            .iter()
+            .map(DeletedItem::from)
+            .collect()
+    }
+
+    /// Move an item out of the trash back into the vault
+    pub fn restore_item(&self, id: String) -> Result<VaultItemData, CryptoError> {
+        let mut vault = self.inner.lock().unwrap();
+        Ok(VaultItemData::from(vault.restore_item(&id)?))
+    }
+
+    /// Permanently delete an item from the trash
+    pub fn purge_item(&self, id: String) -> Result<(), CryptoError> {
+        let mut vault = self.inner.lock().unwrap();
+        vault.purge_item(&id)?;
+        Ok(())
+    }
+
+    /// Permanently delete everything in the trash, returning the number purged
+    pub fn empty_trash(&self) -> u32 {
+        let mut vault = self.inner.lock().unwrap();
+        vault.empty_trash() as u32
+    }
+
+    /// Purge trashed items past the 30-day window, returning the number purged
+    pub fn purge_expired_trash(&self) -> u32 {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut vault = self.inner.lock().unwrap();
+        vault.purge_expired(now) as u32
     }
 
     /// Get all items
@@ -321,6 +1250,37 @@ impl Vault {
             .collect()
     }
 
+    /// List one sorted page of items matching a filter
+    pub fn list_items(
+        &self,
+        offset: u32,
+        limit: u32,
+        filter: Option<ItemFilter>,
+        sort: ItemSort,
+    ) -> Vec<VaultItemData> {
+        let filter: CoreItemFilter = filter.unwrap_or_default().into();
+        let vault = self.inner.lock().unwrap();
+        vault
+            .list_items(&filter, sort.into(), offset as usize, Some(limit as usize))
+            .items
+            .into_iter()
+            .map(VaultItemData::from)
+            .collect()
+    }
+
+    /// Count items matching a filter
+    pub fn count_items(&self, filter: Option<ItemFilter>) -> u32 {
+        let filter: CoreItemFilter = filter.unwrap_or_default().into();
+        let vault = self.inner.lock().unwrap();
+        vault.count_items(&filter) as u32
+    }
+
+    /// Item counts by kind, favorites, tags, and storage size
+    pub fn vault_stats(&self) -> VaultStats {
+        let vault = self.inner.lock().unwrap();
+        vault.stats().into()
+    }
+
     /// Find items by URL (for autofill)
     pub fn find_by_url(&self, url: String) -> Vec<VaultItemData> {
         let vault = self.inner.lock().unwrap();
@@ -356,12 +1316,7 @@ impl Vault {
 
     /// Export encrypted vault
     pub fn export_encrypted(&self, vault_key_base64: String) -> Result<String, CryptoError> {
-        let key_bytes = STANDARD.decode(&vault_key_base64)?;
-        if key_bytes.len() != 32 {
-            return Err(CryptoError::InvalidKeyLength);
-        }
-
-        let key: [u8; 32] = key_bytes.try_into().unwrap();
+        let key = parse_key(&STANDARD.decode(&vault_key_base64)?)?;
         let vault = self.inner.lock().unwrap();
         let blob = vault.export(&key)?;
         Ok(blob.to_base64())
@@ -386,6 +1341,206 @@ impl Vault {
     }
 }
 
+// ============ Key Session ============
+
+/// Derived keys held in Rust for the length of an unlocked session
+///
+/// Keys are zeroized on `destroy` or when the object is released.
+pub struct KeySession {
+    keys: Mutex<Option<kdf::KeySet>>,
+}
+
+impl KeySession {
+    /// Derive a session from the master password and salt
+    pub fn from_password(password: String, salt_base64: String) -> Result<Self, CryptoError> {
+        let salt = kdf::Salt::from_base64(&salt_base64)?;
+        let master_key = kdf::derive_master_key(&password, &salt)?;
+        Self::from_keys(kdf::derive_keys(&master_key)?)
+    }
+
+    /// Derive a session from a base64 master key
+    pub fn from_master_key(master_key_base64: String) -> Result<Self, CryptoError> {
+        let mut master_key_bytes = STANDARD.decode(&master_key_base64)?;
+        let master_key = kdf::MasterKey::from_slice(&master_key_bytes);
+        master_key_bytes.zeroize();
+        Self::from_keys(kdf::derive_keys(&master_key?)?)
+    }
+
+    fn from_keys(keys: kdf::KeySet) -> Result<Self, CryptoError> {
+        Ok(KeySession {
+            keys: Mutex::new(Some(keys)),
+        })
+    }
+
+    /// Run `f` with the vault key, failing if the session was destroyed
+    fn with_vault_key<T>(
+        &self,
+        f: impl FnOnce(&[u8; 32]) -> Result<T, CryptoError>,
+    ) -> Result<T, CryptoError> {
+        let keys = self.keys.lock().unwrap();
+        let keys = keys.as_ref().ok_or(CryptoError::SessionDestroyed)?;
+        f(&keys.vault_key)
+    }
+
+    /// Encrypt plaintext with the vault key
+    pub fn encrypt(&self, plaintext: String) -> Result<String, CryptoError> {
+        self.with_vault_key(|key| Ok(cipher::encrypt(plaintext.as_bytes(), key)?.to_base64()))
+    }
+
+    /// Decrypt ciphertext with the vault key
+    pub fn decrypt(&self, encrypted_base64: String) -> Result<String, CryptoError> {
+        let blob = cipher::EncryptedBlob::from_base64(&encrypted_base64)?;
+        let plaintext = self.with_vault_key(|key| Ok(cipher::decrypt(&blob, key)?))?;
+        String::from_utf8(plaintext).map_err(|e| CryptoError::Decryption {
+            reason: format!("Invalid UTF-8: {}", e),
+        })
+    }
+
+    /// Export a vault encrypted with the vault key
+    pub fn export_vault(&self, vault: Arc<Vault>) -> Result<String, CryptoError> {
+        let vault = vault.inner.lock().unwrap();
+        self.with_vault_key(|key| Ok(vault.export(key)?.to_base64()))
+    }
+
+    /// Import a vault encrypted with the vault key
+    pub fn import_vault(&self, encrypted_base64: String) -> Result<Arc<Vault>, CryptoError> {
+        let blob = cipher::EncryptedBlob::from_base64(&encrypted_base64)?;
+        let vault = self.with_vault_key(|key| Ok(CoreVault::import(&blob, key)?))?;
+        Ok(Arc::new(Vault {
+            inner: Mutex::new(vault),
+        }))
+    }
+
+    /// Zeroize the keys; later calls fail with `SessionDestroyed`
+    pub fn destroy(&self) {
+        self.keys.lock().unwrap().take();
+    }
+
+    /// Whether `destroy` has been called
+    pub fn is_destroyed(&self) -> bool {
+        self.keys.lock().unwrap().is_none()
+    }
+}
+
+// ============ Vault Manager ============
+
+struct OpenVault {
+    vault: Arc<Vault>,
+    session: Arc<KeySession>,
+}
+
+/// Registry of named vaults open at the same time
+pub struct VaultManager {
+    vaults: Mutex<BTreeMap<String, OpenVault>>,
+}
+
+impl Default for VaultManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VaultManager {
+    /// Create a manager with no open vaults
+    pub fn new() -> Self {
+        VaultManager {
+            vaults: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Create an empty vault under `name`
+    pub fn create_vault(
+        &self,
+        name: String,
+        session: Arc<KeySession>,
+    ) -> Result<Arc<Vault>, CryptoError> {
+        self.insert(name, Vault::new(), session)
+    }
+
+    /// Decrypt a vault with `session` and open it under `name`
+    pub fn open_vault(
+        &self,
+        name: String,
+        encrypted_base64: String,
+        session: Arc<KeySession>,
+    ) -> Result<Arc<Vault>, CryptoError> {
+        let blob = cipher::EncryptedBlob::from_base64(&encrypted_base64)?;
+        let vault = session.with_vault_key(|key| Ok(CoreVault::import(&blob, key)?))?;
+        let vault = Vault {
+            inner: Mutex::new(vault),
+        };
+        self.insert(name, vault, session)
+    }
+
+    fn insert(
+        &self,
+        name: String,
+        vault: Vault,
+        session: Arc<KeySession>,
+    ) -> Result<Arc<Vault>, CryptoError> {
+        if session.is_destroyed() {
+            return Err(CryptoError::SessionDestroyed);
+        }
+        let mut vaults = self.vaults.lock().unwrap();
+        if vaults.contains_key(&name) {
+            return Err(CryptoError::invalid_input(format!(
+                "Vault already open: {}",
+                name
+            )));
+        }
+
+        let vault = Arc::new(vault);
+        vaults.insert(
+            name,
+            OpenVault {
+                vault: Arc::clone(&vault),
+                session,
+            },
+        );
+        Ok(vault)
+    }
+
+    /// Get an open vault by name
+    pub fn get_vault(&self, name: String) -> Option<Arc<Vault>> {
+        let vaults = self.vaults.lock().unwrap();
+        vaults.get(&name).map(|open| Arc::clone(&open.vault))
+    }
+
+    /// Names of all open vaults, sorted
+    pub fn list_vaults(&self) -> Vec<String> {
+        self.vaults.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Export an open vault encrypted under its own session
+    pub fn export_vault(&self, name: String) -> Result<String, CryptoError> {
+        let vaults = self.vaults.lock().unwrap();
+        let open = vaults
+            .get(&name)
+            .ok_or(CryptoError::VaultNotOpen { name })?;
+        open.session.export_vault(Arc::clone(&open.vault))
+    }
+
+    /// Close a vault and destroy its key session
+    pub fn close_vault(&self, name: String) -> bool {
+        let removed = self.vaults.lock().unwrap().remove(&name);
+        match removed {
+            Some(open) => {
+                open.session.destroy();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Close every open vault
+    pub fn close_all(&self) {
+        let vaults = std::mem::take(&mut *self.vaults.lock().unwrap());
+        for open in vaults.into_values() {
+            open.session.destroy();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -414,6 +1569,23 @@ mod tests {
         assert_eq!(plaintext, decrypted);
     }
 
+    #[test]
+    fn test_byte_apis() {
+        let salt = generate_salt_bytes().unwrap();
+        let master_key = derive_master_key_bytes("test_password".to_string(), salt).unwrap();
+        let keys = derive_keys_bytes(master_key).unwrap();
+        assert_eq!(keys.vault_key.len(), 32);
+
+        let encrypted = encrypt_bytes(b"hello".to_vec(), keys.vault_key.clone()).unwrap();
+        let decrypted = decrypt_bytes(encrypted, keys.vault_key).unwrap();
+        assert_eq!(decrypted, b"hello");
+
+        assert!(matches!(
+            encrypt_bytes(b"hello".to_vec(), vec![0u8; 16]),
+            Err(CryptoError::InvalidKeyLength)
+        ));
+    }
+
     #[test]
     fn test_vault_operations() {
         let vault = Vault::new();
@@ -429,6 +1601,17 @@ mod tests {
             favorite: false,
             created_at: 0,
             modified_at: 0,
+            kind: ItemKind::Login,
+            tags: vec!["work".to_string()],
+            custom_fields: vec![CustomFieldData {
+                name: "PIN".to_string(),
+                value: "1234".to_string(),
+                hidden: true,
+            }],
+            totp_secret: Some("JBSWY3DPEHPK3PXP".to_string()),
+            icon: None,
+            color: Some("#e53935".to_string()),
+            auto_type: None,
         };
 
         let id = vault.add_item(item).unwrap();
@@ -436,9 +1619,431 @@ mod tests {
 
         let retrieved = vault.get_item(id.clone()).unwrap();
         assert_eq!(retrieved.name, "Test");
+        assert_eq!(retrieved.tags, vec!["work".to_string()]);
+        assert_eq!(retrieved.custom_fields.len(), 1);
+        assert!(retrieved.custom_fields[0].hidden);
+        assert_eq!(retrieved.totp_secret.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+        assert_eq!(retrieved.color.as_deref(), Some("#e53935"));
+        assert!(retrieved.created_at > 0);
+        assert_eq!(retrieved.created_at, retrieved.modified_at);
+
+        let mut stale = retrieved.clone();
+        stale.created_at = 1;
+        vault.update_item(id.clone(), stale).unwrap();
+        assert_eq!(
+            vault.get_item(id.clone()).unwrap().created_at,
+            retrieved.created_at
+        );
+        vault.touch_item(id.clone()).unwrap();
+        let err = vault.touch_item("missing".to_string()).unwrap_err();
+        assert_eq!(err.code(), "ITEM_NOT_FOUND");
 
         let all = vault.get_all_items();
         assert_eq!(all.len(), 1);
+
+        assert!(vault.remove_item(id.clone()).unwrap().is_some());
+        assert!(vault.remove_item(id.clone()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_trash_operations() {
+        let vault = Vault::new();
+        let id = vault
+            .add_item(VaultItemData::from(&CoreVaultItem::new(
+                "GitHub", "user", "pass",
+            )))
+            .unwrap();
+
+        let deleted = vault.trash_item(id.clone()).unwrap();
+        assert_eq!(deleted.expires_at - deleted.deleted_at, 30 * 24 * 60 * 60);
+        assert!(vault.get_all_items().is_empty());
+        assert_eq!(vault.get_deleted_items().len(), 1);
+        assert_eq!(vault.purge_expired_trash(), 0);
+
+        assert_eq!(vault.restore_item(id.clone()).unwrap().name, "GitHub");
+        let err = vault.restore_item(id.clone()).unwrap_err();
+        assert_eq!(err.code(), "ITEM_NOT_FOUND");
+
+        vault.trash_item(id.clone()).unwrap();
+        vault.purge_item(id.clone()).unwrap();
+        assert!(vault.get_deleted_items().is_empty());
+
+        let id = vault
+            .add_item(VaultItemData::from(&CoreVaultItem::new(
+                "Jira", "user", "pass",
+            )))
+            .unwrap();
+        vault.trash_item(id).unwrap();
+        assert_eq!(vault.empty_trash(), 1);
+    }
+
+    #[test]
+    fn test_wrap_vault_key() {
+        let vault_key = vec![1u8; 32];
+        let kek = vec![2u8; 32];
+
+        let wrapped = wrap_vault_key(vault_key.clone(), kek.clone()).unwrap();
+        assert_eq!(unwrap_vault_key(wrapped.clone(), kek).unwrap(), vault_key);
+
+        let err = unwrap_vault_key(wrapped, vec![3u8; 32]).unwrap_err();
+        assert_eq!(err.code(), "DECRYPTION_FAILED");
+        assert!(wrap_vault_key(vec![1u8; 16], vec![2u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_vault_manager() {
+        let manager = VaultManager::new();
+        let personal = Arc::new(KeySession::from_master_key(STANDARD.encode([1u8; 32])).unwrap());
+        let work = Arc::new(KeySession::from_master_key(STANDARD.encode([2u8; 32])).unwrap());
+
+        let vault = manager
+            .create_vault("personal".to_string(), Arc::clone(&personal))
+            .unwrap();
+        vault
+            .add_item(VaultItemData::from(&CoreVaultItem::new(
+                "Site", "user", "pass",
+            )))
+            .unwrap();
+        manager
+            .create_vault("work".to_string(), Arc::clone(&work))
+            .unwrap();
+        assert!(manager
+            .create_vault("work".to_string(), Arc::clone(&work))
+            .is_err());
+        assert_eq!(manager.list_vaults(), vec!["personal", "work"]);
+
+        // Each vault is encrypted under its own session
+        let exported = manager.export_vault("personal".to_string()).unwrap();
+        assert!(work.import_vault(exported.clone()).is_err());
+
+        assert!(manager.close_vault("personal".to_string()));
+        assert!(personal.is_destroyed());
+        assert!(!manager.close_vault("personal".to_string()));
+        let err = manager.export_vault("personal".to_string()).unwrap_err();
+        assert_eq!(err.code(), "VAULT_NOT_OPEN");
+
+        let personal = Arc::new(KeySession::from_master_key(STANDARD.encode([1u8; 32])).unwrap());
+        let reopened = manager
+            .open_vault("personal".to_string(), exported, personal)
+            .unwrap();
+        assert_eq!(reopened.len(), 1);
+
+        manager.close_all();
+        assert!(manager.list_vaults().is_empty());
+        assert!(work.is_destroyed());
+    }
+
+    #[test]
+    fn test_paged_queries() {
+        let vault = Vault::new();
+        for name in ["Charlie", "alpha", "Bravo", "Delta"] {
+            let item = CoreVaultItem::new(name, "user", "pass").with_favorite(name != "Delta");
+            vault.add_item(VaultItemData::from(&item)).unwrap();
+        }
+
+        let page = vault.list_items(1, 2, None, ItemSort::Name);
+        let names: Vec<_> = page.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names, ["Bravo", "Charlie"]);
+
+        let favorites = ItemFilter {
+            favorite: Some(true),
+            ..ItemFilter::default()
+        };
+        assert_eq!(vault.count_items(Some(favorites.clone())), 3);
+        assert_eq!(vault.count_items(None), 4);
+        let page = vault.list_items(0, 10, Some(favorites), ItemSort::NameDesc);
+        assert_eq!(page[0].name, "Charlie");
+
+        let stats = vault.vault_stats();
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.favorites, 3);
+        assert!(stats.storage_bytes > 0);
+    }
+
+    #[test]
+    fn test_match_for_autofill() {
+        let items = vec![
+            VaultItemData::from(
+                &CoreVaultItem::new("GitHub", "dev", "pw").with_url("https://github.com"),
+            ),
+            VaultItemData::from(
+                &CoreVaultItem::new("App", "mobile", "pw")
+                    .with_url("androidapp://com.github.android"),
+            ),
+        ];
+
+        let candidates = match_for_autofill(
+            "com.github.android".to_string(),
+            items.clone(),
+            UrlMatchStrategy::BaseDomain,
+        );
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].username, "mobile");
+        assert_eq!(candidates[0].match_kind, UrlMatchKind::ExactHost);
+        assert_eq!(candidates[1].confidence, 0.7);
+
+        let candidates = match_for_autofill(
+            "https://github.com/login".to_string(),
+            items,
+            UrlMatchStrategy::ExactHost,
+        );
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].name, "GitHub");
+    }
+
+    #[test]
+    fn test_backup_files() {
+        let vault = Vault::new();
+        for (name, favorite) in [("GitHub", true), ("Bank", false)] {
+            let item = CoreVaultItem::new(name, "user", "pass").with_favorite(favorite);
+            vault.add_item(VaultItemData::from(&item)).unwrap();
+        }
+
+        let path = std::env::temp_dir().join(format!("keydrop-backup-{}.kdbk", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        let options = BackupOptions {
+            filter: Some(ItemFilter {
+                favorite: Some(true),
+                ..ItemFilter::default()
+            }),
+        };
+        vault
+            .export_backup(path.clone(), "passphrase".to_string(), Some(options))
+            .unwrap();
+
+        let restored = Vault::import_backup(path.clone(), "passphrase".to_string()).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored.get_all_items()[0].name, "GitHub");
+
+        let err = Vault::import_backup(path.clone(), "wrong".to_string())
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "DECRYPTION_FAILED");
+        std::fs::remove_file(&path).unwrap();
+
+        let err = Vault::import_backup(path, "passphrase".to_string())
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "IO_ERROR");
+    }
+
+    #[test]
+    fn test_merge_vault() {
+        let key = STANDARD.encode([9u8; 32]);
+        let local = Vault::new();
+        let id = local
+            .add_item(VaultItemData::from(&CoreVaultItem::new(
+                "Site", "user", "pass",
+            )))
+            .unwrap();
+
+        let other = Vault::from_json(local.to_json()).unwrap();
+        let mut changed = other.get_item(id.clone()).unwrap();
+        changed.password = "offline-edit".to_string();
+        other.update_item(id.clone(), changed).unwrap();
+        other
+            .add_item(VaultItemData::from(&CoreVaultItem::new(
+                "New", "user", "pass",
+            )))
+            .unwrap();
+        let encrypted = other.export_encrypted(key.clone()).unwrap();
+
+        let report = local
+            .merge_vault(encrypted.clone(), key.clone(), MergePolicy::KeepIncoming)
+            .unwrap();
+        assert_eq!(report.added.len(), 1);
+        assert_eq!(report.updated, vec![id.clone()]);
+        assert_eq!(local.get_item(id).unwrap().password, "offline-edit");
+
+        let err = local
+            .merge_vault(
+                encrypted,
+                STANDARD.encode([1u8; 32]),
+                MergePolicy::NewestWins,
+            )
+            .unwrap_err();
+        assert_eq!(err.code(), "DECRYPTION_FAILED");
+    }
+
+    #[test]
+    fn test_generators() {
+        let passphrase = generate_passphrase_with_options(PassphraseOptions {
+            word_count: 3,
+            separator: ".".to_string(),
+            capitalize: true,
+            include_number: false,
+        })
+        .unwrap();
+        assert_eq!(passphrase.split('.').count(), 3);
+
+        assert_eq!(generate_pin(8).unwrap().len(), 8);
+        assert_eq!(
+            generate_pin(2).unwrap_err().code(),
+            "INVALID_PASSWORD_OPTIONS"
+        );
+        assert!(!generate_username(true, true).is_empty());
+        assert_eq!(generate_from_pattern("dddd".to_string()).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_importers() {
+        let csv = "name,username,password\nGitHub,dev,pw\nEmpty,,\n".to_string();
+        let result = import_csv(csv, None).unwrap();
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].name, "GitHub");
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(result.skipped[0].row, 3);
+
+        let vault = Vault::new();
+        for item in result.items {
+            vault.add_item(item).unwrap();
+        }
+        assert_eq!(vault.len(), 1);
+
+        let err = import_bitwarden_json("not json".to_string()).unwrap_err();
+        assert_eq!(err.code(), "DESERIALIZATION_FAILED");
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let vault = Vault::new();
+        let missing = VaultItemData::from(&CoreVaultItem::new("Site", "user", "pass"));
+        let err = vault
+            .update_item("missing".to_string(), missing)
+            .unwrap_err();
+        assert!(matches!(err, CryptoError::ItemNotFound { ref id } if id == "missing"));
+        assert_eq!(err.code(), "ITEM_NOT_FOUND");
+
+        let err = generate_password(PasswordOptions {
+            length: 0,
+            ..PasswordOptions::default()
+        })
+        .unwrap_err();
+        assert_eq!(err.code(), "INVALID_PASSWORD_OPTIONS");
+
+        let err = decrypt_bytes(vec![0; 8], vec![0; 16]).unwrap_err();
+        assert_eq!(err.code(), "INVALID_KEY_LENGTH");
+    }
+
+    #[test]
+    fn test_derive_master_key_async() {
+        let salt = generate_salt().unwrap();
+        let expected = derive_master_key("test_password".to_string(), salt.clone()).unwrap();
+
+        let future = derive_master_key_async("test_password".to_string(), salt);
+        let master_key = block_on(future).unwrap();
+        assert_eq!(master_key, expected);
+    }
+
+    /// Minimal executor for driving async FFI functions in tests
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct ThreadWaker(std::thread::Thread);
+
+        impl std::task::Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_key_session() {
+        let master_key = STANDARD.encode([9u8; 32]);
+        let session = KeySession::from_master_key(master_key).unwrap();
+
+        let encrypted = session.encrypt("secret".to_string()).unwrap();
+        assert_eq!(session.decrypt(encrypted).unwrap(), "secret");
+
+        let vault = Arc::new(Vault::new());
+        let item = CoreVaultItem::new("GitHub", "user", "pass");
+        vault.add_item(VaultItemData::from(&item)).unwrap();
+        let exported = session.export_vault(Arc::clone(&vault)).unwrap();
+        assert_eq!(session.import_vault(exported).unwrap().len(), 1);
+
+        session.destroy();
+        assert!(session.is_destroyed());
+        assert!(matches!(
+            session.encrypt("secret".to_string()),
+            Err(CryptoError::SessionDestroyed)
+        ));
+    }
+
+    #[test]
+    fn test_totp() {
+        let parsed = parse_otpauth_uri(
+            "otpauth://totp/Example:alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&digits=8"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(parsed.issuer.as_deref(), Some("Example"));
+        assert_eq!(parsed.params.digits, 8);
+
+        let code = generate_totp(parsed.secret, 59, Some(parsed.params)).unwrap();
+        assert_eq!(code.code, "94287082");
+        assert_eq!(code.seconds_remaining, 1);
+    }
+
+    #[test]
+    fn test_item_encryption_and_diff() {
+        let key = STANDARD.encode([7u8; 32]);
+        let old = Arc::new(Vault::new());
+        let id = old
+            .add_item(VaultItemData::from(&CoreVaultItem::new(
+                "Site", "user", "pass",
+            )))
+            .unwrap();
+
+        let encrypted = encrypt_item(old.get_item(id.clone()).unwrap(), key.clone()).unwrap();
+        let decrypted = decrypt_item(encrypted, key).unwrap();
+        assert_eq!(decrypted.id, id);
+        assert_eq!(decrypted.password, "pass");
+
+        let new = Arc::new(Vault::from_json(old.to_json()).unwrap());
+        let mut changed = decrypted;
+        changed.password = "changed".to_string();
+        new.update_item(id.clone(), changed).unwrap();
+        let added = new
+            .add_item(VaultItemData::from(&CoreVaultItem::new(
+                "New", "user", "pass",
+            )))
+            .unwrap();
+
+        let diff = vault_diff(Arc::clone(&old), Arc::clone(&new));
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].id, added);
+        assert_eq!(diff.updated.len(), 1);
+        assert_eq!(diff.updated[0].password, "changed");
+        assert!(diff.removed.is_empty());
+
+        let diff = vault_diff(new, old);
+        assert_eq!(diff.removed, vec![added]);
+    }
+
+    #[test]
+    fn test_audit_and_strength() {
+        assert!(estimate_strength("Xk9#mP2$vL7@qR4!".to_string()).score >= 3);
+
+        let vault = Arc::new(Vault::new());
+        for password in ["short", "short"] {
+            let item = CoreVaultItem::new("Site", "user", password);
+            vault.add_item(VaultItemData::from(&item)).unwrap();
+        }
+
+        let report = audit_vault(Arc::clone(&vault), None);
+        assert_eq!(report.total, 2);
+        assert_eq!(report.weak.len(), 2);
+        assert_eq!(report.reused.len(), 1);
+        assert_eq!(report.score, 0);
     }
 
     #[test]
@@ -0,0 +1,129 @@
+//! IndexedDB persistence for encrypted vault blobs
+//!
+//! Stores the base64 blob produced by `Vault.export()` in a single object
+//! store so extensions and web apps share one storage layout. Works in
+//! windows, dedicated workers, and extension service workers.
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbFactory, IdbOpenDbRequest, IdbRequest, IdbTransaction};
+
+/// Schema version of the Keydrop database
+const DB_VERSION: u32 = 1;
+
+/// Object store holding the encrypted vault
+const STORE_NAME: &str = "vault";
+
+/// Record key of the encrypted vault blob
+const VAULT_RECORD: &str = "encrypted_vault";
+
+/// Store an encrypted vault blob, replacing any previous one
+pub(crate) async fn save_blob(db_name: &str, blob: &str) -> Result<(), JsValue> {
+    let db = open_database(db_name).await?;
+    let tx =
+        db.transaction_with_str_and_mode(STORE_NAME, web_sys::IdbTransactionMode::Readwrite)?;
+    tx.object_store(STORE_NAME)?
+        .put_with_key(&JsValue::from_str(blob), &JsValue::from_str(VAULT_RECORD))?;
+    let result = transaction_complete(&tx).await;
+    db.close();
+    result
+}
+
+/// Load the encrypted vault blob, if one has been saved
+pub(crate) async fn load_blob(db_name: &str) -> Result<Option<String>, JsValue> {
+    let db = open_database(db_name).await?;
+    let tx = db.transaction_with_str(STORE_NAME)?;
+    let request = tx
+        .object_store(STORE_NAME)?
+        .get(&JsValue::from_str(VAULT_RECORD))?;
+    let result = request_result(&request).await;
+    db.close();
+    Ok(result?.as_string())
+}
+
+/// Get the IndexedDB factory from the current global scope
+fn factory() -> Result<IdbFactory, JsValue> {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("indexedDB"))?
+        .dyn_into::<IdbFactory>()
+        .map_err(|_| JsValue::from_str("IndexedDB is not available in this context"))
+}
+
+/// Open the database, creating the object store on first use
+async fn open_database(db_name: &str) -> Result<IdbDatabase, JsValue> {
+    let request: IdbOpenDbRequest = factory()?.open_with_u32(db_name, DB_VERSION)?;
+
+    let upgrade_request = request.clone();
+    let on_upgrade = Closure::<dyn FnMut(web_sys::Event)>::new(move |_event: web_sys::Event| {
+        let db = match upgrade_request.result() {
+            Ok(db) => db.unchecked_into::<IdbDatabase>(),
+            Err(_) => return,
+        };
+        if !db.object_store_names().contains(STORE_NAME) {
+            let _ = db.create_object_store(STORE_NAME);
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+
+    let result = request_result(&request).await;
+    request.set_onupgradeneeded(None);
+    drop(on_upgrade);
+
+    Ok(result?.unchecked_into::<IdbDatabase>())
+}
+
+/// Wait for a request to succeed and return its result
+async fn request_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let success_request = request.clone();
+        let on_success = Closure::once_into_js(move |_event: web_sys::Event| {
+            let value = success_request.result().unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::NULL, &value);
+        });
+
+        let error_request = request.clone();
+        let on_error = Closure::once_into_js(move |_event: web_sys::Event| {
+            let error = error_request
+                .error()
+                .ok()
+                .flatten()
+                .map(JsValue::from)
+                .unwrap_or_else(|| JsValue::from_str("IndexedDB request failed"));
+            let _ = reject.call1(&JsValue::NULL, &error);
+        });
+
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+
+    JsFuture::from(promise).await
+}
+
+/// Wait for a transaction to commit
+async fn transaction_complete(tx: &IdbTransaction) -> Result<(), JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let on_complete = Closure::once_into_js(move |_event: web_sys::Event| {
+            let _ = resolve.call0(&JsValue::NULL);
+        });
+
+        // A failed request fires `error` and then `abort`, so each needs its
+        // own once-closure; rejecting a settled promise does nothing
+        let on_failure = |error_tx: IdbTransaction, reject: js_sys::Function| {
+            Closure::once_into_js(move |_event: web_sys::Event| {
+                let error = error_tx
+                    .error()
+                    .map(JsValue::from)
+                    .unwrap_or_else(|| JsValue::from_str("IndexedDB transaction failed"));
+                let _ = reject.call1(&JsValue::NULL, &error);
+            })
+        };
+        let on_error = on_failure(tx.clone(), reject.clone());
+        let on_abort = on_failure(tx.clone(), reject);
+
+        tx.set_oncomplete(Some(on_complete.unchecked_ref()));
+        tx.set_onerror(Some(on_error.unchecked_ref()));
+        tx.set_onabort(Some(on_abort.unchecked_ref()));
+    });
+
+    JsFuture::from(promise).await.map(|_| ())
+}
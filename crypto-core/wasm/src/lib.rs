@@ -4,24 +4,436 @@
 //! enabling use in browsers and browser extensions via WebAssembly.
 
 use crypto_core::{
+    autofill,
     cipher::{self, EncryptedBlob, KEY_SIZE},
     error::CryptoError,
     kdf::{self, Salt, SALT_SIZE},
+    matching::{UrlMatchKind, UrlMatchStrategy},
     password::{self, PasswordOptions as RustPasswordOptions},
-    vault::{Vault as RustVault, VaultItem as RustVaultItem},
+    strength,
+    totp::{self, TotpAlgorithm, TotpOptions},
+    vault::{
+        CustomField, ItemFilter, ItemKind, ItemSort, Vault as RustVault, VaultItem as RustVaultItem,
+    },
 };
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+#[cfg(feature = "audit")]
+use crypto_core::audit::{self, AuditOptions};
+#[cfg(feature = "importers")]
+use crypto_core::importers::{self, CsvMapping};
+
+#[cfg(feature = "indexeddb")]
+mod idb;
 
 /// Initialize panic hook for better error messages in console
 #[wasm_bindgen(start)]
 pub fn init() {
+    #[cfg(feature = "panic-hook")]
     console_error_panic_hook::set_once();
 }
 
+// =============================================================================
+// TypeScript Definitions
+// =============================================================================
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+/** Stable error codes carried by `KeydropError.code`. */
+export type KeydropErrorCode =
+    | "KEY_DERIVATION_FAILED"
+    | "ENCRYPTION_FAILED"
+    | "DECRYPTION_FAILED"
+    | "INVALID_KEY_LENGTH"
+    | "INVALID_NONCE_LENGTH"
+    | "SERIALIZATION_FAILED"
+    | "DESERIALIZATION_FAILED"
+    | "ITEM_NOT_FOUND"
+    | "INVALID_PASSWORD_OPTIONS"
+    | "RANDOM_GENERATION_FAILED"
+    | "INVALID_TOTP_SECRET"
+    | "INVALID_INPUT"
+    | "NOT_FOUND"
+    | "STORAGE_ERROR"
+    | "SECRET_DISPOSED"
+    | "SESSION_DESTROYED"
+    | "UNSUPPORTED";
+
+/** Error thrown (or rejected) by every Keydrop function. Branch on `code`, not `message`. */
+export interface KeydropError extends Error {
+    name: "KeydropError";
+    code: KeydropErrorCode;
+}
+
+/** A credential stored in the vault. Timestamps are Unix epoch seconds. */
+export interface VaultItem {
+    id: string;
+    name: string;
+    url?: string | null;
+    username: string;
+    password: string;
+    notes?: string | null;
+    category?: string | null;
+    favorite: boolean;
+    created_at: number;
+    modified_at: number;
+    icon?: string | null;
+    color?: string | null;
+    custom_fields?: CustomField[];
+    kind?: ItemKind;
+    tags?: string[];
+    /** Base32 TOTP secret */
+    totp_secret?: string | null;
+    /** Auto-type keystroke sequence, e.g. "{USERNAME}{TAB}{PASSWORD}{ENTER}" */
+    auto_type?: string | null;
+}
+
+/** What a vault item stores. Defaults to "login". */
+export type ItemKind = "login" | "secure_note" | "card" | "identity";
+
+/** Extra named value on a vault item. */
+export interface CustomField {
+    name: string;
+    value: string;
+    hidden: boolean;
+}
+
+/** A vault item whose password is held in WASM memory behind a `SecretHandle`. */
+export interface SecretItem extends Omit<VaultItem, "password"> {
+    password: SecretHandle;
+}
+
+/**
+ * Item list in column form: entry `i` of every array describes the same item.
+ * Passwords are omitted; fetch them with `getItem` or `getSecretItem`.
+ */
+export interface ItemColumns {
+    ids: string[];
+    names: string[];
+    usernames: string[];
+    urls: (string | null)[];
+    categories: (string | null)[];
+    favorites: boolean[];
+    modified_at: number[];
+}
+
+/** Password generation options. Omitted fields use the library defaults. */
+export interface PasswordOptions {
+    length?: number;
+    lowercase?: boolean;
+    uppercase?: boolean;
+    digits?: boolean;
+    symbols?: boolean;
+    exclude_ambiguous?: boolean;
+    exclude_chars?: string;
+}
+
+/** Keys derived from the master key, base64-encoded. */
+export interface KeySet {
+    vault_key: string;
+    auth_key: string;
+    sharing_key: string;
+}
+
+/** Kind of change reported to `Vault.onChange` listeners. */
+export type VaultChangeType = "add" | "update" | "remove";
+
+/** Event passed to `Vault.onChange` listeners. */
+export interface VaultChangeEvent {
+    type: VaultChangeType;
+    id: string;
+}
+
+/** Which URL matches `findByUrl` accepts. Defaults to "base_domain". */
+export type UrlMatchStrategy = "exact_host" | "base_domain" | "equivalent";
+
+/** How an item matched the page URL, strongest first. */
+export type UrlMatchKind = "exact_host" | "base_domain" | "equivalent_domain";
+
+/** A vault item matched against a URL, with match details. */
+export interface UrlMatch extends VaultItem {
+    match_kind: UrlMatchKind;
+    /** Match confidence from 0 to 1 */
+    confidence: number;
+}
+
+/** TOTP options. Defaults: SHA1, 6 digits, 30 second period, current time. */
+export interface TotpOptions {
+    algorithm?: "SHA1" | "SHA256" | "SHA512";
+    digits?: number;
+    period?: number;
+    /** Unix epoch seconds to generate the code for */
+    timestamp?: number;
+}
+
+/** A source row that was not imported. */
+export interface ImportSkippedRow {
+    /** 1-based row number, counting the header */
+    row: number;
+    reason: string;
+}
+
+/** Result of a bulk import. */
+export interface ImportSummary {
+    added: number;
+    ids: string[];
+    skipped: ImportSkippedRow[];
+}
+
+/** Criteria for `listItems`; unset fields match everything. */
+export interface ItemFilter {
+    /** Case-insensitive match against name, username, or URL */
+    query?: string;
+    category?: string;
+    favorite?: boolean;
+    /** Only items carrying this tag */
+    tag?: string;
+    kind?: ItemKind;
+}
+
+/** Sort order for `listItems`. Defaults to "name". */
+export type ItemSort = "name" | "name_desc" | "newest" | "oldest" | "recently_modified";
+
+/** Options for `listItems`. `limit` defaults to 50. */
+export interface ListItemsOptions {
+    offset?: number;
+    limit?: number;
+    filter?: ItemFilter;
+    sort?: ItemSort;
+}
+
+/** One page of items from `listItems`. */
+export interface ItemPage {
+    items: VaultItem[];
+    /** Number of items matching the filter across all pages */
+    total: number;
+    offset: number;
+}
+
+/** Kind of form field an autofill value belongs in. */
+export type AutofillFieldKind = "username" | "password" | "totp";
+
+/** A value to fill, with lowercase field name / autocomplete substrings that identify its input. */
+export interface AutofillField {
+    kind: AutofillFieldKind;
+    value: string;
+    hints: string[];
+}
+
+/** A vault item matching the page, with the fields to fill. */
+export interface AutofillCandidate {
+    item_id: string;
+    name: string;
+    match_kind: UrlMatchKind;
+    confidence: number;
+    fields: AutofillField[];
+}
+
+/** Autofill data for a page, best candidate first. */
+export interface AutofillPayload {
+    url: string;
+    host: string;
+    candidates: AutofillCandidate[];
+}
+
+/** Password strength estimate from `estimateStrength`. */
+export interface StrengthEstimate {
+    /** 0 (very weak) to 4 (very strong) */
+    score: 0 | 1 | 2 | 3 | 4;
+    entropy_bits: number;
+    /** Expected seconds to crack offline at 10 billion guesses per second */
+    crack_time_seconds: number;
+    /** e.g. "3 hours" or "centuries" */
+    crack_time_display: string;
+    warning?: string | null;
+    suggestions: string[];
+}
+
+/** A generated password with its strength. */
+export interface PasswordCandidate {
+    password: string;
+    strength: StrengthEstimate;
+}
+
+/** A generated TOTP code. */
+export interface TotpCode {
+    code: string;
+    seconds_remaining: number;
+    period: number;
+}
+"#;
+
+#[cfg(feature = "importers")]
+#[wasm_bindgen(typescript_custom_section)]
+const TS_IMPORTER_TYPES: &'static str = r#"
+/** CSV column names per field; omitted fields are detected from common headers. */
+export interface CsvMapping {
+    name?: string;
+    url?: string;
+    username?: string;
+    password?: string;
+    notes?: string;
+    category?: string;
+}
+
+/** Items parsed by `importCsv` / `importBitwardenJson`, not yet added to a vault. */
+export interface ImportResult {
+    items: VaultItem[];
+    skipped: ImportSkippedRow[];
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "VaultItem")]
+    pub type TsVaultItem;
+
+    #[wasm_bindgen(typescript_type = "VaultItem | null")]
+    pub type TsOptionalVaultItem;
+
+    #[wasm_bindgen(typescript_type = "VaultItem[]")]
+    pub type TsVaultItemList;
+
+    #[wasm_bindgen(typescript_type = "PasswordOptions")]
+    pub type TsPasswordOptions;
+
+    #[wasm_bindgen(typescript_type = "KeySet")]
+    pub type TsKeySet;
+
+    #[wasm_bindgen(typescript_type = "string[]")]
+    pub type TsStringList;
+
+    #[wasm_bindgen(typescript_type = "UrlMatchStrategy")]
+    pub type TsUrlMatchStrategy;
+
+    #[wasm_bindgen(typescript_type = "UrlMatch[]")]
+    pub type TsUrlMatchList;
+
+    #[wasm_bindgen(typescript_type = "TotpOptions")]
+    pub type TsTotpOptions;
+
+    #[wasm_bindgen(typescript_type = "TotpCode")]
+    pub type TsTotpCode;
+
+    #[wasm_bindgen(typescript_type = "StrengthEstimate")]
+    pub type TsStrengthEstimate;
+
+    #[wasm_bindgen(typescript_type = "PasswordCandidate[]")]
+    pub type TsPasswordCandidateList;
+
+    #[wasm_bindgen(typescript_type = "AutofillPayload")]
+    pub type TsAutofillPayload;
+
+    #[wasm_bindgen(typescript_type = "SecretItem | null")]
+    pub type TsOptionalSecretItem;
+
+    #[wasm_bindgen(typescript_type = "ItemColumns")]
+    pub type TsItemColumns;
+
+    #[wasm_bindgen(typescript_type = "ListItemsOptions")]
+    pub type TsListItemsOptions;
+
+    #[wasm_bindgen(typescript_type = "ItemPage")]
+    pub type TsItemPage;
+
+    #[wasm_bindgen(typescript_type = "ImportSummary")]
+    pub type TsImportSummary;
+}
+
+#[cfg(feature = "audit")]
+#[wasm_bindgen(typescript_custom_section)]
+const TS_AUDIT_TYPES: &'static str = r#"
+/** Thresholds for `Vault.audit`. Defaults: 60 bits, 10 characters, 365 days. */
+export interface AuditOptions {
+    min_entropy_bits?: number;
+    min_length?: number;
+    max_age_days?: number;
+}
+
+/** Vault security checkup. All lists hold item IDs. */
+export interface AuditReport {
+    /** Number of items with a password */
+    total: number;
+    weak: string[];
+    /** Groups of items sharing the same password */
+    reused: string[][];
+    old: string[];
+    /** Items using a common password likely found in breaches */
+    breach_candidates: string[];
+    /** Percentage of items with no findings */
+    score: number;
+}
+"#;
+
+#[cfg(feature = "audit")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "AuditOptions")]
+    pub type TsAuditOptions;
+
+    #[wasm_bindgen(typescript_type = "AuditReport")]
+    pub type TsAuditReport;
+}
+
+#[cfg(feature = "importers")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CsvMapping")]
+    pub type TsCsvMapping;
+
+    #[wasm_bindgen(typescript_type = "ImportResult")]
+    pub type TsImportResult;
+}
+
+/// Error code for malformed arguments (bad base64, wrong lengths, wrong shapes)
+const INVALID_INPUT: &str = "INVALID_INPUT";
+
+/// Error code for lookups that found nothing
+#[cfg(feature = "indexeddb")]
+const NOT_FOUND: &str = "NOT_FOUND";
+
+/// Error code for browser storage failures
+#[cfg(feature = "indexeddb")]
+const STORAGE_ERROR: &str = "STORAGE_ERROR";
+
+/// Error code for using a `SecretHandle` after `dispose`
+const SECRET_DISPOSED: &str = "SECRET_DISPOSED";
+
+/// Error code for using a `KeySession` after `destroy`
+const SESSION_DESTROYED: &str = "SESSION_DESTROYED";
+
+/// Error code for browser APIs missing from the current environment
+const UNSUPPORTED: &str = "UNSUPPORTED";
+
+/// Build a `KeydropError`: a JS `Error` with a stable `code` property
+fn keydrop_error(code: &str, message: &str) -> JsValue {
+    let error = js_sys::Error::new(message);
+    error.set_name("KeydropError");
+    let _ = js_sys::Reflect::set(&error, &JsValue::from_str("code"), &JsValue::from_str(code));
+    error.into()
+}
+
 /// Convert CryptoError to JsValue for JavaScript
 fn to_js_error(e: CryptoError) -> JsValue {
-    JsValue::from_str(&e.to_string())
+    keydrop_error(e.code(), &e.to_string())
+}
+
+/// Wrap a browser storage failure as a `STORAGE_ERROR`
+#[cfg(feature = "indexeddb")]
+fn storage_error(e: JsValue) -> JsValue {
+    let message = js_sys::Reflect::get(&e, &JsValue::from_str("message"))
+        .ok()
+        .and_then(|m| m.as_string())
+        .or_else(|| e.as_string())
+        .unwrap_or_else(|| "IndexedDB operation failed".to_string());
+    keydrop_error(STORAGE_ERROR, &message)
+}
+
+/// Build an `INVALID_INPUT` error
+fn invalid_input(message: impl std::fmt::Display) -> JsValue {
+    keydrop_error(INVALID_INPUT, &message.to_string())
 }
 
 // =============================================================================
@@ -41,7 +453,7 @@ pub fn generate_salt() -> Result<String, JsValue> {
 pub fn derive_master_key(password: &str, salt_base64: &str) -> Result<String, JsValue> {
     let salt_bytes = base64_decode(salt_base64)?;
     if salt_bytes.len() != SALT_SIZE {
-        return Err(JsValue::from_str(&format!(
+        return Err(invalid_input(format!(
             "Invalid salt length: expected {}, got {}",
             SALT_SIZE,
             salt_bytes.len()
@@ -59,10 +471,10 @@ pub fn derive_master_key(password: &str, salt_base64: &str) -> Result<String, Js
 /// Derive key set (vault, auth, sharing keys) from master key
 /// Returns JSON object with vault_key, auth_key, and sharing_key as base64
 #[wasm_bindgen(js_name = deriveKeys)]
-pub fn derive_keys(master_key_base64: &str) -> Result<JsValue, JsValue> {
+pub fn derive_keys(master_key_base64: &str) -> Result<TsKeySet, JsValue> {
     let master_bytes = base64_decode(master_key_base64)?;
     if master_bytes.len() != KEY_SIZE {
-        return Err(JsValue::from_str(&format!(
+        return Err(invalid_input(format!(
             "Invalid master key length: expected {}, got {}",
             KEY_SIZE,
             master_bytes.len()
@@ -81,7 +493,36 @@ pub fn derive_keys(master_key_base64: &str) -> Result<JsValue, JsValue> {
         sharing_key: base64_encode(&keys.sharing_key),
     };
 
-    serde_wasm_bindgen::to_value(&result).map_err(|e| JsValue::from_str(&e.to_string()))
+    to_js(&result)
+}
+
+/// Derive master key inside a Web Worker
+///
+/// Argon2 runs as a single blocking call, so this must not be called on the
+/// page or extension popup thread; run it in a worker and await the result
+/// there. Resolves to the raw 32-byte master key as a Uint8Array whose buffer
+/// can be transferred back to the page with `postMessage(key, [key.buffer])`
+/// instead of copying a base64 string.
+#[wasm_bindgen(js_name = deriveMasterKeyAsync)]
+pub async fn derive_master_key_async(
+    password: String,
+    salt_base64: String,
+) -> Result<js_sys::Uint8Array, JsValue> {
+    let salt_bytes = base64_decode(&salt_base64)?;
+    if salt_bytes.len() != SALT_SIZE {
+        return Err(invalid_input(format!(
+            "Invalid salt length: expected {}, got {}",
+            SALT_SIZE,
+            salt_bytes.len()
+        )));
+    }
+
+    let mut salt_array = [0u8; SALT_SIZE];
+    salt_array.copy_from_slice(&salt_bytes);
+    let salt = Salt::from_bytes(salt_array);
+
+    let master_key = kdf::derive_master_key(&password, &salt).map_err(to_js_error)?;
+    Ok(js_sys::Uint8Array::from(master_key.as_bytes().as_slice()))
 }
 
 #[derive(Serialize)]
@@ -111,7 +552,109 @@ pub fn decrypt(encrypted_base64: &str, key_base64: &str) -> Result<String, JsVal
     let key = parse_key(key_base64)?;
     let blob = EncryptedBlob::from_base64(encrypted_base64).map_err(to_js_error)?;
     let plaintext = cipher::decrypt(&blob, &key).map_err(to_js_error)?;
-    String::from_utf8(plaintext).map_err(|e| JsValue::from_str(&e.to_string()))
+    String::from_utf8(plaintext).map_err(invalid_input)
+}
+
+/// Encrypt raw bytes using AES-256-GCM
+/// Takes plaintext and raw 32-byte key as Uint8Array, returns nonce || ciphertext
+#[wasm_bindgen(js_name = encryptBytes)]
+pub fn encrypt_bytes(plaintext: &[u8], key: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let key = parse_key_bytes(key)?;
+    let blob = cipher::encrypt(plaintext, &key).map_err(to_js_error)?;
+    Ok(blob.to_bytes())
+}
+
+/// Decrypt raw bytes using AES-256-GCM
+/// Takes nonce || ciphertext and raw 32-byte key as Uint8Array, returns plaintext bytes
+#[wasm_bindgen(js_name = decryptBytes)]
+pub fn decrypt_bytes(encrypted: &[u8], key: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let key = parse_key_bytes(key)?;
+    let blob = EncryptedBlob::from_bytes(encrypted).map_err(to_js_error)?;
+    cipher::decrypt(&blob, &key).map_err(to_js_error)
+}
+
+// =============================================================================
+// Key Session
+// =============================================================================
+
+/// Derived keys held in WASM memory for the length of an unlocked session
+///
+/// Keys are never exposed to JS; they are zeroed on `destroy` or `free`.
+#[wasm_bindgen]
+pub struct KeySession {
+    keys: Option<kdf::KeySet>,
+}
+
+impl KeySession {
+    fn vault_key(&self) -> Result<&[u8; KEY_SIZE], JsValue> {
+        self.keys
+            .as_ref()
+            .map(|keys| &keys.vault_key)
+            .ok_or_else(|| keydrop_error(SESSION_DESTROYED, "Key session has been destroyed"))
+    }
+}
+
+#[wasm_bindgen]
+impl KeySession {
+    /// Derive a session from the master password and salt (base64)
+    #[wasm_bindgen(js_name = fromPassword)]
+    pub fn from_password(password: &str, salt_base64: &str) -> Result<KeySession, JsValue> {
+        let salt = Salt::from_base64(salt_base64).map_err(to_js_error)?;
+        let master_key = kdf::derive_master_key(password, &salt).map_err(to_js_error)?;
+        let keys = kdf::derive_keys(&master_key).map_err(to_js_error)?;
+        Ok(KeySession { keys: Some(keys) })
+    }
+
+    /// Derive a session from a base64 master key
+    #[wasm_bindgen(js_name = fromMasterKey)]
+    pub fn from_master_key(master_key_base64: &str) -> Result<KeySession, JsValue> {
+        let master_bytes = Zeroizing::new(base64_decode(master_key_base64)?);
+        let master_key = kdf::MasterKey::from_slice(&master_bytes).map_err(to_js_error)?;
+        let keys = kdf::derive_keys(&master_key).map_err(to_js_error)?;
+        Ok(KeySession { keys: Some(keys) })
+    }
+
+    /// Encrypt a string with the vault key, returns base64 blob
+    #[wasm_bindgen]
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, JsValue> {
+        let blob = cipher::encrypt(plaintext.as_bytes(), self.vault_key()?).map_err(to_js_error)?;
+        Ok(blob.to_base64())
+    }
+
+    /// Decrypt a base64 blob with the vault key
+    #[wasm_bindgen]
+    pub fn decrypt(&self, encrypted_base64: &str) -> Result<String, JsValue> {
+        let blob = EncryptedBlob::from_base64(encrypted_base64).map_err(to_js_error)?;
+        let plaintext = cipher::decrypt(&blob, self.vault_key()?).map_err(to_js_error)?;
+        String::from_utf8(plaintext).map_err(invalid_input)
+    }
+
+    /// Export a vault as an encrypted base64 blob
+    #[wasm_bindgen(js_name = exportVault)]
+    pub fn export_vault(&self, vault: &Vault) -> Result<String, JsValue> {
+        let blob = vault.inner.export(self.vault_key()?).map_err(to_js_error)?;
+        Ok(blob.to_base64())
+    }
+
+    /// Import a vault from an encrypted base64 blob
+    #[wasm_bindgen(js_name = importVault)]
+    pub fn import_vault(&self, encrypted_base64: &str) -> Result<Vault, JsValue> {
+        let blob = EncryptedBlob::from_base64(encrypted_base64).map_err(to_js_error)?;
+        let inner = RustVault::import(&blob, self.vault_key()?).map_err(to_js_error)?;
+        Ok(Vault::wrap(inner))
+    }
+
+    /// Zero the keys; later calls fail with `SESSION_DESTROYED`
+    #[wasm_bindgen]
+    pub fn destroy(&mut self) {
+        self.keys = None;
+    }
+
+    /// Whether `destroy` has been called
+    #[wasm_bindgen(getter, js_name = isDestroyed)]
+    pub fn is_destroyed(&self) -> bool {
+        self.keys.is_none()
+    }
 }
 
 // =============================================================================
@@ -130,26 +673,66 @@ pub struct PasswordOptionsJs {
     pub exclude_chars: Option<String>,
 }
 
+impl From<PasswordOptionsJs> for RustPasswordOptions {
+    fn from(opts: PasswordOptionsJs) -> Self {
+        RustPasswordOptions {
+            length: opts.length.unwrap_or(16),
+            lowercase: opts.lowercase.unwrap_or(true),
+            uppercase: opts.uppercase.unwrap_or(true),
+            digits: opts.digits.unwrap_or(true),
+            symbols: opts.symbols.unwrap_or(true),
+            exclude_ambiguous: opts.exclude_ambiguous.unwrap_or(false),
+            exclude_chars: opts.exclude_chars.unwrap_or_default(),
+        }
+    }
+}
+
+/// Most candidates `generatePasswords` returns in one call
+const MAX_PASSWORD_CANDIDATES: usize = 100;
+
+/// Generated password with its strength, for JavaScript
+#[derive(Serialize)]
+struct PasswordCandidateJs {
+    password: String,
+    strength: strength::StrengthEstimate,
+}
+
 /// Generate a random password with the given options
 #[wasm_bindgen(js_name = generatePassword)]
-pub fn generate_password(options: JsValue) -> Result<String, JsValue> {
-    let opts: PasswordOptionsJs =
-        serde_wasm_bindgen::from_value(options).map_err(|e| JsValue::from_str(&e.to_string()))?;
-
-    let rust_opts = RustPasswordOptions {
-        length: opts.length.unwrap_or(16),
-        lowercase: opts.lowercase.unwrap_or(true),
-        uppercase: opts.uppercase.unwrap_or(true),
-        digits: opts.digits.unwrap_or(true),
-        symbols: opts.symbols.unwrap_or(true),
-        exclude_ambiguous: opts.exclude_ambiguous.unwrap_or(false),
-        exclude_chars: opts.exclude_chars.unwrap_or_default(),
-    };
+pub fn generate_password(options: TsPasswordOptions) -> Result<String, JsValue> {
+    let opts: PasswordOptionsJs = from_js(options)?;
+    let rust_opts = RustPasswordOptions::from(opts);
 
     password::generate_password(&rust_opts).map_err(to_js_error)
 }
 
+/// Generate several candidate passwords, each with its strength estimate
+#[wasm_bindgen(js_name = generatePasswords)]
+pub fn generate_passwords(
+    options: TsPasswordOptions,
+    count: usize,
+) -> Result<TsPasswordCandidateList, JsValue> {
+    if count == 0 || count > MAX_PASSWORD_CANDIDATES {
+        return Err(invalid_input(format!(
+            "Candidate count must be between 1 and {}",
+            MAX_PASSWORD_CANDIDATES
+        )));
+    }
+    let opts: PasswordOptionsJs = from_js(options)?;
+    let rust_opts = RustPasswordOptions::from(opts);
+
+    let candidates = (0..count)
+        .map(|_| {
+            let password = password::generate_password(&rust_opts).map_err(to_js_error)?;
+            let strength = strength::estimate_strength(&password);
+            Ok(PasswordCandidateJs { password, strength })
+        })
+        .collect::<Result<Vec<_>, JsValue>>()?;
+    to_js(&candidates)
+}
+
 /// Generate a passphrase with the given number of words
+#[cfg(feature = "eff-wordlist")]
 #[wasm_bindgen(js_name = generatePassphrase)]
 pub fn generate_passphrase(word_count: usize, separator: &str) -> Result<String, JsValue> {
     password::generate_passphrase(word_count, separator).map_err(to_js_error)
@@ -157,23 +740,174 @@ pub fn generate_passphrase(word_count: usize, separator: &str) -> Result<String,
 
 /// Calculate password entropy
 #[wasm_bindgen(js_name = calculateEntropy)]
-pub fn calculate_entropy(options: JsValue) -> Result<f64, JsValue> {
-    let opts: PasswordOptionsJs =
-        serde_wasm_bindgen::from_value(options).map_err(|e| JsValue::from_str(&e.to_string()))?;
-
-    let rust_opts = RustPasswordOptions {
-        length: opts.length.unwrap_or(16),
-        lowercase: opts.lowercase.unwrap_or(true),
-        uppercase: opts.uppercase.unwrap_or(true),
-        digits: opts.digits.unwrap_or(true),
-        symbols: opts.symbols.unwrap_or(true),
-        exclude_ambiguous: opts.exclude_ambiguous.unwrap_or(false),
-        exclude_chars: opts.exclude_chars.unwrap_or_default(),
-    };
+pub fn calculate_entropy(options: TsPasswordOptions) -> Result<f64, JsValue> {
+    let opts: PasswordOptionsJs = from_js(options)?;
+    let rust_opts = RustPasswordOptions::from(opts);
 
     Ok(password::calculate_entropy(&rust_opts))
 }
 
+/// Estimate the strength of any password, e.g. for signup-form meters
+#[wasm_bindgen(js_name = estimateStrength)]
+pub fn estimate_strength(password: &str) -> Result<TsStrengthEstimate, JsValue> {
+    to_js(&strength::estimate_strength(password))
+}
+
+// =============================================================================
+// TOTP
+// =============================================================================
+
+/// TOTP options
+#[derive(Deserialize, Default)]
+pub struct TotpOptionsJs {
+    pub algorithm: Option<TotpAlgorithm>,
+    pub digits: Option<u32>,
+    pub period: Option<u64>,
+    pub timestamp: Option<u64>,
+}
+
+/// Generate the TOTP code for a base32 secret
+/// Returns the code and the seconds remaining until it changes
+#[wasm_bindgen(js_name = generateTotp)]
+pub fn generate_totp(secret: &str, options: Option<TsTotpOptions>) -> Result<TsTotpCode, JsValue> {
+    let opts: TotpOptionsJs = match options {
+        Some(options) => from_js(options)?,
+        None => TotpOptionsJs::default(),
+    };
+
+    let defaults = TotpOptions::default();
+    let rust_opts = TotpOptions {
+        algorithm: opts.algorithm.unwrap_or(defaults.algorithm),
+        digits: opts.digits.unwrap_or(defaults.digits),
+        period: opts.period.unwrap_or(defaults.period),
+    };
+    let timestamp = opts
+        .timestamp
+        .unwrap_or_else(|| (js_sys::Date::now() / 1000.0) as u64);
+
+    let code = totp::generate_totp(secret, &rust_opts, timestamp).map_err(to_js_error)?;
+    to_js(&code)
+}
+
+// =============================================================================
+// Import
+// =============================================================================
+
+/// Parse CSV text with a header row into items without adding them to a vault
+#[cfg(feature = "importers")]
+#[wasm_bindgen(js_name = importCsv)]
+pub fn import_csv(text: &str, mapping: Option<TsCsvMapping>) -> Result<TsImportResult, JsValue> {
+    let mapping: CsvMapping = match mapping {
+        Some(mapping) => from_js(mapping)?,
+        None => CsvMapping::default(),
+    };
+    let report = importers::import_csv(text, &mapping).map_err(to_js_error)?;
+    to_js(&ImportResultJs::from(report))
+}
+
+/// Parse an unencrypted Bitwarden JSON export into items without adding them to a vault
+#[cfg(feature = "importers")]
+#[wasm_bindgen(js_name = importBitwardenJson)]
+pub fn import_bitwarden_json(text: &str) -> Result<TsImportResult, JsValue> {
+    let report = importers::import_bitwarden_json(text).map_err(to_js_error)?;
+    to_js(&ImportResultJs::from(report))
+}
+
+// =============================================================================
+// Autofill
+// =============================================================================
+
+/// Build the autofill payload for a page from candidate items
+/// Applies URL matching and ranking, and attaches field-name hints and current TOTP codes
+#[wasm_bindgen(js_name = buildAutofillPayload)]
+pub fn build_autofill_payload(
+    url: &str,
+    items: TsVaultItemList,
+    strategy: Option<TsUrlMatchStrategy>,
+) -> Result<TsAutofillPayload, JsValue> {
+    let items: Vec<VaultItemJs> = from_js(items)?;
+    let items: Vec<RustVaultItem> = items.into_iter().map(Into::into).collect();
+    let strategy: UrlMatchStrategy = match strategy {
+        Some(strategy) => from_js(strategy)?,
+        None => UrlMatchStrategy::default(),
+    };
+    let timestamp = (js_sys::Date::now() / 1000.0) as u64;
+
+    to_js(&autofill::build_autofill_payload(
+        url, &items, strategy, timestamp,
+    ))
+}
+
+// =============================================================================
+// Secret Handles
+// =============================================================================
+
+/// Opaque handle to a secret held in WASM memory
+///
+/// The secret only becomes a JS string when `reveal` is called, and is
+/// zeroed on `dispose` or `free`.
+#[wasm_bindgen]
+pub struct SecretHandle {
+    secret: Option<Zeroizing<String>>,
+}
+
+impl SecretHandle {
+    fn new(secret: &str) -> SecretHandle {
+        SecretHandle {
+            secret: Some(Zeroizing::new(secret.to_string())),
+        }
+    }
+
+    fn secret(&self) -> Result<&str, JsValue> {
+        self.secret
+            .as_deref()
+            .map(String::as_str)
+            .ok_or_else(|| keydrop_error(SECRET_DISPOSED, "Secret handle has been disposed"))
+    }
+}
+
+#[wasm_bindgen]
+impl SecretHandle {
+    /// Copy the secret into a JS string
+    #[wasm_bindgen]
+    pub fn reveal(&self) -> Result<String, JsValue> {
+        self.secret().map(str::to_string)
+    }
+
+    /// Write the secret to the system clipboard via `navigator.clipboard`
+    #[wasm_bindgen(js_name = copyToClipboard, unchecked_return_type = "Promise<void>")]
+    pub fn copy_to_clipboard(&self) -> Result<js_sys::Promise, JsValue> {
+        let secret = self.secret()?;
+        let unsupported = || keydrop_error(UNSUPPORTED, "Clipboard API is not available");
+
+        let navigator = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("navigator"))
+            .map_err(|_| unsupported())?;
+        let clipboard = js_sys::Reflect::get(&navigator, &JsValue::from_str("clipboard"))
+            .map_err(|_| unsupported())?;
+        let write_text = js_sys::Reflect::get(&clipboard, &JsValue::from_str("writeText"))
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok())
+            .ok_or_else(unsupported)?;
+
+        write_text
+            .call1(&clipboard, &JsValue::from_str(secret))?
+            .dyn_into::<js_sys::Promise>()
+            .map_err(|_| unsupported())
+    }
+
+    /// Zero the secret; later calls fail with `SECRET_DISPOSED`
+    #[wasm_bindgen]
+    pub fn dispose(&mut self) {
+        self.secret = None;
+    }
+
+    /// Whether `dispose` has been called
+    #[wasm_bindgen(getter, js_name = isDisposed)]
+    pub fn is_disposed(&self) -> bool {
+        self.secret.is_none()
+    }
+}
+
 // =============================================================================
 // Vault Operations
 // =============================================================================
@@ -191,6 +925,20 @@ pub struct VaultItemJs {
     pub favorite: bool,
     pub created_at: u64,
     pub modified_at: u64,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub custom_fields: Vec<CustomField>,
+    #[serde(default)]
+    pub kind: ItemKind,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    #[serde(default)]
+    pub auto_type: Option<String>,
 }
 
 impl From<&RustVaultItem> for VaultItemJs {
@@ -206,6 +954,13 @@ impl From<&RustVaultItem> for VaultItemJs {
             favorite: item.favorite,
             created_at: item.created_at,
             modified_at: item.modified_at,
+            icon: item.icon.clone(),
+            color: item.color.clone(),
+            custom_fields: item.custom_fields.clone(),
+            kind: item.kind,
+            tags: item.tags.clone(),
+            totp_secret: item.totp_secret.clone(),
+            auto_type: item.auto_type.clone(),
         }
     }
 }
@@ -220,14 +975,187 @@ impl From<VaultItemJs> for RustVaultItem {
         rust_item.favorite = item.favorite;
         rust_item.created_at = item.created_at;
         rust_item.modified_at = item.modified_at;
+        rust_item.icon = item.icon;
+        rust_item.color = item.color;
+        rust_item.custom_fields = item.custom_fields;
+        rust_item.kind = item.kind;
+        rust_item.tags = item.tags;
+        rust_item.totp_secret = item.totp_secret;
+        rust_item.auto_type = item.auto_type;
         rust_item
     }
 }
 
+/// URL match result for JavaScript
+#[derive(Serialize)]
+struct UrlMatchJs {
+    #[serde(flatten)]
+    item: VaultItemJs,
+    match_kind: UrlMatchKind,
+    confidence: f64,
+}
+
+/// Default page size for `listItems`
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Listing options from JavaScript
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ListItemsOptionsJs {
+    offset: usize,
+    limit: Option<usize>,
+    filter: ItemFilter,
+    sort: ItemSort,
+}
+
+/// Page of items for JavaScript
+#[derive(Serialize)]
+struct ItemPageJs {
+    items: Vec<VaultItemJs>,
+    total: usize,
+    offset: usize,
+}
+
+/// Bulk import summary for JavaScript
+#[derive(Serialize)]
+struct ImportSummaryJs {
+    added: usize,
+    ids: Vec<String>,
+    skipped: Vec<SkippedRowJs>,
+}
+
+/// Row skipped during import, for JavaScript
+#[derive(Serialize)]
+struct SkippedRowJs {
+    row: usize,
+    reason: String,
+}
+
+/// Parsed import for JavaScript
+#[cfg(feature = "importers")]
+#[derive(Serialize)]
+struct ImportResultJs {
+    items: Vec<VaultItemJs>,
+    skipped: Vec<SkippedRowJs>,
+}
+
+#[cfg(feature = "importers")]
+impl From<importers::ImportReport> for ImportResultJs {
+    fn from(report: importers::ImportReport) -> Self {
+        ImportResultJs {
+            items: report.items.iter().map(Into::into).collect(),
+            skipped: report.skipped.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[cfg(feature = "importers")]
+impl From<importers::SkippedRow> for SkippedRowJs {
+    fn from(row: importers::SkippedRow) -> Self {
+        SkippedRowJs {
+            row: row.row,
+            reason: row.reason,
+        }
+    }
+}
+
+/// Vault change event for JavaScript
+#[derive(Serialize)]
+struct VaultChangeJs<'a> {
+    #[serde(rename = "type")]
+    change_type: &'static str,
+    id: &'a str,
+}
+
 /// WASM Vault wrapper
 #[wasm_bindgen]
 pub struct Vault {
     inner: RustVault,
+    /// Change listeners keyed by subscription id
+    listeners: Vec<(u32, js_sys::Function)>,
+    next_listener_id: u32,
+}
+
+impl Vault {
+    fn wrap(inner: RustVault) -> Vault {
+        Vault {
+            inner,
+            listeners: Vec::new(),
+            next_listener_id: 1,
+        }
+    }
+
+    /// Notify listeners of a change; listener exceptions are ignored
+    fn emit(&self, change_type: &'static str, id: &str) {
+        if self.listeners.is_empty() {
+            return;
+        }
+        let event: JsValue = match to_js(&VaultChangeJs { change_type, id }) {
+            Ok(event) => event,
+            Err(_) => return,
+        };
+        for (_, listener) in &self.listeners {
+            let _ = listener.call1(&JsValue::NULL, &event);
+        }
+    }
+
+    /// Build an `ItemColumns` object directly, without serde
+    fn columns<'a>(items: impl ExactSizeIterator<Item = &'a RustVaultItem>) -> TsItemColumns {
+        let len = items.len() as u32;
+        let ids = js_sys::Array::new_with_length(len);
+        let names = js_sys::Array::new_with_length(len);
+        let usernames = js_sys::Array::new_with_length(len);
+        let urls = js_sys::Array::new_with_length(len);
+        let categories = js_sys::Array::new_with_length(len);
+        let favorites = js_sys::Array::new_with_length(len);
+        let modified_at = js_sys::Array::new_with_length(len);
+
+        let optional = |value: &Option<String>| match value {
+            Some(value) => JsValue::from_str(value),
+            None => JsValue::NULL,
+        };
+        for (i, item) in items.enumerate() {
+            let i = i as u32;
+            ids.set(i, JsValue::from_str(&item.id));
+            names.set(i, JsValue::from_str(&item.name));
+            usernames.set(i, JsValue::from_str(&item.username));
+            urls.set(i, optional(&item.url));
+            categories.set(i, optional(&item.category));
+            favorites.set(i, JsValue::from_bool(item.favorite));
+            modified_at.set(i, JsValue::from_f64(item.modified_at as f64));
+        }
+
+        let columns = js_sys::Object::new();
+        for (key, value) in [
+            ("ids", ids),
+            ("names", names),
+            ("usernames", usernames),
+            ("urls", urls),
+            ("categories", categories),
+            ("favorites", favorites),
+            ("modified_at", modified_at),
+        ] {
+            let _ = js_sys::Reflect::set(&columns, &JsValue::from_str(key), &value);
+        }
+        columns.unchecked_into()
+    }
+
+    /// Add a batch of items and build the import summary
+    fn insert_batch(
+        &mut self,
+        items: Vec<RustVaultItem>,
+        skipped: Vec<SkippedRowJs>,
+    ) -> Result<TsImportSummary, JsValue> {
+        let ids = self.inner.add_items(items);
+        for id in &ids {
+            self.emit("add", id);
+        }
+        to_js(&ImportSummaryJs {
+            added: ids.len(),
+            ids,
+            skipped,
+        })
+    }
 }
 
 #[wasm_bindgen]
@@ -235,98 +1163,189 @@ impl Vault {
     /// Create a new empty vault
     #[wasm_bindgen(constructor)]
     pub fn new() -> Vault {
-        Vault {
-            inner: RustVault::new(),
-        }
+        Vault::wrap(RustVault::new())
+    }
+
+    /// Subscribe to add/update/remove events
+    /// The callback receives a `VaultChangeEvent`; returns an id for `offChange`
+    #[wasm_bindgen(js_name = onChange)]
+    pub fn on_change(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "(event: VaultChangeEvent) => void")]
+        callback: js_sys::Function,
+    ) -> u32 {
+        let id = self.next_listener_id;
+        self.next_listener_id += 1;
+        self.listeners.push((id, callback));
+        id
+    }
+
+    /// Unsubscribe a listener registered with `onChange`
+    /// Returns false if the id was not subscribed
+    #[wasm_bindgen(js_name = offChange)]
+    pub fn off_change(&mut self, subscription_id: u32) -> bool {
+        let before = self.listeners.len();
+        self.listeners.retain(|(id, _)| *id != subscription_id);
+        self.listeners.len() != before
     }
 
     /// Add an item to the vault
     #[wasm_bindgen(js_name = addItem)]
-    pub fn add_item(&mut self, item: JsValue) -> Result<String, JsValue> {
-        let item_js: VaultItemJs =
-            serde_wasm_bindgen::from_value(item).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    pub fn add_item(&mut self, item: TsVaultItem) -> Result<String, JsValue> {
+        let item_js: VaultItemJs = from_js(item)?;
         let rust_item: RustVaultItem = item_js.into();
-        Ok(self.inner.add_item(rust_item))
+        let id = self.inner.add_item(rust_item);
+        self.emit("add", &id);
+        Ok(id)
+    }
+
+    /// Add many items in one call
+    /// The whole batch is validated before any item is added
+    #[wasm_bindgen(js_name = addItems)]
+    pub fn add_items(&mut self, items: TsVaultItemList) -> Result<TsImportSummary, JsValue> {
+        let items: Vec<VaultItemJs> = from_js(items)?;
+        self.insert_batch(items.into_iter().map(Into::into).collect(), Vec::new())
     }
 
     /// Get an item by ID
     #[wasm_bindgen(js_name = getItem)]
-    pub fn get_item(&self, id: &str) -> Result<JsValue, JsValue> {
+    pub fn get_item(&self, id: &str) -> Result<TsOptionalVaultItem, JsValue> {
         match self.inner.get_item(id) {
-            Some(item) => {
-                let item_js = VaultItemJs::from(item);
-                serde_wasm_bindgen::to_value(&item_js)
-                    .map_err(|e| JsValue::from_str(&e.to_string()))
-            }
-            None => Ok(JsValue::NULL),
+            Some(item) => to_js(&VaultItemJs::from(item)),
+            None => Ok(JsValue::NULL.unchecked_into()),
         }
     }
 
+    /// Get an item by ID with its password behind a `SecretHandle`
+    #[wasm_bindgen(js_name = getSecretItem)]
+    pub fn get_secret_item(&self, id: &str) -> Result<TsOptionalSecretItem, JsValue> {
+        let Some(item) = self.inner.get_item(id) else {
+            return Ok(JsValue::NULL.unchecked_into());
+        };
+
+        let mut item_js = VaultItemJs::from(item);
+        drop(Zeroizing::new(std::mem::take(&mut item_js.password)));
+        let value: JsValue = to_js::<_, JsValue>(&item_js)?;
+        let handle = JsValue::from(SecretHandle::new(&item.password));
+        js_sys::Reflect::set(&value, &JsValue::from_str("password"), &handle)?;
+        Ok(value.unchecked_into())
+    }
+
     /// Update an item
     #[wasm_bindgen(js_name = updateItem)]
-    pub fn update_item(&mut self, id: &str, item: JsValue) -> Result<(), JsValue> {
-        let item_js: VaultItemJs =
-            serde_wasm_bindgen::from_value(item).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    pub fn update_item(&mut self, id: &str, item: TsVaultItem) -> Result<(), JsValue> {
+        let item_js: VaultItemJs = from_js(item)?;
         let rust_item: RustVaultItem = item_js.into();
-        self.inner.update_item(id, rust_item).map_err(to_js_error)
+        self.inner.update_item(id, rust_item).map_err(to_js_error)?;
+        self.emit("update", id);
+        Ok(())
     }
 
     /// Remove an item
     #[wasm_bindgen(js_name = removeItem)]
-    pub fn remove_item(&mut self, id: &str) -> Result<JsValue, JsValue> {
+    pub fn remove_item(&mut self, id: &str) -> Result<TsVaultItem, JsValue> {
         let item = self.inner.remove_item(id).map_err(to_js_error)?;
-        let item_js = VaultItemJs::from(&item);
-        serde_wasm_bindgen::to_value(&item_js).map_err(|e| JsValue::from_str(&e.to_string()))
+        self.emit("remove", id);
+        to_js(&VaultItemJs::from(&item))
+    }
+
+    /// Get all items as columns, without passwords
+    /// Cheaper than `getAllItems` for list rendering
+    #[wasm_bindgen(js_name = getAllColumns)]
+    pub fn get_all_columns(&self) -> TsItemColumns {
+        Vault::columns(self.inner.items.iter())
+    }
+
+    /// Search items by query, returning columns without passwords
+    /// Cheaper than `search` for search-as-you-type
+    #[wasm_bindgen(js_name = searchColumns)]
+    pub fn search_columns(&self, query: &str) -> TsItemColumns {
+        Vault::columns(self.inner.search(query).into_iter())
     }
 
     /// Search items by query
     #[wasm_bindgen]
-    pub fn search(&self, query: &str) -> Result<JsValue, JsValue> {
+    pub fn search(&self, query: &str) -> Result<TsVaultItemList, JsValue> {
         let items: Vec<VaultItemJs> = self
             .inner
             .search(query)
             .iter()
             .map(|i| (*i).into())
             .collect();
-        serde_wasm_bindgen::to_value(&items).map_err(|e| JsValue::from_str(&e.to_string()))
+        to_js(&items)
     }
 
-    /// Find items by URL (for autofill)
+    /// Find items by URL (for autofill), strongest matches first
+    /// Each result carries its match kind and confidence
     #[wasm_bindgen(js_name = findByUrl)]
-    pub fn find_by_url(&self, url: &str) -> Result<JsValue, JsValue> {
-        let items: Vec<VaultItemJs> = self
+    pub fn find_by_url(
+        &self,
+        url: &str,
+        strategy: Option<TsUrlMatchStrategy>,
+    ) -> Result<TsUrlMatchList, JsValue> {
+        let strategy: UrlMatchStrategy = match strategy {
+            Some(strategy) => from_js(strategy)?,
+            None => UrlMatchStrategy::default(),
+        };
+
+        let matches: Vec<UrlMatchJs> = self
             .inner
-            .find_by_url(url)
-            .iter()
-            .map(|i| (*i).into())
+            .find_by_url_ranked(url, strategy)
+            .into_iter()
+            .map(|m| UrlMatchJs {
+                item: m.item.into(),
+                match_kind: m.kind,
+                confidence: m.kind.confidence(),
+            })
             .collect();
-        serde_wasm_bindgen::to_value(&items).map_err(|e| JsValue::from_str(&e.to_string()))
+        to_js(&matches)
     }
 
     /// Get all items
     #[wasm_bindgen(js_name = getAllItems)]
-    pub fn get_all_items(&self) -> Result<JsValue, JsValue> {
+    pub fn get_all_items(&self) -> Result<TsVaultItemList, JsValue> {
         let items: Vec<VaultItemJs> = self.inner.items.iter().map(|i| i.into()).collect();
-        serde_wasm_bindgen::to_value(&items).map_err(|e| JsValue::from_str(&e.to_string()))
+        to_js(&items)
+    }
+
+    /// List a filtered, sorted page of items
+    /// Prefer this over `getAllItems` for large vaults
+    #[wasm_bindgen(js_name = listItems)]
+    pub fn list_items(&self, options: Option<TsListItemsOptions>) -> Result<TsItemPage, JsValue> {
+        let options: ListItemsOptionsJs = match options {
+            Some(options) => from_js(options)?,
+            None => ListItemsOptionsJs::default(),
+        };
+
+        let page = self.inner.list_items(
+            &options.filter,
+            options.sort,
+            options.offset,
+            Some(options.limit.unwrap_or(DEFAULT_PAGE_SIZE)),
+        );
+        to_js(&ItemPageJs {
+            items: page.items.into_iter().map(Into::into).collect(),
+            total: page.total,
+            offset: options.offset,
+        })
     }
 
     /// Get categories
     #[wasm_bindgen(js_name = getCategories)]
-    pub fn get_categories(&self) -> Result<JsValue, JsValue> {
-        serde_wasm_bindgen::to_value(&self.inner.categories)
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+    pub fn get_categories(&self) -> Result<TsStringList, JsValue> {
+        to_js(&self.inner.categories)
     }
 
     /// Get favorites
     #[wasm_bindgen(js_name = getFavorites)]
-    pub fn get_favorites(&self) -> Result<JsValue, JsValue> {
+    pub fn get_favorites(&self) -> Result<TsVaultItemList, JsValue> {
         let items: Vec<VaultItemJs> = self
             .inner
             .get_favorites()
             .iter()
             .map(|i| (*i).into())
             .collect();
-        serde_wasm_bindgen::to_value(&items).map_err(|e| JsValue::from_str(&e.to_string()))
+        to_js(&items)
     }
 
     /// Export vault as encrypted base64 blob
@@ -343,7 +1362,37 @@ impl Vault {
         let key = parse_key(key_base64)?;
         let blob = EncryptedBlob::from_base64(encrypted_base64).map_err(to_js_error)?;
         let inner = RustVault::import(&blob, &key).map_err(to_js_error)?;
-        Ok(Vault { inner })
+        Ok(Vault::wrap(inner))
+    }
+
+    /// Encrypt a single item for item-level sync
+    /// Takes a raw 32-byte key, returns nonce || ciphertext
+    #[wasm_bindgen(js_name = exportItem)]
+    pub fn export_item(&self, id: &str, key: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let key = parse_key_bytes(key)?;
+        let item = self
+            .inner
+            .get_item(id)
+            .ok_or_else(|| to_js_error(CryptoError::ItemNotFound(id.to_string())))?;
+        let blob = item.export(&key).map_err(to_js_error)?;
+        Ok(blob.to_bytes())
+    }
+
+    /// Decrypt an item from `exportItem` and add it, replacing any item with the same ID
+    /// Returns the item ID
+    #[wasm_bindgen(js_name = importItem)]
+    pub fn import_item(&mut self, blob: &[u8], key: &[u8]) -> Result<String, JsValue> {
+        let key = parse_key_bytes(key)?;
+        let blob = EncryptedBlob::from_bytes(blob).map_err(to_js_error)?;
+        let item = RustVaultItem::import(&blob, &key).map_err(to_js_error)?;
+        let id = item.id.clone();
+        let change_type = if self.inner.upsert_item(item) {
+            "update"
+        } else {
+            "add"
+        };
+        self.emit(change_type, &id);
+        Ok(id)
     }
 
     /// Export vault as JSON (unencrypted, for backup)
@@ -356,7 +1405,7 @@ impl Vault {
     #[wasm_bindgen(js_name = fromJson)]
     pub fn from_json(json: &str) -> Result<Vault, JsValue> {
         let inner = RustVault::from_json(json).map_err(to_js_error)?;
-        Ok(Vault { inner })
+        Ok(Vault::wrap(inner))
     }
 
     /// Get vault item count
@@ -372,16 +1421,105 @@ impl Vault {
     }
 }
 
+#[cfg(feature = "audit")]
+#[wasm_bindgen]
+impl Vault {
+    /// Audit item passwords for weakness, reuse, age, and likely breaches
+    #[wasm_bindgen]
+    pub fn audit(&self, options: Option<TsAuditOptions>) -> Result<TsAuditReport, JsValue> {
+        let options: AuditOptions = match options {
+            Some(options) => from_js(options)?,
+            None => AuditOptions::default(),
+        };
+        let now = (js_sys::Date::now() / 1000.0) as u64;
+        to_js(&audit::audit_items(&self.inner.items, &options, now))
+    }
+}
+
+#[cfg(feature = "importers")]
+#[wasm_bindgen]
+impl Vault {
+    /// Import items from CSV text with a header row
+    /// Rows without a username or password are skipped and reported
+    #[wasm_bindgen(js_name = importFromCsv)]
+    pub fn import_from_csv(
+        &mut self,
+        text: &str,
+        mapping: Option<TsCsvMapping>,
+    ) -> Result<TsImportSummary, JsValue> {
+        let mapping: CsvMapping = match mapping {
+            Some(mapping) => from_js(mapping)?,
+            None => CsvMapping::default(),
+        };
+        let report = importers::import_csv(text, &mapping).map_err(to_js_error)?;
+        let skipped = report.skipped.into_iter().map(Into::into).collect();
+        self.insert_batch(report.items, skipped)
+    }
+}
+
+#[cfg(feature = "indexeddb")]
+#[wasm_bindgen]
+impl Vault {
+    /// Encrypt the vault and store it in the given IndexedDB database
+    #[wasm_bindgen(js_name = saveToIndexedDb, unchecked_return_type = "Promise<void>")]
+    pub fn save_to_indexed_db(
+        &self,
+        db_name: String,
+        key_base64: &str,
+    ) -> Result<js_sys::Promise, JsValue> {
+        let blob = self.export(key_base64)?;
+        Ok(wasm_bindgen_futures::future_to_promise(async move {
+            idb::save_blob(&db_name, &blob)
+                .await
+                .map_err(storage_error)?;
+            Ok(JsValue::UNDEFINED)
+        }))
+    }
+
+    /// Load and decrypt a vault previously stored with `saveToIndexedDb`
+    #[wasm_bindgen(js_name = loadFromIndexedDb)]
+    pub async fn load_from_indexed_db(
+        db_name: String,
+        key_base64: String,
+    ) -> Result<Vault, JsValue> {
+        let blob = idb::load_blob(&db_name)
+            .await
+            .map_err(storage_error)?
+            .ok_or_else(|| keydrop_error(NOT_FOUND, "No vault stored in IndexedDB"))?;
+        Vault::import_vault(&blob, &key_base64)
+    }
+}
+
 impl Default for Vault {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Get the deterministic fallback color for a seed string (domain or item name)
+#[wasm_bindgen(js_name = colorFor)]
+pub fn color_for(seed: &str) -> String {
+    crypto_core::vault::color_for(seed)
+}
+
 // =============================================================================
 // Helper Functions
 // =============================================================================
 
+/// Serialize a value into a typed JS value
+fn to_js<T: Serialize, R: JsCast>(value: &T) -> Result<R, JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+    value
+        .serialize(&serializer)
+        .map(JsCast::unchecked_into)
+        .map_err(invalid_input)
+}
+
+/// Deserialize a typed JS value
+fn from_js<T: for<'de> Deserialize<'de>>(value: impl Into<JsValue>) -> Result<T, JsValue> {
+    serde_wasm_bindgen::from_value(value.into()).map_err(invalid_input)
+}
+
 fn base64_encode(data: &[u8]) -> String {
     use base64::Engine;
     base64::engine::general_purpose::STANDARD.encode(data)
@@ -391,20 +1529,24 @@ fn base64_decode(encoded: &str) -> Result<Vec<u8>, JsValue> {
     use base64::Engine;
     base64::engine::general_purpose::STANDARD
         .decode(encoded)
-        .map_err(|e| JsValue::from_str(&format!("Base64 decode error: {}", e)))
+        .map_err(|e| invalid_input(format!("Base64 decode error: {}", e)))
 }
 
 fn parse_key(key_base64: &str) -> Result<[u8; KEY_SIZE], JsValue> {
     let key_bytes = base64_decode(key_base64)?;
+    parse_key_bytes(&key_bytes)
+}
+
+fn parse_key_bytes(key_bytes: &[u8]) -> Result<[u8; KEY_SIZE], JsValue> {
     if key_bytes.len() != KEY_SIZE {
-        return Err(JsValue::from_str(&format!(
+        return Err(invalid_input(format!(
             "Invalid key length: expected {}, got {}",
             KEY_SIZE,
             key_bytes.len()
         )));
     }
     let mut key = [0u8; KEY_SIZE];
-    key.copy_from_slice(&key_bytes);
+    key.copy_from_slice(key_bytes);
     Ok(key)
 }
 
@@ -421,4 +1563,55 @@ mod tests {
         let salt = generate_salt().unwrap();
         assert!(!salt.is_empty());
     }
+
+    #[test]
+    fn test_encrypt_decrypt_bytes() {
+        let key = [7u8; KEY_SIZE];
+        let plaintext = b"binary \x00\x01 data";
+
+        let encrypted = encrypt_bytes(plaintext, &key).unwrap();
+        let decrypted = decrypt_bytes(&encrypted, &key).unwrap();
+
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn test_secret_handle_dispose() {
+        let mut handle = SecretHandle::new("hunter2");
+        assert_eq!(handle.reveal().unwrap(), "hunter2");
+        assert!(!handle.is_disposed());
+
+        handle.dispose();
+        assert!(handle.is_disposed());
+    }
+
+    #[test]
+    fn test_key_session_round_trip() {
+        let session = KeySession::from_master_key(&base64_encode(&[3u8; KEY_SIZE])).unwrap();
+        let encrypted = session.encrypt("secret").unwrap();
+        assert_eq!(session.decrypt(&encrypted).unwrap(), "secret");
+
+        let mut vault = Vault::new();
+        vault
+            .inner
+            .add_item(RustVaultItem::new("GitHub", "user", "pass"));
+        let exported = session.export_vault(&vault).unwrap();
+        assert_eq!(session.import_vault(&exported).unwrap().inner.len(), 1);
+
+        let mut session = session;
+        session.destroy();
+        assert!(session.is_destroyed());
+    }
+
+    #[test]
+    fn test_vault_item_js_round_trip() {
+        let item = RustVaultItem::new("GitHub", "user", "pass")
+            .with_auto_type("{USERNAME}{TAB}{PASSWORD}{ENTER}");
+        let js = VaultItemJs::from(&item);
+        assert_eq!(js.auto_type, item.auto_type);
+
+        let json = serde_json::to_string(&js).unwrap();
+        let back: RustVaultItem = serde_json::from_str::<VaultItemJs>(&json).unwrap().into();
+        assert_eq!(back.auto_type, item.auto_type);
+    }
 }
@@ -0,0 +1,214 @@
+use crate::storage::{Storage, StorageError, StoredActivity};
+use serde::{Deserialize, Serialize};
+
+/// Activity log entries kept; the oldest are dropped first
+pub const ACTIVITY_CAP: usize = 1000;
+
+/// Entries returned when the filter doesn't set a limit
+const DEFAULT_LIMIT: usize = 100;
+
+/// Security-relevant things that happened on this machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Unlock,
+    FailedUnlock,
+    /// Local vault erased after too many failed unlocks
+    SelfWipe,
+    Export,
+    ItemDeleted,
+    /// A sync that changed something or failed; syncs with nothing to do aren't logged
+    Sync,
+    RemoteCommand,
+    /// Another user's vault opened in the read-only emergency viewer
+    EmergencyView,
+}
+
+impl ActivityKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ActivityKind::Unlock => "unlock",
+            ActivityKind::FailedUnlock => "failed_unlock",
+            ActivityKind::SelfWipe => "self_wipe",
+            ActivityKind::Export => "export",
+            ActivityKind::ItemDeleted => "item_deleted",
+            ActivityKind::Sync => "sync",
+            ActivityKind::RemoteCommand => "remote_command",
+            ActivityKind::EmergencyView => "emergency_view",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "unlock" => ActivityKind::Unlock,
+            "failed_unlock" => ActivityKind::FailedUnlock,
+            "self_wipe" => ActivityKind::SelfWipe,
+            "export" => ActivityKind::Export,
+            "item_deleted" => ActivityKind::ItemDeleted,
+            "sync" => ActivityKind::Sync,
+            "remote_command" => ActivityKind::RemoteCommand,
+            "emergency_view" => ActivityKind::EmergencyView,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActivityEvent {
+    pub id: i64,
+    pub kind: ActivityKind,
+    /// What it concerned, such as an item title or export path
+    pub detail: Option<String>,
+    pub created_at: i64,
+}
+
+impl ActivityEvent {
+    fn from_stored(stored: StoredActivity) -> Option<Self> {
+        Some(Self {
+            id: stored.id,
+            kind: ActivityKind::parse(&stored.kind)?,
+            detail: stored.detail,
+            created_at: stored.created_at,
+        })
+    }
+}
+
+/// Which entries `get_activity_log` returns; every field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ActivityFilter {
+    /// Only these kinds; all kinds when empty
+    #[serde(default)]
+    pub kinds: Vec<ActivityKind>,
+    /// Earliest time included (Unix epoch seconds)
+    pub since: Option<i64>,
+    /// Latest time included (Unix epoch seconds)
+    pub until: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+impl ActivityFilter {
+    fn matches(&self, event: &ActivityEvent) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&event.kind))
+            && self.since.is_none_or(|since| event.created_at >= since)
+            && self.until.is_none_or(|until| event.created_at <= until)
+    }
+}
+
+/// Add an entry to the activity log
+pub fn record(
+    storage: &Storage,
+    kind: ActivityKind,
+    detail: Option<&str>,
+    now: i64,
+) -> Result<(), StorageError> {
+    storage.add_activity(kind.as_str(), detail, now, ACTIVITY_CAP)
+}
+
+/// Add an entry now, logging a failure instead of returning it
+///
+/// For events recorded alongside other work, which shouldn't fail because
+/// the log couldn't be written.
+pub fn try_record(kind: ActivityKind, detail: Option<&str>) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    if let Err(e) = Storage::open().and_then(|storage| record(&storage, kind, detail, now)) {
+        tracing::warn!("Recording {} activity failed: {}", kind.as_str(), e);
+    }
+}
+
+/// Activity log entries matching `filter`, newest first
+///
+/// Entries written by a newer version with kinds this one doesn't know are
+/// left out.
+pub fn list(
+    storage: &Storage,
+    filter: &ActivityFilter,
+) -> Result<Vec<ActivityEvent>, StorageError> {
+    Ok(filter_events(
+        storage
+            .activity()?
+            .into_iter()
+            .filter_map(ActivityEvent::from_stored),
+        filter,
+    ))
+}
+
+fn filter_events(
+    events: impl Iterator<Item = ActivityEvent>,
+    filter: &ActivityFilter,
+) -> Vec<ActivityEvent> {
+    events
+        .filter(|event| filter.matches(event))
+        .take(filter.limit.unwrap_or(DEFAULT_LIMIT))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: i64, kind: ActivityKind, created_at: i64) -> ActivityEvent {
+        ActivityEvent {
+            id,
+            kind,
+            detail: None,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_kind_round_trip() {
+        for kind in [
+            ActivityKind::Unlock,
+            ActivityKind::FailedUnlock,
+            ActivityKind::SelfWipe,
+            ActivityKind::Export,
+            ActivityKind::ItemDeleted,
+            ActivityKind::Sync,
+            ActivityKind::RemoteCommand,
+        ] {
+            assert_eq!(ActivityKind::parse(kind.as_str()), Some(kind));
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                serde_json::json!(kind.as_str())
+            );
+        }
+        assert_eq!(ActivityKind::parse("teleport"), None);
+    }
+
+    #[test]
+    fn test_filter_events() {
+        let events = vec![
+            event(4, ActivityKind::Unlock, 400),
+            event(3, ActivityKind::FailedUnlock, 300),
+            event(2, ActivityKind::FailedUnlock, 200),
+            event(1, ActivityKind::Export, 100),
+        ];
+        let ids = |filter: ActivityFilter| -> Vec<i64> {
+            filter_events(events.clone().into_iter(), &filter)
+                .iter()
+                .map(|e| e.id)
+                .collect()
+        };
+
+        assert_eq!(ids(ActivityFilter::default()), vec![4, 3, 2, 1]);
+        assert_eq!(
+            ids(ActivityFilter {
+                kinds: vec![ActivityKind::FailedUnlock, ActivityKind::Export],
+                limit: Some(2),
+                ..Default::default()
+            }),
+            vec![3, 2]
+        );
+        assert_eq!(
+            ids(ActivityFilter {
+                since: Some(200),
+                until: Some(300),
+                ..Default::default()
+            }),
+            vec![3, 2]
+        );
+    }
+}
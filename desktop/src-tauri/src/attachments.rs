@@ -0,0 +1,585 @@
+use crate::storage::{Storage, StorageError, StoredAttachment};
+use crypto_core::backup::BackupAttachment;
+use crypto_core::cipher::{decrypt, encrypt, unwrap_key, wrap_key, EncryptedBlob, KEY_SIZE};
+use crypto_core::error::CryptoError;
+use crypto_core::vault::Vault;
+use rand::RngCore;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+const MB: u64 = 1024 * 1024;
+
+/// Largest file that can be attached
+pub const MAX_ATTACHMENT_SIZE: u64 = 25 * MB;
+
+/// Largest total size of all attachments in the vault
+pub const MAX_TOTAL_SIZE: u64 = 500 * MB;
+
+/// Plaintext bytes per encrypted chunk
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Chunk header: index (big-endian `u32`) and a last-chunk flag
+const CHUNK_HEADER_SIZE: usize = 5;
+
+/// Directory under the system temp directory that opened attachments go in
+const TEMP_DIR_NAME: &str = "keydrop-attachments";
+
+/// How long an opened attachment stays on disk for the app showing it
+pub const TEMP_FILE_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Error, Debug)]
+pub enum AttachmentError {
+    #[error("Files larger than {} MB can't be attached", MAX_ATTACHMENT_SIZE / MB)]
+    TooLarge,
+
+    #[error("Attachments are limited to {} MB in total", MAX_TOTAL_SIZE / MB)]
+    QuotaExceeded,
+
+    #[error("Only files can be attached")]
+    NotAFile,
+
+    #[error("File changed while it was being attached")]
+    Changed,
+
+    #[error("Item not found")]
+    ItemNotFound,
+
+    #[error("Attachment not found")]
+    NotFound,
+
+    #[error("Attachment is damaged")]
+    Damaged,
+
+    #[error("Attachment file error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Attachment error: {0}")]
+    Crypto(#[from] CryptoError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+pub type Result<T> = std::result::Result<T, AttachmentError>;
+
+/// A decrypted attachment's details, without its contents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Attachment {
+    pub id: String,
+    pub item_id: String,
+    pub name: String,
+    /// Size in bytes
+    pub size: u64,
+    /// When the file was attached (Unix epoch seconds)
+    pub created_at: i64,
+}
+
+/// Attach the file at `path` to the item `item_id` of `vault`
+///
+/// The file is read and encrypted a chunk at a time under a key of the
+/// attachment's own, which is stored wrapped with the vault key.
+pub fn add(
+    storage: &Storage,
+    vault_key: &[u8; KEY_SIZE],
+    vault: &Vault,
+    item_id: &str,
+    path: &Path,
+    now: i64,
+) -> Result<Attachment> {
+    if vault.get_item(item_id).is_none() {
+        return Err(AttachmentError::ItemNotFound);
+    }
+    let metadata = std::fs::metadata(path)?;
+    if !metadata.is_file() {
+        return Err(AttachmentError::NotAFile);
+    }
+    if metadata.len() > MAX_ATTACHMENT_SIZE {
+        return Err(AttachmentError::TooLarge);
+    }
+    if storage.attachments_size()? + metadata.len() > MAX_TOTAL_SIZE {
+        return Err(AttachmentError::QuotaExceeded);
+    }
+
+    let attachment = Attachment {
+        id: Uuid::new_v4().to_string(),
+        item_id: item_id.to_string(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        size: metadata.len(),
+        created_at: now,
+    };
+    store(storage, vault_key, &attachment, std::fs::File::open(path)?)?;
+    Ok(attachment)
+}
+
+/// Encrypt and store `attachment`, whose contents `reader` yields
+///
+/// Fails with [`AttachmentError::Changed`] if `reader` doesn't yield exactly
+/// `attachment.size` bytes.
+fn store(
+    storage: &Storage,
+    vault_key: &[u8; KEY_SIZE],
+    attachment: &Attachment,
+    reader: impl Read,
+) -> Result<()> {
+    let mut key = Zeroizing::new([0u8; KEY_SIZE]);
+    rand::rngs::OsRng.fill_bytes(key.as_mut());
+    let stored = StoredAttachment {
+        id: attachment.id.clone(),
+        item_id: attachment.item_id.clone(),
+        wrapped_key: wrap_key(&key, vault_key)?,
+        encrypted_name: encrypt(attachment.name.as_bytes(), &key)?.to_bytes(),
+        size: attachment.size as i64,
+        created_at: attachment.created_at,
+    };
+
+    // One byte past the expected size is enough to notice a file that grew
+    let mut reader = reader.take(attachment.size + 1);
+    let mut read = 0u64;
+    let mut index = 0u32;
+    let mut next = Some(read_chunk(&mut reader)?);
+    storage.add_attachment(&stored, || {
+        let Some(chunk) = next.take() else {
+            return Ok(None);
+        };
+        read += chunk.len() as u64;
+        // Read one chunk ahead so the last one can be marked
+        let following = read_chunk(&mut reader)?;
+        let last = following.is_empty();
+        if read > attachment.size || (last && read != attachment.size) {
+            return Err(AttachmentError::Changed);
+        }
+        let encrypted = encrypt_chunk(index, last, &chunk, &key)?;
+        index += 1;
+        if !last {
+            next = Some(following);
+        }
+        Ok(Some(encrypted))
+    })
+}
+
+/// Up to [`CHUNK_SIZE`] bytes; empty only at the end
+fn read_chunk(reader: &mut impl Read) -> std::io::Result<Zeroizing<Vec<u8>>> {
+    let mut chunk = Zeroizing::new(Vec::with_capacity(CHUNK_SIZE));
+    reader.take(CHUNK_SIZE as u64).read_to_end(chunk.as_mut())?;
+    Ok(chunk)
+}
+
+/// Encrypt a chunk with its index and whether it's the last, so chunks can't
+/// be reordered, dropped or truncated without failing to decrypt
+fn encrypt_chunk(index: u32, last: bool, data: &[u8], key: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
+    let mut plaintext = Zeroizing::new(Vec::with_capacity(CHUNK_HEADER_SIZE + data.len()));
+    plaintext.extend_from_slice(&index.to_be_bytes());
+    plaintext.push(last as u8);
+    plaintext.extend_from_slice(data);
+    Ok(encrypt(&plaintext, key)?.to_bytes())
+}
+
+/// Decrypt an attachment's name and unwrap its key
+fn decrypt_stored(
+    stored: &StoredAttachment,
+    vault_key: &[u8; KEY_SIZE],
+) -> Result<(Attachment, Zeroizing<[u8; KEY_SIZE]>)> {
+    let key = unwrap_key(&stored.wrapped_key, vault_key)?;
+    let name = decrypt(&EncryptedBlob::from_bytes(&stored.encrypted_name)?, &key)?;
+    let attachment = Attachment {
+        id: stored.id.clone(),
+        item_id: stored.item_id.clone(),
+        name: String::from_utf8_lossy(&name).into_owned(),
+        size: stored.size as u64,
+        created_at: stored.created_at,
+    };
+    Ok((attachment, key))
+}
+
+/// Attachments of `item_id`, or of every item, oldest first
+///
+/// One that no longer decrypts is left out rather than hiding the rest.
+pub fn list(
+    storage: &Storage,
+    vault_key: &[u8; KEY_SIZE],
+    item_id: Option<&str>,
+) -> Result<Vec<Attachment>> {
+    Ok(storage
+        .attachments()?
+        .iter()
+        .filter(|stored| item_id.is_none_or(|id| stored.item_id == id))
+        .filter_map(|stored| match decrypt_stored(stored, vault_key) {
+            Ok((attachment, _)) => Some(attachment),
+            Err(e) => {
+                tracing::warn!("Attachment {} doesn't decrypt: {}", stored.id, e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Decrypt attachment `id` into `out` a chunk at a time
+pub fn write_to(
+    storage: &Storage,
+    vault_key: &[u8; KEY_SIZE],
+    id: &str,
+    out: &mut impl Write,
+) -> Result<Attachment> {
+    let stored = storage.attachment(id)?.ok_or(AttachmentError::NotFound)?;
+    let (attachment, key) = decrypt_stored(&stored, vault_key)?;
+
+    let mut expected = 0u32;
+    let mut finished = false;
+    storage.read_attachment_chunks(id, |chunk| {
+        if finished {
+            return Err(AttachmentError::Damaged);
+        }
+        let plaintext = EncryptedBlob::from_bytes(&chunk)
+            .and_then(|blob| decrypt(&blob, &key))
+            .map(Zeroizing::new)
+            .map_err(|_| AttachmentError::Damaged)?;
+        if plaintext.len() < CHUNK_HEADER_SIZE
+            || plaintext[..4] != expected.to_be_bytes()
+            || plaintext[4] > 1
+        {
+            return Err(AttachmentError::Damaged);
+        }
+        out.write_all(&plaintext[CHUNK_HEADER_SIZE..])?;
+        finished = plaintext[4] == 1;
+        expected += 1;
+        Ok(())
+    })?;
+    if !finished {
+        return Err(AttachmentError::Damaged);
+    }
+    Ok(attachment)
+}
+
+/// Root of the directories opened attachments are written to
+fn temp_root() -> PathBuf {
+    std::env::temp_dir().join(TEMP_DIR_NAME)
+}
+
+/// Decrypt attachment `id` to a file only the current user can read, for
+/// opening in another app
+///
+/// Each file gets a directory of its own so it keeps its name. Callers
+/// should [`remove_later`] the file's directory; whatever is left is removed
+/// by [`cleanup_temp`].
+pub fn open_temp(storage: &Storage, vault_key: &[u8; KEY_SIZE], id: &str) -> Result<PathBuf> {
+    let stored = storage.attachment(id)?.ok_or(AttachmentError::NotFound)?;
+    let (attachment, _) = decrypt_stored(&stored, vault_key)?;
+
+    let dir = temp_root().join(Uuid::new_v4().to_string());
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&dir)?;
+
+    let path = dir.join(safe_file_name(&attachment.name));
+    let written = std::fs::File::create(&path)
+        .map_err(AttachmentError::from)
+        .and_then(|mut file| {
+            write_to(storage, vault_key, id, &mut file)?;
+            file.sync_all()?;
+            Ok(())
+        });
+    if let Err(e) = written {
+        let _ = std::fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+/// A file name that can't escape the directory it's written to
+fn safe_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    if name.is_empty() {
+        "attachment".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Remove an opened attachment's directory after [`TEMP_FILE_LIFETIME`]
+pub fn remove_later(dir: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(TEMP_FILE_LIFETIME).await;
+        let _ = std::fs::remove_dir_all(dir);
+    });
+}
+
+/// Remove every opened attachment, e.g. when the vault locks or the app starts
+///
+/// Files still open in another app may not be removable on Windows; they're
+/// retried next time.
+pub fn cleanup_temp() {
+    match std::fs::remove_dir_all(temp_root()) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            tracing::warn!("Removing opened attachments failed: {}", e);
+        }
+        _ => {}
+    }
+}
+
+pub fn delete(storage: &Storage, id: &str) -> Result<()> {
+    if !storage.delete_attachment(id)? {
+        return Err(AttachmentError::NotFound);
+    }
+    Ok(())
+}
+
+/// Delete every attachment of an item that was deleted
+pub fn delete_for_item(storage: &Storage, item_id: &str) -> Result<()> {
+    for stored in storage.attachments()? {
+        if stored.item_id == item_id {
+            storage.delete_attachment(&stored.id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete attachments whose item is neither in the vault nor its trash
+pub fn prune(storage: &Storage, vault: &Vault) -> Result<usize> {
+    let item_ids: HashSet<&str> = vault
+        .items
+        .iter()
+        .map(|item| item.id.as_str())
+        .chain(vault.trash.iter().map(|trashed| trashed.item.id.as_str()))
+        .collect();
+    let mut pruned = 0;
+    for stored in storage.attachments()? {
+        if !item_ids.contains(stored.item_id.as_str()) && storage.delete_attachment(&stored.id)? {
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+/// Re-wrap attachment keys for a new vault key; contents aren't re-encrypted
+pub fn rekey(storage: &Storage, old_key: &[u8; KEY_SIZE], new_key: &[u8; KEY_SIZE]) -> Result<()> {
+    for stored in storage.attachments()? {
+        if let Ok(key) = unwrap_key(&stored.wrapped_key, old_key) {
+            storage.set_attachment_key(&stored.id, &wrap_key(&key, new_key)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Every attachment, decrypted, for writing into a backup
+///
+/// A damaged attachment is logged and left out rather than failing the backup.
+pub fn for_backup(storage: &Storage, vault_key: &[u8; KEY_SIZE]) -> Result<Vec<BackupAttachment>> {
+    let mut attachments = Vec::new();
+    for stored in storage.attachments()? {
+        let mut data = Vec::with_capacity(stored.size as usize);
+        let attachment = match write_to(storage, vault_key, &stored.id, &mut data) {
+            Ok(attachment) => attachment,
+            Err(AttachmentError::Storage(e)) => return Err(e.into()),
+            Err(e) => {
+                tracing::warn!("Leaving attachment {} out of the backup: {}", stored.id, e);
+                continue;
+            }
+        };
+        attachments.push(BackupAttachment {
+            id: attachment.id,
+            item_id: attachment.item_id,
+            name: attachment.name,
+            created_at: attachment.created_at as u64,
+            data,
+        });
+    }
+    Ok(attachments)
+}
+
+/// Store a backup's attachments whose item is in `vault` and that aren't
+/// already stored; returns how many were added
+///
+/// Restores aren't held to the size limits, so a backup restores whole.
+pub fn restore(
+    storage: &Storage,
+    vault_key: &[u8; KEY_SIZE],
+    vault: &Vault,
+    attachments: &[BackupAttachment],
+) -> Result<usize> {
+    let mut restored = 0;
+    for attachment in attachments {
+        if vault.get_item(&attachment.item_id).is_none()
+            || storage.attachment(&attachment.id)?.is_some()
+        {
+            continue;
+        }
+        let details = Attachment {
+            id: attachment.id.clone(),
+            item_id: attachment.item_id.clone(),
+            name: attachment.name.clone(),
+            size: attachment.data.len() as u64,
+            created_at: attachment.created_at as i64,
+        };
+        store(storage, vault_key, &details, attachment.data.as_slice())?;
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_core::vault::VaultItem;
+
+    const KEY: [u8; KEY_SIZE] = [7u8; KEY_SIZE];
+
+    fn read(storage: &Storage, key: &[u8; KEY_SIZE], id: &str) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        write_to(storage, key, id, &mut data)?;
+        Ok(data)
+    }
+
+    #[test]
+    fn test_round_trip_and_limits() {
+        let storage = Storage::open_in_memory();
+        let mut vault = Vault::new();
+        let item_id = vault.add_item(VaultItem::new("Passport", "", ""));
+        let dir = tempfile::tempdir().unwrap();
+
+        // Spans several chunks and ends mid-chunk
+        let contents: Vec<u8> = (0..CHUNK_SIZE * 2 + 100).map(|i| i as u8).collect();
+        let path = dir.path().join("scan.pdf");
+        std::fs::write(&path, &contents).unwrap();
+        let attachment = add(&storage, &KEY, &vault, &item_id, &path, 10).unwrap();
+        assert_eq!(attachment.name, "scan.pdf");
+        assert_eq!(attachment.size, contents.len() as u64);
+        assert_eq!(read(&storage, &KEY, &attachment.id).unwrap(), contents);
+        assert_eq!(list(&storage, &KEY, Some(&item_id)).unwrap(), [attachment]);
+        assert!(list(&storage, &KEY, Some("other")).unwrap().is_empty());
+
+        let empty = dir.path().join("empty.txt");
+        std::fs::write(&empty, b"").unwrap();
+        let attachment = add(&storage, &KEY, &vault, &item_id, &empty, 11).unwrap();
+        assert!(read(&storage, &KEY, &attachment.id).unwrap().is_empty());
+
+        assert!(matches!(
+            add(&storage, &KEY, &vault, "missing", &path, 12),
+            Err(AttachmentError::ItemNotFound)
+        ));
+        assert!(matches!(
+            add(&storage, &KEY, &vault, &item_id, dir.path(), 12),
+            Err(AttachmentError::NotAFile)
+        ));
+
+        // Files that grow or shrink while being read are refused
+        for size in [contents.len() as u64 - 1, contents.len() as u64 + 1] {
+            let grown = Attachment {
+                id: "grown".to_string(),
+                item_id: item_id.clone(),
+                name: "grown".to_string(),
+                size,
+                created_at: 12,
+            };
+            assert!(matches!(
+                store(&storage, &KEY, &grown, contents.as_slice()),
+                Err(AttachmentError::Changed)
+            ));
+            assert!(storage.attachment("grown").unwrap().is_none());
+        }
+    }
+
+    fn details(id: &str, item_id: &str, name: &str, size: usize) -> Attachment {
+        Attachment {
+            id: id.to_string(),
+            item_id: item_id.to_string(),
+            name: name.to_string(),
+            size: size as u64,
+            created_at: 1,
+        }
+    }
+
+    #[test]
+    fn test_damaged_and_rekeyed() {
+        let storage = Storage::open_in_memory();
+        let mut vault = Vault::new();
+        let item_id = vault.add_item(VaultItem::new("Passport", "", ""));
+        let contents = vec![1u8; CHUNK_SIZE + 1];
+        let attachment = details("a1", &item_id, "a", contents.len());
+        store(&storage, &KEY, &attachment, contents.as_slice()).unwrap();
+
+        let new_key = [8u8; KEY_SIZE];
+        rekey(&storage, &KEY, &new_key).unwrap();
+        assert_eq!(read(&storage, &new_key, "a1").unwrap(), contents);
+        assert!(read(&storage, &KEY, "a1").is_err());
+
+        // Chunks stored out of order, or a missing last chunk, are caught
+        let stored = storage.attachment("a1").unwrap().unwrap();
+        let mut chunks = Vec::new();
+        storage
+            .read_attachment_chunks::<StorageError>("a1", |chunk| {
+                chunks.push(chunk);
+                Ok(())
+            })
+            .unwrap();
+        for reordered in [
+            vec![chunks[1].clone(), chunks[0].clone()],
+            vec![chunks[0].clone()],
+        ] {
+            storage.delete_attachment("a1").unwrap();
+            let mut reordered = reordered.into_iter();
+            storage
+                .add_attachment::<StorageError>(&stored, || Ok(reordered.next()))
+                .unwrap();
+            assert!(matches!(
+                read(&storage, &new_key, "a1"),
+                Err(AttachmentError::Damaged)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_backup_restore_and_prune() {
+        let storage = Storage::open_in_memory();
+        let mut vault = Vault::new();
+        let item_id = vault.add_item(VaultItem::new("Passport", "", ""));
+        let attachment = details("a1", &item_id, "scan.pdf", 4);
+        store(&storage, &KEY, &attachment, &b"scan"[..]).unwrap();
+
+        let backed_up = for_backup(&storage, &KEY).unwrap();
+        assert_eq!(backed_up[0].name, "scan.pdf");
+        assert_eq!(backed_up[0].data, b"scan");
+
+        let restored = Storage::open_in_memory();
+        let mut orphan = backed_up[0].clone();
+        orphan.id = "a2".to_string();
+        orphan.item_id = "gone".to_string();
+        let attachments = [backed_up[0].clone(), orphan];
+        assert_eq!(restore(&restored, &KEY, &vault, &attachments).unwrap(), 1);
+        // Already there
+        assert_eq!(restore(&restored, &KEY, &vault, &attachments).unwrap(), 0);
+        assert_eq!(read(&restored, &KEY, "a1").unwrap(), b"scan");
+
+        assert_eq!(prune(&restored, &vault).unwrap(), 0);
+        vault.remove_item(&item_id).unwrap();
+        assert_eq!(prune(&restored, &vault).unwrap(), 1);
+        assert!(list(&restored, &KEY, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_safe_file_name() {
+        assert_eq!(safe_file_name("scan.pdf"), "scan.pdf");
+        assert_eq!(safe_file_name("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(safe_file_name("a\\b:c"), "a_b_c");
+        assert_eq!(safe_file_name(".."), "attachment");
+        assert_eq!(safe_file_name(""), "attachment");
+    }
+}
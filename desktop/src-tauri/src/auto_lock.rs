@@ -0,0 +1,322 @@
+use crate::attachments;
+use crate::breach::BreachState;
+use crate::capture;
+use crate::emergency::EmergencyViewer;
+use crate::palette;
+use crate::state::AppState;
+use crate::tray;
+use serde::Serialize;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted to all windows when the backend locks the vault
+pub const VAULT_LOCKED_EVENT: &str = "vault-locked";
+
+/// How often system idle time and screen lock state are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A poll arriving this much later than scheduled means the machine was asleep
+const SLEEP_GAP: Duration = Duration::from_secs(30);
+
+/// Why the vault was locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockReason {
+    /// Locked by the user from a window or the tray
+    Manual,
+    /// A remote lock or wipe command
+    Remote,
+    Idle,
+    Sleep,
+    ScreenLock,
+}
+
+/// Payload of [`VAULT_LOCKED_EVENT`]
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultLockedEvent {
+    pub reason: LockReason,
+}
+
+/// Lock the vault and notify every window; no-op if already locked
+///
+/// The main window and the hidden quick-search palette both listen for
+/// [`VAULT_LOCKED_EVENT`] and drop the decrypted items they hold.
+pub fn lock_vault(app: &AppHandle, reason: LockReason) {
+    let state = app.state::<AppState>();
+    if !state.is_unlocked() {
+        return;
+    }
+    state.lock();
+    app.state::<BreachState>().clear();
+    app.state::<EmergencyViewer>().close();
+    palette::hide_palette(app);
+    tray::refresh_tray(app);
+    capture::refresh(app);
+    attachments::cleanup_temp();
+    let _ = app.emit(VAULT_LOCKED_EVENT, VaultLockedEvent { reason });
+}
+
+/// Start the background task that locks the vault on sleep, screen lock, and idle
+///
+/// Idle time is measured system-wide where the OS reports it, so the vault
+/// stays open while the user works in other apps and locks once they walk
+/// away. Elsewhere it goes by activity in the app; see [`crate::session`].
+pub fn spawn_auto_lock(app: AppHandle) {
+    #[cfg(target_os = "linux")]
+    tauri::async_runtime::spawn(platform::lock_before_sleep(app.clone()));
+
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let monitor = platform::Monitor::new().await;
+        let mut last_wall = SystemTime::now();
+        let mut last_mono = Instant::now();
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let slept = resumed_from_sleep(last_wall.elapsed().ok(), last_mono.elapsed());
+            last_wall = SystemTime::now();
+            last_mono = Instant::now();
+            if !state.is_unlocked() {
+                continue;
+            }
+
+            let timeout = *state.auto_lock_timeout.lock().unwrap();
+            state.session.set_system_idle(monitor.idle_secs().await);
+            let reason = if slept {
+                Some(LockReason::Sleep)
+            } else if monitor.is_screen_locked().await == Some(true) {
+                Some(LockReason::ScreenLock)
+            } else if state.session.should_lock(timeout) {
+                Some(LockReason::Idle)
+            } else {
+                None
+            };
+
+            if let Some(reason) = reason {
+                lock_vault(&app, reason);
+            }
+        }
+    });
+}
+
+/// Whether the gap since the last poll means the machine was suspended
+///
+/// The monotonic clock stops during sleep on Linux and macOS while wall time
+/// keeps going; on Windows it keeps counting. Taking the larger of the two
+/// catches both. A manual clock change can also trip this, which only costs
+/// an extra unlock.
+fn resumed_from_sleep(wall_elapsed: Option<Duration>, mono_elapsed: Duration) -> bool {
+    let elapsed = wall_elapsed.unwrap_or_default().max(mono_elapsed);
+    elapsed > POLL_INTERVAL + SLEEP_GAP
+}
+
+/// Linux: logind session hints and the PrepareForSleep signal over D-Bus
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{lock_vault, LockReason};
+    use futures_util::StreamExt;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use tauri::AppHandle;
+    use zbus::{Connection, Proxy};
+
+    const LOGIN1: &str = "org.freedesktop.login1";
+
+    pub struct Monitor {
+        session: Option<Proxy<'static>>,
+    }
+
+    impl Monitor {
+        pub async fn new() -> Self {
+            Self {
+                session: session_proxy().await.ok(),
+            }
+        }
+
+        /// Seconds since the desktop environment marked the session idle
+        pub async fn idle_secs(&self) -> Option<u64> {
+            let session = self.session.as_ref()?;
+            if !session.get_property::<bool>("IdleHint").await.ok()? {
+                return Some(0);
+            }
+            // Microseconds since the Unix epoch
+            let since = session.get_property::<u64>("IdleSinceHint").await.ok()?;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+            Some((now.as_micros() as u64).saturating_sub(since) / 1_000_000)
+        }
+
+        pub async fn is_screen_locked(&self) -> Option<bool> {
+            let session = self.session.as_ref()?;
+            session.get_property::<bool>("LockedHint").await.ok()
+        }
+    }
+
+    async fn session_proxy() -> zbus::Result<Proxy<'static>> {
+        let connection = Connection::system().await?;
+        Proxy::new(
+            &connection,
+            LOGIN1,
+            "/org/freedesktop/login1/session/auto",
+            "org.freedesktop.login1.Session",
+        )
+        .await
+    }
+
+    /// Lock as soon as logind announces a suspend, before memory is frozen
+    pub async fn lock_before_sleep(app: AppHandle) {
+        let _ = watch_prepare_for_sleep(&app).await;
+    }
+
+    async fn watch_prepare_for_sleep(app: &AppHandle) -> zbus::Result<()> {
+        let connection = Connection::system().await?;
+        let manager = Proxy::new(
+            &connection,
+            LOGIN1,
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+        )
+        .await?;
+
+        let mut signals = manager.receive_signal("PrepareForSleep").await?;
+        while let Some(message) = signals.next().await {
+            // true before suspending, false after resuming
+            if message.body().deserialize::<bool>()? {
+                lock_vault(app, LockReason::Sleep);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Windows: last input tick and whether the secure desktop has taken input
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows_sys::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, DESKTOP_SWITCHDESKTOP,
+    };
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    pub struct Monitor;
+
+    impl Monitor {
+        pub async fn new() -> Self {
+            Self
+        }
+
+        pub async fn idle_secs(&self) -> Option<u64> {
+            let mut info = LASTINPUTINFO {
+                cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+                dwTime: 0,
+            };
+            // SAFETY: `info` is a valid LASTINPUTINFO with cbSize set
+            if unsafe { GetLastInputInfo(&mut info) } == 0 {
+                return None;
+            }
+            let now = unsafe { GetTickCount() };
+            Some(u64::from(now.wrapping_sub(info.dwTime)) / 1000)
+        }
+
+        /// The input desktop can't be opened while the lock screen is up
+        pub async fn is_screen_locked(&self) -> Option<bool> {
+            // SAFETY: plain Win32 calls; the handle is closed before returning
+            unsafe {
+                let desktop = OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP);
+                if desktop.is_null() {
+                    return Some(true);
+                }
+                CloseDesktop(desktop);
+            }
+            Some(false)
+        }
+    }
+}
+
+/// macOS: CoreGraphics event source idle time and session screen lock flag
+#[cfg(target_os = "macos")]
+mod platform {
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::boolean::CFBoolean;
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::string::CFString;
+
+    /// kCGEventSourceStateCombinedSessionState
+    const COMBINED_SESSION_STATE: i32 = 0;
+    /// kCGAnyInputEventType
+    const ANY_INPUT_EVENT: u32 = u32::MAX;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+        fn CGSessionCopyCurrentDictionary() -> CFDictionaryRef;
+    }
+
+    pub struct Monitor;
+
+    impl Monitor {
+        pub async fn new() -> Self {
+            Self
+        }
+
+        pub async fn idle_secs(&self) -> Option<u64> {
+            // SAFETY: pure query with constant arguments
+            let secs = unsafe {
+                CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT)
+            };
+            secs.is_finite().then_some(secs as u64)
+        }
+
+        pub async fn is_screen_locked(&self) -> Option<bool> {
+            // SAFETY: the copied dictionary is owned and released by the wrapper
+            let session: CFDictionary<CFString, CFType> = unsafe {
+                let dict = CGSessionCopyCurrentDictionary();
+                if dict.is_null() {
+                    return None;
+                }
+                CFDictionary::wrap_under_create_rule(dict)
+            };
+            let locked = session
+                .find(CFString::from_static_string("CGSSessionScreenIsLocked"))
+                .and_then(|value| value.downcast::<CFBoolean>())
+                .is_some_and(bool::from);
+            Some(locked)
+        }
+    }
+}
+
+/// Other platforms: no system signals, idle falls back to vault activity
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+mod platform {
+    pub struct Monitor;
+
+    impl Monitor {
+        pub async fn new() -> Self {
+            Self
+        }
+
+        pub async fn idle_secs(&self) -> Option<u64> {
+            None
+        }
+
+        pub async fn is_screen_locked(&self) -> Option<bool> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resumed_from_sleep() {
+        let on_time = Duration::from_secs(5);
+        assert!(!resumed_from_sleep(Some(on_time), on_time));
+        assert!(!resumed_from_sleep(None, Duration::from_secs(20)));
+
+        // Wall clock ran on while the monotonic clock was frozen
+        assert!(resumed_from_sleep(Some(Duration::from_secs(3600)), on_time));
+        // Monotonic clock counts sleep (Windows)
+        assert!(resumed_from_sleep(Some(on_time), Duration::from_secs(3600)));
+    }
+}
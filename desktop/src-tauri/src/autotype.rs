@@ -0,0 +1,280 @@
+use crate::clipboard;
+use crate::palette::PALETTE_LABEL;
+use crate::tray;
+use crypto_core::vault::VaultItem;
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Sequence typed when neither the caller nor the item specifies one
+pub const DEFAULT_SEQUENCE: &str = "{USERNAME}{TAB}{PASSWORD}{ENTER}";
+
+/// Time for the OS to hand focus back to the target after Keydrop steps aside
+const FOCUS_SETTLE: Duration = Duration::from_millis(400);
+
+#[derive(Error, Debug)]
+pub enum AutoTypeError {
+    #[error("Invalid auto-type sequence: {0}")]
+    InvalidSequence(String),
+
+    #[error("Item has no {0} to type")]
+    MissingField(String),
+
+    #[error("Could not determine the focused window")]
+    NoTarget,
+
+    #[error("Keystroke simulation failed: {0}")]
+    Input(String),
+}
+
+pub type Result<T> = std::result::Result<T, AutoTypeError>;
+
+/// Non-text key that a sequence can press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialKey {
+    Tab,
+    Enter,
+    Space,
+}
+
+/// One parsed element of a sequence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Literal text typed as-is
+    Text(String),
+    /// Item field looked up with [`clipboard::item_field`]
+    Field(String),
+    Key(SpecialKey),
+    /// Pause in milliseconds, for slow login forms
+    Delay(u64),
+}
+
+/// A step with its field value filled in, ready to type
+#[derive(Debug)]
+pub enum Action {
+    Text(Zeroizing<String>),
+    Key(SpecialKey),
+    Delay(u64),
+}
+
+/// Result of an [`auto_type`] attempt
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoTypeResult {
+    /// Whether keystrokes were sent
+    pub typed: bool,
+    /// Title of the window that had focus once Keydrop stepped aside
+    pub target_title: String,
+}
+
+/// Parse a KeePass-style sequence
+///
+/// Placeholders are case-insensitive: `{USERNAME}`, `{PASSWORD}`, `{URL}`,
+/// `{NOTES}`, `{S:Field Name}` for a custom field, `{TAB}`, `{ENTER}`,
+/// `{SPACE}`, and `{DELAY 500}`. Everything outside braces is literal text.
+pub fn parse_sequence(sequence: &str) -> Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    let mut rest = sequence;
+
+    while let Some(open) = rest.find('{') {
+        if open > 0 {
+            steps.push(Step::Text(rest[..open].to_string()));
+        }
+        let close = rest[open..].find('}').map(|i| open + i).ok_or_else(|| {
+            AutoTypeError::InvalidSequence(format!("unclosed '{{' in {}", sequence))
+        })?;
+        steps.push(parse_placeholder(&rest[open + 1..close])?);
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        steps.push(Step::Text(rest.to_string()));
+    }
+    Ok(steps)
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Step> {
+    if let Some(name) = placeholder
+        .strip_prefix("S:")
+        .or_else(|| placeholder.strip_prefix("s:"))
+    {
+        return Ok(Step::Field(name.to_string()));
+    }
+
+    let upper = placeholder.to_ascii_uppercase();
+    if let Some(ms) = upper.strip_prefix("DELAY ") {
+        return ms
+            .trim()
+            .parse()
+            .map(Step::Delay)
+            .map_err(|_| AutoTypeError::InvalidSequence(format!("bad delay {{{}}}", placeholder)));
+    }
+
+    Ok(match upper.as_str() {
+        "USERNAME" | "PASSWORD" | "URL" | "NOTES" => Step::Field(upper.to_ascii_lowercase()),
+        "TAB" => Step::Key(SpecialKey::Tab),
+        "ENTER" => Step::Key(SpecialKey::Enter),
+        "SPACE" => Step::Key(SpecialKey::Space),
+        _ => {
+            return Err(AutoTypeError::InvalidSequence(format!(
+                "unknown placeholder {{{}}}",
+                placeholder
+            )))
+        }
+    })
+}
+
+/// Fill in field values for `sequence`, or the item's own or default sequence
+pub fn resolve(item: &VaultItem, sequence: Option<&str>) -> Result<Vec<Action>> {
+    let sequence = sequence
+        .or(item.auto_type.as_deref())
+        .unwrap_or(DEFAULT_SEQUENCE);
+
+    parse_sequence(sequence)?
+        .into_iter()
+        .map(|step| {
+            Ok(match step {
+                Step::Text(text) => Action::Text(Zeroizing::new(text)),
+                Step::Field(name) => {
+                    let value = clipboard::item_field(item, &name)
+                        .ok_or(AutoTypeError::MissingField(name))?;
+                    Action::Text(Zeroizing::new(value.to_string()))
+                }
+                Step::Key(key) => Action::Key(key),
+                Step::Delay(ms) => Action::Delay(ms),
+            })
+        })
+        .collect()
+}
+
+/// Type `actions` into whatever window takes focus once Keydrop steps aside
+///
+/// Keystrokes are only sent when the focused window's title equals
+/// `confirmed_title`, which the user approved after a previous call reported
+/// it. Otherwise Keydrop comes back and the result carries the title to ask
+/// about.
+pub async fn auto_type(
+    app: &AppHandle,
+    actions: Vec<Action>,
+    confirmed_title: Option<&str>,
+) -> Result<AutoTypeResult> {
+    step_aside(app);
+    tokio::time::sleep(FOCUS_SETTLE).await;
+
+    let target_title = active_win_pos_rs::get_active_window()
+        .map_err(|_| AutoTypeError::NoTarget)?
+        .title;
+    if confirmed_title != Some(target_title.as_str()) {
+        tray::show_main_window(app);
+        return Ok(AutoTypeResult {
+            typed: false,
+            target_title,
+        });
+    }
+
+    tauri::async_runtime::spawn_blocking(move || type_actions(&actions))
+        .await
+        .map_err(|e| AutoTypeError::Input(e.to_string()))??;
+    Ok(AutoTypeResult {
+        typed: true,
+        target_title,
+    })
+}
+
+/// Minimize the main window and close the palette so focus returns to the target
+fn step_aside(app: &AppHandle) {
+    for (label, window) in app.webview_windows() {
+        let _ = if label == PALETTE_LABEL {
+            window.hide()
+        } else {
+            window.minimize()
+        };
+    }
+    // Hidden windows alone leave Keydrop the active app on macOS
+    #[cfg(target_os = "macos")]
+    let _ = app.hide();
+}
+
+fn type_actions(actions: &[Action]) -> Result<()> {
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| AutoTypeError::Input(e.to_string()))?;
+    for action in actions {
+        match action {
+            Action::Text(text) => enigo.text(text),
+            Action::Key(key) => enigo.key(
+                match key {
+                    SpecialKey::Tab => Key::Tab,
+                    SpecialKey::Enter => Key::Return,
+                    SpecialKey::Space => Key::Space,
+                },
+                Direction::Click,
+            ),
+            Action::Delay(ms) => {
+                std::thread::sleep(Duration::from_millis(*ms));
+                Ok(())
+            }
+        }
+        .map_err(|e| AutoTypeError::Input(e.to_string()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sequence() {
+        assert_eq!(
+            parse_sequence(DEFAULT_SEQUENCE).unwrap(),
+            vec![
+                Step::Field("username".to_string()),
+                Step::Key(SpecialKey::Tab),
+                Step::Field("password".to_string()),
+                Step::Key(SpecialKey::Enter),
+            ]
+        );
+        assert_eq!(
+            parse_sequence("id:{S:Account No}{delay 250}{Enter}").unwrap(),
+            vec![
+                Step::Text("id:".to_string()),
+                Step::Field("Account No".to_string()),
+                Step::Delay(250),
+                Step::Key(SpecialKey::Enter),
+            ]
+        );
+
+        assert!(parse_sequence("{USERNAME").is_err());
+        assert!(parse_sequence("{BOGUS}").is_err());
+        assert!(parse_sequence("{DELAY soon}").is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let item = VaultItem::new("Bank", "alice", "hunter2");
+        let texts = |actions: Vec<Action>| -> Vec<String> {
+            actions
+                .into_iter()
+                .filter_map(|action| match action {
+                    Action::Text(text) => Some(text.to_string()),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        assert_eq!(texts(resolve(&item, None).unwrap()), ["alice", "hunter2"]);
+        assert_eq!(
+            texts(resolve(&item.clone().with_auto_type("{PASSWORD}"), None).unwrap()),
+            ["hunter2"]
+        );
+        assert_eq!(
+            texts(resolve(&item, Some("{USERNAME}")).unwrap()),
+            ["alice"]
+        );
+        assert!(matches!(
+            resolve(&item, Some("{URL}")),
+            Err(AutoTypeError::MissingField(_))
+        ));
+    }
+}
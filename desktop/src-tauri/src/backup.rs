@@ -0,0 +1,534 @@
+use crate::attachments::{self, AttachmentError};
+use crate::state::AppState;
+use crate::storage::{Storage, StorageError};
+use crypto_core::backup::{open_backup_with, seal_backup_with, BackupAttachment, BackupContents};
+use crypto_core::cipher::{decrypt_string, encrypt_string};
+use crypto_core::error::CryptoError;
+use crypto_core::vault::Vault;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tokio::sync::Notify;
+use zeroize::Zeroizing;
+
+/// File extension used for backups
+pub const BACKUP_EXTENSION: &str = "kdbk";
+
+/// Name prefix of automatic backups, which pruning is limited to
+const AUTO_BACKUP_PREFIX: &str = "keydrop-backup-";
+
+/// Settings key that held the JSON [`AutoBackupConfig`] before it moved into
+/// [`crate::settings::Settings`]
+pub const AUTO_BACKUP_SETTING: &str = "auto_backup";
+
+/// Settings key holding the backup passphrase, encrypted with the vault key
+const AUTO_BACKUP_PASSPHRASE_SETTING: &str = "auto_backup_passphrase";
+
+/// Settings key holding when the last automatic backup was written
+const LAST_BACKUP_SETTING: &str = "auto_backup_last";
+
+/// Event emitted to the frontend after each automatic backup attempt
+pub const BACKUP_STATUS_EVENT: &str = "backup-status";
+
+/// How often the scheduler checks for a due daily backup
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("Backup file error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Backup passphrase must not be empty")]
+    EmptyPassphrase,
+
+    #[error("Automatic backups need a passphrase")]
+    NoPassphrase,
+
+    #[error("Vault is locked")]
+    Locked,
+
+    #[error("Backup failed: {0}")]
+    Crypto(#[from] CryptoError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error(transparent)]
+    Attachments(#[from] AttachmentError),
+}
+
+pub type Result<T> = std::result::Result<T, BackupError>;
+
+/// Encrypt `vault` and its attachments with `passphrase` and write them to `path`
+///
+/// The backup is written next to `path` first and renamed into place, so an
+/// interrupted export never leaves a truncated file behind.
+pub fn write_backup(
+    vault: &Vault,
+    attachments: &[BackupAttachment],
+    path: &Path,
+    passphrase: &str,
+) -> Result<()> {
+    if passphrase.is_empty() {
+        return Err(BackupError::EmptyPassphrase);
+    }
+    let data = seal_backup_with(vault, attachments, passphrase)?;
+
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    std::fs::write(&partial, data)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Read and decrypt the backup at `path`
+pub fn read_backup(path: &Path, passphrase: &str) -> Result<BackupContents> {
+    let data = std::fs::read(path)?;
+    Ok(open_backup_with(&data, passphrase)?)
+}
+
+/// When and where automatic backups are written, and how many are kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoBackupConfig {
+    pub enabled: bool,
+    /// Backup directory; `None` uses `backups` in the app data directory
+    pub directory: Option<PathBuf>,
+    /// Back up after this many vault changes (0 = never by count)
+    pub every_changes: u32,
+    /// Back up once a day when the vault has changed
+    pub daily: bool,
+    /// Newest backups to keep (0 = no limit)
+    pub keep_count: usize,
+    /// Delete backups older than this many days (0 = no limit)
+    pub keep_days: u64,
+}
+
+impl Default for AutoBackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: None,
+            every_changes: 20,
+            daily: true,
+            keep_count: 10,
+            keep_days: 30,
+        }
+    }
+}
+
+impl AutoBackupConfig {
+    /// Directory backups are written to
+    pub fn resolved_directory(&self) -> Result<PathBuf> {
+        match &self.directory {
+            Some(directory) => Ok(directory.clone()),
+            None => Ok(Storage::app_dir()?.join("backups")),
+        }
+    }
+}
+
+/// Outcome of automatic backups so far
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AutoBackupStatus {
+    /// When the last automatic backup was written (Unix epoch seconds)
+    pub last_backup_at: Option<u64>,
+    pub last_backup_path: Option<PathBuf>,
+    /// Vault changes not yet in a backup
+    pub changes_since_backup: u32,
+    /// Error from the last attempt, cleared by the next success
+    pub last_error: Option<String>,
+}
+
+/// Automatic backup settings and progress
+pub struct BackupState {
+    config: Mutex<AutoBackupConfig>,
+    status: Mutex<AutoBackupStatus>,
+    /// Wakes the scheduler when a change may have made a backup due
+    wake: Notify,
+}
+
+impl BackupState {
+    pub fn new() -> Self {
+        Self {
+            config: Mutex::new(AutoBackupConfig::default()),
+            status: Mutex::new(AutoBackupStatus::default()),
+            wake: Notify::new(),
+        }
+    }
+
+    /// Restore the last backup time; the configuration comes from settings
+    pub fn load(&self, storage: &Storage) {
+        self.status.lock().unwrap().last_backup_at = storage
+            .get_setting(LAST_BACKUP_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|value| value.parse().ok());
+    }
+
+    pub fn config(&self) -> AutoBackupConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn set_config(&self, config: AutoBackupConfig) {
+        *self.config.lock().unwrap() = config;
+        self.wake.notify_one();
+    }
+
+    pub fn status(&self) -> AutoBackupStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Count vault changes toward the next backup
+    pub fn record_changes(&self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let mut status = self.status.lock().unwrap();
+        status.changes_since_backup = status
+            .changes_since_backup
+            .saturating_add(count.try_into().unwrap_or(u32::MAX));
+        self.wake.notify_one();
+    }
+}
+
+impl Default for BackupState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Store the automatic backup passphrase, encrypted with the vault key
+pub fn save_passphrase(storage: &Storage, passphrase: &str, vault_key: &[u8; 32]) -> Result<()> {
+    if passphrase.is_empty() {
+        return Err(BackupError::EmptyPassphrase);
+    }
+    let encrypted = encrypt_string(passphrase, vault_key)?;
+    storage.set_setting(AUTO_BACKUP_PASSPHRASE_SETTING, &encrypted)?;
+    Ok(())
+}
+
+pub fn has_passphrase(storage: &Storage) -> Result<bool> {
+    Ok(storage
+        .get_setting(AUTO_BACKUP_PASSPHRASE_SETTING)?
+        .is_some())
+}
+
+/// Re-encrypt the stored passphrase after the vault key changes
+pub fn rekey_passphrase(storage: &Storage, old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<()> {
+    if !has_passphrase(storage)? {
+        return Ok(());
+    }
+    let passphrase = load_passphrase(storage, old_key)?;
+    save_passphrase(storage, &passphrase, new_key)
+}
+
+fn load_passphrase(storage: &Storage, vault_key: &[u8; 32]) -> Result<Zeroizing<String>> {
+    let encrypted = storage
+        .get_setting(AUTO_BACKUP_PASSPHRASE_SETTING)?
+        .ok_or(BackupError::NoPassphrase)?;
+    Ok(Zeroizing::new(decrypt_string(&encrypted, vault_key)?))
+}
+
+/// Start the background task that writes automatic backups
+///
+/// Wakes on every recorded change and every [`CHECK_INTERVAL`]. Backups are
+/// only written while the vault is unlocked, since the passphrase is stored
+/// under the vault key.
+pub fn spawn_backup_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let backup_state = app.state::<BackupState>();
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+                _ = backup_state.wake.notified() => {}
+            }
+
+            let config = backup_state.config();
+            let state = app.state::<AppState>();
+            if !config.enabled
+                || !backup_due(&config, &backup_state.status(), now_secs())
+                || !state.is_unlocked()
+                || state.is_decoy()
+            {
+                continue;
+            }
+
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || run_auto_backup(&handle)).await;
+        }
+    });
+}
+
+/// Whether enough has changed, or enough time passed, for another backup
+fn backup_due(config: &AutoBackupConfig, status: &AutoBackupStatus, now: u64) -> bool {
+    let changes = status.changes_since_backup;
+    if changes == 0 {
+        return false;
+    }
+    if config.every_changes > 0 && changes >= config.every_changes {
+        return true;
+    }
+    config.daily
+        && status
+            .last_backup_at
+            .is_none_or(|last| now.saturating_sub(last) >= DAY_SECS)
+}
+
+/// Write an automatic backup now and prune old ones
+///
+/// The outcome is recorded in [`BackupState`] and emitted as [`BACKUP_STATUS_EVENT`].
+pub fn run_auto_backup(app: &AppHandle) -> Result<AutoBackupStatus> {
+    let backup_state = app.state::<BackupState>();
+    let result = write_auto_backup(app, &backup_state.config());
+
+    let status = {
+        let mut status = backup_state.status.lock().unwrap();
+        match &result {
+            Ok((path, now)) => {
+                status.last_backup_at = Some(*now);
+                status.last_backup_path = Some(path.clone());
+                status.changes_since_backup = 0;
+                status.last_error = None;
+            }
+            Err(e) => {
+                tracing::warn!("Automatic backup failed: {}", e);
+                status.last_error = Some(e.to_string());
+            }
+        }
+        status.clone()
+    };
+    let _ = app.emit(BACKUP_STATUS_EVENT, &status);
+    result.map(|_| status)
+}
+
+fn write_auto_backup(app: &AppHandle, config: &AutoBackupConfig) -> Result<(PathBuf, u64)> {
+    let state = app.state::<AppState>();
+    if state.is_decoy() {
+        return Err(BackupError::Locked);
+    }
+    let vault = state
+        .vault
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or(BackupError::Locked)?;
+    let vault_key = Zeroizing::new(
+        state
+            .keys
+            .lock()
+            .unwrap()
+            .as_ref()
+            .ok_or(BackupError::Locked)?
+            .vault_key,
+    );
+
+    let storage = Storage::open()?;
+    let passphrase = load_passphrase(&storage, &vault_key)?;
+    let directory = config.resolved_directory()?;
+    std::fs::create_dir_all(&directory)?;
+
+    let now = now_secs();
+    let path = directory.join(backup_file_name(now));
+    let attachments = attachments::for_backup(&storage, &vault_key)?;
+    write_backup(&vault, &attachments, &path, &passphrase)?;
+    storage.set_setting(LAST_BACKUP_SETTING, &now.to_string())?;
+
+    for old in backups_to_prune(list_backups(&directory)?, config, now) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok((path, now))
+}
+
+/// `keydrop-backup-YYYYMMDD-HHMMSS.kdbk` in UTC
+fn backup_file_name(secs: u64) -> String {
+    let days = (secs / DAY_SECS) as i64;
+    let time = secs % DAY_SECS;
+
+    // Civil date from days since 1970-01-01 (proleptic Gregorian)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{}{:04}{:02}{:02}-{:02}{:02}{:02}.{}",
+        AUTO_BACKUP_PREFIX,
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        BACKUP_EXTENSION
+    )
+}
+
+/// Number of automatic backups in `directory`; none if it doesn't exist yet
+pub fn backup_count(directory: &Path) -> Result<usize> {
+    if !directory.exists() {
+        return Ok(0);
+    }
+    Ok(list_backups(directory)?.len())
+}
+
+/// Automatic backups in `directory` with their modification times
+fn list_backups(directory: &Path) -> Result<Vec<(PathBuf, u64)>> {
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        let is_backup = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                name.starts_with(AUTO_BACKUP_PREFIX)
+                    && name.ends_with(&format!(".{}", BACKUP_EXTENSION))
+            });
+        if !is_backup {
+            continue;
+        }
+        let modified = std::fs::metadata(&path)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|age| age.as_secs())
+            .unwrap_or(0);
+        backups.push((path, modified));
+    }
+    Ok(backups)
+}
+
+/// Backups beyond `keep_count` or older than `keep_days`; the newest is always kept
+fn backups_to_prune(
+    mut backups: Vec<(PathBuf, u64)>,
+    config: &AutoBackupConfig,
+    now: u64,
+) -> Vec<PathBuf> {
+    backups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+    let max_age = config.keep_days.saturating_mul(DAY_SECS);
+
+    backups
+        .into_iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(index, (_, modified))| {
+            (config.keep_count > 0 && *index >= config.keep_count)
+                || (config.keep_days > 0 && now.saturating_sub(*modified) > max_age)
+        })
+        .map(|(_, (path, _))| path)
+        .collect()
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_core::vault::VaultItem;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_backup_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.kdbk");
+        let mut vault = Vault::new();
+        vault.add_item(VaultItem::new("Mail", "alice", "hunter2"));
+
+        write_backup(&vault, &[], &path, "correct horse").unwrap();
+        let restored = read_backup(&path, "correct horse").unwrap();
+        assert_eq!(restored.vault.items, vault.items);
+        assert!(restored.attachments.is_empty());
+
+        assert!(read_backup(&path, "wrong").is_err());
+        assert!(matches!(
+            write_backup(&vault, &[], &path, ""),
+            Err(BackupError::EmptyPassphrase)
+        ));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_backup_due() {
+        let config = AutoBackupConfig::default();
+        let now = 10 * DAY_SECS;
+        let status = |changes, last_backup_at| AutoBackupStatus {
+            changes_since_backup: changes,
+            last_backup_at,
+            ..Default::default()
+        };
+
+        assert!(!backup_due(&config, &status(0, None), now));
+        assert!(backup_due(&config, &status(1, None), now));
+        assert!(!backup_due(&config, &status(1, Some(now - 60)), now));
+        assert!(backup_due(&config, &status(1, Some(now - DAY_SECS)), now));
+        assert!(backup_due(&config, &status(20, Some(now - 60)), now));
+
+        let manual = AutoBackupConfig {
+            every_changes: 0,
+            daily: false,
+            ..config
+        };
+        assert!(!backup_due(&manual, &status(500, None), now));
+    }
+
+    #[test]
+    fn test_backup_file_name() {
+        assert_eq!(backup_file_name(0), "keydrop-backup-19700101-000000.kdbk");
+        assert_eq!(
+            backup_file_name(1_709_210_096),
+            "keydrop-backup-20240229-123456.kdbk"
+        );
+    }
+
+    #[test]
+    fn test_backups_to_prune() {
+        let now = 100 * DAY_SECS;
+        let backups: Vec<(PathBuf, u64)> = (0..5)
+            .map(|i| (PathBuf::from(format!("b{}", i)), now - i * 10 * DAY_SECS))
+            .collect();
+
+        let config = AutoBackupConfig {
+            keep_count: 3,
+            keep_days: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            backups_to_prune(backups.clone(), &config, now),
+            [PathBuf::from("b3"), PathBuf::from("b4")]
+        );
+
+        let config = AutoBackupConfig {
+            keep_count: 0,
+            keep_days: 15,
+            ..Default::default()
+        };
+        assert_eq!(
+            backups_to_prune(backups.clone(), &config, now),
+            [
+                PathBuf::from("b2"),
+                PathBuf::from("b3"),
+                PathBuf::from("b4")
+            ]
+        );
+
+        // The newest backup survives even when everything is too old
+        assert_eq!(
+            backups_to_prune(backups, &config, now + 365 * DAY_SECS).len(),
+            4
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! Native messaging host launched by the browser extension
+//!
+//! Relays each length-prefixed JSON message from the browser to the running
+//! Keydrop app over its local bridge and writes the reply back, so the
+//! extension reads the desktop vault instead of keeping its own copy.
+
+use keydrop_desktop_lib::native_messaging::{self, BridgeStream, Response};
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Write};
+
+struct Bridge {
+    reader: BufReader<BridgeStream>,
+    writer: BridgeStream,
+}
+
+impl Bridge {
+    fn connect() -> io::Result<Self> {
+        let writer = native_messaging::connect_bridge()?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(Self { reader, writer })
+    }
+
+    fn send(&mut self, request: &Value) -> io::Result<Vec<u8>> {
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+
+        let mut reply = Vec::new();
+        if self.reader.read_until(b'\n', &mut reply)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        reply.pop();
+        Ok(reply)
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    let mut bridge: Option<Bridge> = None;
+
+    while let Some(frame) = native_messaging::read_frame(&mut stdin)? {
+        let reply = match serde_json::from_slice::<Value>(&frame) {
+            Ok(request) => relay(&mut bridge, &request).unwrap_or_else(|_| {
+                // Reconnect on the next message in case the app restarted
+                bridge = None;
+                let id = request.get("id").cloned().unwrap_or_default();
+                error_reply(id, "Keydrop is not running or browser integration is off")
+            }),
+            Err(e) => error_reply(Value::Null, &format!("Invalid request: {}", e)),
+        };
+        native_messaging::write_frame(&mut stdout, &reply)?;
+    }
+    Ok(())
+}
+
+fn relay(bridge: &mut Option<Bridge>, request: &Value) -> io::Result<Vec<u8>> {
+    if bridge.is_none() {
+        *bridge = Some(Bridge::connect()?);
+    }
+    bridge.as_mut().unwrap().send(request)
+}
+
+fn error_reply(id: Value, message: &str) -> Vec<u8> {
+    serde_json::to_vec(&Response::error(id, message)).unwrap_or_default()
+}
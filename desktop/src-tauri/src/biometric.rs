@@ -0,0 +1,132 @@
+use crate::storage::{Keychain, Secret, Storage, StorageError};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crypto_core::{
+    cipher::{unwrap_key, wrap_key, KEY_SIZE},
+    error::CryptoError,
+    kdf::MasterKey,
+};
+use rand::RngCore;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Settings key holding the master key wrapped with the keychain-held KEK
+const WRAPPED_KEY_SETTING: &str = "biometric_wrapped_key";
+
+#[derive(Error, Debug)]
+pub enum BiometricError {
+    #[error("Biometric unlock is not available on this device")]
+    Unavailable,
+
+    #[error("Biometric unlock is not enabled")]
+    NotEnrolled,
+
+    #[error("Biometric verification was canceled")]
+    #[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+    Canceled,
+
+    #[error("Biometric verification failed")]
+    #[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+    Failed,
+
+    #[error(transparent)]
+    Crypto(#[from] CryptoError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+pub type Result<T> = std::result::Result<T, BiometricError>;
+
+/// Whether this platform has a supported biometric prompt
+pub fn is_available() -> bool {
+    cfg!(any(target_os = "macos", target_os = "windows"))
+}
+
+/// Whether a wrapped master key has been enrolled on this device
+pub fn is_enrolled(storage: &Storage) -> Result<bool> {
+    Ok(storage.get_setting(WRAPPED_KEY_SETTING)?.is_some())
+}
+
+/// Ask the OS to verify the user with Touch ID or Windows Hello
+///
+/// Blocks until the user responds, so call it off the main thread.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+pub fn verify_user(reason: &str) -> Result<()> {
+    use robius_authentication::{
+        AndroidText, BiometricStrength, Context, Error, PolicyBuilder, Text, WindowsText,
+    };
+
+    let policy = PolicyBuilder::new()
+        .biometrics(Some(BiometricStrength::Strong))
+        .password(true)
+        .build()
+        .ok_or(BiometricError::Unavailable)?;
+    let text = Text {
+        android: AndroidText {
+            title: reason,
+            subtitle: None,
+            description: None,
+        },
+        apple: reason,
+        windows: WindowsText::new("Keydrop", reason).ok_or(BiometricError::Unavailable)?,
+    };
+
+    Context::new(())
+        .blocking_authenticate(text, &policy)
+        .map_err(|e| match e {
+            Error::UserCanceled => BiometricError::Canceled,
+            Error::Unavailable => BiometricError::Unavailable,
+            _ => BiometricError::Failed,
+        })
+}
+
+/// Ask the OS to verify the user with Touch ID or Windows Hello
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn verify_user(_reason: &str) -> Result<()> {
+    Err(BiometricError::Unavailable)
+}
+
+/// Wrap the master key under a fresh KEK kept in the OS keychain
+///
+/// Unlocking later only needs the keychain entry and a successful
+/// [`verify_user`], so Argon2 doesn't run again.
+pub fn enroll(storage: &Storage, master_key: &MasterKey) -> Result<()> {
+    let mut kek = Zeroizing::new([0u8; KEY_SIZE]);
+    rand::rngs::OsRng.fill_bytes(kek.as_mut());
+
+    let wrapped = wrap_key(master_key.as_bytes(), &kek)?;
+    Keychain::set(Secret::BiometricKey, &STANDARD.encode(kek.as_ref()))?;
+    storage.set_setting(WRAPPED_KEY_SETTING, &STANDARD.encode(wrapped))?;
+    Ok(())
+}
+
+/// Recover the enrolled master key; the caller must have run [`verify_user`]
+pub fn unwrap_master_key(storage: &Storage) -> Result<MasterKey> {
+    let wrapped = storage
+        .get_setting(WRAPPED_KEY_SETTING)?
+        .ok_or(BiometricError::NotEnrolled)?;
+    let kek = Keychain::get(Secret::BiometricKey)?.ok_or(BiometricError::NotEnrolled)?;
+
+    let kek = Zeroizing::new(decode(&kek)?);
+    let kek = Zeroizing::new(<[u8; KEY_SIZE]>::try_from(kek.as_slice()).map_err(|_| {
+        CryptoError::InvalidKeyLength {
+            expected: KEY_SIZE,
+            got: kek.len(),
+        }
+    })?);
+    let master_key = unwrap_key(&decode(&wrapped)?, &kek)?;
+    Ok(MasterKey::from_bytes(*master_key))
+}
+
+/// Remove the enrollment from the keychain and settings
+pub fn unenroll(storage: &Storage) -> Result<()> {
+    Keychain::delete(Secret::BiometricKey)?;
+    storage.delete_setting(WRAPPED_KEY_SETTING)?;
+    Ok(())
+}
+
+fn decode(value: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| CryptoError::Deserialization(e.to_string()).into())
+}
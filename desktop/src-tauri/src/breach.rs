@@ -0,0 +1,184 @@
+use crypto_core::audit::{breach_count, breach_query};
+use crypto_core::vault::Vault;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Pwned Passwords range endpoint; the hash prefix is appended
+const RANGE_URL: &str = "https://api.pwnedpasswords.com/range/";
+
+/// Shortest gap between range requests
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How long an item's result is reused before it is checked again
+const RESULT_TTL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Error, Debug)]
+pub enum BreachError {
+    #[error("Breach check failed: {0}")]
+    Network(#[from] reqwest::Error),
+}
+
+pub type Result<T> = std::result::Result<T, BreachError>;
+
+/// Breach check outcome for one item's password
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ItemResult {
+    /// Item modification time the result was computed for
+    modified_at: u64,
+    /// Times the password appears in known breaches
+    count: u64,
+    checked_at: u64,
+}
+
+/// An item whose password appears in a known breach
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BreachedItem {
+    pub item_id: String,
+    pub count: u64,
+}
+
+/// Result of `check_breaches`
+#[derive(Debug, Clone, Serialize)]
+pub struct BreachReport {
+    /// Items looked up in this run; the rest reused recent results
+    pub checked: usize,
+    pub breached: Vec<BreachedItem>,
+}
+
+/// Breach results per item, and pacing for range requests
+///
+/// Results are keyed by item ID and modification time, so no password hashes
+/// are kept once a check finishes.
+#[derive(Default)]
+pub struct BreachState {
+    results: Mutex<HashMap<String, ItemResult>>,
+    /// Held while a request is paced and sent, so checks never run in parallel
+    last_request: tokio::sync::Mutex<Option<Instant>>,
+}
+
+impl BreachState {
+    /// Items whose current password was found in a breach by an earlier check
+    pub fn breached(&self, vault: &Vault) -> Vec<BreachedItem> {
+        let results = self.results.lock().unwrap();
+        vault
+            .items
+            .iter()
+            .filter_map(|item| {
+                let result = results.get(&item.id)?;
+                (result.modified_at == item.modified_at && result.count > 0).then(|| BreachedItem {
+                    item_id: item.id.clone(),
+                    count: result.count,
+                })
+            })
+            .collect()
+    }
+
+    /// Look up item passwords that have no recent result
+    ///
+    /// `vault` is a snapshot, so the vault lock isn't held across requests.
+    pub async fn check(&self, vault: &Vault, now: u64) -> Result<BreachReport> {
+        // Items grouped by hash prefix, so each prefix is fetched once
+        let mut pending: BTreeMap<String, Vec<(String, u64, String)>> = BTreeMap::new();
+        {
+            let results = self.results.lock().unwrap();
+            for item in vault.items.iter().filter(|item| !item.password.is_empty()) {
+                let fresh = results.get(&item.id).is_some_and(|result| {
+                    result.modified_at == item.modified_at
+                        && now.saturating_sub(result.checked_at) < RESULT_TTL_SECS
+                });
+                if !fresh {
+                    let query = breach_query(&item.password);
+                    pending.entry(query.prefix).or_default().push((
+                        item.id.clone(),
+                        item.modified_at,
+                        query.suffix,
+                    ));
+                }
+            }
+        }
+
+        let client = reqwest::Client::new();
+        let mut checked = 0;
+        for (prefix, items) in pending {
+            let response = self.fetch_range(&client, &prefix).await?;
+            let mut results = self.results.lock().unwrap();
+            for (id, modified_at, suffix) in items {
+                results.insert(
+                    id,
+                    ItemResult {
+                        modified_at,
+                        count: breach_count(&response, &suffix),
+                        checked_at: now,
+                    },
+                );
+                checked += 1;
+            }
+        }
+
+        Ok(BreachReport {
+            checked,
+            breached: self.breached(vault),
+        })
+    }
+
+    async fn fetch_range(&self, client: &reqwest::Client, prefix: &str) -> Result<String> {
+        let mut last_request = self.last_request.lock().await;
+        if let Some(at) = *last_request {
+            tokio::time::sleep(MIN_REQUEST_INTERVAL.saturating_sub(at.elapsed())).await;
+        }
+        *last_request = Some(Instant::now());
+
+        // Padding hides the real response size from network observers
+        Ok(client
+            .get(format!("{}{}", RANGE_URL, prefix))
+            .header("Add-Padding", "true")
+            .header(reqwest::header::USER_AGENT, "Keydrop")
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?)
+    }
+
+    /// Forget all results, e.g. when the vault locks
+    pub fn clear(&self) {
+        self.results.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_core::vault::VaultItem;
+
+    #[test]
+    fn test_breached_ignores_changed_items() {
+        let mut vault = Vault::new();
+        let id = vault.add_item(VaultItem::new("GitHub", "user", "password"));
+        let modified_at = vault.get_item(&id).unwrap().modified_at;
+
+        let state = BreachState::default();
+        state.results.lock().unwrap().insert(
+            id.clone(),
+            ItemResult {
+                modified_at,
+                count: 3,
+                checked_at: 0,
+            },
+        );
+        assert_eq!(
+            state.breached(&vault),
+            vec![BreachedItem {
+                item_id: id.clone(),
+                count: 3
+            }]
+        );
+
+        // A new password makes the old result stale
+        vault.get_item_mut(&id).unwrap().modified_at = modified_at + 1;
+        assert!(state.breached(&vault).is_empty());
+    }
+}
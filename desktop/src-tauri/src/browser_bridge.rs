@@ -0,0 +1,311 @@
+use crate::native_messaging::{bridge_endpoint, Request, RequestEnvelope, Response, HOST_NAME};
+use crate::search;
+use crate::state::AppState;
+use crate::storage::Storage;
+use crate::tray;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Settings key holding the extension ID allowed to use the bridge
+pub const EXTENSION_ID_SETTING: &str = "browser_extension_id";
+
+/// Running bridge server, if browser integration is enabled
+#[derive(Default)]
+pub struct BridgeState {
+    server: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Item summary returned by `find_by_url`; secrets need `get_credentials`
+#[derive(Debug, Serialize)]
+struct CredentialSummary<'a> {
+    id: &'a str,
+    name: &'a str,
+    username: &'a str,
+    url: Option<&'a str>,
+}
+
+/// Start serving native messaging host connections, replacing any running server
+pub fn start(app: &AppHandle) {
+    stop(app);
+    let server = tauri::async_runtime::spawn(serve(app.clone()));
+    *app.state::<BridgeState>().server.lock().unwrap() = Some(server);
+}
+
+pub fn stop(app: &AppHandle) {
+    if let Some(server) = app.state::<BridgeState>().server.lock().unwrap().take() {
+        server.abort();
+    }
+    #[cfg(unix)]
+    if let Some(path) = bridge_endpoint() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(unix)]
+async fn serve(app: AppHandle) {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    use tokio::net::UnixListener;
+
+    let Some(path) = bridge_endpoint() else {
+        return;
+    };
+    let _ = std::fs::remove_file(&path);
+    let Ok(listener) = UnixListener::bind(&path) else {
+        return;
+    };
+    // Only this user may talk to the vault
+    let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+    let Ok(owner) = std::fs::metadata(&path).map(|metadata| metadata.uid()) else {
+        return;
+    };
+
+    while let Ok((stream, _)) = listener.accept().await {
+        let same_user = stream.peer_cred().is_ok_and(|cred| cred.uid() == owner);
+        if same_user {
+            tauri::async_runtime::spawn(handle_connection(app.clone(), stream));
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn serve(app: AppHandle) {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let Some(name) = bridge_endpoint() else {
+        return;
+    };
+    // Fail rather than share the name if another process already owns it
+    let Ok(mut server) = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(true)
+        .create(&name)
+    else {
+        return;
+    };
+
+    while server.connect().await.is_ok() {
+        let connected = server;
+        server = match ServerOptions::new()
+            .reject_remote_clients(true)
+            .create(&name)
+        {
+            Ok(next) => next,
+            Err(_) => return,
+        };
+        tauri::async_runtime::spawn(handle_connection(app.clone(), connected));
+    }
+}
+
+/// Answer newline-delimited JSON requests until the host disconnects
+async fn handle_connection(app: AppHandle, stream: impl AsyncRead + AsyncWrite + Send + 'static) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let response = handle_line(&app, &line);
+        let Ok(mut reply) = serde_json::to_vec(&response) else {
+            break;
+        };
+        reply.push(b'\n');
+        if writer.write_all(&reply).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn handle_line(app: &AppHandle, line: &str) -> Response {
+    let envelope = match serde_json::from_str::<RequestEnvelope>(line) {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            let id = serde_json::from_str::<Value>(line)
+                .ok()
+                .and_then(|value| value.get("id").cloned())
+                .unwrap_or_default();
+            return Response::error(id, format!("Invalid request: {}", e));
+        }
+    };
+
+    match handle_request(app, envelope.request) {
+        Ok(result) => Response::ok(envelope.id, result),
+        Err(message) => Response::error(envelope.id, message),
+    }
+}
+
+fn handle_request(app: &AppHandle, request: Request) -> Result<Value, String> {
+    let state = app.state::<AppState>();
+
+    match request {
+        Request::Status => {
+            let exists = Storage::open()
+                .and_then(|storage| storage.vault_exists())
+                .map_err(|e| e.to_string())?;
+            Ok(json!({ "exists": exists, "unlocked": state.is_unlocked() }))
+        }
+        Request::RequestUnlock => {
+            let unlocked = state.is_unlocked();
+            if !unlocked {
+                tray::show_main_window(app);
+            }
+            Ok(json!({ "unlocked": unlocked }))
+        }
+        Request::FindByUrl { url } => {
+            state.session.record_activity();
+            let vault = state.vault.lock().unwrap();
+            let vault = vault.as_ref().ok_or("Vault is locked")?;
+            let items: Vec<CredentialSummary> = vault
+                .find_by_url(&url)
+                .into_iter()
+                .map(|item| CredentialSummary {
+                    id: &item.id,
+                    name: &item.name,
+                    username: &item.username,
+                    url: item.url.as_deref(),
+                })
+                .collect();
+            Ok(json!({ "items": items }))
+        }
+        Request::GetCredentials { item_id, url } => {
+            state.session.record_activity();
+            let vault = state.vault.lock().unwrap();
+            let vault = vault.as_ref().ok_or("Vault is locked")?;
+            // Only hand out credentials for the site the extension is filling
+            let item = vault
+                .find_by_url(&url)
+                .into_iter()
+                .find(|item| item.id == item_id)
+                .ok_or("No matching credential for this site")?;
+            search::mark_used(&state, &item.id);
+            Ok(json!({ "username": item.username, "password": item.password }))
+        }
+    }
+}
+
+/// Native messaging host binary shipped next to the app executable
+fn host_executable() -> io::Result<PathBuf> {
+    let name = if cfg!(windows) {
+        "keydrop-native-host.exe"
+    } else {
+        "keydrop-native-host"
+    };
+    Ok(std::env::current_exe()?.with_file_name(name))
+}
+
+fn host_manifest(extension_id: &str) -> io::Result<Value> {
+    Ok(json!({
+        "name": HOST_NAME,
+        "description": "Keydrop desktop vault",
+        "path": host_executable()?,
+        "type": "stdio",
+        "allowed_origins": [format!("chrome-extension://{}/", extension_id)],
+    }))
+}
+
+/// Browser profile roots that get a host manifest, relative to the config directory
+#[cfg(not(windows))]
+const BROWSER_DIRS: &[&str] = if cfg!(target_os = "macos") {
+    &[
+        "Google/Chrome",
+        "Chromium",
+        "Microsoft Edge",
+        "BraveSoftware/Brave-Browser",
+    ]
+} else {
+    &[
+        "google-chrome",
+        "chromium",
+        "microsoft-edge",
+        "BraveSoftware/Brave-Browser",
+    ]
+};
+
+/// Register the host with installed Chromium-based browsers for `extension_id`
+#[cfg(not(windows))]
+pub fn install_host_manifests(extension_id: &str) -> io::Result<()> {
+    let manifest = serde_json::to_vec_pretty(&host_manifest(extension_id)?)?;
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config dir"))?;
+
+    for browser in BROWSER_DIRS {
+        let browser_dir = config_dir.join(browser);
+        if !browser_dir.is_dir() {
+            continue;
+        }
+        let hosts_dir = browser_dir.join("NativeMessagingHosts");
+        std::fs::create_dir_all(&hosts_dir)?;
+        std::fs::write(hosts_dir.join(format!("{}.json", HOST_NAME)), &manifest)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn uninstall_host_manifests() -> io::Result<()> {
+    let Some(config_dir) = dirs::config_dir() else {
+        return Ok(());
+    };
+    for browser in BROWSER_DIRS {
+        let manifest = config_dir
+            .join(browser)
+            .join("NativeMessagingHosts")
+            .join(format!("{}.json", HOST_NAME));
+        if manifest.exists() {
+            std::fs::remove_file(manifest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Registry keys pointing Chromium-based browsers at the host manifest
+#[cfg(windows)]
+const REGISTRY_KEYS: &[&str] = &[
+    r"HKCU\Software\Google\Chrome\NativeMessagingHosts",
+    r"HKCU\Software\Microsoft\Edge\NativeMessagingHosts",
+    r"HKCU\Software\BraveSoftware\Brave-Browser\NativeMessagingHosts",
+];
+
+/// Register the host with Chromium-based browsers for `extension_id`
+#[cfg(windows)]
+pub fn install_host_manifests(extension_id: &str) -> io::Result<()> {
+    let manifest_path = Storage::app_dir()
+        .map_err(|e| io::Error::other(e.to_string()))?
+        .join(format!("{}.json", HOST_NAME));
+    std::fs::write(
+        &manifest_path,
+        serde_json::to_vec_pretty(&host_manifest(extension_id)?)?,
+    )?;
+
+    for key in REGISTRY_KEYS {
+        let status = std::process::Command::new("reg")
+            .args([
+                "add",
+                &format!(r"{}\{}", key, HOST_NAME),
+                "/ve",
+                "/t",
+                "REG_SZ",
+                "/f",
+                "/d",
+            ])
+            .arg(&manifest_path)
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("reg add failed for {}", key)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn uninstall_host_manifests() -> io::Result<()> {
+    for key in REGISTRY_KEYS {
+        // Fails harmlessly when the key was never created
+        let _ = std::process::Command::new("reg")
+            .args(["delete", &format!(r"{}\{}", key, HOST_NAME), "/f"])
+            .status();
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use crate::settings::SettingsState;
+use crate::state::AppState;
+use tauri::{AppHandle, Manager};
+
+/// Whether the OS honours capture protection
+///
+/// Windows excludes protected windows from capture with
+/// `SetWindowDisplayAffinity`, macOS with the window's `sharingType`. Linux
+/// has no equivalent, so windows there stay capturable.
+pub const SUPPORTED: bool = cfg!(any(target_os = "windows", target_os = "macos"));
+
+/// Whether windows should be hidden from screenshots, recordings and screen shares
+///
+/// Only while the vault is unlocked, so the lock screen can still be shared
+/// when asking for help.
+pub fn should_protect(app: &AppHandle) -> bool {
+    app.state::<SettingsState>().get().capture_protection && app.state::<AppState>().is_unlocked()
+}
+
+/// Bring every window's capture protection in line with the setting and lock state
+///
+/// Call after the vault is unlocked or locked.
+pub fn refresh(app: &AppHandle) {
+    apply(app, app.state::<SettingsState>().get().capture_protection);
+}
+
+/// Protect every window if `enabled` and the vault is unlocked, else unprotect them
+pub fn apply(app: &AppHandle, enabled: bool) {
+    let protect = enabled && app.state::<AppState>().is_unlocked();
+    for (label, window) in app.webview_windows() {
+        if let Err(e) = window.set_content_protected(protect) {
+            tracing::warn!("Setting capture protection on {} failed: {}", label, e);
+        }
+    }
+}
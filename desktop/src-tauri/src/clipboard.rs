@@ -0,0 +1,178 @@
+use crypto_core::error::CryptoError;
+use crypto_core::totp::{generate_totp, parse_otpauth_uri, TotpCode, TotpOptions};
+use crypto_core::vault::VaultItem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use zeroize::Zeroizing;
+
+/// Default seconds before a copied secret is cleared from the clipboard
+pub const DEFAULT_CLIPBOARD_TIMEOUT: u64 = 30;
+
+/// Clipboard auto-clear state
+pub struct ClipboardState {
+    /// Seconds before a copied secret is cleared (0 disables clearing)
+    pub timeout: Mutex<u64>,
+    /// Incremented on every copy so only the latest copy's timer clears
+    generation: AtomicU64,
+}
+
+impl ClipboardState {
+    pub fn new() -> Self {
+        Self {
+            timeout: Mutex::new(DEFAULT_CLIPBOARD_TIMEOUT),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn timeout(&self) -> u64 {
+        *self.timeout.lock().unwrap()
+    }
+}
+
+impl Default for ClipboardState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Look up a copyable field: `username`, `password`, `url`, `notes`, or a custom field name
+pub fn item_field<'a>(item: &'a VaultItem, field: &str) -> Option<&'a str> {
+    match field {
+        "username" => Some(item.username.as_str()),
+        "password" => Some(item.password.as_str()),
+        "url" => item.url.as_deref(),
+        "notes" => item.notes.as_deref(),
+        name => item
+            .custom_fields
+            .iter()
+            .find(|custom| custom.name == name)
+            .map(|custom| custom.value.as_str()),
+    }
+    .filter(|value| !value.is_empty())
+}
+
+/// Current TOTP code for an item at Unix time `now`, if it has a secret
+///
+/// The secret may be a bare base32 key or an `otpauth://` URI carrying its own
+/// algorithm, digits, and period.
+pub fn item_totp(item: &VaultItem, now: u64) -> Result<Option<TotpCode>, CryptoError> {
+    let Some(secret) = item.totp_secret.as_deref().filter(|s| !s.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let code = if secret.trim_start().starts_with("otpauth://") {
+        let uri = parse_otpauth_uri(secret)?;
+        generate_totp(&uri.secret, &uri.options, now)?
+    } else {
+        generate_totp(secret, &TotpOptions::default(), now)?
+    };
+    Ok(Some(code))
+}
+
+/// Copy a secret to the clipboard and schedule it to be cleared
+///
+/// The clipboard is cleared only if it still holds our value, so anything the user
+/// copied in the meantime is left alone. Returns the seconds until clearing
+/// (0 if auto-clear is disabled).
+pub fn copy_secret(app: &AppHandle, value: &str) -> Result<u64, arboard::Error> {
+    write_transient(value)?;
+
+    let state = app.state::<ClipboardState>();
+    let generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let timeout = state.timeout();
+    if timeout == 0 {
+        return Ok(0);
+    }
+
+    let app = app.clone();
+    let value = Zeroizing::new(value.to_string());
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(timeout)).await;
+        let state = app.state::<ClipboardState>();
+        if state.generation.load(Ordering::SeqCst) == generation {
+            let _ = clear_if_unchanged(&value);
+        }
+    });
+    Ok(timeout)
+}
+
+/// Write text marked as sensitive, so clipboard history managers and cloud
+/// clipboard sync skip it where the OS has a convention for that
+///
+/// The clipboard plugin can't set these hints, so this goes through arboard
+/// directly; the plugin's long-lived instance keeps the Linux selection owned.
+fn write_transient(value: &str) -> Result<(), arboard::Error> {
+    let mut clipboard = arboard::Clipboard::new()?;
+    let set = clipboard.set();
+
+    #[cfg(target_os = "windows")]
+    let set = {
+        use arboard::SetExtWindows;
+        set.exclude_from_history().exclude_from_cloud()
+    };
+    #[cfg(target_os = "macos")]
+    let set = {
+        use arboard::SetExtApple;
+        set.exclude_from_history()
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let set = {
+        use arboard::SetExtLinux;
+        set.exclude_from_history()
+    };
+
+    set.text(value)
+}
+
+fn clear_if_unchanged(value: &str) -> Result<(), arboard::Error> {
+    let mut clipboard = arboard::Clipboard::new()?;
+    let current = Zeroizing::new(clipboard.get_text().unwrap_or_default());
+    if current.as_str() == value {
+        clipboard.clear()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_core::vault::CustomField;
+
+    #[test]
+    fn test_item_field() {
+        let mut item = VaultItem::new("Bank", "alice", "hunter2");
+        item.custom_fields.push(CustomField {
+            name: "PIN".to_string(),
+            value: "1234".to_string(),
+            hidden: true,
+        });
+
+        assert_eq!(item_field(&item, "username"), Some("alice"));
+        assert_eq!(item_field(&item, "password"), Some("hunter2"));
+        assert_eq!(item_field(&item, "PIN"), Some("1234"));
+        assert_eq!(item_field(&item, "url"), None);
+        assert_eq!(item_field(&item, "missing"), None);
+    }
+
+    #[test]
+    fn test_item_totp() {
+        let mut item = VaultItem::new("GitHub", "alice", "hunter2");
+        assert_eq!(item_totp(&item, 59).unwrap(), None);
+
+        // RFC 6238 SHA-1 test key
+        item.totp_secret = Some("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_string());
+        let code = item_totp(&item, 59).unwrap().unwrap();
+        assert_eq!(code.code, "287082");
+        assert_eq!(code.seconds_remaining, 1);
+
+        item.totp_secret = Some(
+            "otpauth://totp/GitHub:alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&digits=8"
+                .to_string(),
+        );
+        assert_eq!(item_totp(&item, 59).unwrap().unwrap().code, "94287082");
+
+        item.totp_secret = Some("not base32!".to_string());
+        assert!(item_totp(&item, 59).is_err());
+    }
+}
@@ -1,14 +1,53 @@
+use crate::activity::{self, ActivityEvent, ActivityFilter, ActivityKind};
+use crate::attachments::{self, Attachment, AttachmentError};
+use crate::auto_lock::{self, LockReason};
+use crate::autotype::{self, AutoTypeError, AutoTypeResult};
+use crate::backup::{self, AutoBackupConfig, AutoBackupStatus, BackupError, BackupState};
+use crate::biometric::{self, BiometricError};
+use crate::breach::{BreachError, BreachReport, BreachState};
+use crate::browser_bridge;
+use crate::capture;
+use crate::clipboard::{self, ClipboardState};
+use crate::conflicts::{self, ConflictChoice};
+use crate::deep_link::{DeepLink, DeepLinkState};
+use crate::emergency::{
+    self, AccessLogEntry, AccessRequest, AccessRequestStatus, AddContactRequest, EmergencyContact,
+    EmergencyKeyError, EmergencyVaultInfo, EmergencyViewer, GrantedAccess, PendingAccessRequest,
+    ViewedVault,
+};
+use crate::favicons::{self, IconState, ICON_FETCH_SETTING};
+use crate::generator::{
+    self, GeneratedPassword, GeneratorError, GeneratorOptions, GeneratorPreset,
+};
+use crate::import::{self, ImportError, ImportFormat, ImportOptions, ImportPreview, ImportSummary};
+use crate::keyfile::{self, KeyFileError};
+use crate::lockout::{self, FailedUnlocks, LockoutError, VAULT_WIPED_EVENT};
+use crate::logging::{LogEntry, RecentLogs};
+use crate::palette::{self, DEFAULT_PALETTE_SHORTCUT, PALETTE_SHORTCUT_SETTING};
+use crate::remote_commands;
+use crate::search::{self, SearchSort, DEFAULT_PAGE_SIZE};
+use crate::session::LockCountdown;
+use crate::settings::{self, Settings, SettingsError, SettingsState};
 use crate::state::AppState;
-use crate::storage::Storage;
-use crate::sync::{RemoteCommand, SyncState, SyncStatus};
+use crate::storage::{ChangeOp, Storage, StorageError, SyncCredentials};
+use crate::sync::{self, RemoteCommand, SyncError, SyncState, SyncStatus, SyncSummary};
+use crate::sync_client::SyncClient;
+use crate::tray;
+use crate::updater::{self, UpdateError, UpdateInfo};
+use crate::vault_store::{self, VaultStoreError};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crypto_core::audit::{audit_items, AuditOptions, AuditReport};
 use crypto_core::{
     cipher::EncryptedBlob,
-    kdf::{derive_keys, derive_master_key, Salt},
+    kdf::{combine_key_file, derive_keys, derive_master_key, KeySet, MasterKey, Salt},
     password::{generate_passphrase, generate_password, PasswordOptions},
-    vault::{Vault, VaultItem},
+    totp::TotpCode,
+    vault::{ItemFilter, MergePolicy, MergeReport, Vault, VaultItem},
 };
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager, State};
+use zeroize::Zeroizing;
 
 #[derive(Debug, Serialize)]
 pub struct CommandError {
@@ -31,6 +70,118 @@ impl From<crate::storage::StorageError> for CommandError {
     }
 }
 
+impl From<BackupError> for CommandError {
+    fn from(e: BackupError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<LockoutError> for CommandError {
+    fn from(e: LockoutError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<BiometricError> for CommandError {
+    fn from(e: BiometricError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<AutoTypeError> for CommandError {
+    fn from(e: AutoTypeError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<ImportError> for CommandError {
+    fn from(e: ImportError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<KeyFileError> for CommandError {
+    fn from(e: KeyFileError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<BreachError> for CommandError {
+    fn from(e: BreachError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<AttachmentError> for CommandError {
+    fn from(e: AttachmentError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<GeneratorError> for CommandError {
+    fn from(e: GeneratorError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<SettingsError> for CommandError {
+    fn from(e: SettingsError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<VaultStoreError> for CommandError {
+    fn from(e: VaultStoreError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<UpdateError> for CommandError {
+    fn from(e: UpdateError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<EmergencyKeyError> for CommandError {
+    fn from(e: EmergencyKeyError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<SyncError> for CommandError {
+    fn from(e: SyncError) -> Self {
+        CommandError {
+            message: e.to_string(),
+        }
+    }
+}
+
 type CommandResult<T> = Result<T, CommandError>;
 
 // =============================================================================
@@ -41,6 +192,14 @@ type CommandResult<T> = Result<T, CommandError>;
 pub struct VaultStatus {
     pub exists: bool,
     pub unlocked: bool,
+    /// Whether unlocking needs the key file as well as the password
+    pub key_file_required: bool,
+    /// Whether the saved vault is unreadable and must be restored from a backup
+    pub damaged: bool,
+    /// Whether the vault was wiped after failed unlocks and can be restored from sync
+    pub restorable: bool,
+    /// Seconds before another unlock attempt is allowed
+    pub retry_after: i64,
 }
 
 #[tauri::command]
@@ -49,6 +208,10 @@ pub fn get_vault_status(state: State<AppState>) -> CommandResult<VaultStatus> {
     Ok(VaultStatus {
         exists: storage.vault_exists()?,
         unlocked: state.is_unlocked(),
+        key_file_required: keyfile::registered_path(&storage)?.is_some(),
+        damaged: storage.vault_damaged()?,
+        restorable: storage.wiped_salt()?.is_some(),
+        retry_after: FailedUnlocks::load(&storage)?.retry_after(now_secs() as i64),
     })
 }
 
@@ -57,7 +220,7 @@ pub fn get_vault_status(state: State<AppState>) -> CommandResult<VaultStatus> {
 // =============================================================================
 
 #[tauri::command]
-pub fn create_vault(password: String, state: State<AppState>) -> CommandResult<()> {
+pub fn create_vault(password: String, app: AppHandle, state: State<AppState>) -> CommandResult<()> {
     let storage = Storage::open()?;
 
     if storage.vault_exists()? {
@@ -75,25 +238,30 @@ pub fn create_vault(password: String, state: State<AppState>) -> CommandResult<(
     let vault = Vault::new();
 
     // Encrypt and save
-    let encrypted = vault.export(&keys.vault_key)?;
-    let encrypted_bytes = serde_json::to_vec(&encrypted).map_err(|e| CommandError {
-        message: e.to_string(),
-    })?;
-
     storage.create_vault(salt.as_bytes())?;
-    storage.save_vault(&encrypted_bytes)?;
+    vault_store::save(&storage, &vault, &keys.vault_key)?;
 
     // Update state
     *state.vault.lock().unwrap() = Some(vault);
     *state.keys.lock().unwrap() = Some(keys);
     *state.salt.lock().unwrap() = Some(*salt.as_bytes());
-    state.touch();
+    state.session.record_activity();
+    tray::refresh_tray(&app);
+    capture::refresh(&app);
 
     Ok(())
 }
 
+/// Unlock with the master password, plus the key file if the vault requires one
+///
+/// `key_file` overrides the registered key file location.
 #[tauri::command]
-pub fn unlock_vault(password: String, state: State<AppState>) -> CommandResult<()> {
+pub fn unlock_vault(
+    password: String,
+    key_file: Option<String>,
+    app: AppHandle,
+    state: State<AppState>,
+) -> CommandResult<()> {
     let storage = Storage::open()?;
 
     if !storage.vault_exists()? {
@@ -102,34 +270,511 @@ pub fn unlock_vault(password: String, state: State<AppState>) -> CommandResult<(
         });
     }
 
-    // Load salt and encrypted vault
-    let salt_bytes = storage.get_salt()?;
-    let salt = Salt::from_bytes(salt_bytes);
-    let encrypted_bytes = storage.load_vault()?;
+    let now = now_secs() as i64;
+    lockout::check(&storage, now)?;
 
-    // Derive keys
+    let salt = Salt::from_bytes(storage.get_salt()?);
     let master_key = derive_master_key(&password, &salt)?;
-    let keys = derive_keys(&master_key)?;
+    // Derive the decoy key up front so a duress unlock takes as long as a real one
+    let decoy_key = storage
+        .get_decoy_salt()?
+        .map(|salt| derive_master_key(&password, &Salt::from_bytes(salt)))
+        .transpose()?;
+
+    let requires_key_file = keyfile::registered_path(&storage)?.is_some();
+    let opened = keyfile::apply(&storage, master_key, key_file.as_deref().map(Path::new))
+        .map_err(CommandError::from)
+        .and_then(|master_key| {
+            open_vault(&storage, &master_key, &state, false).map_err(|e| {
+                if requires_key_file {
+                    CommandError {
+                        message: "Incorrect master password or key file".to_string(),
+                    }
+                } else {
+                    e
+                }
+            })
+        });
+    let recovered = match opened {
+        Ok(recovered) => recovered,
+        // The duress password opens the decoy instead of failing
+        Err(e) => match decoy_key.map(|key| open_vault(&storage, &key, &state, true)) {
+            Some(Ok(recovered)) => recovered,
+            _ => return Err(failed_unlock(&app, &storage, now, e)),
+        },
+    };
+    lockout::reset(&storage)?;
+    activity::try_record(ActivityKind::Unlock, Some("password"));
+    if recovered {
+        let _ = app.emit(VAULT_RECOVERED_EVENT, ());
+    }
+    tray::refresh_tray(&app);
+    capture::refresh(&app);
+    app.state::<IconState>().request_refresh();
+    Ok(())
+}
 
-    // Decrypt vault
-    let encrypted: EncryptedBlob =
-        serde_json::from_slice(&encrypted_bytes).map_err(|e| CommandError {
-            message: e.to_string(),
+/// Count a failed unlock, erasing the local vault once the self-wipe limit is reached
+///
+/// Returns the error to report: `error` itself, unless the vault was wiped.
+fn failed_unlock(
+    app: &AppHandle,
+    storage: &Storage,
+    now: i64,
+    error: CommandError,
+) -> CommandError {
+    let failures = match lockout::record_failure(storage, now) {
+        Ok(failures) => failures,
+        Err(e) => return e.into(),
+    };
+    tracing::warn!(failures = failures.count, "Unlock failed");
+    activity::try_record(
+        ActivityKind::FailedUnlock,
+        Some(&format!("attempt {}", failures.count)),
+    );
+    let wipe_after = app.state::<SettingsState>().get().wipe_after_failures;
+    if wipe_after.is_none_or(|limit| failures.count < limit) {
+        return error;
+    }
+
+    if let Err(e) = lockout::wipe(storage) {
+        tracing::error!("Self-wipe after failed unlocks failed: {}", e);
+        return e.into();
+    }
+    tracing::warn!("Local vault wiped after {} failed unlocks", failures.count);
+    activity::try_record(
+        ActivityKind::SelfWipe,
+        Some(&format!("after {} failed unlocks", failures.count)),
+    );
+    tray::refresh_tray(app);
+    let _ = app.emit(VAULT_WIPED_EVENT, ());
+    LockoutError::Wiped.into()
+}
+
+/// Rebuild a vault wiped after failed unlocks from the sync server
+///
+/// The password is checked against the server's items before anything is
+/// written, and wrong passwords count towards the unlock delay.
+#[tauri::command]
+pub async fn restore_from_sync(
+    password: String,
+    key_file: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<SyncSummary> {
+    let config = sync_state.get_config().ok_or(SyncError::NotEnabled)?;
+    let (salt, keys) = run_blocking(move || {
+        let storage = Storage::open()?;
+        let salt = storage.wiped_salt()?.ok_or(CommandError {
+            message: "There is no wiped vault to restore".to_string(),
         })?;
-    let vault = Vault::import(&encrypted, &keys.vault_key)?;
+        if storage.vault_exists()? {
+            return Err(CommandError {
+                message: "Vault already exists".to_string(),
+            });
+        }
+        lockout::check(&storage, now_secs() as i64)?;
+
+        let master_key = derive_master_key(&password, &Salt::from_bytes(salt))?;
+        let master_key = keyfile::apply(&storage, master_key, key_file.as_deref().map(Path::new))?;
+        Ok((salt, derive_keys(&master_key)?))
+    })
+    .await?;
+
+    // Any item the server holds proves the password
+    let pulled = SyncClient::new(config).pull(0).await?;
+    if let Some(item) = pulled.items.iter().find(|item| !item.is_deleted) {
+        let opens = EncryptedBlob::from_base64(&item.encrypted_data)
+            .and_then(|blob| VaultItem::import(&blob, &keys.vault_key))
+            .is_ok();
+        if !opens {
+            lockout::record_failure(&Storage::open()?, now_secs() as i64)?;
+            activity::try_record(ActivityKind::FailedUnlock, Some("restore from sync"));
+            return Err(CommandError {
+                message: "Incorrect master password".to_string(),
+            });
+        }
+    }
+
+    let vault = Vault::new();
+    let storage = Storage::open()?;
+    storage.create_vault(&salt)?;
+    vault_store::save(&storage, &vault, &keys.vault_key)?;
+    storage.clear_wiped_salt()?;
+    lockout::reset(&storage)?;
+    activity::try_record(ActivityKind::Unlock, Some("restored from sync"));
+
+    *state.vault.lock().unwrap() = Some(vault);
+    *state.keys.lock().unwrap() = Some(keys);
+    *state.salt.lock().unwrap() = Some(salt);
+    state.session.record_activity();
+
+    // A failed first sync shows in the sync status and is retried on schedule
+    let summary = sync::run_sync(&state, &sync_state)
+        .await
+        .unwrap_or_default();
+    tray::refresh_tray(&app);
+    capture::refresh(&app);
+    app.state::<IconState>().request_refresh();
+    Ok(summary)
+}
+
+/// Emitted after unlocking fell back to the save before a damaged one
+pub const VAULT_RECOVERED_EVENT: &str = "vault-recovered";
+
+/// Decrypt the stored vault, or the decoy, with a master key and make it the unlocked vault
+///
+/// Returns whether the latest save was damaged and the one before it was
+/// opened instead; that copy is then written back as the current vault.
+fn open_vault(
+    storage: &Storage,
+    master_key: &MasterKey,
+    state: &AppState,
+    decoy: bool,
+) -> CommandResult<bool> {
+    // Load salt and encrypted vault
+    let mut recovered = false;
+    let (salt_bytes, encrypted_bytes) = if decoy {
+        let salt = storage
+            .get_decoy_salt()?
+            .ok_or(StorageError::VaultNotFound)?;
+        (salt, storage.load_decoy()?)
+    } else {
+        let encrypted_bytes = match storage.load_vault() {
+            Err(StorageError::Corrupted) => {
+                recovered = true;
+                storage.load_previous_vault()?
+            }
+            loaded => loaded?,
+        };
+        (storage.get_salt()?, encrypted_bytes)
+    };
+
+    // Derive keys
+    let keys = derive_keys(master_key)?;
+
+    // Decrypt vault; the decoy is small and still kept as a single blob
+    let vault = if decoy {
+        let encrypted: EncryptedBlob =
+            serde_json::from_slice(&encrypted_bytes).map_err(|e| CommandError {
+                message: e.to_string(),
+            })?;
+        Vault::import(&encrypted, &keys.vault_key)?
+    } else {
+        vault_store::load(storage, &encrypted_bytes, &keys.vault_key)?
+    };
+    if recovered {
+        storage.save_vault(&encrypted_bytes)?;
+    }
+    // Items deleted while syncing leave their attachments behind
+    if !decoy {
+        if let Err(e) = attachments::prune(storage, &vault) {
+            tracing::warn!("Removing orphaned attachments failed: {}", e);
+        }
+    }
 
     // Update state
     *state.vault.lock().unwrap() = Some(vault);
     *state.keys.lock().unwrap() = Some(keys);
     *state.salt.lock().unwrap() = Some(salt_bytes);
-    state.touch();
+    *state.decoy.lock().unwrap() = decoy;
+    state.session.record_activity();
+
+    Ok(recovered)
+}
 
+/// Replace a damaged vault with a backup and unlock it
+///
+/// Only allowed when no intact copy of the vault is left. The backup is
+/// re-encrypted under `password` and the vault's existing salt and key file.
+#[tauri::command]
+pub async fn recover_vault(
+    path: String,
+    passphrase: String,
+    password: String,
+    key_file: Option<String>,
+    app: AppHandle,
+) -> CommandResult<()> {
+    run_blocking(move || {
+        let storage = Storage::open()?;
+        if !storage.vault_damaged()? {
+            return Err(CommandError {
+                message: "Vault is not damaged".to_string(),
+            });
+        }
+
+        let backup = backup::read_backup(Path::new(&path), &passphrase)?;
+        let master_key = derive_master_key(&password, &Salt::from_bytes(storage.get_salt()?))?;
+        let master_key = keyfile::apply(&storage, master_key, key_file.as_deref().map(Path::new))?;
+        let vault_key = derive_keys(&master_key)?.vault_key;
+        storage.delete_damaged_items()?;
+        vault_store::save(&storage, &backup.vault, &vault_key)?;
+        attachments::restore(&storage, &vault_key, &backup.vault, &backup.attachments)?;
+
+        open_vault(&storage, &master_key, &app.state::<AppState>(), false)?;
+        tray::refresh_tray(&app);
+        capture::refresh(&app);
+        app.state::<IconState>().request_refresh();
+        Ok(())
+    })
+    .await
+}
+
+// =============================================================================
+// Biometric Unlock
+// =============================================================================
+
+#[derive(Serialize)]
+pub struct BiometricStatus {
+    pub available: bool,
+    pub enrolled: bool,
+}
+
+#[tauri::command]
+pub fn get_biometric_status() -> CommandResult<BiometricStatus> {
+    let storage = Storage::open()?;
+    Ok(BiometricStatus {
+        available: biometric::is_available(),
+        enrolled: biometric::is_enrolled(&storage)?,
+    })
+}
+
+/// Enroll biometric unlock; the master password is checked against the vault first
+#[tauri::command]
+pub async fn enable_biometric_unlock(password: String) -> CommandResult<()> {
+    run_blocking(move || {
+        biometric::verify_user("enable biometric unlock for Keydrop")?;
+
+        let storage = Storage::open()?;
+        let master_key = derive_master_key(&password, &Salt::from_bytes(storage.get_salt()?))?;
+        let master_key = keyfile::apply(&storage, master_key, None)?;
+        let encrypted: EncryptedBlob =
+            serde_json::from_slice(&storage.load_vault()?).map_err(|e| CommandError {
+                message: e.to_string(),
+            })?;
+        Vault::import(&encrypted, &derive_keys(&master_key)?.vault_key)?;
+
+        biometric::enroll(&storage, &master_key)?;
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn disable_biometric_unlock() -> CommandResult<()> {
+    let storage = Storage::open()?;
+    biometric::unenroll(&storage)?;
+    Ok(())
+}
+
+/// Unlock with Touch ID or Windows Hello instead of the master password
+#[tauri::command]
+pub async fn unlock_with_biometrics(app: AppHandle) -> CommandResult<()> {
+    run_blocking(move || {
+        let storage = Storage::open()?;
+        if !biometric::is_enrolled(&storage)? {
+            return Err(BiometricError::NotEnrolled.into());
+        }
+
+        biometric::verify_user("unlock your Keydrop vault")?;
+        let master_key = biometric::unwrap_master_key(&storage)?;
+        let recovered = open_vault(&storage, &master_key, &app.state::<AppState>(), false)?;
+        activity::try_record(ActivityKind::Unlock, Some("biometrics"));
+        if recovered {
+            let _ = app.emit(VAULT_RECOVERED_EVENT, ());
+        }
+        tray::refresh_tray(&app);
+        capture::refresh(&app);
+        app.state::<IconState>().request_refresh();
+        Ok(())
+    })
+    .await
+}
+
+/// Run blocking work (OS prompts, Argon2) off the main thread
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> CommandResult<T> + Send + 'static,
+) -> CommandResult<T> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| CommandError {
+            message: e.to_string(),
+        })?
+}
+
+// =============================================================================
+// Key File
+// =============================================================================
+
+/// Registered key file path, if the vault requires one
+#[tauri::command]
+pub fn get_key_file() -> CommandResult<Option<String>> {
+    let storage = Storage::open()?;
+    Ok(keyfile::registered_path(&storage)?.map(|path| path.to_string_lossy().into_owned()))
+}
+
+/// Write a new random key file at `path`
+#[tauri::command]
+pub fn generate_key_file(path: String) -> CommandResult<()> {
+    Ok(keyfile::generate(Path::new(&path))?)
+}
+
+/// Require the key file at `path` to unlock, or stop requiring one when `None`
+///
+/// The vault is re-encrypted under the new master key, so the master password
+/// is checked first.
+#[tauri::command]
+pub async fn set_key_file(
+    password: String,
+    path: Option<String>,
+    app: AppHandle,
+) -> CommandResult<()> {
+    run_blocking(move || {
+        // Other devices derive keys from the password alone
+        if path.is_some() && app.state::<SyncState>().is_enabled() {
+            return Err(CommandError {
+                message: "Turn off sync before adding a key file".to_string(),
+            });
+        }
+
+        let state = app.state::<AppState>();
+        ensure_primary(&state)?;
+        let storage = Storage::open()?;
+        let password_key = verify_master_password(&storage, &state, &password)?;
+
+        let master_key = match &path {
+            Some(path) => combine_key_file(&password_key, &keyfile::read(Path::new(path))?)?,
+            None => password_key,
+        };
+        rekey_vault(&storage, &state, &master_key)?;
+        match &path {
+            Some(path) => storage.set_setting(keyfile::KEY_FILE_SETTING, path)?,
+            None => storage.delete_setting(keyfile::KEY_FILE_SETTING)?,
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Check `password` against the unlocked vault, returning its key before any key file
+fn verify_master_password(
+    storage: &Storage,
+    state: &AppState,
+    password: &str,
+) -> CommandResult<MasterKey> {
+    let password_key = derive_master_key(password, &Salt::from_bytes(storage.get_salt()?))?;
+    let current_key = derive_keys(&keyfile::apply(storage, password_key.clone(), None)?)?;
+    let unlocked_key = state.keys.lock().unwrap().as_ref().map(|k| k.vault_key);
+    if unlocked_key != Some(current_key.vault_key) {
+        return Err(CommandError {
+            message: "Incorrect master password".to_string(),
+        });
+    }
+    Ok(password_key)
+}
+
+/// Refuse account-level changes while the decoy vault is open
+///
+/// The message doesn't mention the decoy, so it reads like any other failure.
+fn ensure_primary(state: &AppState) -> CommandResult<()> {
+    if state.is_decoy() {
+        return Err(CommandError {
+            message: "This setting can't be changed right now".to_string(),
+        });
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Duress Password
+// =============================================================================
+
+/// Whether a duress password is set; always false while the decoy is open
+#[tauri::command]
+pub fn get_duress_enabled(state: State<AppState>) -> CommandResult<bool> {
+    if state.is_decoy() {
+        return Ok(false);
+    }
+    Ok(Storage::open()?.get_decoy_salt()?.is_some())
+}
+
+/// Set a duress password that unlocks an empty decoy vault, or remove it with `None`
+///
+/// Setting a new duress password replaces the decoy with a fresh one.
+#[tauri::command]
+pub async fn set_duress_password(
+    password: String,
+    duress_password: Option<String>,
+    app: AppHandle,
+) -> CommandResult<()> {
+    run_blocking(move || {
+        let state = app.state::<AppState>();
+        ensure_primary(&state)?;
+        let storage = Storage::open()?;
+        verify_master_password(&storage, &state, &password)?;
+
+        let Some(duress_password) = duress_password else {
+            storage.delete_decoy()?;
+            return Ok(());
+        };
+        if duress_password == password {
+            return Err(CommandError {
+                message: "Duress password must differ from the master password".to_string(),
+            });
+        }
+        if duress_password.len() < 8 {
+            return Err(CommandError {
+                message: "Duress password must be at least 8 characters".to_string(),
+            });
+        }
+
+        let salt = Salt::generate()?;
+        let keys = derive_keys(&derive_master_key(&duress_password, &salt)?)?;
+        let encrypted = Vault::new().export(&keys.vault_key)?;
+        let encrypted_bytes = serde_json::to_vec(&encrypted).map_err(|e| CommandError {
+            message: e.to_string(),
+        })?;
+        storage.create_decoy(salt.as_bytes(), &encrypted_bytes)?;
+        Ok(())
+    })
+    .await
+}
+
+/// Re-encrypt the unlocked vault, and secrets kept under its keys, for a new master key
+fn rekey_vault(storage: &Storage, state: &AppState, master_key: &MasterKey) -> CommandResult<()> {
+    let keys = derive_keys(master_key)?;
+    let old_key = state
+        .keys
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|k| Zeroizing::new(k.vault_key))
+        .ok_or(CommandError {
+            message: "Vault is locked".to_string(),
+        })?;
+
+    {
+        let vault = state.vault.lock().unwrap();
+        let vault = vault.as_ref().ok_or(CommandError {
+            message: "Vault is locked".to_string(),
+        })?;
+        vault_store::save(storage, vault, &keys.vault_key)?;
+    }
+
+    backup::rekey_passphrase(storage, &old_key, &keys.vault_key)?;
+    generator::rekey_history(storage, &old_key, &keys.vault_key)?;
+    attachments::rekey(storage, &old_key, &keys.vault_key)?;
+    if biometric::is_enrolled(storage)? {
+        biometric::enroll(storage, master_key)?;
+    }
+    *state.keys.lock().unwrap() = Some(keys);
     Ok(())
 }
 
 #[tauri::command]
-pub fn lock_vault(state: State<AppState>) -> CommandResult<()> {
-    state.lock();
+pub fn lock_vault(app: AppHandle) -> CommandResult<()> {
+    auto_lock::lock_vault(&app, LockReason::Manual);
     Ok(())
 }
 
@@ -149,6 +794,12 @@ pub struct VaultItemDto {
     pub favorite: bool,
     pub created_at: u64,
     pub modified_at: u64,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub auto_type: Option<String>,
 }
 
 impl From<&VaultItem> for VaultItemDto {
@@ -164,6 +815,9 @@ impl From<&VaultItem> for VaultItemDto {
             favorite: item.favorite,
             created_at: item.created_at,
             modified_at: item.modified_at,
+            icon: item.icon.clone(),
+            color: item.color.clone(),
+            auto_type: item.auto_type.clone(),
         }
     }
 }
@@ -176,11 +830,24 @@ impl From<VaultItemDto> for VaultItem {
         item.notes = dto.notes;
         item.category = dto.category;
         item.favorite = dto.favorite;
+        item.icon = dto.icon;
+        item.color = dto.color;
+        item.auto_type = dto.auto_type;
         item
     }
 }
 
+/// Write the whole vault, for changes beyond individual items such as categories
 fn save_vault_to_storage(state: &State<AppState>) -> CommandResult<()> {
+    save_to_storage(state, None)
+}
+
+/// Write only the items with these ids, removing those no longer in the vault
+fn save_items_to_storage(state: &State<AppState>, ids: &[&str]) -> CommandResult<()> {
+    save_to_storage(state, Some(ids))
+}
+
+fn save_to_storage(state: &State<AppState>, ids: Option<&[&str]>) -> CommandResult<()> {
     let vault = state.vault.lock().unwrap();
     let keys = state.keys.lock().unwrap();
 
@@ -191,20 +858,54 @@ fn save_vault_to_storage(state: &State<AppState>) -> CommandResult<()> {
         message: "Keys not available".to_string(),
     })?;
 
-    let encrypted = vault.export(&keys.vault_key)?;
-    let encrypted_bytes = serde_json::to_vec(&encrypted).map_err(|e| CommandError {
-        message: e.to_string(),
-    })?;
+    let storage = Storage::open()?;
+    if state.is_decoy() {
+        let encrypted = vault.export(&keys.vault_key)?;
+        let encrypted_bytes = serde_json::to_vec(&encrypted).map_err(|e| CommandError {
+            message: e.to_string(),
+        })?;
+        storage.save_decoy(&encrypted_bytes)?;
+        return Ok(());
+    }
+
+    match ids {
+        Some(ids) => {
+            vault_store::save_items(&storage, vault, &keys.vault_key, ids.iter().copied())?
+        }
+        None => vault_store::save(&storage, vault, &keys.vault_key)?,
+    }
+    Ok(())
+}
+
+/// Queue item changes for sync, wake the background scheduler, and count
+/// them toward the next automatic backup
+fn record_changes<'a>(
+    changes: impl IntoIterator<Item = (&'a str, ChangeOp)>,
+    app: &AppHandle,
+) -> CommandResult<()> {
+    app.state::<IconState>().request_refresh();
+
+    // Decoy edits never reach the sync server or backups
+    if app.state::<AppState>().is_decoy() {
+        return Ok(());
+    }
 
     let storage = Storage::open()?;
-    storage.save_vault(&encrypted_bytes)?;
+    let mut count = 0;
+    for (id, op) in changes {
+        storage.queue_change(id, op)?;
+        count += 1;
+    }
 
+    let sync_state = app.state::<SyncState>();
+    sync_state.set_pending_changes(storage.pending_change_count()?);
+    sync_state.request_sync();
+    app.state::<BackupState>().record_changes(count);
     Ok(())
 }
 
 #[tauri::command]
 pub fn get_all_items(state: State<AppState>) -> CommandResult<Vec<VaultItemDto>> {
-    state.touch();
     let vault = state.vault.lock().unwrap();
     let vault = vault.as_ref().ok_or(CommandError {
         message: "Vault is locked".to_string(),
@@ -215,7 +916,6 @@ pub fn get_all_items(state: State<AppState>) -> CommandResult<Vec<VaultItemDto>>
 
 #[tauri::command]
 pub fn get_item(id: String, state: State<AppState>) -> CommandResult<Option<VaultItemDto>> {
-    state.touch();
     let vault = state.vault.lock().unwrap();
     let vault = vault.as_ref().ok_or(CommandError {
         message: "Vault is locked".to_string(),
@@ -225,8 +925,11 @@ pub fn get_item(id: String, state: State<AppState>) -> CommandResult<Option<Vaul
 }
 
 #[tauri::command]
-pub fn add_item(item: VaultItemDto, state: State<AppState>) -> CommandResult<String> {
-    state.touch();
+pub fn add_item(
+    item: VaultItemDto,
+    app: AppHandle,
+    state: State<AppState>,
+) -> CommandResult<String> {
     let id = {
         let mut vault_guard = state.vault.lock().unwrap();
         let vault = vault_guard.as_mut().ok_or(CommandError {
@@ -237,13 +940,19 @@ pub fn add_item(item: VaultItemDto, state: State<AppState>) -> CommandResult<Str
         vault.add_item(vault_item)
     };
 
-    save_vault_to_storage(&state)?;
+    save_items_to_storage(&state, &[&id])?;
+    record_changes([(id.as_str(), ChangeOp::Add)], &app)?;
+    tray::refresh_tray(&app);
     Ok(id)
 }
 
 #[tauri::command]
-pub fn update_item(id: String, item: VaultItemDto, state: State<AppState>) -> CommandResult<()> {
-    state.touch();
+pub fn update_item(
+    id: String,
+    item: VaultItemDto,
+    app: AppHandle,
+    state: State<AppState>,
+) -> CommandResult<()> {
     {
         let mut vault_guard = state.vault.lock().unwrap();
         let vault = vault_guard.as_mut().ok_or(CommandError {
@@ -254,40 +963,86 @@ pub fn update_item(id: String, item: VaultItemDto, state: State<AppState>) -> Co
         vault.update_item(&id, vault_item)?;
     }
 
-    save_vault_to_storage(&state)?;
+    save_items_to_storage(&state, &[&id])?;
+    record_changes([(id.as_str(), ChangeOp::Update)], &app)?;
+    tray::refresh_tray(&app);
     Ok(())
 }
 
 #[tauri::command]
-pub fn delete_item(id: String, state: State<AppState>) -> CommandResult<()> {
-    state.touch();
-    {
+pub fn delete_item(id: String, app: AppHandle, state: State<AppState>) -> CommandResult<()> {
+    let removed = {
         let mut vault_guard = state.vault.lock().unwrap();
         let vault = vault_guard.as_mut().ok_or(CommandError {
             message: "Vault is locked".to_string(),
         })?;
 
-        vault.remove_item(&id)?;
-    }
+        vault.remove_item(&id)?
+    };
 
-    save_vault_to_storage(&state)?;
+    save_items_to_storage(&state, &[&id])?;
+    if !state.is_decoy() {
+        attachments::delete_for_item(&Storage::open()?, &id)?;
+    }
+    record_changes([(id.as_str(), ChangeOp::Delete)], &app)?;
+    activity::try_record(ActivityKind::ItemDeleted, Some(&removed.name));
+    tray::refresh_tray(&app);
     Ok(())
 }
 
+/// One page of `search_items` results
+#[derive(Serialize)]
+pub struct ItemPageDto {
+    pub items: Vec<VaultItemDto>,
+    /// Items matching the filter across all pages
+    pub total: usize,
+}
+
+/// Items matching a query and filter, sorted and paged
+///
+/// A non-empty `query` overrides the filter's own. `limit` defaults to
+/// [`DEFAULT_PAGE_SIZE`].
 #[tauri::command]
-pub fn search_items(query: String, state: State<AppState>) -> CommandResult<Vec<VaultItemDto>> {
-    state.touch();
+pub fn search_items(
+    query: Option<String>,
+    filter: Option<ItemFilter>,
+    sort: Option<SearchSort>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> CommandResult<ItemPageDto> {
+    let mut filter = filter.unwrap_or_default();
+    if let Some(query) = query.filter(|q| !q.trim().is_empty()) {
+        filter.query = Some(query);
+    }
+    let sort = sort.unwrap_or_default();
+    let usage = if state.is_decoy() {
+        Default::default()
+    } else {
+        search::usage(sort)?
+    };
+
     let vault = state.vault.lock().unwrap();
     let vault = vault.as_ref().ok_or(CommandError {
         message: "Vault is locked".to_string(),
     })?;
 
-    Ok(vault.search(&query).iter().map(|i| (*i).into()).collect())
+    let page = search::search(
+        vault,
+        &filter,
+        sort,
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_PAGE_SIZE),
+        &usage,
+    );
+    Ok(ItemPageDto {
+        items: page.items.into_iter().map(VaultItemDto::from).collect(),
+        total: page.total,
+    })
 }
 
 #[tauri::command]
 pub fn get_favorites(state: State<AppState>) -> CommandResult<Vec<VaultItemDto>> {
-    state.touch();
     let vault = state.vault.lock().unwrap();
     let vault = vault.as_ref().ok_or(CommandError {
         message: "Vault is locked".to_string(),
@@ -296,11 +1051,337 @@ pub fn get_favorites(state: State<AppState>) -> CommandResult<Vec<VaultItemDto>>
     Ok(vault.get_favorites().iter().map(|i| (*i).into()).collect())
 }
 
-// =============================================================================
-// Password Generation Commands
-// =============================================================================
-
-#[derive(Deserialize)]
+/// Most recently modified items, as listed in the tray's quick-copy menu
+#[tauri::command]
+pub fn get_recent_items(limit: usize, state: State<AppState>) -> CommandResult<Vec<VaultItemDto>> {
+    let vault = state.vault.lock().unwrap();
+    let vault = vault.as_ref().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+
+    Ok(tray::recent_items(vault, limit)
+        .into_iter()
+        .map(VaultItemDto::from)
+        .collect())
+}
+
+/// Copy an item field to the clipboard, returning seconds until it is cleared (0 = never)
+#[tauri::command]
+pub fn copy_secret(
+    item_id: String,
+    field: String,
+    app: AppHandle,
+    state: State<AppState>,
+) -> CommandResult<u64> {
+    let vault = state.vault.lock().unwrap();
+    let vault = vault.as_ref().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+    let item = vault
+        .get_item(&item_id)
+        .ok_or_else(|| crypto_core::error::CryptoError::ItemNotFound(item_id.clone()))?;
+    let value = clipboard::item_field(item, &field).ok_or_else(|| CommandError {
+        message: format!("Item has no {} to copy", field),
+    })?;
+
+    let clear_after = clipboard::copy_secret(&app, value).map_err(|e| CommandError {
+        message: e.to_string(),
+    })?;
+    search::mark_used(&state, &item_id);
+    Ok(clear_after)
+}
+
+fn item_totp(state: &State<AppState>, item_id: &str) -> CommandResult<TotpCode> {
+    let vault = state.vault.lock().unwrap();
+    let vault = vault.as_ref().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+    let item = vault
+        .get_item(item_id)
+        .ok_or_else(|| crypto_core::error::CryptoError::ItemNotFound(item_id.to_string()))?;
+
+    clipboard::item_totp(item, now_secs())?.ok_or_else(|| CommandError {
+        message: "Item has no TOTP secret".to_string(),
+    })
+}
+
+/// Current TOTP code for an item and the seconds until it changes
+#[tauri::command]
+pub fn get_totp(item_id: String, state: State<AppState>) -> CommandResult<TotpCode> {
+    item_totp(&state, &item_id)
+}
+
+/// Copy an item's current TOTP code, returning seconds until it is cleared (0 = never)
+#[tauri::command]
+pub fn copy_totp(item_id: String, app: AppHandle, state: State<AppState>) -> CommandResult<u64> {
+    let code = item_totp(&state, &item_id)?;
+
+    let clear_after = clipboard::copy_secret(&app, &code.code).map_err(|e| CommandError {
+        message: e.to_string(),
+    })?;
+    search::mark_used(&state, &item_id);
+    Ok(clear_after)
+}
+
+/// Type an item's credentials into the window that gets focus when Keydrop steps aside
+///
+/// Uses `sequence`, else the item's own sequence, else username-Tab-password-Enter.
+/// Nothing is typed until the caller passes back the `target_title` from an
+/// earlier call as `confirmed_title`, after the user approved that window.
+#[tauri::command]
+pub async fn auto_type(
+    item_id: String,
+    sequence: Option<String>,
+    confirmed_title: Option<String>,
+    app: AppHandle,
+) -> CommandResult<AutoTypeResult> {
+    let actions = {
+        let state = app.state::<AppState>();
+        let vault = state.vault.lock().unwrap();
+        let vault = vault.as_ref().ok_or(CommandError {
+            message: "Vault is locked".to_string(),
+        })?;
+        let item = vault
+            .get_item(&item_id)
+            .ok_or_else(|| crypto_core::error::CryptoError::ItemNotFound(item_id.clone()))?;
+        autotype::resolve(item, sequence.as_deref())?
+    };
+
+    let result = autotype::auto_type(&app, actions, confirmed_title.as_deref()).await?;
+    if result.typed {
+        search::mark_used(&app.state::<AppState>(), &item_id);
+    }
+    Ok(result)
+}
+
+// =============================================================================
+// Import Commands
+// =============================================================================
+
+/// Parse an import file and report what would be added, without changing the vault
+#[tauri::command]
+pub fn preview_import(
+    path: String,
+    format: ImportFormat,
+    options: Option<ImportOptions>,
+    state: State<AppState>,
+) -> CommandResult<ImportPreview> {
+    let options = options.unwrap_or_default();
+    let report = import::parse_file(path.as_ref(), format, options.mapping.as_ref())?;
+
+    let vault = state.vault.lock().unwrap();
+    let vault = vault.as_ref().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+    Ok(import::preview(vault, report))
+}
+
+/// Import the entries kept in the review step
+#[tauri::command]
+pub fn run_import(
+    path: String,
+    format: ImportFormat,
+    options: ImportOptions,
+    app: AppHandle,
+    state: State<AppState>,
+) -> CommandResult<ImportSummary> {
+    let report = import::parse_file(path.as_ref(), format, options.mapping.as_ref())?;
+
+    let summary = {
+        let mut vault_guard = state.vault.lock().unwrap();
+        let vault = vault_guard.as_mut().ok_or(CommandError {
+            message: "Vault is locked".to_string(),
+        })?;
+        import::apply(vault, report, &options)
+    };
+    if summary.imported == 0 {
+        return Ok(summary);
+    }
+
+    save_vault_to_storage(&state)?;
+    record_changes(
+        summary.ids.iter().map(|id| (id.as_str(), ChangeOp::Add)),
+        &app,
+    )?;
+    tray::refresh_tray(&app);
+    Ok(summary)
+}
+
+// =============================================================================
+// Backup Commands
+// =============================================================================
+
+/// Write an encrypted backup of the vault and its attachments, protected by
+/// its own passphrase
+#[tauri::command]
+pub async fn export_backup(
+    path: String,
+    passphrase: String,
+    state: State<'_, AppState>,
+) -> CommandResult<()> {
+    let vault = state.vault.lock().unwrap().clone().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+    let vault_key = attachments_key(&state)?;
+
+    run_blocking(move || {
+        let attachments = match vault_key {
+            Some(key) => attachments::for_backup(&Storage::open()?, &key)?,
+            None => Vec::new(),
+        };
+        backup::write_backup(&vault, &attachments, path.as_ref(), &passphrase)?;
+        activity::try_record(ActivityKind::Export, Some(&path));
+        Ok(())
+    })
+    .await
+}
+
+/// Merge a backup into the current vault, resolving differing items by `merge_policy`
+///
+/// The backup's attachments are added to items that kept their IDs, unless
+/// they're already there.
+#[tauri::command]
+pub async fn restore_backup(
+    path: String,
+    passphrase: String,
+    merge_policy: MergePolicy,
+    app: AppHandle,
+) -> CommandResult<MergeReport> {
+    run_blocking(move || {
+        let restored = backup::read_backup(path.as_ref(), &passphrase)?;
+        let state = app.state::<AppState>();
+        let vault_key = attachments_key(&state)?;
+
+        let (report, merged) = {
+            let mut vault_guard = state.vault.lock().unwrap();
+            let vault = vault_guard.as_mut().ok_or(CommandError {
+                message: "Vault is locked".to_string(),
+            })?;
+            let report = vault.merge(&restored.vault, merge_policy);
+            (report, vault.clone())
+        };
+        if let Some(key) = vault_key {
+            attachments::restore(&Storage::open()?, &key, &merged, &restored.attachments)?;
+        }
+        if report.added.is_empty() && report.updated.is_empty() && report.duplicated.is_empty() {
+            return Ok(report);
+        }
+
+        save_vault_to_storage(&state)?;
+        let added = report.added.iter().chain(&report.duplicated);
+        record_changes(
+            added.map(|id| (id.as_str(), ChangeOp::Add)).chain(
+                report
+                    .updated
+                    .iter()
+                    .map(|id| (id.as_str(), ChangeOp::Update)),
+            ),
+            &app,
+        )?;
+        tray::refresh_tray(&app);
+        Ok(report)
+    })
+    .await
+}
+
+// =============================================================================
+// Attachment Commands
+// =============================================================================
+
+/// The vault key attachments are stored under, or `None` for the decoy vault,
+/// which has none
+fn attachments_key(state: &AppState) -> CommandResult<Option<Zeroizing<[u8; 32]>>> {
+    let key = state
+        .keys
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|keys| Zeroizing::new(keys.vault_key))
+        .ok_or(CommandError {
+            message: "Vault is locked".to_string(),
+        })?;
+    Ok((!state.is_decoy()).then_some(key))
+}
+
+fn require_attachments_key(state: &AppState) -> CommandResult<Zeroizing<[u8; 32]>> {
+    attachments_key(state)?.ok_or(CommandError {
+        message: "Attachments aren't available for this vault".to_string(),
+    })
+}
+
+/// Attach the file at `path` to an item
+#[tauri::command]
+pub async fn add_attachment(
+    item_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> CommandResult<Attachment> {
+    let vault_key = require_attachments_key(&state)?;
+    let vault = state.vault.lock().unwrap().clone().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+
+    run_blocking(move || {
+        let storage = Storage::open()?;
+        let attachment = attachments::add(
+            &storage,
+            &vault_key,
+            &vault,
+            &item_id,
+            Path::new(&path),
+            now_secs() as i64,
+        )?;
+        Ok(attachment)
+    })
+    .await
+}
+
+/// Attachments of an item, or of every item when `item_id` is unset
+#[tauri::command]
+pub fn list_attachments(
+    item_id: Option<String>,
+    state: State<AppState>,
+) -> CommandResult<Vec<Attachment>> {
+    let Some(vault_key) = attachments_key(&state)? else {
+        return Ok(Vec::new());
+    };
+    let storage = Storage::open()?;
+    Ok(attachments::list(&storage, &vault_key, item_id.as_deref())?)
+}
+
+/// Decrypt an attachment to a temporary file and open it in its default app
+///
+/// The file is removed after [`attachments::TEMP_FILE_LIFETIME`], or when
+/// the vault locks.
+#[tauri::command]
+pub async fn open_attachment(id: String, state: State<'_, AppState>) -> CommandResult<()> {
+    let vault_key = require_attachments_key(&state)?;
+
+    run_blocking(move || {
+        let path = attachments::open_temp(&Storage::open()?, &vault_key, &id)?;
+        if let Some(dir) = path.parent() {
+            attachments::remove_later(dir.to_path_buf());
+        }
+        tauri_plugin_opener::open_path(&path, None::<&str>).map_err(|e| CommandError {
+            message: format!("Opening the attachment failed: {}", e),
+        })?;
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn delete_attachment(id: String, state: State<AppState>) -> CommandResult<()> {
+    require_attachments_key(&state)?;
+    attachments::delete(&Storage::open()?, &id)?;
+    Ok(())
+}
+
+// =============================================================================
+// Password Generation Commands
+// =============================================================================
+
+#[derive(Deserialize)]
 pub struct PasswordOptionsDto {
     pub length: Option<usize>,
     pub lowercase: Option<bool>,
@@ -311,16 +1392,23 @@ pub struct PasswordOptionsDto {
     pub exclude_chars: Option<String>,
 }
 
+/// Generate a password; options left out come from the generator defaults in settings
 #[tauri::command]
-pub fn generate_password_cmd(options: PasswordOptionsDto) -> CommandResult<String> {
+pub fn generate_password_cmd(
+    options: PasswordOptionsDto,
+    settings_state: State<SettingsState>,
+) -> CommandResult<String> {
+    let defaults = settings_state.get().generator.password;
     let opts = PasswordOptions {
-        length: options.length.unwrap_or(16),
-        lowercase: options.lowercase.unwrap_or(true),
-        uppercase: options.uppercase.unwrap_or(true),
-        digits: options.digits.unwrap_or(true),
-        symbols: options.symbols.unwrap_or(true),
-        exclude_ambiguous: options.exclude_ambiguous.unwrap_or(false),
-        exclude_chars: options.exclude_chars.unwrap_or_default(),
+        length: options.length.unwrap_or(defaults.length),
+        lowercase: options.lowercase.unwrap_or(defaults.lowercase),
+        uppercase: options.uppercase.unwrap_or(defaults.uppercase),
+        digits: options.digits.unwrap_or(defaults.digits),
+        symbols: options.symbols.unwrap_or(defaults.symbols),
+        exclude_ambiguous: options
+            .exclude_ambiguous
+            .unwrap_or(defaults.exclude_ambiguous),
+        exclude_chars: options.exclude_chars.unwrap_or(defaults.exclude_chars),
     };
 
     generate_password(&opts).map_err(|e| e.into())
@@ -331,32 +1419,531 @@ pub fn generate_passphrase_cmd(word_count: usize, separator: String) -> CommandR
     generate_passphrase(word_count, &separator).map_err(|e| e.into())
 }
 
+/// Options the generator should open with: the last used, else the defaults
+#[tauri::command]
+pub fn get_generator_options(
+    settings_state: State<SettingsState>,
+) -> CommandResult<GeneratorOptions> {
+    Ok(settings_state.get().generator.initial())
+}
+
+/// Generate a password or passphrase and remember the options for next time
+#[tauri::command]
+pub fn generate_with_options(
+    options: GeneratorOptions,
+    app: AppHandle,
+    settings_state: State<SettingsState>,
+) -> CommandResult<String> {
+    let generated = options.generate()?;
+    let last_used = settings_state.get().generator.last_used;
+    if !last_used.is_some_and(|last| last.same_as(&options)) {
+        settings::update(&app, |settings| {
+            settings.generator.last_used = Some(options)
+        })?;
+    }
+    Ok(generated)
+}
+
+#[tauri::command]
+pub fn list_generator_presets(
+    settings_state: State<SettingsState>,
+) -> CommandResult<Vec<GeneratorPreset>> {
+    Ok(settings_state.get().generator.presets)
+}
+
+/// Save a preset, replacing any with the same name
+#[tauri::command]
+pub fn save_generator_preset(
+    preset: GeneratorPreset,
+    app: AppHandle,
+) -> CommandResult<Vec<GeneratorPreset>> {
+    let settings = settings::update(&app, |settings| {
+        generator::upsert_preset(&mut settings.generator.presets, preset)
+    })?;
+    Ok(settings.generator.presets)
+}
+
+#[tauri::command]
+pub fn delete_generator_preset(
+    name: String,
+    app: AppHandle,
+) -> CommandResult<Vec<GeneratorPreset>> {
+    let settings = settings::update(&app, |settings| {
+        settings
+            .generator
+            .presets
+            .retain(|preset| preset.name != name)
+    })?;
+    Ok(settings.generator.presets)
+}
+
+/// Keep a generated password in the history once it leaves the generator
+#[tauri::command]
+pub fn record_generated_password(
+    password: String,
+    site: Option<String>,
+    state: State<AppState>,
+) -> CommandResult<()> {
+    let keys = state.keys.lock().unwrap();
+    let keys = keys.as_ref().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+    // The history belongs to the real vault
+    if state.is_decoy() {
+        return Ok(());
+    }
+
+    let storage = Storage::open()?;
+    generator::record(&storage, &keys.vault_key, password, site, now_secs() as i64)?;
+    Ok(())
+}
+
+/// Recently generated passwords, newest first
+#[tauri::command]
+pub fn list_generated_history(state: State<AppState>) -> CommandResult<Vec<GeneratedPassword>> {
+    let keys = state.keys.lock().unwrap();
+    let keys = keys.as_ref().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+
+    let storage = Storage::open()?;
+    Ok(generator::history(&storage, &keys.vault_key)?)
+}
+
+/// Remove one history entry, or all of them when `id` is omitted
+#[tauri::command]
+pub fn purge_generated_history(id: Option<i64>, state: State<AppState>) -> CommandResult<()> {
+    if !state.is_unlocked() {
+        return Err(CommandError {
+            message: "Vault is locked".to_string(),
+        });
+    }
+    if state.is_decoy() {
+        return Ok(());
+    }
+
+    let storage = Storage::open()?;
+    match id {
+        Some(id) => storage.delete_generated(id)?,
+        None => storage.clear_generated()?,
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Security Dashboard Commands
+// =============================================================================
+
+/// Audit findings plus items found in known breaches by [`check_breaches`]
+#[derive(Serialize)]
+pub struct SecurityReport {
+    #[serde(flatten)]
+    pub audit: AuditReport,
+    /// Items whose current password appeared in a breach
+    pub breached: Vec<String>,
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[tauri::command]
+pub fn get_audit_report(
+    options: Option<AuditOptions>,
+    state: State<AppState>,
+    breach_state: State<BreachState>,
+) -> CommandResult<SecurityReport> {
+    let vault = state.vault.lock().unwrap();
+    let vault = vault.as_ref().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+
+    Ok(SecurityReport {
+        audit: audit_items(&vault.items, &options.unwrap_or_default(), now_secs()),
+        breached: breach_state
+            .breached(vault)
+            .into_iter()
+            .map(|item| item.item_id)
+            .collect(),
+    })
+}
+
+/// Look up item passwords in Pwned Passwords
+///
+/// Only the first characters of each password's SHA-1 hash are sent. Results
+/// are reused for a day unless the item changes.
+#[tauri::command]
+pub async fn check_breaches(
+    state: State<'_, AppState>,
+    breach_state: State<'_, BreachState>,
+) -> CommandResult<BreachReport> {
+    let vault = state.vault.lock().unwrap().clone().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+    Ok(breach_state.check(&vault, now_secs()).await?)
+}
+
 // =============================================================================
 // Settings Commands
 // =============================================================================
 
+#[tauri::command]
+pub fn get_settings(settings_state: State<SettingsState>) -> CommandResult<Settings> {
+    Ok(settings_state.get())
+}
+
+/// Change any subset of settings; `changes` is merged into the current settings
+///
+/// Automatic backup changes go through the same checks as [`set_auto_backup`].
+#[tauri::command]
+pub fn update_settings(
+    changes: serde_json::Value,
+    app: AppHandle,
+    state: State<AppState>,
+    settings_state: State<SettingsState>,
+) -> CommandResult<Settings> {
+    let current = settings_state.get();
+    let updated = current.merged(changes)?;
+    if updated.auto_backup != current.auto_backup {
+        ensure_primary(&state)?;
+        check_auto_backup(&updated.auto_backup)?;
+    }
+    Ok(settings::update(&app, |settings| *settings = updated)?)
+}
+
 #[tauri::command]
 pub fn get_auto_lock_timeout(state: State<AppState>) -> CommandResult<u64> {
     Ok(*state.auto_lock_timeout.lock().unwrap())
 }
 
 #[tauri::command]
-pub fn set_auto_lock_timeout(timeout: u64, state: State<AppState>) -> CommandResult<()> {
-    *state.auto_lock_timeout.lock().unwrap() = timeout;
+pub fn set_auto_lock_timeout(timeout: u64, app: AppHandle) -> CommandResult<()> {
+    settings::update(&app, |settings| settings.auto_lock_timeout = timeout)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_clipboard_timeout(clipboard_state: State<ClipboardState>) -> CommandResult<u64> {
+    Ok(clipboard_state.timeout())
+}
+
+#[tauri::command]
+pub fn set_clipboard_timeout(timeout: u64, app: AppHandle) -> CommandResult<()> {
+    settings::update(&app, |settings| settings.clipboard_timeout = timeout)?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct CaptureProtection {
+    pub enabled: bool,
+    /// False where the OS can't keep windows out of captures (Linux)
+    pub supported: bool,
+}
+
+#[tauri::command]
+pub fn get_capture_protection(settings_state: State<SettingsState>) -> CaptureProtection {
+    CaptureProtection {
+        enabled: settings_state.get().capture_protection,
+        supported: capture::SUPPORTED,
+    }
+}
+
+/// Keep windows out of screenshots, recordings and screen shares while unlocked
+#[tauri::command]
+pub fn set_capture_protection(enabled: bool, app: AppHandle) -> CommandResult<()> {
+    settings::update(&app, |settings| settings.capture_protection = enabled)?;
+    Ok(())
+}
+
+/// Cached favicon for a domain or URL as a `data:` URL, if one was downloaded
+#[tauri::command]
+pub fn get_icon(domain: String, state: State<AppState>) -> CommandResult<Option<String>> {
+    if !state.is_unlocked() {
+        return Err(CommandError {
+            message: "Vault is locked".to_string(),
+        });
+    }
+    let Some(domain) = favicons::icon_domain(&domain) else {
+        return Ok(None);
+    };
+
+    let storage = Storage::open()?;
+    Ok(storage.icon(&domain)?.and_then(|icon| {
+        Some(format!(
+            "data:{};base64,{}",
+            icon.content_type?,
+            STANDARD.encode(icon.data?)
+        ))
+    }))
+}
+
+#[tauri::command]
+pub fn get_icon_fetching() -> CommandResult<bool> {
+    let storage = Storage::open()?;
+    Ok(favicons::fetch_enabled(&storage)?)
+}
+
+/// Turn favicon downloads on or off; cached icons stay available either way
+#[tauri::command]
+pub fn set_icon_fetching(enabled: bool, icon_state: State<IconState>) -> CommandResult<()> {
     let storage = Storage::open()?;
-    storage.set_setting("auto_lock_timeout", &timeout.to_string())?;
+    storage.set_setting(ICON_FETCH_SETTING, &enabled.to_string())?;
+    if enabled {
+        icon_state.request_refresh();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_palette_shortcut() -> CommandResult<String> {
+    let storage = Storage::open()?;
+    Ok(storage
+        .get_setting(PALETTE_SHORTCUT_SETTING)?
+        .unwrap_or_else(|| DEFAULT_PALETTE_SHORTCUT.to_string()))
+}
+
+/// Change the quick-search shortcut; the old one stays active if the new one is rejected
+#[tauri::command]
+pub fn set_palette_shortcut(shortcut: String, app: AppHandle) -> CommandResult<()> {
+    let storage = Storage::open()?;
+    let previous = storage
+        .get_setting(PALETTE_SHORTCUT_SETTING)?
+        .unwrap_or_else(|| DEFAULT_PALETTE_SHORTCUT.to_string());
+
+    palette::register_shortcut(&app, &shortcut, Some(&previous)).map_err(|e| CommandError {
+        message: format!("Could not register shortcut {}: {}", shortcut, e),
+    })?;
+    storage.set_setting(PALETTE_SHORTCUT_SETTING, &shortcut)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn hide_palette(app: AppHandle) -> CommandResult<()> {
+    palette::hide_palette(&app);
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct AutoBackupSettings {
+    pub config: AutoBackupConfig,
+    pub status: AutoBackupStatus,
+    /// Directory backups go to, with the default filled in
+    pub directory: String,
+    pub has_passphrase: bool,
+}
+
+#[tauri::command]
+pub fn get_auto_backup(backup_state: State<BackupState>) -> CommandResult<AutoBackupSettings> {
+    let config = backup_state.config();
+    Ok(AutoBackupSettings {
+        directory: config.resolved_directory()?.to_string_lossy().into_owned(),
+        has_passphrase: backup::has_passphrase(&Storage::open()?)?,
+        status: backup_state.status(),
+        config,
+    })
+}
+
+/// Save automatic backup settings; `passphrase` replaces the stored one when given
+#[tauri::command]
+pub fn set_auto_backup(
+    config: AutoBackupConfig,
+    passphrase: Option<String>,
+    app: AppHandle,
+    state: State<AppState>,
+) -> CommandResult<()> {
+    ensure_primary(&state)?;
+    if let Some(passphrase) = passphrase {
+        let keys = state.keys.lock().unwrap();
+        let keys = keys.as_ref().ok_or(CommandError {
+            message: "Vault is locked".to_string(),
+        })?;
+        backup::save_passphrase(&Storage::open()?, &passphrase, &keys.vault_key)?;
+    }
+    check_auto_backup(&config)?;
+
+    settings::update(&app, |settings| settings.auto_backup = config)?;
+    Ok(())
+}
+
+/// Make sure enabled automatic backups have a passphrase and somewhere to go
+fn check_auto_backup(config: &AutoBackupConfig) -> CommandResult<()> {
+    if config.enabled {
+        if !backup::has_passphrase(&Storage::open()?)? {
+            return Err(BackupError::NoPassphrase.into());
+        }
+        std::fs::create_dir_all(config.resolved_directory()?).map_err(BackupError::from)?;
+    }
     Ok(())
 }
 
+/// Write an automatic backup immediately
+#[tauri::command]
+pub async fn run_backup_now(app: AppHandle) -> CommandResult<AutoBackupStatus> {
+    run_blocking(move || Ok(backup::run_auto_backup(&app)?)).await
+}
+
+// =============================================================================
+// Storage Maintenance
+// =============================================================================
+
+/// Space the local vault takes up, in bytes
+#[derive(Serialize)]
+pub struct StorageInfo {
+    /// Encrypted vault, including its previous copy
+    pub vault_size: u64,
+    /// Encrypted attachment contents
+    pub attachment_size: u64,
+    /// Automatic backups in the backup directory
+    pub backup_count: usize,
+    /// The whole database file
+    pub database_size: u64,
+    /// Part of the database file that compacting would give back
+    pub reclaimable_size: u64,
+}
+
 #[tauri::command]
-pub fn check_auto_lock(state: State<AppState>) -> CommandResult<bool> {
-    if state.is_unlocked() && state.should_auto_lock() {
-        state.lock();
+pub async fn get_storage_info(
+    state: State<'_, AppState>,
+    backup_state: State<'_, BackupState>,
+) -> CommandResult<StorageInfo> {
+    if state.vault.lock().unwrap().is_none() {
+        return Err(CommandError {
+            message: "Vault is locked".to_string(),
+        });
+    }
+    let decoy = state.is_decoy();
+    let directory = backup_state.config().resolved_directory()?;
+
+    run_blocking(move || {
+        let stats = Storage::open()?.stats()?;
+        // The decoy has no attachments or backups, and the real vault stays out of sight
+        Ok(if decoy {
+            StorageInfo {
+                vault_size: stats.decoy_size,
+                attachment_size: 0,
+                backup_count: 0,
+                database_size: stats.database_size,
+                reclaimable_size: stats.free_size,
+            }
+        } else {
+            StorageInfo {
+                vault_size: stats.vault_size,
+                attachment_size: stats.attachment_size,
+                backup_count: backup::backup_count(&directory)?,
+                database_size: stats.database_size,
+                reclaimable_size: stats.free_size,
+            }
+        })
+    })
+    .await
+}
+
+/// What [`compact_storage`] cleaned up
+#[derive(Serialize)]
+pub struct CompactReport {
+    /// Orphaned rows and attachments deleted
+    pub removed: usize,
+    /// Bytes the database file shrank by
+    pub freed: u64,
+}
+
+/// Delete orphaned rows and attachments, then vacuum the database
+#[tauri::command]
+pub async fn compact_storage(state: State<'_, AppState>) -> CommandResult<CompactReport> {
+    ensure_primary(&state)?;
+    let vault = state.vault.lock().unwrap().clone().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+
+    run_blocking(move || {
+        let storage = Storage::open()?;
+        let before = storage.stats()?.database_size;
+        let removed = attachments::prune(&storage, &vault)? + storage.remove_orphans()?;
+        storage.vacuum()?;
+        let freed = before.saturating_sub(storage.stats()?.database_size);
+        tracing::info!(
+            "Compacted local storage: {} rows removed, {} bytes freed",
+            removed,
+            freed
+        );
+        Ok(CompactReport { removed, freed })
+    })
+    .await
+}
+
+#[derive(Serialize)]
+pub struct BrowserIntegration {
+    pub enabled: bool,
+    pub extension_id: Option<String>,
+}
+
+#[tauri::command]
+pub fn get_browser_integration() -> CommandResult<BrowserIntegration> {
+    let storage = Storage::open()?;
+    let extension_id = storage.get_setting(browser_bridge::EXTENSION_ID_SETTING)?;
+    Ok(BrowserIntegration {
+        enabled: extension_id.is_some(),
+        extension_id,
+    })
+}
+
+/// Let the browser extension with `extension_id` read the vault through the native host
+#[tauri::command]
+pub fn set_browser_integration(
+    enabled: bool,
+    extension_id: Option<String>,
+    app: AppHandle,
+) -> CommandResult<()> {
+    let storage = Storage::open()?;
+    let io_error = |e: std::io::Error| CommandError {
+        message: format!("Could not register the native messaging host: {}", e),
+    };
+
+    if !enabled {
+        browser_bridge::stop(&app);
+        browser_bridge::uninstall_host_manifests().map_err(io_error)?;
+        storage.delete_setting(browser_bridge::EXTENSION_ID_SETTING)?;
+        return Ok(());
+    }
+
+    let extension_id = extension_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .ok_or(CommandError {
+            message: "Extension ID is required".to_string(),
+        })?;
+    browser_bridge::install_host_manifests(&extension_id).map_err(io_error)?;
+    storage.set_setting(browser_bridge::EXTENSION_ID_SETTING, &extension_id)?;
+    browser_bridge::start(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn check_auto_lock(app: AppHandle, state: State<AppState>) -> CommandResult<bool> {
+    let timeout = *state.auto_lock_timeout.lock().unwrap();
+    if state.is_unlocked() && state.session.should_lock(timeout) {
+        auto_lock::lock_vault(&app, LockReason::Idle);
         return Ok(true);
     }
     Ok(false)
 }
 
+/// Note that the user is interacting with a window, or asked to stay unlocked
+#[tauri::command]
+pub fn report_activity(state: State<AppState>) {
+    state.session.record_activity();
+}
+
+/// Time left until the vault locks for inactivity, for the "locking soon" warning
+#[tauri::command]
+pub fn get_lock_countdown(state: State<AppState>) -> LockCountdown {
+    let timeout = *state.auto_lock_timeout.lock().unwrap();
+    state.session.countdown(timeout, state.is_unlocked())
+}
+
 // =============================================================================
 // Sync Commands
 // =============================================================================
@@ -366,15 +1953,40 @@ pub fn get_sync_status(sync_state: State<SyncState>) -> CommandResult<SyncStatus
     Ok(sync_state.get_status())
 }
 
+#[tauri::command]
+pub fn is_sync_enabled(sync_state: State<SyncState>) -> CommandResult<bool> {
+    Ok(sync_state.is_enabled())
+}
+
 #[derive(Deserialize)]
 pub struct EnableSyncRequest {
     pub server_url: String,
     pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
     pub device_id: String,
 }
 
 #[tauri::command]
-pub fn enable_sync(request: EnableSyncRequest, sync_state: State<SyncState>) -> CommandResult<()> {
+pub fn enable_sync(
+    request: EnableSyncRequest,
+    state: State<AppState>,
+    sync_state: State<SyncState>,
+) -> CommandResult<()> {
+    ensure_primary(&state)?;
+    let storage = Storage::open()?;
+    if keyfile::registered_path(&storage)?.is_some() {
+        return Err(CommandError {
+            message: "Sync isn't available for vaults that use a key file".to_string(),
+        });
+    }
+    storage.save_sync_credentials(&SyncCredentials {
+        server_url: request.server_url.clone(),
+        access_token: request.access_token.clone(),
+        refresh_token: request.refresh_token,
+        device_id: request.device_id.clone(),
+    })?;
+
     sync_state.enable(request.server_url, request.access_token, request.device_id);
     Ok(())
 }
@@ -382,47 +1994,467 @@ pub fn enable_sync(request: EnableSyncRequest, sync_state: State<SyncState>) ->
 #[tauri::command]
 pub fn disable_sync(sync_state: State<SyncState>) -> CommandResult<()> {
     sync_state.disable();
+    let storage = Storage::open()?;
+    storage.clear_sync_credentials()?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn trigger_sync(sync_state: State<SyncState>) -> CommandResult<()> {
-    if !sync_state.is_enabled() {
+pub async fn trigger_sync(
+    app: AppHandle,
+    app_state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<SyncSummary> {
+    let summary = sync::run_sync(&app_state, &sync_state).await?;
+    if summary.pulled > 0 {
+        tray::refresh_tray(&app);
+    }
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn get_sync_interval(sync_state: State<SyncState>) -> CommandResult<u64> {
+    Ok(sync_state.interval())
+}
+
+#[tauri::command]
+pub fn set_sync_interval(interval: u64, app: AppHandle) -> CommandResult<()> {
+    settings::update(&app, |settings| settings.sync_interval = interval)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn check_remote_commands(app: AppHandle) -> CommandResult<Vec<RemoteCommand>> {
+    if !app.state::<SyncState>().is_enabled() {
+        return Ok(vec![]);
+    }
+
+    Ok(remote_commands::process_remote_commands(&app).await?)
+}
+
+// =============================================================================
+// Deep Links
+// =============================================================================
+
+/// The last `keydrop://` link opened, if the frontend hasn't taken it yet
+#[tauri::command]
+pub fn take_deep_link(deep_link_state: State<DeepLinkState>) -> Option<DeepLink> {
+    deep_link_state.take()
+}
+
+// =============================================================================
+// Activity Log
+// =============================================================================
+
+/// Security-relevant events on this machine, newest first
+#[tauri::command]
+pub fn get_activity_log(
+    filter: Option<ActivityFilter>,
+    state: State<AppState>,
+) -> CommandResult<Vec<ActivityEvent>> {
+    if !state.is_unlocked() {
         return Err(CommandError {
-            message: "Sync is not enabled".to_string(),
+            message: "Vault is locked".to_string(),
         });
     }
+    // The log belongs to the real vault
+    if state.is_decoy() {
+        return Ok(vec![]);
+    }
 
-    // Set syncing state
-    sync_state.set_syncing();
+    let storage = Storage::open()?;
+    Ok(activity::list(&storage, &filter.unwrap_or_default())?)
+}
 
-    // In a full implementation, this would:
-    // 1. Pull changes from server
-    // 2. Push local changes
-    // 3. Update sync status
+// =============================================================================
+// Diagnostics
+// =============================================================================
 
-    // For now, simulate completion
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    sync_state.set_idle(now);
+/// Log entries returned when the caller doesn't ask for a number
+const DEFAULT_LOG_LIMIT: usize = 200;
 
-    Ok(())
+/// Recent log lines for support requests, oldest first
+///
+/// Entries are redacted as they are recorded, so this is safe to share.
+#[tauri::command]
+pub fn get_recent_logs(limit: Option<usize>, logs: State<RecentLogs>) -> Vec<LogEntry> {
+    logs.recent(limit.unwrap_or(DEFAULT_LOG_LIMIT))
 }
 
+// =============================================================================
+// Updates
+// =============================================================================
+
+/// A newer signed release with its notes, or `None` if this is the latest
 #[tauri::command]
-pub fn check_remote_commands(sync_state: State<SyncState>) -> CommandResult<Vec<RemoteCommand>> {
-    if !sync_state.is_enabled() {
-        return Ok(vec![]);
+pub async fn check_for_updates(app: AppHandle) -> CommandResult<Option<UpdateInfo>> {
+    Ok(updater::check(&app).await?)
+}
+
+/// Install the release found by `check_for_updates` and restart into it
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> CommandResult<()> {
+    Ok(updater::install(&app).await?)
+}
+
+// =============================================================================
+// Emergency Access
+// =============================================================================
+
+/// Client for the signed-in account; emergency access is never shown in decoy mode
+fn emergency_client(state: &AppState, sync_state: &SyncState) -> CommandResult<SyncClient> {
+    if state.is_decoy() {
+        return Err(CommandError {
+            message: "Emergency access isn't available right now".to_string(),
+        });
+    }
+    let config = sync_state.get_config().ok_or(SyncError::NotEnabled)?;
+    Ok(SyncClient::new(config))
+}
+
+#[tauri::command]
+pub async fn add_emergency_contact(
+    email: String,
+    name: Option<String>,
+    waiting_period_hours: Option<i32>,
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<EmergencyContact> {
+    let client = emergency_client(&state, &sync_state)?;
+    let request = AddContactRequest {
+        email,
+        name,
+        waiting_period_hours,
+    };
+    Ok(client.add_emergency_contact(&request).await?)
+}
+
+/// Keys of the unlocked vault, for handing the vault key to and from contacts
+fn emergency_keys(state: &AppState) -> CommandResult<KeySet> {
+    state.keys.lock().unwrap().clone().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })
+}
+
+/// This user's contacts, sealing the vault key for any that just accepted
+#[tauri::command]
+pub async fn list_emergency_contacts(
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<Vec<EmergencyContact>> {
+    let client = emergency_client(&state, &sync_state)?;
+    let mut contacts = client.emergency_contacts().await?;
+    if let Ok(keys) = emergency_keys(&state) {
+        emergency::share_vault_keys(&client, &mut contacts, &keys.vault_key).await?;
     }
+    Ok(contacts)
+}
+
+#[tauri::command]
+pub async fn remove_emergency_contact(
+    contact_id: String,
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<()> {
+    let client = emergency_client(&state, &sync_state)?;
+    Ok(client.remove_emergency_contact(&contact_id).await?)
+}
 
-    // In a full implementation, this would:
-    // 1. Call the API to get pending commands
-    // 2. Return them for the frontend to handle
+/// Accept an invitation from the token in the invitation email
+#[tauri::command]
+pub async fn accept_emergency_invitation(
+    contact_id: String,
+    token: String,
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<()> {
+    let client = emergency_client(&state, &sync_state)?;
+    let public_key = emergency::contact_public_key(&emergency_keys(&state)?.sharing_key);
+    Ok(client
+        .accept_emergency_invitation(&contact_id, token.trim(), &public_key)
+        .await?)
+}
+
+#[tauri::command]
+pub async fn request_emergency_access(
+    contact_id: String,
+    reason: Option<String>,
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<AccessRequestStatus> {
+    let client = emergency_client(&state, &sync_state)?;
+    let request = AccessRequest {
+        emergency_contact_id: contact_id,
+        reason,
+    };
+    Ok(client.request_emergency_access(&request).await?)
+}
+
+/// Pending requests from this user's contacts
+#[tauri::command]
+pub async fn list_emergency_requests(
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<Vec<PendingAccessRequest>> {
+    let client = emergency_client(&state, &sync_state)?;
+    Ok(client.emergency_requests().await?)
+}
+
+#[tauri::command]
+pub async fn deny_emergency_request(
+    request_id: String,
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<()> {
+    let client = emergency_client(&state, &sync_state)?;
+    Ok(client.deny_emergency_request(&request_id).await?)
+}
+
+/// Vaults other users have granted this user access to
+#[tauri::command]
+pub async fn get_emergency_vault_access(
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<Vec<GrantedAccess>> {
+    let client = emergency_client(&state, &sync_state)?;
+    let keys = emergency_keys(&state).ok();
+    let granted = client.emergency_vault_access().await?;
+    Ok(granted
+        .into_iter()
+        .map(|granted| {
+            let mut grant = GrantedAccess::from(granted);
+            // Only a key this user can actually open counts
+            if let Some(keys) = &keys {
+                grant.key_available = grant.vault_key(&keys.sharing_key).is_ok();
+            }
+            grant
+        })
+        .collect())
+}
 
-    // For now, return empty
-    Ok(vec![])
+/// Open a vault another user granted access to in the read-only viewer
+///
+/// The owner's vault key comes sealed with the grant and is opened with this
+/// user's sharing key; the owner's items are then fetched and decrypted with
+/// it. Nothing opened here is saved, synced or backed up.
+#[tauri::command]
+pub async fn open_emergency_vault(
+    request_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<EmergencyVaultInfo> {
+    let keys = emergency_keys(&state)?;
+    let client = emergency_client(&state, &sync_state)?;
+    let grant = client
+        .emergency_vault_access()
+        .await?
+        .into_iter()
+        .map(GrantedAccess::from)
+        .find(|grant| grant.request_id == request_id)
+        .ok_or(CommandError {
+            message: "Emergency access to this vault hasn't been granted".to_string(),
+        })?;
+    let vault_key = grant.vault_key(&keys.sharing_key)?;
+
+    let mut vault = Vault::new();
+    let mut cursor = None;
+    loop {
+        let page = client.emergency_vault_items(&request_id, cursor).await?;
+        for remote in page.items.iter().filter(|remote| !remote.is_deleted) {
+            vault.upsert_item(vault_store::decrypt_item(
+                &remote.encrypted_data,
+                &vault_key,
+            )?);
+        }
+        match page.next_cursor.filter(|_| page.has_more) {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    let viewed = ViewedVault::new(grant, vault);
+    let info = viewed.info();
+    app.state::<EmergencyViewer>().open(viewed);
+    activity::try_record(ActivityKind::EmergencyView, Some(&info.owner_email));
+    Ok(info)
+}
+
+fn no_emergency_vault() -> CommandError {
+    CommandError {
+        message: "No emergency vault is open".to_string(),
+    }
+}
+
+/// The vault open in the emergency viewer, if any
+#[tauri::command]
+pub fn get_emergency_vault_info(viewer: State<EmergencyViewer>) -> Option<EmergencyVaultInfo> {
+    viewer.with(ViewedVault::info)
+}
+
+/// Items of the vault in the emergency viewer, optionally matching `query`
+#[tauri::command]
+pub fn list_emergency_vault_items(
+    query: Option<String>,
+    viewer: State<EmergencyViewer>,
+) -> CommandResult<Vec<VaultItemDto>> {
+    viewer
+        .with(|viewed| match query.as_deref().filter(|q| !q.is_empty()) {
+            Some(query) => viewed
+                .vault()
+                .search(query)
+                .into_iter()
+                .map(VaultItemDto::from)
+                .collect(),
+            None => viewed
+                .vault()
+                .items
+                .iter()
+                .map(VaultItemDto::from)
+                .collect(),
+        })
+        .ok_or_else(no_emergency_vault)
+}
+
+/// Copy a field of an item in the emergency viewer, cleared like any other secret
+#[tauri::command]
+pub fn copy_emergency_secret(
+    item_id: String,
+    field: String,
+    app: AppHandle,
+    viewer: State<EmergencyViewer>,
+) -> CommandResult<u64> {
+    viewer
+        .with(|viewed| {
+            let item = viewed
+                .vault()
+                .get_item(&item_id)
+                .ok_or_else(|| crypto_core::error::CryptoError::ItemNotFound(item_id.clone()))?;
+            let value = clipboard::item_field(item, &field).ok_or_else(|| CommandError {
+                message: format!("Item has no {} to copy", field),
+            })?;
+            clipboard::copy_secret(&app, value).map_err(|e| CommandError {
+                message: e.to_string(),
+            })
+        })
+        .ok_or_else(no_emergency_vault)?
+}
+
+/// Write the vault in the emergency viewer to a backup file, if the owner allows it
+#[tauri::command]
+pub async fn export_emergency_vault(
+    path: String,
+    passphrase: String,
+    viewer: State<'_, EmergencyViewer>,
+) -> CommandResult<()> {
+    let vault = viewer
+        .with(|viewed| viewed.export_allowed().then(|| viewed.vault().clone()))
+        .ok_or_else(no_emergency_vault)?
+        .ok_or(CommandError {
+            message: "The vault owner doesn't allow exporting this vault".to_string(),
+        })?;
+
+    run_blocking(move || {
+        backup::write_backup(&vault, &[], path.as_ref(), &passphrase)?;
+        activity::try_record(ActivityKind::Export, Some(&path));
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn close_emergency_vault(viewer: State<EmergencyViewer>) {
+    viewer.close();
+}
+
+#[tauri::command]
+pub async fn get_emergency_logs(
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncState>,
+) -> CommandResult<Vec<AccessLogEntry>> {
+    let client = emergency_client(&state, &sync_state)?;
+    Ok(client.emergency_logs().await?)
+}
+
+// =============================================================================
+// Sync Conflicts
+// =============================================================================
+
+/// Both versions of an item the server and this device changed independently
+#[derive(Serialize)]
+pub struct SyncConflictDto {
+    pub item_id: String,
+    /// This device's version; `None` if it was deleted here
+    pub local: Option<VaultItemDto>,
+    /// The server's version; `None` if it was deleted elsewhere
+    pub remote: Option<VaultItemDto>,
+    pub detected_at: i64,
+}
+
+#[tauri::command]
+pub fn list_conflicts(state: State<AppState>) -> CommandResult<Vec<SyncConflictDto>> {
+    if state.is_decoy() {
+        return Ok(Vec::new());
+    }
+    let key = sync_vault_key(&state)?;
+
+    Storage::open()?
+        .conflicts()?
+        .into_iter()
+        .map(|stored| {
+            let conflict = conflicts::decrypt(stored, &key)?;
+            Ok(SyncConflictDto {
+                item_id: conflict.item_id,
+                local: conflict.local.as_ref().map(VaultItemDto::from),
+                remote: conflict.remote.as_ref().map(VaultItemDto::from),
+                detected_at: conflict.detected_at,
+            })
+        })
+        .collect()
+}
+
+/// Settle a conflict by keeping the local version, the remote one, or both
+#[tauri::command]
+pub fn resolve_conflict(
+    id: String,
+    choice: ConflictChoice,
+    app: AppHandle,
+    state: State<AppState>,
+    sync_state: State<SyncState>,
+) -> CommandResult<()> {
+    let storage = Storage::open()?;
+    let stored = storage.conflict(&id)?.ok_or(CommandError {
+        message: "Conflict not found".to_string(),
+    })?;
+    let key = sync_vault_key(&state)?;
+    let conflict = conflicts::decrypt(stored, &key)?;
+
+    let changes = {
+        let mut vault_guard = state.vault.lock().unwrap();
+        let vault = vault_guard.as_mut().ok_or(CommandError {
+            message: "Vault is locked".to_string(),
+        })?;
+        conflicts::resolve(vault, conflict, choice)
+    };
+
+    // Taking the remote side changes the item locally without anything to push
+    let changed: Vec<&str> = std::iter::once(id.as_str())
+        .chain(changes.iter().map(|(id, _)| id.as_str()))
+        .collect();
+    save_items_to_storage(&state, &changed)?;
+    storage.delete_conflict(&id)?;
+    record_changes(changes.iter().map(|(id, op)| (id.as_str(), *op)), &app)?;
+    sync_state.set_conflicts(storage.conflict_count()?);
+    tray::refresh_tray(&app);
+    Ok(())
+}
+
+fn sync_vault_key(state: &AppState) -> CommandResult<Zeroizing<[u8; 32]>> {
+    let keys = state.keys.lock().unwrap();
+    let keys = keys.as_ref().ok_or(CommandError {
+        message: "Vault is locked".to_string(),
+    })?;
+    Ok(Zeroizing::new(keys.vault_key))
 }
 
 // =============================================================================
@@ -430,9 +2462,9 @@ pub fn check_remote_commands(sync_state: State<SyncState>) -> CommandResult<Vec<
 // =============================================================================
 
 #[tauri::command]
-pub fn wipe_vault(app_state: State<AppState>, sync_state: State<SyncState>) -> CommandResult<()> {
-    // Lock the vault first
-    app_state.lock();
+pub fn wipe_vault(app: AppHandle, sync_state: State<SyncState>) -> CommandResult<()> {
+    // Lock the vault first, clearing what every window holds
+    auto_lock::lock_vault(&app, LockReason::Manual);
 
     // Disable sync
     sync_state.disable();
@@ -440,6 +2472,7 @@ pub fn wipe_vault(app_state: State<AppState>, sync_state: State<SyncState>) -> C
     // Delete the vault file
     let storage = Storage::open()?;
     storage.delete_vault()?;
+    tray::refresh_tray(&app);
 
     Ok(())
 }
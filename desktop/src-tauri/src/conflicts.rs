@@ -0,0 +1,213 @@
+use crate::storage::{ChangeOp, Storage, StoredConflict};
+use crate::sync::SyncError;
+use crate::sync_client::SyncItem;
+use crypto_core::{
+    cipher::{EncryptedBlob, KEY_SIZE},
+    vault::{Vault, VaultItem},
+};
+use serde::Deserialize;
+
+/// Both sides of a sync conflict, decrypted for the user to compare
+///
+/// A missing side means that device or the server deleted the item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncConflict {
+    pub item_id: String,
+    pub local: Option<VaultItem>,
+    pub remote: Option<VaultItem>,
+    pub detected_at: i64,
+}
+
+/// Which version of a conflicted item to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictChoice {
+    Local,
+    Remote,
+    /// Take the remote version and keep the local one as a separate item
+    Both,
+}
+
+/// Store server versions that won over pushed items, returning how many
+/// actually differ from the vault
+///
+/// The vault keeps its local version until the user picks a side.
+pub fn record(
+    storage: &Storage,
+    vault: &Vault,
+    key: &[u8; KEY_SIZE],
+    remote_items: &[SyncItem],
+    now: i64,
+) -> Result<usize, SyncError> {
+    let mut recorded = 0;
+    for remote in remote_items {
+        let local = vault.get_item(&remote.id);
+        if local == decrypt_remote(remote, key)?.as_ref() {
+            continue;
+        }
+
+        storage.save_conflict(&StoredConflict {
+            item_id: remote.id.clone(),
+            local_data: local
+                .map(|item| item.export(key).map(|blob| blob.to_base64()))
+                .transpose()?,
+            remote_data: (!remote.is_deleted).then(|| remote.encrypted_data.clone()),
+            detected_at: now,
+        })?;
+        recorded += 1;
+    }
+    Ok(recorded)
+}
+
+/// Fold pulled changes to conflicted items into their conflicts
+///
+/// Returns the items that aren't in conflict, which can be applied as usual.
+pub fn absorb_pulled(storage: &Storage, items: &[SyncItem]) -> Result<Vec<SyncItem>, SyncError> {
+    let mut unaffected = Vec::with_capacity(items.len());
+    for item in items {
+        let remote_data = (!item.is_deleted).then_some(item.encrypted_data.as_str());
+        if !storage.update_conflict_remote(&item.id, remote_data)? {
+            unaffected.push(item.clone());
+        }
+    }
+    Ok(unaffected)
+}
+
+pub fn decrypt(stored: StoredConflict, key: &[u8; KEY_SIZE]) -> Result<SyncConflict, SyncError> {
+    let open = |data: Option<String>| -> Result<Option<VaultItem>, SyncError> {
+        data.map(|data| Ok(VaultItem::import(&EncryptedBlob::from_base64(&data)?, key)?))
+            .transpose()
+    };
+    Ok(SyncConflict {
+        item_id: stored.item_id,
+        local: open(stored.local_data)?,
+        remote: open(stored.remote_data)?,
+        detected_at: stored.detected_at,
+    })
+}
+
+/// Apply the user's choice to the vault, returning the changes to push
+pub fn resolve(
+    vault: &mut Vault,
+    conflict: SyncConflict,
+    choice: ConflictChoice,
+) -> Vec<(String, ChangeOp)> {
+    let id = conflict.item_id;
+    if choice == ConflictChoice::Local {
+        return match conflict.local {
+            Some(local) => {
+                vault.upsert_item(local);
+                vec![(id, ChangeOp::Update)]
+            }
+            None => {
+                let _ = vault.remove_item(&id);
+                vec![(id, ChangeOp::Delete)]
+            }
+        };
+    }
+
+    // The server already has the remote version, so it isn't pushed again
+    match conflict.remote.clone() {
+        Some(remote) => {
+            vault.upsert_item(remote);
+        }
+        None => {
+            let _ = vault.remove_item(&id);
+        }
+    }
+
+    match conflict.local {
+        Some(mut local)
+            if choice == ConflictChoice::Both && conflict.remote.as_ref() != Some(&local) =>
+        {
+            local.id = uuid::Uuid::new_v4().to_string();
+            local.name = format!("{} (conflicted copy)", local.name);
+            vec![(vault.add_item(local), ChangeOp::Add)]
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn decrypt_remote(remote: &SyncItem, key: &[u8; KEY_SIZE]) -> Result<Option<VaultItem>, SyncError> {
+    if remote.is_deleted {
+        return Ok(None);
+    }
+    let blob = EncryptedBlob::from_base64(&remote.encrypted_data)?;
+    Ok(Some(VaultItem::import(&blob, key)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; KEY_SIZE] = [7u8; KEY_SIZE];
+
+    fn conflict(local: Option<&VaultItem>, remote: Option<&VaultItem>) -> SyncConflict {
+        SyncConflict {
+            item_id: local.or(remote).unwrap().id.clone(),
+            local: local.cloned(),
+            remote: remote.cloned(),
+            detected_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let local = VaultItem::new("GitHub", "user", "local");
+        let mut remote = local.clone();
+        remote.password = "remote".to_string();
+        let id = local.id.clone();
+
+        let mut vault = Vault::new();
+        vault.upsert_item(local.clone());
+        let changes = resolve(
+            &mut vault,
+            conflict(Some(&local), Some(&remote)),
+            ConflictChoice::Local,
+        );
+        assert_eq!(changes, vec![(id.clone(), ChangeOp::Update)]);
+        assert_eq!(vault.get_item(&id).unwrap().password, "local");
+
+        let changes = resolve(
+            &mut vault,
+            conflict(Some(&local), Some(&remote)),
+            ConflictChoice::Remote,
+        );
+        assert!(changes.is_empty());
+        assert_eq!(vault.get_item(&id).unwrap().password, "remote");
+
+        let changes = resolve(
+            &mut vault,
+            conflict(Some(&local), Some(&remote)),
+            ConflictChoice::Both,
+        );
+        assert_eq!(changes.len(), 1);
+        let copy = vault.get_item(&changes[0].0).unwrap();
+        assert_eq!(copy.password, "local");
+        assert_eq!(copy.name, "GitHub (conflicted copy)");
+        assert_eq!(vault.get_item(&id).unwrap().password, "remote");
+
+        // Keeping a local deletion pushes it again
+        let changes = resolve(
+            &mut vault,
+            conflict(None, Some(&remote)),
+            ConflictChoice::Local,
+        );
+        assert_eq!(changes, vec![(id.clone(), ChangeOp::Delete)]);
+        assert!(vault.get_item(&id).is_none());
+    }
+
+    #[test]
+    fn test_decrypt() {
+        let item = VaultItem::new("GitHub", "user", "pass");
+        let stored = StoredConflict {
+            item_id: item.id.clone(),
+            local_data: Some(item.export(&KEY).unwrap().to_base64()),
+            remote_data: None,
+            detected_at: 5,
+        };
+        let conflict = decrypt(stored, &KEY).unwrap();
+        assert_eq!(conflict.local, Some(item));
+        assert_eq!(conflict.remote, None);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::tray;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use url::Url;
+use uuid::Uuid;
+
+/// URL scheme registered for Keydrop links, also listed in `tauri.conf.json`
+pub const DEEP_LINK_SCHEME: &str = "keydrop";
+
+/// Emitted when a link arrives; the frontend collects it with `take_deep_link`
+pub const DEEP_LINK_EVENT: &str = "deep-link";
+
+/// Longest invitation token accepted; the server issues 43-character tokens
+const MAX_TOKEN_LEN: usize = 256;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DeepLinkError {
+    #[error("Not a Keydrop link")]
+    Scheme,
+
+    #[error("Unsupported Keydrop link: {0}")]
+    Unsupported(String),
+
+    #[error("Keydrop link is missing its {0}")]
+    Missing(&'static str),
+
+    #[error("Keydrop link has an invalid {0}")]
+    Invalid(&'static str),
+}
+
+pub type Result<T> = std::result::Result<T, DeepLinkError>;
+
+/// A validated `keydrop://` link and what it asks for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeepLink {
+    /// `keydrop://emergency/accept?contact=<id>&token=<token>` from an invitation email
+    EmergencyInvitation { contact_id: String, token: String },
+    /// `keydrop://share/<id>`, reserved for shared items
+    Share { share_id: String },
+}
+
+impl DeepLink {
+    pub fn parse(url: &Url) -> Result<Self> {
+        if url.scheme() != DEEP_LINK_SCHEME {
+            return Err(DeepLinkError::Scheme);
+        }
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+
+        match (url.host_str(), segments.as_slice()) {
+            (Some("emergency"), ["accept"]) => {
+                let contact_id = param("contact").ok_or(DeepLinkError::Missing("contact"))?;
+                let token = param("token").ok_or(DeepLinkError::Missing("token"))?;
+                if !is_token(&token) {
+                    return Err(DeepLinkError::Invalid("token"));
+                }
+                Ok(DeepLink::EmergencyInvitation {
+                    contact_id: parse_id(&contact_id, "contact")?,
+                    token,
+                })
+            }
+            (Some("share"), [share_id]) => Ok(DeepLink::Share {
+                share_id: parse_id(share_id, "share id")?,
+            }),
+            // Only the route is echoed back, never query values such as tokens
+            (host, _) => Err(DeepLinkError::Unsupported(format!(
+                "{}{}",
+                host.unwrap_or_default(),
+                url.path()
+            ))),
+        }
+    }
+}
+
+fn parse_id(id: &str, what: &'static str) -> Result<String> {
+    Uuid::parse_str(id)
+        .map(|id| id.to_string())
+        .map_err(|_| DeepLinkError::Invalid(what))
+}
+
+/// Invitation tokens are URL-safe base64
+fn is_token(token: &str) -> bool {
+    !token.is_empty()
+        && token.len() <= MAX_TOKEN_LEN
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The latest link, held until the frontend is ready for it
+#[derive(Default)]
+pub struct DeepLinkState {
+    pending: Mutex<Option<DeepLink>>,
+}
+
+impl DeepLinkState {
+    pub fn take(&self) -> Option<DeepLink> {
+        self.pending.lock().unwrap().take()
+    }
+}
+
+/// Handle URLs the OS opened Keydrop with
+///
+/// Valid links are queued for the frontend and the main window is brought
+/// forward; anything else is ignored.
+pub fn handle_urls(app: &AppHandle, urls: Vec<Url>) {
+    let link = urls.iter().find_map(|url| {
+        DeepLink::parse(url)
+            .inspect_err(|e| tracing::debug!("Ignoring link: {}", e))
+            .ok()
+    });
+    let Some(link) = link else {
+        return;
+    };
+    *app.state::<DeepLinkState>().pending.lock().unwrap() = Some(link);
+
+    tray::show_main_window(app);
+    let _ = app.emit(DEEP_LINK_EVENT, ());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTACT_ID: &str = "6f1c2b1e-8d4a-4c7e-9f3b-2a5d7e9c1b40";
+
+    fn parse(url: &str) -> Result<DeepLink> {
+        DeepLink::parse(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_parse_invitation() {
+        assert_eq!(
+            parse(&format!(
+                "keydrop://emergency/accept?contact={}&token=aB3_-x9",
+                CONTACT_ID
+            )),
+            Ok(DeepLink::EmergencyInvitation {
+                contact_id: CONTACT_ID.to_string(),
+                token: "aB3_-x9".to_string(),
+            })
+        );
+        assert_eq!(
+            parse("keydrop://emergency/accept?token=abc"),
+            Err(DeepLinkError::Missing("contact"))
+        );
+        assert_eq!(
+            parse("keydrop://emergency/accept?contact=42&token=abc"),
+            Err(DeepLinkError::Invalid("contact"))
+        );
+        assert_eq!(
+            parse(&format!(
+                "keydrop://emergency/accept?contact={}&token=a%20b",
+                CONTACT_ID
+            )),
+            Err(DeepLinkError::Invalid("token"))
+        );
+    }
+
+    #[test]
+    fn test_parse_other_links() {
+        assert_eq!(
+            parse(&format!("keydrop://share/{}", CONTACT_ID)),
+            Ok(DeepLink::Share {
+                share_id: CONTACT_ID.to_string()
+            })
+        );
+        assert_eq!(
+            parse("https://emergency/accept?contact=x"),
+            Err(DeepLinkError::Scheme)
+        );
+        assert_eq!(
+            parse("keydrop://vault/export?token=secret"),
+            Err(DeepLinkError::Unsupported("vault/export".to_string()))
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use crate::sync::SyncError;
+use crate::sync_client::SyncClient;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crypto_core::{
+    cipher::KEY_SIZE,
+    error::CryptoError,
+    sharing::{self, PUBLIC_KEY_SIZE},
+    vault::Vault,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+#[derive(Error, Debug)]
+pub enum EmergencyKeyError {
+    #[error("The vault owner hasn't shared their vault key for this grant yet")]
+    NotShared,
+
+    #[error("The vault key for this grant can't be opened: {0}")]
+    Invalid(#[from] CryptoError),
+}
+
+/// Someone the user trusts to request access to their vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmergencyContact {
+    pub id: String,
+    pub contact_email: String,
+    pub contact_name: Option<String>,
+    /// `pending` until the invitation is accepted, then `accepted`
+    pub status: String,
+    pub waiting_period_hours: i32,
+    pub can_view_vault: bool,
+    pub accepted_at: Option<i64>,
+    pub created_at: i64,
+    /// Key to seal the vault key for, published when the contact accepted
+    #[serde(default, skip_serializing)]
+    pub contact_public_key: Option<String>,
+    /// Whether the server holds the vault key sealed for this contact
+    #[serde(default)]
+    pub vault_key_shared: bool,
+}
+
+impl EmergencyContact {
+    /// The vault key sealed for this contact, if they accepted and the server
+    /// doesn't have it yet
+    pub fn seal_vault_key(
+        &self,
+        vault_key: &[u8; KEY_SIZE],
+    ) -> Result<Option<String>, CryptoError> {
+        let Some(public_key) = self
+            .contact_public_key
+            .as_deref()
+            .filter(|_| self.status == "accepted" && !self.vault_key_shared)
+        else {
+            return Ok(None);
+        };
+        let public_key: [u8; PUBLIC_KEY_SIZE] = STANDARD
+            .decode(public_key)
+            .ok()
+            .and_then(|key| key.try_into().ok())
+            .ok_or_else(|| CryptoError::Encryption("Invalid contact public key".to_string()))?;
+        Ok(Some(
+            STANDARD.encode(sharing::seal_key(vault_key, &public_key)?),
+        ))
+    }
+}
+
+/// Seal the vault key for every accepted contact the server has none for yet
+pub async fn share_vault_keys(
+    client: &SyncClient,
+    contacts: &mut [EmergencyContact],
+    vault_key: &[u8; KEY_SIZE],
+) -> Result<(), SyncError> {
+    for contact in contacts {
+        if let Some(sealed) = contact.seal_vault_key(vault_key)? {
+            client
+                .share_emergency_vault_key(&contact.id, &sealed)
+                .await?;
+            contact.vault_key_shared = true;
+        }
+    }
+    Ok(())
+}
+
+/// Public key (base64) a contact publishes so owners can seal their vault key for it
+pub fn contact_public_key(sharing_key: &[u8; KEY_SIZE]) -> String {
+    STANDARD.encode(sharing::public_key(sharing_key))
+}
+
+/// Add contact request body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddContactRequest {
+    pub email: String,
+    pub name: Option<String>,
+    pub waiting_period_hours: Option<i32>,
+}
+
+/// Access request body, sent by a contact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessRequest {
+    pub emergency_contact_id: String,
+    pub reason: Option<String>,
+}
+
+/// Access request as seen by the contact who made it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessRequestStatus {
+    pub request_id: String,
+    pub status: String,
+    /// When access is granted unless the owner denies it first
+    pub waiting_period_ends_at: i64,
+    pub created_at: i64,
+}
+
+/// Access request waiting on the vault owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingAccessRequest {
+    pub request_id: String,
+    pub contact_id: String,
+    pub contact_email: String,
+    pub contact_name: Option<String>,
+    pub reason: Option<String>,
+    pub waiting_period_ends_at: i64,
+    pub created_at: i64,
+}
+
+/// Approved access as returned by the server
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrantedAccessResponse {
+    pub contact_id: String,
+    pub user_email: String,
+    pub request_id: String,
+    pub approved_at: i64,
+    /// Owner's vault key sealed for the contact; empty until the owner's
+    /// client has sealed it
+    pub vault_key_encrypted: Option<String>,
+    /// Whether the owner lets the contact export the vault; off unless the
+    /// server says otherwise
+    #[serde(default)]
+    pub allow_export: bool,
+}
+
+/// `GET /emergency/vault` response body
+#[derive(Debug, Clone, Deserialize)]
+pub struct VaultAccessResponse {
+    pub granted_access: Vec<GrantedAccessResponse>,
+}
+
+/// Vault access another user granted to this one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GrantedAccess {
+    pub contact_id: String,
+    /// Email of the vault owner
+    pub owner_email: String,
+    pub request_id: String,
+    pub approved_at: i64,
+    /// Whether the owner's vault key came with the grant
+    pub key_available: bool,
+    pub allow_export: bool,
+    /// Owner's vault key sealed for this user's sharing key; never sent to
+    /// the frontend
+    #[serde(skip)]
+    vault_key_sealed: String,
+}
+
+impl GrantedAccess {
+    /// Open the owner's vault key with this user's sharing key
+    pub fn vault_key(
+        &self,
+        sharing_key: &[u8; KEY_SIZE],
+    ) -> Result<Zeroizing<[u8; KEY_SIZE]>, EmergencyKeyError> {
+        if self.vault_key_sealed.is_empty() {
+            return Err(EmergencyKeyError::NotShared);
+        }
+        let sealed = STANDARD
+            .decode(&self.vault_key_sealed)
+            .map_err(|e| CryptoError::Decryption(e.to_string()))?;
+        Ok(sharing::open_key(&sealed, sharing_key)?)
+    }
+}
+
+impl From<GrantedAccessResponse> for GrantedAccess {
+    fn from(granted: GrantedAccessResponse) -> Self {
+        let vault_key_sealed = granted.vault_key_encrypted.unwrap_or_default();
+        Self {
+            contact_id: granted.contact_id,
+            owner_email: granted.user_email,
+            request_id: granted.request_id,
+            approved_at: granted.approved_at,
+            key_available: !vault_key_sealed.is_empty(),
+            allow_export: granted.allow_export,
+            vault_key_sealed,
+        }
+    }
+}
+
+/// Another user's vault opened by an emergency contact, for viewing only
+///
+/// It's kept apart from [`crate::state::AppState`], so no save, sync or
+/// backup path can reach it, and is only readable through [`Self::vault`].
+pub struct ViewedVault {
+    grant: GrantedAccess,
+    vault: Vault,
+}
+
+impl ViewedVault {
+    pub fn new(grant: GrantedAccess, vault: Vault) -> Self {
+        Self { grant, vault }
+    }
+
+    pub fn vault(&self) -> &Vault {
+        &self.vault
+    }
+
+    /// Whether the owner's policy lets the contact export the vault
+    pub fn export_allowed(&self) -> bool {
+        self.grant.allow_export
+    }
+
+    pub fn info(&self) -> EmergencyVaultInfo {
+        EmergencyVaultInfo {
+            owner_email: self.grant.owner_email.clone(),
+            request_id: self.grant.request_id.clone(),
+            item_count: self.vault.len(),
+            allow_export: self.grant.allow_export,
+        }
+    }
+}
+
+/// What the frontend shows about the vault in the emergency viewer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EmergencyVaultInfo {
+    pub owner_email: String,
+    pub request_id: String,
+    pub item_count: usize,
+    pub allow_export: bool,
+}
+
+/// The vault open in the emergency viewer, if any; closed when the user's own
+/// vault locks
+#[derive(Default)]
+pub struct EmergencyViewer {
+    viewed: Mutex<Option<ViewedVault>>,
+}
+
+impl EmergencyViewer {
+    pub fn open(&self, viewed: ViewedVault) {
+        *self.viewed.lock().unwrap() = Some(viewed);
+    }
+
+    pub fn close(&self) {
+        *self.viewed.lock().unwrap() = None;
+    }
+
+    /// Run `f` on the open vault; `None` if none is open
+    pub fn with<R>(&self, f: impl FnOnce(&ViewedVault) -> R) -> Option<R> {
+        self.viewed.lock().unwrap().as_ref().map(f)
+    }
+}
+
+/// Entry in the owner's emergency access log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessLogEntry {
+    pub id: String,
+    pub action: String,
+    pub details: Option<serde_json::Value>,
+    pub created_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_granted_access() {
+        let response: VaultAccessResponse = serde_json::from_value(serde_json::json!({
+            "granted_access": [
+                {
+                    "contact_id": "c1",
+                    "user_email": "owner@example.com",
+                    "request_id": "r1",
+                    "approved_at": 1700000000,
+                    "vault_key_encrypted": "",
+                },
+                {
+                    "contact_id": "c2",
+                    "user_email": "other@example.com",
+                    "request_id": "r2",
+                    "approved_at": 1700000000,
+                    "vault_key_encrypted": "d3JhcHBlZA==",
+                },
+            ]
+        }))
+        .unwrap();
+
+        let granted: Vec<GrantedAccess> = response
+            .granted_access
+            .into_iter()
+            .map(GrantedAccess::from)
+            .collect();
+        assert_eq!(granted[0].owner_email, "owner@example.com");
+        assert!(!granted[0].key_available);
+        assert!(granted[1].key_available);
+        assert!(!granted[1].allow_export);
+    }
+
+    #[test]
+    fn test_vault_key_handover() {
+        let owner_vault_key = [7u8; KEY_SIZE];
+        let contact_sharing_key = [9u8; KEY_SIZE];
+        let contact: EmergencyContact = serde_json::from_value(serde_json::json!({
+            "id": "c1",
+            "contact_email": "contact@example.com",
+            "contact_name": null,
+            "status": "accepted",
+            "waiting_period_hours": 48,
+            "can_view_vault": true,
+            "accepted_at": 1700000000,
+            "created_at": 1700000000,
+            "contact_public_key": contact_public_key(&contact_sharing_key),
+            "vault_key_shared": false,
+        }))
+        .unwrap();
+        let sealed = contact.seal_vault_key(&owner_vault_key).unwrap().unwrap();
+
+        // Nothing to do once the server has it, or before the contact accepts
+        let shared = EmergencyContact {
+            vault_key_shared: true,
+            ..contact.clone()
+        };
+        assert!(shared.seal_vault_key(&owner_vault_key).unwrap().is_none());
+        let pending = EmergencyContact {
+            status: "pending".to_string(),
+            ..contact
+        };
+        assert!(pending.seal_vault_key(&owner_vault_key).unwrap().is_none());
+
+        let grant = |vault_key_encrypted: &str| {
+            GrantedAccess::from(GrantedAccessResponse {
+                contact_id: "c1".to_string(),
+                user_email: "owner@example.com".to_string(),
+                request_id: "r1".to_string(),
+                approved_at: 1700000000,
+                vault_key_encrypted: Some(vault_key_encrypted.to_string()),
+                allow_export: false,
+            })
+        };
+        assert_eq!(
+            *grant(&sealed).vault_key(&contact_sharing_key).unwrap(),
+            owner_vault_key
+        );
+        assert!(matches!(
+            grant(&sealed).vault_key(&[1u8; KEY_SIZE]),
+            Err(EmergencyKeyError::Invalid(_))
+        ));
+        assert!(matches!(
+            grant("").vault_key(&contact_sharing_key),
+            Err(EmergencyKeyError::NotShared)
+        ));
+    }
+
+    #[test]
+    fn test_emergency_viewer() {
+        let grant = GrantedAccess {
+            contact_id: "c1".to_string(),
+            owner_email: "owner@example.com".to_string(),
+            request_id: "r1".to_string(),
+            approved_at: 1700000000,
+            key_available: false,
+            allow_export: false,
+            vault_key_sealed: String::new(),
+        };
+        let mut vault = Vault::new();
+        vault.add_item(crypto_core::vault::VaultItem::new(
+            "Bank", "owner", "secret",
+        ));
+
+        let viewer = EmergencyViewer::default();
+        assert!(viewer.with(|viewed| viewed.info()).is_none());
+        viewer.open(ViewedVault::new(grant, vault));
+        let info = viewer.with(|viewed| viewed.info()).unwrap();
+        assert_eq!(info.item_count, 1);
+        assert!(!viewer.with(ViewedVault::export_allowed).unwrap());
+
+        viewer.close();
+        assert!(viewer.with(|viewed| viewed.vault().len()).is_none());
+    }
+}
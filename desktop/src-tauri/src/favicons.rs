@@ -0,0 +1,243 @@
+use crate::state::AppState;
+use crate::storage::{Storage, StorageError, StoredIcon};
+use crypto_core::matching::extract_host;
+use crypto_core::vault::Vault;
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tokio::sync::Notify;
+
+/// Settings key for the privacy toggle; `"false"` stops all icon downloads
+pub const ICON_FETCH_SETTING: &str = "icon_fetch_enabled";
+
+/// Event emitted with the domains whose icons were just downloaded
+pub const ICONS_UPDATED_EVENT: &str = "icons-updated";
+
+/// Largest icon accepted
+const MAX_ICON_SIZE: usize = 100 * 1024;
+
+/// How long a cached icon, or a site's lack of one, is trusted
+const ICON_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// How often the vault is rescanned for domains without icons
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Pause between downloads so a large vault doesn't burst requests
+const FETCH_SPACING: Duration = Duration::from_millis(500);
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Error, Debug)]
+pub enum IconError {
+    #[error("Icon download failed: {0}")]
+    Network(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+pub type Result<T> = std::result::Result<T, IconError>;
+
+/// Wakes the background icon fetcher
+pub struct IconState {
+    wake: Notify,
+}
+
+impl IconState {
+    pub fn new() -> Self {
+        Self {
+            wake: Notify::new(),
+        }
+    }
+
+    /// Ask the fetcher to look for new domains soon, e.g. after an unlock or edit
+    pub fn request_refresh(&self) {
+        self.wake.notify_one();
+    }
+
+    async fn refresh_requested(&self) {
+        self.wake.notified().await;
+    }
+}
+
+impl Default for IconState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether icons may be downloaded; on unless the user turned it off
+pub fn fetch_enabled(storage: &Storage) -> std::result::Result<bool, StorageError> {
+    Ok(storage.get_setting(ICON_FETCH_SETTING)?.as_deref() != Some("false"))
+}
+
+/// Normalize a domain or URL to the host icons are cached under
+pub fn icon_domain(url: &str) -> Option<String> {
+    let host = extract_host(url);
+    is_fetchable(&host).then_some(host)
+}
+
+/// Only public-looking host names are fetched, never IP addresses or local names
+fn is_fetchable(host: &str) -> bool {
+    host.contains('.')
+        && !host.starts_with('.')
+        && !host.ends_with('.')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '-')
+        && host.chars().any(|c| c.is_ascii_lowercase())
+}
+
+/// Distinct icon domains of the vault's item URLs
+fn vault_domains(vault: &Vault) -> BTreeSet<String> {
+    vault
+        .items
+        .iter()
+        .filter_map(|item| item.url.as_deref())
+        .filter_map(icon_domain)
+        .collect()
+}
+
+/// Start the background task that downloads favicons for item domains
+///
+/// Runs hourly and after [`IconState::request_refresh`]. Only domains are
+/// requested; item names and other fields never leave the device.
+pub fn spawn_icon_fetcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let icon_state = app.state::<IconState>();
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(REFRESH_INTERVAL) => {}
+                _ = icon_state.refresh_requested() => {}
+            }
+
+            let domains = match app.state::<AppState>().vault.lock().unwrap().as_ref() {
+                Some(vault) => vault_domains(vault),
+                None => continue,
+            };
+            if let Ok(updated) = fetch_missing(domains).await {
+                if !updated.is_empty() {
+                    let _ = app.emit(ICONS_UPDATED_EVENT, updated);
+                }
+            }
+        }
+    });
+}
+
+/// Download icons for domains with no fresh cache entry, returning those that got one
+async fn fetch_missing(domains: BTreeSet<String>) -> Result<Vec<String>> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent("Keydrop")
+        .build()?;
+    let mut updated = Vec::new();
+
+    for domain in domains {
+        let now = now_secs();
+        {
+            let storage = Storage::open()?;
+            // Checked per domain so turning the toggle off stops a pass midway
+            if !fetch_enabled(&storage)? {
+                break;
+            }
+            let cached = storage.icon(&domain)?;
+            if cached.is_some_and(|icon| now - icon.fetched_at < ICON_TTL_SECS) {
+                continue;
+            }
+        }
+
+        // A network failure ends the pass; the next one retries
+        let icon = fetch_icon(&client, &domain).await?;
+        let found = icon.is_some();
+        let (data, content_type) = icon.unzip();
+        Storage::open()?.save_icon(&StoredIcon {
+            domain: domain.clone(),
+            data,
+            content_type,
+            fetched_at: now,
+        })?;
+        if found {
+            updated.push(domain);
+        }
+        tokio::time::sleep(FETCH_SPACING).await;
+    }
+    Ok(updated)
+}
+
+/// Download `/favicon.ico` for a domain; `None` if the site has no usable icon
+async fn fetch_icon(client: &reqwest::Client, domain: &str) -> Result<Option<(Vec<u8>, String)>> {
+    let response = client
+        .get(format!("https://{}/favicon.ico", domain))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        })
+        .unwrap_or_default();
+    if !content_type.starts_with("image/")
+        || response
+            .content_length()
+            .is_some_and(|len| len > MAX_ICON_SIZE as u64)
+    {
+        return Ok(None);
+    }
+
+    let data = response.bytes().await?;
+    if data.is_empty() || data.len() > MAX_ICON_SIZE {
+        return Ok(None);
+    }
+    Ok(Some((data.to_vec(), content_type)))
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_core::vault::VaultItem;
+
+    #[test]
+    fn test_icon_domain() {
+        assert_eq!(
+            icon_domain("https://www.GitHub.com/login").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(icon_domain("example.org").as_deref(), Some("example.org"));
+        assert_eq!(icon_domain("http://192.168.1.1/admin"), None);
+        assert_eq!(icon_domain("http://localhost:8080"), None);
+        assert_eq!(icon_domain(""), None);
+    }
+
+    #[test]
+    fn test_vault_domains() {
+        let mut vault = Vault::new();
+        vault.add_item(VaultItem::new("GitHub", "a", "p").with_url("https://github.com/login"));
+        vault.add_item(VaultItem::new("GitHub 2", "b", "p").with_url("https://www.github.com"));
+        vault.add_item(VaultItem::new("Router", "admin", "p").with_url("http://192.168.0.1"));
+        vault.add_item(VaultItem::new("Note", "", ""));
+
+        assert_eq!(
+            vault_domains(&vault).into_iter().collect::<Vec<_>>(),
+            vec!["github.com".to_string()]
+        );
+    }
+}
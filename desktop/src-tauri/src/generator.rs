@@ -0,0 +1,169 @@
+use crate::storage::{Storage, StorageError};
+use crypto_core::cipher::{decrypt_string, encrypt_string, KEY_SIZE};
+use crypto_core::error::CryptoError;
+use crypto_core::password::{
+    generate_passphrase_with, generate_password, PassphraseOptions, PasswordOptions,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Longest preset name accepted
+pub const MAX_PRESET_NAME_LEN: usize = 64;
+
+/// Generated passwords kept in the history
+pub const HISTORY_CAP: usize = 100;
+
+#[derive(Error, Debug)]
+pub enum GeneratorError {
+    #[error("Generator history error: {0}")]
+    Crypto(#[from] CryptoError),
+
+    #[error("Generator history error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+pub type Result<T> = std::result::Result<T, GeneratorError>;
+
+/// Options for either kind of generated secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GeneratorOptions {
+    Password(PasswordOptions),
+    Passphrase(PassphraseOptions),
+}
+
+impl GeneratorOptions {
+    pub fn generate(&self) -> std::result::Result<String, CryptoError> {
+        match self {
+            GeneratorOptions::Password(options) => generate_password(options),
+            GeneratorOptions::Passphrase(options) => generate_passphrase_with(options),
+        }
+    }
+
+    /// Compare by serialized form, since the core option types aren't `PartialEq`
+    pub fn same_as(&self, other: &GeneratorOptions) -> bool {
+        serde_json::to_value(self).ok() == serde_json::to_value(other).ok()
+    }
+}
+
+/// Generator options saved under a name, e.g. "Banking 12 no-symbols"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorPreset {
+    pub name: String,
+    pub options: GeneratorOptions,
+}
+
+/// Add `preset`, replacing any preset with the same name
+pub fn upsert_preset(presets: &mut Vec<GeneratorPreset>, preset: GeneratorPreset) {
+    match presets.iter_mut().find(|p| p.name == preset.name) {
+        Some(existing) => *existing = preset,
+        None => presets.push(preset),
+    }
+}
+
+/// A generated password kept in case it never made it into an item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GeneratedPassword {
+    pub id: i64,
+    pub password: String,
+    /// Site the password was generated for, if known
+    pub site: Option<String>,
+    pub created_at: i64,
+}
+
+/// Encrypted part of a history entry
+#[derive(Serialize, Deserialize)]
+struct HistoryEntry {
+    password: String,
+    site: Option<String>,
+}
+
+/// Add a generated password to the history, dropping the oldest past [`HISTORY_CAP`]
+pub fn record(
+    storage: &Storage,
+    vault_key: &[u8; KEY_SIZE],
+    password: String,
+    site: Option<String>,
+    now: i64,
+) -> Result<()> {
+    let entry = Zeroizing::new(serde_json::to_string(&HistoryEntry { password, site })?);
+    storage.add_generated(&encrypt_string(&entry, vault_key)?, now, HISTORY_CAP)?;
+    Ok(())
+}
+
+/// Generator history, newest first
+///
+/// Entries that don't decrypt with `vault_key` are left out.
+pub fn history(storage: &Storage, vault_key: &[u8; KEY_SIZE]) -> Result<Vec<GeneratedPassword>> {
+    Ok(storage
+        .generated_history()?
+        .into_iter()
+        .filter_map(|stored| {
+            let json = Zeroizing::new(decrypt_string(&stored.encrypted_entry, vault_key).ok()?);
+            let entry: HistoryEntry = serde_json::from_str(&json).ok()?;
+            Some(GeneratedPassword {
+                id: stored.id,
+                password: entry.password,
+                site: entry.site,
+                created_at: stored.created_at,
+            })
+        })
+        .collect())
+}
+
+/// Re-encrypt the history for a new vault key
+pub fn rekey_history(
+    storage: &Storage,
+    old_key: &[u8; KEY_SIZE],
+    new_key: &[u8; KEY_SIZE],
+) -> Result<()> {
+    for stored in storage.generated_history()? {
+        if let Ok(entry) = decrypt_string(&stored.encrypted_entry, old_key) {
+            let entry = Zeroizing::new(entry);
+            storage.update_generated(stored.id, &encrypt_string(&entry, new_key)?)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_serde() {
+        let options = GeneratorOptions::Passphrase(PassphraseOptions {
+            word_count: 5,
+            ..Default::default()
+        });
+        let json = serde_json::to_value(&options).unwrap();
+        assert_eq!(json["type"], "passphrase");
+        assert_eq!(json["word_count"], 5);
+
+        let parsed: GeneratorOptions = serde_json::from_value(json).unwrap();
+        assert!(parsed.same_as(&options));
+        assert_eq!(parsed.generate().unwrap().split('-').count(), 5);
+    }
+
+    #[test]
+    fn test_upsert_preset() {
+        let banking = |length| GeneratorPreset {
+            name: "Banking".to_string(),
+            options: GeneratorOptions::Password(PasswordOptions {
+                length,
+                symbols: false,
+                ..Default::default()
+            }),
+        };
+        let mut presets = Vec::new();
+        upsert_preset(&mut presets, banking(12));
+        upsert_preset(&mut presets, banking(14));
+
+        assert_eq!(presets.len(), 1);
+        assert!(presets[0].options.same_as(&banking(14).options));
+    }
+}
@@ -0,0 +1,275 @@
+use crypto_core::error::CryptoError;
+use crypto_core::importers::{self, CsvMapping, ImportReport, SkippedRow};
+use crypto_core::matching::extract_host;
+use crypto_core::vault::{Vault, VaultItem};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+/// Largest import file read into memory
+const MAX_IMPORT_SIZE: u64 = 50 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("Could not read import file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Import file is larger than {} MB", MAX_IMPORT_SIZE / 1024 / 1024)]
+    TooLarge,
+
+    #[error("Import failed: {0}")]
+    Parse(#[from] CryptoError),
+}
+
+pub type Result<T> = std::result::Result<T, ImportError>;
+
+/// Source format of an import file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// Generic CSV with a header row
+    Csv,
+    Lastpass,
+    Keepass,
+    /// Unencrypted Bitwarden JSON export
+    Bitwarden,
+}
+
+/// Choices made in the review step
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// Column mapping for [`ImportFormat::Csv`]; ignored for other formats
+    pub mapping: Option<CsvMapping>,
+    /// Leave out entries flagged as duplicates
+    pub skip_duplicates: bool,
+    /// Preview indexes the user deselected
+    pub exclude: Vec<usize>,
+}
+
+/// One parsed entry, without its secrets
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportCandidate {
+    /// Position in the preview, used by [`ImportOptions::exclude`]
+    pub index: usize,
+    pub name: String,
+    pub username: String,
+    pub url: Option<String>,
+    pub category: Option<String>,
+    /// Set when the entry repeats a vault item or an earlier entry in the file
+    pub duplicate: Option<Duplicate>,
+}
+
+/// What an import entry duplicates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "of", rename_all = "snake_case")]
+pub enum Duplicate {
+    /// An item already in the vault
+    Existing { item_id: String },
+    /// An earlier entry in the same file
+    Entry { index: usize },
+}
+
+/// Result of `preview_import`
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    pub total: usize,
+    pub duplicates: usize,
+    pub items: Vec<ImportCandidate>,
+    /// Rows that could not be parsed
+    pub errors: Vec<SkippedRow>,
+}
+
+/// Result of `run_import`
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped_duplicates: usize,
+    pub excluded: usize,
+    pub errors: Vec<SkippedRow>,
+    /// IDs of the new vault items
+    #[serde(skip)]
+    pub ids: Vec<String>,
+}
+
+/// Read and parse an import file without touching the vault
+pub fn parse_file(
+    path: &Path,
+    format: ImportFormat,
+    mapping: Option<&CsvMapping>,
+) -> Result<ImportReport> {
+    if std::fs::metadata(path)?.len() > MAX_IMPORT_SIZE {
+        return Err(ImportError::TooLarge);
+    }
+    let text = std::fs::read_to_string(path)?;
+    // Spreadsheet apps often prepend a byte order mark
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+
+    Ok(match format {
+        ImportFormat::Csv => {
+            importers::import_csv(text, mapping.unwrap_or(&CsvMapping::default()))?
+        }
+        ImportFormat::Lastpass => importers::import_lastpass_csv(text)?,
+        ImportFormat::Keepass => importers::import_keepass_csv(text)?,
+        ImportFormat::Bitwarden => importers::import_bitwarden_json(text)?,
+    })
+}
+
+/// Key two items share when they are the same login
+///
+/// Items with a URL match on host and username; others on name and username.
+fn duplicate_key(item: &VaultItem) -> (String, String) {
+    let place = match item.url.as_deref().filter(|url| !url.trim().is_empty()) {
+        Some(url) => extract_host(url),
+        None => item.name.trim().to_lowercase(),
+    };
+    (place, item.username.trim().to_lowercase())
+}
+
+/// Find what each parsed item duplicates, in order
+pub fn find_duplicates(vault: &Vault, items: &[VaultItem]) -> Vec<Option<Duplicate>> {
+    let mut seen: Vec<((String, String), Duplicate)> = vault
+        .items
+        .iter()
+        .map(|item| {
+            (
+                duplicate_key(item),
+                Duplicate::Existing {
+                    item_id: item.id.clone(),
+                },
+            )
+        })
+        .collect();
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let key = duplicate_key(item);
+            let found = seen
+                .iter()
+                .find(|(seen_key, _)| *seen_key == key)
+                .map(|(_, duplicate)| duplicate.clone());
+            if found.is_none() {
+                seen.push((key, Duplicate::Entry { index }));
+            }
+            found
+        })
+        .collect()
+}
+
+/// Summarize parsed items for the review step
+pub fn preview(vault: &Vault, report: ImportReport) -> ImportPreview {
+    let duplicates = find_duplicates(vault, &report.items);
+    let items: Vec<ImportCandidate> = report
+        .items
+        .into_iter()
+        .zip(duplicates)
+        .enumerate()
+        .map(|(index, (item, duplicate))| ImportCandidate {
+            index,
+            name: item.name,
+            username: item.username,
+            url: item.url,
+            category: item.category,
+            duplicate,
+        })
+        .collect();
+
+    ImportPreview {
+        total: items.len(),
+        duplicates: items.iter().filter(|item| item.duplicate.is_some()).count(),
+        items,
+        errors: report.skipped,
+    }
+}
+
+/// Add the parsed items the user kept to `vault`
+pub fn apply(vault: &mut Vault, report: ImportReport, options: &ImportOptions) -> ImportSummary {
+    let duplicates = find_duplicates(vault, &report.items);
+    let mut summary = ImportSummary {
+        imported: 0,
+        skipped_duplicates: 0,
+        excluded: 0,
+        errors: report.skipped,
+        ids: Vec::new(),
+    };
+
+    let mut keep = Vec::new();
+    for (index, (item, duplicate)) in report.items.into_iter().zip(duplicates).enumerate() {
+        if options.exclude.contains(&index) {
+            summary.excluded += 1;
+        } else if options.skip_duplicates && duplicate.is_some() {
+            summary.skipped_duplicates += 1;
+        } else {
+            keep.push(item);
+        }
+    }
+
+    for category in keep.iter().filter_map(|item| item.category.as_deref()) {
+        vault.add_category(category);
+    }
+    summary.ids = vault.add_items(keep);
+    summary.imported = summary.ids.len();
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(items: Vec<VaultItem>) -> ImportReport {
+        ImportReport {
+            items,
+            skipped: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let mut vault = Vault::new();
+        let existing = vault.add_item(
+            VaultItem::new("GitHub", "alice", "old").with_url("https://github.com/login"),
+        );
+
+        let items = vec![
+            VaultItem::new("GitHub", "Alice", "new").with_url("https://github.com"),
+            VaultItem::new("Router", "admin", "a"),
+            VaultItem::new("router", "admin", "b"),
+            VaultItem::new("GitHub", "bob", "c").with_url("https://github.com"),
+        ];
+        assert_eq!(
+            find_duplicates(&vault, &items),
+            vec![
+                Some(Duplicate::Existing { item_id: existing }),
+                None,
+                Some(Duplicate::Entry { index: 1 }),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut vault = Vault::new();
+        vault.add_item(VaultItem::new("Mail", "alice", "x"));
+
+        let items = vec![
+            VaultItem::new("Mail", "alice", "y"),
+            VaultItem::new("Bank", "alice", "z").with_category("Finance"),
+            VaultItem::new("Shop", "alice", "w"),
+        ];
+        let options = ImportOptions {
+            skip_duplicates: true,
+            exclude: vec![2],
+            ..Default::default()
+        };
+        let summary = apply(&mut vault, report(items), &options);
+
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.skipped_duplicates, 1);
+        assert_eq!(summary.excluded, 1);
+        assert_eq!(vault.items.len(), 2);
+        assert!(vault.categories.contains(&"Finance".to_string()));
+    }
+}
@@ -0,0 +1,124 @@
+use crate::storage::{Storage, StorageError};
+use crypto_core::error::CryptoError;
+use crypto_core::kdf::{combine_key_file, MasterKey};
+use rand::RngCore;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Settings key holding the path of the registered key file
+///
+/// Its presence means the vault can't be unlocked without the file.
+pub const KEY_FILE_SETTING: &str = "key_file_path";
+
+/// Random bytes written by [`generate`]
+const GENERATED_SIZE: usize = 64;
+
+/// Largest file accepted as a key file
+const MAX_KEY_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum KeyFileError {
+    #[error("Key file not found at {0}")]
+    Missing(PathBuf),
+
+    #[error("A file already exists at {0}")]
+    Exists(PathBuf),
+
+    #[error("Key file is empty")]
+    Empty,
+
+    #[error("Key file is larger than {} MB", MAX_KEY_FILE_SIZE / 1024 / 1024)]
+    TooLarge,
+
+    #[error("Could not read key file: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Key file error: {0}")]
+    Crypto(#[from] CryptoError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+pub type Result<T> = std::result::Result<T, KeyFileError>;
+
+/// Write a new random key file; never overwrites an existing file
+pub fn generate(path: &Path) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => KeyFileError::Exists(path.to_path_buf()),
+        _ => KeyFileError::Io(e),
+    })?;
+
+    let mut contents = Zeroizing::new([0u8; GENERATED_SIZE]);
+    rand::rngs::OsRng.fill_bytes(contents.as_mut());
+    file.write_all(contents.as_ref())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Read a key file's contents
+pub fn read(path: &Path) -> Result<Zeroizing<Vec<u8>>> {
+    let metadata = std::fs::metadata(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => KeyFileError::Missing(path.to_path_buf()),
+        _ => KeyFileError::Io(e),
+    })?;
+    if metadata.len() > MAX_KEY_FILE_SIZE {
+        return Err(KeyFileError::TooLarge);
+    }
+
+    let contents = Zeroizing::new(std::fs::read(path)?);
+    if contents.is_empty() {
+        return Err(KeyFileError::Empty);
+    }
+    Ok(contents)
+}
+
+/// Path of the registered key file, if the vault requires one
+pub fn registered_path(storage: &Storage) -> Result<Option<PathBuf>> {
+    Ok(storage.get_setting(KEY_FILE_SETTING)?.map(PathBuf::from))
+}
+
+/// Mix the key file into a password-derived master key when the vault needs one
+///
+/// `path` overrides the registered location, e.g. when the file lives on a
+/// removable drive that mounted somewhere else. Vaults without a key file get
+/// `master_key` back unchanged.
+pub fn apply(storage: &Storage, master_key: MasterKey, path: Option<&Path>) -> Result<MasterKey> {
+    let Some(registered) = registered_path(storage)? else {
+        return Ok(master_key);
+    };
+    let contents = read(path.unwrap_or(&registered))?;
+    Ok(combine_key_file(&master_key, &contents)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_generate_and_read() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vault.key");
+
+        generate(&path).unwrap();
+        assert_eq!(read(&path).unwrap().len(), GENERATED_SIZE);
+        assert!(matches!(generate(&path), Err(KeyFileError::Exists(_))));
+
+        let missing = dir.path().join("missing.key");
+        assert!(matches!(read(&missing), Err(KeyFileError::Missing(_))));
+
+        let empty = dir.path().join("empty.key");
+        std::fs::write(&empty, b"").unwrap();
+        assert!(matches!(read(&empty), Err(KeyFileError::Empty)));
+    }
+}
@@ -1,26 +1,176 @@
+mod activity;
+mod attachments;
+mod auto_lock;
+mod autotype;
+mod backup;
+mod biometric;
+mod breach;
+mod browser_bridge;
+mod capture;
+mod clipboard;
 mod commands;
+mod conflicts;
+mod deep_link;
+mod emergency;
+mod favicons;
+mod generator;
+mod import;
+mod keyfile;
+mod lockout;
+mod logging;
+pub mod native_messaging;
+mod palette;
+mod remote_commands;
+mod scheduler;
+mod search;
+mod session;
+mod settings;
 mod state;
 mod storage;
 mod sync;
+mod sync_client;
+mod tray;
+mod updater;
+mod vault_store;
 
+use backup::BackupState;
+use breach::BreachState;
+use browser_bridge::BridgeState;
+use clipboard::ClipboardState;
 use commands::*;
+use deep_link::DeepLinkState;
+use emergency::EmergencyViewer;
+use favicons::IconState;
+use settings::SettingsState;
 use state::AppState;
+use storage::Storage;
 use sync::SyncState;
+use tauri::Manager;
+use tauri_plugin_deep_link::DeepLinkExt;
+use updater::UpdaterState;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let recent_logs = logging::init();
+
     tauri::Builder::default()
+        // First, so a second launch hands its arguments and links over, then exits
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            tray::show_main_window(app);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(updater::plugin())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(palette::handle_shortcut)
+                .build(),
+        )
         .manage(AppState::new())
         .manage(SyncState::new())
+        .manage(ClipboardState::new())
+        .manage(BridgeState::default())
+        .manage(BackupState::new())
+        .manage(BreachState::default())
+        .manage(IconState::new())
+        .manage(SettingsState::new())
+        .manage(DeepLinkState::default())
+        .manage(EmergencyViewer::default())
+        .manage(UpdaterState::default())
+        .manage(recent_logs)
+        .on_window_event(|window, event| {
+            // Switching to a window counts as activity; so does anything the
+            // frontend reports with `report_activity`
+            if let tauri::WindowEvent::Focused(true) = event {
+                window.state::<AppState>().session.record_activity();
+            }
+        })
+        .setup(|app| {
+            // Attachments left open when the app last quit
+            attachments::cleanup_temp();
+            if let Ok(storage) = Storage::open() {
+                let sync_state = app.state::<SyncState>();
+                let _ = settings::load(app.handle(), &storage);
+                app.state::<BackupState>().load(&storage);
+                if let Ok(count) = storage.pending_change_count() {
+                    sync_state.set_pending_changes(count);
+                }
+                if let Ok(count) = storage.conflict_count() {
+                    sync_state.set_conflicts(count);
+                }
+                // Resume sync with credentials from the OS keychain
+                if let Ok(Some(credentials)) = storage.load_sync_credentials() {
+                    sync_state.enable(
+                        credentials.server_url,
+                        credentials.access_token,
+                        credentials.device_id,
+                    );
+                }
+            }
+            scheduler::spawn_sync_scheduler(app.handle().clone());
+            auto_lock::spawn_auto_lock(app.handle().clone());
+            backup::spawn_backup_scheduler(app.handle().clone());
+            favicons::spawn_icon_fetcher(app.handle().clone());
+            tray::create_tray(app.handle())?;
+
+            // Installers register the scheme; this covers unpackaged builds
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            let _ = app.deep_link().register_all();
+            let handle = app.handle().clone();
+            app.deep_link()
+                .on_open_url(move |event| deep_link::handle_urls(&handle, event.urls()));
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                deep_link::handle_urls(app.handle(), urls);
+            }
+
+            let shortcut = Storage::open()
+                .ok()
+                .and_then(|storage| {
+                    storage
+                        .get_setting(palette::PALETTE_SHORTCUT_SETTING)
+                        .ok()
+                        .flatten()
+                })
+                .unwrap_or_else(|| palette::DEFAULT_PALETTE_SHORTCUT.to_string());
+            // Another app may own the shortcut; the user can pick a new one in settings
+            let _ = palette::register_shortcut(app.handle(), &shortcut, None);
+
+            let extension_id = Storage::open().ok().and_then(|storage| {
+                storage
+                    .get_setting(browser_bridge::EXTENSION_ID_SETTING)
+                    .ok()
+                    .flatten()
+            });
+            if extension_id.is_some() {
+                browser_bridge::start(app.handle());
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Vault status
             get_vault_status,
             // Vault operations
             create_vault,
             unlock_vault,
+            recover_vault,
+            restore_from_sync,
             lock_vault,
+            // Biometric unlock
+            get_biometric_status,
+            enable_biometric_unlock,
+            disable_biometric_unlock,
+            unlock_with_biometrics,
+            // Key file
+            get_key_file,
+            generate_key_file,
+            set_key_file,
+            // Duress password
+            get_duress_enabled,
+            set_duress_password,
+            // Sync conflicts
+            list_conflicts,
+            resolve_conflict,
             wipe_vault,
             // Item operations
             get_all_items,
@@ -30,19 +180,93 @@ pub fn run() {
             delete_item,
             search_items,
             get_favorites,
+            get_recent_items,
+            copy_secret,
+            get_totp,
+            copy_totp,
+            auto_type,
+            // Import
+            preview_import,
+            run_import,
+            // Backup
+            export_backup,
+            restore_backup,
+            get_auto_backup,
+            set_auto_backup,
+            run_backup_now,
+            get_storage_info,
+            compact_storage,
+            // Attachments
+            add_attachment,
+            list_attachments,
+            open_attachment,
+            delete_attachment,
             // Password generation
             generate_password_cmd,
             generate_passphrase_cmd,
+            get_generator_options,
+            generate_with_options,
+            list_generator_presets,
+            save_generator_preset,
+            delete_generator_preset,
+            record_generated_password,
+            list_generated_history,
+            purge_generated_history,
+            get_audit_report,
+            check_breaches,
             // Settings
+            get_settings,
+            update_settings,
             get_auto_lock_timeout,
             set_auto_lock_timeout,
             check_auto_lock,
+            report_activity,
+            get_lock_countdown,
+            get_clipboard_timeout,
+            set_clipboard_timeout,
+            get_capture_protection,
+            set_capture_protection,
+            get_icon,
+            get_icon_fetching,
+            set_icon_fetching,
+            get_palette_shortcut,
+            set_palette_shortcut,
+            hide_palette,
+            get_browser_integration,
+            set_browser_integration,
             // Sync
             get_sync_status,
+            is_sync_enabled,
             enable_sync,
             disable_sync,
             trigger_sync,
+            get_sync_interval,
+            set_sync_interval,
             check_remote_commands,
+            // Deep links
+            take_deep_link,
+            // Activity log
+            get_activity_log,
+            // Diagnostics
+            get_recent_logs,
+            check_for_updates,
+            install_update,
+            // Emergency access
+            add_emergency_contact,
+            list_emergency_contacts,
+            remove_emergency_contact,
+            accept_emergency_invitation,
+            request_emergency_access,
+            list_emergency_requests,
+            deny_emergency_request,
+            get_emergency_vault_access,
+            open_emergency_vault,
+            get_emergency_vault_info,
+            list_emergency_vault_items,
+            copy_emergency_secret,
+            export_emergency_vault,
+            close_emergency_vault,
+            get_emergency_logs,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,146 @@
+use crate::biometric::{self, BiometricError};
+use crate::storage::{Storage, StorageError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Settings key holding the [`FailedUnlocks`] record
+const FAILED_UNLOCKS_KEY: &str = "failed_unlocks";
+
+/// Failed attempts allowed before unlocking is delayed
+pub const FREE_ATTEMPTS: u32 = 3;
+
+/// Delay after the first failure past [`FREE_ATTEMPTS`]; doubles with each one after
+const BASE_DELAY_SECS: i64 = 5;
+
+const MAX_DELAY_SECS: i64 = 15 * 60;
+
+/// Lowest failure count the self-wipe policy accepts
+pub const MIN_WIPE_THRESHOLD: u32 = 5;
+
+/// Emitted after the local vault was wiped for too many failed unlocks
+pub const VAULT_WIPED_EVENT: &str = "vault-wiped";
+
+#[derive(Error, Debug)]
+pub enum LockoutError {
+    #[error("Too many failed attempts. Try again in {0} seconds")]
+    Throttled(i64),
+
+    #[error("Too many failed attempts. The vault was erased from this device")]
+    Wiped,
+
+    #[error("Invalid failed unlock record: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Biometric(#[from] BiometricError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+pub type Result<T> = std::result::Result<T, LockoutError>;
+
+/// Consecutive failed unlocks, kept in storage so restarting doesn't reset them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedUnlocks {
+    pub count: u32,
+    /// When the last attempt failed (Unix epoch seconds)
+    pub last_failed_at: i64,
+}
+
+impl FailedUnlocks {
+    pub fn load(storage: &Storage) -> Result<Self> {
+        Ok(storage
+            .get_setting(FAILED_UNLOCKS_KEY)?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default())
+    }
+
+    fn save(&self, storage: &Storage) -> Result<()> {
+        storage.set_setting(FAILED_UNLOCKS_KEY, &serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Wait required after the last failure before trying again
+    pub fn delay(&self) -> i64 {
+        match self.count.checked_sub(FREE_ATTEMPTS) {
+            None => 0,
+            Some(extra) => BASE_DELAY_SECS
+                .saturating_mul(1 << extra.min(16))
+                .min(MAX_DELAY_SECS),
+        }
+    }
+
+    /// Seconds until the next attempt is allowed
+    ///
+    /// Never more than the full delay, so setting the clock back doesn't
+    /// lock the user out for longer.
+    pub fn retry_after(&self, now: i64) -> i64 {
+        let delay = self.delay();
+        (self.last_failed_at + delay - now).clamp(0, delay)
+    }
+}
+
+/// Refuse an attempt made before the current delay has passed
+pub fn check(storage: &Storage, now: i64) -> Result<()> {
+    match FailedUnlocks::load(storage)?.retry_after(now) {
+        0 => Ok(()),
+        wait => Err(LockoutError::Throttled(wait)),
+    }
+}
+
+/// Count a failed attempt, returning the updated record
+pub fn record_failure(storage: &Storage, now: i64) -> Result<FailedUnlocks> {
+    let mut failures = FailedUnlocks::load(storage)?;
+    failures.count += 1;
+    failures.last_failed_at = now;
+    failures.save(storage)?;
+    Ok(failures)
+}
+
+/// Forget failed attempts after a successful unlock
+pub fn reset(storage: &Storage) -> Result<()> {
+    storage.delete_setting(FAILED_UNLOCKS_KEY)?;
+    Ok(())
+}
+
+/// Erase the local vault, leaving it restorable from sync
+///
+/// Biometric unlock is removed as well, since it would still open the vault
+/// once restored.
+pub fn wipe(storage: &Storage) -> Result<()> {
+    storage.wipe_local_vault()?;
+    biometric::unenroll(storage)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failures(count: u32) -> FailedUnlocks {
+        FailedUnlocks {
+            count,
+            last_failed_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_delay() {
+        assert_eq!(failures(0).delay(), 0);
+        assert_eq!(failures(FREE_ATTEMPTS - 1).delay(), 0);
+        assert_eq!(failures(FREE_ATTEMPTS).delay(), BASE_DELAY_SECS);
+        assert_eq!(failures(FREE_ATTEMPTS + 2).delay(), BASE_DELAY_SECS * 4);
+        assert_eq!(failures(100).delay(), MAX_DELAY_SECS);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let failed = failures(FREE_ATTEMPTS + 1);
+        assert_eq!(failed.retry_after(1_000), 10);
+        assert_eq!(failed.retry_after(1_004), 6);
+        assert_eq!(failed.retry_after(1_010), 0);
+        // Clock set back
+        assert_eq!(failed.retry_after(0), 10);
+    }
+}
@@ -0,0 +1,282 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Environment variable overriding the log filter, e.g. `KEYDROP_LOG=debug`
+const LOG_FILTER_ENV: &str = "KEYDROP_LOG";
+
+const DEFAULT_FILTER: &str = "keydrop_desktop_lib=info,warn";
+
+/// Log entries kept in memory for [`RecentLogs::recent`]
+const MAX_RECENT_LOGS: usize = 1000;
+
+/// Replacement for anything that looks like a secret
+const REDACTED: &str = "[redacted]";
+
+/// Field and parameter names whose values are never logged
+const SENSITIVE_NAMES: [&str; 6] = ["password", "passphrase", "secret", "token", "key", "auth"];
+
+/// Unbroken runs of this length or more with both letters and digits are
+/// treated as keys, tokens or ciphertext
+const MIN_SECRET_RUN: usize = 24;
+
+/// A log line as shown in support diagnostics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogEntry {
+    /// Unix epoch seconds
+    pub timestamp: u64,
+    pub level: String,
+    pub target: String,
+    /// Already redacted
+    pub message: String,
+}
+
+/// The latest redacted log entries, shared with the `get_recent_logs` command
+#[derive(Clone, Default)]
+pub struct RecentLogs {
+    entries: Arc<Mutex<VecDeque<LogEntry>>>,
+}
+
+impl RecentLogs {
+    fn push(&self, entry: LogEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == MAX_RECENT_LOGS {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Up to `limit` most recent entries, oldest first
+    pub fn recent(&self, limit: usize) -> Vec<LogEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .skip(entries.len().saturating_sub(limit))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Install the global subscriber and a panic hook, both redacting secrets
+///
+/// Every log line goes through [`redact`] before it reaches stderr or the
+/// in-memory buffer, and panics are logged through the same path instead of
+/// the default hook, which would print the raw panic message.
+pub fn init() -> RecentLogs {
+    let recent = RecentLogs::default();
+    let filter =
+        EnvFilter::try_from_env(LOG_FILTER_ENV).unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(RedactingLayer {
+            recent: recent.clone(),
+        })
+        .try_init();
+
+    std::panic::set_hook(Box::new(|info| {
+        tracing::error!(target: "panic", "{}", info);
+    }));
+    recent
+}
+
+/// Writes events to stderr and [`RecentLogs`], with secrets removed
+struct RedactingLayer {
+    recent: RecentLogs,
+}
+
+impl<S: Subscriber> Layer<S> for RedactingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        let entry = LogEntry {
+            timestamp: now_secs(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: redact(&visitor.finish()),
+        };
+        eprintln!("{:>5} {}: {}", entry.level, entry.target, entry.message);
+        self.recent.push(entry);
+    }
+}
+
+/// Collects an event's message and fields, dropping sensitive field values
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn field(&mut self, field: &Field, value: fmt::Arguments<'_>) {
+        if field.name() == "message" {
+            let _ = self.message.write_fmt(value);
+        } else if is_sensitive(field.name()) {
+            let _ = write!(self.fields, " {}={}", field.name(), REDACTED);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn finish(self) -> String {
+        self.message + &self.fields
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.field(field, format_args!("{}", value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.field(field, format_args!("{:?}", value));
+    }
+}
+
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_NAMES
+        .iter()
+        .any(|sensitive| name.contains(sensitive))
+}
+
+/// Remove anything that looks like a secret from free text
+///
+/// Catches values after sensitive names (`password=…`, `"token": "…"`),
+/// bearer credentials, and long letter-and-digit runs such as keys, tokens
+/// and ciphertext. UUIDs are kept, since item and device ids help debugging.
+pub fn redact(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut hide_next = false;
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let start = rest.find(is_word_char).unwrap_or(rest.len());
+        let (gap, tail) = rest.split_at(start);
+        out.push_str(gap);
+        if tail.is_empty() {
+            break;
+        }
+        let end = tail.find(|c| !is_word_char(c)).unwrap_or(tail.len());
+        let (word, tail) = tail.split_at(end);
+        rest = tail;
+
+        if word.eq_ignore_ascii_case("bearer") {
+            out.push_str(word);
+            hide_next = true;
+            continue;
+        }
+        // A value directly follows its name, separated by `=`, `:` and quotes
+        if hide_next && gap.chars().all(|c| "=: \"'".contains(c)) {
+            out.push_str(REDACTED);
+            hide_next = false;
+            continue;
+        }
+        hide_next = is_sensitive(word) && value_follows(tail);
+
+        if looks_secret(word) {
+            out.push_str(REDACTED);
+            // Base64 padding
+            rest = rest.trim_start_matches('=');
+        } else {
+            out.push_str(word);
+        }
+    }
+    out
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "+/_-.".contains(c)
+}
+
+/// Whether a sensitive name is followed by `=` or `:` and then its value
+fn value_follows(tail: &str) -> bool {
+    let tail = tail.trim_start_matches(['"', '\'']).trim_start();
+    tail.starts_with('=') || tail.starts_with(':')
+}
+
+fn looks_secret(word: &str) -> bool {
+    let word = word.trim_end_matches('.');
+    word.len() >= MIN_SECRET_RUN
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().any(|c| c.is_ascii_alphabetic())
+        && !is_uuid(word)
+        && !word.starts_with('/')
+}
+
+fn is_uuid(word: &str) -> bool {
+    let groups: Vec<&str> = word.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_named_values() {
+        assert_eq!(
+            redact("unlock failed: password=hunter2, attempts=3"),
+            "unlock failed: password=[redacted], attempts=3"
+        );
+        assert_eq!(
+            redact(r#"{"access_token": "abc", "device": "laptop"}"#),
+            r#"{"access_token": "[redacted]", "device": "laptop"}"#
+        );
+        assert_eq!(
+            redact("Authorization: Bearer eyJhbGciOi"),
+            "Authorization: Bearer [redacted]"
+        );
+        // A name alone isn't a value
+        assert_eq!(redact("Invalid password"), "Invalid password");
+    }
+
+    #[test]
+    fn test_redact_secret_runs() {
+        let key = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        assert_eq!(redact(&format!("mac key {}", key)), "mac key [redacted]");
+        assert_eq!(
+            redact("blob dGhpcyBpcyBhIHNlY3JldCB2YWx1ZTEyMw== failed"),
+            "blob [redacted] failed"
+        );
+
+        let kept = "item 6f1c2b1e-8d4a-4c7e-9f3b-2a5d7e9c1b40 at https://example.com/a/b";
+        assert_eq!(redact(kept), kept);
+    }
+
+    #[test]
+    fn test_recent_logs() {
+        let recent = RecentLogs::default();
+        for i in 0..MAX_RECENT_LOGS + 5 {
+            recent.push(LogEntry {
+                timestamp: i as u64,
+                level: "INFO".to_string(),
+                target: "test".to_string(),
+                message: i.to_string(),
+            });
+        }
+
+        let last = recent.recent(2);
+        assert_eq!(last.len(), 2);
+        assert_eq!(last[1].timestamp, (MAX_RECENT_LOGS + 4) as u64);
+        assert_eq!(recent.recent(usize::MAX).len(), MAX_RECENT_LOGS);
+    }
+}
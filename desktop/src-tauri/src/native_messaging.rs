@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+/// Native messaging host name registered with browsers
+pub const HOST_NAME: &str = "com.keydrop.native";
+
+/// Largest message accepted from the browser or relayed back to it
+///
+/// Browsers cap host-to-extension messages at 1 MB.
+pub const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+/// Request from the browser extension
+///
+/// Sent as `{"id": ..., "action": "find_by_url", "url": "..."}`; `id` is
+/// optional and echoed back so the extension can match replies.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Request {
+    /// Whether a vault exists and is unlocked
+    Status,
+    /// Credentials whose URL matches `url`, without secrets
+    FindByUrl { url: String },
+    /// Bring up the desktop unlock screen
+    RequestUnlock,
+    /// Username and password of an item, which must match `url`
+    GetCredentials { item_id: String, url: String },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequestEnvelope {
+    #[serde(default)]
+    pub id: Value,
+    #[serde(flatten)]
+    pub request: Request,
+}
+
+/// Reply to a [`Request`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub id: Value,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    pub fn ok(id: Value, result: Value) -> Self {
+        Self {
+            id,
+            ok: true,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn error(id: Value, message: impl Into<String>) -> Self {
+        Self {
+            id,
+            ok: false,
+            result: None,
+            error: Some(message.into()),
+        }
+    }
+}
+
+/// Read one length-prefixed message from the browser; `None` at end of input
+pub fn read_frame(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_ne_bytes(len) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes exceeds the limit", len),
+        ));
+    }
+    let mut message = vec![0u8; len];
+    reader.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+/// Write one length-prefixed message to the browser
+pub fn write_frame(writer: &mut impl Write, message: &[u8]) -> io::Result<()> {
+    if message.len() > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes exceeds the limit", message.len()),
+        ));
+    }
+    writer.write_all(&(message.len() as u32).to_ne_bytes())?;
+    writer.write_all(message)?;
+    writer.flush()
+}
+
+/// Where the running app listens for the native messaging host
+///
+/// A Unix socket in the app data directory, or a per-user named pipe on Windows.
+#[cfg(unix)]
+pub fn bridge_endpoint() -> Option<PathBuf> {
+    Some(
+        dirs::data_dir()?
+            .join("keydrop")
+            .join("browser-bridge.sock"),
+    )
+}
+
+/// Where the running app listens for the native messaging host
+///
+/// A Unix socket in the app data directory, or a per-user named pipe on Windows.
+#[cfg(windows)]
+pub fn bridge_endpoint() -> Option<PathBuf> {
+    let user = std::env::var("USERNAME").ok()?;
+    Some(PathBuf::from(format!(
+        r"\\.\pipe\keydrop-browser-bridge-{}",
+        user
+    )))
+}
+
+/// Client side of the bridge, used by the native messaging host
+#[cfg(unix)]
+pub type BridgeStream = std::os::unix::net::UnixStream;
+
+/// Client side of the bridge, used by the native messaging host
+#[cfg(windows)]
+pub type BridgeStream = std::fs::File;
+
+/// Connect to the running app's bridge
+pub fn connect_bridge() -> io::Result<BridgeStream> {
+    let endpoint = bridge_endpoint()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no bridge endpoint"))?;
+
+    #[cfg(unix)]
+    return BridgeStream::connect(endpoint);
+
+    #[cfg(windows)]
+    return std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(endpoint);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, br#"{"action":"status"}"#).unwrap();
+        write_frame(&mut buffer, b"{}").unwrap();
+
+        let mut reader = buffer.as_slice();
+        assert_eq!(
+            read_frame(&mut reader).unwrap().unwrap(),
+            br#"{"action":"status"}"#
+        );
+        assert_eq!(read_frame(&mut reader).unwrap().unwrap(), b"{}");
+        assert!(read_frame(&mut reader).unwrap().is_none());
+
+        let oversized = ((MAX_MESSAGE_SIZE + 1) as u32).to_ne_bytes();
+        assert!(read_frame(&mut oversized.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_request_envelope() {
+        let envelope: RequestEnvelope = serde_json::from_str(
+            r#"{"id": 7, "action": "get_credentials", "item_id": "abc", "url": "https://example.com"}"#,
+        )
+        .unwrap();
+        assert_eq!(envelope.id, Value::from(7));
+        assert_eq!(
+            envelope.request,
+            Request::GetCredentials {
+                item_id: "abc".to_string(),
+                url: "https://example.com".to_string(),
+            }
+        );
+
+        let envelope: RequestEnvelope = serde_json::from_str(r#"{"action": "status"}"#).unwrap();
+        assert_eq!(envelope.id, Value::Null);
+        assert_eq!(envelope.request, Request::Status);
+
+        assert!(serde_json::from_str::<RequestEnvelope>(r#"{"action": "wipe"}"#).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::capture;
+use crate::state::AppState;
+use crate::tray;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+/// Window label of the quick-search palette
+pub const PALETTE_LABEL: &str = "palette";
+
+/// Shortcut used until the user picks another one
+pub const DEFAULT_PALETTE_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+
+/// Settings key holding the user's palette shortcut
+pub const PALETTE_SHORTCUT_SETTING: &str = "palette_shortcut";
+
+/// Global shortcut handler registered with the plugin
+pub fn handle_shortcut(app: &AppHandle, _shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() == ShortcutState::Pressed {
+        toggle_palette(app);
+    }
+}
+
+/// Make `shortcut` the only registered global shortcut
+///
+/// If it can't be registered (bad syntax, taken by another app), `previous`
+/// is restored so the palette stays reachable.
+pub fn register_shortcut(
+    app: &AppHandle,
+    shortcut: &str,
+    previous: Option<&str>,
+) -> Result<(), tauri_plugin_global_shortcut::Error> {
+    let global_shortcut = app.global_shortcut();
+    global_shortcut.unregister_all()?;
+    if let Err(e) = global_shortcut.register(shortcut) {
+        if let Some(previous) = previous {
+            let _ = global_shortcut.register(previous);
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Show the palette, or hide it if it's already up
+///
+/// While the vault is locked the main window is shown instead, since there is
+/// nothing to search.
+pub fn toggle_palette(app: &AppHandle) {
+    if !app.state::<AppState>().is_unlocked() {
+        tray::show_main_window(app);
+        return;
+    }
+
+    if let Some(window) = app.get_webview_window(PALETTE_LABEL) {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            let _ = window.center();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        return;
+    }
+
+    let window = WebviewWindowBuilder::new(app, PALETTE_LABEL, WebviewUrl::default())
+        .title("Keydrop Search")
+        .inner_size(600.0, 360.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .content_protected(capture::should_protect(app))
+        .build();
+    if let Ok(window) = window {
+        // Behave like a popup: dismiss as soon as focus moves elsewhere
+        let palette = window.clone();
+        window.on_window_event(move |event| {
+            if let WindowEvent::Focused(false) = event {
+                let _ = palette.hide();
+            }
+        });
+    }
+}
+
+/// Hide the palette if it's open
+pub fn hide_palette(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(PALETTE_LABEL) {
+        let _ = window.hide();
+    }
+}
@@ -0,0 +1,77 @@
+use crate::activity::{self, ActivityKind};
+use crate::auto_lock::{self, LockReason};
+use crate::storage::Storage;
+use crate::sync::{RemoteCommand, SyncError, SyncState};
+use crate::sync_client::SyncClient;
+use crate::tray;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted to all windows after a remote command ran on this device
+pub const REMOTE_COMMAND_EVENT: &str = "remote-command";
+
+/// Payload of [`REMOTE_COMMAND_EVENT`]
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteCommandEvent {
+    pub command_id: String,
+    pub command_type: String,
+    /// Human-readable explanation for the user
+    pub message: String,
+}
+
+/// Fetch pending remote commands, execute them locally, and acknowledge each
+///
+/// Returns the commands that were executed. Unknown command types are
+/// acknowledged as failed so the server doesn't redeliver them forever.
+pub async fn process_remote_commands(app: &AppHandle) -> Result<Vec<RemoteCommand>, SyncError> {
+    let sync_state = app.state::<SyncState>();
+    let config = sync_state.get_config().ok_or(SyncError::NotEnabled)?;
+    let client = SyncClient::new(config);
+
+    let mut executed = Vec::new();
+    for command in client.pending_commands().await? {
+        let message = match command.command_type.as_str() {
+            "lock" => {
+                auto_lock::lock_vault(app, LockReason::Remote);
+                "Your vault was locked remotely from another device."
+            }
+            "wipe" => {
+                auto_lock::lock_vault(app, LockReason::Remote);
+                Storage::open()?.delete_vault()?;
+                "Your vault was wiped from this device remotely. Sign in again to restore it from sync."
+            }
+            _ => {
+                tracing::warn!("Ignoring unknown remote command {}", command.command_type);
+                client.ack_command(&command.id, false).await?;
+                continue;
+            }
+        };
+
+        tracing::info!(
+            "Executed remote {} command {}",
+            command.command_type,
+            command.id
+        );
+        client.ack_command(&command.id, true).await?;
+        // After the command, so a wipe's own entry survives it
+        activity::try_record(ActivityKind::RemoteCommand, Some(&command.command_type));
+        let _ = app.emit(
+            REMOTE_COMMAND_EVENT,
+            RemoteCommandEvent {
+                command_id: command.id.clone(),
+                command_type: command.command_type.clone(),
+                message: message.to_string(),
+            },
+        );
+
+        let wiped = command.command_type == "wipe";
+        executed.push(command);
+        if wiped {
+            // The wipe removed sync settings too; remaining commands are moot
+            sync_state.disable();
+            tray::refresh_tray(app);
+            break;
+        }
+    }
+    Ok(executed)
+}
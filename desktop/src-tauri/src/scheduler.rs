@@ -0,0 +1,108 @@
+use crate::remote_commands;
+use crate::state::AppState;
+use crate::sync::{self, SyncState, SyncStatusState};
+use crate::tray;
+use rand::Rng;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Pause after a sync request so a burst of edits goes out in one push
+const REQUEST_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// First retry delay after a failed sync
+const BACKOFF_BASE_SECS: u64 = 5;
+
+/// Longest retry delay after repeated failures
+const BACKOFF_MAX_SECS: u64 = 15 * 60;
+
+/// Retry delay while the server is unreachable, so queued changes drain soon
+/// after reconnecting
+const OFFLINE_RETRY: Duration = Duration::from_secs(15);
+
+/// Start the background task that keeps the vault in sync
+///
+/// Every `SyncState::interval` seconds and shortly after each
+/// `SyncState::request_sync`, executes pending remote commands and then syncs.
+/// Failed syncs are retried with jittered exponential backoff, or every
+/// [`OFFLINE_RETRY`] while the server is unreachable. Status changes are
+/// emitted to the frontend as `sync-status` events.
+pub fn spawn_sync_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let app_state = app.state::<AppState>();
+        let sync_state = app.state::<SyncState>();
+        let mut failures = 0u32;
+
+        loop {
+            let delay = match failures {
+                0 => Duration::from_secs(sync_state.interval()),
+                _ if sync_state.get_status().state == SyncStatusState::Offline => OFFLINE_RETRY,
+                n => backoff_delay(n),
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = sync_state.sync_requested() => tokio::time::sleep(REQUEST_DEBOUNCE).await,
+            }
+
+            if !sync_state.is_enabled() {
+                failures = 0;
+                continue;
+            }
+
+            // Lock and wipe must reach a locked vault too
+            if let Err(e) = remote_commands::process_remote_commands(&app).await {
+                tracing::warn!("Checking remote commands failed: {}", e);
+            }
+            if !sync_state.is_enabled() || !app_state.is_unlocked() {
+                failures = 0;
+                continue;
+            }
+
+            failures = match sync::run_sync(&app_state, &sync_state).await {
+                Ok(summary) => {
+                    if summary.pulled > 0 {
+                        tray::refresh_tray(&app);
+                    }
+                    0
+                }
+                Err(e) => {
+                    tracing::warn!(failures, "Sync failed: {}", e);
+                    failures.saturating_add(1)
+                }
+            };
+            let _ = app.emit("sync-status", sync_state.get_status());
+        }
+    });
+}
+
+/// Retry delay after `failures` consecutive failed syncs
+///
+/// Doubles from [`BACKOFF_BASE_SECS`] up to [`BACKOFF_MAX_SECS`], then picks a
+/// random point in the upper half so devices don't retry in lockstep.
+fn backoff_delay(failures: u32) -> Duration {
+    let exponent = failures.saturating_sub(1).min(16);
+    let ceiling = BACKOFF_BASE_SECS
+        .saturating_mul(1 << exponent)
+        .min(BACKOFF_MAX_SECS);
+    let millis = ceiling * 1000;
+    Duration::from_millis(rand::thread_rng().gen_range(millis / 2..=millis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        for _ in 0..100 {
+            let first = backoff_delay(1);
+            assert!(first >= Duration::from_millis(2500) && first <= Duration::from_secs(5));
+
+            let third = backoff_delay(3);
+            assert!(third >= Duration::from_secs(10) && third <= Duration::from_secs(20));
+
+            let capped = backoff_delay(50);
+            assert!(capped >= Duration::from_secs(BACKOFF_MAX_SECS / 2));
+            assert!(capped <= Duration::from_secs(BACKOFF_MAX_SECS));
+        }
+    }
+}
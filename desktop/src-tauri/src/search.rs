@@ -0,0 +1,147 @@
+use crate::state::AppState;
+use crate::storage::{Storage, StorageError};
+use crypto_core::vault::{ItemFilter, ItemPage, ItemSort, Vault};
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Items per page when the caller doesn't set a limit
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Order of `search_items` results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    /// Name A-Z, case-insensitive
+    #[default]
+    Name,
+    /// Most recently modified first
+    Recent,
+    /// Most recently copied or filled on this device first; never-used items
+    /// follow, most recently modified first
+    LastUsed,
+}
+
+/// A sorted page of the items matching `filter`
+///
+/// `usage` maps item IDs to when they were last used and is only consulted
+/// for [`SearchSort::LastUsed`].
+pub fn search<'a>(
+    vault: &'a Vault,
+    filter: &ItemFilter,
+    sort: SearchSort,
+    offset: usize,
+    limit: usize,
+    usage: &HashMap<String, i64>,
+) -> ItemPage<'a> {
+    match sort {
+        SearchSort::Name => vault.list_items(filter, ItemSort::Name, offset, Some(limit)),
+        SearchSort::Recent => {
+            vault.list_items(filter, ItemSort::RecentlyModified, offset, Some(limit))
+        }
+        SearchSort::LastUsed => {
+            let mut page = vault.list_items(filter, ItemSort::RecentlyModified, 0, None);
+            page.items
+                .sort_by_key(|item| Reverse(usage.get(&item.id).copied()));
+            page.items = page.items.into_iter().skip(offset).take(limit).collect();
+            page
+        }
+    }
+}
+
+/// When each item was last used, for [`SearchSort::LastUsed`]
+pub fn usage(sort: SearchSort) -> Result<HashMap<String, i64>, StorageError> {
+    match sort {
+        SearchSort::LastUsed => Storage::open()?.item_usage(),
+        _ => Ok(HashMap::new()),
+    }
+}
+
+/// Note that an item's secret was just copied, typed or filled
+///
+/// Nothing is recorded for the decoy vault, whose items aren't stored in the
+/// item table. A failure is logged rather than failing the copy.
+pub fn mark_used(state: &AppState, item_id: &str) {
+    if state.is_decoy() {
+        return;
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    if let Err(e) = Storage::open().and_then(|storage| storage.mark_item_used(item_id, now)) {
+        tracing::warn!("Recording use of item {} failed: {}", item_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_core::vault::{ItemKind, VaultItem};
+
+    fn names(page: &ItemPage) -> Vec<String> {
+        page.items.iter().map(|i| i.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_search_sorts_and_pages() {
+        let mut vault = Vault::new();
+        let mut ids = HashMap::new();
+        for (name, modified_at) in [("Charlie", 30), ("alpha", 10), ("Bravo", 20), ("Delta", 40)] {
+            let mut item = VaultItem::new(name, "user", "pass");
+            item.modified_at = modified_at;
+            ids.insert(name, item.id.clone());
+            vault.upsert_item(item);
+        }
+        let all = ItemFilter::default();
+        let none = HashMap::new();
+
+        let page = search(&vault, &all, SearchSort::Name, 1, 2, &none);
+        assert_eq!(page.total, 4);
+        assert_eq!(names(&page), ["Bravo", "Charlie"]);
+
+        let page = search(&vault, &all, SearchSort::Recent, 0, 2, &none);
+        assert_eq!(names(&page), ["Delta", "Charlie"]);
+
+        let usage = HashMap::from([(ids["alpha"].clone(), 200), (ids["Bravo"].clone(), 100)]);
+        let page = search(&vault, &all, SearchSort::LastUsed, 0, 10, &usage);
+        assert_eq!(page.total, 4);
+        assert_eq!(names(&page), ["alpha", "Bravo", "Delta", "Charlie"]);
+
+        let page = search(&vault, &all, SearchSort::LastUsed, 1, 2, &usage);
+        assert_eq!(names(&page), ["Bravo", "Delta"]);
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let mut vault = Vault::new();
+        vault.add_item(VaultItem::new("GitHub", "user", "pass").with_tag("work"));
+        vault.add_item(
+            VaultItem::new("Visa", "", "")
+                .with_kind(ItemKind::Card)
+                .with_favorite(true),
+        );
+
+        let filter = ItemFilter {
+            kind: Some(ItemKind::Card),
+            ..Default::default()
+        };
+        let page = search(&vault, &filter, SearchSort::Name, 0, 10, &HashMap::new());
+        assert_eq!(names(&page), ["Visa"]);
+
+        let filter = ItemFilter {
+            query: Some("git".to_string()),
+            tag: Some("work".to_string()),
+            ..Default::default()
+        };
+        let page = search(
+            &vault,
+            &filter,
+            SearchSort::LastUsed,
+            0,
+            10,
+            &HashMap::new(),
+        );
+        assert_eq!(page.total, 1);
+    }
+}
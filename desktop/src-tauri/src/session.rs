@@ -0,0 +1,158 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How long before an idle lock the frontend starts warning
+pub const LOCK_WARNING_SECS: u64 = 30;
+
+/// Tracks how long the user has been idle, to decide when the vault locks
+///
+/// Activity is what the user does: interacting with or focusing a window,
+/// copying from the tray, filling from the browser extension. Commands alone
+/// don't count, so windows polling for status can't keep the vault open.
+/// Where the OS reports system-wide idle time, the auto-lock task samples it
+/// too, so working in other apps also keeps the vault unlocked.
+#[derive(Default)]
+pub struct SessionTracker {
+    /// Last user activity (Unix epoch seconds, 0 = none yet)
+    last_activity: Mutex<u64>,
+    /// Latest OS idle time and when it was sampled
+    system_idle: Mutex<Option<IdleSample>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IdleSample {
+    sampled_at: u64,
+    idle_secs: u64,
+}
+
+/// Time left until the vault locks for inactivity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LockCountdown {
+    /// Idle timeout in seconds; 0 when idle locking is off
+    pub timeout: u64,
+    /// Seconds until the idle lock, or `None` when locked or idle locking is off
+    pub seconds_remaining: Option<u64>,
+    /// Whether to show the "locking soon" warning
+    pub warning: bool,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl SessionTracker {
+    /// Note user activity, e.g. a click in a window or a copy from the tray
+    pub fn record_activity(&self) {
+        self.record_activity_at(now_secs());
+    }
+
+    fn record_activity_at(&self, now: u64) {
+        *self.last_activity.lock().unwrap() = now;
+    }
+
+    /// Store the OS-reported idle time, or `None` where it isn't available
+    pub fn set_system_idle(&self, idle_secs: Option<u64>) {
+        self.set_system_idle_at(idle_secs, now_secs());
+    }
+
+    fn set_system_idle_at(&self, idle_secs: Option<u64>, now: u64) {
+        *self.system_idle.lock().unwrap() = idle_secs.map(|idle_secs| IdleSample {
+            sampled_at: now,
+            idle_secs,
+        });
+    }
+
+    /// Seconds since the user last did anything, in the app or, where the OS
+    /// reports it, anywhere on the system
+    fn idle_secs_at(&self, now: u64) -> u64 {
+        let last = *self.last_activity.lock().unwrap();
+        let app_idle = (last > 0).then(|| now.saturating_sub(last));
+        let system_idle = self
+            .system_idle
+            .lock()
+            .unwrap()
+            .map(|sample| sample.idle_secs + now.saturating_sub(sample.sampled_at));
+        match (app_idle, system_idle) {
+            (Some(app), Some(system)) => app.min(system),
+            (app, system) => app.or(system).unwrap_or(0),
+        }
+    }
+
+    /// Whether the user has been idle longer than `timeout` seconds; a timeout
+    /// of 0 disables idle locking
+    pub fn should_lock(&self, timeout: u64) -> bool {
+        timeout > 0 && self.idle_secs_at(now_secs()) > timeout
+    }
+
+    /// Time left until an idle lock with `timeout`, for an unlocked vault
+    pub fn countdown(&self, timeout: u64, unlocked: bool) -> LockCountdown {
+        self.countdown_at(timeout, unlocked, now_secs())
+    }
+
+    fn countdown_at(&self, timeout: u64, unlocked: bool, now: u64) -> LockCountdown {
+        let seconds_remaining =
+            (unlocked && timeout > 0).then(|| timeout.saturating_sub(self.idle_secs_at(now)));
+        LockCountdown {
+            timeout,
+            seconds_remaining,
+            warning: seconds_remaining.is_some_and(|secs| secs <= LOCK_WARNING_SECS),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_from_app_activity() {
+        let session = SessionTracker::default();
+        // Nothing recorded yet
+        assert_eq!(session.idle_secs_at(1000), 0);
+
+        session.record_activity_at(1000);
+        assert_eq!(session.idle_secs_at(1100), 100);
+
+        let countdown = session.countdown_at(300, true, 1100);
+        assert_eq!(countdown.seconds_remaining, Some(200));
+        assert!(!countdown.warning);
+        let countdown = session.countdown_at(300, true, 1280);
+        assert_eq!(countdown.seconds_remaining, Some(20));
+        assert!(countdown.warning);
+        assert_eq!(
+            session.countdown_at(300, true, 2000).seconds_remaining,
+            Some(0)
+        );
+
+        assert_eq!(session.countdown_at(0, true, 1100).seconds_remaining, None);
+        assert_eq!(
+            session.countdown_at(300, false, 1100).seconds_remaining,
+            None
+        );
+    }
+
+    #[test]
+    fn test_idle_from_system() {
+        let session = SessionTracker::default();
+        session.set_system_idle_at(Some(50), 1000);
+        assert_eq!(session.idle_secs_at(1000), 50);
+        session.record_activity_at(1000);
+
+        // Busy in another app: the system's idle time wins
+        session.set_system_idle_at(Some(5), 1400);
+        assert_eq!(session.idle_secs_at(1400), 5);
+        // and keeps counting between samples
+        assert_eq!(session.idle_secs_at(1410), 15);
+
+        // Activity in the app is newer than the sample
+        session.record_activity_at(1408);
+        assert_eq!(session.idle_secs_at(1410), 2);
+
+        session.set_system_idle_at(None, 1500);
+        assert_eq!(session.idle_secs_at(1500), 92);
+    }
+}
@@ -0,0 +1,352 @@
+use crate::backup::{AutoBackupConfig, BackupState, AUTO_BACKUP_SETTING};
+use crate::capture;
+use crate::clipboard::{ClipboardState, DEFAULT_CLIPBOARD_TIMEOUT};
+use crate::generator::{GeneratorOptions, GeneratorPreset, MAX_PRESET_NAME_LEN};
+use crate::lockout::MIN_WIPE_THRESHOLD;
+use crate::state::AppState;
+use crate::storage::{Storage, StorageError};
+use crate::sync::{SyncState, DEFAULT_SYNC_INTERVAL, MIN_SYNC_INTERVAL};
+use crypto_core::error::CryptoError;
+use crypto_core::password::{
+    generate_passphrase_with, generate_password, PassphraseOptions, PasswordOptions,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+
+/// Settings key holding the JSON [`Settings`]
+pub const SETTINGS_KEY: &str = "settings";
+
+/// Event emitted to all windows with the new [`Settings`] after each change
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+/// Keys that held single settings before they moved into [`SETTINGS_KEY`]
+const LEGACY_AUTO_LOCK_TIMEOUT: &str = "auto_lock_timeout";
+const LEGACY_CLIPBOARD_TIMEOUT: &str = "clipboard_timeout";
+const LEGACY_SYNC_INTERVAL: &str = "sync_interval";
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("Sync interval must be at least {MIN_SYNC_INTERVAL} seconds")]
+    SyncInterval,
+
+    #[error("Self-wipe needs at least {MIN_WIPE_THRESHOLD} failed attempts")]
+    WipeThreshold,
+
+    #[error("Invalid generator defaults: {0}")]
+    Generator(#[from] CryptoError),
+
+    #[error("Invalid generator preset: {0}")]
+    Preset(String),
+
+    #[error("Invalid settings: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+pub type Result<T> = std::result::Result<T, SettingsError>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Follow the OS appearance
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// Options the generator starts with
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorDefaults {
+    pub password: PasswordOptions,
+    pub passphrase: PassphraseOptions,
+    /// Options of the last generation, which the generator reopens with
+    pub last_used: Option<GeneratorOptions>,
+    pub presets: Vec<GeneratorPreset>,
+}
+
+impl GeneratorDefaults {
+    /// Options the generator opens with: the last used, else the password defaults
+    pub fn initial(&self) -> GeneratorOptions {
+        self.last_used
+            .clone()
+            .unwrap_or_else(|| GeneratorOptions::Password(self.password.clone()))
+    }
+}
+
+/// User preferences, stored as one JSON value
+///
+/// Fields missing from the stored JSON take their defaults, so settings added
+/// later don't invalidate what's already saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Seconds of inactivity before the vault locks
+    pub auto_lock_timeout: u64,
+    /// Seconds before a copied secret is cleared (0 = never)
+    pub clipboard_timeout: u64,
+    /// Seconds between background syncs
+    pub sync_interval: u64,
+    /// Failed unlocks after which the local vault is erased (`None` = never)
+    pub wipe_after_failures: Option<u32>,
+    /// Automatic backups, including the backup directory
+    pub auto_backup: AutoBackupConfig,
+    pub theme: Theme,
+    pub generator: GeneratorDefaults,
+    /// Hide windows from screenshots and screen shares while the vault is unlocked
+    pub capture_protection: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            auto_lock_timeout: 300,
+            clipboard_timeout: DEFAULT_CLIPBOARD_TIMEOUT,
+            sync_interval: DEFAULT_SYNC_INTERVAL,
+            wipe_after_failures: None,
+            auto_backup: AutoBackupConfig::default(),
+            theme: Theme::default(),
+            generator: GeneratorDefaults::default(),
+            capture_protection: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Load saved settings, moving any older per-key settings over on first run
+    pub fn load(storage: &Storage) -> Result<Self> {
+        if let Some(json) = storage.get_setting(SETTINGS_KEY)? {
+            return Ok(serde_json::from_str(&json)?);
+        }
+
+        let parse = |key: &str| -> Result<Option<u64>> {
+            Ok(storage
+                .get_setting(key)?
+                .and_then(|value| value.parse().ok()))
+        };
+        let defaults = Settings::default();
+        let settings = Settings {
+            auto_lock_timeout: parse(LEGACY_AUTO_LOCK_TIMEOUT)?
+                .unwrap_or(defaults.auto_lock_timeout),
+            clipboard_timeout: parse(LEGACY_CLIPBOARD_TIMEOUT)?
+                .unwrap_or(defaults.clipboard_timeout),
+            sync_interval: parse(LEGACY_SYNC_INTERVAL)?.unwrap_or(defaults.sync_interval),
+            auto_backup: storage
+                .get_setting(AUTO_BACKUP_SETTING)?
+                .and_then(|value| serde_json::from_str(&value).ok())
+                .unwrap_or_default(),
+            ..defaults
+        };
+
+        settings.save(storage)?;
+        for key in [
+            LEGACY_AUTO_LOCK_TIMEOUT,
+            LEGACY_CLIPBOARD_TIMEOUT,
+            LEGACY_SYNC_INTERVAL,
+            AUTO_BACKUP_SETTING,
+        ] {
+            storage.delete_setting(key)?;
+        }
+        Ok(settings)
+    }
+
+    pub fn save(&self, storage: &Storage) -> Result<()> {
+        storage.set_setting(SETTINGS_KEY, &serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Reject values the rest of the app can't work with
+    pub fn validate(&self) -> Result<()> {
+        if self.sync_interval < MIN_SYNC_INTERVAL {
+            return Err(SettingsError::SyncInterval);
+        }
+        if self
+            .wipe_after_failures
+            .is_some_and(|limit| limit < MIN_WIPE_THRESHOLD)
+        {
+            return Err(SettingsError::WipeThreshold);
+        }
+        // The generator checks its own options
+        generate_password(&self.generator.password)?;
+        generate_passphrase_with(&self.generator.passphrase)?;
+
+        let presets = &self.generator.presets;
+        for (i, preset) in presets.iter().enumerate() {
+            if preset.name.trim().is_empty() || preset.name.len() > MAX_PRESET_NAME_LEN {
+                return Err(SettingsError::Preset(format!(
+                    "names must be 1-{} characters",
+                    MAX_PRESET_NAME_LEN
+                )));
+            }
+            if presets[..i].iter().any(|other| other.name == preset.name) {
+                return Err(SettingsError::Preset(format!(
+                    "\"{}\" is used twice",
+                    preset.name
+                )));
+            }
+            preset
+                .options
+                .generate()
+                .map_err(|e| SettingsError::Preset(format!("\"{}\": {}", preset.name, e)))?;
+        }
+        Ok(())
+    }
+
+    /// Apply a partial JSON update; nested objects are merged field by field
+    pub fn merged(&self, patch: Value) -> Result<Self> {
+        let mut value = serde_json::to_value(self)?;
+        merge(&mut value, patch);
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// Current settings, loaded at startup
+pub struct SettingsState {
+    settings: Mutex<Settings>,
+}
+
+impl SettingsState {
+    pub fn new() -> Self {
+        Self {
+            settings: Mutex::new(Settings::default()),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+}
+
+impl Default for SettingsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Load saved settings into managed state and the services that use them
+pub fn load(app: &AppHandle, storage: &Storage) -> Result<()> {
+    let settings = Settings::load(storage)?;
+    apply(app, &settings);
+    *app.state::<SettingsState>().settings.lock().unwrap() = settings;
+    Ok(())
+}
+
+/// Change settings, save them, and notify every window
+///
+/// Nothing changes if the result fails validation.
+pub fn update(app: &AppHandle, change: impl FnOnce(&mut Settings)) -> Result<Settings> {
+    let state = app.state::<SettingsState>();
+    let mut current = state.settings.lock().unwrap();
+    let mut settings = current.clone();
+    change(&mut settings);
+    settings.validate()?;
+
+    settings.save(&Storage::open()?)?;
+    apply(app, &settings);
+    *current = settings.clone();
+    drop(current);
+
+    let _ = app.emit(SETTINGS_CHANGED_EVENT, &settings);
+    Ok(settings)
+}
+
+/// Push settings into the services that keep their own copy
+fn apply(app: &AppHandle, settings: &Settings) {
+    *app.state::<AppState>().auto_lock_timeout.lock().unwrap() = settings.auto_lock_timeout;
+    *app.state::<ClipboardState>().timeout.lock().unwrap() = settings.clipboard_timeout;
+    *app.state::<SyncState>().interval.lock().unwrap() = settings.sync_interval;
+    let backup_state = app.state::<BackupState>();
+    if backup_state.config() != settings.auto_backup {
+        backup_state.set_config(settings.auto_backup.clone());
+    }
+    capture::apply(app, settings.capture_protection);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merged() {
+        let settings = Settings::default();
+        let merged = settings
+            .merged(json!({
+                "theme": "dark",
+                "generator": { "password": { "length": 24 } },
+            }))
+            .unwrap();
+
+        assert_eq!(merged.theme, Theme::Dark);
+        assert_eq!(merged.generator.password.length, 24);
+        assert!(merged.generator.password.symbols);
+        assert_eq!(merged.sync_interval, settings.sync_interval);
+        assert!(merged.capture_protection);
+        let merged = settings
+            .merged(json!({ "capture_protection": false }))
+            .unwrap();
+        assert!(!merged.capture_protection);
+
+        assert!(settings.merged(json!({ "theme": "neon" })).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Settings::default().validate().is_ok());
+
+        let settings = Settings {
+            sync_interval: MIN_SYNC_INTERVAL - 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsError::SyncInterval)
+        ));
+
+        let settings = Settings {
+            wipe_after_failures: Some(MIN_WIPE_THRESHOLD - 1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsError::WipeThreshold)
+        ));
+
+        let mut settings = Settings::default();
+        settings.generator.password.length = 0;
+        assert!(matches!(
+            settings.validate(),
+            Err(SettingsError::Generator(_))
+        ));
+
+        let mut settings = Settings::default();
+        let preset = GeneratorPreset {
+            name: "Passphrase-5".to_string(),
+            options: GeneratorOptions::Passphrase(PassphraseOptions::default()),
+        };
+        settings.generator.presets = vec![preset.clone(), preset];
+        assert!(matches!(settings.validate(), Err(SettingsError::Preset(_))));
+    }
+}
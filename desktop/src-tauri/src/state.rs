@@ -1,3 +1,4 @@
+use crate::session::SessionTracker;
 use crypto_core::kdf::KeySet;
 use crypto_core::vault::Vault;
 use std::sync::Mutex;
@@ -12,8 +13,10 @@ pub struct AppState {
     pub salt: Mutex<Option<[u8; 16]>>,
     /// Auto-lock timeout in seconds
     pub auto_lock_timeout: Mutex<u64>,
-    /// Last activity timestamp
-    pub last_activity: Mutex<u64>,
+    /// User activity, for idle locking
+    pub session: SessionTracker,
+    /// Whether the open vault is the decoy unlocked by the duress password
+    pub decoy: Mutex<bool>,
 }
 
 impl AppState {
@@ -23,7 +26,8 @@ impl AppState {
             keys: Mutex::new(None),
             salt: Mutex::new(None),
             auto_lock_timeout: Mutex::new(300), // 5 minutes default
-            last_activity: Mutex::new(0),
+            session: SessionTracker::default(),
+            decoy: Mutex::new(false),
         }
     }
 
@@ -34,24 +38,11 @@ impl AppState {
     pub fn lock(&self) {
         *self.vault.lock().unwrap() = None;
         *self.keys.lock().unwrap() = None;
+        *self.decoy.lock().unwrap() = false;
     }
 
-    pub fn touch(&self) {
-        *self.last_activity.lock().unwrap() = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-    }
-
-    pub fn should_auto_lock(&self) -> bool {
-        let last = *self.last_activity.lock().unwrap();
-        let timeout = *self.auto_lock_timeout.lock().unwrap();
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        last > 0 && now - last > timeout
+    pub fn is_decoy(&self) -> bool {
+        *self.decoy.lock().unwrap()
     }
 }
 
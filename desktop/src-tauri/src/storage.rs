@@ -1,6 +1,12 @@
-use rusqlite::{Connection, Result as SqliteResult};
-use std::path::PathBuf;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rusqlite::{Connection, OptionalExtension, Result as SqliteResult};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use thiserror::Error;
+use zeroize::Zeroizing;
 
 #[derive(Error, Debug)]
 pub enum StorageError {
@@ -13,15 +19,349 @@ pub enum StorageError {
     #[error("Vault not found")]
     VaultNotFound,
 
+    #[error("Vault data is damaged")]
+    Corrupted,
+
     #[error("Failed to get data directory")]
     NoDataDir,
+
+    #[error("Keychain error: {0}")]
+    Keychain(#[from] keyring::Error),
+
+    #[error("Local database can't be decrypted; its key is missing from the keychain")]
+    Undecryptable,
 }
 
 pub type Result<T> = std::result::Result<T, StorageError>;
 
-/// Local storage manager using SQLite
+/// Settings key holding the last server version this device synced to
+const SYNC_VERSION_KEY: &str = "sync_version";
+
+/// Settings key holding the sync server URL (the only non-secret credential)
+const SYNC_SERVER_URL_KEY: &str = "sync_server_url";
+
+/// Settings key holding the hex salt of a vault erased by [`Storage::wipe_local_vault`]
+const WIPED_SALT_KEY: &str = "wiped_vault_salt";
+
+/// Service name Keydrop's entries are filed under in the OS keychain
+const KEYCHAIN_SERVICE: &str = "com.keydrop.desktop";
+
+/// Secrets kept in the OS keychain rather than the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Secret {
+    AccessToken,
+    RefreshToken,
+    DeviceId,
+    /// Key-encryption key for biometric unlock
+    BiometricKey,
+    /// SQLCipher key for the local database
+    DatabaseKey,
+}
+
+impl Secret {
+    /// Secrets removed by [`Keychain::clear`]
+    ///
+    /// The database key is left out: the database file outlives a vault reset.
+    const ALL: [Secret; 4] = [
+        Secret::AccessToken,
+        Secret::RefreshToken,
+        Secret::DeviceId,
+        Secret::BiometricKey,
+    ];
+
+    fn account(self) -> &'static str {
+        match self {
+            Secret::AccessToken => "access_token",
+            Secret::RefreshToken => "refresh_token",
+            Secret::DeviceId => "device_id",
+            Secret::BiometricKey => "biometric_key",
+            Secret::DatabaseKey => "database_key",
+        }
+    }
+}
+
+/// OS keychain access (Keychain on macOS, Credential Manager on Windows,
+/// Secret Service on Linux)
+pub struct Keychain;
+
+impl Keychain {
+    fn entry(secret: Secret) -> Result<keyring::Entry> {
+        Ok(keyring::Entry::new(KEYCHAIN_SERVICE, secret.account())?)
+    }
+
+    /// Read a secret, or `None` if it was never stored
+    pub fn get(secret: Secret) -> Result<Option<String>> {
+        match Self::entry(secret)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store a secret, replacing any previous value
+    pub fn set(secret: Secret, value: &str) -> Result<()> {
+        Ok(Self::entry(secret)?.set_password(value)?)
+    }
+
+    /// Remove a secret; missing entries are not an error
+    pub fn delete(secret: Secret) -> Result<()> {
+        match Self::entry(secret)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Remove every Keydrop secret
+    pub fn clear() -> Result<()> {
+        Secret::ALL.into_iter().try_for_each(Self::delete)
+    }
+}
+
+/// Sync credentials persisted across restarts
+#[derive(Debug, Clone)]
+pub struct SyncCredentials {
+    pub server_url: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub device_id: String,
+}
+
+/// Kind of local change waiting to be pushed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOp {
+    Add,
+    Update,
+    Delete,
+}
+
+impl ChangeOp {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeOp::Add => "add",
+            ChangeOp::Update => "update",
+            ChangeOp::Delete => "delete",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "add" => ChangeOp::Add,
+            "delete" => ChangeOp::Delete,
+            _ => ChangeOp::Update,
+        }
+    }
+
+    /// Collapse a new change onto one already queued for the same item
+    ///
+    /// An edit to an unsynced item is still an add; a delete always wins so the
+    /// server drops the item even if an earlier add was already pushed.
+    fn then(self, next: ChangeOp) -> ChangeOp {
+        match (self, next) {
+            (_, ChangeOp::Delete) => ChangeOp::Delete,
+            (ChangeOp::Add, _) => ChangeOp::Add,
+            (ChangeOp::Delete, _) => ChangeOp::Update,
+            (ChangeOp::Update, next) => next,
+        }
+    }
+}
+
+/// A local item change waiting to be pushed to the sync server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingChange {
+    pub item_id: String,
+    pub op: ChangeOp,
+    /// When the change was queued (Unix epoch milliseconds)
+    pub queued_at: i64,
+}
+
+/// A sync conflict waiting for the user to pick a side
+///
+/// Both sides are encrypted item blobs (base64); `None` means that side
+/// deleted the item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredConflict {
+    pub item_id: String,
+    pub local_data: Option<String>,
+    pub remote_data: Option<String>,
+    /// When the conflict was found (Unix epoch seconds)
+    pub detected_at: i64,
+}
+
+/// Cached favicon for a domain
+///
+/// `data` is `None` when the site had no usable icon, so it isn't re-fetched
+/// until the entry goes stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredIcon {
+    pub domain: String,
+    pub data: Option<Vec<u8>>,
+    pub content_type: Option<String>,
+    /// When the icon was downloaded (Unix epoch seconds)
+    pub fetched_at: i64,
+}
+
+/// Generator history entry; `encrypted_entry` is encrypted with the vault key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredGenerated {
+    pub id: i64,
+    pub encrypted_entry: String,
+    /// When the password was generated (Unix epoch seconds)
+    pub created_at: i64,
+}
+
+/// Vault item encrypted on its own, in the same form the sync server stores it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredItem {
+    pub item_id: String,
+    /// Base64 encrypted item blob
+    pub encrypted_item: String,
+    /// The item's own modification time (Unix epoch seconds)
+    pub modified_at: i64,
+}
+
+/// File attached to a vault item
+///
+/// The name and contents are encrypted with the attachment's own key, which
+/// is stored wrapped with the vault key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredAttachment {
+    pub id: String,
+    pub item_id: String,
+    pub wrapped_key: Vec<u8>,
+    pub encrypted_name: Vec<u8>,
+    /// Plaintext size in bytes
+    pub size: i64,
+    /// When the file was attached (Unix epoch seconds)
+    pub created_at: i64,
+}
+
+/// How much space the database and what it holds take up, in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageStats {
+    /// Encrypted vault blob, its previous copy, and the per-item rows
+    pub vault_size: u64,
+    /// Encrypted decoy vault
+    pub decoy_size: u64,
+    /// Encrypted attachment contents
+    pub attachment_size: u64,
+    /// The whole database file
+    pub database_size: u64,
+    /// Unused pages that compacting would give back
+    pub free_size: u64,
+}
+
+/// Local activity log entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredActivity {
+    pub id: i64,
+    pub kind: String,
+    pub detail: Option<String>,
+    /// When it happened (Unix epoch seconds)
+    pub created_at: i64,
+}
+
+/// Size of the generated database key
+const DATABASE_KEY_SIZE: usize = 32;
+
+/// Database key read from the keychain, cached since storage is opened per command
+static DATABASE_KEY: Mutex<Option<Zeroizing<String>>> = Mutex::new(None);
+
+/// Hex-encoded key the database is encrypted with, created on first use
+///
+/// A missing key is only generated when there's no database yet; otherwise the
+/// existing file would become unreadable.
+fn database_key(db_path: &Path) -> Result<Zeroizing<String>> {
+    let mut cached = DATABASE_KEY.lock().unwrap();
+    if let Some(key) = cached.as_ref() {
+        return Ok(key.clone());
+    }
+
+    let key = match Keychain::get(Secret::DatabaseKey)? {
+        Some(key) => Zeroizing::new(key),
+        None if db_path.exists() && !is_plaintext(db_path)? => {
+            return Err(StorageError::Undecryptable);
+        }
+        None => {
+            let mut bytes = Zeroizing::new([0u8; DATABASE_KEY_SIZE]);
+            rand::rngs::OsRng.fill_bytes(bytes.as_mut());
+            let key: Zeroizing<String> =
+                Zeroizing::new(bytes.iter().map(|byte| format!("{:02x}", byte)).collect());
+            Keychain::set(Secret::DatabaseKey, &key)?;
+            key
+        }
+    };
+    *cached = Some(key.clone());
+    Ok(key)
+}
+
+/// SQLCipher's raw key syntax, which skips its own key derivation
+fn raw_key(key: &str) -> Zeroizing<String> {
+    Zeroizing::new(format!("x'{}'", key))
+}
+
+/// Whether a key-less connection can read the schema
+fn is_readable(conn: &Connection) -> bool {
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| {
+        row.get::<_, i64>(0)
+    })
+    .is_ok()
+}
+
+/// Whether the file at `path` is an unencrypted database from an older version
+fn is_plaintext(path: &Path) -> Result<bool> {
+    Ok(is_readable(&Connection::open(path)?))
+}
+
+/// Open the database at `path` with `key`, encrypting it first if it's plaintext
+fn open_encrypted(path: &Path, key: &str) -> Result<Connection> {
+    if path.exists() && is_plaintext(path)? {
+        encrypt_plaintext(path, key)?;
+    }
+
+    let conn = Connection::open(path)?;
+    conn.pragma_update(None, "key", raw_key(key).as_str())?;
+    if !is_readable(&conn) {
+        return Err(StorageError::Undecryptable);
+    }
+    Ok(conn)
+}
+
+/// Replace a plaintext database with an encrypted copy
+///
+/// The copy is written next to the original and renamed over it, so a crash
+/// leaves one complete database or the other.
+fn encrypt_plaintext(path: &Path, key: &str) -> Result<()> {
+    let encrypted_path = path.with_extension("db.encrypting");
+    if encrypted_path.exists() {
+        std::fs::remove_file(&encrypted_path)?;
+    }
+
+    let plain = Connection::open(path)?;
+    plain.execute(
+        "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+        rusqlite::params![encrypted_path.to_string_lossy(), raw_key(key).as_str()],
+    )?;
+    plain.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+    plain.execute("DETACH DATABASE encrypted", [])?;
+    drop(plain);
+
+    std::fs::rename(&encrypted_path, path)?;
+    Ok(())
+}
+
+/// Key for the vault blob's HMAC, derived from the database key
+fn vault_mac_key(database_key: &str) -> Zeroizing<[u8; 32]> {
+    let mut mac = <Hmac<Sha256>>::new_from_slice(database_key.as_bytes())
+        .expect("HMAC accepts any key length");
+    mac.update(b"keydrop-vault-mac");
+    Zeroizing::new(mac.finalize().into_bytes().into())
+}
+
+/// Local storage manager using SQLite, encrypted with SQLCipher
 pub struct Storage {
     conn: Connection,
+    /// Authenticates the vault blob so damage is caught before decryption
+    mac_key: Zeroizing<[u8; 32]>,
 }
 
 impl Storage {
@@ -34,16 +374,36 @@ impl Storage {
             std::fs::create_dir_all(parent)?;
         }
 
-        let conn = Connection::open(&db_path)?;
-        let storage = Self { conn };
+        let key = database_key(&db_path)?;
+        let conn = open_encrypted(&db_path, &key)?;
+        let storage = Self {
+            conn,
+            mac_key: vault_mac_key(&key),
+        };
         storage.init_schema()?;
         Ok(storage)
     }
 
+    /// Unencrypted in-memory database, for tests of modules built on storage
+    #[cfg(test)]
+    pub(crate) fn open_in_memory() -> Self {
+        let storage = Self {
+            conn: Connection::open_in_memory().unwrap(),
+            mac_key: vault_mac_key("in-memory"),
+        };
+        storage.init_schema().unwrap();
+        storage
+    }
+
+    /// Directory holding the database and other app files
+    pub fn app_dir() -> Result<PathBuf> {
+        let data_dir = dirs::data_dir().ok_or(StorageError::NoDataDir)?;
+        Ok(data_dir.join("keydrop"))
+    }
+
     /// Get the database file path
     fn get_db_path() -> Result<PathBuf> {
-        let data_dir = dirs::data_dir().ok_or(StorageError::NoDataDir)?;
-        Ok(data_dir.join("keydrop").join("vault.db"))
+        Ok(Self::app_dir()?.join("vault.db"))
     }
 
     /// Initialize database schema
@@ -54,17 +414,112 @@ impl Storage {
                 id INTEGER PRIMARY KEY CHECK (id = 1),
                 salt BLOB NOT NULL,
                 encrypted_vault BLOB,
+                vault_mac BLOB,
+                previous_vault BLOB,
+                previous_mac BLOB,
                 version INTEGER DEFAULT 1,
                 created_at INTEGER NOT NULL,
                 modified_at INTEGER NOT NULL
             );
 
+            CREATE TABLE IF NOT EXISTS vault_items (
+                item_id TEXT PRIMARY KEY,
+                encrypted_item TEXT NOT NULL,
+                item_mac BLOB,
+                modified_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS item_usage (
+                item_id TEXT PRIMARY KEY,
+                last_used_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS decoy_vault (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                salt BLOB NOT NULL,
+                encrypted_vault BLOB NOT NULL,
+                created_at INTEGER NOT NULL,
+                modified_at INTEGER NOT NULL
+            );
+
             CREATE TABLE IF NOT EXISTS settings (
                 key TEXT PRIMARY KEY,
                 value TEXT NOT NULL
             );
+
+            CREATE TABLE IF NOT EXISTS sync_queue (
+                item_id TEXT PRIMARY KEY,
+                op TEXT NOT NULL,
+                queued_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS sync_conflicts (
+                item_id TEXT PRIMARY KEY,
+                local_data TEXT,
+                remote_data TEXT,
+                detected_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS generated_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                encrypted_entry TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS activity_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                detail TEXT,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS icons (
+                domain TEXT PRIMARY KEY,
+                data BLOB,
+                content_type TEXT,
+                fetched_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS attachments (
+                id TEXT PRIMARY KEY,
+                item_id TEXT NOT NULL,
+                wrapped_key BLOB NOT NULL,
+                encrypted_name BLOB NOT NULL,
+                size INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS attachment_chunks (
+                attachment_id TEXT NOT NULL,
+                idx INTEGER NOT NULL,
+                data BLOB NOT NULL,
+                PRIMARY KEY (attachment_id, idx)
+            );
             ",
         )?;
+
+        // Databases created before vault integrity checks lack these columns
+        if self
+            .conn
+            .prepare("SELECT vault_mac FROM vault_meta LIMIT 0")
+            .is_err()
+        {
+            self.conn.execute_batch(
+                "
+                ALTER TABLE vault_meta ADD COLUMN vault_mac BLOB;
+                ALTER TABLE vault_meta ADD COLUMN previous_vault BLOB;
+                ALTER TABLE vault_meta ADD COLUMN previous_mac BLOB;
+                ",
+            )?;
+        }
+        if self
+            .conn
+            .prepare("SELECT item_mac FROM vault_items LIMIT 0")
+            .is_err()
+        {
+            self.conn
+                .execute("ALTER TABLE vault_items ADD COLUMN item_mac BLOB", [])?;
+        }
         Ok(())
     }
 
@@ -111,31 +566,307 @@ impl Storage {
     }
 
     /// Save encrypted vault data
+    ///
+    /// The previous save is kept alongside as a fallback, unless it was the
+    /// damaged copy. Both rows change in one transaction, so a crash leaves
+    /// either the old or the new state on disk.
     pub fn save_vault(&self, encrypted_data: &[u8]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.write_vault(&tx, encrypted_data)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn write_vault(&self, tx: &rusqlite::Transaction, encrypted_data: &[u8]) -> Result<()> {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs() as i64;
 
+        if self.load_vault().is_ok() {
+            tx.execute(
+                "UPDATE vault_meta SET previous_vault = encrypted_vault, previous_mac = vault_mac WHERE id = 1",
+                [],
+            )?;
+        }
+        tx.execute(
+            "UPDATE vault_meta SET encrypted_vault = ?1, vault_mac = ?2, modified_at = ?3 WHERE id = 1",
+            rusqlite::params![encrypted_data, self.vault_mac(encrypted_data).finalize().into_bytes().as_slice(), now],
+        )?;
+        Ok(())
+    }
+
+    /// Save the vault blob and replace every stored item, in one transaction
+    ///
+    /// Fails with [`StorageError::Corrupted`] while any stored item is
+    /// damaged: such an item can't have been loaded into the vault being
+    /// saved, and replacing would silently drop it. Recovery removes damaged
+    /// items explicitly with [`Storage::delete_damaged_items`] first.
+    pub fn replace_vault(&self, encrypted_data: &[u8], items: &[StoredItem]) -> Result<()> {
+        if !self.damaged_items()?.is_empty() {
+            return Err(StorageError::Corrupted);
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        self.write_vault(&tx, encrypted_data)?;
+        tx.execute("DELETE FROM vault_items", [])?;
+        self.upsert_items(&tx, items)?;
+        tx.execute(
+            "DELETE FROM item_usage WHERE item_id NOT IN (SELECT item_id FROM vault_items)",
+            [],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store changed items and drop deleted ones, in one transaction
+    pub fn write_items(&self, upserts: &[StoredItem], deletes: &[String]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.upsert_items(&tx, upserts)?;
+        for item_id in deletes {
+            tx.execute("DELETE FROM vault_items WHERE item_id = ?1", [item_id])?;
+            tx.execute("DELETE FROM item_usage WHERE item_id = ?1", [item_id])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Updates keep the row in place, so items load in the order they were added
+    fn upsert_items(&self, tx: &rusqlite::Transaction, items: &[StoredItem]) -> Result<()> {
+        let mut stmt = tx.prepare(
+            "INSERT INTO vault_items (item_id, encrypted_item, item_mac, modified_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(item_id) DO UPDATE SET encrypted_item = excluded.encrypted_item, item_mac = excluded.item_mac, modified_at = excluded.modified_at",
+        )?;
+        for item in items {
+            stmt.execute(rusqlite::params![
+                item.item_id,
+                item.encrypted_item,
+                self.item_mac(item).finalize().into_bytes().as_slice(),
+                item.modified_at
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// All stored items, oldest first
+    ///
+    /// Fails with [`StorageError::Corrupted`] if any item doesn't match its HMAC.
+    pub fn load_items(&self) -> Result<Vec<StoredItem>> {
+        self.item_rows()?
+            .into_iter()
+            .map(|(item, mac)| self.check_item(item, mac))
+            .collect()
+    }
+
+    fn item_rows(&self) -> Result<Vec<(StoredItem, Option<Vec<u8>>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT item_id, encrypted_item, modified_at, item_mac FROM vault_items ORDER BY rowid",
+        )?;
+        let rows = stmt
+            .query_map([], Self::item_from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    pub fn load_item(&self, item_id: &str) -> Result<Option<StoredItem>> {
+        self.conn
+            .query_row(
+                "SELECT item_id, encrypted_item, modified_at, item_mac FROM vault_items WHERE item_id = ?1",
+                [item_id],
+                Self::item_from_row,
+            )
+            .optional()?
+            .map(|(item, mac)| self.check_item(item, mac))
+            .transpose()
+    }
+
+    fn item_from_row(row: &rusqlite::Row) -> SqliteResult<(StoredItem, Option<Vec<u8>>)> {
+        Ok((
+            StoredItem {
+                item_id: row.get(0)?,
+                encrypted_item: row.get(1)?,
+                modified_at: row.get(2)?,
+            },
+            row.get(3)?,
+        ))
+    }
+
+    /// Items saved before integrity checks have no HMAC and are accepted
+    fn check_item(&self, item: StoredItem, expected: Option<Vec<u8>>) -> Result<StoredItem> {
+        if let Some(expected) = expected {
+            self.item_mac(&item)
+                .verify_slice(&expected)
+                .map_err(|_| StorageError::Corrupted)?;
+        }
+        Ok(item)
+    }
+
+    /// The item's HMAC covers its id too, so rows can't be swapped
+    fn item_mac(&self, item: &StoredItem) -> Hmac<Sha256> {
+        let mut mac = self.vault_mac(item.item_id.as_bytes());
+        mac.update(&[0]);
+        mac.update(item.encrypted_item.as_bytes());
+        mac
+    }
+
+    /// Ids of stored items that are damaged, in storage order
+    pub fn damaged_items(&self) -> Result<Vec<String>> {
+        Ok(self
+            .item_rows()?
+            .into_iter()
+            .filter_map(|(item, mac)| {
+                let id = item.item_id.clone();
+                self.check_item(item, mac).err().map(|_| id)
+            })
+            .collect())
+    }
+
+    /// Record that an item which matched its HMAC (or had none) still didn't decrypt
+    ///
+    /// Its HMAC is replaced with one that can never match, so the item
+    /// counts as damaged from then on.
+    pub fn mark_item_damaged(&self, item_id: &str) -> Result<()> {
         self.conn.execute(
-            "UPDATE vault_meta SET encrypted_vault = ?1, modified_at = ?2 WHERE id = 1",
-            rusqlite::params![encrypted_data, now],
+            "UPDATE vault_items SET item_mac = X'' WHERE item_id = ?1",
+            [item_id],
         )?;
         Ok(())
     }
 
-    /// Load encrypted vault data
+    /// Drop damaged items before replacing the vault from a backup
+    pub fn delete_damaged_items(&self) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for item_id in self.damaged_items()? {
+            tx.execute("DELETE FROM vault_items WHERE item_id = ?1", [&item_id])?;
+            tx.execute("DELETE FROM item_usage WHERE item_id = ?1", [&item_id])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Load encrypted vault data, failing with [`StorageError::Corrupted`] if
+    /// it doesn't match its HMAC
     pub fn load_vault(&self) -> Result<Vec<u8>> {
-        let data: Option<Vec<u8>> = self
+        self.load_checked("encrypted_vault", "vault_mac")
+    }
+
+    /// Load the vault as it was before the latest save
+    pub fn load_previous_vault(&self) -> Result<Vec<u8>> {
+        self.load_checked("previous_vault", "previous_mac")
+    }
+
+    /// Whether the saved vault is damaged and there's no intact earlier copy
+    ///
+    /// Items have no earlier copy, so any damaged item counts.
+    pub fn vault_damaged(&self) -> Result<bool> {
+        if !self.damaged_items()?.is_empty() {
+            return Ok(true);
+        }
+        match self.load_vault() {
+            Err(StorageError::Corrupted) => match self.load_previous_vault() {
+                Ok(_) => Ok(false),
+                Err(StorageError::Corrupted | StorageError::VaultNotFound) => Ok(true),
+                Err(e) => Err(e),
+            },
+            Ok(_) | Err(StorageError::VaultNotFound) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn vault_mac(&self, data: &[u8]) -> Hmac<Sha256> {
+        let mut mac = <Hmac<Sha256>>::new_from_slice(self.mac_key.as_ref())
+            .expect("HMAC accepts any key length");
+        mac.update(data);
+        mac
+    }
+
+    /// Read a vault blob and check it against its HMAC
+    ///
+    /// Blobs saved before integrity checks have no HMAC and are accepted.
+    fn load_checked(&self, data_column: &str, mac_column: &str) -> Result<Vec<u8>> {
+        let (data, expected): (Option<Vec<u8>>, Option<Vec<u8>>) = self
             .conn
             .query_row(
-                "SELECT encrypted_vault FROM vault_meta WHERE id = 1",
+                &format!(
+                    "SELECT {}, {} FROM vault_meta WHERE id = 1",
+                    data_column, mac_column
+                ),
                 [],
-                |row| row.get(0),
+                |row| Ok((row.get(0)?, row.get(1)?)),
             )
             .map_err(|_| StorageError::VaultNotFound)?;
+        let data = data.ok_or(StorageError::VaultNotFound)?;
+
+        if let Some(expected) = expected {
+            self.vault_mac(&data)
+                .verify_slice(&expected)
+                .map_err(|_| StorageError::Corrupted)?;
+        }
+        Ok(data)
+    }
+
+    /// Create or replace the decoy vault opened by the duress password
+    pub fn create_decoy(&self, salt: &[u8; 16], encrypted_data: &[u8]) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO decoy_vault (id, salt, encrypted_vault, created_at, modified_at) VALUES (1, ?1, ?2, ?3, ?3)",
+            rusqlite::params![salt.as_slice(), encrypted_data, now],
+        )?;
+        Ok(())
+    }
+
+    /// Salt of the decoy vault, if one is configured
+    pub fn get_decoy_salt(&self) -> Result<Option<[u8; 16]>> {
+        let salt: Option<Vec<u8>> = self
+            .conn
+            .query_row("SELECT salt FROM decoy_vault WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+
+        match salt {
+            Some(salt) => Ok(Some(
+                salt.try_into().map_err(|_| StorageError::VaultNotFound)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Save encrypted decoy vault data
+    pub fn save_decoy(&self, encrypted_data: &[u8]) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
 
-        data.ok_or(StorageError::VaultNotFound)
+        let updated = self.conn.execute(
+            "UPDATE decoy_vault SET encrypted_vault = ?1, modified_at = ?2 WHERE id = 1",
+            rusqlite::params![encrypted_data, now],
+        )?;
+        if updated == 0 {
+            return Err(StorageError::VaultNotFound);
+        }
+        Ok(())
+    }
+
+    /// Load encrypted decoy vault data
+    pub fn load_decoy(&self) -> Result<Vec<u8>> {
+        self.conn
+            .query_row(
+                "SELECT encrypted_vault FROM decoy_vault WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|_| StorageError::VaultNotFound)
+    }
+
+    pub fn delete_decoy(&self) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM decoy_vault WHERE id = 1", [])?;
+        Ok(())
     }
 
     /// Save a setting
@@ -148,7 +879,6 @@ impl Storage {
     }
 
     /// Get a setting
-    #[allow(dead_code)]
     pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
         let result: SqliteResult<String> = self.conn.query_row(
             "SELECT value FROM settings WHERE key = ?1",
@@ -163,43 +893,650 @@ impl Storage {
         }
     }
 
-    /// Delete vault (for remote wipe/reset)
-    pub fn delete_vault(&self) -> Result<()> {
-        self.conn
-            .execute("DELETE FROM vault_meta WHERE id = 1", [])?;
-        self.conn.execute("DELETE FROM settings", [])?;
+    /// Delete a setting
+    pub fn delete_setting(&self, key: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM settings WHERE key = ?1",
+            rusqlite::params![key],
+        )?;
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+    /// Get the server version this device last synced to (0 if never synced)
+    pub fn get_sync_version(&self) -> Result<i64> {
+        Ok(self
+            .get_setting(SYNC_VERSION_KEY)?
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0))
+    }
 
-    fn temp_storage() -> Storage {
-        let dir = tempdir().unwrap();
-        let db_path = dir.path().join("test.db");
-        let conn = Connection::open(&db_path).unwrap();
-        let storage = Storage { conn };
-        storage.init_schema().unwrap();
-        storage
+    /// Record the server version this device has synced to
+    pub fn set_sync_version(&self, version: i64) -> Result<()> {
+        self.set_setting(SYNC_VERSION_KEY, &version.to_string())
     }
 
-    #[test]
-    fn test_vault_lifecycle() {
-        let storage = temp_storage();
+    /// Persist sync credentials: tokens and device id go to the OS keychain
+    pub fn save_sync_credentials(&self, credentials: &SyncCredentials) -> Result<()> {
+        Keychain::set(Secret::AccessToken, &credentials.access_token)?;
+        Keychain::set(Secret::DeviceId, &credentials.device_id)?;
+        match &credentials.refresh_token {
+            Some(token) => Keychain::set(Secret::RefreshToken, token)?,
+            None => Keychain::delete(Secret::RefreshToken)?,
+        }
+        self.set_setting(SYNC_SERVER_URL_KEY, &credentials.server_url)
+    }
 
-        // Initially no vault
-        assert!(!storage.vault_exists().unwrap());
+    /// Load sync credentials saved by [`Storage::save_sync_credentials`]
+    ///
+    /// Returns `None` if sync was never enabled or any required part is missing.
+    pub fn load_sync_credentials(&self) -> Result<Option<SyncCredentials>> {
+        let Some(server_url) = self.get_setting(SYNC_SERVER_URL_KEY)? else {
+            return Ok(None);
+        };
+        let (Some(access_token), Some(device_id)) = (
+            Keychain::get(Secret::AccessToken)?,
+            Keychain::get(Secret::DeviceId)?,
+        ) else {
+            return Ok(None);
+        };
 
-        // Create vault
-        let salt = [1u8; 16];
-        storage.create_vault(&salt).unwrap();
-        assert!(storage.vault_exists().unwrap());
+        Ok(Some(SyncCredentials {
+            server_url,
+            access_token,
+            refresh_token: Keychain::get(Secret::RefreshToken)?,
+            device_id,
+        }))
+    }
 
-        // Get salt
-        let loaded_salt = storage.get_salt().unwrap();
+    /// Forget sync credentials
+    pub fn clear_sync_credentials(&self) -> Result<()> {
+        for secret in [Secret::AccessToken, Secret::RefreshToken, Secret::DeviceId] {
+            Keychain::delete(secret)?;
+        }
+        self.delete_setting(SYNC_SERVER_URL_KEY)
+    }
+
+    /// Queue an item change for the next sync push
+    ///
+    /// Changes to the same item collapse into one entry stamped with the latest time.
+    pub fn queue_change(&self, item_id: &str, op: ChangeOp) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+
+        let queued: SqliteResult<String> = self.conn.query_row(
+            "SELECT op FROM sync_queue WHERE item_id = ?1",
+            rusqlite::params![item_id],
+            |row| row.get(0),
+        );
+        let op = match queued {
+            Ok(existing) => ChangeOp::parse(&existing).then(op),
+            Err(rusqlite::Error::QueryReturnedNoRows) => op,
+            Err(e) => return Err(StorageError::Sqlite(e)),
+        };
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO sync_queue (item_id, op, queued_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![item_id, op.as_str(), now],
+        )?;
+        Ok(())
+    }
+
+    /// Get queued changes, oldest first
+    pub fn pending_changes(&self) -> Result<Vec<PendingChange>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT item_id, op, queued_at FROM sync_queue ORDER BY queued_at")?;
+        let changes = stmt
+            .query_map([], |row| {
+                Ok(PendingChange {
+                    item_id: row.get(0)?,
+                    op: ChangeOp::parse(&row.get::<_, String>(1)?),
+                    queued_at: row.get(2)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(changes)
+    }
+
+    /// Number of queued changes
+    pub fn pending_change_count(&self) -> Result<u32> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM sync_queue", [], |row| row.get(0))?;
+        Ok(count as u32)
+    }
+
+    /// Remove pushed changes from the queue
+    ///
+    /// Entries re-queued after the given snapshot was taken are kept.
+    pub fn dequeue_changes(&self, changes: &[PendingChange]) -> Result<()> {
+        for change in changes {
+            self.conn.execute(
+                "DELETE FROM sync_queue WHERE item_id = ?1 AND queued_at = ?2",
+                rusqlite::params![change.item_id, change.queued_at],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Record a sync conflict, replacing any earlier one for the same item
+    pub fn save_conflict(&self, conflict: &StoredConflict) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO sync_conflicts (item_id, local_data, remote_data, detected_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                conflict.item_id,
+                conflict.local_data,
+                conflict.remote_data,
+                conflict.detected_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Replace the remote side of an open conflict; returns false if there is none
+    pub fn update_conflict_remote(&self, item_id: &str, remote_data: Option<&str>) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE sync_conflicts SET remote_data = ?2 WHERE item_id = ?1",
+            rusqlite::params![item_id, remote_data],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Open conflicts, oldest first
+    pub fn conflicts(&self) -> Result<Vec<StoredConflict>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT item_id, local_data, remote_data, detected_at FROM sync_conflicts ORDER BY detected_at, item_id",
+        )?;
+        let rows = stmt.query_map([], Self::conflict_from_row)?;
+        Ok(rows.collect::<SqliteResult<Vec<_>>>()?)
+    }
+
+    pub fn conflict(&self, item_id: &str) -> Result<Option<StoredConflict>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT item_id, local_data, remote_data, detected_at FROM sync_conflicts WHERE item_id = ?1",
+                [item_id],
+                Self::conflict_from_row,
+            )
+            .optional()?)
+    }
+
+    pub fn delete_conflict(&self, item_id: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM sync_conflicts WHERE item_id = ?1", [item_id])?;
+        Ok(())
+    }
+
+    pub fn conflict_count(&self) -> Result<u32> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM sync_conflicts", [], |row| row.get(0))?;
+        Ok(count as u32)
+    }
+
+    fn conflict_from_row(row: &rusqlite::Row) -> SqliteResult<StoredConflict> {
+        Ok(StoredConflict {
+            item_id: row.get(0)?,
+            local_data: row.get(1)?,
+            remote_data: row.get(2)?,
+            detected_at: row.get(3)?,
+        })
+    }
+
+    /// Add a generator history entry, keeping only the newest `cap`
+    pub fn add_generated(&self, encrypted_entry: &str, created_at: i64, cap: usize) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO generated_history (encrypted_entry, created_at) VALUES (?1, ?2)",
+            rusqlite::params![encrypted_entry, created_at],
+        )?;
+        tx.execute(
+            "DELETE FROM generated_history WHERE id NOT IN (SELECT id FROM generated_history ORDER BY id DESC LIMIT ?1)",
+            [cap as i64],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Generator history, newest first
+    pub fn generated_history(&self) -> Result<Vec<StoredGenerated>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, encrypted_entry, created_at FROM generated_history ORDER BY id DESC",
+        )?;
+        let entries = stmt
+            .query_map([], |row| {
+                Ok(StoredGenerated {
+                    id: row.get(0)?,
+                    encrypted_entry: row.get(1)?,
+                    created_at: row.get(2)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    pub fn update_generated(&self, id: i64, encrypted_entry: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE generated_history SET encrypted_entry = ?2 WHERE id = ?1",
+            rusqlite::params![id, encrypted_entry],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_generated(&self, id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM generated_history WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    pub fn clear_generated(&self) -> Result<()> {
+        self.conn.execute("DELETE FROM generated_history", [])?;
+        Ok(())
+    }
+
+    /// Add an activity log entry, keeping only the newest `cap`
+    pub fn add_activity(
+        &self,
+        kind: &str,
+        detail: Option<&str>,
+        created_at: i64,
+        cap: usize,
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO activity_log (kind, detail, created_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![kind, detail, created_at],
+        )?;
+        tx.execute(
+            "DELETE FROM activity_log WHERE id NOT IN (SELECT id FROM activity_log ORDER BY id DESC LIMIT ?1)",
+            [cap as i64],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Activity log, newest first
+    pub fn activity(&self) -> Result<Vec<StoredActivity>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, kind, detail, created_at FROM activity_log ORDER BY id DESC")?;
+        let entries = stmt
+            .query_map([], |row| {
+                Ok(StoredActivity {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    detail: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    /// Note that an item was copied or filled on this device
+    pub fn mark_item_used(&self, item_id: &str, used_at: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO item_usage (item_id, last_used_at) VALUES (?1, ?2)",
+            rusqlite::params![item_id, used_at],
+        )?;
+        Ok(())
+    }
+
+    /// When each item was last used on this device, by item ID
+    pub fn item_usage(&self) -> Result<HashMap<String, i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT item_id, last_used_at FROM item_usage")?;
+        let usage = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqliteResult<HashMap<_, _>>>()?;
+        Ok(usage)
+    }
+
+    /// Store an attachment with the chunks `next_chunk` yields until it returns `None`
+    ///
+    /// Nothing is stored unless every chunk is, so a failed read or encryption
+    /// leaves no partial attachment behind.
+    pub fn add_attachment<E: From<StorageError>>(
+        &self,
+        attachment: &StoredAttachment,
+        mut next_chunk: impl FnMut() -> std::result::Result<Option<Vec<u8>>, E>,
+    ) -> std::result::Result<(), E> {
+        let tx = self
+            .conn
+            .unchecked_transaction()
+            .map_err(StorageError::from)?;
+        tx.execute(
+            "INSERT INTO attachments (id, item_id, wrapped_key, encrypted_name, size, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                attachment.id,
+                attachment.item_id,
+                attachment.wrapped_key,
+                attachment.encrypted_name,
+                attachment.size,
+                attachment.created_at
+            ],
+        )
+        .map_err(StorageError::from)?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO attachment_chunks (attachment_id, idx, data) VALUES (?1, ?2, ?3)",
+                )
+                .map_err(StorageError::from)?;
+            let mut idx = 0i64;
+            while let Some(chunk) = next_chunk()? {
+                stmt.execute(rusqlite::params![attachment.id, idx, chunk])
+                    .map_err(StorageError::from)?;
+                idx += 1;
+            }
+        }
+        tx.commit().map_err(StorageError::from)?;
+        Ok(())
+    }
+
+    /// All attachments, oldest first
+    pub fn attachments(&self) -> Result<Vec<StoredAttachment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, item_id, wrapped_key, encrypted_name, size, created_at
+             FROM attachments ORDER BY created_at, rowid",
+        )?;
+        let attachments = stmt
+            .query_map([], Self::attachment_from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(attachments)
+    }
+
+    pub fn attachment(&self, id: &str) -> Result<Option<StoredAttachment>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id, item_id, wrapped_key, encrypted_name, size, created_at
+                 FROM attachments WHERE id = ?1",
+                [id],
+                Self::attachment_from_row,
+            )
+            .optional()?)
+    }
+
+    fn attachment_from_row(row: &rusqlite::Row) -> SqliteResult<StoredAttachment> {
+        Ok(StoredAttachment {
+            id: row.get(0)?,
+            item_id: row.get(1)?,
+            wrapped_key: row.get(2)?,
+            encrypted_name: row.get(3)?,
+            size: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }
+
+    /// Pass an attachment's chunks to `f` in order, one at a time
+    pub fn read_attachment_chunks<E: From<StorageError>>(
+        &self,
+        id: &str,
+        mut f: impl FnMut(Vec<u8>) -> std::result::Result<(), E>,
+    ) -> std::result::Result<(), E> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data FROM attachment_chunks WHERE attachment_id = ?1 ORDER BY idx")
+            .map_err(StorageError::from)?;
+        let mut rows = stmt.query([id]).map_err(StorageError::from)?;
+        while let Some(row) = rows.next().map_err(StorageError::from)? {
+            f(row.get(0).map_err(StorageError::from)?)?;
+        }
+        Ok(())
+    }
+
+    /// Total plaintext size of all attachments, in bytes
+    pub fn attachments_size(&self) -> Result<u64> {
+        let size: i64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(size), 0) FROM attachments",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(size as u64)
+    }
+
+    /// Replace an attachment's wrapped key, e.g. after the vault key changed
+    pub fn set_attachment_key(&self, id: &str, wrapped_key: &[u8]) -> Result<()> {
+        self.conn.execute(
+            "UPDATE attachments SET wrapped_key = ?2 WHERE id = ?1",
+            rusqlite::params![id, wrapped_key],
+        )?;
+        Ok(())
+    }
+
+    /// Delete an attachment and its contents; returns false if there was none
+    pub fn delete_attachment(&self, id: &str) -> Result<bool> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM attachment_chunks WHERE attachment_id = ?1",
+            [id],
+        )?;
+        let deleted = tx.execute("DELETE FROM attachments WHERE id = ?1", [id])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    pub fn save_icon(&self, icon: &StoredIcon) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO icons (domain, data, content_type, fetched_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![icon.domain, icon.data, icon.content_type, icon.fetched_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn icon(&self, domain: &str) -> Result<Option<StoredIcon>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT domain, data, content_type, fetched_at FROM icons WHERE domain = ?1",
+                [domain],
+                |row| {
+                    Ok(StoredIcon {
+                        domain: row.get(0)?,
+                        data: row.get(1)?,
+                        content_type: row.get(2)?,
+                        fetched_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    /// Sizes of the vault, attachments and database file
+    pub fn stats(&self) -> Result<StorageStats> {
+        let size = |sql: &str| -> Result<u64> {
+            let size: i64 = self.conn.query_row(sql, [], |row| row.get(0))?;
+            Ok(size as u64)
+        };
+        let page_size = size("PRAGMA page_size")?;
+        Ok(StorageStats {
+            vault_size: size(
+                "SELECT COALESCE(SUM(LENGTH(encrypted_vault)) + SUM(COALESCE(LENGTH(previous_vault), 0)), 0) FROM vault_meta",
+            )? + size("SELECT COALESCE(SUM(LENGTH(encrypted_item)), 0) FROM vault_items")?,
+            decoy_size: size("SELECT COALESCE(SUM(LENGTH(encrypted_vault)), 0) FROM decoy_vault")?,
+            attachment_size: size("SELECT COALESCE(SUM(LENGTH(data)), 0) FROM attachment_chunks")?,
+            database_size: size("PRAGMA page_count")? * page_size,
+            free_size: size("PRAGMA freelist_count")? * page_size,
+        })
+    }
+
+    /// Delete rows nothing refers to any more: chunks of deleted attachments
+    /// and usage times of deleted items. Returns how many rows went.
+    pub fn remove_orphans(&self) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let removed = tx.execute(
+            "DELETE FROM attachment_chunks WHERE attachment_id NOT IN (SELECT id FROM attachments)",
+            [],
+        )? + tx.execute(
+            "DELETE FROM item_usage WHERE item_id NOT IN (SELECT item_id FROM vault_items)",
+            [],
+        )?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Rebuild the database file without its free pages
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// Erase the local vault but keep what's needed to restore it from sync
+    ///
+    /// Settings, sync credentials and the vault's salt stay; the vault, its
+    /// usage times, attachments, decoy, queued changes, conflicts, icons and generator history go, and
+    /// the next sync starts from scratch.
+    pub fn wipe_local_vault(&self) -> Result<()> {
+        let salt: String = self
+            .get_salt()?
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            rusqlite::params![WIPED_SALT_KEY, salt],
+        )?;
+        tx.execute("DELETE FROM settings WHERE key = ?1", [SYNC_VERSION_KEY])?;
+        for table in [
+            "vault_meta",
+            "vault_items",
+            "item_usage",
+            "attachments",
+            "attachment_chunks",
+            "decoy_vault",
+            "sync_queue",
+            "sync_conflicts",
+            "icons",
+            "generated_history",
+        ] {
+            tx.execute(&format!("DELETE FROM {}", table), [])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Salt of a vault erased by [`Storage::wipe_local_vault`], until it's restored
+    pub fn wiped_salt(&self) -> Result<Option<[u8; 16]>> {
+        let Some(hex) = self.get_setting(WIPED_SALT_KEY)? else {
+            return Ok(None);
+        };
+        let bytes: Option<Vec<u8>> = (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            })
+            .collect();
+        Ok(bytes.and_then(|bytes| bytes.try_into().ok()))
+    }
+
+    /// Forget the wiped vault's salt once it's restored or replaced
+    pub fn clear_wiped_salt(&self) -> Result<()> {
+        self.delete_setting(WIPED_SALT_KEY)
+    }
+
+    /// Delete vault (for remote wipe/reset)
+    pub fn delete_vault(&self) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM vault_meta WHERE id = 1", [])?;
+        self.conn.execute("DELETE FROM vault_items", [])?;
+        self.conn.execute("DELETE FROM item_usage", [])?;
+        self.conn.execute("DELETE FROM attachments", [])?;
+        self.conn.execute("DELETE FROM attachment_chunks", [])?;
+        self.delete_decoy()?;
+        self.conn.execute("DELETE FROM settings", [])?;
+        self.conn.execute("DELETE FROM sync_queue", [])?;
+        self.conn.execute("DELETE FROM sync_conflicts", [])?;
+        self.conn.execute("DELETE FROM icons", [])?;
+        self.clear_generated()?;
+        self.conn.execute("DELETE FROM activity_log", [])?;
+        Keychain::clear()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const TEST_KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    fn temp_storage() -> Storage {
+        Storage::open_in_memory()
+    }
+
+    #[test]
+    fn test_stats_and_compaction() {
+        let storage = temp_storage();
+        storage.create_vault(&[1u8; 16]).unwrap();
+        storage
+            .replace_vault(
+                b"vault",
+                &[StoredItem {
+                    item_id: "item".to_string(),
+                    encrypted_item: "abcd".to_string(),
+                    modified_at: 1,
+                }],
+            )
+            .unwrap();
+        storage.mark_item_used("item", 5).unwrap();
+        storage.mark_item_used("gone", 5).unwrap();
+        let attachment = StoredAttachment {
+            id: "a1".to_string(),
+            item_id: "item".to_string(),
+            wrapped_key: vec![1],
+            encrypted_name: vec![2],
+            size: 3,
+            created_at: 10,
+        };
+        let mut chunks = vec![b"abcdef".to_vec()].into_iter();
+        storage
+            .add_attachment::<StorageError>(&attachment, || Ok(chunks.next()))
+            .unwrap();
+        storage
+            .conn
+            .execute(
+                "INSERT INTO attachment_chunks (attachment_id, idx, data) VALUES ('lost', 0, x'00')",
+                [],
+            )
+            .unwrap();
+
+        let stats = storage.stats().unwrap();
+        assert_eq!(stats.vault_size, 9);
+        assert_eq!(stats.decoy_size, 0);
+        assert_eq!(stats.attachment_size, 7);
+        assert!(stats.database_size > 0);
+
+        assert_eq!(storage.remove_orphans().unwrap(), 2);
+        assert_eq!(storage.remove_orphans().unwrap(), 0);
+        assert_eq!(storage.stats().unwrap().attachment_size, 6);
+        assert_eq!(storage.item_usage().unwrap().len(), 1);
+        storage.vacuum().unwrap();
+    }
+
+    #[test]
+    fn test_vault_lifecycle() {
+        let storage = temp_storage();
+
+        // Initially no vault
+        assert!(!storage.vault_exists().unwrap());
+
+        // Create vault
+        let salt = [1u8; 16];
+        storage.create_vault(&salt).unwrap();
+        assert!(storage.vault_exists().unwrap());
+
+        // Get salt
+        let loaded_salt = storage.get_salt().unwrap();
         assert_eq!(salt, loaded_salt);
 
         // Save and load vault
@@ -209,6 +1546,363 @@ mod tests {
         assert_eq!(data.as_slice(), loaded.as_slice());
     }
 
+    #[test]
+    fn test_conflicts() {
+        let storage = temp_storage();
+        let conflict = StoredConflict {
+            item_id: "a".to_string(),
+            local_data: Some("local".to_string()),
+            remote_data: None,
+            detected_at: 10,
+        };
+        storage.save_conflict(&conflict).unwrap();
+        assert_eq!(storage.conflict_count().unwrap(), 1);
+        assert_eq!(storage.conflict("a").unwrap(), Some(conflict));
+
+        assert!(storage.update_conflict_remote("a", Some("remote")).unwrap());
+        assert!(!storage.update_conflict_remote("b", None).unwrap());
+        assert_eq!(
+            storage.conflicts().unwrap()[0].remote_data.as_deref(),
+            Some("remote")
+        );
+
+        storage.delete_conflict("a").unwrap();
+        assert!(storage.conflict("a").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_wipe_local_vault() {
+        let storage = temp_storage();
+        let salt = [7u8; 16];
+        storage.create_vault(&salt).unwrap();
+        storage.save_vault(b"vault data").unwrap();
+        storage.set_sync_version(42).unwrap();
+        storage.queue_change("item", ChangeOp::Update).unwrap();
+        storage.set_setting("theme", "dark").unwrap();
+        assert_eq!(storage.wiped_salt().unwrap(), None);
+
+        storage.wipe_local_vault().unwrap();
+
+        assert!(!storage.vault_exists().unwrap());
+        assert_eq!(storage.wiped_salt().unwrap(), Some(salt));
+        assert_eq!(storage.get_sync_version().unwrap(), 0);
+        assert_eq!(storage.pending_change_count().unwrap(), 0);
+        assert_eq!(
+            storage.get_setting("theme").unwrap().as_deref(),
+            Some("dark")
+        );
+
+        storage.clear_wiped_salt().unwrap();
+        assert_eq!(storage.wiped_salt().unwrap(), None);
+    }
+
+    #[test]
+    fn test_generated_history() {
+        let storage = temp_storage();
+        for i in 0..5 {
+            storage.add_generated(&format!("entry{}", i), i, 3).unwrap();
+        }
+
+        let history = storage.generated_history().unwrap();
+        let entries: Vec<_> = history.iter().map(|e| e.encrypted_entry.as_str()).collect();
+        assert_eq!(entries, vec!["entry4", "entry3", "entry2"]);
+
+        storage.update_generated(history[0].id, "updated").unwrap();
+        storage.delete_generated(history[1].id).unwrap();
+        let history = storage.generated_history().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].encrypted_entry, "updated");
+
+        storage.clear_generated().unwrap();
+        assert!(storage.generated_history().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_activity_log() {
+        let storage = temp_storage();
+        for i in 0..5 {
+            storage.add_activity("unlock", None, i, 3).unwrap();
+        }
+        storage
+            .add_activity("export", Some("/tmp/vault.kdbk"), 5, 3)
+            .unwrap();
+
+        let activity = storage.activity().unwrap();
+        let times: Vec<_> = activity.iter().map(|e| e.created_at).collect();
+        assert_eq!(times, vec![5, 4, 3]);
+        assert_eq!(activity[0].kind, "export");
+        assert_eq!(activity[0].detail.as_deref(), Some("/tmp/vault.kdbk"));
+
+        // Kept through a self-wipe, unlike the vault
+        storage.create_vault(&[7u8; 16]).unwrap();
+        storage.wipe_local_vault().unwrap();
+        assert_eq!(storage.activity().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_icons() {
+        let storage = temp_storage();
+        assert!(storage.icon("github.com").unwrap().is_none());
+
+        let icon = StoredIcon {
+            domain: "github.com".to_string(),
+            data: Some(vec![1, 2, 3]),
+            content_type: Some("image/x-icon".to_string()),
+            fetched_at: 10,
+        };
+        storage.save_icon(&icon).unwrap();
+        assert_eq!(storage.icon("github.com").unwrap(), Some(icon));
+
+        let missing = StoredIcon {
+            domain: "github.com".to_string(),
+            data: None,
+            content_type: None,
+            fetched_at: 20,
+        };
+        storage.save_icon(&missing).unwrap();
+        assert_eq!(storage.icon("github.com").unwrap(), Some(missing));
+    }
+
+    #[test]
+    fn test_vault_items() {
+        let storage = temp_storage();
+        storage.create_vault(&[1u8; 16]).unwrap();
+        let item = |id: &str, data: &str| StoredItem {
+            item_id: id.to_string(),
+            encrypted_item: data.to_string(),
+            modified_at: 1,
+        };
+
+        storage
+            .replace_vault(b"vault", &[item("a", "a1"), item("b", "b1")])
+            .unwrap();
+        storage
+            .write_items(&[item("a", "a2"), item("c", "c1")], &["b".to_string()])
+            .unwrap();
+
+        let items = storage.load_items().unwrap();
+        let ids: Vec<_> = items.iter().map(|i| i.item_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+        assert_eq!(
+            storage.load_item("a").unwrap().unwrap().encrypted_item,
+            "a2"
+        );
+        assert_eq!(storage.load_item("b").unwrap(), None);
+
+        storage.replace_vault(b"vault", &[item("d", "d1")]).unwrap();
+        assert_eq!(storage.load_items().unwrap(), vec![item("d", "d1")]);
+
+        storage.wipe_local_vault().unwrap();
+        assert!(storage.load_items().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_item_usage() {
+        let storage = temp_storage();
+        storage.create_vault(&[1u8; 16]).unwrap();
+        let item = |id: &str| StoredItem {
+            item_id: id.to_string(),
+            encrypted_item: String::new(),
+            modified_at: 1,
+        };
+        storage
+            .replace_vault(b"vault", &[item("a"), item("b"), item("c")])
+            .unwrap();
+
+        storage.mark_item_used("a", 10).unwrap();
+        storage.mark_item_used("b", 20).unwrap();
+        storage.mark_item_used("a", 30).unwrap();
+        assert_eq!(storage.item_usage().unwrap()["a"], 30);
+
+        // Forgotten along with the item
+        storage.write_items(&[], &["b".to_string()]).unwrap();
+        assert!(!storage.item_usage().unwrap().contains_key("b"));
+        storage.replace_vault(b"vault", &[item("c")]).unwrap();
+        assert!(storage.item_usage().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_attachments() {
+        let storage = temp_storage();
+        let attachment = StoredAttachment {
+            id: "a1".to_string(),
+            item_id: "item".to_string(),
+            wrapped_key: vec![1],
+            encrypted_name: vec![2],
+            size: 6,
+            created_at: 10,
+        };
+        let mut chunks = vec![b"abc".to_vec(), b"def".to_vec()].into_iter();
+        storage
+            .add_attachment::<StorageError>(&attachment, || Ok(chunks.next()))
+            .unwrap();
+        assert_eq!(storage.attachments().unwrap(), vec![attachment.clone()]);
+        assert_eq!(storage.attachments_size().unwrap(), 6);
+
+        let mut read = Vec::new();
+        storage
+            .read_attachment_chunks::<StorageError>("a1", |chunk| {
+                read.push(chunk);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(read, [b"abc".to_vec(), b"def".to_vec()]);
+
+        // A failed write stores nothing
+        let failed = StoredAttachment {
+            id: "a2".to_string(),
+            ..attachment.clone()
+        };
+        let mut calls = 0;
+        let result = storage.add_attachment(&failed, || {
+            calls += 1;
+            match calls {
+                1 => Ok(Some(b"abc".to_vec())),
+                _ => Err(StorageError::VaultNotFound),
+            }
+        });
+        assert!(result.is_err());
+        assert!(storage.attachment("a2").unwrap().is_none());
+
+        storage.set_attachment_key("a1", &[9]).unwrap();
+        assert_eq!(storage.attachment("a1").unwrap().unwrap().wrapped_key, [9]);
+
+        assert!(storage.delete_attachment("a1").unwrap());
+        assert!(!storage.delete_attachment("a1").unwrap());
+        assert_eq!(storage.attachments_size().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_vault_integrity() {
+        let storage = temp_storage();
+        storage.create_vault(&[1u8; 16]).unwrap();
+        assert!(matches!(
+            storage.load_previous_vault(),
+            Err(StorageError::VaultNotFound)
+        ));
+
+        storage.save_vault(b"first").unwrap();
+        storage.save_vault(b"second").unwrap();
+        assert_eq!(storage.load_vault().unwrap(), b"second");
+        assert_eq!(storage.load_previous_vault().unwrap(), b"first");
+
+        // Flip the stored blob behind the HMAC's back
+        storage
+            .conn
+            .execute("UPDATE vault_meta SET encrypted_vault = X'00'", [])
+            .unwrap();
+        assert!(matches!(storage.load_vault(), Err(StorageError::Corrupted)));
+        assert!(!storage.vault_damaged().unwrap());
+
+        // Saving over a damaged copy keeps the intact fallback
+        storage.save_vault(b"third").unwrap();
+        assert_eq!(storage.load_previous_vault().unwrap(), b"first");
+
+        storage
+            .conn
+            .execute(
+                "UPDATE vault_meta SET encrypted_vault = X'00', previous_vault = X'00'",
+                [],
+            )
+            .unwrap();
+        assert!(storage.vault_damaged().unwrap());
+    }
+
+    #[test]
+    fn test_item_integrity() {
+        let storage = temp_storage();
+        storage.create_vault(&[1u8; 16]).unwrap();
+        let item = |id: &str| StoredItem {
+            item_id: id.to_string(),
+            encrypted_item: format!("{}-data", id),
+            modified_at: 1,
+        };
+        storage
+            .replace_vault(b"vault", &[item("a"), item("b")])
+            .unwrap();
+        assert!(storage.damaged_items().unwrap().is_empty());
+
+        storage
+            .conn
+            .execute(
+                "UPDATE vault_items SET encrypted_item = 'x' WHERE item_id = 'b'",
+                [],
+            )
+            .unwrap();
+        assert!(matches!(storage.load_items(), Err(StorageError::Corrupted)));
+        assert!(matches!(
+            storage.load_item("b"),
+            Err(StorageError::Corrupted)
+        ));
+        assert_eq!(storage.load_item("a").unwrap(), Some(item("a")));
+        assert_eq!(storage.damaged_items().unwrap(), vec!["b".to_string()]);
+        assert!(storage.vault_damaged().unwrap());
+
+        // The damaged item isn't silently replaced
+        assert!(matches!(
+            storage.replace_vault(b"vault", &[item("a")]),
+            Err(StorageError::Corrupted)
+        ));
+        storage.delete_damaged_items().unwrap();
+        assert!(!storage.vault_damaged().unwrap());
+        assert_eq!(storage.load_items().unwrap(), vec![item("a")]);
+    }
+
+    #[test]
+    fn test_encrypts_plaintext_database() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("vault.db");
+        {
+            let storage = Storage {
+                conn: Connection::open(&db_path).unwrap(),
+                mac_key: vault_mac_key(TEST_KEY),
+            };
+            storage.init_schema().unwrap();
+            storage.set_setting("theme", "dark").unwrap();
+        }
+        assert!(is_plaintext(&db_path).unwrap());
+
+        let storage = Storage {
+            conn: open_encrypted(&db_path, TEST_KEY).unwrap(),
+            mac_key: vault_mac_key(TEST_KEY),
+        };
+        assert_eq!(
+            storage.get_setting("theme").unwrap(),
+            Some("dark".to_string())
+        );
+        drop(storage);
+
+        assert!(!is_plaintext(&db_path).unwrap());
+        let contents = std::fs::read(&db_path).unwrap();
+        assert!(!contents.windows(4).any(|window| window == b"dark"));
+
+        let wrong_key = "ff".repeat(DATABASE_KEY_SIZE);
+        assert!(matches!(
+            open_encrypted(&db_path, &wrong_key),
+            Err(StorageError::Undecryptable)
+        ));
+    }
+
+    #[test]
+    fn test_decoy_vault() {
+        let storage = temp_storage();
+        assert_eq!(storage.get_decoy_salt().unwrap(), None);
+        assert!(storage.save_decoy(b"data").is_err());
+
+        let salt = [2u8; 16];
+        storage.create_decoy(&salt, b"decoy data").unwrap();
+        assert_eq!(storage.get_decoy_salt().unwrap(), Some(salt));
+        assert_eq!(storage.load_decoy().unwrap(), b"decoy data");
+
+        storage.save_decoy(b"updated").unwrap();
+        assert_eq!(storage.load_decoy().unwrap(), b"updated");
+        // The real vault is a separate row
+        assert!(!storage.vault_exists().unwrap());
+
+        storage.delete_decoy().unwrap();
+        assert_eq!(storage.get_decoy_salt().unwrap(), None);
+    }
+
     #[test]
     fn test_settings() {
         let storage = temp_storage();
@@ -230,4 +1924,44 @@ mod tests {
             Some("new_value".to_string())
         );
     }
+
+    #[test]
+    fn test_change_op_collapse() {
+        assert_eq!(ChangeOp::Add.then(ChangeOp::Update), ChangeOp::Add);
+        assert_eq!(ChangeOp::Add.then(ChangeOp::Delete), ChangeOp::Delete);
+        assert_eq!(ChangeOp::Update.then(ChangeOp::Delete), ChangeOp::Delete);
+        assert_eq!(ChangeOp::Delete.then(ChangeOp::Add), ChangeOp::Update);
+        assert_eq!(ChangeOp::Update.then(ChangeOp::Update), ChangeOp::Update);
+    }
+
+    #[test]
+    fn test_sync_queue() {
+        let storage = temp_storage();
+
+        assert_eq!(storage.get_sync_version().unwrap(), 0);
+        storage.set_sync_version(42).unwrap();
+        assert_eq!(storage.get_sync_version().unwrap(), 42);
+
+        storage.queue_change("a", ChangeOp::Add).unwrap();
+        storage.queue_change("a", ChangeOp::Update).unwrap();
+        storage.queue_change("b", ChangeOp::Update).unwrap();
+        storage.queue_change("b", ChangeOp::Delete).unwrap();
+        let snapshot = storage.pending_changes().unwrap();
+        assert_eq!(storage.pending_change_count().unwrap(), 2);
+        assert_eq!(snapshot[0].op, ChangeOp::Add);
+        assert_eq!(snapshot[1].op, ChangeOp::Delete);
+
+        // A change queued after the snapshot survives the dequeue
+        storage
+            .conn
+            .execute(
+                "UPDATE sync_queue SET queued_at = queued_at + 1 WHERE item_id = 'b'",
+                [],
+            )
+            .unwrap();
+        storage.dequeue_changes(&snapshot).unwrap();
+        let remaining = storage.pending_changes().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].item_id, "b");
+    }
 }
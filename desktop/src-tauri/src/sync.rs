@@ -1,5 +1,54 @@
+use crate::activity::{self, ActivityKind};
+use crate::conflicts;
+use crate::emergency;
+use crate::state::AppState;
+use crate::storage::{ChangeOp, PendingChange, Storage, StorageError, StoredItem};
+use crate::sync_client::{SyncClient, SyncItem, SyncPushRequest};
+use crate::vault_store::{self, VaultStoreError};
+use crypto_core::{cipher::KEY_SIZE, error::CryptoError, vault::Vault};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
+use thiserror::Error;
+use tokio::sync::Notify;
+use zeroize::Zeroizing;
+
+#[derive(Error, Debug)]
+pub enum SyncError {
+    #[error("Sync is not enabled")]
+    NotEnabled,
+
+    #[error("Vault is locked")]
+    Locked,
+
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+
+    #[error("Server error ({status}): {message}")]
+    Server { status: u16, message: String },
+
+    #[error(transparent)]
+    Crypto(#[from] CryptoError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
+    #[error(transparent)]
+    VaultStore(#[from] VaultStoreError),
+}
+
+impl SyncError {
+    /// Whether the server could not be reached at all
+    pub fn is_offline(&self) -> bool {
+        matches!(self, SyncError::Network(e) if e.is_connect() || e.is_timeout())
+    }
+
+    /// Whether the server pruned deletions newer than the version asked for,
+    /// so the vault has to be pulled from scratch
+    pub fn is_resync_required(&self) -> bool {
+        matches!(self, SyncError::Server { status: 410, .. })
+    }
+}
 
 /// Sync status state
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -17,6 +66,8 @@ pub struct SyncStatus {
     pub last_sync_time: Option<u64>,
     pub error: Option<String>,
     pub pending_changes: u32,
+    /// Conflicts waiting for the user to pick a side
+    pub conflicts: u32,
 }
 
 impl Default for SyncStatus {
@@ -26,6 +77,7 @@ impl Default for SyncStatus {
             last_sync_time: None,
             error: None,
             pending_changes: 0,
+            conflicts: 0,
         }
     }
 }
@@ -38,6 +90,12 @@ pub struct RemoteCommand {
     pub created_at: u64,
 }
 
+/// Default time between background syncs in seconds
+pub const DEFAULT_SYNC_INTERVAL: u64 = 300;
+
+/// Shortest background sync interval accepted from settings
+pub const MIN_SYNC_INTERVAL: u64 = 30;
+
 /// Sync state manager
 pub struct SyncState {
     pub status: Mutex<SyncStatus>,
@@ -45,6 +103,12 @@ pub struct SyncState {
     pub server_url: Mutex<Option<String>>,
     pub access_token: Mutex<Option<String>>,
     pub device_id: Mutex<Option<String>>,
+    /// Background sync interval in seconds
+    pub interval: Mutex<u64>,
+    /// Wakes the background scheduler early, e.g. after a vault save
+    wake: Notify,
+    /// Held for the duration of a sync so runs never overlap
+    running: tokio::sync::Mutex<()>,
 }
 
 impl SyncState {
@@ -55,9 +119,26 @@ impl SyncState {
             server_url: Mutex::new(None),
             access_token: Mutex::new(None),
             device_id: Mutex::new(None),
+            interval: Mutex::new(DEFAULT_SYNC_INTERVAL),
+            wake: Notify::new(),
+            running: tokio::sync::Mutex::new(()),
         }
     }
 
+    /// Ask the background scheduler to sync soon
+    pub fn request_sync(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Wait until [`SyncState::request_sync`] is called
+    pub async fn sync_requested(&self) {
+        self.wake.notified().await;
+    }
+
+    pub fn interval(&self) -> u64 {
+        *self.interval.lock().unwrap()
+    }
+
     pub fn get_status(&self) -> SyncStatus {
         self.status.lock().unwrap().clone()
     }
@@ -91,6 +172,11 @@ impl SyncState {
         status.pending_changes = count;
     }
 
+    pub fn set_conflicts(&self, count: u32) {
+        let mut status = self.status.lock().unwrap();
+        status.conflicts = count;
+    }
+
     pub fn is_enabled(&self) -> bool {
         *self.is_enabled.lock().unwrap()
     }
@@ -139,5 +225,392 @@ impl Default for SyncState {
 pub struct SyncConfig {
     pub server_url: String,
     pub access_token: String,
+    /// The server currently identifies the device from the access token
+    #[allow(dead_code)]
     pub device_id: String,
 }
+
+/// Outcome of a sync run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncSummary {
+    /// Remote changes applied to the local vault
+    pub pulled: usize,
+    /// Local changes accepted by the server
+    pub pushed: usize,
+    /// Local changes the server rejected, now waiting for the user to resolve
+    pub conflicts: usize,
+    /// Server version after the sync
+    pub version: i64,
+}
+
+/// Run a full sync and reflect the outcome in `sync_state`'s status
+pub async fn run_sync(
+    app_state: &AppState,
+    sync_state: &SyncState,
+) -> Result<SyncSummary, SyncError> {
+    let config = sync_state.get_config().ok_or(SyncError::NotEnabled)?;
+    // Never mix decoy items into the synced vault
+    if app_state.is_decoy() {
+        return Ok(SyncSummary::default());
+    }
+    let _running = sync_state.running.lock().await;
+    sync_state.set_syncing();
+
+    let result = sync_vault(app_state, SyncClient::new(config.clone())).await;
+    if result.is_ok() {
+        // Contacts who accepted since the last run get the vault key sealed for them
+        if let Err(e) = share_emergency_keys(app_state, &SyncClient::new(config)).await {
+            tracing::warn!(
+                "Failed to share the vault key with emergency contacts: {}",
+                e
+            );
+        }
+    }
+    match &result {
+        Ok(_) => sync_state.set_idle(now_secs()),
+        Err(e) if e.is_offline() => sync_state.set_offline(),
+        Err(e) => sync_state.set_error(e.to_string()),
+    }
+    match &result {
+        Ok(summary) if summary.pulled + summary.pushed + summary.conflicts > 0 => {
+            activity::try_record(
+                ActivityKind::Sync,
+                Some(&format!(
+                    "pulled {}, pushed {}, conflicts {}",
+                    summary.pulled, summary.pushed, summary.conflicts
+                )),
+            )
+        }
+        Err(e) if !e.is_offline() => {
+            activity::try_record(ActivityKind::Sync, Some(&format!("failed: {}", e)))
+        }
+        _ => {}
+    }
+    if let Ok(storage) = Storage::open() {
+        if let Ok(count) = storage.pending_change_count() {
+            sync_state.set_pending_changes(count);
+        }
+        if let Ok(count) = storage.conflict_count() {
+            sync_state.set_conflicts(count);
+        }
+    }
+    result
+}
+
+/// Pull remote changes since the stored version, then push queued local changes
+///
+/// Locks are never held across network calls, so the vault stays usable while
+/// a sync is in flight. Edits made meanwhile stay queued for the next run.
+async fn sync_vault(app_state: &AppState, client: SyncClient) -> Result<SyncSummary, SyncError> {
+    let key = vault_key(app_state)?;
+    let mut version = Storage::open()?.get_sync_version()?;
+    let mut summary = SyncSummary::default();
+
+    // A device that has never synced uploads everything it has
+    if version == 0 {
+        let storage = Storage::open()?;
+        with_vault(app_state, |vault| {
+            vault
+                .items
+                .iter()
+                .try_for_each(|item| storage.queue_change(&item.id, ChangeOp::Add))
+        })??;
+    }
+
+    // Set once the server says deletions we missed were pruned; the whole
+    // vault is pulled again and whatever it no longer has is dropped
+    let mut full_resync = false;
+    let mut server_ids = HashSet::new();
+
+    loop {
+        let response = match client.pull(version).await {
+            Err(e) if e.is_resync_required() && version > 0 => {
+                tracing::info!("Deletions since version {} were pruned; resyncing", version);
+                full_resync = true;
+                server_ids.clear();
+                version = 0;
+                continue;
+            }
+            result => result?,
+        };
+        server_ids.extend(
+            response
+                .items
+                .iter()
+                .filter(|remote| !remote.is_deleted)
+                .map(|remote| remote.id.clone()),
+        );
+        let storage = Storage::open()?;
+        // Items waiting on the user keep their local version until resolved
+        let items = conflicts::absorb_pulled(&storage, &response.items)?;
+        let pending = storage.pending_changes()?;
+        let pending = pending_by_id(&pending);
+        summary.pulled += with_vault(app_state, |vault| {
+            let applied = apply_remote_items(vault, &key, &items, &pending)?;
+            // Pulled blobs are stored as they came; they're already encrypted items
+            let (deleted, changed): (Vec<&SyncItem>, Vec<&SyncItem>) =
+                applied.iter().partition(|remote| remote.is_deleted);
+            storage.write_items(
+                &changed
+                    .iter()
+                    .map(|remote| StoredItem {
+                        item_id: remote.id.clone(),
+                        encrypted_item: remote.encrypted_data.clone(),
+                        modified_at: remote.modified_at,
+                    })
+                    .collect::<Vec<_>>(),
+                &deleted
+                    .iter()
+                    .map(|remote| remote.id.clone())
+                    .collect::<Vec<_>>(),
+            )?;
+            Ok::<_, SyncError>(applied.len())
+        })??;
+
+        match response.next_cursor.filter(|_| response.has_more) {
+            Some(cursor) => version = cursor,
+            None => {
+                version = response.current_version;
+                break;
+            }
+        }
+    }
+
+    if full_resync {
+        let storage = Storage::open()?;
+        let pending = storage.pending_changes()?;
+        let pending = pending_by_id(&pending);
+        summary.pulled += with_vault(app_state, |vault| {
+            let gone: Vec<String> = vault
+                .items
+                .iter()
+                .map(|item| item.id.clone())
+                .filter(|id| !server_ids.contains(id) && !pending.contains_key(id.as_str()))
+                .collect();
+            for id in &gone {
+                let _ = vault.remove_item(id);
+            }
+            storage.write_items(&[], &gone)?;
+            Ok::<_, SyncError>(gone.len())
+        })??;
+    }
+
+    let pending = Storage::open()?.pending_changes()?;
+    if !pending.is_empty() {
+        let items = build_push_items(&Storage::open()?, &pending, version)?;
+        let response = client
+            .push(&SyncPushRequest {
+                base_version: version,
+                items,
+            })
+            .await?;
+
+        summary.pushed = pending.len() - response.conflicts.len();
+        let storage = Storage::open()?;
+        summary.conflicts = with_vault(app_state, |vault| {
+            conflicts::record(
+                &storage,
+                vault,
+                &key,
+                &response.conflicts,
+                now_secs() as i64,
+            )
+        })??;
+
+        // Conflicted changes now live in their conflicts and are pushed again
+        // only if the user keeps the local version
+        storage.dequeue_changes(&pending)?;
+        version = version.max(response.new_version);
+    }
+
+    Storage::open()?.set_sync_version(version)?;
+    summary.version = version;
+    Ok(summary)
+}
+
+async fn share_emergency_keys(app_state: &AppState, client: &SyncClient) -> Result<(), SyncError> {
+    let key = vault_key(app_state)?;
+    let mut contacts = client.emergency_contacts().await?;
+    emergency::share_vault_keys(client, &mut contacts, &key).await
+}
+
+/// Apply remote item changes to the vault, returning those that were applied
+///
+/// Items with a local change queued after the remote modification are skipped;
+/// the local version is pushed afterwards and wins.
+fn apply_remote_items<'a>(
+    vault: &mut Vault,
+    key: &[u8; KEY_SIZE],
+    items: &'a [SyncItem],
+    pending: &HashMap<&str, i64>,
+) -> Result<Vec<&'a SyncItem>, SyncError> {
+    let mut applied = Vec::new();
+    for remote in items {
+        if let Some(&queued_at) = pending.get(remote.id.as_str()) {
+            if queued_at / 1000 >= remote.modified_at {
+                continue;
+            }
+        }
+
+        if remote.is_deleted {
+            if vault.remove_item(&remote.id).is_ok() {
+                applied.push(remote);
+            }
+            continue;
+        }
+
+        let item = vault_store::decrypt_item(&remote.encrypted_data, key)?;
+        if vault.get_item(&item.id) != Some(&item) {
+            vault.upsert_item(item);
+            applied.push(remote);
+        }
+    }
+    Ok(applied)
+}
+
+/// Queued changes as uploaded, taken from the stored item blobs
+///
+/// Deletions, and changes to items no longer stored, are sent as tombstones.
+fn build_push_items(
+    storage: &Storage,
+    pending: &[PendingChange],
+    version: i64,
+) -> Result<Vec<SyncItem>, SyncError> {
+    pending
+        .iter()
+        .map(|change| {
+            let stored = match change.op {
+                ChangeOp::Delete => None,
+                ChangeOp::Add | ChangeOp::Update => storage.load_item(&change.item_id)?,
+            };
+            Ok(match stored {
+                Some(stored) => SyncItem {
+                    id: stored.item_id,
+                    encrypted_data: stored.encrypted_item,
+                    version,
+                    is_deleted: false,
+                    modified_at: stored.modified_at,
+                },
+                None => SyncItem {
+                    id: change.item_id.clone(),
+                    encrypted_data: String::new(),
+                    version,
+                    is_deleted: true,
+                    modified_at: change.queued_at / 1000,
+                },
+            })
+        })
+        .collect()
+}
+
+fn pending_by_id(pending: &[PendingChange]) -> HashMap<&str, i64> {
+    pending
+        .iter()
+        .map(|change| (change.item_id.as_str(), change.queued_at))
+        .collect()
+}
+
+fn vault_key(app_state: &AppState) -> Result<Zeroizing<[u8; KEY_SIZE]>, SyncError> {
+    let keys = app_state.keys.lock().unwrap();
+    let keys = keys.as_ref().ok_or(SyncError::Locked)?;
+    Ok(Zeroizing::new(keys.vault_key))
+}
+
+fn with_vault<T>(app_state: &AppState, f: impl FnOnce(&mut Vault) -> T) -> Result<T, SyncError> {
+    let mut vault = app_state.vault.lock().unwrap();
+    Ok(f(vault.as_mut().ok_or(SyncError::Locked)?))
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_core::{cipher::EncryptedBlob, vault::VaultItem};
+
+    const KEY: [u8; KEY_SIZE] = [7u8; KEY_SIZE];
+
+    fn remote(item: &VaultItem, modified_at: i64) -> SyncItem {
+        SyncItem {
+            id: item.id.clone(),
+            encrypted_data: item.export(&KEY).unwrap().to_base64(),
+            version: 1,
+            is_deleted: false,
+            modified_at,
+        }
+    }
+
+    #[test]
+    fn test_apply_remote_items() {
+        let mut vault = Vault::new();
+        let local = VaultItem::new("GitHub", "user", "old");
+        let id = local.id.clone();
+        vault.upsert_item(local.clone());
+
+        let mut changed = local.clone();
+        changed.password = "new".to_string();
+        let added = VaultItem::new("Jira", "user", "pass");
+        let items = vec![remote(&changed, 100), remote(&added, 100)];
+
+        // A newer local change wins over the remote version
+        let pending = HashMap::from([(id.as_str(), 200_000)]);
+        let applied = apply_remote_items(&mut vault, &KEY, &items, &pending).unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].id, added.id);
+        assert_eq!(vault.get_item(&id).unwrap().password, "old");
+
+        assert_eq!(
+            apply_remote_items(&mut vault, &KEY, &items, &HashMap::new())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(vault.get_item(&id).unwrap().password, "new");
+
+        let mut deleted = remote(&changed, 300);
+        deleted.is_deleted = true;
+        apply_remote_items(&mut vault, &KEY, &[deleted], &HashMap::new()).unwrap();
+        assert!(vault.get_item(&id).is_none());
+        assert_eq!(vault.len(), 1);
+    }
+
+    #[test]
+    fn test_build_push_items() {
+        let storage = Storage::open_in_memory();
+        let mut vault = Vault::new();
+        let id = vault.add_item(VaultItem::new("GitHub", "user", "pass"));
+        vault_store::save_items(&storage, &vault, &KEY, [id.as_str()]).unwrap();
+        let pending = vec![
+            PendingChange {
+                item_id: id.clone(),
+                op: ChangeOp::Update,
+                queued_at: 1_000,
+            },
+            PendingChange {
+                item_id: "removed".to_string(),
+                op: ChangeOp::Update,
+                queued_at: 5_000,
+            },
+            PendingChange {
+                item_id: id.clone(),
+                op: ChangeOp::Delete,
+                queued_at: 6_000,
+            },
+        ];
+
+        let items = build_push_items(&storage, &pending, 3).unwrap();
+        assert!(!items[0].is_deleted);
+        let blob = EncryptedBlob::from_base64(&items[0].encrypted_data).unwrap();
+        assert_eq!(VaultItem::import(&blob, &KEY).unwrap().password, "pass");
+        assert!(items[1].is_deleted);
+        assert_eq!(items[1].modified_at, 5);
+        assert_eq!(items[1].version, 3);
+        assert!(items[2].is_deleted);
+        assert!(items[2].encrypted_data.is_empty());
+    }
+}
@@ -0,0 +1,320 @@
+use crate::emergency::{
+    AccessLogEntry, AccessRequest, AccessRequestStatus, AddContactRequest, EmergencyContact,
+    GrantedAccessResponse, PendingAccessRequest, VaultAccessResponse,
+};
+use crate::sync::{RemoteCommand, SyncConfig, SyncError};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Item change exchanged with the sync server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncItem {
+    pub id: String,
+    /// Encrypted item blob (base64), empty for deletions
+    pub encrypted_data: String,
+    pub version: i64,
+    pub is_deleted: bool,
+    /// Modified timestamp (Unix epoch seconds)
+    pub modified_at: i64,
+}
+
+/// Push request body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPushRequest {
+    pub base_version: i64,
+    pub items: Vec<SyncItem>,
+}
+
+/// Idempotency key for a push of `items`
+///
+/// Edits always move `modified_at`, so a later push of different changes
+/// never reuses a key.
+fn push_idempotency_key(items: &[SyncItem]) -> String {
+    let mut hasher = Sha256::new();
+    for item in items {
+        hasher.update(item.id.as_bytes());
+        hasher.update(item.encrypted_data.as_bytes());
+        hasher.update([item.is_deleted as u8]);
+        hasher.update(item.modified_at.to_be_bytes());
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Push response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPushResponse {
+    pub new_version: i64,
+    pub had_conflicts: bool,
+    /// Server versions that won over our pushed items
+    pub conflicts: Vec<SyncItem>,
+}
+
+/// Pull response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncPullResponse {
+    pub current_version: i64,
+    pub items: Vec<SyncItem>,
+    pub has_more: bool,
+    #[serde(default)]
+    pub next_cursor: Option<i64>,
+}
+
+/// HTTP client for the Keydrop sync API
+pub struct SyncClient {
+    http: reqwest::Client,
+    config: SyncConfig,
+}
+
+impl SyncClient {
+    pub fn new(config: SyncConfig) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/api/v1{}",
+            self.config.server_url.trim_end_matches('/'),
+            path
+        )
+    }
+
+    /// Fetch item changes made after `since_version`
+    pub async fn pull(&self, since_version: i64) -> Result<SyncPullResponse, SyncError> {
+        let response = self
+            .http
+            .get(self.url("/sync/pull"))
+            .bearer_auth(&self.config.access_token)
+            .query(&[("since_version", since_version)])
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Upload local item changes
+    ///
+    /// The idempotency key is derived from the items, so retrying the same
+    /// queued changes after a timeout can't apply them twice.
+    pub async fn push(&self, request: &SyncPushRequest) -> Result<SyncPushResponse, SyncError> {
+        let response = self
+            .http
+            .post(self.url("/sync/push"))
+            .bearer_auth(&self.config.access_token)
+            .header("Idempotency-Key", push_idempotency_key(&request.items))
+            .json(request)
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Fetch remote commands waiting for this device
+    pub async fn pending_commands(&self) -> Result<Vec<RemoteCommand>, SyncError> {
+        let response = self
+            .http
+            .get(self.url("/devices/commands"))
+            .bearer_auth(&self.config.access_token)
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Report whether a remote command was executed
+    pub async fn ack_command(&self, command_id: &str, success: bool) -> Result<(), SyncError> {
+        let response = self
+            .http
+            .post(self.url(&format!("/devices/commands/{}/ack", command_id)))
+            .bearer_auth(&self.config.access_token)
+            .json(&serde_json::json!({ "success": success }))
+            .send()
+            .await?;
+
+        Self::check(response).await?;
+        Ok(())
+    }
+
+    /// Invite someone as an emergency contact
+    pub async fn add_emergency_contact(
+        &self,
+        request: &AddContactRequest,
+    ) -> Result<EmergencyContact, SyncError> {
+        let response = self
+            .http
+            .post(self.url("/emergency/contacts"))
+            .bearer_auth(&self.config.access_token)
+            .json(request)
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Emergency contacts this user has added
+    pub async fn emergency_contacts(&self) -> Result<Vec<EmergencyContact>, SyncError> {
+        let response = self
+            .http
+            .get(self.url("/emergency/contacts"))
+            .bearer_auth(&self.config.access_token)
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    pub async fn remove_emergency_contact(&self, contact_id: &str) -> Result<(), SyncError> {
+        let response = self
+            .http
+            .delete(self.url(&format!("/emergency/contacts/{}", contact_id)))
+            .bearer_auth(&self.config.access_token)
+            .send()
+            .await?;
+
+        Self::check(response).await?;
+        Ok(())
+    }
+
+    /// Accept an invitation to be someone's emergency contact, publishing the
+    /// key the owner seals their vault key for
+    pub async fn accept_emergency_invitation(
+        &self,
+        contact_id: &str,
+        token: &str,
+        public_key: &str,
+    ) -> Result<(), SyncError> {
+        let response = self
+            .http
+            .post(self.url(&format!("/emergency/contacts/{}/accept", contact_id)))
+            .bearer_auth(&self.config.access_token)
+            .json(&serde_json::json!({ "token": token, "public_key": public_key }))
+            .send()
+            .await?;
+
+        Self::check(response).await?;
+        Ok(())
+    }
+
+    /// Store this user's vault key sealed for one of their contacts
+    pub async fn share_emergency_vault_key(
+        &self,
+        contact_id: &str,
+        vault_key_encrypted: &str,
+    ) -> Result<(), SyncError> {
+        let response = self
+            .http
+            .put(self.url(&format!("/emergency/contacts/{}/key", contact_id)))
+            .bearer_auth(&self.config.access_token)
+            .json(&serde_json::json!({ "vault_key_encrypted": vault_key_encrypted }))
+            .send()
+            .await?;
+
+        Self::check(response).await?;
+        Ok(())
+    }
+
+    /// Ask for access to a vault this user is an emergency contact for
+    pub async fn request_emergency_access(
+        &self,
+        request: &AccessRequest,
+    ) -> Result<AccessRequestStatus, SyncError> {
+        let response = self
+            .http
+            .post(self.url("/emergency/request"))
+            .bearer_auth(&self.config.access_token)
+            .json(request)
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Access requests made by this user's contacts that are still waiting
+    pub async fn emergency_requests(&self) -> Result<Vec<PendingAccessRequest>, SyncError> {
+        let response = self
+            .http
+            .get(self.url("/emergency/requests"))
+            .bearer_auth(&self.config.access_token)
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    pub async fn deny_emergency_request(&self, request_id: &str) -> Result<(), SyncError> {
+        let response = self
+            .http
+            .post(self.url(&format!("/emergency/requests/{}/deny", request_id)))
+            .bearer_auth(&self.config.access_token)
+            .send()
+            .await?;
+
+        Self::check(response).await?;
+        Ok(())
+    }
+
+    /// Vault access granted to this user
+    ///
+    /// The server approves requests whose waiting period has passed first.
+    pub async fn emergency_vault_access(&self) -> Result<Vec<GrantedAccessResponse>, SyncError> {
+        let response = self
+            .http
+            .get(self.url("/emergency/vault"))
+            .bearer_auth(&self.config.access_token)
+            .send()
+            .await?;
+
+        let body: VaultAccessResponse = Self::check(response).await?.json().await?;
+        Ok(body.granted_access)
+    }
+
+    /// A page of the owner's encrypted items for a granted request
+    pub async fn emergency_vault_items(
+        &self,
+        request_id: &str,
+        cursor: Option<i64>,
+    ) -> Result<SyncPullResponse, SyncError> {
+        let mut request = self
+            .http
+            .get(self.url(&format!("/emergency/vault/{}/items", request_id)))
+            .bearer_auth(&self.config.access_token);
+        if let Some(cursor) = cursor {
+            request = request.query(&[("cursor", cursor)]);
+        }
+        let response = request.send().await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Emergency access events on this user's vault
+    pub async fn emergency_logs(&self) -> Result<Vec<AccessLogEntry>, SyncError> {
+        let response = self
+            .http
+            .get(self.url("/emergency/logs"))
+            .bearer_auth(&self.config.access_token)
+            .send()
+            .await?;
+
+        Ok(Self::check(response).await?.json().await?)
+    }
+
+    /// Turn non-success responses into errors carrying the server's message
+    async fn check(response: reqwest::Response) -> Result<reqwest::Response, SyncError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let message = response.text().await.unwrap_or_default();
+        Err(SyncError::Server {
+            status: status.as_u16(),
+            message,
+        })
+    }
+}
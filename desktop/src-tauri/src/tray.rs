@@ -0,0 +1,212 @@
+use crate::auto_lock::{self, LockReason};
+use crate::clipboard;
+use crate::search;
+use crate::state::AppState;
+use crate::sync::SyncState;
+use crypto_core::vault::{Vault, VaultItem};
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager};
+
+const TRAY_ID: &str = "main";
+
+/// Items offered in the tray's quick-copy submenu
+pub const RECENT_ITEM_LIMIT: usize = 5;
+
+/// Menu id prefix for quick-copy entries, followed by the item id
+const COPY_PASSWORD_PREFIX: &str = "copy-password:";
+
+/// Most recently modified items first, ties broken by name
+pub fn recent_items(vault: &Vault, limit: usize) -> Vec<&VaultItem> {
+    let mut items: Vec<&VaultItem> = vault.items.iter().collect();
+    items.sort_by(|a, b| {
+        b.modified_at
+            .cmp(&a.modified_at)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    items.truncate(limit);
+    items
+}
+
+/// Create the tray icon; its menu is rebuilt by [`refresh_tray`]
+pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Keydrop")
+        .menu(&build_menu(app)?)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+/// Rebuild the tray menu after the lock state or the recent items changed
+pub fn refresh_tray(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Ok(menu) = build_menu(app) {
+        let _ = tray.set_menu(Some(menu));
+    }
+    let tooltip = if app.state::<AppState>().is_unlocked() {
+        "Keydrop (unlocked)"
+    } else {
+        "Keydrop (locked)"
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let app_state = app.state::<AppState>();
+    let unlocked = app_state.is_unlocked();
+    let sync_enabled = app.state::<SyncState>().is_enabled();
+
+    let status = if unlocked {
+        "Vault unlocked"
+    } else {
+        "Vault locked"
+    };
+    let menu = Menu::new(app)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "status",
+        status,
+        false,
+        None::<&str>,
+    )?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+
+    if unlocked {
+        menu.append(&MenuItem::with_id(
+            app,
+            "lock",
+            "Lock Now",
+            true,
+            None::<&str>,
+        )?)?;
+
+        // Collected first: menu calls may wait on the main thread, which
+        // must not be blocked on the vault lock meanwhile
+        let recent: Vec<(String, String)> = app_state
+            .vault
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|vault| {
+                recent_items(vault, RECENT_ITEM_LIMIT)
+                    .into_iter()
+                    .map(|item| (item.id.clone(), item.name.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let copy = Submenu::new(app, "Copy Password", true)?;
+        if recent.is_empty() {
+            copy.append(&MenuItem::with_id(
+                app,
+                "no-items",
+                "No items",
+                false,
+                None::<&str>,
+            )?)?;
+        }
+        for (id, name) in recent {
+            let id = format!("{}{}", COPY_PASSWORD_PREFIX, id);
+            copy.append(&MenuItem::with_id(app, id, name, true, None::<&str>)?)?;
+        }
+        menu.append(&copy)?;
+    } else {
+        menu.append(&MenuItem::with_id(
+            app,
+            "unlock",
+            "Unlock…",
+            true,
+            None::<&str>,
+        )?)?;
+    }
+
+    menu.append(&MenuItem::with_id(
+        app,
+        "sync",
+        "Sync Now",
+        unlocked && sync_enabled,
+        None::<&str>,
+    )?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "show",
+        "Show Keydrop",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "quit",
+        "Quit Keydrop",
+        true,
+        None::<&str>,
+    )?)?;
+    Ok(menu)
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        "lock" => auto_lock::lock_vault(app, LockReason::Manual),
+        "sync" => app.state::<SyncState>().request_sync(),
+        "show" | "unlock" => show_main_window(app),
+        "quit" => app.exit(0),
+        _ => {
+            if let Some(item_id) = id.strip_prefix(COPY_PASSWORD_PREFIX) {
+                copy_password(app, item_id);
+            }
+        }
+    }
+}
+
+fn copy_password(app: &AppHandle, item_id: &str) {
+    let app_state = app.state::<AppState>();
+    app_state.session.record_activity();
+    let vault = app_state.vault.lock().unwrap();
+    let password = vault
+        .as_ref()
+        .and_then(|vault| vault.get_item(item_id))
+        .and_then(|item| clipboard::item_field(item, "password"));
+    if let Some(password) = password {
+        if clipboard::copy_secret(app, password).is_ok() {
+            search::mark_used(&app_state, item_id);
+        }
+    }
+}
+
+/// Bring the main window to the front, restoring it if minimized or hidden
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_items() {
+        let mut vault = Vault::new();
+        for (name, modified_at) in [("Bank", 100), ("Email", 300), ("Chat", 300), ("Shop", 200)] {
+            let mut item = VaultItem::new(name, "user", "pass");
+            item.modified_at = modified_at;
+            vault.upsert_item(item);
+        }
+
+        let names: Vec<&str> = recent_items(&vault, 3)
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect();
+        assert_eq!(names, ["Chat", "Email", "Shop"]);
+        assert!(recent_items(&Vault::new(), 3).is_empty());
+    }
+}
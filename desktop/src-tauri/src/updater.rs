@@ -0,0 +1,114 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use thiserror::Error;
+
+/// Public key release artifacts are signed with, from `tauri signer generate`
+///
+/// Set by the release workflow; builds without it can't verify updates and
+/// don't offer them.
+const PUBKEY: Option<&str> = option_env!("KEYDROP_UPDATER_PUBKEY");
+
+/// Emitted with [`UpdateProgress`] while an update downloads
+pub const UPDATE_PROGRESS_EVENT: &str = "update-progress";
+
+#[derive(Error, Debug)]
+pub enum UpdateError {
+    #[error("Updates aren't available in this build")]
+    Unavailable,
+
+    #[error("No update to install; check for updates first")]
+    NotChecked,
+
+    #[error(transparent)]
+    Updater(#[from] tauri_plugin_updater::Error),
+}
+
+pub type Result<T> = std::result::Result<T, UpdateError>;
+
+/// A newer release than the one running
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    /// Release notes, as written for the release
+    pub notes: Option<String>,
+    /// Publish time (Unix epoch seconds)
+    pub published_at: Option<i64>,
+}
+
+impl From<&Update> for UpdateInfo {
+    fn from(update: &Update) -> Self {
+        Self {
+            version: update.version.clone(),
+            current_version: update.current_version.clone(),
+            notes: update.body.clone(),
+            published_at: update.date.map(|date| date.unix_timestamp()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct UpdateProgress {
+    pub downloaded: u64,
+    /// Download size, when the server reports it
+    pub total: Option<u64>,
+}
+
+/// The update found by the last check, kept for `install_update`
+#[derive(Default)]
+pub struct UpdaterState {
+    pending: Mutex<Option<Update>>,
+}
+
+/// The updater plugin, verifying against [`PUBKEY`]
+///
+/// Endpoints come from `tauri.conf.json`.
+pub fn plugin<R: Runtime>() -> tauri::plugin::TauriPlugin<R, tauri_plugin_updater::Config> {
+    tauri_plugin_updater::Builder::new()
+        .pubkey(PUBKEY.unwrap_or_default())
+        .build()
+}
+
+/// Ask the release server for a newer version
+pub async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>> {
+    if PUBKEY.is_none_or(str::is_empty) {
+        return Err(UpdateError::Unavailable);
+    }
+
+    let update = app.updater()?.check().await?;
+    let info = update.as_ref().map(UpdateInfo::from);
+    if let Some(info) = &info {
+        tracing::info!("Update {} available", info.version);
+    }
+    *app.state::<UpdaterState>().pending.lock().unwrap() = update;
+    Ok(info)
+}
+
+/// Download, verify and install the update found by [`check`], then restart
+///
+/// The signature is checked before anything is installed. On Windows the
+/// installer closes the app itself.
+pub async fn install(app: &AppHandle) -> Result<()> {
+    let update = app
+        .state::<UpdaterState>()
+        .pending
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or(UpdateError::NotChecked)?;
+
+    let mut downloaded = 0u64;
+    update
+        .download_and_install(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = app.emit(UPDATE_PROGRESS_EVENT, UpdateProgress { downloaded, total });
+            },
+            || {},
+        )
+        .await?;
+    tracing::info!("Update {} installed, restarting", update.version);
+    app.restart()
+}
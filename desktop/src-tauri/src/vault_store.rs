@@ -0,0 +1,222 @@
+use crate::storage::{Storage, StorageError, StoredItem};
+use crypto_core::cipher::{EncryptedBlob, KEY_SIZE};
+use crypto_core::error::CryptoError;
+use crypto_core::vault::{Vault, VaultItem};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum VaultStoreError {
+    #[error(transparent)]
+    Crypto(#[from] CryptoError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+pub type Result<T> = std::result::Result<T, VaultStoreError>;
+
+/// Encrypt an item on its own, as the sync server stores it
+pub fn encrypt_item(item: &VaultItem, key: &[u8; KEY_SIZE]) -> Result<StoredItem> {
+    Ok(StoredItem {
+        item_id: item.id.clone(),
+        encrypted_item: item.export(key)?.to_base64(),
+        modified_at: item.modified_at as i64,
+    })
+}
+
+pub fn decrypt_item(encrypted_item: &str, key: &[u8; KEY_SIZE]) -> Result<VaultItem> {
+    let blob = EncryptedBlob::from_base64(encrypted_item)?;
+    Ok(VaultItem::import(&blob, key)?)
+}
+
+/// The vault without its items, which are stored one by one
+///
+/// Holds what isn't per item: categories, trash and format version.
+fn encrypt_header(vault: &Vault, key: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
+    let header = Vault {
+        version: vault.version,
+        items: Vec::new(),
+        categories: vault.categories.clone(),
+        last_sync: vault.last_sync,
+        trash: vault.trash.clone(),
+    };
+    let encrypted = header.export(key)?;
+    Ok(serde_json::to_vec(&encrypted).map_err(|e| CryptoError::Serialization(e.to_string()))?)
+}
+
+/// Decrypt a vault blob and add its stored items
+///
+/// Vaults saved before items were stored separately carry their items in the
+/// blob; they're moved into the item table the first time they're opened.
+/// Fails with [`StorageError::Corrupted`] if any item is damaged, rather than
+/// opening a vault whose next full save would drop it. An item that doesn't
+/// decrypt once its blob opened is marked damaged, so the vault reports
+/// itself as needing recovery.
+pub fn load(storage: &Storage, encrypted_data: &[u8], key: &[u8; KEY_SIZE]) -> Result<Vault> {
+    let encrypted: EncryptedBlob = serde_json::from_slice(encrypted_data)
+        .map_err(|e| CryptoError::Deserialization(e.to_string()))?;
+    let mut vault = Vault::import(&encrypted, key)?;
+
+    let stored = storage.load_items()?;
+    if stored.is_empty() {
+        if !vault.items.is_empty() {
+            save(storage, &vault, key)?;
+        }
+        return Ok(vault);
+    }
+
+    let mut damaged = false;
+    for stored in &stored {
+        match decrypt_item(&stored.encrypted_item, key) {
+            Ok(item) => vault.items.push(item),
+            Err(e) => {
+                tracing::error!("Item {} is unreadable: {}", stored.item_id, e);
+                storage.mark_item_damaged(&stored.item_id)?;
+                damaged = true;
+            }
+        }
+    }
+    if damaged {
+        return Err(StorageError::Corrupted.into());
+    }
+    Ok(vault)
+}
+
+/// Write the whole vault, replacing every stored item
+///
+/// For new vaults, new keys and bulk changes; everyday edits use [`save_items`].
+pub fn save(storage: &Storage, vault: &Vault, key: &[u8; KEY_SIZE]) -> Result<()> {
+    let items = vault
+        .items
+        .iter()
+        .map(|item| encrypt_item(item, key))
+        .collect::<Result<Vec<_>>>()?;
+    storage.replace_vault(&encrypt_header(vault, key)?, &items)?;
+    Ok(())
+}
+
+/// Persist the items with these ids, removing those no longer in the vault
+///
+/// Only the named items are encrypted and written, so an edit costs the
+/// same however large the vault is.
+pub fn save_items<'a>(
+    storage: &Storage,
+    vault: &Vault,
+    key: &[u8; KEY_SIZE],
+    ids: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    let mut upserts = Vec::new();
+    let mut deletes = Vec::new();
+    for id in ids {
+        match vault.get_item(id) {
+            Some(item) => upserts.push(encrypt_item(item, key)?),
+            None => deletes.push(id.to_string()),
+        }
+    }
+    storage.write_items(&upserts, &deletes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; KEY_SIZE] = [7u8; KEY_SIZE];
+
+    #[test]
+    fn test_header_leaves_out_items() {
+        let mut vault = Vault::new();
+        vault.add_item(VaultItem::new("GitHub", "octocat", "hunter2"));
+        vault.add_category("Work");
+
+        let encrypted: EncryptedBlob =
+            serde_json::from_slice(&encrypt_header(&vault, &KEY).unwrap()).unwrap();
+        let header = Vault::import(&encrypted, &KEY).unwrap();
+        assert!(header.items.is_empty());
+        assert_eq!(header.categories, vault.categories);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let storage = Storage::open_in_memory();
+        storage.create_vault(&[1u8; 16]).unwrap();
+        let mut vault = Vault::new();
+        let github = vault.add_item(VaultItem::new("GitHub", "octocat", "hunter2"));
+        let email = vault.add_item(VaultItem::new("Email", "me", "secret"));
+        save(&storage, &vault, &KEY).unwrap();
+
+        vault.get_item_mut(&github).unwrap().password = "changed".to_string();
+        vault.remove_item(&email).unwrap();
+        save_items(&storage, &vault, &KEY, [github.as_str(), email.as_str()]).unwrap();
+
+        let loaded = load(&storage, &storage.load_vault().unwrap(), &KEY).unwrap();
+        assert_eq!(loaded.items, vault.items);
+        assert_eq!(storage.load_items().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_load_migrates_whole_vault_blob() {
+        let storage = Storage::open_in_memory();
+        storage.create_vault(&[1u8; 16]).unwrap();
+        let mut vault = Vault::new();
+        vault.add_item(VaultItem::new("GitHub", "octocat", "hunter2"));
+        let legacy = serde_json::to_vec(&vault.export(&KEY).unwrap()).unwrap();
+        storage.save_vault(&legacy).unwrap();
+
+        let loaded = load(&storage, &legacy, &KEY).unwrap();
+        assert_eq!(loaded.items, vault.items);
+        assert_eq!(storage.load_items().unwrap().len(), 1);
+
+        // The blob no longer carries the items
+        let reloaded = load(&storage, &storage.load_vault().unwrap(), &KEY).unwrap();
+        assert_eq!(reloaded.items, vault.items);
+        let encrypted: EncryptedBlob =
+            serde_json::from_slice(&storage.load_vault().unwrap()).unwrap();
+        assert!(Vault::import(&encrypted, &KEY).unwrap().items.is_empty());
+    }
+
+    #[test]
+    fn test_item_round_trip() {
+        let item = VaultItem::new("GitHub", "octocat", "hunter2");
+        let stored = encrypt_item(&item, &KEY).unwrap();
+        assert_eq!(stored.item_id, item.id);
+        assert_eq!(decrypt_item(&stored.encrypted_item, &KEY).unwrap(), item);
+        assert!(decrypt_item(&stored.encrypted_item, &[8u8; KEY_SIZE]).is_err());
+    }
+
+    #[test]
+    fn test_load_refuses_damaged_items() {
+        let storage = Storage::open_in_memory();
+        storage.create_vault(&[1u8; 16]).unwrap();
+        let mut vault = Vault::new();
+        vault.add_item(VaultItem::new("GitHub", "octocat", "hunter2"));
+        let email = vault.add_item(VaultItem::new("Email", "me", "secret"));
+        save(&storage, &vault, &KEY).unwrap();
+
+        // Intact HMAC over an item that no longer decrypts
+        let mut unreadable =
+            encrypt_item(vault.get_item(&email).unwrap(), &[8u8; KEY_SIZE]).unwrap();
+        unreadable.item_id = email.clone();
+        storage.write_items(&[unreadable], &[]).unwrap();
+        assert!(!storage.vault_damaged().unwrap());
+
+        let encrypted = storage.load_vault().unwrap();
+        assert!(matches!(
+            load(&storage, &encrypted, &KEY),
+            Err(VaultStoreError::Storage(StorageError::Corrupted))
+        ));
+        assert_eq!(storage.damaged_items().unwrap(), vec![email]);
+        assert!(storage.vault_damaged().unwrap());
+
+        // A full save can't drop the damaged item; recovery removes it explicitly
+        assert!(save(&storage, &Vault::new(), &KEY).is_err());
+        storage.delete_damaged_items().unwrap();
+        save(&storage, &vault, &KEY).unwrap();
+        assert_eq!(
+            load(&storage, &storage.load_vault().unwrap(), &KEY)
+                .unwrap()
+                .items,
+            vault.items
+        );
+    }
+}
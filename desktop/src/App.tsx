@@ -1,17 +1,29 @@
 import { useState, useEffect, useCallback } from 'react';
 import { useVault } from './hooks/useVault';
 import { useSync } from './hooks/useSync';
-import { VaultItem, RemoteCommand, tauri } from './hooks/useTauri';
+import { useItemSearch } from './hooks/useItemSearch';
+import { useActivityReporter } from './hooks/useActivityReporter';
+import { useVaultLocked } from './hooks/useVaultLocked';
+import { listen } from '@tauri-apps/api/event';
+import { tauri, VaultItem, RemoteCommandEvent, DeepLink, SearchSort } from './hooks/useTauri';
 import UnlockScreen from './components/UnlockScreen';
 import VaultList from './components/VaultList';
 import CredentialForm from './components/CredentialForm';
 import SearchBar from './components/SearchBar';
 import SyncStatusIndicator from './components/SyncStatusIndicator';
+import BackupSettings from './components/BackupSettings';
+import SyncConflicts from './components/SyncConflicts';
+import EmergencyAccess from './components/EmergencyAccess';
+import ActivityLog from './components/ActivityLog';
+import UpdateNotice from './components/UpdateNotice';
+import LockWarning from './components/LockWarning';
 
 const icons = {
   list: <path d="M3 13h2v-2H3v2zm0 4h2v-2H3v2zm0-8h2V7H3v2zm4 4h14v-2H7v2zm0 4h14v-2H7v2zM7 7v2h14V7H7z"/>,
   star: <path d="M12 17.27L18.18 21l-1.64-7.03L22 9.24l-7.19-.61L12 2 9.19 8.63 2 9.24l5.46 4.73L5.82 21z"/>,
   plus: <path d="M19 13h-6v6h-2v-6H5v-2h6V5h2v6h6v2z"/>,
+  backup: <path d="M19.35 10.04C18.67 6.59 15.64 4 12 4 9.11 4 6.6 5.64 5.35 8.04 2.34 8.36 0 10.91 0 14c0 3.31 2.69 6 6 6h13c2.76 0 5-2.24 5-5 0-2.64-2.05-4.78-4.65-4.96zM14 13v4h-4v-4H7l5-5 5 5h-3z"/>,
+  shield: <path d="M12 1L3 5v6c0 5.55 3.84 10.74 9 12 5.16-1.26 9-6.45 9-12V5l-9-4z"/>,
   lock: <path d="M18 8h-1V6c0-2.76-2.24-5-5-5S7 3.24 7 6v2H6c-1.1 0-2 .9-2 2v10c0 1.1.9 2 2 2h12c1.1 0 2-.9 2-2V10c0-1.1-.9-2-2-2zm-6 9c-1.1 0-2-.9-2-2s.9-2 2-2 2 .9 2 2-.9 2-2 2zm3.1-9H8.9V6c0-1.71 1.39-3.1 3.1-3.1 1.71 0 3.1 1.39 3.1 3.1v2z"/>,
 };
 
@@ -26,26 +38,31 @@ function Icon({ name }: { name: keyof typeof icons }) {
 type View = 'all' | 'favorites' | 'generator';
 
 function App() {
+  useActivityReporter();
   const {
     status,
-    items,
+    revision,
     loading,
     error,
     createVault,
     unlock,
+    unlockWithBiometrics,
+    recover,
+    restoreFromSync,
     lock,
     addItem,
     updateItem,
     deleteItem,
-    search,
+    refreshItems,
     clearError,
   } = useVault();
 
-  const handleRemoteCommand = useCallback(async (command: RemoteCommand) => {
-    if (command.command_type === 'lock') {
+  // The backend has already locked or wiped the vault; tell the user and refresh
+  const handleRemoteCommand = useCallback(async (event: RemoteCommandEvent) => {
+    window.alert(event.message);
+    if (event.command_type === 'lock') {
       await lock();
-    } else if (command.command_type === 'wipe') {
-      await tauri.wipeVault();
+    } else if (event.command_type === 'wipe') {
       window.location.reload();
     }
   }, [lock]);
@@ -54,21 +71,67 @@ function App() {
 
   const [view, setView] = useState<View>('all');
   const [searchQuery, setSearchQuery] = useState('');
-  const [searchResults, setSearchResults] = useState<VaultItem[] | null>(null);
+  const [sort, setSort] = useState<SearchSort>('name');
+  const [counts, setCounts] = useState({ all: 0, favorites: 0 });
   const [showForm, setShowForm] = useState(false);
   const [editingItem, setEditingItem] = useState<VaultItem | null>(null);
+  const [showBackups, setShowBackups] = useState(false);
+  const [showConflicts, setShowConflicts] = useState(false);
+  const [showEmergency, setShowEmergency] = useState(false);
+  const [showActivity, setShowActivity] = useState(false);
+  const [invitation, setInvitation] = useState<Extract<DeepLink, { type: 'emergency_invitation' }> | null>(null);
 
+  // Don't bring back forms or dialogs holding decrypted data after unlocking again
+  useVaultLocked(() => {
+    setShowForm(false);
+    setEditingItem(null);
+    setSearchQuery('');
+    setShowBackups(false);
+    setShowConflicts(false);
+    setShowEmergency(false);
+    setShowActivity(false);
+    setInvitation(null);
+  });
+
+  const unlocked = status?.unlocked ?? false;
+  const list = useItemSearch(
+    searchQuery,
+    view === 'favorites' ? { favorite: true } : {},
+    sort,
+    revision,
+    unlocked,
+  );
+
+  useEffect(() => {
+    if (!unlocked) return;
+
+    Promise.all([
+      tauri.searchItems({ limit: 0 }),
+      tauri.searchItems({ filter: { favorite: true }, limit: 0 }),
+    ])
+      .then(([all, favorites]) => setCounts({ all: all.total, favorites: favorites.total }))
+      .catch(() => {});
+  }, [unlocked, revision]);
+
+  // keydrop:// links wait in the backend until the vault is unlocked
   useEffect(() => {
-    const performSearch = async () => {
-      if (searchQuery.trim()) {
-        const results = await search(searchQuery);
-        setSearchResults(results);
-      } else {
-        setSearchResults(null);
+    if (!unlocked) return;
+
+    const openLink = async () => {
+      const link = await tauri.takeDeepLink().catch(() => null);
+      if (link?.type === 'emergency_invitation') {
+        setInvitation(link);
+        setShowEmergency(true);
+      } else if (link?.type === 'share') {
+        window.alert('Shared items are not supported yet.');
       }
     };
-    performSearch();
-  }, [searchQuery, search]);
+    openLink();
+    const unlisten = listen('deep-link', openLink);
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [unlocked]);
 
   if (loading) {
     return (
@@ -82,7 +145,14 @@ function App() {
     return (
       <UnlockScreen
         hasVault={status?.exists ?? false}
+        keyFileRequired={status?.key_file_required ?? false}
+        damaged={status?.damaged ?? false}
+        restorable={status?.restorable ?? false}
+        retryAfter={status?.retry_after ?? 0}
         onUnlock={unlock}
+        onBiometricUnlock={unlockWithBiometrics}
+        onRecover={recover}
+        onRestoreFromSync={restoreFromSync}
         onCreate={createVault}
         error={error}
         onClearError={clearError}
@@ -90,8 +160,6 @@ function App() {
     );
   }
 
-  const displayedItems = searchResults ?? (view === 'favorites' ? items.filter(i => i.favorite) : items);
-
   const handleEdit = (item: VaultItem) => {
     setEditingItem(item);
     setShowForm(true);
@@ -139,13 +207,13 @@ function App() {
               className={`nav-item ${view === 'all' ? 'active' : ''}`}
               onClick={() => { setView('all'); setSearchQuery(''); }}
             >
-              <Icon name="list" /> All Items ({items.length})
+              <Icon name="list" /> All Items ({counts.all})
             </div>
             <div
               className={`nav-item ${view === 'favorites' ? 'active' : ''}`}
               onClick={() => { setView('favorites'); setSearchQuery(''); }}
             >
-              <Icon name="star" /> Favorites ({items.filter(i => i.favorite).length})
+              <Icon name="star" /> Favorites ({counts.favorites})
             </div>
           </div>
 
@@ -154,6 +222,15 @@ function App() {
             <div className="nav-item" onClick={handleAdd}>
               <Icon name="plus" /> Add New
             </div>
+            <div className="nav-item" onClick={() => setShowBackups(true)}>
+              <Icon name="backup" /> Backups
+            </div>
+            <div className="nav-item" onClick={() => setShowEmergency(true)}>
+              <Icon name="shield" /> Emergency Access
+            </div>
+            <div className="nav-item" onClick={() => setShowActivity(true)}>
+              <Icon name="list" /> Activity Log
+            </div>
             <div className="nav-item" onClick={lock}>
               <Icon name="lock" /> Lock Vault
             </div>
@@ -171,6 +248,16 @@ function App() {
               status={sync.status}
               onSyncClick={sync.triggerSync}
             />
+            <select
+              className="input"
+              value={sort}
+              onChange={(e) => setSort(e.target.value as SearchSort)}
+              style={{ width: 'auto' }}
+            >
+              <option value="name">Name</option>
+              <option value="recent">Recently changed</option>
+              <option value="last_used">Recently used</option>
+            </select>
             <SearchBar
               value={searchQuery}
               onChange={setSearchQuery}
@@ -179,19 +266,37 @@ function App() {
           </div>
         </div>
 
-        {error && (
+        <LockWarning />
+        <UpdateNotice />
+
+        {(error || list.error) && (
           <div className="error-message">
-            {error}
+            {error ?? list.error}
             <button className="btn btn-ghost" onClick={clearError}>×</button>
           </div>
         )}
 
+        {sync.status.conflicts > 0 && (
+          <div className="error-message">
+            {sync.status.conflicts === 1
+              ? '1 item was changed on another device too.'
+              : `${sync.status.conflicts} items were changed on another device too.`}
+            <button className="btn btn-ghost" onClick={() => setShowConflicts(true)}>Review</button>
+          </div>
+        )}
+
         <VaultList
-          items={displayedItems}
+          items={list.items}
           onEdit={handleEdit}
           onDelete={handleDelete}
         />
 
+        {list.hasMore && (
+          <button className="btn btn-ghost" onClick={list.loadMore}>
+            Show more ({list.total - list.items.length} left)
+          </button>
+        )}
+
         {showForm && (
           <CredentialForm
             item={editingItem}
@@ -199,6 +304,24 @@ function App() {
             onCancel={() => { setShowForm(false); setEditingItem(null); }}
           />
         )}
+
+        {showBackups && <BackupSettings onClose={() => setShowBackups(false)} />}
+
+        {showEmergency && (
+          <EmergencyAccess
+            invitation={invitation}
+            onClose={() => { setShowEmergency(false); setInvitation(null); }}
+          />
+        )}
+
+        {showActivity && <ActivityLog onClose={() => setShowActivity(false)} />}
+
+        {showConflicts && (
+          <SyncConflicts
+            onResolved={() => { refreshItems(); sync.refresh(); }}
+            onClose={() => setShowConflicts(false)}
+          />
+        )}
       </main>
     </div>
   );
@@ -0,0 +1,71 @@
+import { useState, useEffect } from 'react';
+import { tauri, type ActivityEvent, type ActivityKind } from '../hooks/useTauri';
+
+interface ActivityLogProps {
+  onClose: () => void;
+}
+
+const KIND_LABELS: Record<ActivityKind, string> = {
+  unlock: 'Unlocked',
+  failed_unlock: 'Failed unlock',
+  self_wipe: 'Vault erased',
+  export: 'Exported',
+  item_deleted: 'Item deleted',
+  sync: 'Synced',
+  remote_command: 'Remote command',
+  emergency_view: 'Emergency vault opened',
+};
+
+export default function ActivityLog({ onClose }: ActivityLogProps) {
+  const [events, setEvents] = useState<ActivityEvent[]>([]);
+  const [kind, setKind] = useState<ActivityKind | ''>('');
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    tauri.getActivityLog({ kinds: kind ? [kind] : [], limit: 200 })
+      .then(setEvents)
+      .catch((e) => setError(String(e)));
+  }, [kind]);
+
+  return (
+    <div className="modal-overlay" onClick={onClose}>
+      <div className="modal" onClick={(e) => e.stopPropagation()}>
+        <div className="modal-header">
+          <h2 className="modal-title">Activity on This Device</h2>
+          <button className="modal-close" onClick={onClose}>×</button>
+        </div>
+
+        {error && <div className="error-message">{error}</div>}
+
+        <div className="input-group">
+          <select
+            className="input"
+            value={kind}
+            onChange={(e) => setKind(e.target.value as ActivityKind | '')}
+          >
+            <option value="">All activity</option>
+            {Object.entries(KIND_LABELS).map(([value, label]) => (
+              <option key={value} value={value}>{label}</option>
+            ))}
+          </select>
+        </div>
+
+        {events.length === 0 ? (
+          <p>No activity recorded.</p>
+        ) : (
+          <table style={{ width: '100%' }}>
+            <tbody>
+              {events.map((event) => (
+                <tr key={event.id}>
+                  <td>{new Date(event.created_at * 1000).toLocaleString()}</td>
+                  <td>{KIND_LABELS[event.kind]}</td>
+                  <td>{event.detail}</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,239 @@
+import { useState, useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import {
+  tauri,
+  type AutoBackupConfig,
+  type AutoBackupStatus,
+  type StorageInfo,
+} from '../hooks/useTauri';
+
+interface BackupSettingsProps {
+  onClose: () => void;
+}
+
+function formatBackupTime(timestamp: number | null): string {
+  if (!timestamp) return 'Never';
+  return new Date(timestamp * 1000).toLocaleString();
+}
+
+function formatSize(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+export default function BackupSettings({ onClose }: BackupSettingsProps) {
+  const [config, setConfig] = useState<AutoBackupConfig | null>(null);
+  const [status, setStatus] = useState<AutoBackupStatus | null>(null);
+  const [directory, setDirectory] = useState('');
+  const [hasPassphrase, setHasPassphrase] = useState(false);
+  const [passphrase, setPassphrase] = useState('');
+  const [error, setError] = useState<string | null>(null);
+  const [busy, setBusy] = useState(false);
+  const [storage, setStorage] = useState<StorageInfo | null>(null);
+  const [compactResult, setCompactResult] = useState<string | null>(null);
+
+  useEffect(() => {
+    tauri.getStorageInfo().then(setStorage).catch(() => {});
+
+    tauri.getAutoBackup()
+      .then((settings) => {
+        setConfig(settings.config);
+        setStatus(settings.status);
+        setDirectory(settings.directory);
+        setHasPassphrase(settings.has_passphrase);
+      })
+      .catch((e) => setError(String(e)));
+
+    const unlisten = listen<AutoBackupStatus>('backup-status', (event) => {
+      setStatus(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  if (!config) {
+    return null;
+  }
+
+  const update = (changes: Partial<AutoBackupConfig>) => setConfig({ ...config, ...changes });
+
+  const handleSave = async () => {
+    setBusy(true);
+    setError(null);
+    try {
+      await tauri.setAutoBackup(
+        { ...config, directory: directory.trim() || null },
+        passphrase || undefined,
+      );
+      if (passphrase) {
+        setHasPassphrase(true);
+        setPassphrase('');
+      }
+      onClose();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleCompact = async () => {
+    setBusy(true);
+    setError(null);
+    try {
+      const report = await tauri.compactStorage();
+      setCompactResult(`Removed ${report.removed} unused entries, freed ${formatSize(report.freed)}`);
+      setStorage(await tauri.getStorageInfo());
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const handleBackupNow = async () => {
+    setBusy(true);
+    setError(null);
+    try {
+      setStatus(await tauri.runBackupNow());
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div className="modal-overlay" onClick={onClose}>
+      <div className="modal" onClick={(e) => e.stopPropagation()}>
+        <div className="modal-header">
+          <h2 className="modal-title">Automatic Backups</h2>
+          <button className="modal-close" onClick={onClose}>×</button>
+        </div>
+
+        {error && <div className="error-message">{error}</div>}
+
+        <div className="input-group">
+          <label className="input-label">
+            <input
+              type="checkbox"
+              checked={config.enabled}
+              onChange={(e) => update({ enabled: e.target.checked })}
+            />{' '}
+            Back up automatically
+          </label>
+        </div>
+
+        <div className="input-group">
+          <label className="input-label">Backup Folder</label>
+          <input
+            type="text"
+            className="input"
+            value={directory}
+            onChange={(e) => setDirectory(e.target.value)}
+          />
+        </div>
+
+        <div className="input-group">
+          <label className="input-label">
+            {hasPassphrase ? 'New Backup Passphrase (optional)' : 'Backup Passphrase *'}
+          </label>
+          <input
+            type="password"
+            className="input"
+            value={passphrase}
+            onChange={(e) => setPassphrase(e.target.value)}
+            placeholder="Needed to restore these backups"
+          />
+        </div>
+
+        <div className="input-group">
+          <label className="input-label">Back up after this many changes (0 = off)</label>
+          <input
+            type="number"
+            min={0}
+            className="input"
+            value={config.every_changes}
+            onChange={(e) => update({ every_changes: Number(e.target.value) })}
+          />
+        </div>
+
+        <div className="input-group">
+          <label className="input-label">
+            <input
+              type="checkbox"
+              checked={config.daily}
+              onChange={(e) => update({ daily: e.target.checked })}
+            />{' '}
+            Back up daily when the vault changed
+          </label>
+        </div>
+
+        <div className="input-group">
+          <label className="input-label">Keep newest backups (0 = all)</label>
+          <input
+            type="number"
+            min={0}
+            className="input"
+            value={config.keep_count}
+            onChange={(e) => update({ keep_count: Number(e.target.value) })}
+          />
+        </div>
+
+        <div className="input-group">
+          <label className="input-label">Delete backups older than days (0 = never)</label>
+          <input
+            type="number"
+            min={0}
+            className="input"
+            value={config.keep_days}
+            onChange={(e) => update({ keep_days: Number(e.target.value) })}
+          />
+        </div>
+
+        {status && (
+          <div className="input-group">
+            <div>Last backup: {formatBackupTime(status.last_backup_at)}</div>
+            <div>Changes since last backup: {status.changes_since_backup}</div>
+            {status.last_error && (
+              <div className="error-message">Last attempt failed: {status.last_error}</div>
+            )}
+          </div>
+        )}
+
+        {storage && (
+          <div className="input-group">
+            <label className="input-label">Storage</label>
+            <div>Vault: {formatSize(storage.vault_size)}</div>
+            <div>Attachments: {formatSize(storage.attachment_size)}</div>
+            <div>Automatic backups: {storage.backup_count}</div>
+            <div>
+              Database: {formatSize(storage.database_size)}
+              {storage.reclaimable_size > 0 && ` (${formatSize(storage.reclaimable_size)} reclaimable)`}
+            </div>
+            {compactResult && <div>{compactResult}</div>}
+            <button type="button" className="btn btn-secondary" onClick={handleCompact} disabled={busy}>
+              Compact Database
+            </button>
+          </div>
+        )}
+
+        <div className="modal-footer">
+          <button
+            type="button"
+            className="btn btn-secondary"
+            onClick={handleBackupNow}
+            disabled={busy || !hasPassphrase}
+          >
+            Back Up Now
+          </button>
+          <button type="button" className="btn btn-primary" onClick={handleSave} disabled={busy}>
+            Save
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
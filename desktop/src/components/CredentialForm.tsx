@@ -39,6 +39,7 @@ export default function CredentialForm({ item, onSave, onCancel }: CredentialFor
   const [notes, setNotes] = useState(item?.notes ?? '');
   const [category, setCategory] = useState(item?.category ?? 'Login');
   const [favorite, setFavorite] = useState(item?.favorite ?? false);
+  const [autoType, setAutoType] = useState(item?.auto_type ?? '');
   const [showPassword, setShowPassword] = useState(false);
   const [showGenerator, setShowGenerator] = useState(false);
   const [saving, setSaving] = useState(false);
@@ -55,6 +56,7 @@ export default function CredentialForm({ item, onSave, onCancel }: CredentialFor
         notes: notes || null,
         category: category || null,
         favorite,
+        auto_type: autoType || null,
       });
     } finally {
       setSaving(false);
@@ -140,7 +142,7 @@ export default function CredentialForm({ item, onSave, onCancel }: CredentialFor
           </div>
 
           {showGenerator && (
-            <PasswordGenerator onSelect={handlePasswordGenerated} />
+            <PasswordGenerator onSelect={handlePasswordGenerated} site={url || undefined} />
           )}
 
           <div className="input-group">
@@ -168,6 +170,17 @@ export default function CredentialForm({ item, onSave, onCancel }: CredentialFor
             />
           </div>
 
+          <div className="input-group">
+            <label className="input-label">Auto-type Sequence</label>
+            <input
+              type="text"
+              className="input"
+              value={autoType}
+              onChange={(e) => setAutoType(e.target.value)}
+              placeholder="{USERNAME}{TAB}{PASSWORD}{ENTER}"
+            />
+          </div>
+
           <div className="input-group">
             <label className="checkbox-wrapper">
               <input
@@ -1,85 +1,22 @@
 import { useState, useEffect } from 'react';
-
-interface EmergencyContact {
-  id: string;
-  email: string;
-  name: string | null;
-  status: 'pending' | 'accepted' | 'revoked';
-  waitingPeriodHours: number;
-  canViewVault: boolean;
-  acceptedAt: number | null;
-  createdAt: number;
-}
-
-interface EmergencyAccessRequest {
-  id: string;
-  contactId: string;
-  contactEmail: string;
-  contactName: string | null;
-  reason: string | null;
-  waitingPeriodEndsAt: number;
-  createdAt: number;
-}
-
-interface GrantedAccess {
-  contactId: string;
-  userEmail: string;
-  requestId: string;
-  approvedAt: number;
-}
+import {
+  tauri,
+  type DeepLink,
+  type EmergencyContact,
+  type GrantedAccess,
+  type PendingAccessRequest,
+} from '../hooks/useTauri';
+import EmergencyVaultViewer from './EmergencyVaultViewer';
 
 interface EmergencyAccessProps {
+  /** Invitation from a keydrop:// link, offered for acceptance */
+  invitation?: Extract<DeepLink, { type: 'emergency_invitation' }> | null;
   onClose: () => void;
 }
 
-// Mock API functions
-async function fetchContacts(): Promise<EmergencyContact[]> {
-  return [
-    {
-      id: '1',
-      email: 'trusted@example.com',
-      name: 'Trusted Person',
-      status: 'accepted',
-      waitingPeriodHours: 48,
-      canViewVault: true,
-      acceptedAt: Date.now() - 86400000,
-      createdAt: Date.now() - 172800000,
-    },
-  ];
-}
-
-async function fetchPendingRequests(): Promise<EmergencyAccessRequest[]> {
-  return [];
-}
-
-async function fetchGrantedAccess(): Promise<GrantedAccess[]> {
-  return [];
-}
-
-async function addContact(email: string, name: string | null, waitingPeriodHours: number): Promise<EmergencyContact> {
-  return {
-    id: String(Date.now()),
-    email,
-    name,
-    status: 'pending',
-    waitingPeriodHours,
-    canViewVault: true,
-    acceptedAt: null,
-    createdAt: Date.now(),
-  };
-}
-
-async function removeContact(contactId: string): Promise<void> {
-  console.log('Removing contact:', contactId);
-}
-
-async function denyRequest(requestId: string): Promise<void> {
-  console.log('Denying request:', requestId);
-}
-
 function formatTimeRemaining(endsAt: number): string {
   const now = Date.now();
-  const remaining = endsAt - now;
+  const remaining = endsAt * 1000 - now;
 
   if (remaining <= 0) {
     return 'Access will be granted soon';
@@ -95,12 +32,13 @@ function formatTimeRemaining(endsAt: number): string {
 }
 
 function formatDate(timestamp: number): string {
-  return new Date(timestamp).toLocaleDateString();
+  return new Date(timestamp * 1000).toLocaleDateString();
 }
 
-export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
+export default function EmergencyAccess({ invitation, onClose }: EmergencyAccessProps) {
+  const [pendingInvitation, setPendingInvitation] = useState(invitation ?? null);
   const [contacts, setContacts] = useState<EmergencyContact[]>([]);
-  const [pendingRequests, setPendingRequests] = useState<EmergencyAccessRequest[]>([]);
+  const [pendingRequests, setPendingRequests] = useState<PendingAccessRequest[]>([]);
   const [grantedAccess, setGrantedAccess] = useState<GrantedAccess[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
@@ -110,6 +48,7 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
   const [addWaitingPeriod, setAddWaitingPeriod] = useState(48);
   const [confirmRemove, setConfirmRemove] = useState<string | null>(null);
   const [confirmDeny, setConfirmDeny] = useState<string | null>(null);
+  const [viewing, setViewing] = useState<GrantedAccess | null>(null);
 
   useEffect(() => {
     loadData();
@@ -119,9 +58,9 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
     setLoading(true);
     try {
       const [contactsData, requestsData, accessData] = await Promise.all([
-        fetchContacts(),
-        fetchPendingRequests(),
-        fetchGrantedAccess(),
+        tauri.listEmergencyContacts(),
+        tauri.listEmergencyRequests(),
+        tauri.getEmergencyVaultAccess(),
       ]);
       setContacts(contactsData);
       setPendingRequests(requestsData);
@@ -137,7 +76,7 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
     if (!addEmail.trim()) return;
 
     try {
-      const contact = await addContact(addEmail, addName || null, addWaitingPeriod);
+      const contact = await tauri.addEmergencyContact(addEmail, addName || null, addWaitingPeriod);
       setContacts([...contacts, contact]);
       setShowAddDialog(false);
       setAddEmail('');
@@ -148,9 +87,20 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
     }
   };
 
+  const handleAcceptInvitation = async () => {
+    if (!pendingInvitation) return;
+    try {
+      await tauri.acceptEmergencyInvitation(pendingInvitation.contact_id, pendingInvitation.token);
+      setPendingInvitation(null);
+      await loadData();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
   const handleRemoveContact = async (contactId: string) => {
     try {
-      await removeContact(contactId);
+      await tauri.removeEmergencyContact(contactId);
       setContacts(contacts.filter(c => c.id !== contactId));
       setConfirmRemove(null);
     } catch (e) {
@@ -160,8 +110,8 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
 
   const handleDenyRequest = async (requestId: string) => {
     try {
-      await denyRequest(requestId);
-      setPendingRequests(pendingRequests.filter(r => r.id !== requestId));
+      await tauri.denyEmergencyRequest(requestId);
+      setPendingRequests(pendingRequests.filter(r => r.request_id !== requestId));
       setConfirmDeny(null);
     } catch (e) {
       setError(String(e));
@@ -188,19 +138,37 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
             </div>
           ) : (
             <>
+              {pendingInvitation && (
+                <section className="section">
+                  <h3 className="section-title">Invitation</h3>
+                  <div className="request-item">
+                    <div className="request-info">
+                      You were invited to be someone's emergency contact. Accepting lets
+                      you request access to their vault.
+                    </div>
+                    <button className="btn btn-primary" onClick={handleAcceptInvitation}>
+                      Accept
+                    </button>
+                    <button className="btn btn-secondary" onClick={() => setPendingInvitation(null)}>
+                      Dismiss
+                    </button>
+                  </div>
+                </section>
+              )}
+
               {/* Pending Access Requests */}
               {pendingRequests.length > 0 && (
                 <section className="section">
                   <h3 className="section-title warning">Pending Access Requests</h3>
                   <div className="request-list">
                     {pendingRequests.map(request => (
-                      <div key={request.id} className="request-item warning">
+                      <div key={request.request_id} className="request-item warning">
                         <div className="request-info">
                           <div className="request-contact">
-                            {request.contactName || request.contactEmail}
+                            {request.contact_name || request.contact_email}
                           </div>
                           <div className="request-time">
-                            {formatTimeRemaining(request.waitingPeriodEndsAt)}
+                            {formatTimeRemaining(request.waiting_period_ends_at)}
                           </div>
                           {request.reason && (
                             <div className="request-reason">
@@ -210,7 +178,7 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
                         </div>
                         <button
                           className="btn btn-danger"
-                          onClick={() => setConfirmDeny(request.id)}
+                          onClick={() => setConfirmDeny(request.request_id)}
                         >
                           Deny
                         </button>
@@ -242,16 +210,19 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
                       <div key={contact.id} className="contact-item">
                         <div className="contact-info">
                           <div className="contact-name">
-                            {contact.name || contact.email}
+                            {contact.contact_name || contact.contact_email}
                           </div>
-                          {contact.name && (
-                            <div className="contact-email">{contact.email}</div>
+                          {contact.contact_name && (
+                            <div className="contact-email">{contact.contact_email}</div>
                           )}
                           <div className="contact-meta">
                             <span className={`status-badge ${contact.status}`}>
                               {contact.status}
                             </span>
-                            <span>{contact.waitingPeriodHours}h waiting period</span>
+                            <span>{contact.waiting_period_hours}h waiting period</span>
+                            {contact.status === 'accepted' && !contact.vault_key_shared && (
+                              <span>Vault key not shared yet; unlock and sync to share it</span>
+                            )}
                           </div>
                         </div>
                         <button
@@ -273,11 +244,23 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
                   <h3 className="section-title">Vaults You Can Access</h3>
                   <div className="access-list">
                     {grantedAccess.map(access => (
-                      <div key={access.requestId} className="access-item">
-                        <div className="access-email">{access.userEmail}</div>
+                      <div key={access.request_id} className="access-item">
+                        <div className="access-email">{access.owner_email}</div>
                         <div className="access-date">
-                          Approved: {formatDate(access.approvedAt)}
+                          Approved: {formatDate(access.approved_at)}
                         </div>
+                        <button
+                          className="btn btn-secondary"
+                          onClick={() => setViewing(access)}
+                          disabled={!access.key_available}
+                          title={
+                            access.key_available
+                              ? undefined
+                              : "The owner's vault key hasn't been shared with you yet"
+                          }
+                        >
+                          View
+                        </button>
                       </div>
                     ))}
                   </div>
@@ -287,6 +270,10 @@ export default function EmergencyAccess({ onClose }: EmergencyAccessProps) {
           )}
         </div>
 
+        {viewing && (
+          <EmergencyVaultViewer access={viewing} onClose={() => setViewing(null)} />
+        )}
+
         {/* Add Contact Dialog */}
         {showAddDialog && (
           <div className="confirm-overlay">
@@ -0,0 +1,141 @@
+import { useState, useEffect } from 'react';
+import {
+  tauri,
+  type EmergencyVaultInfo,
+  type GrantedAccess,
+  type VaultItem,
+} from '../hooks/useTauri';
+
+interface EmergencyVaultViewerProps {
+  access: GrantedAccess;
+  onClose: () => void;
+}
+
+/**
+ * Read-only view of a vault shared through emergency access, opened with the
+ * owner's vault key from the grant. Items can be looked at and copied, never
+ * edited, saved or synced.
+ */
+export default function EmergencyVaultViewer({ access, onClose }: EmergencyVaultViewerProps) {
+  const [info, setInfo] = useState<EmergencyVaultInfo | null>(null);
+  const [items, setItems] = useState<VaultItem[]>([]);
+  const [query, setQuery] = useState('');
+  const [exportPath, setExportPath] = useState('');
+  const [exportPassphrase, setExportPassphrase] = useState('');
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (!info) return;
+    tauri.listEmergencyVaultItems(query || undefined)
+      .then(setItems)
+      .catch((e) => setError(String(e)));
+  }, [info, query]);
+
+  useEffect(() => {
+    tauri.openEmergencyVault(access.request_id)
+      .then(setInfo)
+      .catch((e) => setError(String(e)));
+  }, [access.request_id]);
+
+  const close = async () => {
+    await tauri.closeEmergencyVault().catch(() => {});
+    onClose();
+  };
+
+  const copy = async (itemId: string, field: string) => {
+    try {
+      await tauri.copyEmergencySecret(itemId, field);
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const exportVault = async () => {
+    setError(null);
+    try {
+      await tauri.exportEmergencyVault(exportPath, exportPassphrase);
+      setExportPassphrase('');
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <div className="confirm-overlay">
+      <div className="confirm-dialog emergency-viewer">
+        <h3>{access.owner_email}'s vault (read-only)</h3>
+        {error && <div className="error-message">{error}</div>}
+
+        {!info ? (
+          <>
+            {!error && <p className="hint">Opening the vault...</p>}
+            <div className="confirm-actions">
+              <button className="btn btn-secondary" onClick={close}>Cancel</button>
+            </div>
+          </>
+        ) : (
+          <>
+            <input
+              type="search"
+              className="input"
+              value={query}
+              onChange={e => setQuery(e.target.value)}
+              placeholder={`Search ${info.item_count} items...`}
+            />
+            <div className="access-list">
+              {items.map(item => (
+                <div key={item.id} className="access-item">
+                  <div className="access-email">{item.name}</div>
+                  <div className="access-date">{item.username}</div>
+                  <div>
+                    {item.username && (
+                      <button className="btn btn-ghost" onClick={() => copy(item.id, 'username')}>
+                        Copy username
+                      </button>
+                    )}
+                    {item.password && (
+                      <button className="btn btn-ghost" onClick={() => copy(item.id, 'password')}>
+                        Copy password
+                      </button>
+                    )}
+                  </div>
+                </div>
+              ))}
+            </div>
+
+            {info.allow_export ? (
+              <div className="form-group">
+                <label>Export a backup</label>
+                <input
+                  type="text"
+                  value={exportPath}
+                  onChange={e => setExportPath(e.target.value)}
+                  placeholder="/path/to/export.kdbk"
+                />
+                <input
+                  type="password"
+                  value={exportPassphrase}
+                  onChange={e => setExportPassphrase(e.target.value)}
+                  placeholder="New backup passphrase"
+                />
+                <button
+                  className="btn btn-secondary"
+                  onClick={exportVault}
+                  disabled={!exportPath.trim() || !exportPassphrase}
+                >
+                  Export
+                </button>
+              </div>
+            ) : (
+              <p className="hint">The owner doesn't allow exporting this vault.</p>
+            )}
+
+            <div className="confirm-actions">
+              <button className="btn btn-primary" onClick={close}>Close</button>
+            </div>
+          </>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,28 @@
+import { useState, useEffect } from 'react';
+import { tauri, type LockCountdown } from '../hooks/useTauri';
+
+/** Warns shortly before the vault locks for inactivity, with a way to stay unlocked */
+export default function LockWarning() {
+  const [countdown, setCountdown] = useState<LockCountdown | null>(null);
+
+  useEffect(() => {
+    const poll = () => tauri.getLockCountdown().then(setCountdown).catch(() => {});
+    poll();
+    const timer = window.setInterval(poll, 1000);
+    return () => window.clearInterval(timer);
+  }, []);
+
+  if (!countdown?.warning || countdown.seconds_remaining === null) return null;
+
+  const stayUnlocked = async () => {
+    await tauri.reportActivity().catch(() => {});
+    setCountdown(await tauri.getLockCountdown().catch(() => null));
+  };
+
+  return (
+    <div className="lock-warning" role="alert">
+      <span>Locking in {countdown.seconds_remaining}s due to inactivity</span>
+      <button className="btn btn-primary" onClick={stayUnlocked}>Stay unlocked</button>
+    </div>
+  );
+}
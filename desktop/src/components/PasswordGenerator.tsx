@@ -1,5 +1,5 @@
 import { useState, useEffect } from 'react';
-import { tauri } from '../hooks/useTauri';
+import { tauri, type GeneratorOptions, type GeneratorPreset } from '../hooks/useTauri';
 
 const icons = {
   refresh: <path d="M17.65 6.35A7.958 7.958 0 0012 4c-4.42 0-7.99 3.58-7.99 8s3.57 8 7.99 8c3.73 0 6.84-2.55 7.73-6h-2.08A5.99 5.99 0 0112 18c-3.31 0-6-2.69-6-6s2.69-6 6-6c1.66 0 3.14.69 4.22 1.78L13 11h7V4l-2.35 2.35z"/>,
@@ -17,36 +17,49 @@ function Icon({ name }: { name: keyof typeof icons }) {
 
 interface PasswordGeneratorProps {
   onSelect: (password: string) => void;
+  /** Site the password is for, kept with it in the generator history */
+  site?: string;
 }
 
-export default function PasswordGenerator({ onSelect }: PasswordGeneratorProps) {
+const DEFAULT_PASSPHRASE: GeneratorOptions = {
+  type: 'passphrase',
+  word_count: 4,
+  separator: '-',
+  capitalize: false,
+  include_number: false,
+};
+
+const DEFAULT_PASSWORD: GeneratorOptions = {
+  type: 'password',
+  length: 16,
+  lowercase: true,
+  uppercase: true,
+  digits: true,
+  symbols: true,
+  exclude_ambiguous: false,
+  exclude_chars: '',
+};
+
+export default function PasswordGenerator({ onSelect, site }: PasswordGeneratorProps) {
   const [password, setPassword] = useState('');
-  const [length, setLength] = useState(16);
-  const [lowercase, setLowercase] = useState(true);
-  const [uppercase, setUppercase] = useState(true);
-  const [digits, setDigits] = useState(true);
-  const [symbols, setSymbols] = useState(true);
-  const [excludeAmbiguous, setExcludeAmbiguous] = useState(false);
-  const [mode, setMode] = useState<'password' | 'passphrase'>('password');
-  const [wordCount, setWordCount] = useState(4);
-  const [separator, setSeparator] = useState('-');
+  const [options, setOptions] = useState<GeneratorOptions | null>(null);
+  const [presets, setPresets] = useState<GeneratorPreset[]>([]);
+  const [presetName, setPresetName] = useState('');
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    tauri.getGeneratorOptions()
+      .then(setOptions)
+      .catch(() => setOptions(DEFAULT_PASSWORD));
+    tauri.listGeneratorPresets()
+      .then(setPresets)
+      .catch(() => {});
+  }, []);
 
   const generate = async () => {
+    if (!options) return;
     try {
-      let newPassword: string;
-      if (mode === 'password') {
-        newPassword = await tauri.generatePassword({
-          length,
-          lowercase,
-          uppercase,
-          digits,
-          symbols,
-          exclude_ambiguous: excludeAmbiguous,
-        });
-      } else {
-        newPassword = await tauri.generatePassphrase(wordCount, separator);
-      }
-      setPassword(newPassword);
+      setPassword(await tauri.generateWithOptions(options));
     } catch (err) {
       console.error('Failed to generate password:', err);
     }
@@ -54,12 +67,57 @@ export default function PasswordGenerator({ onSelect }: PasswordGeneratorProps)
 
   useEffect(() => {
     generate();
-  }, [length, lowercase, uppercase, digits, symbols, excludeAmbiguous, mode, wordCount, separator]);
+  }, [options]);
+
+  const update = (changes: Partial<GeneratorOptions>) =>
+    setOptions((prev) => (prev ? ({ ...prev, ...changes } as GeneratorOptions) : prev));
+
+  const setMode = (mode: GeneratorOptions['type']) => {
+    if (options?.type !== mode) {
+      setOptions(mode === 'password' ? DEFAULT_PASSWORD : DEFAULT_PASSPHRASE);
+    }
+  };
+
+  const savePreset = async () => {
+    if (!options || !presetName.trim()) return;
+    setError(null);
+    try {
+      setPresets(await tauri.saveGeneratorPreset({ name: presetName.trim(), options }));
+      setPresetName('');
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const deletePreset = async (name: string) => {
+    setError(null);
+    try {
+      setPresets(await tauri.deleteGeneratorPreset(name));
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  // Kept in history once it leaves the generator, in case it never gets saved
+  const remember = () => {
+    tauri.recordGeneratedPassword(password, site ?? null).catch(() => {});
+  };
 
   const copyToClipboard = async () => {
     await navigator.clipboard.writeText(password);
+    remember();
+  };
+
+  const handleUse = () => {
+    remember();
+    onSelect(password);
   };
 
+  if (!options) {
+    return null;
+  }
+  const mode = options.type;
+
   return (
     <div className="password-generator">
       <div style={{ display: 'flex', gap: '10px', marginBottom: '16px' }}>
@@ -79,6 +137,30 @@ export default function PasswordGenerator({ onSelect }: PasswordGeneratorProps)
         </button>
       </div>
 
+      {presets.length > 0 && (
+        <div style={{ display: 'flex', gap: '8px', flexWrap: 'wrap', marginBottom: '16px' }}>
+          {presets.map((preset) => (
+            <span key={preset.name} style={{ display: 'inline-flex' }}>
+              <button
+                type="button"
+                className="btn btn-secondary"
+                onClick={() => setOptions(preset.options)}
+              >
+                {preset.name}
+              </button>
+              <button
+                type="button"
+                className="btn btn-secondary"
+                title={`Delete ${preset.name}`}
+                onClick={() => deletePreset(preset.name)}
+              >
+                ×
+              </button>
+            </span>
+          ))}
+        </div>
+      )}
+
       <div className="password-display" style={{ fontFamily: 'monospace' }}>
         {password}
       </div>
@@ -90,22 +172,22 @@ export default function PasswordGenerator({ onSelect }: PasswordGeneratorProps)
         <button type="button" className="btn btn-secondary" onClick={copyToClipboard}>
           <Icon name="copy" /> Copy
         </button>
-        <button type="button" className="btn btn-primary" onClick={() => onSelect(password)}>
+        <button type="button" className="btn btn-primary" onClick={handleUse}>
           <Icon name="check" /> Use This
         </button>
       </div>
 
-      {mode === 'password' ? (
+      {options.type === 'password' ? (
         <>
           <div className="password-length">
-            <label className="input-label">Length: {length}</label>
+            <label className="input-label">Length: {options.length}</label>
             <input
               type="range"
               className="password-length-slider"
               min="8"
               max="64"
-              value={length}
-              onChange={(e) => setLength(Number(e.target.value))}
+              value={options.length}
+              onChange={(e) => update({ length: Number(e.target.value) })}
             />
             <div className="password-length-value">
               <span>8</span>
@@ -117,40 +199,40 @@ export default function PasswordGenerator({ onSelect }: PasswordGeneratorProps)
             <label className="password-option">
               <input
                 type="checkbox"
-                checked={lowercase}
-                onChange={(e) => setLowercase(e.target.checked)}
+                checked={options.lowercase}
+                onChange={(e) => update({ lowercase: e.target.checked })}
               />
               Lowercase (a-z)
             </label>
             <label className="password-option">
               <input
                 type="checkbox"
-                checked={uppercase}
-                onChange={(e) => setUppercase(e.target.checked)}
+                checked={options.uppercase}
+                onChange={(e) => update({ uppercase: e.target.checked })}
               />
               Uppercase (A-Z)
             </label>
             <label className="password-option">
               <input
                 type="checkbox"
-                checked={digits}
-                onChange={(e) => setDigits(e.target.checked)}
+                checked={options.digits}
+                onChange={(e) => update({ digits: e.target.checked })}
               />
               Numbers (0-9)
             </label>
             <label className="password-option">
               <input
                 type="checkbox"
-                checked={symbols}
-                onChange={(e) => setSymbols(e.target.checked)}
+                checked={options.symbols}
+                onChange={(e) => update({ symbols: e.target.checked })}
               />
               Symbols (!@#$...)
             </label>
             <label className="password-option">
               <input
                 type="checkbox"
-                checked={excludeAmbiguous}
-                onChange={(e) => setExcludeAmbiguous(e.target.checked)}
+                checked={options.exclude_ambiguous}
+                onChange={(e) => update({ exclude_ambiguous: e.target.checked })}
               />
               Exclude ambiguous (0OlI1)
             </label>
@@ -159,14 +241,14 @@ export default function PasswordGenerator({ onSelect }: PasswordGeneratorProps)
       ) : (
         <>
           <div className="password-length">
-            <label className="input-label">Words: {wordCount}</label>
+            <label className="input-label">Words: {options.word_count}</label>
             <input
               type="range"
               className="password-length-slider"
               min="3"
               max="10"
-              value={wordCount}
-              onChange={(e) => setWordCount(Number(e.target.value))}
+              value={options.word_count}
+              onChange={(e) => update({ word_count: Number(e.target.value) })}
             />
             <div className="password-length-value">
               <span>3</span>
@@ -178,8 +260,8 @@ export default function PasswordGenerator({ onSelect }: PasswordGeneratorProps)
             <label className="input-label">Separator</label>
             <select
               className="input"
-              value={separator}
-              onChange={(e) => setSeparator(e.target.value)}
+              value={options.separator}
+              onChange={(e) => update({ separator: e.target.value })}
             >
               <option value="-">Hyphen (-)</option>
               <option value="_">Underscore (_)</option>
@@ -189,6 +271,25 @@ export default function PasswordGenerator({ onSelect }: PasswordGeneratorProps)
           </div>
         </>
       )}
+
+      {error && <div className="error-message">{error}</div>}
+      <div style={{ display: 'flex', gap: '8px' }}>
+        <input
+          type="text"
+          className="input"
+          placeholder="Preset name"
+          value={presetName}
+          onChange={(e) => setPresetName(e.target.value)}
+        />
+        <button
+          type="button"
+          className="btn btn-secondary"
+          onClick={savePreset}
+          disabled={!presetName.trim()}
+        >
+          Save Preset
+        </button>
+      </div>
     </div>
   );
 }
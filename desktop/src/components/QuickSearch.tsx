@@ -0,0 +1,111 @@
+import { useEffect, useRef, useState } from 'react';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import { tauri, VaultItem } from '../hooks/useTauri';
+import { useActivityReporter } from '../hooks/useActivityReporter';
+import { useVaultLocked } from '../hooks/useVaultLocked';
+
+const MAX_RESULTS = 8;
+
+export default function QuickSearch() {
+  const [query, setQuery] = useState('');
+  const [results, setResults] = useState<VaultItem[]>([]);
+  const [selected, setSelected] = useState(0);
+  const [error, setError] = useState<string | null>(null);
+  // Bumped to search again for the same query, e.g. after the vault was unlocked
+  const [reload, setReload] = useState(0);
+  const inputRef = useRef<HTMLInputElement>(null);
+  useActivityReporter();
+
+  // Start fresh each time the palette is summoned
+  useEffect(() => {
+    const unlisten = getCurrentWindow().onFocusChanged(({ payload: focused }) => {
+      if (focused) {
+        setQuery('');
+        setError(null);
+        setReload((r) => r + 1);
+        inputRef.current?.focus();
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // The palette stays loaded while hidden, so drop its results as soon as the vault locks
+  useVaultLocked(() => {
+    setResults([]);
+    setQuery('');
+    setSelected(0);
+  });
+
+  useEffect(() => {
+    const load = async () => {
+      try {
+        const page = await tauri.searchItems({ query, sort: 'last_used', limit: MAX_RESULTS });
+        setResults(page.items);
+        setSelected(0);
+      } catch (err) {
+        setResults([]);
+        setError(String(err));
+      }
+    };
+    load();
+  }, [query, reload]);
+
+  const copy = async (item: VaultItem, field: 'password' | 'username') => {
+    try {
+      await tauri.copySecret(item.id, field);
+      await tauri.hidePalette();
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const handleKeyDown = (e: React.KeyboardEvent) => {
+    if (e.key === 'ArrowDown') {
+      e.preventDefault();
+      setSelected((i) => Math.min(i + 1, results.length - 1));
+    } else if (e.key === 'ArrowUp') {
+      e.preventDefault();
+      setSelected((i) => Math.max(i - 1, 0));
+    } else if (e.key === 'Enter' && results[selected]) {
+      e.preventDefault();
+      copy(results[selected], e.shiftKey ? 'username' : 'password');
+    } else if (e.key === 'Escape') {
+      tauri.hidePalette();
+    }
+  };
+
+  return (
+    <div className="quick-search">
+      <input
+        ref={inputRef}
+        type="text"
+        className="input"
+        value={query}
+        onChange={(e) => setQuery(e.target.value)}
+        onKeyDown={handleKeyDown}
+        placeholder="Search vault..."
+        autoFocus
+      />
+      {error && <div className="error-message">{error}</div>}
+      <div className="quick-search-results">
+        {results.map((item, index) => (
+          <div
+            key={item.id}
+            className={`quick-search-item ${index === selected ? 'active' : ''}`}
+            onMouseEnter={() => setSelected(index)}
+            onClick={(e) => copy(item, e.shiftKey ? 'username' : 'password')}
+          >
+            <div className="vault-item-name">{item.name}</div>
+            <div className="vault-item-username">{item.username}</div>
+          </div>
+        ))}
+      </div>
+      <div className="quick-search-hint">
+        Enter copies password · Shift+Enter copies username · Esc closes
+      </div>
+    </div>
+  );
+}
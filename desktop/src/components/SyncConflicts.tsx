@@ -0,0 +1,104 @@
+import { useState, useEffect } from 'react';
+import { tauri, type ConflictChoice, type SyncConflict, type VaultItem } from '../hooks/useTauri';
+
+interface SyncConflictsProps {
+  onResolved: () => void;
+  onClose: () => void;
+}
+
+function formatModified(item: VaultItem): string {
+  return new Date(item.modified_at * 1000).toLocaleString();
+}
+
+function ConflictSide({ title, item }: { title: string; item: VaultItem | null }) {
+  return (
+    <div style={{ flex: 1 }}>
+      <div className="input-label">{title}</div>
+      {item ? (
+        <>
+          <div>{item.name}</div>
+          <div style={{ color: 'var(--text-secondary)' }}>{item.username}</div>
+          {item.url && <div style={{ color: 'var(--text-secondary)' }}>{item.url}</div>}
+          <small style={{ color: 'var(--text-secondary)' }}>Modified {formatModified(item)}</small>
+        </>
+      ) : (
+        <div style={{ color: 'var(--text-secondary)' }}>Deleted</div>
+      )}
+    </div>
+  );
+}
+
+export default function SyncConflicts({ onResolved, onClose }: SyncConflictsProps) {
+  const [conflicts, setConflicts] = useState<SyncConflict[]>([]);
+  const [error, setError] = useState<string | null>(null);
+  const [busy, setBusy] = useState(false);
+
+  useEffect(() => {
+    tauri.listConflicts()
+      .then(setConflicts)
+      .catch((e) => setError(String(e)));
+  }, []);
+
+  const handleResolve = async (id: string, choice: ConflictChoice) => {
+    setBusy(true);
+    setError(null);
+    try {
+      await tauri.resolveConflict(id, choice);
+      setConflicts((prev) => prev.filter((conflict) => conflict.item_id !== id));
+      onResolved();
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div className="modal-overlay" onClick={onClose}>
+      <div className="modal" onClick={(e) => e.stopPropagation()}>
+        <div className="modal-header">
+          <h2 className="modal-title">Sync Conflicts</h2>
+          <button className="modal-close" onClick={onClose}>×</button>
+        </div>
+
+        {error && <div className="error-message">{error}</div>}
+
+        {conflicts.length === 0 && !error && (
+          <p style={{ color: 'var(--text-secondary)' }}>No conflicts left to resolve.</p>
+        )}
+
+        {conflicts.map((conflict) => (
+          <div key={conflict.item_id} className="input-group">
+            <div style={{ display: 'flex', gap: '16px' }}>
+              <ConflictSide title="This Device" item={conflict.local} />
+              <ConflictSide title="Other Devices" item={conflict.remote} />
+            </div>
+            <div style={{ display: 'flex', gap: '8px', marginTop: '8px' }}>
+              <button
+                className="btn btn-secondary"
+                onClick={() => handleResolve(conflict.item_id, 'local')}
+                disabled={busy}
+              >
+                Keep This Device's
+              </button>
+              <button
+                className="btn btn-secondary"
+                onClick={() => handleResolve(conflict.item_id, 'remote')}
+                disabled={busy}
+              >
+                Keep Other Devices'
+              </button>
+              <button
+                className="btn btn-secondary"
+                onClick={() => handleResolve(conflict.item_id, 'both')}
+                disabled={busy || !conflict.local || !conflict.remote}
+              >
+                Keep Both
+              </button>
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -1,4 +1,5 @@
-import { useState } from 'react';
+import { useState, useEffect } from 'react';
+import { tauri } from '../hooks/useTauri';
 
 function KeyIcon() {
   return (
@@ -25,7 +26,14 @@ function EyeIcon({ open }: { open: boolean }) {
 
 interface UnlockScreenProps {
   hasVault: boolean;
-  onUnlock: (password: string) => Promise<void>;
+  keyFileRequired: boolean;
+  damaged: boolean;
+  restorable: boolean;
+  retryAfter: number;
+  onUnlock: (password: string, keyFile?: string) => Promise<void>;
+  onBiometricUnlock: () => Promise<void>;
+  onRecover: (backupPath: string, passphrase: string, password: string, keyFile?: string) => Promise<void>;
+  onRestoreFromSync: (password: string, keyFile?: string) => Promise<void>;
   onCreate: (password: string) => Promise<void>;
   error: string | null;
   onClearError: () => void;
@@ -33,28 +41,77 @@ interface UnlockScreenProps {
 
 export default function UnlockScreen({
   hasVault,
+  keyFileRequired,
+  damaged,
+  restorable,
+  retryAfter,
   onUnlock,
+  onBiometricUnlock,
+  onRecover,
+  onRestoreFromSync,
   onCreate,
   error,
   onClearError,
 }: UnlockScreenProps) {
   const [password, setPassword] = useState('');
   const [confirmPassword, setConfirmPassword] = useState('');
+  const [keyFile, setKeyFile] = useState('');
+  const [backupPath, setBackupPath] = useState('');
+  const [backupPassphrase, setBackupPassphrase] = useState('');
   const [showPassword, setShowPassword] = useState(false);
   const [loading, setLoading] = useState(false);
+  const [canUseBiometrics, setCanUseBiometrics] = useState(false);
+  const [wait, setWait] = useState(retryAfter);
+  const restoring = !hasVault && restorable;
+
+  useEffect(() => {
+    setWait(retryAfter);
+    if (retryAfter <= 0) return;
+    const timer = setInterval(() => setWait((w) => Math.max(0, w - 1)), 1000);
+    return () => clearInterval(timer);
+  }, [retryAfter]);
+
+  useEffect(() => {
+    if (!keyFileRequired) return;
+    tauri.getKeyFile()
+      .then(path => setKeyFile(path ?? ''))
+      .catch(() => setKeyFile(''));
+  }, [keyFileRequired]);
+
+  useEffect(() => {
+    if (!hasVault) return;
+    tauri.getBiometricStatus()
+      .then(status => setCanUseBiometrics(status.available && status.enrolled))
+      .catch(() => setCanUseBiometrics(false));
+  }, [hasVault]);
+
+  const handleBiometricUnlock = async () => {
+    onClearError();
+    setLoading(true);
+    try {
+      await onBiometricUnlock();
+    } finally {
+      setLoading(false);
+    }
+  };
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     onClearError();
 
-    if (!hasVault && password !== confirmPassword) {
+    if (!hasVault && !restoring && password !== confirmPassword) {
       return;
     }
 
     setLoading(true);
     try {
-      if (hasVault) {
-        await onUnlock(password);
+      const keyFilePath = keyFileRequired ? keyFile.trim() || undefined : undefined;
+      if (restoring) {
+        await onRestoreFromSync(password, keyFilePath);
+      } else if (hasVault && damaged) {
+        await onRecover(backupPath.trim(), backupPassphrase, password, keyFilePath);
+      } else if (hasVault) {
+        await onUnlock(password, keyFilePath);
       } else {
         await onCreate(password);
       }
@@ -63,14 +120,22 @@ export default function UnlockScreen({
     }
   };
 
-  const isValid = password.length >= 8 && (hasVault || password === confirmPassword);
+  const isValid = password.length >= 8
+    && (hasVault || restoring || password === confirmPassword)
+    && (!damaged || (backupPath.trim() !== '' && backupPassphrase !== ''));
 
   return (
     <div className="unlock-screen">
       <div className="unlock-card">
         <h1 className="unlock-title"><KeyIcon /> Keydrop</h1>
         <p className="unlock-subtitle">
-          {hasVault ? 'Enter your master password to unlock' : 'Create a master password to get started'}
+          {restoring
+            ? 'This vault was erased after too many failed unlock attempts. Enter your master password to restore it from sync.'
+            : !hasVault
+              ? 'Create a master password to get started'
+              : damaged
+                ? 'Your vault data is damaged. Restore it from a backup to continue.'
+                : 'Enter your master password to unlock'}
         </p>
 
         {error && <div className="error-message">{error}</div>}
@@ -97,7 +162,45 @@ export default function UnlockScreen({
             </div>
           </div>
 
-          {!hasVault && (
+          {(hasVault || restoring) && keyFileRequired && (
+            <div className="input-group">
+              <label className="input-label">Key File</label>
+              <input
+                type="text"
+                className="input"
+                value={keyFile}
+                onChange={(e) => setKeyFile(e.target.value)}
+                placeholder="Path to your key file"
+              />
+            </div>
+          )}
+
+          {hasVault && damaged && (
+            <>
+              <div className="input-group">
+                <label className="input-label">Backup File</label>
+                <input
+                  type="text"
+                  className="input"
+                  value={backupPath}
+                  onChange={(e) => setBackupPath(e.target.value)}
+                  placeholder="Path to a Keydrop backup"
+                />
+              </div>
+              <div className="input-group">
+                <label className="input-label">Backup Passphrase</label>
+                <input
+                  type="password"
+                  className="input"
+                  value={backupPassphrase}
+                  onChange={(e) => setBackupPassphrase(e.target.value)}
+                  placeholder="Passphrase the backup was saved with"
+                />
+              </div>
+            </>
+          )}
+
+          {!hasVault && !restoring && (
             <div className="input-group">
               <label className="input-label">Confirm Password</label>
               <input
@@ -115,7 +218,7 @@ export default function UnlockScreen({
             </div>
           )}
 
-          {!hasVault && (
+          {!hasVault && !restoring && (
             <p style={{ fontSize: '14px', color: 'var(--text-secondary)', marginBottom: '16px' }}>
               Password must be at least 8 characters. This password cannot be recovered if lost.
             </p>
@@ -125,10 +228,28 @@ export default function UnlockScreen({
             type="submit"
             className="btn btn-primary"
             style={{ width: '100%' }}
-            disabled={!isValid || loading}
+            disabled={!isValid || loading || wait > 0}
           >
-            {loading ? 'Please wait...' : hasVault ? 'Unlock Vault' : 'Create Vault'}
+            {loading
+              ? 'Please wait...'
+              : wait > 0
+                ? `Try again in ${wait}s`
+                : restoring
+                  ? 'Restore from Sync'
+                  : !hasVault ? 'Create Vault' : damaged ? 'Restore from Backup' : 'Unlock Vault'}
           </button>
+
+          {canUseBiometrics && !damaged && (
+            <button
+              type="button"
+              className="btn btn-secondary"
+              style={{ width: '100%', marginTop: '8px' }}
+              onClick={handleBiometricUnlock}
+              disabled={loading}
+            >
+              Unlock with Touch ID / Windows Hello
+            </button>
+          )}
         </form>
       </div>
     </div>
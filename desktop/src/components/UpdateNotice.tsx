@@ -0,0 +1,61 @@
+import { useState, useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { tauri, type UpdateInfo, type UpdateProgress } from '../hooks/useTauri';
+
+/** Offers a newer release, with its notes, once per launch */
+export default function UpdateNotice() {
+  const [update, setUpdate] = useState<UpdateInfo | null>(null);
+  const [progress, setProgress] = useState<UpdateProgress | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [dismissed, setDismissed] = useState(false);
+
+  useEffect(() => {
+    // Builds without update signing report an error; there's nothing to offer then
+    tauri.checkForUpdates().then(setUpdate).catch(() => {});
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<UpdateProgress>('update-progress', (event) => setProgress(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  if (!update || dismissed) return null;
+
+  const install = async () => {
+    setError(null);
+    try {
+      // Restarts the app once installed
+      await tauri.installUpdate();
+    } catch (err) {
+      setProgress(null);
+      setError(String(err));
+    }
+  };
+
+  const percent = progress?.total ? Math.round((progress.downloaded / progress.total) * 100) : null;
+
+  return (
+    <div className="update-notice">
+      <div>
+        <strong>Keydrop {update.version} is available</strong> (you have {update.current_version})
+        {update.notes && (
+          <details>
+            <summary>Release notes</summary>
+            <pre className="update-notes">{update.notes}</pre>
+          </details>
+        )}
+        {error && <div className="error-message">{error}</div>}
+      </div>
+      {progress ? (
+        <span>Downloading{percent !== null ? ` ${percent}%` : '...'}</span>
+      ) : (
+        <div>
+          <button className="btn btn-primary" onClick={install}>Install and Restart</button>
+          <button className="btn btn-ghost" onClick={() => setDismissed(true)}>Later</button>
+        </div>
+      )}
+    </div>
+  );
+}
@@ -1,5 +1,4 @@
-import { VaultItem } from '../hooks/useTauri';
-import { writeText } from '@tauri-apps/plugin-clipboard-manager';
+import { VaultItem, tauri } from '../hooks/useTauri';
 import { useState } from 'react';
 
 const icons = {
@@ -8,6 +7,7 @@ const icons = {
   user: <path d="M12 12c2.21 0 4-1.79 4-4s-1.79-4-4-4-4 1.79-4 4 1.79 4 4 4zm0 2c-2.67 0-8 1.34-8 4v2h16v-2c0-2.66-5.33-4-8-4z"/>,
   password: <path d="M18 8h-1V6c0-2.76-2.24-5-5-5S7 3.24 7 6v2H6c-1.1 0-2 .9-2 2v10c0 1.1.9 2 2 2h12c1.1 0 2-.9 2-2V10c0-1.1-.9-2-2-2zm-6 9c-1.1 0-2-.9-2-2s.9-2 2-2 2 .9 2 2-.9 2-2 2zm3.1-9H8.9V6c0-1.71 1.39-3.1 3.1-3.1 1.71 0 3.1 1.39 3.1 3.1v2z"/>,
   trash: <path d="M6 19c0 1.1.9 2 2 2h8c1.1 0 2-.9 2-2V7H6v12zM19 4h-3.5l-1-1h-5l-1 1H5v2h14V4z"/>,
+  keyboard: <path d="M20 5H4c-1.1 0-1.99.9-1.99 2L2 17c0 1.1.9 2 2 2h16c1.1 0 2-.9 2-2V7c0-1.1-.9-2-2-2zm-9 3h2v2h-2V8zm0 3h2v2h-2v-2zM8 8h2v2H8V8zm0 3h2v2H8v-2zm-1 2H5v-2h2v2zm0-3H5V8h2v2zm9 7H8v-2h8v2zm0-4h-2v-2h2v2zm0-3h-2V8h2v2zm3 3h-2v-2h2v2zm0-3h-2V8h2v2z"/>,
   check: <path d="M9 16.17L4.83 12l-1.42 1.41L9 19 21 7l-1.41-1.41z"/>,
 };
 
@@ -38,12 +38,28 @@ export default function VaultList({ items, onEdit, onDelete }: VaultListProps) {
     );
   }
 
-  const copyToClipboard = async (text: string, id: string) => {
-    await writeText(text);
+  const copyToClipboard = async (itemId: string, field: string, id: string) => {
+    await tauri.copySecret(itemId, field);
     setCopiedId(id);
     setTimeout(() => setCopiedId(null), 2000);
   };
 
+  // The first call reports which window would receive the keystrokes; type only once the user approves it
+  const autoType = async (item: VaultItem) => {
+    try {
+      const probe = await tauri.autoType(item.id);
+      if (!window.confirm(`Type credentials for "${item.name}" into "${probe.target_title}"?`)) {
+        return;
+      }
+      const result = await tauri.autoType(item.id, undefined, probe.target_title);
+      if (!result.typed) {
+        window.alert(`Focus moved to "${result.target_title}", so nothing was typed.`);
+      }
+    } catch (err) {
+      window.alert(String(err));
+    }
+  };
+
   const getInitial = (name: string) => {
     return name.charAt(0).toUpperCase();
   };
@@ -77,18 +93,25 @@ export default function VaultList({ items, onEdit, onDelete }: VaultListProps) {
           <div className="vault-item-actions" onClick={(e) => e.stopPropagation()}>
             <button
               className="btn btn-icon btn-ghost"
-              onClick={() => copyToClipboard(item.username, `user-${item.id}`)}
+              onClick={() => copyToClipboard(item.id, 'username', `user-${item.id}`)}
               title="Copy username"
             >
               {copiedId === `user-${item.id}` ? <Icon name="check" /> : <Icon name="user" />}
             </button>
             <button
               className="btn btn-icon btn-ghost"
-              onClick={() => copyToClipboard(item.password, `pass-${item.id}`)}
+              onClick={() => copyToClipboard(item.id, 'password', `pass-${item.id}`)}
               title="Copy password"
             >
               {copiedId === `pass-${item.id}` ? <Icon name="check" /> : <Icon name="password" />}
             </button>
+            <button
+              className="btn btn-icon btn-ghost"
+              onClick={() => autoType(item)}
+              title="Auto-type"
+            >
+              <Icon name="keyboard" />
+            </button>
             <button
               className="btn btn-icon btn-ghost"
               onClick={() => onDelete(item.id)}
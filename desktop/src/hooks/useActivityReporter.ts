@@ -0,0 +1,28 @@
+import { useEffect } from 'react';
+import { tauri } from './useTauri';
+
+/** At most one report per this many milliseconds */
+const REPORT_INTERVAL_MS = 10_000;
+
+const EVENTS = ['pointerdown', 'keydown', 'wheel', 'focus'] as const;
+
+/**
+ * Tell the backend the user is interacting with this window, so the vault
+ * doesn't idle-lock while it's in use. Commands alone don't count as activity.
+ */
+export function useActivityReporter() {
+  useEffect(() => {
+    let lastReport = 0;
+    const report = () => {
+      const now = Date.now();
+      if (now - lastReport < REPORT_INTERVAL_MS) return;
+      lastReport = now;
+      tauri.reportActivity().catch(() => {});
+    };
+
+    EVENTS.forEach((name) => window.addEventListener(name, report, { passive: true }));
+    return () => {
+      EVENTS.forEach((name) => window.removeEventListener(name, report));
+    };
+  }, []);
+}
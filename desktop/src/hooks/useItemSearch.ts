@@ -0,0 +1,62 @@
+import { useState, useEffect, useCallback } from 'react';
+import { tauri, ItemFilter, SearchSort, VaultItem } from './useTauri';
+
+const PAGE_SIZE = 100;
+
+/**
+ * Pages of items matching a query and filter, fetched from the backend
+ *
+ * Starts over whenever the query, filter, sort or `revision` changes;
+ * `loadMore` appends the next page.
+ */
+export function useItemSearch(
+  query: string,
+  filter: ItemFilter,
+  sort: SearchSort,
+  revision: number,
+  enabled: boolean,
+) {
+  const [items, setItems] = useState<VaultItem[]>([]);
+  const [total, setTotal] = useState(0);
+  const [error, setError] = useState<string | null>(null);
+  const filterKey = JSON.stringify(filter);
+
+  useEffect(() => {
+    if (!enabled) {
+      setItems([]);
+      setTotal(0);
+      return;
+    }
+
+    let cancelled = false;
+    tauri.searchItems({ query, filter: JSON.parse(filterKey), sort, limit: PAGE_SIZE })
+      .then((page) => {
+        if (cancelled) return;
+        setItems(page.items);
+        setTotal(page.total);
+        setError(null);
+      })
+      .catch((err) => !cancelled && setError(String(err)));
+    return () => {
+      cancelled = true;
+    };
+  }, [query, filterKey, sort, revision, enabled]);
+
+  const loadMore = useCallback(async () => {
+    try {
+      const page = await tauri.searchItems({
+        query,
+        filter: JSON.parse(filterKey),
+        sort,
+        offset: items.length,
+        limit: PAGE_SIZE,
+      });
+      setItems((prev) => [...prev, ...page.items]);
+      setTotal(page.total);
+    } catch (err) {
+      setError(String(err));
+    }
+  }, [query, filterKey, sort, items.length]);
+
+  return { items, total, error, loadMore, hasMore: items.length < total };
+}
@@ -1,5 +1,6 @@
 import { useState, useEffect, useCallback } from 'react';
-import { tauri, SyncStatus, RemoteCommand } from './useTauri';
+import { listen } from '@tauri-apps/api/event';
+import { tauri, SyncStatus, RemoteCommandEvent } from './useTauri';
 
 export interface UseSyncResult {
   status: SyncStatus;
@@ -9,16 +10,18 @@ export interface UseSyncResult {
   error: string | null;
   pendingChanges: number;
   triggerSync: () => Promise<void>;
+  refresh: () => Promise<void>;
   enable: (serverUrl: string, accessToken: string, deviceId: string) => Promise<void>;
   disable: () => Promise<void>;
 }
 
-export function useSync(onRemoteCommand?: (command: RemoteCommand) => void): UseSyncResult {
+export function useSync(onRemoteCommand?: (event: RemoteCommandEvent) => void): UseSyncResult {
   const [status, setStatus] = useState<SyncStatus>({
     state: 'Idle',
     last_sync_time: null,
     error: null,
     pending_changes: 0,
+    conflicts: 0,
   });
   const [isEnabled, setIsEnabled] = useState(false);
 
@@ -31,48 +34,40 @@ export function useSync(onRemoteCommand?: (command: RemoteCommand) => void): Use
     }
   }, []);
 
-  const checkCommands = useCallback(async () => {
-    if (!isEnabled || !onRemoteCommand) return;
 
-    try {
-      const commands = await tauri.checkRemoteCommands();
-      for (const command of commands) {
-        onRemoteCommand(command);
-      }
-    } catch (err) {
-      console.error('Failed to check remote commands:', err);
-    }
-  }, [isEnabled, onRemoteCommand]);
-
-  // Initial status fetch
+  // Initial status fetch; sync resumes at startup if credentials are in the keychain
   useEffect(() => {
     refreshStatus();
+    tauri.isSyncEnabled().then(setIsEnabled).catch(() => setIsEnabled(false));
   }, [refreshStatus]);
 
-  // Poll for status updates when enabled
+  // The background scheduler pushes status changes
   useEffect(() => {
-    if (!isEnabled) return;
+    const unlisten = listen<SyncStatus>('sync-status', event => setStatus(event.payload));
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
 
-    const interval = setInterval(() => {
-      refreshStatus();
-      checkCommands();
-    }, 30000); // Check every 30 seconds
+  // Remote lock/wipe commands are executed by the backend, which then reports them
+  useEffect(() => {
+    if (!onRemoteCommand) return;
 
-    return () => clearInterval(interval);
-  }, [isEnabled, refreshStatus, checkCommands]);
+    const unlisten = listen<RemoteCommandEvent>('remote-command', event => onRemoteCommand(event.payload));
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [onRemoteCommand]);
 
   const triggerSync = useCallback(async () => {
     try {
       setStatus(prev => ({ ...prev, state: 'Syncing' }));
       await tauri.triggerSync();
-      await refreshStatus();
     } catch (err) {
-      setStatus(prev => ({
-        ...prev,
-        state: 'Error',
-        error: String(err),
-      }));
+      console.error('Sync failed:', err);
     }
+    // The backend records whether the failure was an error or just offline
+    await refreshStatus();
   }, [refreshStatus]);
 
   const enable = useCallback(async (serverUrl: string, accessToken: string, deviceId: string) => {
@@ -99,6 +94,7 @@ export function useSync(onRemoteCommand?: (command: RemoteCommand) => void): Use
         last_sync_time: null,
         error: null,
         pending_changes: 0,
+        conflicts: 0,
       });
     } catch (err) {
       console.error('Failed to disable sync:', err);
@@ -114,6 +110,7 @@ export function useSync(onRemoteCommand?: (command: RemoteCommand) => void): Use
     error: status.error,
     pendingChanges: status.pending_changes,
     triggerSync,
+    refresh: refreshStatus,
     enable,
     disable,
   };
@@ -3,6 +3,12 @@ import { invoke } from '@tauri-apps/api/core';
 export interface VaultStatus {
   exists: boolean;
   unlocked: boolean;
+  key_file_required: boolean;
+  damaged: boolean;
+  /** The vault was wiped after failed unlocks and can be restored from sync */
+  restorable: boolean;
+  /** Seconds before another unlock attempt is allowed */
+  retry_after: number;
 }
 
 export interface VaultItem {
@@ -16,6 +22,146 @@ export interface VaultItem {
   favorite: boolean;
   created_at: number;
   modified_at: number;
+  auto_type?: string | null;
+}
+
+export type ItemKind = 'login' | 'secure_note' | 'card' | 'identity';
+
+/** Criteria for `searchItems`; unset fields match everything */
+export interface ItemFilter {
+  /** Case-insensitive match against name, username, or URL */
+  query?: string;
+  category?: string;
+  favorite?: boolean;
+  tag?: string;
+  kind?: ItemKind;
+}
+
+/** `last_used` puts items copied or filled on this device first */
+export type SearchSort = 'name' | 'recent' | 'last_used';
+
+/** Options for `searchItems`; `limit` defaults to 50 */
+export interface SearchOptions {
+  query?: string;
+  filter?: ItemFilter;
+  sort?: SearchSort;
+  offset?: number;
+  limit?: number;
+}
+
+export interface ItemPage {
+  items: VaultItem[];
+  /** Items matching across all pages */
+  total: number;
+}
+
+export interface AutoTypeResult {
+  typed: boolean;
+  target_title: string;
+}
+
+export type ImportFormat = 'csv' | 'lastpass' | 'keepass' | 'bitwarden';
+
+export interface CsvMapping {
+  name?: string;
+  url?: string;
+  username?: string;
+  password?: string;
+  notes?: string;
+  category?: string;
+}
+
+export interface ImportOptions {
+  mapping?: CsvMapping;
+  skip_duplicates?: boolean;
+  exclude?: number[];
+}
+
+export type ImportDuplicate =
+  | { of: 'existing'; item_id: string }
+  | { of: 'entry'; index: number };
+
+export interface ImportCandidate {
+  index: number;
+  name: string;
+  username: string;
+  url: string | null;
+  category: string | null;
+  duplicate: ImportDuplicate | null;
+}
+
+export interface SkippedRow {
+  row: number;
+  reason: string;
+}
+
+export interface ImportPreview {
+  total: number;
+  duplicates: number;
+  items: ImportCandidate[];
+  errors: SkippedRow[];
+}
+
+export interface ImportSummary {
+  imported: number;
+  skipped_duplicates: number;
+  excluded: number;
+  errors: SkippedRow[];
+}
+
+export type MergePolicy = 'newest_wins' | 'keep_local' | 'keep_incoming' | 'keep_both';
+
+export interface MergeReport {
+  added: string[];
+  updated: string[];
+  conflicts: string[];
+  duplicated: string[];
+  unchanged: number;
+}
+
+export interface AutoBackupConfig {
+  enabled: boolean;
+  directory: string | null;
+  every_changes: number;
+  daily: boolean;
+  keep_count: number;
+  keep_days: number;
+}
+
+export interface AutoBackupStatus {
+  last_backup_at: number | null;
+  last_backup_path: string | null;
+  changes_since_backup: number;
+  last_error: string | null;
+}
+
+export interface AutoBackupSettings {
+  config: AutoBackupConfig;
+  status: AutoBackupStatus;
+  directory: string;
+  has_passphrase: boolean;
+}
+
+export interface StorageInfo {
+  vault_size: number;
+  attachment_size: number;
+  backup_count: number;
+  database_size: number;
+  reclaimable_size: number;
+}
+
+export interface CompactReport {
+  removed: number;
+  freed: number;
+}
+
+export interface Attachment {
+  id: string;
+  item_id: string;
+  name: string;
+  /** Size in bytes */
+  size: number;
+  created_at: number;
 }
 
 export interface PasswordOptions {
@@ -28,6 +174,60 @@ export interface PasswordOptions {
   exclude_chars?: string;
 }
 
+export interface PassphraseOptions {
+  word_count: number;
+  separator: string;
+  capitalize: boolean;
+  include_number: boolean;
+}
+
+export type GeneratorOptions =
+  | ({ type: 'password' } & Required<PasswordOptions>)
+  | ({ type: 'passphrase' } & PassphraseOptions);
+
+export interface GeneratorPreset {
+  name: string;
+  options: GeneratorOptions;
+}
+
+export interface GeneratedPassword {
+  id: number;
+  password: string;
+  site: string | null;
+  created_at: number;
+}
+
+export type Theme = 'system' | 'light' | 'dark';
+
+export interface Settings {
+  auto_lock_timeout: number;
+  clipboard_timeout: number;
+  sync_interval: number;
+  /** Failed unlocks after which the local vault is erased; null = never */
+  wipe_after_failures: number | null;
+  auto_backup: AutoBackupConfig;
+  theme: Theme;
+  generator: {
+    password: Required<PasswordOptions>;
+    passphrase: PassphraseOptions;
+    last_used: GeneratorOptions | null;
+    presets: GeneratorPreset[];
+  };
+  /** Hide windows from screenshots and screen shares while unlocked */
+  capture_protection: boolean;
+}
+
+export interface CaptureProtection {
+  enabled: boolean;
+  /** False on Linux, where windows can't be kept out of captures */
+  supported: boolean;
+}
+
+/** Partial settings for updateSettings; nested objects are merged */
+export type SettingsChanges = {
+  [K in keyof Settings]?: Settings[K] extends object ? Partial<Settings[K]> : Settings[K];
+};
+
 export type SyncStatusState = 'Idle' | 'Syncing' | 'Error' | 'Offline';
 
 export interface SyncStatus {
@@ -35,6 +235,84 @@ export interface SyncStatus {
   last_sync_time: number | null;
   error: string | null;
   pending_changes: number;
+  conflicts: number;
+}
+
+export interface SyncConflict {
+  item_id: string;
+  /** This device's version; null if it was deleted here */
+  local: VaultItem | null;
+  /** The server's version; null if it was deleted elsewhere */
+  remote: VaultItem | null;
+  detected_at: number;
+}
+
+export interface TotpCode {
+  code: string;
+  seconds_remaining: number;
+  period: number;
+}
+
+export type ConflictChoice = 'local' | 'remote' | 'both';
+
+export interface AuditOptions {
+  min_entropy_bits?: number;
+  min_length?: number;
+  max_age_days?: number;
+}
+
+export interface SecurityReport {
+  total: number;
+  weak: string[];
+  /** Groups of item IDs sharing a password */
+  reused: string[][];
+  old: string[];
+  breach_candidates: string[];
+  score: number;
+  /** Items found in known breaches by the last breach check */
+  breached: string[];
+}
+
+export interface BreachedItem {
+  item_id: string;
+  count: number;
+}
+
+export interface BreachReport {
+  /** Items looked up in this check; the rest reused recent results */
+  checked: number;
+  breached: BreachedItem[];
+}
+
+export type LockReason = 'manual' | 'remote' | 'idle' | 'sleep' | 'screen_lock';
+
+export interface LockCountdown {
+  /** Idle timeout in seconds; 0 when idle locking is off */
+  timeout: number;
+  /** Null when locked or idle locking is off */
+  seconds_remaining: number | null;
+  warning: boolean;
+}
+
+export interface VaultLockedEvent {
+  reason: LockReason;
+}
+
+export interface BrowserIntegration {
+  enabled: boolean;
+  extension_id: string | null;
+}
+
+export interface BiometricStatus {
+  available: boolean;
+  enrolled: boolean;
+}
+
+export interface SyncSummary {
+  pulled: number;
+  pushed: number;
+  conflicts: number;
+  version: number;
 }
 
 export interface RemoteCommand {
@@ -43,9 +321,125 @@ export interface RemoteCommand {
   created_at: number;
 }
 
+export interface RemoteCommandEvent {
+  command_id: string;
+  command_type: string;
+  message: string;
+}
+
+export interface EmergencyContact {
+  id: string;
+  contact_email: string;
+  contact_name: string | null;
+  status: 'pending' | 'accepted' | 'revoked';
+  waiting_period_hours: number;
+  can_view_vault: boolean;
+  accepted_at: number | null;
+  created_at: number;
+  /** Whether the vault key has been sealed for this contact */
+  vault_key_shared: boolean;
+}
+
+export interface PendingAccessRequest {
+  request_id: string;
+  contact_id: string;
+  contact_email: string;
+  contact_name: string | null;
+  reason: string | null;
+  waiting_period_ends_at: number;
+  created_at: number;
+}
+
+export interface AccessRequestStatus {
+  request_id: string;
+  status: string;
+  waiting_period_ends_at: number;
+  created_at: number;
+}
+
+export interface GrantedAccess {
+  contact_id: string;
+  owner_email: string;
+  request_id: string;
+  approved_at: number;
+  /** Whether the owner's vault key came with the grant and opens with this user's key */
+  key_available: boolean;
+  /** Whether the owner lets this contact export their vault */
+  allow_export: boolean;
+}
+
+/** A vault open in the read-only emergency viewer */
+export interface EmergencyVaultInfo {
+  owner_email: string;
+  request_id: string;
+  item_count: number;
+  allow_export: boolean;
+}
+
+export interface AccessLogEntry {
+  id: string;
+  action: string;
+  details: unknown;
+  created_at: number;
+}
+
+/** A validated keydrop:// link, handed over by the backend */
+export type DeepLink =
+  | { type: 'emergency_invitation'; contact_id: string; token: string }
+  | { type: 'share'; share_id: string };
+
+export type ActivityKind =
+  | 'unlock'
+  | 'failed_unlock'
+  | 'self_wipe'
+  | 'export'
+  | 'item_deleted'
+  | 'sync'
+  | 'remote_command'
+  | 'emergency_view';
+
+/** created_at is in seconds */
+export interface ActivityEvent {
+  id: number;
+  kind: ActivityKind;
+  detail: string | null;
+  created_at: number;
+}
+
+/** Times are in seconds; an empty `kinds` matches every kind */
+export interface ActivityFilter {
+  kinds?: ActivityKind[];
+  since?: number;
+  until?: number;
+  limit?: number;
+}
+
+/** Redacted log line for support diagnostics; timestamp is in seconds */
+export interface LogEntry {
+  timestamp: number;
+  level: string;
+  target: string;
+  message: string;
+}
+
+/** A newer signed release; published_at is in seconds */
+export interface UpdateInfo {
+  version: string;
+  current_version: string;
+  notes: string | null;
+  published_at: number | null;
+}
+
+/** Payload of the 'update-progress' event, in bytes */
+export interface UpdateProgress {
+  downloaded: number;
+  total: number | null;
+}
+
 export interface EnableSyncRequest {
   server_url: string;
   access_token: string;
+  refresh_token?: string;
   device_id: string;
 }
 
@@ -55,38 +449,188 @@ export const tauri = {
 
   // Vault operations
   createVault: (password: string) => invoke<void>('create_vault', { password }),
-  unlockVault: (password: string) => invoke<void>('unlock_vault', { password }),
+  unlockVault: (password: string, keyFile?: string) =>
+    invoke<void>('unlock_vault', { password, keyFile }),
+  recoverVault: (path: string, passphrase: string, password: string, keyFile?: string) =>
+    invoke<void>('recover_vault', { path, passphrase, password, keyFile }),
+  restoreFromSync: (password: string, keyFile?: string) =>
+    invoke<SyncSummary>('restore_from_sync', { password, keyFile }),
   lockVault: () => invoke<void>('lock_vault'),
 
+  // Biometric unlock
+  getBiometricStatus: () => invoke<BiometricStatus>('get_biometric_status'),
+  enableBiometricUnlock: (password: string) => invoke<void>('enable_biometric_unlock', { password }),
+  disableBiometricUnlock: () => invoke<void>('disable_biometric_unlock'),
+  unlockWithBiometrics: () => invoke<void>('unlock_with_biometrics'),
+
+  // Key file
+  getKeyFile: () => invoke<string | null>('get_key_file'),
+  generateKeyFile: (path: string) => invoke<void>('generate_key_file', { path }),
+  setKeyFile: (password: string, path: string | null) =>
+    invoke<void>('set_key_file', { password, path }),
+
+  // Duress password
+  getDuressEnabled: () => invoke<boolean>('get_duress_enabled'),
+  setDuressPassword: (password: string, duressPassword: string | null) =>
+    invoke<void>('set_duress_password', { password, duressPassword }),
+
   // Item operations
   getAllItems: () => invoke<VaultItem[]>('get_all_items'),
   getItem: (id: string) => invoke<VaultItem | null>('get_item', { id }),
   addItem: (item: VaultItem) => invoke<string>('add_item', { item }),
   updateItem: (id: string, item: VaultItem) => invoke<void>('update_item', { id, item }),
   deleteItem: (id: string) => invoke<void>('delete_item', { id }),
-  searchItems: (query: string) => invoke<VaultItem[]>('search_items', { query }),
+  searchItems: (options: SearchOptions = {}) => invoke<ItemPage>('search_items', { ...options }),
   getFavorites: () => invoke<VaultItem[]>('get_favorites'),
+  getRecentItems: (limit: number) => invoke<VaultItem[]>('get_recent_items', { limit }),
+  autoType: (itemId: string, sequence?: string, confirmedTitle?: string) =>
+    invoke<AutoTypeResult>('auto_type', { itemId, sequence, confirmedTitle }),
+  copySecret: (itemId: string, field: string) =>
+    invoke<number>('copy_secret', { itemId, field }),
+  getTotp: (itemId: string) => invoke<TotpCode>('get_totp', { itemId }),
+  copyTotp: (itemId: string) => invoke<number>('copy_totp', { itemId }),
+
+  // Import
+  previewImport: (path: string, format: ImportFormat, options?: ImportOptions) =>
+    invoke<ImportPreview>('preview_import', { path, format, options }),
+  runImport: (path: string, format: ImportFormat, options: ImportOptions) =>
+    invoke<ImportSummary>('run_import', { path, format, options }),
+
+  // Backup
+  exportBackup: (path: string, passphrase: string) =>
+    invoke<void>('export_backup', { path, passphrase }),
+  restoreBackup: (path: string, passphrase: string, mergePolicy: MergePolicy) =>
+    invoke<MergeReport>('restore_backup', { path, passphrase, mergePolicy }),
+  getAutoBackup: () => invoke<AutoBackupSettings>('get_auto_backup'),
+  setAutoBackup: (config: AutoBackupConfig, passphrase?: string) =>
+    invoke<void>('set_auto_backup', { config, passphrase }),
+  runBackupNow: () => invoke<AutoBackupStatus>('run_backup_now'),
+
+  // Storage maintenance
+  getStorageInfo: () => invoke<StorageInfo>('get_storage_info'),
+  compactStorage: () => invoke<CompactReport>('compact_storage'),
+
+  // Attachments
+  addAttachment: (itemId: string, path: string) =>
+    invoke<Attachment>('add_attachment', { itemId, path }),
+  /** Attachments of an item, or of every item when `itemId` is unset */
+  listAttachments: (itemId?: string) =>
+    invoke<Attachment[]>('list_attachments', { itemId }),
+  /** Opens a temporary decrypted copy in the default app; it's removed on lock */
+  openAttachment: (id: string) => invoke<void>('open_attachment', { id }),
+  deleteAttachment: (id: string) => invoke<void>('delete_attachment', { id }),
 
   // Password generation
   generatePassword: (options: PasswordOptions) =>
     invoke<string>('generate_password_cmd', { options }),
   generatePassphrase: (wordCount: number, separator: string) =>
     invoke<string>('generate_passphrase_cmd', { wordCount, separator }),
+  /** Options the generator opens with: the last used, else the defaults */
+  getGeneratorOptions: () => invoke<GeneratorOptions>('get_generator_options'),
+  generateWithOptions: (options: GeneratorOptions) =>
+    invoke<string>('generate_with_options', { options }),
+  listGeneratorPresets: () => invoke<GeneratorPreset[]>('list_generator_presets'),
+  saveGeneratorPreset: (preset: GeneratorPreset) =>
+    invoke<GeneratorPreset[]>('save_generator_preset', { preset }),
+  deleteGeneratorPreset: (name: string) =>
+    invoke<GeneratorPreset[]>('delete_generator_preset', { name }),
+  recordGeneratedPassword: (password: string, site: string | null) =>
+    invoke<void>('record_generated_password', { password, site }),
+  listGeneratedHistory: () => invoke<GeneratedPassword[]>('list_generated_history'),
+  /** Remove one history entry, or the whole history when id is omitted */
+  purgeGeneratedHistory: (id?: number) =>
+    invoke<void>('purge_generated_history', { id: id ?? null }),
+
+  // Security dashboard
+  getAuditReport: (options?: AuditOptions) =>
+    invoke<SecurityReport>('get_audit_report', { options: options ?? null }),
+  checkBreaches: () => invoke<BreachReport>('check_breaches'),
 
   // Settings
+  getSettings: () => invoke<Settings>('get_settings'),
+  updateSettings: (changes: SettingsChanges) =>
+    invoke<Settings>('update_settings', { changes }),
   getAutoLockTimeout: () => invoke<number>('get_auto_lock_timeout'),
   setAutoLockTimeout: (timeout: number) =>
     invoke<void>('set_auto_lock_timeout', { timeout }),
   checkAutoLock: () => invoke<boolean>('check_auto_lock'),
+  /** Keeps the vault from idle-locking; also the "stay unlocked" action */
+  reportActivity: () => invoke<void>('report_activity'),
+  getLockCountdown: () => invoke<LockCountdown>('get_lock_countdown'),
+  /** Cached favicon as a data: URL, or null if none was downloaded */
+  getIcon: (domain: string) => invoke<string | null>('get_icon', { domain }),
+  getIconFetching: () => invoke<boolean>('get_icon_fetching'),
+  setIconFetching: (enabled: boolean) =>
+    invoke<void>('set_icon_fetching', { enabled }),
+  getPaletteShortcut: () => invoke<string>('get_palette_shortcut'),
+  setPaletteShortcut: (shortcut: string) =>
+    invoke<void>('set_palette_shortcut', { shortcut }),
+  hidePalette: () => invoke<void>('hide_palette'),
+  getBrowserIntegration: () => invoke<BrowserIntegration>('get_browser_integration'),
+  setBrowserIntegration: (enabled: boolean, extensionId?: string) =>
+    invoke<void>('set_browser_integration', { enabled, extensionId }),
+  getClipboardTimeout: () => invoke<number>('get_clipboard_timeout'),
+  setClipboardTimeout: (timeout: number) =>
+    invoke<void>('set_clipboard_timeout', { timeout }),
+  getCaptureProtection: () => invoke<CaptureProtection>('get_capture_protection'),
+  setCaptureProtection: (enabled: boolean) =>
+    invoke<void>('set_capture_protection', { enabled }),
 
   // Sync
   getSyncStatus: () => invoke<SyncStatus>('get_sync_status'),
+  isSyncEnabled: () => invoke<boolean>('is_sync_enabled'),
+  listConflicts: () => invoke<SyncConflict[]>('list_conflicts'),
+  resolveConflict: (id: string, choice: ConflictChoice) =>
+    invoke<void>('resolve_conflict', { id, choice }),
   enableSync: (request: EnableSyncRequest) =>
     invoke<void>('enable_sync', { request }),
   disableSync: () => invoke<void>('disable_sync'),
-  triggerSync: () => invoke<void>('trigger_sync'),
+  triggerSync: () => invoke<SyncSummary>('trigger_sync'),
+  getSyncInterval: () => invoke<number>('get_sync_interval'),
+  setSyncInterval: (interval: number) => invoke<void>('set_sync_interval', { interval }),
   checkRemoteCommands: () => invoke<RemoteCommand[]>('check_remote_commands'),
 
+  // Deep links
+  takeDeepLink: () => invoke<DeepLink | null>('take_deep_link'),
+
+  // Activity log
+  getActivityLog: (filter?: ActivityFilter) => invoke<ActivityEvent[]>('get_activity_log', { filter }),
+
+  // Diagnostics
+  getRecentLogs: (limit?: number) => invoke<LogEntry[]>('get_recent_logs', { limit }),
+
+  // Updates
+  checkForUpdates: () => invoke<UpdateInfo | null>('check_for_updates'),
+  installUpdate: () => invoke<void>('install_update'),
+
+  // Emergency access
+  addEmergencyContact: (email: string, name: string | null, waitingPeriodHours: number | null) =>
+    invoke<EmergencyContact>('add_emergency_contact', { email, name, waitingPeriodHours }),
+  listEmergencyContacts: () => invoke<EmergencyContact[]>('list_emergency_contacts'),
+  removeEmergencyContact: (contactId: string) =>
+    invoke<void>('remove_emergency_contact', { contactId }),
+  acceptEmergencyInvitation: (contactId: string, token: string) =>
+    invoke<void>('accept_emergency_invitation', { contactId, token }),
+  requestEmergencyAccess: (contactId: string, reason: string | null) =>
+    invoke<AccessRequestStatus>('request_emergency_access', { contactId, reason }),
+  listEmergencyRequests: () => invoke<PendingAccessRequest[]>('list_emergency_requests'),
+  denyEmergencyRequest: (requestId: string) =>
+    invoke<void>('deny_emergency_request', { requestId }),
+  getEmergencyVaultAccess: () => invoke<GrantedAccess[]>('get_emergency_vault_access'),
+  /** Opens the owner's vault read-only with the key from the grant; nothing in it is saved or synced */
+  openEmergencyVault: (requestId: string) =>
+    invoke<EmergencyVaultInfo>('open_emergency_vault', { requestId }),
+  getEmergencyVaultInfo: () => invoke<EmergencyVaultInfo | null>('get_emergency_vault_info'),
+  listEmergencyVaultItems: (query?: string) =>
+    invoke<VaultItem[]>('list_emergency_vault_items', { query }),
+  copyEmergencySecret: (itemId: string, field: string) =>
+    invoke<number>('copy_emergency_secret', { itemId, field }),
+  /** Fails unless the owner allows exports */
+  exportEmergencyVault: (path: string, passphrase: string) =>
+    invoke<void>('export_emergency_vault', { path, passphrase }),
+  closeEmergencyVault: () => invoke<void>('close_emergency_vault'),
+  getEmergencyLogs: () => invoke<AccessLogEntry[]>('get_emergency_logs'),
+
   // Wipe
   wipeVault: () => invoke<void>('wipe_vault'),
 };
@@ -1,9 +1,12 @@
 import { useState, useEffect, useCallback } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { tauri, VaultItem, VaultStatus } from './useTauri';
+import { useVaultLocked } from './useVaultLocked';
 
 export function useVault() {
   const [status, setStatus] = useState<VaultStatus | null>(null);
-  const [items, setItems] = useState<VaultItem[]>([]);
+  // Bumped whenever items change, so lists know to fetch again
+  const [revision, setRevision] = useState(0);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
 
@@ -18,13 +21,8 @@ export function useVault() {
     }
   }, []);
 
-  const refreshItems = useCallback(async () => {
-    try {
-      const newItems = await tauri.getAllItems();
-      setItems(newItems);
-    } catch (err) {
-      setError(String(err));
-    }
+  const refreshItems = useCallback(() => {
+    setRevision((r) => r + 1);
   }, []);
 
   useEffect(() => {
@@ -39,20 +37,30 @@ export function useVault() {
     init();
   }, [refreshStatus, refreshItems]);
 
-  // Auto-lock check interval
+  // The backend locks on idle, sleep, and screen lock
+  useVaultLocked(() => {
+    setStatus((prev) => (prev ? { ...prev, unlocked: false } : null));
+  });
+
   useEffect(() => {
-    if (!status?.unlocked) return;
+    const unlisten = listen('vault-wiped', () => {
+      setStatus((prev) => (prev ? { ...prev, exists: false, unlocked: false, restorable: true } : null));
+    });
 
-    const interval = setInterval(async () => {
-      const locked = await tauri.checkAutoLock();
-      if (locked) {
-        setStatus((prev) => (prev ? { ...prev, unlocked: false } : null));
-        setItems([]);
-      }
-    }, 10000);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen('vault-recovered', () => {
+      setError('Your last save was damaged, so the vault was opened from the save before it.');
+    });
 
-    return () => clearInterval(interval);
-  }, [status?.unlocked]);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
 
   const createVault = async (password: string) => {
     setError(null);
@@ -66,10 +74,49 @@ export function useVault() {
     }
   };
 
-  const unlock = async (password: string) => {
+  const unlock = async (password: string, keyFile?: string) => {
+    setError(null);
+    try {
+      await tauri.unlockVault(password, keyFile);
+      await refreshStatus();
+      await refreshItems();
+    } catch (err) {
+      setError(String(err));
+      // Failures delay the next attempt and may wipe the vault
+      await refreshStatus();
+      throw err;
+    }
+  };
+
+  const restoreFromSync = async (password: string, keyFile?: string) => {
     setError(null);
     try {
-      await tauri.unlockVault(password);
+      await tauri.restoreFromSync(password, keyFile);
+      await refreshStatus();
+      await refreshItems();
+    } catch (err) {
+      setError(String(err));
+      await refreshStatus();
+      throw err;
+    }
+  };
+
+  const recover = async (backupPath: string, passphrase: string, password: string, keyFile?: string) => {
+    setError(null);
+    try {
+      await tauri.recoverVault(backupPath, passphrase, password, keyFile);
+      await refreshStatus();
+      await refreshItems();
+    } catch (err) {
+      setError(String(err));
+      throw err;
+    }
+  };
+
+  const unlockWithBiometrics = async () => {
+    setError(null);
+    try {
+      await tauri.unlockWithBiometrics();
       await refreshStatus();
       await refreshItems();
     } catch (err) {
@@ -82,7 +129,6 @@ export function useVault() {
     try {
       await tauri.lockVault();
       setStatus((prev) => (prev ? { ...prev, unlocked: false } : null));
-      setItems([]);
     } catch (err) {
       setError(String(err));
     }
@@ -128,30 +174,20 @@ export function useVault() {
     }
   };
 
-  const search = async (query: string): Promise<VaultItem[]> => {
-    if (!query.trim()) {
-      return items;
-    }
-    try {
-      return await tauri.searchItems(query);
-    } catch (err) {
-      setError(String(err));
-      return [];
-    }
-  };
-
   return {
     status,
-    items,
+    revision,
     loading,
     error,
     createVault,
     unlock,
+    unlockWithBiometrics,
+    recover,
+    restoreFromSync,
     lock,
     addItem,
     updateItem,
     deleteItem,
-    search,
     refreshItems,
     clearError: () => setError(null),
   };
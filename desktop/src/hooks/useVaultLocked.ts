@@ -0,0 +1,23 @@
+import { useEffect, useRef } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { VaultLockedEvent } from './useTauri';
+
+/**
+ * Run `onLocked` in this window whenever the backend locks the vault, from
+ * any window, the tray, idle, sleep or a remote command. Windows use it to
+ * drop decrypted items they still hold.
+ */
+export function useVaultLocked(onLocked: (event: VaultLockedEvent) => void) {
+  const callback = useRef(onLocked);
+  callback.current = onLocked;
+
+  useEffect(() => {
+    const unlisten = listen<VaultLockedEvent>('vault-locked', (event) => {
+      callback.current(event.payload);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+}
@@ -1,10 +1,12 @@
 import React from 'react';
 import ReactDOM from 'react-dom/client';
+import { getCurrentWindow } from '@tauri-apps/api/window';
 import App from './App';
+import QuickSearch from './components/QuickSearch';
 import './styles.css';
 
 ReactDOM.createRoot(document.getElementById('root')!).render(
   <React.StrictMode>
-    <App />
+    {getCurrentWindow().label === 'palette' ? <QuickSearch /> : <App />}
   </React.StrictMode>
 );
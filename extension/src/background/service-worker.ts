@@ -22,6 +22,12 @@ import {
   type VaultData,
   type VaultItem,
 } from '../lib/storage';
+import {
+  getDesktopStatus,
+  findDesktopItems,
+  requestDesktopUnlock,
+  getDesktopCredentials,
+} from '../lib/native';
 
 // In-memory state (cleared on service worker restart)
 let unlockedVault: VaultData | null = null;
@@ -41,7 +47,11 @@ type MessageType =
   | { type: 'UPDATE_ITEM'; item: VaultItem }
   | { type: 'DELETE_ITEM'; id: string }
   | { type: 'GENERATE_PASSWORD'; options: { length: number; lowercase: boolean; uppercase: boolean; digits: boolean; symbols: boolean } }
-  | { type: 'AUTOFILL'; tabId: number; item: VaultItem };
+  | { type: 'AUTOFILL'; tabId: number; item: VaultItem }
+  | { type: 'DESKTOP_STATUS' }
+  | { type: 'DESKTOP_FIND'; url: string }
+  | { type: 'DESKTOP_UNLOCK' }
+  | { type: 'DESKTOP_AUTOFILL'; tabId: number; itemId: string; url: string };
 
 interface MessageResponse {
   success: boolean;
@@ -283,6 +293,28 @@ async function handleMessage(message: MessageType): Promise<MessageResponse> {
         return { success: true };
       }
 
+      case 'DESKTOP_STATUS': {
+        return { success: true, data: await getDesktopStatus() };
+      }
+
+      case 'DESKTOP_FIND': {
+        return { success: true, data: await findDesktopItems(message.url) };
+      }
+
+      case 'DESKTOP_UNLOCK': {
+        return { success: true, data: await requestDesktopUnlock() };
+      }
+
+      case 'DESKTOP_AUTOFILL': {
+        const credentials = await getDesktopCredentials(message.itemId, message.url);
+        chrome.tabs.sendMessage(message.tabId, {
+          type: 'AUTOFILL',
+          username: credentials.username,
+          password: credentials.password,
+        });
+        return { success: true };
+      }
+
       default:
         return { success: false, error: 'Unknown message type' };
     }
@@ -0,0 +1,72 @@
+// Client for the Keydrop desktop app's native messaging host
+// Lets the extension read the desktop vault instead of keeping its own copy
+
+const HOST_NAME = 'com.keydrop.native';
+
+export interface DesktopStatus {
+  exists: boolean;
+  unlocked: boolean;
+}
+
+export interface DesktopItemSummary {
+  id: string;
+  name: string;
+  username: string;
+  url: string | null;
+}
+
+export interface DesktopCredentials {
+  username: string;
+  password: string;
+}
+
+type DesktopRequest =
+  | { action: 'status' }
+  | { action: 'find_by_url'; url: string }
+  | { action: 'request_unlock' }
+  | { action: 'get_credentials'; item_id: string; url: string };
+
+interface DesktopResponse<T> {
+  id: number;
+  ok: boolean;
+  result?: T;
+  error?: string;
+}
+
+let nextId = 1;
+
+async function send<T>(request: DesktopRequest): Promise<T> {
+  const response = (await chrome.runtime.sendNativeMessage(HOST_NAME, {
+    id: nextId++,
+    ...request,
+  })) as DesktopResponse<T>;
+  if (!response.ok) {
+    throw new Error(response.error || 'Desktop app request failed');
+  }
+  return response.result as T;
+}
+
+// Whether the desktop app is reachable and its vault unlocked
+export async function getDesktopStatus(): Promise<DesktopStatus> {
+  return send<DesktopStatus>({ action: 'status' });
+}
+
+// Desktop items matching a URL, without passwords
+export async function findDesktopItems(url: string): Promise<DesktopItemSummary[]> {
+  const result = await send<{ items: DesktopItemSummary[] }>({ action: 'find_by_url', url });
+  return result.items;
+}
+
+// Bring up the desktop unlock screen
+export async function requestDesktopUnlock(): Promise<boolean> {
+  const result = await send<{ unlocked: boolean }>({ action: 'request_unlock' });
+  return result.unlocked;
+}
+
+// Username and password of a desktop item; the item must match the URL
+export async function getDesktopCredentials(
+  itemId: string,
+  url: string
+): Promise<DesktopCredentials> {
+  return send<DesktopCredentials>({ action: 'get_credentials', item_id: itemId, url });
+}